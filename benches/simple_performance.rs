@@ -1,6 +1,6 @@
 use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
 use sms_core::*;
-use sms_web_generic::WebhookProcessor;
+use sms_web_generic::{Bytes, WebhookProcessor};
 use smskit::rate_limiter::{RateLimitConfig, RateLimiter};
 use std::collections::HashMap;
 use tokio::runtime::Runtime;
@@ -15,7 +15,7 @@ fn benchmark_webhook_processing(c: &mut Criterion) {
     let mut group = c.benchmark_group("webhook_processing");
 
     for size in payload_sizes {
-        let payload = "x".repeat(size);
+        let payload = Bytes::from("x".repeat(size));
         let headers: Headers = vec![("content-type".to_string(), "application/json".to_string())];
 
         group.bench_with_input(
@@ -26,7 +26,7 @@ fn benchmark_webhook_processing(c: &mut Criterion) {
                     black_box(processor.process_webhook(
                         "test-provider",
                         headers.clone(),
-                        payload.as_bytes(),
+                        payload.clone(),
                     ))
                 })
             },
@@ -43,6 +43,8 @@ fn benchmark_rate_limiting(c: &mut Criterion) {
         window_seconds: 60,
         enabled: true,
         per_provider: HashMap::new(),
+        per_tenant: HashMap::new(),
+        trusted_proxy_hops: 0,
     };
     let limiter = RateLimiter::new(config);
 
@@ -0,0 +1,514 @@
+//! # 46elks SMS Provider
+//!
+//! [46elks](https://46elks.com/) backend for the smskit multi-provider SMS
+//! abstraction, popular across Sweden and the rest of the Nordics.
+//!
+//! ## Sending messages
+//!
+//! ```rust,ignore
+//! use sms_core::{SendRequest, SmsClient};
+//! use sms_46elks::ElksClient;
+//!
+//! let client = ElksClient::new("YOUR_API_USERNAME", "YOUR_API_PASSWORD");
+//! let response = client.send(SendRequest {
+//!     to: "+46701234567",
+//!     from: "MyBrand",
+//!     text: "Hej från 46elks!",
+//!     ..Default::default()
+//! }).await?;
+//! println!("Message ID: {}", response.id);
+//! ```
+//!
+//! Use [`ElksClient::with_dry_run`] while developing: 46elks validates and
+//! prices the message without sending or charging for it.
+//!
+//! ## Webhooks
+//!
+//! 46elks POSTs inbound messages to the number's `sms_url` and delivery
+//! reports to the `whendelivered` URL of each send, both form-encoded.
+//! [`InboundWebhook::parse_inbound`] tells them apart, so both URLs can point
+//! at the same unified endpoint.
+
+use serde::{Deserialize, Serialize};
+use sms_core::{
+    Channel, ExposeSecret, Headers, HttpClientConfig, IdGenerator, InboundDirection,
+    InboundMessage, InboundWebhook, SecretString, SendRequest, SendResponse, SmsClient, SmsError,
+    UuidV4,
+};
+use sms_core::http_provider::transport_error;
+use std::sync::Arc;
+use std::time::Duration;
+
+const PROVIDER: &str = "46elks";
+
+/// Production API base URL.
+const LIVE_URL: &str = "https://api.46elks.com";
+
+/// Default deadline for a single API call.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 46elks REST API client.
+///
+/// Implements [`SmsClient`] for sending SMS and [`InboundWebhook`] for
+/// receiving inbound messages and delivery reports.
+#[derive(Clone)]
+pub struct ElksClient {
+    /// API username (starts with `u`).
+    pub api_username: String,
+    /// API password.  Zeroized on drop.
+    pub api_password: SecretString,
+    /// API base URL; override with
+    /// [`with_base_url`](ElksClient::with_base_url) for testing.
+    pub base_url: String,
+    /// URL 46elks POSTs delivery reports to, sent as `whendelivered`.
+    pub when_delivered: Option<String>,
+    /// Validate sends without delivering or charging for them.
+    pub dry_run: bool,
+    /// Deadline for each API call unless the request sets its own
+    /// [`SendRequest::timeout`].
+    pub timeout: Duration,
+    id_generator: Arc<dyn IdGenerator>,
+    http: reqwest::Client,
+}
+
+impl std::fmt::Debug for ElksClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ElksClient")
+            .field("api_username", &self.api_username)
+            .field("api_password", &"[REDACTED]")
+            .field("base_url", &self.base_url)
+            .field("when_delivered", &self.when_delivered)
+            .field("dry_run", &self.dry_run)
+            .field("timeout", &self.timeout)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ElksClient {
+    /// Create a client against the production API.
+    pub fn new(api_username: impl Into<String>, api_password: impl Into<String>) -> Self {
+        Self::with_base_url(api_username, api_password, LIVE_URL)
+    }
+
+    /// Create a client by reading credentials from environment variables.
+    ///
+    /// | Variable             | Maps to          |
+    /// |----------------------|------------------|
+    /// | `ELKS_API_USERNAME`  | `api_username`   |
+    /// | `ELKS_API_PASSWORD`  | `api_password`   |
+    /// | `ELKS_WHENDELIVERED` | `when_delivered` |
+    ///
+    /// Returns [`SmsError::Auth`] if either credential is missing.
+    pub fn from_env() -> Result<Self, SmsError> {
+        let api_username = std::env::var("ELKS_API_USERNAME")
+            .map_err(|_| SmsError::Auth("ELKS_API_USERNAME not set".into()))?;
+        let api_password = std::env::var("ELKS_API_PASSWORD")
+            .map_err(|_| SmsError::Auth("ELKS_API_PASSWORD not set".into()))?;
+        let client = Self::new(api_username, api_password);
+        Ok(match std::env::var("ELKS_WHENDELIVERED") {
+            Ok(url) => client.with_when_delivered(url),
+            Err(_) => client,
+        })
+    }
+
+    /// Create a client with a custom API base URL, e.g. a mock server.
+    pub fn with_base_url(
+        api_username: impl Into<String>,
+        api_password: impl Into<String>,
+        base_url: impl Into<String>,
+    ) -> Self {
+        Self {
+            api_username: api_username.into(),
+            api_password: SecretString::from(api_password.into()),
+            base_url: base_url.into(),
+            when_delivered: None,
+            dry_run: false,
+            timeout: DEFAULT_TIMEOUT,
+            id_generator: Arc::new(UuidV4),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Ask 46elks to POST delivery reports for each send to `url`.
+    pub fn with_when_delivered(mut self, url: impl Into<String>) -> Self {
+        self.when_delivered = Some(url.into());
+        self
+    }
+
+    /// Validate and price sends without delivering them (default: off).
+    pub fn with_dry_run(mut self, enabled: bool) -> Self {
+        self.dry_run = enabled;
+        self
+    }
+
+    /// Set the default deadline for each API call (default: 30 seconds).
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Rebuild the HTTP client with custom transport settings (proxy,
+    /// `User-Agent`, extra headers, etc.).
+    pub fn with_http_config(mut self, config: HttpClientConfig) -> Result<Self, SmsError> {
+        self.http = sms_core::http_provider::client_with(&config)?;
+        Ok(self)
+    }
+
+    /// Generate IDs with `ids` when the response doesn't include one
+    /// (default: [`UuidV4`]).
+    pub fn with_id_generator(mut self, ids: impl IdGenerator + 'static) -> Self {
+        self.id_generator = Arc::new(ids);
+        self
+    }
+}
+
+/// Wire format for the send-SMS request body.
+#[derive(Debug, Serialize)]
+struct ElksSendRequest<'a> {
+    from: &'a str,
+    to: &'a str,
+    message: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    whendelivered: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dryrun: Option<&'a str>,
+}
+
+/// Typed view of the JSON returned from a send, available from
+/// [`SendResponse::raw`] via [`ElksSendDetails::from_response`].
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ElksSendDetails {
+    /// Message ID, e.g. `"s70df59406a1b4643b96f3f91e0bfb7b0"`.  Absent on
+    /// dry runs.
+    #[serde(default)]
+    pub id: Option<String>,
+    /// `created`, `sent`, `delivered` or `failed`.
+    #[serde(default)]
+    pub status: String,
+    /// Sender as 46elks will present it.
+    #[serde(default)]
+    pub from: String,
+    /// Destination number.
+    #[serde(default)]
+    pub to: String,
+    /// Number of SMS parts the message was split into.
+    #[serde(default)]
+    pub parts: u32,
+    /// Cost in 1/10000 of the account currency.
+    #[serde(default)]
+    pub cost: Option<u64>,
+    /// Estimated cost, in place of `cost` on dry runs.
+    #[serde(default)]
+    pub estimated_cost: Option<u64>,
+    /// Creation time, e.g. `"2024-01-15T10:30:00.123000"`.
+    #[serde(default)]
+    pub created: Option<String>,
+}
+
+impl ElksSendDetails {
+    /// Parse the raw payload of a 46elks [`SendResponse`].
+    pub fn from_response(resp: &SendResponse) -> Result<Self, SmsError> {
+        if resp.provider != PROVIDER {
+            return Err(SmsError::Invalid(format!(
+                "expected a {} response, got {}",
+                PROVIDER, resp.provider
+            )));
+        }
+        serde_json::from_value(resp.raw.clone())
+            .map_err(|e| SmsError::Invalid(format!("46elks send response: {}", e)))
+    }
+}
+
+impl SmsClient for ElksClient {
+    async fn send(&self, req: SendRequest<'_>) -> Result<SendResponse, SmsError> {
+        sms_core::validate_text(req.text, self.max_text_len())?;
+        let url = format!("{}/a1/sms", self.base_url.trim_end_matches('/'));
+        let payload = ElksSendRequest {
+            from: req.from,
+            to: req.to,
+            message: req.text,
+            whendelivered: self.when_delivered.as_deref(),
+            dryrun: self.dry_run.then_some("yes"),
+        };
+        let res = self
+            .http
+            .post(&url)
+            .basic_auth(&self.api_username, Some(self.api_password.expose_secret()))
+            .timeout(req.timeout.unwrap_or(self.timeout))
+            .form(&payload)
+            .send()
+            .await
+            .map_err(transport_error)?;
+
+        let status = res.status();
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = res
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(sms_core::parse_retry_after);
+            return Err(SmsError::RateLimited { retry_after });
+        }
+        let raw_text = res.text().await.map_err(transport_error)?;
+        if status == reqwest::StatusCode::UNAUTHORIZED {
+            return Err(SmsError::Auth(raw_text));
+        }
+        // 46elks reports bad numbers, senders and texts as a plain-text 403.
+        if status == reqwest::StatusCode::FORBIDDEN {
+            return Err(SmsError::Invalid(raw_text));
+        }
+        if !status.is_success() {
+            return Err(SmsError::Provider(format!("HTTP {}: {}", status, raw_text)));
+        }
+
+        let raw: serde_json::Value = serde_json::from_str(&raw_text)
+            .map_err(|e| SmsError::Provider(format!("send response: {}: {}", e, raw_text)))?;
+        let details: ElksSendDetails = serde_json::from_value(raw.clone())
+            .map_err(|e| SmsError::Provider(format!("send response: {}", e)))?;
+
+        Ok(SendResponse {
+            id: details.id.unwrap_or_else(|| self.id_generator.generate()),
+            provider: PROVIDER,
+            channel: Channel::Sms,
+            http_status: Some(status.as_u16()),
+            provider_request_id: None,
+            thread_id: Some(req.thread_id()),
+            raw,
+        })
+    }
+}
+
+/// The form-encoded payload 46elks POSTs to a number's `sms_url`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ElksInbound {
+    /// 46elks message ID.
+    pub id: String,
+    /// Sender phone number.
+    pub from: String,
+    /// Your 46elks number.
+    pub to: String,
+    /// Message body.
+    pub message: String,
+    /// Receive time, e.g. `"2024-01-15T10:30:00.123000"` (UTC).
+    pub created: Option<String>,
+    /// Always `incoming`.
+    pub direction: Option<String>,
+}
+
+/// The form-encoded payload 46elks POSTs to a send's `whendelivered` URL.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ElksDeliveryReport {
+    /// ID of the message the report is about.
+    pub id: String,
+    /// `delivered` or `failed`.
+    pub status: String,
+    /// Delivery time, for `delivered` reports.
+    pub delivered: Option<String>,
+}
+
+impl ElksDeliveryReport {
+    /// Returns `true` if the message could not be delivered.
+    pub fn is_failure(&self) -> bool {
+        self.status == "failed"
+    }
+}
+
+impl From<ElksInbound> for InboundMessage {
+    fn from(m: ElksInbound) -> Self {
+        let timestamp = m.created.as_deref().and_then(sms_core::timestamp::parse);
+        let raw = serde_json::to_value(&m).unwrap_or_default();
+        InboundMessage {
+            id: Some(m.id),
+            from: m.from,
+            to: m.to,
+            text: m.message,
+            timestamp,
+            provider: PROVIDER,
+            direction: InboundDirection::Inbound,
+            channel: Channel::Sms,
+            provider_account: None,
+            thread_id: None,
+            flag: None,
+            raw,
+        }
+        .with_thread_id()
+    }
+}
+
+impl From<ElksDeliveryReport> for InboundMessage {
+    fn from(r: ElksDeliveryReport) -> Self {
+        let timestamp = r.delivered.as_deref().and_then(sms_core::timestamp::parse);
+        let raw = serde_json::to_value(&r).unwrap_or_default();
+        InboundMessage {
+            id: Some(r.id),
+            from: "46elks".to_string(),
+            to: String::new(),
+            text: format!("Delivery Status: {}", r.status),
+            timestamp,
+            provider: PROVIDER,
+            direction: InboundDirection::DeliveryReport,
+            channel: Channel::Sms,
+            provider_account: None,
+            thread_id: None,
+            flag: None,
+            raw,
+        }
+        .with_thread_id()
+    }
+}
+
+impl InboundWebhook for ElksClient {
+    fn provider(&self) -> &'static str {
+        PROVIDER
+    }
+
+    fn parse_inbound(&self, _headers: &Headers, body: &[u8]) -> Result<InboundMessage, SmsError> {
+        let fields: Vec<(String, String)> = serde_urlencoded::from_bytes(body)
+            .map_err(|e| SmsError::Invalid(format!("form decode: {}", e)))?;
+        let is_report = fields.iter().any(|(k, _)| k == "status")
+            && !fields.iter().any(|(k, _)| k == "message");
+        if is_report {
+            let report: ElksDeliveryReport = serde_urlencoded::from_bytes(body)
+                .map_err(|e| SmsError::Invalid(format!("delivery report: {}", e)))?;
+            Ok(report.into())
+        } else {
+            let inbound: ElksInbound = serde_urlencoded::from_bytes(body)
+                .map_err(|e| SmsError::Invalid(format!("inbound message: {}", e)))?;
+            Ok(inbound.into())
+        }
+    }
+}
+
+/// The `46elks` configuration section read by the provider plugin.
+#[derive(Deserialize)]
+struct ElksSettings {
+    api_username: String,
+    api_password: String,
+    base_url: Option<String>,
+    when_delivered: Option<String>,
+    #[serde(default)]
+    dry_run: bool,
+    #[serde(default)]
+    http: Option<HttpClientConfig>,
+}
+
+/// Build an [`ElksClient`] from the `46elks` section.
+fn build_plugin(settings: &serde_json::Value) -> Result<sms_core::BuiltProvider, SmsError> {
+    let s: ElksSettings = sms_core::provider_settings(PROVIDER, settings)?;
+    let mut client = match s.base_url {
+        Some(base_url) => ElksClient::with_base_url(s.api_username, s.api_password, base_url),
+        None => ElksClient::new(s.api_username, s.api_password),
+    }
+    .with_dry_run(s.dry_run);
+    client.when_delivered = s.when_delivered;
+    let client = match s.http {
+        Some(http) => client.with_http_config(http)?,
+        None => client,
+    };
+    Ok(sms_core::BuiltProvider::both(client))
+}
+
+sms_core::register_provider!("46elks", build_plugin);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn send_request_is_form_encoded() {
+        let payload = ElksSendRequest {
+            from: "MyBrand",
+            to: "+46701234567",
+            message: "Hej då",
+            whendelivered: Some("https://example.com/dlr"),
+            dryrun: None,
+        };
+        let form = serde_urlencoded::to_string(&payload).unwrap();
+        assert_eq!(
+            form,
+            "from=MyBrand&to=%2B46701234567&message=Hej+d%C3%A5&whendelivered=https%3A%2F%2Fexample.com%2Fdlr"
+        );
+    }
+
+    #[test]
+    fn send_details_from_response() {
+        let resp = SendResponse {
+            id: "s70df59406a1b4643b96f3f91e0bfb7b0".into(),
+            provider: PROVIDER,
+            channel: Channel::Sms,
+            http_status: Some(200),
+            provider_request_id: None,
+            thread_id: None,
+            raw: json!({
+                "status": "created",
+                "direction": "outgoing",
+                "from": "MyBrand",
+                "created": "2024-01-15T10:30:00.123000",
+                "parts": 2,
+                "to": "+46701234567",
+                "cost": 7000,
+                "message": "Hej",
+                "id": "s70df59406a1b4643b96f3f91e0bfb7b0"
+            }),
+        };
+        let details = ElksSendDetails::from_response(&resp).unwrap();
+        assert_eq!(details.parts, 2);
+        assert_eq!(details.cost, Some(7000));
+        assert_eq!(details.id.as_deref(), Some(resp.id.as_str()));
+    }
+
+    #[test]
+    fn parses_inbound_message() {
+        let client = ElksClient::new("u1", "p1");
+        let body = b"direction=incoming&id=sf8425555e5d8db61dda7a7b3f1b91bdb&from=%2B46704508449&to=%2B46766861004&created=2024-01-15T10%3A30%3A00Z&message=Hej";
+        let msg = client.parse_inbound(&Headers::new(), body).unwrap();
+        assert_eq!(msg.direction, InboundDirection::Inbound);
+        assert_eq!(msg.from, "+46704508449");
+        assert_eq!(msg.to, "+46766861004");
+        assert_eq!(msg.text, "Hej");
+        assert_eq!(msg.id.as_deref(), Some("sf8425555e5d8db61dda7a7b3f1b91bdb"));
+        assert!(msg.timestamp.is_some());
+    }
+
+    #[test]
+    fn parses_delivery_report() {
+        let client = ElksClient::new("u1", "p1");
+        let body = b"id=s70df59406a1b4643b96f3f91e0bfb7b0&status=failed";
+        let msg = client.parse_inbound(&Headers::new(), body).unwrap();
+        assert_eq!(msg.direction, InboundDirection::DeliveryReport);
+        assert_eq!(msg.id.as_deref(), Some("s70df59406a1b4643b96f3f91e0bfb7b0"));
+        assert_eq!(msg.text, "Delivery Status: failed");
+
+        let report: ElksDeliveryReport = serde_json::from_value(msg.raw).unwrap();
+        assert!(report.is_failure());
+    }
+
+    #[test]
+    fn rejects_malformed_webhooks() {
+        let client = ElksClient::new("u1", "p1");
+        assert!(
+            client
+                .parse_inbound(&Headers::new(), b"message=orphan")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn plugin_builds_from_settings() {
+        let plugin = sms_core::ProviderPlugin::find("46elks").unwrap();
+        let built = plugin
+            .build(&json!({ "api_username": "u1", "api_password": "p1", "dry_run": true }))
+            .unwrap();
+        assert_eq!(built.webhook.unwrap().provider(), "46elks");
+        assert!(built.client.is_some());
+        assert!(plugin.build(&json!({ "api_username": "u1" })).is_err());
+    }
+
+    #[test]
+    fn debug_redacts_api_password() {
+        let debug = format!("{:?}", ElksClient::new("u1", "hunter2"));
+        assert!(debug.contains("u1"));
+        assert!(!debug.contains("hunter2"));
+    }
+}
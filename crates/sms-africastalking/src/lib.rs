@@ -0,0 +1,554 @@
+//! # Africa's Talking SMS Provider
+//!
+//! [Africa's Talking](https://africastalking.com/) backend for the smskit
+//! multi-provider SMS abstraction, covering Kenya, Nigeria, Uganda and the
+//! rest of their network.
+//!
+//! ## Sending messages
+//!
+//! ```rust,ignore
+//! use sms_core::{SendRequest, SmsClient};
+//! use sms_africastalking::AfricasTalkingClient;
+//!
+//! let client = AfricasTalkingClient::new("myapp", "YOUR_API_KEY");
+//! let response = client.send(SendRequest {
+//!     to: "+254711000000",
+//!     from: "MYSHORTCODE",
+//!     text: "Hello from Africa's Talking!",
+//!     ..Default::default()
+//! }).await?;
+//! println!("Message ID: {}", response.id);
+//! ```
+//!
+//! Use [`AfricasTalkingClient::sandbox`] with the `sandbox` username while
+//! developing.
+//!
+//! ## Webhooks
+//!
+//! Africa's Talking POSTs inbound messages and delivery reports to separate
+//! callback URLs, both form-encoded.  [`InboundWebhook::parse_inbound`] tells
+//! them apart, so both URLs can point at the same unified endpoint.
+
+use serde::{Deserialize, Serialize};
+use sms_core::{
+    Channel, ExposeSecret, Headers, HttpClientConfig, IdGenerator, InboundDirection,
+    InboundMessage, InboundWebhook, SecretString, SendRequest, SendResponse, SmsClient, SmsError,
+    UuidV4,
+};
+use sms_core::http_provider::transport_error;
+use std::sync::Arc;
+use std::time::Duration;
+
+const PROVIDER: &str = "africastalking";
+
+/// Production API base URL.
+const LIVE_URL: &str = "https://api.africastalking.com";
+
+/// Sandbox API base URL.
+const SANDBOX_URL: &str = "https://api.sandbox.africastalking.com";
+
+/// Default deadline for a single API call.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Africa's Talking REST API client.
+///
+/// Implements [`SmsClient`] for sending SMS and [`InboundWebhook`] for
+/// receiving inbound messages and delivery reports.
+#[derive(Clone)]
+pub struct AfricasTalkingClient {
+    /// Application username (`"sandbox"` in the sandbox).
+    pub username: String,
+    /// API key, sent in the `apiKey` header.  Zeroized on drop.
+    pub api_key: SecretString,
+    /// API base URL; override with
+    /// [`with_base_url`](AfricasTalkingClient::with_base_url) for testing.
+    pub base_url: String,
+    /// Deadline for each API call unless the request sets its own
+    /// [`SendRequest::timeout`].
+    pub timeout: Duration,
+    id_generator: Arc<dyn IdGenerator>,
+    http: reqwest::Client,
+}
+
+impl std::fmt::Debug for AfricasTalkingClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AfricasTalkingClient")
+            .field("username", &self.username)
+            .field("api_key", &"[REDACTED]")
+            .field("base_url", &self.base_url)
+            .field("timeout", &self.timeout)
+            .finish_non_exhaustive()
+    }
+}
+
+impl AfricasTalkingClient {
+    /// Create a client against the production API.
+    pub fn new(username: impl Into<String>, api_key: impl Into<String>) -> Self {
+        Self::with_base_url(username, api_key, LIVE_URL)
+    }
+
+    /// Create a client against the sandbox API.
+    pub fn sandbox(api_key: impl Into<String>) -> Self {
+        Self::with_base_url("sandbox", api_key, SANDBOX_URL)
+    }
+
+    /// Create a client by reading credentials from environment variables.
+    ///
+    /// | Variable                  | Maps to    |
+    /// |---------------------------|------------|
+    /// | `AFRICASTALKING_USERNAME` | `username` |
+    /// | `AFRICASTALKING_API_KEY`  | `api_key`  |
+    ///
+    /// A username of `sandbox` selects the sandbox API.  Returns
+    /// [`SmsError::Auth`] if either variable is missing.
+    pub fn from_env() -> Result<Self, SmsError> {
+        let username = std::env::var("AFRICASTALKING_USERNAME")
+            .map_err(|_| SmsError::Auth("AFRICASTALKING_USERNAME not set".into()))?;
+        let api_key = std::env::var("AFRICASTALKING_API_KEY")
+            .map_err(|_| SmsError::Auth("AFRICASTALKING_API_KEY not set".into()))?;
+        Ok(if username == "sandbox" {
+            Self::sandbox(api_key)
+        } else {
+            Self::new(username, api_key)
+        })
+    }
+
+    /// Create a client with a custom API base URL, e.g. a mock server.
+    pub fn with_base_url(
+        username: impl Into<String>,
+        api_key: impl Into<String>,
+        base_url: impl Into<String>,
+    ) -> Self {
+        Self {
+            username: username.into(),
+            api_key: SecretString::from(api_key.into()),
+            base_url: base_url.into(),
+            timeout: DEFAULT_TIMEOUT,
+            id_generator: Arc::new(UuidV4),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Set the default deadline for each API call (default: 30 seconds).
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Rebuild the HTTP client with custom transport settings (proxy,
+    /// `User-Agent`, extra headers, etc.).
+    pub fn with_http_config(mut self, config: HttpClientConfig) -> Result<Self, SmsError> {
+        self.http = sms_core::http_provider::client_with(&config)?;
+        Ok(self)
+    }
+
+    /// Generate IDs with `ids` when the response doesn't include one
+    /// (default: [`UuidV4`]).
+    pub fn with_id_generator(mut self, ids: impl IdGenerator + 'static) -> Self {
+        self.id_generator = Arc::new(ids);
+        self
+    }
+}
+
+/// Wire format for the send-message request body.
+#[derive(Debug, Serialize)]
+struct AtSendRequest<'a> {
+    username: &'a str,
+    to: &'a str,
+    message: &'a str,
+    #[serde(skip_serializing_if = "str::is_empty")]
+    from: &'a str,
+}
+
+/// Typed view of the JSON returned from a send, available from
+/// [`SendResponse::raw`] via [`AtSendDetails::from_response`].
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct AtSendDetails {
+    /// The per-request summary.
+    #[serde(rename = "SMSMessageData")]
+    pub data: AtMessageData,
+}
+
+/// The `SMSMessageData` object of a send response.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct AtMessageData {
+    /// Summary, e.g. `"Sent to 1/1 Total Cost: KES 0.8000"`.
+    #[serde(rename = "Message", default)]
+    pub message: String,
+    /// One entry per destination.
+    #[serde(rename = "Recipients", default)]
+    pub recipients: Vec<AtRecipient>,
+}
+
+/// Outcome for one destination of a send.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AtRecipient {
+    /// `100` (processed), `101` (sent) or `102` (queued) on success; a
+    /// 4xx/5xx code on failure.
+    pub status_code: u16,
+    /// Destination number.
+    pub number: String,
+    /// Status text, e.g. `"Success"` or `"InvalidPhoneNumber"`.
+    pub status: String,
+    /// Cost with currency, e.g. `"KES 0.8000"`.
+    #[serde(default)]
+    pub cost: String,
+    /// Message ID (`"None"` when the send failed).
+    #[serde(default)]
+    pub message_id: String,
+}
+
+impl AtRecipient {
+    /// Returns `true` if Africa's Talking accepted the message.
+    pub fn is_success(&self) -> bool {
+        matches!(self.status_code, 100..=102)
+    }
+}
+
+impl AtSendDetails {
+    /// Parse the raw payload of an Africa's Talking [`SendResponse`].
+    pub fn from_response(resp: &SendResponse) -> Result<Self, SmsError> {
+        if resp.provider != PROVIDER {
+            return Err(SmsError::Invalid(format!(
+                "expected a {} response, got {}",
+                PROVIDER, resp.provider
+            )));
+        }
+        serde_json::from_value(resp.raw.clone())
+            .map_err(|e| SmsError::Invalid(format!("africastalking send response: {}", e)))
+    }
+}
+
+impl SmsClient for AfricasTalkingClient {
+    async fn send(&self, req: SendRequest<'_>) -> Result<SendResponse, SmsError> {
+        sms_core::validate_text(req.text, self.max_text_len())?;
+        let url = format!("{}/version1/messaging", self.base_url.trim_end_matches('/'));
+        let payload = AtSendRequest {
+            username: &self.username,
+            to: req.to,
+            message: req.text,
+            from: req.from,
+        };
+        let res = self
+            .http
+            .post(&url)
+            .header("apiKey", self.api_key.expose_secret())
+            .header(reqwest::header::ACCEPT, "application/json")
+            .timeout(req.timeout.unwrap_or(self.timeout))
+            .form(&payload)
+            .send()
+            .await
+            .map_err(transport_error)?;
+
+        let status = res.status();
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = res
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(sms_core::parse_retry_after);
+            return Err(SmsError::RateLimited { retry_after });
+        }
+        let raw_text = res.text().await.map_err(transport_error)?;
+        if status == reqwest::StatusCode::UNAUTHORIZED {
+            return Err(SmsError::Auth(raw_text));
+        }
+        if !status.is_success() {
+            return Err(SmsError::Provider(format!("HTTP {}: {}", status, raw_text)));
+        }
+
+        let raw: serde_json::Value = serde_json::from_str(&raw_text)
+            .map_err(|e| SmsError::Provider(format!("send response: {}: {}", e, raw_text)))?;
+        let details: AtSendDetails = serde_json::from_value(raw.clone())
+            .map_err(|e| SmsError::Provider(format!("send response: {}", e)))?;
+        let recipient = details
+            .data
+            .recipients
+            .first()
+            .ok_or_else(|| SmsError::Provider(details.data.message.clone()))?;
+        if !recipient.is_success() {
+            return Err(SmsError::Provider(format!(
+                "{} ({})",
+                recipient.status, recipient.status_code
+            )));
+        }
+
+        let id = Some(recipient.message_id.clone())
+            .filter(|id| !id.is_empty() && id != "None")
+            .unwrap_or_else(|| self.id_generator.generate());
+        Ok(SendResponse {
+            id,
+            provider: PROVIDER,
+            channel: Channel::Sms,
+            http_status: Some(status.as_u16()),
+            provider_request_id: None,
+            thread_id: Some(req.thread_id()),
+            raw,
+        })
+    }
+}
+
+/// The form-encoded payload Africa's Talking POSTs to the incoming-messages
+/// callback URL.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AtInbound {
+    /// Sender phone number.
+    pub from: String,
+    /// Your short code or number.
+    pub to: String,
+    /// Message body.
+    pub text: String,
+    /// Africa's Talking message ID.
+    pub id: Option<String>,
+    /// Receive time, e.g. `"2024-01-15 10:30:00"`.
+    pub date: Option<String>,
+    /// Premium-SMS session ID, to be echoed when replying.
+    #[serde(rename = "linkId")]
+    pub link_id: Option<String>,
+    /// Mobile network code of the sender.
+    #[serde(rename = "networkCode")]
+    pub network_code: Option<String>,
+}
+
+/// The form-encoded payload Africa's Talking POSTs to the delivery-reports
+/// callback URL.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AtDeliveryReport {
+    /// ID of the message the report is about.
+    pub id: String,
+    /// `Sent`, `Submitted`, `Buffered`, `Rejected`, `Success`, `Failed`
+    /// or `Expired`.
+    pub status: String,
+    /// Destination number of the original message.
+    #[serde(rename = "phoneNumber")]
+    pub phone_number: String,
+    /// Mobile network code of the destination.
+    #[serde(rename = "networkCode")]
+    pub network_code: Option<String>,
+    /// Why delivery failed, for `Rejected` and `Failed` reports.
+    #[serde(rename = "failureReason")]
+    pub failure_reason: Option<String>,
+    /// Delivery retries so far.
+    #[serde(rename = "retryCount")]
+    pub retry_count: Option<String>,
+}
+
+impl AtDeliveryReport {
+    /// Returns `true` for final failures (`Rejected`, `Failed`, `Expired`).
+    pub fn is_failure(&self) -> bool {
+        matches!(self.status.as_str(), "Rejected" | "Failed" | "Expired")
+    }
+}
+
+impl From<AtInbound> for InboundMessage {
+    fn from(m: AtInbound) -> Self {
+        let timestamp = m.date.as_deref().and_then(sms_core::timestamp::parse);
+        let raw = serde_json::to_value(&m).unwrap_or_default();
+        InboundMessage {
+            id: m.id,
+            from: m.from,
+            to: m.to,
+            text: m.text,
+            timestamp,
+            provider: PROVIDER,
+            direction: InboundDirection::Inbound,
+            channel: Channel::Sms,
+            provider_account: None,
+            thread_id: None,
+            flag: None,
+            raw,
+        }
+        .with_thread_id()
+    }
+}
+
+impl From<AtDeliveryReport> for InboundMessage {
+    fn from(r: AtDeliveryReport) -> Self {
+        let raw = serde_json::to_value(&r).unwrap_or_default();
+        InboundMessage {
+            id: Some(r.id),
+            from: "AfricasTalking".to_string(),
+            to: r.phone_number,
+            text: format!("Delivery Status: {}", r.status),
+            timestamp: None,
+            provider: PROVIDER,
+            direction: InboundDirection::DeliveryReport,
+            channel: Channel::Sms,
+            provider_account: None,
+            thread_id: None,
+            flag: None,
+            raw,
+        }
+        .with_thread_id()
+    }
+}
+
+impl InboundWebhook for AfricasTalkingClient {
+    fn provider(&self) -> &'static str {
+        PROVIDER
+    }
+
+    fn parse_inbound(&self, _headers: &Headers, body: &[u8]) -> Result<InboundMessage, SmsError> {
+        let fields: Vec<(String, String)> = serde_urlencoded::from_bytes(body)
+            .map_err(|e| SmsError::Invalid(format!("form decode: {}", e)))?;
+        let is_report = fields.iter().any(|(k, _)| k == "phoneNumber")
+            && fields.iter().any(|(k, _)| k == "status");
+        if is_report {
+            let report: AtDeliveryReport = serde_urlencoded::from_bytes(body)
+                .map_err(|e| SmsError::Invalid(format!("delivery report: {}", e)))?;
+            Ok(report.into())
+        } else {
+            let inbound: AtInbound = serde_urlencoded::from_bytes(body)
+                .map_err(|e| SmsError::Invalid(format!("inbound message: {}", e)))?;
+            Ok(inbound.into())
+        }
+    }
+}
+
+/// The `africastalking` configuration section read by the provider plugin.
+#[derive(Deserialize)]
+struct AfricasTalkingSettings {
+    username: String,
+    api_key: String,
+    base_url: Option<String>,
+    #[serde(default)]
+    http: Option<HttpClientConfig>,
+}
+
+/// Build an [`AfricasTalkingClient`] from the `africastalking` section.
+fn build_plugin(settings: &serde_json::Value) -> Result<sms_core::BuiltProvider, SmsError> {
+    let s: AfricasTalkingSettings = sms_core::provider_settings(PROVIDER, settings)?;
+    let client = match s.base_url {
+        Some(base_url) => AfricasTalkingClient::with_base_url(s.username, s.api_key, base_url),
+        None if s.username == "sandbox" => AfricasTalkingClient::sandbox(s.api_key),
+        None => AfricasTalkingClient::new(s.username, s.api_key),
+    };
+    let client = match s.http {
+        Some(http) => client.with_http_config(http)?,
+        None => client,
+    };
+    Ok(sms_core::BuiltProvider::both(client))
+}
+
+sms_core::register_provider!("africastalking", build_plugin);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn send_request_is_form_encoded() {
+        let payload = AtSendRequest {
+            username: "myapp",
+            to: "+254711000000",
+            message: "Hi there",
+            from: "",
+        };
+        let form = serde_urlencoded::to_string(&payload).unwrap();
+        assert_eq!(form, "username=myapp&to=%2B254711000000&message=Hi+there");
+    }
+
+    #[test]
+    fn send_details_from_response() {
+        let resp = SendResponse {
+            id: "ATXid_1".into(),
+            provider: PROVIDER,
+            channel: Channel::Sms,
+            http_status: Some(201),
+            provider_request_id: None,
+            thread_id: None,
+            raw: json!({
+                "SMSMessageData": {
+                    "Message": "Sent to 1/1 Total Cost: KES 0.8000",
+                    "Recipients": [{
+                        "statusCode": 101,
+                        "number": "+254711000000",
+                        "status": "Success",
+                        "cost": "KES 0.8000",
+                        "messageId": "ATXid_1"
+                    }]
+                }
+            }),
+        };
+        let details = AtSendDetails::from_response(&resp).unwrap();
+        let recipient = &details.data.recipients[0];
+        assert!(recipient.is_success());
+        assert_eq!(recipient.cost, "KES 0.8000");
+
+        let failed: AtRecipient = serde_json::from_value(json!({
+            "statusCode": 403,
+            "number": "+2547",
+            "status": "InvalidPhoneNumber",
+            "messageId": "None"
+        }))
+        .unwrap();
+        assert!(!failed.is_success());
+    }
+
+    #[test]
+    fn parses_inbound_message() {
+        let client = AfricasTalkingClient::sandbox("key");
+        let body = b"from=%2B254711000000&to=12345&text=Hello&id=abc&date=2024-01-15T10%3A30%3A00Z&linkId=L1";
+        let msg = client.parse_inbound(&Headers::new(), body).unwrap();
+        assert_eq!(msg.direction, InboundDirection::Inbound);
+        assert_eq!(msg.from, "+254711000000");
+        assert_eq!(msg.to, "12345");
+        assert_eq!(msg.text, "Hello");
+        assert_eq!(msg.id.as_deref(), Some("abc"));
+        assert!(msg.timestamp.is_some());
+        assert_eq!(msg.raw["linkId"], "L1");
+    }
+
+    #[test]
+    fn parses_delivery_report() {
+        let client = AfricasTalkingClient::sandbox("key");
+        let body = b"id=ATXid_1&status=Failed&phoneNumber=%2B254711000000&networkCode=63902&failureReason=UserInBlacklist&retryCount=0";
+        let msg = client.parse_inbound(&Headers::new(), body).unwrap();
+        assert_eq!(msg.direction, InboundDirection::DeliveryReport);
+        assert_eq!(msg.id.as_deref(), Some("ATXid_1"));
+        assert_eq!(msg.to, "+254711000000");
+        assert_eq!(msg.text, "Delivery Status: Failed");
+
+        let report: AtDeliveryReport = serde_json::from_value(msg.raw).unwrap();
+        assert!(report.is_failure());
+        assert_eq!(report.failure_reason.as_deref(), Some("UserInBlacklist"));
+    }
+
+    #[test]
+    fn rejects_malformed_webhooks() {
+        let client = AfricasTalkingClient::sandbox("key");
+        assert!(
+            client
+                .parse_inbound(&Headers::new(), b"text=orphan")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn plugin_builds_from_settings() {
+        let plugin = sms_core::ProviderPlugin::find("africastalking").unwrap();
+        let built = plugin
+            .build(&json!({ "username": "sandbox", "api_key": "key" }))
+            .unwrap();
+        assert_eq!(built.webhook.unwrap().provider(), "africastalking");
+        assert!(built.client.is_some());
+        assert!(plugin.build(&json!({ "username": "myapp" })).is_err());
+    }
+
+    #[test]
+    fn constructors_pick_base_url() {
+        assert_eq!(AfricasTalkingClient::new("app", "k").base_url, LIVE_URL);
+        let sandbox = AfricasTalkingClient::sandbox("k");
+        assert_eq!(sandbox.base_url, SANDBOX_URL);
+        assert_eq!(sandbox.username, "sandbox");
+    }
+
+    #[test]
+    fn debug_redacts_api_key() {
+        let debug = format!("{:?}", AfricasTalkingClient::new("app", "hunter2"));
+        assert!(debug.contains("app"));
+        assert!(!debug.contains("hunter2"));
+    }
+}
@@ -0,0 +1,639 @@
+//! # AWS Pinpoint SMS Provider
+//!
+//! AWS End User Messaging SMS (the Pinpoint SMS and Voice v2 API) provider
+//! for smskit.  This is the API AWS now recommends over SNS for SMS: sends go
+//! through `SendTextMessage` from a registered origination identity, and
+//! delivery events and two-way replies are published to SNS topics you
+//! subscribe a webhook to.
+//!
+//! ## Sending messages
+//!
+//! ```rust,ignore
+//! use sms_core::{SendRequest, SmsClient};
+//! use sms_aws_pinpoint::AwsPinpointClient;
+//!
+//! let client = AwsPinpointClient::new("us-east-1", "access_key", "secret_key")
+//!     .with_origination_identity("pool-0123456789abcdef0123456789abcdef")
+//!     .with_configuration_set("delivery-events");
+//! let response = client.send(SendRequest {
+//!     to: "+14155551234",
+//!     text: "Hello from AWS Pinpoint!",
+//!     ..Default::default()
+//! }).await?;
+//! ```
+//!
+//! A non-empty [`SendRequest::from`] overrides the default origination
+//! identity for that send.  Either may be a phone number, sender ID, phone
+//! number ID, pool ID, or the ARN of any of those.
+//!
+//! ## Webhooks
+//!
+//! [`InboundWebhook::parse_inbound`] accepts the SNS notification envelope
+//! (or its bare `Message` payload) for both two-way SMS replies and
+//! configuration-set delivery events, and tells them apart by shape.
+//!
+//! ## Features
+//!
+//! - Send SMS via `SendTextMessage`
+//! - Origination identities and pools, with per-request override
+//! - Configuration sets for event destinations
+//! - Two-way SMS and delivery event parsing
+//! - Standard AWS credential management
+
+use aws_config::{BehaviorVersion, Region};
+use aws_sdk_pinpointsmsvoicev2::config::Credentials;
+use aws_sdk_pinpointsmsvoicev2::operation::RequestId;
+use aws_sdk_pinpointsmsvoicev2::operation::send_text_message::SendTextMessageError;
+use aws_sdk_pinpointsmsvoicev2::{Client as PinpointClient, Config as PinpointConfig};
+use serde::{Deserialize, Serialize};
+use sms_core::*;
+use tracing::{debug, error, info};
+
+pub use aws_sdk_pinpointsmsvoicev2::types::MessageType;
+
+const PROVIDER: &str = "aws-pinpoint";
+
+/// AWS Pinpoint SMS and Voice v2 client.
+///
+/// Wraps the AWS SDK's `pinpointsmsvoicev2` client with the smskit
+/// [`SmsClient`] and [`InboundWebhook`] traits.
+///
+/// # Construction
+///
+/// | Method | Description |
+/// |--------|-------------|
+/// | [`AwsPinpointClient::new`] | Explicit region + credentials |
+/// | [`AwsPinpointClient::from_env`] | Read standard `AWS_*` env vars |
+/// | [`AwsPinpointClient::with_default_credentials`] | Use the default AWS credential chain (async) |
+#[derive(Debug, Clone)]
+pub struct AwsPinpointClient {
+    client: PinpointClient,
+    region: String,
+    origination_identity: Option<String>,
+    configuration_set: Option<String>,
+    message_type: Option<MessageType>,
+}
+
+impl AwsPinpointClient {
+    /// Create a new client with explicit credentials.
+    ///
+    /// # Arguments
+    ///
+    /// * `region`            - AWS region name, e.g. `"us-east-1"`.
+    /// * `access_key_id`     - IAM access key ID.
+    /// * `secret_access_key` - IAM secret access key.
+    pub fn new(
+        region: impl Into<String>,
+        access_key_id: impl Into<String>,
+        secret_access_key: impl Into<String>,
+    ) -> Self {
+        let region = region.into();
+        let credentials = Credentials::new(access_key_id, secret_access_key, None, None, "smskit");
+        let config = PinpointConfig::builder()
+            .region(Region::new(region.clone()))
+            .credentials_provider(credentials)
+            .behavior_version(BehaviorVersion::latest())
+            .build();
+        Self::from_sdk(PinpointClient::from_conf(config), region)
+    }
+
+    /// Create a client from standard AWS environment variables.
+    ///
+    /// | Variable                             | Required | Notes |
+    /// |--------------------------------------|----------|-------|
+    /// | `AWS_REGION`                         | Yes*     | Falls back to `AWS_DEFAULT_REGION` |
+    /// | `AWS_ACCESS_KEY_ID`                  | Yes      | |
+    /// | `AWS_SECRET_ACCESS_KEY`              | Yes      | |
+    /// | `AWS_PINPOINT_ORIGINATION_IDENTITY`  | No       | Default origination identity |
+    /// | `AWS_PINPOINT_CONFIGURATION_SET`     | No       | Default configuration set |
+    ///
+    /// Returns [`SmsError::Auth`] if any required variable is missing.
+    pub fn from_env() -> Result<Self, SmsError> {
+        let region = std::env::var("AWS_REGION")
+            .or_else(|_| std::env::var("AWS_DEFAULT_REGION"))
+            .map_err(|_| SmsError::Auth("AWS_REGION (or AWS_DEFAULT_REGION) not set".into()))?;
+        let access_key_id = std::env::var("AWS_ACCESS_KEY_ID")
+            .map_err(|_| SmsError::Auth("AWS_ACCESS_KEY_ID not set".into()))?;
+        let secret_access_key = std::env::var("AWS_SECRET_ACCESS_KEY")
+            .map_err(|_| SmsError::Auth("AWS_SECRET_ACCESS_KEY not set".into()))?;
+        let mut client = Self::new(region, access_key_id, secret_access_key);
+        client.origination_identity = std::env::var("AWS_PINPOINT_ORIGINATION_IDENTITY").ok();
+        client.configuration_set = std::env::var("AWS_PINPOINT_CONFIGURATION_SET").ok();
+        Ok(client)
+    }
+
+    /// Create a client using the default AWS credential chain (profile files,
+    /// instance metadata, ECS task role, etc.).
+    ///
+    /// This is an async constructor because the default credential chain may
+    /// need to make HTTP calls (e.g. to the EC2 metadata service).
+    pub async fn with_default_credentials(region: impl Into<String>) -> Self {
+        let region = region.into();
+        let config = aws_config::defaults(BehaviorVersion::latest())
+            .region(Region::new(region.clone()))
+            .load()
+            .await;
+        Self::from_sdk(PinpointClient::new(&config), region)
+    }
+
+    fn from_sdk(client: PinpointClient, region: String) -> Self {
+        Self {
+            client,
+            region,
+            origination_identity: None,
+            configuration_set: None,
+            message_type: None,
+        }
+    }
+
+    /// Send from `identity` when the request leaves `from` empty: a phone
+    /// number, sender ID, phone number ID, pool ID, or any of their ARNs.
+    ///
+    /// Without one, AWS picks an identity from the account.
+    pub fn with_origination_identity(mut self, identity: impl Into<String>) -> Self {
+        self.origination_identity = Some(identity.into());
+        self
+    }
+
+    /// Publish send events to the event destinations of configuration set
+    /// `name`.
+    pub fn with_configuration_set(mut self, name: impl Into<String>) -> Self {
+        self.configuration_set = Some(name.into());
+        self
+    }
+
+    /// Mark sends as transactional or promotional (default: the account's
+    /// setting).
+    pub fn with_message_type(mut self, message_type: MessageType) -> Self {
+        self.message_type = Some(message_type);
+        self
+    }
+}
+
+/// Typed view of the data recorded for a `SendTextMessage`, available from
+/// [`SendResponse::raw`] via [`PinpointSendDetails::from_response`].
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PinpointSendDetails {
+    /// Pinpoint-assigned message ID.
+    #[serde(rename = "MessageId")]
+    pub message_id: String,
+    /// AWS region the message was sent from.
+    #[serde(rename = "Region")]
+    pub region: String,
+    /// Origination identity the send asked for, if any.
+    #[serde(rename = "OriginationIdentity")]
+    pub origination_identity: Option<String>,
+    /// Configuration set the send used, if any.
+    #[serde(rename = "ConfigurationSetName")]
+    pub configuration_set_name: Option<String>,
+}
+
+impl PinpointSendDetails {
+    /// Parse the raw payload of an AWS Pinpoint [`SendResponse`].
+    pub fn from_response(resp: &SendResponse) -> Result<Self, SmsError> {
+        if resp.provider != PROVIDER {
+            return Err(SmsError::Invalid(format!(
+                "expected a {} response, got {}",
+                PROVIDER, resp.provider
+            )));
+        }
+        serde_json::from_value(resp.raw.clone())
+            .map_err(|e| SmsError::Invalid(format!("pinpoint send response: {}", e)))
+    }
+}
+
+impl SmsClient for AwsPinpointClient {
+    async fn send(&self, req: SendRequest<'_>) -> Result<SendResponse, SmsError> {
+        validate_text(req.text, self.max_text_len())?;
+        let origination = Some(req.from)
+            .filter(|from| !from.is_empty())
+            .map(str::to_string)
+            .or_else(|| self.origination_identity.clone());
+
+        debug!(
+            "Sending Pinpoint message to {} from {:?}",
+            redact_number(req.to),
+            origination
+        );
+
+        let result = self
+            .client
+            .send_text_message()
+            .destination_phone_number(req.to)
+            .message_body(req.text)
+            .set_origination_identity(origination.clone())
+            .set_configuration_set_name(self.configuration_set.clone())
+            .set_message_type(self.message_type.clone())
+            .send()
+            .await
+            .map_err(|e| {
+                if let aws_sdk_pinpointsmsvoicev2::error::SdkError::TimeoutError(_) = &e {
+                    return SmsError::Timeout("AWS Pinpoint send timed out".to_string());
+                }
+                match e.into_service_error() {
+                    SendTextMessageError::AccessDeniedException(e) => SmsError::Auth(
+                        e.message()
+                            .unwrap_or("AWS authorization failed")
+                            .to_string(),
+                    ),
+                    SendTextMessageError::ValidationException(e) => {
+                        SmsError::Invalid(e.message().unwrap_or("Invalid parameter").to_string())
+                    }
+                    SendTextMessageError::ResourceNotFoundException(e) => {
+                        SmsError::Invalid(e.message().unwrap_or("Resource not found").to_string())
+                    }
+                    SendTextMessageError::ThrottlingException(_) => {
+                        SmsError::RateLimited { retry_after: None }
+                    }
+                    e => SmsError::Provider(format!("AWS Pinpoint error: {}", e)),
+                }
+            })?;
+
+        let message_id = result.message_id().unwrap_or_default().to_string();
+        let request_id = result.request_id().map(String::from);
+
+        let raw_json = serde_json::json!({
+            "MessageId": message_id,
+            "Region": self.region,
+            "OriginationIdentity": origination,
+            "ConfigurationSetName": self.configuration_set,
+        });
+
+        Ok(SendResponse {
+            id: message_id,
+            provider: PROVIDER,
+            channel: Channel::Sms,
+            http_status: Some(200),
+            provider_request_id: request_id,
+            thread_id: Some(req.thread_id()),
+            raw: raw_json,
+        })
+    }
+}
+
+/// A two-way SMS reply, as published to the phone number's or pool's
+/// two-way SNS topic.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PinpointInboundSms {
+    /// Sender phone number.
+    pub origination_number: String,
+    /// Your phone number the reply was sent to.
+    pub destination_number: String,
+    /// Message body.
+    pub message_body: String,
+    /// Pinpoint-assigned ID of the inbound message.
+    pub inbound_message_id: String,
+    /// Registered keyword the message matched, if any.
+    #[serde(default)]
+    pub message_keyword: Option<String>,
+    /// ID of the last message you sent to this number, if any.
+    #[serde(default)]
+    pub previous_published_message_id: Option<String>,
+}
+
+impl PinpointInboundSms {
+    /// Extract the reply from a Pinpoint inbound [`InboundMessage`].
+    pub fn from_message(msg: &InboundMessage) -> Result<Self, SmsError> {
+        expect_provider(msg)?;
+        serde_json::from_value(msg.raw.clone())
+            .map_err(|e| SmsError::Invalid(format!("pinpoint inbound sms: {}", e)))
+    }
+}
+
+/// A send event published to a configuration set's SNS event destination.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PinpointDeliveryEvent {
+    /// e.g. `"TEXT_DELIVERED"`, `"TEXT_BLOCKED"`, `"TEXT_CARRIER_UNREACHABLE"`.
+    pub event_type: String,
+    /// ID of the message the event is about.
+    pub message_id: String,
+    /// Destination number of the original message.
+    pub destination_phone_number: String,
+    /// e.g. `"DELIVERED"`, `"PENDING"`, `"BLOCKED"`.
+    pub message_status: String,
+    /// Human-readable detail for the status.
+    #[serde(default)]
+    pub message_status_description: Option<String>,
+    /// Origination number the message was sent from.
+    #[serde(default)]
+    pub origination_phone_number: Option<String>,
+    /// Event time in milliseconds since the Unix epoch.
+    #[serde(default)]
+    pub event_timestamp: Option<i64>,
+    /// `true` once no further events will follow for this message.
+    #[serde(default)]
+    pub is_final: Option<bool>,
+    /// Price in USD across all parts of the message.
+    #[serde(default)]
+    pub total_message_price: Option<f64>,
+}
+
+impl PinpointDeliveryEvent {
+    /// Extract the event from a Pinpoint delivery-report
+    /// [`InboundMessage`].
+    pub fn from_message(msg: &InboundMessage) -> Result<Self, SmsError> {
+        expect_provider(msg)?;
+        serde_json::from_value(msg.raw.clone())
+            .map_err(|e| SmsError::Invalid(format!("pinpoint delivery event: {}", e)))
+    }
+
+    /// Returns `true` if the carrier confirmed delivery.
+    pub fn is_delivered(&self) -> bool {
+        self.message_status == "DELIVERED"
+    }
+}
+
+fn expect_provider(msg: &InboundMessage) -> Result<(), SmsError> {
+    if msg.provider != PROVIDER {
+        return Err(SmsError::Invalid(format!(
+            "expected a {} message, got {}",
+            PROVIDER, msg.provider
+        )));
+    }
+    Ok(())
+}
+
+/// Extract the AWS account ID from an ARN such as
+/// `arn:aws:sns:us-east-1:123456789012:topic`.
+fn account_from_arn(arn: &str) -> Option<String> {
+    arn.split(':')
+        .nth(4)
+        .filter(|a| !a.is_empty())
+        .map(String::from)
+}
+
+impl InboundWebhook for AwsPinpointClient {
+    fn provider(&self) -> &'static str {
+        PROVIDER
+    }
+
+    fn parse_inbound(&self, _headers: &Headers, body: &[u8]) -> Result<InboundMessage, SmsError> {
+        let mut payload: serde_json::Value = serde_json::from_slice(body).map_err(|e| {
+            error!("Invalid Pinpoint webhook payload: {}", e);
+            SmsError::Invalid(format!("Invalid notification format: {}", e))
+        })?;
+
+        // Unwrap the SNS envelope, if the payload is still in one.
+        let mut provider_account = None;
+        if let Some(kind) = payload.get("Type").and_then(|t| t.as_str()) {
+            if kind != "Notification" {
+                return Err(SmsError::Invalid(format!(
+                    "Unsupported SNS notification type: {}",
+                    kind
+                )));
+            }
+            provider_account = payload
+                .get("TopicArn")
+                .and_then(|a| a.as_str())
+                .and_then(account_from_arn);
+            let message = payload
+                .get("Message")
+                .and_then(|m| m.as_str())
+                .ok_or_else(|| SmsError::Invalid("SNS notification without Message".into()))?;
+            payload = serde_json::from_str(message)
+                .map_err(|e| SmsError::Invalid(format!("Invalid Pinpoint message: {}", e)))?;
+        }
+
+        if payload.get("inboundMessageId").is_some() {
+            let sms: PinpointInboundSms = serde_json::from_value(payload.clone())
+                .map_err(|e| SmsError::Invalid(format!("pinpoint inbound sms: {}", e)))?;
+            info!("Received Pinpoint two-way SMS {}", sms.inbound_message_id);
+            return Ok(InboundMessage {
+                id: Some(sms.inbound_message_id),
+                from: sms.origination_number,
+                to: sms.destination_number,
+                text: sms.message_body,
+                timestamp: None,
+                provider: PROVIDER,
+                direction: InboundDirection::Inbound,
+                channel: Channel::Sms,
+                provider_account,
+                thread_id: None,
+                flag: None,
+                raw: payload,
+            }
+            .with_thread_id());
+        }
+
+        if payload.get("eventType").is_some() {
+            let event: PinpointDeliveryEvent = serde_json::from_value(payload.clone())
+                .map_err(|e| SmsError::Invalid(format!("pinpoint delivery event: {}", e)))?;
+            info!(
+                "Received Pinpoint {} for message {}",
+                event.event_type, event.message_id
+            );
+            let timestamp = event.event_timestamp.and_then(|ms| {
+                time::OffsetDateTime::from_unix_timestamp_nanos(ms as i128 * 1_000_000).ok()
+            });
+            return Ok(InboundMessage {
+                id: Some(event.message_id),
+                from: "AWS-Pinpoint".to_string(),
+                to: event.destination_phone_number,
+                text: format!("Delivery Status: {}", event.message_status),
+                timestamp,
+                provider: PROVIDER,
+                direction: InboundDirection::DeliveryReport,
+                channel: Channel::Sms,
+                provider_account,
+                thread_id: None,
+                flag: None,
+                raw: payload,
+            }
+            .with_thread_id());
+        }
+
+        Err(SmsError::Invalid(
+            "Neither a two-way SMS nor a delivery event".into(),
+        ))
+    }
+}
+
+/// The `aws_pinpoint` configuration section read by the provider plugin.
+#[derive(Deserialize)]
+struct AwsPinpointSettings {
+    region: String,
+    access_key_id: String,
+    secret_access_key: String,
+    origination_identity: Option<String>,
+    configuration_set: Option<String>,
+    /// `"transactional"` or `"promotional"`.
+    message_type: Option<String>,
+}
+
+/// Build an [`AwsPinpointClient`] from the `aws_pinpoint` configuration
+/// section.
+fn build_plugin(settings: &serde_json::Value) -> Result<BuiltProvider, SmsError> {
+    let s: AwsPinpointSettings = provider_settings("aws_pinpoint", settings)?;
+    let mut client = AwsPinpointClient::new(s.region, s.access_key_id, s.secret_access_key);
+    client.origination_identity = s.origination_identity;
+    client.configuration_set = s.configuration_set;
+    if let Some(kind) = s.message_type {
+        client.message_type = Some(match kind.to_ascii_lowercase().as_str() {
+            "transactional" => MessageType::Transactional,
+            "promotional" => MessageType::Promotional,
+            other => {
+                return Err(SmsError::Invalid(format!(
+                    "aws_pinpoint: unknown message_type {}",
+                    other
+                )));
+            }
+        });
+    }
+    Ok(BuiltProvider::both(client))
+}
+
+sms_core::register_provider!("aws_pinpoint", build_plugin);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn client() -> AwsPinpointClient {
+        AwsPinpointClient::new("us-east-1", "key", "secret")
+    }
+
+    fn sns_envelope(message: &serde_json::Value) -> Vec<u8> {
+        json!({
+            "Type": "Notification",
+            "MessageId": "sns-1",
+            "TopicArn": "arn:aws:sns:us-east-1:123456789012:two-way",
+            "Message": message.to_string(),
+            "Timestamp": "2024-01-01T00:00:00.000Z",
+        })
+        .to_string()
+        .into_bytes()
+    }
+
+    fn inbound_json() -> serde_json::Value {
+        json!({
+            "originationNumber": "+14155550100",
+            "destinationNumber": "+18445550199",
+            "messageKeyword": "HELP",
+            "messageBody": "HELP please",
+            "inboundMessageId": "in-123",
+            "previousPublishedMessageId": "out-456"
+        })
+    }
+
+    #[test]
+    fn builders_set_send_defaults() {
+        let client = client()
+            .with_origination_identity("pool-abc")
+            .with_configuration_set("events")
+            .with_message_type(MessageType::Transactional);
+        assert_eq!(client.region, "us-east-1");
+        assert_eq!(client.origination_identity.as_deref(), Some("pool-abc"));
+        assert_eq!(client.configuration_set.as_deref(), Some("events"));
+        assert_eq!(client.message_type, Some(MessageType::Transactional));
+    }
+
+    #[test]
+    fn parses_bare_two_way_sms() {
+        let body = inbound_json().to_string();
+        let msg = client()
+            .parse_inbound(&Headers::new(), body.as_bytes())
+            .unwrap();
+        assert_eq!(msg.direction, InboundDirection::Inbound);
+        assert_eq!(msg.id.as_deref(), Some("in-123"));
+        assert_eq!(msg.from, "+14155550100");
+        assert_eq!(msg.to, "+18445550199");
+        assert_eq!(msg.text, "HELP please");
+        assert_eq!(msg.provider_account, None);
+
+        let sms = PinpointInboundSms::from_message(&msg).unwrap();
+        assert_eq!(sms.message_keyword.as_deref(), Some("HELP"));
+    }
+
+    #[test]
+    fn parses_two_way_sms_in_sns_envelope() {
+        let body = sns_envelope(&inbound_json());
+        let msg = client().parse_inbound(&Headers::new(), &body).unwrap();
+        assert_eq!(msg.text, "HELP please");
+        assert_eq!(msg.provider_account.as_deref(), Some("123456789012"));
+    }
+
+    #[test]
+    fn parses_delivery_event() {
+        let event = json!({
+            "eventType": "TEXT_DELIVERED",
+            "eventVersion": "1.0",
+            "eventTimestamp": 1_704_067_200_000_i64,
+            "isFinal": true,
+            "originationPhoneNumber": "+18445550199",
+            "destinationPhoneNumber": "+14155550100",
+            "messageId": "out-456",
+            "messageStatus": "DELIVERED",
+            "messageStatusDescription": "Message has been accepted by phone",
+            "totalMessagePrice": 0.00581
+        });
+        let msg = client()
+            .parse_inbound(&Headers::new(), &sns_envelope(&event))
+            .unwrap();
+        assert_eq!(msg.direction, InboundDirection::DeliveryReport);
+        assert_eq!(msg.id.as_deref(), Some("out-456"));
+        assert_eq!(msg.to, "+14155550100");
+        assert_eq!(msg.text, "Delivery Status: DELIVERED");
+        assert_eq!(msg.timestamp.unwrap().unix_timestamp(), 1_704_067_200);
+
+        let event = PinpointDeliveryEvent::from_message(&msg).unwrap();
+        assert!(event.is_delivered());
+        assert_eq!(event.is_final, Some(true));
+    }
+
+    #[test]
+    fn rejects_unrecognized_payloads() {
+        let client = client();
+        assert!(client.parse_inbound(&Headers::new(), b"not json").is_err());
+        assert!(client.parse_inbound(&Headers::new(), b"{}").is_err());
+        let confirm = json!({ "Type": "SubscriptionConfirmation", "Message": "" }).to_string();
+        assert!(
+            client
+                .parse_inbound(&Headers::new(), confirm.as_bytes())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn send_details_from_response() {
+        let resp = SendResponse {
+            id: "out-456".into(),
+            provider: PROVIDER,
+            channel: Channel::Sms,
+            http_status: Some(200),
+            provider_request_id: None,
+            thread_id: None,
+            raw: json!({
+                "MessageId": "out-456",
+                "Region": "us-east-1",
+                "OriginationIdentity": "pool-abc",
+                "ConfigurationSetName": null
+            }),
+        };
+        let details = PinpointSendDetails::from_response(&resp).unwrap();
+        assert_eq!(details.origination_identity.as_deref(), Some("pool-abc"));
+        assert_eq!(details.configuration_set_name, None);
+    }
+
+    #[test]
+    fn plugin_builds_from_settings() {
+        let plugin = sms_core::ProviderPlugin::find("aws_pinpoint").unwrap();
+        let settings = json!({
+            "region": "us-east-1",
+            "access_key_id": "key",
+            "secret_access_key": "secret",
+            "origination_identity": "+18445550199",
+            "message_type": "Promotional"
+        });
+        let built = plugin.build(&settings).unwrap();
+        assert_eq!(built.webhook.unwrap().provider(), "aws-pinpoint");
+        assert!(built.client.is_some());
+
+        let mut bad = settings;
+        bad["message_type"] = json!("urgent");
+        assert!(plugin.build(&bad).is_err());
+        assert!(plugin.build(&json!({ "region": "us-east-1" })).is_err());
+    }
+}
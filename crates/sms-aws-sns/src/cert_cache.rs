@@ -0,0 +1,272 @@
+//! Caching of SNS signing certificates.
+//!
+//! Every SNS message names the certificate it was signed with
+//! (`SigningCertURL`).  Fetching it on every delivery report would add an
+//! HTTPS round trip per webhook, so [`SigningCertCache`] keeps fetched PEMs
+//! for a TTL and remembers failed fetches for a shorter negative TTL, which
+//! stops a flood of forged messages pointing at a bad URL from turning into
+//! a flood of outbound requests.
+//!
+//! Only URLs that pass [`is_trusted_cert_url`] are ever fetched.
+//!
+//! ```rust,ignore
+//! use sms_aws_sns::{CertFetcher, SigningCertCache};
+//!
+//! let cache = SigningCertCache::new(my_fetcher).with_ttl(Duration::from_secs(3600));
+//! let pem = cache.get(&notification.signing_cert_url)?;
+//! ```
+
+use sms_core::SmsError;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Default lifetime of a cached certificate.
+pub const DEFAULT_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Default lifetime of a cached fetch failure.
+pub const DEFAULT_NEGATIVE_TTL: Duration = Duration::from_secs(60);
+
+/// Retrieves a PEM-encoded certificate by URL.
+///
+/// Webhook verification is synchronous, so implementations block; a
+/// `reqwest::blocking` client or a pre-populated map both work.
+pub trait CertFetcher: Send + Sync {
+    /// Fetch the certificate at `url`.
+    fn fetch(&self, url: &str) -> Result<String, SmsError>;
+}
+
+impl<F> CertFetcher for F
+where
+    F: Fn(&str) -> Result<String, SmsError> + Send + Sync,
+{
+    fn fetch(&self, url: &str) -> Result<String, SmsError> {
+        self(url)
+    }
+}
+
+/// Returns `true` if `url` is an HTTPS URL on an SNS endpoint
+/// (`sns.<region>.amazonaws.com`, including the China partition) pointing at
+/// a `.pem` file.
+pub fn is_trusted_cert_url(url: &str) -> bool {
+    let Some(rest) = url.strip_prefix("https://") else {
+        return false;
+    };
+    let Some((host, path)) = rest.split_once('/') else {
+        return false;
+    };
+    let region_host = host.strip_prefix("sns.").and_then(|h| {
+        h.strip_suffix(".amazonaws.com")
+            .or_else(|| h.strip_suffix(".amazonaws.com.cn"))
+    });
+    let valid_region = region_host.is_some_and(|region| {
+        !region.is_empty()
+            && region
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+    });
+    valid_region && path.ends_with(".pem") && !path.contains("..")
+}
+
+#[derive(Debug, Clone)]
+enum Entry {
+    Cert(Arc<String>),
+    Failed(String),
+}
+
+/// TTL cache of SNS signing certificates keyed by URL, with negative caching.
+///
+/// Cloning yields another handle to the same cache.
+#[derive(Clone)]
+pub struct SigningCertCache {
+    fetcher: Arc<dyn CertFetcher>,
+    entries: Arc<Mutex<HashMap<String, (Entry, Instant)>>>,
+    ttl: Duration,
+    negative_ttl: Duration,
+}
+
+impl std::fmt::Debug for SigningCertCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SigningCertCache")
+            .field("entries", &self.len())
+            .field("ttl", &self.ttl)
+            .field("negative_ttl", &self.negative_ttl)
+            .finish()
+    }
+}
+
+impl SigningCertCache {
+    /// Create a cache with [`DEFAULT_TTL`] and [`DEFAULT_NEGATIVE_TTL`].
+    pub fn new(fetcher: impl CertFetcher + 'static) -> Self {
+        Self {
+            fetcher: Arc::new(fetcher),
+            entries: Arc::default(),
+            ttl: DEFAULT_TTL,
+            negative_ttl: DEFAULT_NEGATIVE_TTL,
+        }
+    }
+
+    /// Keep fetched certificates for `ttl`.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Remember failed fetches for `ttl`.
+    pub fn with_negative_ttl(mut self, ttl: Duration) -> Self {
+        self.negative_ttl = ttl;
+        self
+    }
+
+    /// Return the certificate at `url`, fetching it if it is not cached.
+    ///
+    /// Untrusted URLs are rejected with [`SmsError::Auth`] without a fetch.
+    /// A cached failure is returned as [`SmsError::Auth`] until its negative
+    /// TTL expires.
+    pub fn get(&self, url: &str) -> Result<Arc<String>, SmsError> {
+        if !is_trusted_cert_url(url) {
+            return Err(SmsError::Auth(format!(
+                "untrusted signing cert URL: {}",
+                url
+            )));
+        }
+
+        let now = Instant::now();
+        let cached = self
+            .lock()
+            .get(url)
+            .filter(|(_, expires)| now < *expires)
+            .map(|(entry, _)| entry.clone());
+        match cached {
+            Some(Entry::Cert(pem)) => return Ok(pem),
+            Some(Entry::Failed(e)) => {
+                return Err(SmsError::Auth(format!(
+                    "signing cert unavailable (cached): {}",
+                    e
+                )));
+            }
+            None => {}
+        }
+
+        // Fetch outside the lock so one slow fetch doesn't block lookups of
+        // other certificates.
+        let result = self.fetcher.fetch(url);
+        let mut entries = self.lock();
+        entries.retain(|_, (_, expires)| now < *expires);
+        match result {
+            Ok(pem) => {
+                let pem = Arc::new(pem);
+                entries.insert(url.to_string(), (Entry::Cert(pem.clone()), now + self.ttl));
+                Ok(pem)
+            }
+            Err(e) => {
+                let message = e.to_string();
+                entries.insert(
+                    url.to_string(),
+                    (Entry::Failed(message.clone()), now + self.negative_ttl),
+                );
+                Err(SmsError::Auth(format!(
+                    "signing cert unavailable: {}",
+                    message
+                )))
+            }
+        }
+    }
+
+    /// Number of cached entries, including cached failures.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Returns `true` if nothing is cached.
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Drop every cached entry.
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, (Entry, Instant)>> {
+        self.entries.lock().expect("cert cache poisoned")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const URL: &str = "https://sns.us-east-1.amazonaws.com/SimpleNotificationService-abc.pem";
+
+    fn counting(fail: bool) -> (Arc<AtomicUsize>, impl CertFetcher + 'static) {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let fetcher = move |_: &str| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            if fail {
+                Err(SmsError::Http("connection refused".into()))
+            } else {
+                Ok("-----BEGIN CERTIFICATE-----".to_string())
+            }
+        };
+        (calls, fetcher)
+    }
+
+    #[test]
+    fn trusted_urls() {
+        assert!(is_trusted_cert_url(URL));
+        assert!(is_trusted_cert_url(
+            "https://sns.cn-north-1.amazonaws.com.cn/cert.pem"
+        ));
+        assert!(!is_trusted_cert_url(
+            "http://sns.us-east-1.amazonaws.com/cert.pem"
+        ));
+        assert!(!is_trusted_cert_url("https://evil.example.com/cert.pem"));
+        assert!(!is_trusted_cert_url(
+            "https://sns.us-east-1.amazonaws.com.evil.com/cert.pem"
+        ));
+        assert!(!is_trusted_cert_url(
+            "https://sns.us-east-1.amazonaws.com/cert.txt"
+        ));
+    }
+
+    #[test]
+    fn caches_fetched_certificates() {
+        let (calls, fetcher) = counting(false);
+        let cache = SigningCertCache::new(fetcher);
+        cache.get(URL).unwrap();
+        cache.get(URL).unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn refetches_after_ttl() {
+        let (calls, fetcher) = counting(false);
+        let cache = SigningCertCache::new(fetcher).with_ttl(Duration::ZERO);
+        cache.get(URL).unwrap();
+        cache.get(URL).unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn caches_failures() {
+        let (calls, fetcher) = counting(true);
+        let cache = SigningCertCache::new(fetcher);
+        assert!(matches!(cache.get(URL), Err(SmsError::Auth(_))));
+        let cached = cache.get(URL).unwrap_err().to_string();
+        assert!(cached.contains("cached"));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn never_fetches_untrusted_urls() {
+        let (calls, fetcher) = counting(false);
+        let cache = SigningCertCache::new(fetcher);
+        assert!(cache.get("https://attacker.example/cert.pem").is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+        assert!(cache.is_empty());
+    }
+}
@@ -0,0 +1,123 @@
+//! SNS `Publish` error codes.
+//!
+//! Authorization, invalid-parameter and throttling failures keep their
+//! dedicated [`SmsError`](sms_core::SmsError) variants; every other SNS
+//! exception is returned as [`SmsError::Rejected`](sms_core::SmsError::Rejected)
+//! with its code classified by [`SnsErrorCode`].
+
+use sms_core::{ProviderError, ProviderErrorKind};
+
+/// An SNS error code, as returned in the `Code` element of an error response.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum SnsErrorCode {
+    /// `AuthorizationError`: the caller may not perform the action.
+    AuthorizationError,
+    /// `InvalidSecurity`: the request was not signed correctly.
+    InvalidSecurity,
+    /// `InvalidParameter`: a request parameter is invalid.
+    InvalidParameter,
+    /// `ParameterValueInvalid`: a parameter value is out of range.
+    ParameterValueInvalid,
+    /// `ValidationException`: the request failed validation.
+    Validation,
+    /// `Throttled`: the account's send rate was exceeded.
+    Throttled,
+    /// `KMSThrottling`: KMS throttled the request for an encrypted topic.
+    KmsThrottling,
+    /// `EndpointDisabled`: the target endpoint is disabled.
+    EndpointDisabled,
+    /// `PlatformApplicationDisabled`: the platform application is disabled.
+    PlatformApplicationDisabled,
+    /// `NotFound`: the target resource does not exist.
+    NotFound,
+    /// `InternalError`: SNS failed internally.
+    InternalError,
+    /// `KMSAccessDenied`, `KMSDisabled`, `KMSInvalidState`, `KMSNotFound` or
+    /// `KMSOptInRequired`: the topic's KMS key is unusable.
+    KmsUnavailable(String),
+    /// Any other code.
+    Other(String),
+}
+
+impl SnsErrorCode {
+    /// The variant for an error code string.
+    pub fn from_code(code: &str) -> Self {
+        match code {
+            "AuthorizationError" => Self::AuthorizationError,
+            "InvalidSecurity" => Self::InvalidSecurity,
+            "InvalidParameter" => Self::InvalidParameter,
+            "ParameterValueInvalid" => Self::ParameterValueInvalid,
+            "ValidationException" => Self::Validation,
+            "Throttled" => Self::Throttled,
+            "KMSThrottling" => Self::KmsThrottling,
+            "EndpointDisabled" => Self::EndpointDisabled,
+            "PlatformApplicationDisabled" => Self::PlatformApplicationDisabled,
+            "NotFound" => Self::NotFound,
+            "InternalError" => Self::InternalError,
+            kms if kms.starts_with("KMS") => Self::KmsUnavailable(kms.to_string()),
+            other => Self::Other(other.to_string()),
+        }
+    }
+
+    /// The error code string.
+    pub fn code(&self) -> &str {
+        match self {
+            Self::AuthorizationError => "AuthorizationError",
+            Self::InvalidSecurity => "InvalidSecurity",
+            Self::InvalidParameter => "InvalidParameter",
+            Self::ParameterValueInvalid => "ParameterValueInvalid",
+            Self::Validation => "ValidationException",
+            Self::Throttled => "Throttled",
+            Self::KmsThrottling => "KMSThrottling",
+            Self::EndpointDisabled => "EndpointDisabled",
+            Self::PlatformApplicationDisabled => "PlatformApplicationDisabled",
+            Self::NotFound => "NotFound",
+            Self::InternalError => "InternalError",
+            Self::KmsUnavailable(code) | Self::Other(code) => code,
+        }
+    }
+
+    /// The provider-independent classification of this code.
+    pub fn kind(&self) -> ProviderErrorKind {
+        match self {
+            Self::AuthorizationError | Self::InvalidSecurity => ProviderErrorKind::Unauthorized,
+            Self::InvalidParameter | Self::ParameterValueInvalid | Self::Validation => {
+                ProviderErrorKind::InvalidDestination
+            }
+            Self::Throttled | Self::KmsThrottling => ProviderErrorKind::Throttled,
+            Self::EndpointDisabled => ProviderErrorKind::UnreachableDestination,
+            Self::PlatformApplicationDisabled => ProviderErrorKind::AccountSuspended,
+            Self::InternalError => ProviderErrorKind::ProviderFailure,
+            Self::NotFound | Self::KmsUnavailable(_) | Self::Other(_) => ProviderErrorKind::Other,
+        }
+    }
+
+    /// A [`ProviderError`] for this code with the given message.
+    pub fn to_provider_error(&self, message: impl Into<String>) -> ProviderError {
+        ProviderError::new(crate::PROVIDER, self.code(), self.kind(), message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_round_trip_and_classify() {
+        for code in ["Throttled", "EndpointDisabled", "KMSDisabled", "Mystery"] {
+            assert_eq!(SnsErrorCode::from_code(code).code(), code);
+        }
+        assert_eq!(
+            SnsErrorCode::from_code("KMSNotFound"),
+            SnsErrorCode::KmsUnavailable("KMSNotFound".into())
+        );
+        assert_eq!(
+            SnsErrorCode::from_code("InternalError").kind(),
+            ProviderErrorKind::ProviderFailure
+        );
+        let err = SnsErrorCode::EndpointDisabled.to_provider_error("disabled");
+        assert_eq!(err.provider, "aws-sns");
+        assert_eq!(err.kind, ProviderErrorKind::UnreachableDestination);
+    }
+}
@@ -13,6 +13,7 @@
 //!     to: "+14155551234",
 //!     from: "+10005551234",
 //!     text: "Hello from AWS SNS!",
+//!     ..Default::default()
 //! }).await?;
 //! ```
 //!
@@ -31,16 +32,26 @@
 //! - Send SMS messages via AWS SNS `Publish`
 //! - Delivery status webhook parsing
 //! - Subscription confirmation handling
+//! - Signing certificate caching ([`SigningCertCache`])
+//! - Typed `Publish` error codes ([`SnsErrorCode`])
 //! - Standard AWS credential management
 
-use async_trait::async_trait;
+pub mod cert_cache;
+pub mod errors;
+
+pub use cert_cache::{CertFetcher, SigningCertCache};
+pub use errors::SnsErrorCode;
+
 use aws_config::{BehaviorVersion, Region};
 use aws_sdk_sns::{config::Credentials, Client as SnsClient, Config as SnsConfig};
+use aws_sdk_sns::operation::RequestId;
 use serde::{Deserialize, Serialize};
 use sms_core::*;
 use std::collections::HashMap;
 use tracing::{debug, error, info, warn};
 
+const PROVIDER: &str = "aws-sns";
+
 /// AWS SNS SMS client.
 ///
 /// Wraps the AWS SDK's SNS client with the smskit [`SmsClient`] and
@@ -84,11 +95,52 @@ pub struct SnsDeliveryNotification {
     /// Base64-encoded signature.
     #[serde(rename = "Signature")]
     pub signature: String,
-    /// URL of the signing certificate.
-    #[serde(rename = "SigningCertURL")]
+    /// URL of the signing certificate.  Lambda's SNS event records spell
+    /// this `SigningCertUrl`.
+    #[serde(rename = "SigningCertURL", alias = "SigningCertUrl")]
     pub signing_cert_url: String,
 }
 
+impl SnsDeliveryNotification {
+    /// Recover the SNS envelope from [`InboundMessage::raw`].
+    pub fn from_message(msg: &InboundMessage) -> Result<Self, SmsError> {
+        if msg.provider != PROVIDER {
+            return Err(SmsError::Invalid(format!(
+                "expected a {} message, got {}",
+                PROVIDER, msg.provider
+            )));
+        }
+        serde_json::from_value(msg.raw.clone())
+            .map_err(|e| SmsError::Invalid(format!("sns notification: {}", e)))
+    }
+}
+
+/// Typed view of the data recorded for an SNS publish, available from
+/// [`SendResponse::raw`] via [`SnsSendDetails::from_response`].
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SnsSendDetails {
+    /// SNS-assigned message ID.
+    #[serde(rename = "MessageId")]
+    pub message_id: String,
+    /// AWS region the message was published in.
+    #[serde(rename = "Region")]
+    pub region: String,
+}
+
+impl SnsSendDetails {
+    /// Parse the raw payload of an AWS SNS [`SendResponse`].
+    pub fn from_response(resp: &SendResponse) -> Result<Self, SmsError> {
+        if resp.provider != PROVIDER {
+            return Err(SmsError::Invalid(format!(
+                "expected a {} response, got {}",
+                PROVIDER, resp.provider
+            )));
+        }
+        serde_json::from_value(resp.raw.clone())
+            .map_err(|e| SmsError::Invalid(format!("sns send response: {}", e)))
+    }
+}
+
 /// The inner delivery-report payload nested inside
 /// [`SnsDeliveryNotification::message`].
 #[derive(Debug, Deserialize, Serialize)]
@@ -107,6 +159,16 @@ pub struct SmsDeliveryReport {
     pub destination_phone_number: String,
 }
 
+impl SmsDeliveryReport {
+    /// Extract the delivery report from an SNS delivery-status
+    /// [`InboundMessage`].
+    pub fn from_message(msg: &InboundMessage) -> Result<Self, SmsError> {
+        let notification = SnsDeliveryNotification::from_message(msg)?;
+        serde_json::from_str(&notification.message)
+            .map_err(|e| SmsError::Invalid(format!("sns delivery report: {}", e)))
+    }
+}
+
 /// Metadata within an SNS delivery report.
 #[derive(Debug, Deserialize, Serialize)]
 pub struct SmsNotificationData {
@@ -215,13 +277,34 @@ impl AwsSnsClient {
             region: region_str,
         }
     }
+
+    /// Every phone number on the account's SNS opt-out list, following
+    /// pagination to the end.
+    pub async fn opted_out_numbers(&self) -> Result<Vec<String>, SmsError> {
+        let mut numbers = Vec::new();
+        let mut next_token = None;
+        loop {
+            let page = self
+                .client
+                .list_phone_numbers_opted_out()
+                .set_next_token(next_token)
+                .send()
+                .await
+                .map_err(|e| SmsError::Provider(format!("AWS SNS opt-out list error: {}", e)))?;
+            numbers.extend(page.phone_numbers().iter().cloned());
+            match page.next_token() {
+                Some(token) if !token.is_empty() => next_token = Some(token.to_string()),
+                _ => break,
+            }
+        }
+        debug!("Fetched {} opted-out numbers from SNS", numbers.len());
+        Ok(numbers)
+    }
 }
 
-#[async_trait]
 impl SmsClient for AwsSnsClient {
     async fn send(&self, req: SendRequest<'_>) -> Result<SendResponse, SmsError> {
-        info!("Sending SMS via AWS SNS to {}", req.to);
-
+        validate_text(req.text, self.max_text_len())?;
         let mut message_attributes = HashMap::new();
 
         message_attributes.insert(
@@ -249,7 +332,8 @@ impl SmsClient for AwsSnsClient {
         }
 
         debug!(
-            "Sending SNS message with attributes: {:?}",
+            "Sending SNS message to {} with attributes: {:?}",
+            redact_number(req.to),
             message_attributes
         );
 
@@ -262,7 +346,9 @@ impl SmsClient for AwsSnsClient {
             .send()
             .await
             .map_err(|e| {
-                error!("AWS SNS publish failed: {}", e);
+                if let aws_sdk_sns::error::SdkError::TimeoutError(_) = &e {
+                    return SmsError::Timeout("AWS SNS publish timed out".to_string());
+                }
                 match e.into_service_error() {
                     aws_sdk_sns::operation::publish::PublishError::AuthorizationErrorException(_) => {
                         SmsError::Auth("AWS authorization failed".to_string())
@@ -273,16 +359,25 @@ impl SmsClient for AwsSnsClient {
                     aws_sdk_sns::operation::publish::PublishError::InvalidParameterValueException(e) => {
                         SmsError::Invalid(e.message().unwrap_or("Invalid parameter value").to_string())
                     }
-                    e => SmsError::Provider(format!("AWS SNS error: {}", e)),
+                    e => {
+                        use aws_sdk_sns::error::ProvideErrorMetadata;
+                        match e.code() {
+                            Some("Throttled" | "Throttling") => {
+                                SmsError::RateLimited { retry_after: None }
+                            }
+                            Some(code) => SmsError::Rejected(
+                                SnsErrorCode::from_code(code).to_provider_error(
+                                    e.message().map_or_else(|| e.to_string(), str::to_string),
+                                ),
+                            ),
+                            None => SmsError::Provider(format!("AWS SNS error: {}", e)),
+                        }
+                    }
                 }
             })?;
 
         let message_id = result.message_id().unwrap_or_default().to_string();
-
-        info!(
-            "SMS sent successfully via AWS SNS with MessageId: {}",
-            message_id
-        );
+        let request_id = result.request_id().map(String::from);
 
         let raw_json = serde_json::json!({
             "MessageId": message_id,
@@ -295,12 +390,24 @@ impl SmsClient for AwsSnsClient {
         Ok(SendResponse {
             id: message_id,
             provider: "aws-sns",
+            channel: Channel::Sms,
+            http_status: Some(200),
+            provider_request_id: request_id,
+            thread_id: Some(req.thread_id()),
             raw: raw_json,
         })
     }
 }
 
-#[async_trait]
+/// Extract the AWS account ID from an ARN such as
+/// `arn:aws:sns:us-east-1:123456789012:topic`.
+fn account_from_arn(arn: &str) -> Option<String> {
+    arn.split(':')
+        .nth(4)
+        .filter(|a| !a.is_empty())
+        .map(String::from)
+}
+
 impl InboundWebhook for AwsSnsClient {
     fn provider(&self) -> &'static str {
         "aws-sns"
@@ -309,7 +416,7 @@ impl InboundWebhook for AwsSnsClient {
     fn parse_inbound(&self, headers: &Headers, body: &[u8]) -> Result<InboundMessage, SmsError> {
         debug!("Parsing AWS SNS webhook");
 
-        let payload_str = String::from_utf8(body.to_vec()).map_err(|e| {
+        let payload_str = std::str::from_utf8(body).map_err(|e| {
             error!("Invalid UTF-8 in AWS SNS webhook: {}", e);
             SmsError::Provider(format!("Invalid UTF-8: {}", e))
         })?;
@@ -325,39 +432,44 @@ impl InboundWebhook for AwsSnsClient {
         }
 
         let notification: SnsDeliveryNotification =
-            serde_json::from_str(&payload_str).map_err(|e| {
+            serde_json::from_str(payload_str).map_err(|e| {
                 error!("Failed to parse SNS notification: {}", e);
                 SmsError::Provider(format!("Invalid notification format: {}", e))
             })?;
 
-        if notification.notification_type == "Notification" {
-            if let Ok(delivery_report) =
+        if notification.notification_type == "Notification"
+            && let Ok(delivery_report) =
                 serde_json::from_str::<SmsDeliveryReport>(&notification.message)
-            {
-                info!(
-                    "Received SMS delivery report for message: {}",
-                    delivery_report.message_id
-                );
-
-                let timestamp = time::OffsetDateTime::parse(
-                    &notification.timestamp,
-                    &time::format_description::well_known::Rfc3339,
-                )
-                .ok();
-
-                let raw_json = serde_json::to_value(&notification)
-                    .map_err(|e| SmsError::Provider(format!("JSON serialization error: {}", e)))?;
-
-                return Ok(InboundMessage {
-                    id: Some(delivery_report.message_id),
-                    from: "AWS-SNS".to_string(),
-                    to: delivery_report.destination_phone_number,
-                    text: format!("Delivery Status: {}", delivery_report.status),
-                    timestamp,
-                    provider: "aws-sns",
-                    raw: raw_json,
-                });
+        {
+            info!(
+                "Received SMS delivery report for message: {}",
+                delivery_report.message_id
+            );
+
+            let timestamp = time::OffsetDateTime::parse(
+                &notification.timestamp,
+                &time::format_description::well_known::Rfc3339,
+            )
+            .ok();
+
+            let raw_json = serde_json::to_value(&notification)
+                .map_err(|e| SmsError::Provider(format!("JSON serialization error: {}", e)))?;
+
+            return Ok(InboundMessage {
+                id: Some(delivery_report.message_id),
+                from: "AWS-SNS".to_string(),
+                to: delivery_report.destination_phone_number,
+                text: format!("Delivery Status: {}", delivery_report.status),
+                timestamp,
+                provider: "aws-sns",
+                direction: InboundDirection::DeliveryReport,
+                channel: Channel::Sms,
+                provider_account: account_from_arn(&notification.topic_arn),
+                thread_id: None,
+                flag: None,
+                raw: raw_json,
             }
+            .with_thread_id());
         }
 
         if notification.notification_type == "SubscriptionConfirmation" {
@@ -379,6 +491,11 @@ impl InboundWebhook for AwsSnsClient {
                 text: "Subscription confirmation required".to_string(),
                 timestamp,
                 provider: "aws-sns",
+                direction: InboundDirection::Inbound,
+                channel: Channel::Sms,
+                provider_account: account_from_arn(&notification.topic_arn),
+                thread_id: None,
+                flag: None,
                 raw: raw_json,
             });
         }
@@ -392,11 +509,65 @@ impl InboundWebhook for AwsSnsClient {
             notification.notification_type
         )))
     }
+
+    /// Accepts a single notification, a JSON array of notifications, or an
+    /// SQS / Lambda event (`{"Records": [...]}`) whose records carry the
+    /// notification in `body` (SQS) or `Sns` (Lambda).
+    fn parse_inbound_batch(
+        &self,
+        headers: &Headers,
+        body: &[u8],
+    ) -> Result<Vec<InboundMessage>, SmsError> {
+        let value: serde_json::Value = serde_json::from_slice(body)
+            .map_err(|e| SmsError::Provider(format!("Invalid notification format: {}", e)))?;
+
+        let records: Vec<String> = match &value {
+            serde_json::Value::Array(items) => items.iter().map(|v| v.to_string()).collect(),
+            serde_json::Value::Object(obj) if obj.contains_key("Records") => obj["Records"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(|record| match record.get("body").and_then(|b| b.as_str()) {
+                    Some(body) => body.to_string(),
+                    None => record.get("Sns").map(|s| s.to_string()).unwrap_or_default(),
+                })
+                .collect(),
+            _ => return Ok(vec![self.parse_inbound(headers, body)?]),
+        };
+
+        debug!("Parsing {} batched SNS records", records.len());
+        records
+            .iter()
+            .map(|record| self.parse_inbound(headers, record.as_bytes()))
+            .collect()
+    }
 }
 
+/// The `aws_sns` configuration section read by the provider plugin.
+#[derive(Deserialize)]
+struct AwsSnsSettings {
+    region: String,
+    access_key_id: String,
+    secret_access_key: String,
+}
+
+/// Build an [`AwsSnsClient`] from the `aws_sns` configuration section.
+fn build_plugin(settings: &serde_json::Value) -> Result<BuiltProvider, SmsError> {
+    let s: AwsSnsSettings = provider_settings("aws_sns", settings)?;
+    Ok(BuiltProvider::both(AwsSnsClient::new(
+        s.region,
+        s.access_key_id,
+        s.secret_access_key,
+    )))
+}
+
+sms_core::register_provider!("aws_sns", build_plugin);
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
+    use sms_core::strategies::*;
 
     // -- Construction --
 
@@ -516,6 +687,62 @@ mod tests {
         assert!(msg.raw.get("TopicArn").is_some());
     }
 
+    #[test]
+    fn webhook_delivery_report_metadata() {
+        let client = AwsSnsClient::new("us-east-1", "k", "s");
+        let json = delivery_report_json();
+        let msg = client.parse_inbound(&vec![], json.as_bytes()).unwrap();
+        assert_eq!(msg.direction, InboundDirection::DeliveryReport);
+        assert_eq!(msg.provider_account.as_deref(), Some("123456789012"));
+    }
+
+    #[test]
+    fn batch_parsing_accepts_arrays_and_sqs_records() {
+        let client = AwsSnsClient::new("us-east-1", "k", "s");
+        let json = delivery_report_json();
+
+        let single = client.parse_inbound_batch(&vec![], json.as_bytes()).unwrap();
+        assert_eq!(single.len(), 1);
+
+        let array = format!("[{},{}]", json, json);
+        let msgs = client.parse_inbound_batch(&vec![], array.as_bytes()).unwrap();
+        assert_eq!(msgs.len(), 2);
+
+        let sqs = serde_json::json!({
+            "Records": [{ "body": json }, { "body": json }, { "body": json }]
+        });
+        let msgs = client
+            .parse_inbound_batch(&vec![], sqs.to_string().as_bytes())
+            .unwrap();
+        assert_eq!(msgs.len(), 3);
+        assert!(msgs.iter().all(|m| m.id.as_deref() == Some("msg-123")));
+    }
+
+    #[test]
+    fn delivery_report_typed_view() {
+        let client = AwsSnsClient::new("us-east-1", "k", "s");
+        let json = delivery_report_json();
+        let msg = client.parse_inbound(&vec![], json.as_bytes()).unwrap();
+        let report = SmsDeliveryReport::from_message(&msg).unwrap();
+        assert_eq!(report.status, "SUCCESS");
+        assert_eq!(report.destination_phone_number, "+1234567890");
+    }
+
+    #[test]
+    fn send_details_from_response() {
+        let resp = SendResponse {
+            id: "m1".into(),
+            provider: "aws-sns",
+            channel: Channel::Sms,
+            http_status: None,
+            provider_request_id: None,
+            thread_id: None,
+            raw: serde_json::json!({ "MessageId": "m1", "Region": "eu-west-1" }),
+        };
+        let details = SnsSendDetails::from_response(&resp).unwrap();
+        assert_eq!(details.region, "eu-west-1");
+    }
+
     // -- Webhook parsing: subscription confirmation --
 
     fn subscription_confirmation_json() -> String {
@@ -682,4 +909,69 @@ mod tests {
         assert_eq!(borrowed.from, "MySenderID");
         assert!(!borrowed.from.starts_with('+'));
     }
+
+    // -- Property tests --
+
+    fn client() -> AwsSnsClient {
+        AwsSnsClient::new("us-east-1", "test_key", "test_secret")
+    }
+
+    /// A delivery-status notification for `(message_id, destination)`.
+    fn delivery_notification() -> impl Strategy<Value = (String, String, Vec<u8>)> {
+        let fields = (
+            "[a-f0-9-]{1,36}",
+            phone_number(),
+            prop_oneof![Just("SUCCESS"), Just("FAILURE")],
+            field_value(),
+            field_value(),
+        );
+        fields.prop_map(|(message_id, destination, status, timestamp, topic_arn)| {
+            let report = serde_json::json!({
+                "notification": { "messageId": message_id, "timestamp": timestamp },
+                "delivery": { "destination": destination, "smsType": "Transactional" },
+                "status": status,
+                "messageId": message_id,
+                "destinationPhoneNumber": destination,
+            });
+            let envelope = serde_json::json!({
+                "Type": "Notification",
+                "MessageId": "sns-1",
+                "TopicArn": topic_arn,
+                "Message": report.to_string(),
+                "Timestamp": timestamp,
+                "SignatureVersion": "1",
+                "Signature": "sig",
+                "SigningCertURL": "https://sns.us-east-1.amazonaws.com/cert.pem",
+            });
+            (message_id, destination, envelope.to_string().into_bytes())
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn parse_inbound_is_total(body in arbitrary_body(), headers in hostile_headers()) {
+            check_total(&client(), &headers, &body)?;
+        }
+
+        #[test]
+        fn delivery_reports_round_trip((id, destination, body) in delivery_notification()) {
+            let msg = client().parse_inbound(&vec![], &body).unwrap();
+            prop_assert_eq!(msg.direction, InboundDirection::DeliveryReport);
+            prop_assert_eq!(msg.id.as_deref(), Some(id.as_str()));
+            prop_assert_eq!(&msg.to, &destination);
+            let report = SmsDeliveryReport::from_message(&msg).unwrap();
+            prop_assert_eq!(report.message_id, id);
+            prop_assert_eq!(report.destination_phone_number, destination);
+        }
+
+        #[test]
+        fn damaged_notifications_never_panic(
+            body in delivery_notification().prop_flat_map(|(.., body)| {
+                prop_oneof![truncated(body.clone()), mangled(body)]
+            }),
+            headers in hostile_headers(),
+        ) {
+            check_total(&client(), &headers, &body)?;
+        }
+    }
 }
@@ -0,0 +1,806 @@
+//! # Azure Communication Services SMS Provider
+//!
+//! [Azure Communication Services](https://learn.microsoft.com/azure/communication-services/)
+//! backend for smskit.  Sends go through the ACS SMS REST API, authenticated
+//! with the resource's HMAC-SHA256 access key; inbound messages and delivery
+//! reports arrive as Event Grid events.
+//!
+//! ## Sending messages
+//!
+//! ```rust,ignore
+//! use sms_core::{SendRequest, SmsClient};
+//! use sms_azure_acs::AzureAcsClient;
+//!
+//! let client = AzureAcsClient::from_connection_string(
+//!     "endpoint=https://contoso.communication.azure.com/;accesskey=BASE64KEY",
+//! )?;
+//! let response = client.send(SendRequest {
+//!     to: "+14155551234",
+//!     from: "+18445550199",
+//!     text: "Hello from Azure!",
+//!     ..Default::default()
+//! }).await?;
+//! ```
+//!
+//! ## Webhooks
+//!
+//! Subscribe an Event Grid webhook to the `SMSReceived` and
+//! `SMSDeliveryReportReceived` events.  Event Grid posts JSON arrays, so
+//! prefer [`InboundWebhook::parse_inbound_batch`]; both the Event Grid and
+//! CloudEvents schemas are accepted.  Event Grid first sends a subscription
+//! validation event, which the endpoint must answer with
+//! [`validation_response`].
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sms_core::{
+    Channel, ExposeSecret, Headers, HttpClientConfig, InboundDirection, InboundMessage,
+    InboundWebhook, SecretString, SendRequest, SendResponse, SmsClient, SmsError,
+};
+use sms_core::http_provider::transport_error;
+use std::time::Duration;
+use time::OffsetDateTime;
+use tracing::debug;
+
+type HmacSha256 = Hmac<Sha256>;
+
+const PROVIDER: &str = "azure-acs";
+
+/// SMS REST API version this client speaks.
+const API_VERSION: &str = "2021-03-07";
+
+/// Default deadline for a single API call.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Headers covered by the request signature, in signing order.
+const SIGNED_HEADERS: &str = "x-ms-date;host;x-ms-content-sha256";
+
+/// Event Grid event types this crate understands.
+const SMS_RECEIVED: &str = "Microsoft.Communication.SMSReceived";
+const SMS_DELIVERY_REPORT: &str = "Microsoft.Communication.SMSDeliveryReportReceived";
+const SUBSCRIPTION_VALIDATION: &str = "Microsoft.EventGrid.SubscriptionValidationEvent";
+
+/// Azure Communication Services SMS client.
+///
+/// Implements [`SmsClient`] for sending SMS and [`InboundWebhook`] for
+/// receiving Event Grid SMS events.
+#[derive(Clone)]
+pub struct AzureAcsClient {
+    /// Resource endpoint, e.g. `https://contoso.communication.azure.com`.
+    pub endpoint: String,
+    /// Base64-encoded resource access key.  Zeroized on drop.
+    pub access_key: SecretString,
+    /// Deadline for each API call unless the request sets its own
+    /// [`SendRequest::timeout`].
+    pub timeout: Duration,
+    /// Ask ACS to publish `SMSDeliveryReportReceived` events for sends
+    /// (default: `true`).
+    pub delivery_reports: bool,
+    http: reqwest::Client,
+}
+
+impl std::fmt::Debug for AzureAcsClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AzureAcsClient")
+            .field("endpoint", &self.endpoint)
+            .field("access_key", &"[REDACTED]")
+            .field("timeout", &self.timeout)
+            .field("delivery_reports", &self.delivery_reports)
+            .finish_non_exhaustive()
+    }
+}
+
+impl AzureAcsClient {
+    /// Create a client for the resource at `endpoint`, signing with its
+    /// base64-encoded `access_key`.
+    pub fn new(endpoint: impl Into<String>, access_key: impl Into<String>) -> Self {
+        Self {
+            endpoint: endpoint.into().trim_end_matches('/').to_string(),
+            access_key: SecretString::from(access_key.into()),
+            timeout: DEFAULT_TIMEOUT,
+            delivery_reports: true,
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Create a client from a connection string as shown in the Azure
+    /// portal: `endpoint=https://...;accesskey=...`.
+    ///
+    /// Returns [`SmsError::Auth`] if either part is missing.
+    pub fn from_connection_string(connection_string: &str) -> Result<Self, SmsError> {
+        let mut endpoint = None;
+        let mut access_key = None;
+        for part in connection_string.split(';') {
+            if let Some((key, value)) = part.trim().split_once('=') {
+                match key.to_ascii_lowercase().as_str() {
+                    "endpoint" => endpoint = Some(value),
+                    "accesskey" => access_key = Some(value),
+                    _ => {}
+                }
+            }
+        }
+        match (endpoint, access_key) {
+            (Some(endpoint), Some(access_key)) => Ok(Self::new(endpoint, access_key)),
+            _ => Err(SmsError::Auth(
+                "connection string needs endpoint= and accesskey=".into(),
+            )),
+        }
+    }
+
+    /// Create a client from the `AZURE_COMMUNICATION_CONNECTION_STRING`
+    /// environment variable.
+    ///
+    /// Returns [`SmsError::Auth`] if it is missing or malformed.
+    pub fn from_env() -> Result<Self, SmsError> {
+        let connection_string = std::env::var("AZURE_COMMUNICATION_CONNECTION_STRING")
+            .map_err(|_| SmsError::Auth("AZURE_COMMUNICATION_CONNECTION_STRING not set".into()))?;
+        Self::from_connection_string(&connection_string)
+    }
+
+    /// Set the default deadline for each API call (default: 30 seconds).
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Turn delivery report events on or off for sends (default: on).
+    pub fn with_delivery_reports(mut self, enabled: bool) -> Self {
+        self.delivery_reports = enabled;
+        self
+    }
+
+    /// Rebuild the HTTP client with custom transport settings (proxy,
+    /// `User-Agent`, extra headers, etc.).
+    pub fn with_http_config(mut self, config: HttpClientConfig) -> Result<Self, SmsError> {
+        self.http = sms_core::http_provider::client_with(&config)?;
+        Ok(self)
+    }
+
+    /// The `Authorization` header value for a request.
+    ///
+    /// Algorithm: HMAC-SHA256(base64-decoded access key,
+    /// `"{method}\n{path_and_query}\n{date};{host};{content_hash}"`),
+    /// base64-encoded, where `date` is the `x-ms-date` header and
+    /// `content_hash` the `x-ms-content-sha256` header.
+    pub fn authorization(
+        &self,
+        method: &str,
+        path_and_query: &str,
+        host: &str,
+        date: &str,
+        content_hash: &str,
+    ) -> Result<String, SmsError> {
+        let key = BASE64
+            .decode(self.access_key.expose_secret())
+            .map_err(|e| SmsError::Auth(format!("access key is not base64: {}", e)))?;
+        let string_to_sign = format!(
+            "{}\n{}\n{};{};{}",
+            method, path_and_query, date, host, content_hash
+        );
+        let mut mac = HmacSha256::new_from_slice(&key).expect("HMAC accepts any key size");
+        mac.update(string_to_sign.as_bytes());
+        Ok(format!(
+            "HMAC-SHA256 SignedHeaders={}&Signature={}",
+            SIGNED_HEADERS,
+            BASE64.encode(mac.finalize().into_bytes())
+        ))
+    }
+
+    /// The endpoint's host, as signed in the string-to-sign.
+    fn host(&self) -> &str {
+        let rest = self
+            .endpoint
+            .split_once("://")
+            .map_or(self.endpoint.as_str(), |(_, rest)| rest);
+        rest.split('/').next().unwrap_or(rest)
+    }
+}
+
+/// Base64 SHA-256 of a request body, for the `x-ms-content-sha256` header.
+fn content_hash(body: &[u8]) -> String {
+    BASE64.encode(Sha256::digest(body))
+}
+
+/// Format a time as an RFC 1123 HTTP date, for the `x-ms-date` header.
+fn http_date(at: OffsetDateTime) -> String {
+    let format = time::format_description::parse(
+        "[weekday repr:short], [day] [month repr:short] [year] [hour]:[minute]:[second] GMT",
+    )
+    .expect("valid format description");
+    at.to_offset(time::UtcOffset::UTC)
+        .format(&format)
+        .expect("formatting a UTC date cannot fail")
+}
+
+/// Wire format for the send request body.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AcsSendRequest<'a> {
+    from: &'a str,
+    sms_recipients: [AcsRecipient<'a>; 1],
+    message: &'a str,
+    sms_send_options: AcsSendOptions,
+}
+
+#[derive(Debug, Serialize)]
+struct AcsRecipient<'a> {
+    to: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AcsSendOptions {
+    enable_delivery_report: bool,
+}
+
+/// Typed view of the JSON returned from a send, available from
+/// [`SendResponse::raw`] via [`AcsSendDetails::from_response`].
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct AcsSendDetails {
+    /// One result per recipient.
+    pub value: Vec<AcsSendResult>,
+}
+
+/// Outcome for one recipient of a send.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AcsSendResult {
+    /// Destination number.
+    pub to: String,
+    /// ACS message ID, e.g. `"Outgoing_20240101..."`, when accepted.
+    #[serde(default)]
+    pub message_id: Option<String>,
+    /// Per-recipient HTTP status.
+    pub http_status_code: u16,
+    /// Whether ACS accepted the message for this recipient.
+    pub successful: bool,
+    /// Why the recipient failed, if it did.
+    #[serde(default)]
+    pub error_message: Option<String>,
+}
+
+impl AcsSendDetails {
+    /// Parse the raw payload of an Azure ACS [`SendResponse`].
+    pub fn from_response(resp: &SendResponse) -> Result<Self, SmsError> {
+        if resp.provider != PROVIDER {
+            return Err(SmsError::Invalid(format!(
+                "expected a {} response, got {}",
+                PROVIDER, resp.provider
+            )));
+        }
+        serde_json::from_value(resp.raw.clone())
+            .map_err(|e| SmsError::Invalid(format!("azure acs send response: {}", e)))
+    }
+}
+
+impl SmsClient for AzureAcsClient {
+    async fn send(&self, req: SendRequest<'_>) -> Result<SendResponse, SmsError> {
+        sms_core::validate_text(req.text, self.max_text_len())?;
+        if req.from.is_empty() {
+            return Err(SmsError::Invalid(
+                "Azure Communication Services requires a from number".into(),
+            ));
+        }
+        let body = serde_json::to_vec(&AcsSendRequest {
+            from: req.from,
+            sms_recipients: [AcsRecipient { to: req.to }],
+            message: req.text,
+            sms_send_options: AcsSendOptions {
+                enable_delivery_report: self.delivery_reports,
+            },
+        })
+        .map_err(|e| SmsError::Unexpected(format!("encode send request: {}", e)))?;
+
+        let path_and_query = format!("/sms?api-version={}", API_VERSION);
+        let date = http_date(OffsetDateTime::now_utc());
+        let hash = content_hash(&body);
+        let authorization =
+            self.authorization("POST", &path_and_query, self.host(), &date, &hash)?;
+        debug!("Sending ACS message to {}", sms_core::redact_number(req.to));
+
+        let res = self
+            .http
+            .post(format!("{}{}", self.endpoint, path_and_query))
+            .header("x-ms-date", &date)
+            .header("x-ms-content-sha256", &hash)
+            .header(reqwest::header::AUTHORIZATION, authorization)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .timeout(req.timeout.unwrap_or(self.timeout))
+            .body(body)
+            .send()
+            .await
+            .map_err(transport_error)?;
+
+        let status = res.status();
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = res
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(sms_core::parse_retry_after);
+            return Err(SmsError::RateLimited { retry_after });
+        }
+        let request_id = res
+            .headers()
+            .get("x-ms-request-id")
+            .and_then(|v| v.to_str().ok())
+            .map(String::from);
+        let raw_text = res.text().await.map_err(transport_error)?;
+        if status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN {
+            return Err(SmsError::Auth(raw_text));
+        }
+        if !status.is_success() {
+            return Err(SmsError::Provider(format!("HTTP {}: {}", status, raw_text)));
+        }
+
+        let raw: serde_json::Value = serde_json::from_str(&raw_text)
+            .map_err(|e| SmsError::Provider(format!("send response: {}: {}", e, raw_text)))?;
+        let details: AcsSendDetails = serde_json::from_value(raw.clone())
+            .map_err(|e| SmsError::Provider(format!("send response: {}", e)))?;
+        let result = details
+            .value
+            .first()
+            .ok_or_else(|| SmsError::Provider("send response has no results".into()))?;
+        let id = match (result.successful, &result.message_id) {
+            (true, Some(id)) => id.clone(),
+            _ => {
+                return Err(SmsError::Provider(format!(
+                    "HTTP {}: {}",
+                    result.http_status_code,
+                    result.error_message.as_deref().unwrap_or("send failed")
+                )));
+            }
+        };
+
+        Ok(SendResponse {
+            id,
+            provider: PROVIDER,
+            channel: Channel::Sms,
+            http_status: Some(status.as_u16()),
+            provider_request_id: request_id,
+            thread_id: Some(req.thread_id()),
+            raw,
+        })
+    }
+}
+
+/// An Event Grid event, in either the Event Grid or the CloudEvents schema.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct EventGridEvent {
+    /// Event ID.
+    pub id: String,
+    /// e.g. `"Microsoft.Communication.SMSReceived"`.
+    #[serde(rename = "eventType", alias = "type")]
+    pub event_type: String,
+    /// ARM ID of the Communication Services resource that published the event.
+    #[serde(default, alias = "source")]
+    pub topic: Option<String>,
+    /// When the event was published.
+    #[serde(default, rename = "eventTime", alias = "time")]
+    pub event_time: Option<String>,
+    /// Event-specific payload.
+    #[serde(default)]
+    pub data: serde_json::Value,
+}
+
+/// The `data` of an `SMSReceived` event.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AcsSmsReceived {
+    /// ACS message ID.
+    #[serde(rename = "MessageId", alias = "messageId")]
+    pub message_id: String,
+    /// Sender phone number.
+    #[serde(rename = "From", alias = "from")]
+    pub from: String,
+    /// Your number the message was sent to.
+    #[serde(rename = "To", alias = "to")]
+    pub to: String,
+    /// Message body.
+    #[serde(rename = "Message", alias = "message")]
+    pub message: String,
+    /// When ACS received the message.
+    #[serde(default, rename = "ReceivedTimestamp", alias = "receivedTimestamp")]
+    pub received_timestamp: Option<String>,
+}
+
+/// The `data` of an `SMSDeliveryReportReceived` event.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AcsDeliveryReport {
+    /// ID of the message the report is about.
+    #[serde(rename = "MessageId", alias = "messageId")]
+    pub message_id: String,
+    /// Your number the message was sent from.
+    #[serde(rename = "From", alias = "from")]
+    pub from: String,
+    /// Destination number of the original message.
+    #[serde(rename = "To", alias = "to")]
+    pub to: String,
+    /// e.g. `"Delivered"` or `"Failed"`.
+    #[serde(rename = "DeliveryStatus", alias = "deliveryStatus")]
+    pub delivery_status: String,
+    /// Human-readable detail for the status.
+    #[serde(
+        default,
+        rename = "DeliveryStatusDetails",
+        alias = "deliveryStatusDetails"
+    )]
+    pub delivery_status_details: Option<String>,
+    /// When ACS received the report.
+    #[serde(default, rename = "ReceivedTimestamp", alias = "receivedTimestamp")]
+    pub received_timestamp: Option<String>,
+    /// Tag set on the original send, if any.
+    #[serde(default, rename = "Tag", alias = "tag")]
+    pub tag: Option<String>,
+}
+
+impl AcsDeliveryReport {
+    /// Extract the report from an Azure ACS delivery-report
+    /// [`InboundMessage`].
+    pub fn from_message(msg: &InboundMessage) -> Result<Self, SmsError> {
+        if msg.provider != PROVIDER {
+            return Err(SmsError::Invalid(format!(
+                "expected a {} message, got {}",
+                PROVIDER, msg.provider
+            )));
+        }
+        let event: EventGridEvent = serde_json::from_value(msg.raw.clone())
+            .map_err(|e| SmsError::Invalid(format!("event grid event: {}", e)))?;
+        serde_json::from_value(event.data)
+            .map_err(|e| SmsError::Invalid(format!("acs delivery report: {}", e)))
+    }
+
+    /// Returns `true` if the carrier confirmed delivery.
+    pub fn is_delivered(&self) -> bool {
+        self.delivery_status.eq_ignore_ascii_case("Delivered")
+    }
+}
+
+/// The reply an Event Grid webhook must send to a subscription validation
+/// request, or `None` if `body` is not one.
+///
+/// ```rust,ignore
+/// if let Some(reply) = sms_azure_acs::validation_response(&body) {
+///     return Json(reply);
+/// }
+/// ```
+pub fn validation_response(body: &[u8]) -> Option<serde_json::Value> {
+    let events: Vec<EventGridEvent> = serde_json::from_slice(body).ok()?;
+    let event = events
+        .into_iter()
+        .find(|e| e.event_type == SUBSCRIPTION_VALIDATION)?;
+    let code = event.data.get("validationCode")?.as_str()?;
+    Some(serde_json::json!({ "validationResponse": code }))
+}
+
+/// The resource name at the end of an ACS ARM ID such as
+/// `/subscriptions/.../CommunicationServices/contoso`.
+fn resource_from_topic(topic: &str) -> Option<String> {
+    topic
+        .rsplit('/')
+        .next()
+        .filter(|name| !name.is_empty())
+        .map(String::from)
+}
+
+fn parse_event(event: EventGridEvent) -> Result<InboundMessage, SmsError> {
+    let provider_account = event.topic.as_deref().and_then(resource_from_topic);
+    let raw = serde_json::to_value(&event).unwrap_or_default();
+    match event.event_type.as_str() {
+        SMS_RECEIVED => {
+            let data: AcsSmsReceived = serde_json::from_value(event.data)
+                .map_err(|e| SmsError::Invalid(format!("SMSReceived data: {}", e)))?;
+            let timestamp = data
+                .received_timestamp
+                .as_deref()
+                .and_then(sms_core::timestamp::parse);
+            Ok(InboundMessage {
+                id: Some(data.message_id),
+                from: data.from,
+                to: data.to,
+                text: data.message,
+                timestamp,
+                provider: PROVIDER,
+                direction: InboundDirection::Inbound,
+                channel: Channel::Sms,
+                provider_account,
+                thread_id: None,
+                flag: None,
+                raw,
+            }
+            .with_thread_id())
+        }
+        SMS_DELIVERY_REPORT => {
+            let data: AcsDeliveryReport = serde_json::from_value(event.data)
+                .map_err(|e| SmsError::Invalid(format!("SMSDeliveryReportReceived data: {}", e)))?;
+            let timestamp = data
+                .received_timestamp
+                .as_deref()
+                .and_then(sms_core::timestamp::parse);
+            Ok(InboundMessage {
+                id: Some(data.message_id),
+                from: data.from,
+                to: data.to,
+                text: format!("Delivery Status: {}", data.delivery_status),
+                timestamp,
+                provider: PROVIDER,
+                direction: InboundDirection::DeliveryReport,
+                channel: Channel::Sms,
+                provider_account,
+                thread_id: None,
+                flag: None,
+                raw,
+            }
+            .with_thread_id())
+        }
+        SUBSCRIPTION_VALIDATION => Err(SmsError::Invalid(
+            "Event Grid subscription validation; answer it with validation_response()".into(),
+        )),
+        other => Err(SmsError::Invalid(format!(
+            "Unsupported Event Grid event type: {}",
+            other
+        ))),
+    }
+}
+
+impl InboundWebhook for AzureAcsClient {
+    fn provider(&self) -> &'static str {
+        PROVIDER
+    }
+
+    /// Accepts a single event, or an Event Grid array holding exactly one.
+    fn parse_inbound(&self, headers: &Headers, body: &[u8]) -> Result<InboundMessage, SmsError> {
+        let mut messages = self.parse_inbound_batch(headers, body)?;
+        match messages.len() {
+            1 => Ok(messages.remove(0)),
+            n => Err(SmsError::Invalid(format!(
+                "expected one Event Grid event, got {}; use parse_inbound_batch",
+                n
+            ))),
+        }
+    }
+
+    /// Accepts a single event or an Event Grid array of events.
+    fn parse_inbound_batch(
+        &self,
+        _headers: &Headers,
+        body: &[u8],
+    ) -> Result<Vec<InboundMessage>, SmsError> {
+        let value: serde_json::Value = serde_json::from_slice(body)
+            .map_err(|e| SmsError::Invalid(format!("Invalid Event Grid payload: {}", e)))?;
+        let events: Vec<EventGridEvent> = match value {
+            serde_json::Value::Array(_) => serde_json::from_value(value),
+            _ => serde_json::from_value(value).map(|e| vec![e]),
+        }
+        .map_err(|e| SmsError::Invalid(format!("Invalid Event Grid event: {}", e)))?;
+        debug!("Parsing {} Event Grid events", events.len());
+        events.into_iter().map(parse_event).collect()
+    }
+}
+
+/// The `azure_acs` configuration section read by the provider plugin.
+#[derive(Deserialize)]
+struct AzureAcsSettings {
+    connection_string: Option<String>,
+    endpoint: Option<String>,
+    access_key: Option<String>,
+    #[serde(default = "default_true")]
+    delivery_reports: bool,
+    #[serde(default)]
+    http: Option<HttpClientConfig>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Build an [`AzureAcsClient`] from the `azure_acs` section.
+fn build_plugin(settings: &serde_json::Value) -> Result<sms_core::BuiltProvider, SmsError> {
+    let s: AzureAcsSettings = sms_core::provider_settings("azure_acs", settings)?;
+    let client = match (s.connection_string, s.endpoint, s.access_key) {
+        (Some(cs), _, _) => AzureAcsClient::from_connection_string(&cs)?,
+        (None, Some(endpoint), Some(access_key)) => AzureAcsClient::new(endpoint, access_key),
+        _ => {
+            return Err(SmsError::Invalid(
+                "azure_acs: set connection_string, or endpoint and access_key".into(),
+            ));
+        }
+    }
+    .with_delivery_reports(s.delivery_reports);
+    let client = match s.http {
+        Some(http) => client.with_http_config(http)?,
+        None => client,
+    };
+    Ok(sms_core::BuiltProvider::both(client))
+}
+
+sms_core::register_provider!("azure_acs", build_plugin);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const ENDPOINT: &str = "https://contoso.communication.azure.com/";
+
+    fn client() -> AzureAcsClient {
+        // "secret-key", base64-encoded.
+        AzureAcsClient::new(ENDPOINT, "c2VjcmV0LWtleQ==")
+    }
+
+    #[test]
+    fn connection_string_is_parsed() {
+        let client = AzureAcsClient::from_connection_string(
+            "endpoint=https://contoso.communication.azure.com/;accesskey=c2VjcmV0LWtleQ==",
+        )
+        .unwrap();
+        assert_eq!(client.endpoint, "https://contoso.communication.azure.com");
+        assert_eq!(client.access_key.expose_secret(), "c2VjcmV0LWtleQ==");
+        assert_eq!(client.host(), "contoso.communication.azure.com");
+        assert!(AzureAcsClient::from_connection_string("endpoint=https://x").is_err());
+    }
+
+    #[test]
+    fn signs_requests_with_hmac_sha256() {
+        let hash = content_hash(b"{}");
+        assert_eq!(hash, "RBNvo1WzZ4oRRq0W9+hknpT7T8If536DEMBg9hyq/4o=");
+        let auth = client()
+            .authorization(
+                "POST",
+                "/sms?api-version=2021-03-07",
+                "contoso.communication.azure.com",
+                "Mon, 01 Jan 2024 00:00:00 GMT",
+                &hash,
+            )
+            .unwrap();
+        assert_eq!(
+            auth,
+            "HMAC-SHA256 SignedHeaders=x-ms-date;host;x-ms-content-sha256\
+             &Signature=Qyr3ki2j2emWfNKli3w03sChkpGRFjzJcmYWygXWufI="
+        );
+
+        let bad = AzureAcsClient::new(ENDPOINT, "not base64!");
+        assert!(matches!(
+            bad.authorization("POST", "/", "h", "d", &hash),
+            Err(SmsError::Auth(_))
+        ));
+    }
+
+    #[test]
+    fn http_date_is_rfc1123() {
+        let at = OffsetDateTime::from_unix_timestamp(1_704_067_200).unwrap();
+        assert_eq!(http_date(at), "Mon, 01 Jan 2024 00:00:00 GMT");
+    }
+
+    #[test]
+    fn send_request_wire_format() {
+        let body = serde_json::to_value(AcsSendRequest {
+            from: "+18445550199",
+            sms_recipients: [AcsRecipient { to: "+14155550100" }],
+            message: "hi",
+            sms_send_options: AcsSendOptions {
+                enable_delivery_report: true,
+            },
+        })
+        .unwrap();
+        assert_eq!(body["smsRecipients"][0]["to"], "+14155550100");
+        assert_eq!(body["smsSendOptions"]["enableDeliveryReport"], true);
+    }
+
+    fn events() -> serde_json::Value {
+        json!([
+            {
+                "id": "e1",
+                "topic": "/subscriptions/s/resourceGroups/g/providers/Microsoft.Communication/CommunicationServices/contoso",
+                "subject": "/phonenumber/18445550199",
+                "eventType": "Microsoft.Communication.SMSReceived",
+                "eventTime": "2024-01-01T00:00:00Z",
+                "data": {
+                    "MessageId": "Incoming_1",
+                    "From": "+14155550100",
+                    "To": "+18445550199",
+                    "Message": "hello",
+                    "ReceivedTimestamp": "2024-01-01T00:00:00Z"
+                }
+            },
+            {
+                "id": "e2",
+                "source": "/subscriptions/s/resourceGroups/g/providers/Microsoft.Communication/CommunicationServices/contoso",
+                "type": "Microsoft.Communication.SMSDeliveryReportReceived",
+                "time": "2024-01-01T00:00:05Z",
+                "data": {
+                    "messageId": "Outgoing_1",
+                    "from": "+18445550199",
+                    "to": "+14155550100",
+                    "deliveryStatus": "Delivered",
+                    "deliveryStatusDetails": "No error.",
+                    "receivedTimestamp": "2024-01-01T00:00:05Z",
+                    "tag": "welcome"
+                }
+            }
+        ])
+    }
+
+    #[test]
+    fn parses_event_grid_batch() {
+        let body = events().to_string();
+        let messages = client()
+            .parse_inbound_batch(&Headers::new(), body.as_bytes())
+            .unwrap();
+        assert_eq!(messages.len(), 2);
+
+        let inbound = &messages[0];
+        assert_eq!(inbound.direction, InboundDirection::Inbound);
+        assert_eq!(inbound.id.as_deref(), Some("Incoming_1"));
+        assert_eq!(inbound.from, "+14155550100");
+        assert_eq!(inbound.text, "hello");
+        assert_eq!(inbound.provider_account.as_deref(), Some("contoso"));
+        assert!(inbound.timestamp.is_some());
+
+        let report = &messages[1];
+        assert_eq!(report.direction, InboundDirection::DeliveryReport);
+        assert_eq!(report.id.as_deref(), Some("Outgoing_1"));
+        assert_eq!(report.text, "Delivery Status: Delivered");
+        let typed = AcsDeliveryReport::from_message(report).unwrap();
+        assert!(typed.is_delivered());
+        assert_eq!(typed.tag.as_deref(), Some("welcome"));
+    }
+
+    #[test]
+    fn parse_inbound_wants_a_single_event() {
+        let client = client();
+        let single = events()[0].to_string();
+        assert!(
+            client
+                .parse_inbound(&Headers::new(), single.as_bytes())
+                .is_ok()
+        );
+        let both = events().to_string();
+        assert!(
+            client
+                .parse_inbound(&Headers::new(), both.as_bytes())
+                .is_err()
+        );
+        assert!(client.parse_inbound(&Headers::new(), b"nope").is_err());
+    }
+
+    #[test]
+    fn answers_subscription_validation() {
+        let body = json!([{
+            "id": "v1",
+            "eventType": "Microsoft.EventGrid.SubscriptionValidationEvent",
+            "data": { "validationCode": "512d38b6-c7b8-40c8-89fe-f46f9e9622b6" }
+        }])
+        .to_string();
+        assert_eq!(
+            validation_response(body.as_bytes()),
+            Some(json!({ "validationResponse": "512d38b6-c7b8-40c8-89fe-f46f9e9622b6" }))
+        );
+        assert!(
+            client()
+                .parse_inbound(&Headers::new(), body.as_bytes())
+                .is_err()
+        );
+        assert_eq!(validation_response(events().to_string().as_bytes()), None);
+    }
+
+    #[test]
+    fn plugin_builds_from_settings() {
+        let plugin = sms_core::ProviderPlugin::find("azure_acs").unwrap();
+        let built = plugin
+            .build(&json!({ "endpoint": ENDPOINT, "access_key": "c2VjcmV0LWtleQ==" }))
+            .unwrap();
+        assert_eq!(built.webhook.unwrap().provider(), "azure-acs");
+        assert!(built.client.is_some());
+        assert!(plugin.build(&json!({ "endpoint": ENDPOINT })).is_err());
+    }
+
+    #[test]
+    fn debug_redacts_access_key() {
+        let debug = format!("{:?}", client());
+        assert!(debug.contains("contoso"));
+        assert!(!debug.contains("c2VjcmV0LWtleQ=="));
+    }
+}
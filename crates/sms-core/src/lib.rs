@@ -7,6 +7,9 @@
 //! - [`InboundWebhook`] trait for processing incoming webhooks
 //! - [`SmsRouter`] for dispatching sends to named providers
 //! - [`FallbackClient`] for try-in-order provider chaining
+//! - [`ProviderPlugin`] for building providers by configuration name
+//! - [`define_http_provider!`] for declaring simple HTTP providers
+//!   (`http-provider` feature)
 //! - Common types for requests, responses, and errors
 //!
 //! ## Sending a message
@@ -18,7 +21,8 @@
 //! let response = client.send(SendRequest {
 //!     to: "+1234567890",
 //!     from: "+0987654321",
-//!     text: "Hello world!"
+//!     text: "Hello world!",
+//!     ..Default::default()
 //! }).await?;
 //! ```
 //!
@@ -62,6 +66,56 @@ use serde::{Deserialize, Serialize};
 use time::OffsetDateTime;
 use uuid::Uuid;
 
+/// Credential wrappers: [`SecretString`] zeroizes its contents on drop,
+/// prints as `[REDACTED]` and can only be read through
+/// [`ExposeSecret::expose_secret`].  Provider clients and configuration hold
+/// auth tokens and keys in it.
+pub use secrecy::{ExposeSecret, SecretString};
+
+/// Serializers that write [`SecretString`] fields as `[REDACTED]`, so a
+/// serialized config can be logged or shown on a debug endpoint without
+/// leaking credentials.  Presence is kept: an unset optional secret still
+/// serializes as `null`.
+///
+/// ```rust,ignore
+/// #[derive(Serialize, Deserialize)]
+/// struct Settings {
+///     #[serde(serialize_with = "sms_core::redact_secret::serialize")]
+///     api_key: SecretString,
+///     #[serde(default, serialize_with = "sms_core::redact_secret::serialize_opt")]
+///     password: Option<SecretString>,
+/// }
+/// ```
+pub mod redact_secret {
+    use secrecy::SecretString;
+    use serde::Serializer;
+
+    /// Written in place of every credential.
+    pub const REDACTED: &str = "[REDACTED]";
+
+    /// Serialize a secret as [`REDACTED`].
+    pub fn serialize<S: Serializer>(_: &SecretString, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(REDACTED)
+    }
+
+    /// Serialize a present secret as [`REDACTED`] and an absent one as
+    /// `null`.
+    pub fn serialize_opt<S: Serializer>(
+        secret: &Option<SecretString>,
+        s: S,
+    ) -> Result<S::Ok, S::Error> {
+        match secret {
+            Some(_) => s.serialize_some(REDACTED),
+            None => s.serialize_none(),
+        }
+    }
+
+    /// Serialize a list of secrets as one [`REDACTED`] per entry.
+    pub fn serialize_all<S: Serializer>(secrets: &[SecretString], s: S) -> Result<S::Ok, S::Error> {
+        s.collect_seq(secrets.iter().map(|_| REDACTED))
+    }
+}
+
 use std::collections::HashMap;
 use std::sync::Arc;
 
@@ -72,8 +126,10 @@ use std::sync::Arc;
 /// Errors that can occur during SMS send operations.
 ///
 /// Each variant maps to a distinct failure class so callers can decide whether
-/// to retry, re-authenticate, fix their input, or escalate.
-#[derive(Debug, thiserror::Error)]
+/// to retry, re-authenticate, fix their input, or escalate.  New variants may
+/// be added, so matches need a wildcard arm.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[non_exhaustive]
 pub enum SmsError {
     /// An HTTP / network-level transport error (timeouts, DNS failures, etc.).
     #[error("http error: {0}")]
@@ -95,6 +151,188 @@ pub enum SmsError {
     /// Catch-all for errors that don't fit the categories above.
     #[error("unexpected: {0}")]
     Unexpected(String),
+
+    /// The provider throttled the request (HTTP 429 or equivalent).
+    /// `retry_after` carries the provider's hint when it sent one.
+    #[error("rate limited{}", .retry_after.map(|d| format!("; retry after {}s", d.as_secs())).unwrap_or_default())]
+    RateLimited {
+        /// How long the provider asked us to wait before retrying.
+        retry_after: Option<std::time::Duration>,
+    },
+
+    /// The provider did not answer within the allotted time.
+    #[error("timed out: {0}")]
+    Timeout(String),
+
+    /// The send was deliberately not attempted (opted-out recipient,
+    /// blocklisted number, etc.).  Retrying will not help.
+    #[error("suppressed: {0}")]
+    Suppressed(String),
+
+    /// The provider rejected the send with an error code smskit recognises.
+    /// Branch on [`ProviderError::kind`] rather than the message text.
+    #[error("{0}")]
+    Rejected(ProviderError),
+}
+
+impl SmsError {
+    /// Returns `true` for failures that may succeed if retried later
+    /// (transport errors, throttling, timeouts).
+    pub fn is_transient(&self) -> bool {
+        match self {
+            SmsError::Http(_) | SmsError::RateLimited { .. } | SmsError::Timeout(_) => true,
+            SmsError::Rejected(e) => e.kind.is_transient(),
+            _ => false,
+        }
+    }
+
+    /// The structured provider error, if the provider returned a recognised
+    /// error code.
+    pub fn provider_error(&self) -> Option<&ProviderError> {
+        match self {
+            SmsError::Rejected(e) => Some(e),
+            _ => None,
+        }
+    }
+
+    /// The HTTP status an API server wrapping smskit should answer with when
+    /// a send fails with this error.
+    ///
+    /// | Error | Status |
+    /// |-------|--------|
+    /// | `Invalid` | 400 Bad Request |
+    /// | `Auth` | 401 Unauthorized |
+    /// | `Suppressed` | 422 Unprocessable Entity |
+    /// | `RateLimited` | 429 Too Many Requests |
+    /// | `Unexpected` | 500 Internal Server Error |
+    /// | `Http`, `Provider` | 502 Bad Gateway |
+    /// | `Timeout` | 504 Gateway Timeout |
+    /// | `Rejected` | per [`ProviderErrorKind::http_status`] |
+    pub fn http_status(&self) -> HttpStatus {
+        match self {
+            SmsError::Invalid(_) => HttpStatus::BadRequest,
+            SmsError::Auth(_) => HttpStatus::Unauthorized,
+            SmsError::Suppressed(_) => HttpStatus::UnprocessableEntity,
+            SmsError::RateLimited { .. } => HttpStatus::TooManyRequests,
+            SmsError::Unexpected(_) => HttpStatus::InternalServerError,
+            SmsError::Http(_) | SmsError::Provider(_) => HttpStatus::BadGateway,
+            SmsError::Timeout(_) => HttpStatus::GatewayTimeout,
+            SmsError::Rejected(e) => e.kind.http_status(),
+        }
+    }
+}
+
+/// Provider-independent classification of a provider error code.
+///
+/// Each provider crate maps its own codes (Twilio `21211`, Plivo `50`, SNS
+/// `OptedOut`, …) onto these kinds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum ProviderErrorKind {
+    /// The destination number is malformed or does not exist.
+    InvalidDestination,
+    /// The destination exists but cannot receive SMS (landline, switched
+    /// off, out of coverage).
+    UnreachableDestination,
+    /// The sender number or ID is invalid or not owned by the account.
+    InvalidSender,
+    /// The recipient has opted out (replied STOP) of messages from this sender.
+    OptedOut,
+    /// The carrier or provider blocked the message as spam or filtered content.
+    Blocked,
+    /// The message body is too long or otherwise unacceptable.
+    InvalidContent,
+    /// The account is not permitted to send to the destination's region.
+    RegionNotPermitted,
+    /// The account has run out of credit.
+    InsufficientFunds,
+    /// The account is suspended, disabled or closed.
+    AccountSuspended,
+    /// The credentials were rejected.
+    Unauthorized,
+    /// The provider's queue or rate limit was exceeded.
+    Throttled,
+    /// The provider or carrier failed internally.
+    ProviderFailure,
+    /// A code smskit does not classify.
+    Other,
+}
+
+impl ProviderErrorKind {
+    /// Returns `true` for kinds that may succeed if retried later.
+    pub fn is_transient(self) -> bool {
+        matches!(
+            self,
+            ProviderErrorKind::Throttled | ProviderErrorKind::ProviderFailure
+        )
+    }
+
+    /// The HTTP status an API server should answer with: 400 for problems
+    /// with the request, 422 for opted-out or blocked recipients, 401 and 429
+    /// for credential and throttling errors, and 502 otherwise.
+    pub fn http_status(self) -> HttpStatus {
+        match self {
+            ProviderErrorKind::InvalidDestination
+            | ProviderErrorKind::InvalidSender
+            | ProviderErrorKind::InvalidContent => HttpStatus::BadRequest,
+            ProviderErrorKind::UnreachableDestination
+            | ProviderErrorKind::OptedOut
+            | ProviderErrorKind::Blocked
+            | ProviderErrorKind::RegionNotPermitted => HttpStatus::UnprocessableEntity,
+            ProviderErrorKind::Unauthorized => HttpStatus::Unauthorized,
+            ProviderErrorKind::Throttled => HttpStatus::TooManyRequests,
+            _ => HttpStatus::BadGateway,
+        }
+    }
+}
+
+/// A provider error code together with its classification.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, thiserror::Error)]
+#[error("{provider} error {code}: {message}")]
+pub struct ProviderError {
+    /// Provider that returned the error (e.g. `"twilio"`).
+    pub provider: String,
+    /// The provider's own code, as a string (`"21211"`, `"OptedOut"`, …).
+    pub code: String,
+    /// What the code means.
+    pub kind: ProviderErrorKind,
+    /// The provider's human-readable message.
+    pub message: String,
+}
+
+impl ProviderError {
+    /// Create a provider error.
+    pub fn new(
+        provider: impl Into<String>,
+        code: impl Into<String>,
+        kind: ProviderErrorKind,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            provider: provider.into(),
+            code: code.into(),
+            kind,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<ProviderError> for SmsError {
+    fn from(e: ProviderError) -> Self {
+        SmsError::Rejected(e)
+    }
+}
+
+/// Parse an HTTP `Retry-After` header given in delta-seconds.
+///
+/// HTTP-date values are not supported and return `None`.
+pub fn parse_retry_after(value: &str) -> Option<std::time::Duration> {
+    value
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .map(std::time::Duration::from_secs)
 }
 
 /// Errors specific to inbound webhook processing.
@@ -104,6 +342,10 @@ pub enum WebhookError {
     #[error("provider not found: {0}")]
     ProviderNotFound(String),
 
+    /// The tenant name in the URL did not match any registered tenant.
+    #[error("tenant not found: {0}")]
+    TenantNotFound(String),
+
     /// Signature / HMAC verification on the incoming payload failed.
     #[error("signature verification failed: {0}")]
     VerificationFailed(String),
@@ -115,6 +357,28 @@ pub enum WebhookError {
     /// A lower-level [`SmsError`] surfaced during webhook handling.
     #[error("SMS processing error: {0}")]
     SmsError(#[from] SmsError),
+
+    /// The caller exceeded its webhook rate limit.
+    #[error("rate limit exceeded; retry after {}s", .0.as_secs())]
+    RateLimited(std::time::Duration),
+}
+
+impl WebhookError {
+    /// The HTTP status to answer the webhook caller with: 404 for unknown
+    /// providers and tenants, 401 for failed verification, 400 for
+    /// unparseable payloads, 429 when rate limited, and
+    /// [`SmsError::http_status`] for wrapped send errors.
+    pub fn http_status(&self) -> HttpStatus {
+        match self {
+            WebhookError::ProviderNotFound(_) | WebhookError::TenantNotFound(_) => {
+                HttpStatus::NotFound
+            }
+            WebhookError::VerificationFailed(_) => HttpStatus::Unauthorized,
+            WebhookError::ParseError(_) => HttpStatus::BadRequest,
+            WebhookError::RateLimited(_) => HttpStatus::TooManyRequests,
+            WebhookError::SmsError(e) => e.http_status(),
+        }
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -123,8 +387,9 @@ pub enum WebhookError {
 
 /// Minimal HTTP status codes used by [`WebhookResponse`].
 ///
-/// Only the codes that the webhook pipeline actually produces are listed here;
-/// this is **not** a general-purpose HTTP status enum.
+/// Only the codes that the webhook pipeline and [`SmsError::http_status`]
+/// actually produce are listed here; this is **not** a general-purpose HTTP
+/// status enum.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum HttpStatus {
     /// 200 OK
@@ -135,8 +400,24 @@ pub enum HttpStatus {
     Unauthorized = 401,
     /// 404 Not Found
     NotFound = 404,
+    /// 405 Method Not Allowed
+    MethodNotAllowed = 405,
+    /// 413 Payload Too Large
+    PayloadTooLarge = 413,
+    /// 415 Unsupported Media Type
+    UnsupportedMediaType = 415,
+    /// 422 Unprocessable Entity
+    UnprocessableEntity = 422,
+    /// 429 Too Many Requests
+    TooManyRequests = 429,
     /// 500 Internal Server Error
     InternalServerError = 500,
+    /// 502 Bad Gateway
+    BadGateway = 502,
+    /// 503 Service Unavailable
+    ServiceUnavailable = 503,
+    /// 504 Gateway Timeout
+    GatewayTimeout = 504,
 }
 
 impl HttpStatus {
@@ -144,6 +425,518 @@ impl HttpStatus {
     pub fn as_u16(self) -> u16 {
         self as u16
     }
+
+    /// The standard reason phrase, e.g. `"Not Found"`.
+    pub fn reason(self) -> &'static str {
+        match self {
+            HttpStatus::Ok => "OK",
+            HttpStatus::BadRequest => "Bad Request",
+            HttpStatus::Unauthorized => "Unauthorized",
+            HttpStatus::NotFound => "Not Found",
+            HttpStatus::MethodNotAllowed => "Method Not Allowed",
+            HttpStatus::PayloadTooLarge => "Payload Too Large",
+            HttpStatus::UnsupportedMediaType => "Unsupported Media Type",
+            HttpStatus::UnprocessableEntity => "Unprocessable Entity",
+            HttpStatus::TooManyRequests => "Too Many Requests",
+            HttpStatus::InternalServerError => "Internal Server Error",
+            HttpStatus::BadGateway => "Bad Gateway",
+            HttpStatus::ServiceUnavailable => "Service Unavailable",
+            HttpStatus::GatewayTimeout => "Gateway Timeout",
+        }
+    }
+}
+
+/// Outbound proxy for provider API calls.
+///
+/// Without one, HTTP providers still honor the standard `HTTPS_PROXY`,
+/// `HTTP_PROXY`, `ALL_PROXY` and `NO_PROXY` environment variables.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProxyConfig {
+    /// Proxy URL: `http://`, `https://`, or `socks5://` (SOCKS needs the
+    /// provider crate's `socks` feature).
+    pub url: String,
+    /// Username for proxy basic auth.
+    #[serde(default)]
+    pub username: Option<String>,
+    /// Password for proxy basic auth; serialized as `[REDACTED]`.
+    #[serde(default, serialize_with = "redact_secret::serialize_opt")]
+    pub password: Option<SecretString>,
+    /// Comma-separated hosts that bypass the proxy.
+    #[serde(default)]
+    pub no_proxy: Option<String>,
+}
+
+impl ProxyConfig {
+    /// Route every request through `url`.
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            username: None,
+            password: None,
+            no_proxy: None,
+        }
+    }
+
+    /// Authenticate to the proxy with basic auth.
+    pub fn with_basic_auth(
+        mut self,
+        username: impl Into<String>,
+        password: impl Into<String>,
+    ) -> Self {
+        self.username = Some(username.into());
+        self.password = Some(SecretString::from(password.into()));
+        self
+    }
+
+    /// Bypass the proxy for a comma-separated list of hosts.
+    pub fn with_no_proxy(mut self, hosts: impl Into<String>) -> Self {
+        self.no_proxy = Some(hosts.into());
+        self
+    }
+
+    /// Read a provider-specific proxy from `{PREFIX}_PROXY` (e.g.
+    /// `PLIVO_PROXY`), falling back to `SMSKIT_PROXY`.  `NO_PROXY` is applied
+    /// as the bypass list.  Returns `None` if neither variable is set.
+    pub fn from_env(prefix: &str) -> Option<Self> {
+        let url = std::env::var(format!("{}_PROXY", prefix))
+            .or_else(|_| std::env::var("SMSKIT_PROXY"))
+            .ok()
+            .filter(|u| !u.is_empty())?;
+        let mut proxy = Self::new(url);
+        proxy.no_proxy = std::env::var("NO_PROXY").ok().filter(|n| !n.is_empty());
+        Some(proxy)
+    }
+}
+
+impl PartialEq for ProxyConfig {
+    fn eq(&self, other: &Self) -> bool {
+        self.url == other.url
+            && self.username == other.username
+            && self.password.as_ref().map(ExposeSecret::expose_secret)
+                == other.password.as_ref().map(ExposeSecret::expose_secret)
+            && self.no_proxy == other.no_proxy
+    }
+}
+
+impl Eq for ProxyConfig {}
+
+/// Transport settings for HTTP-based provider clients.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HttpClientConfig {
+    /// Explicit proxy; overrides the standard proxy environment variables.
+    #[serde(default)]
+    pub proxy: Option<ProxyConfig>,
+    /// Extra PEM-encoded root certificates to trust, for self-hosted
+    /// gateways behind a private CA.
+    #[serde(default)]
+    pub root_certificates: Vec<String>,
+    /// Skip TLS certificate verification entirely.  Only for tests against
+    /// local mock servers; never enable this in production.
+    #[serde(default)]
+    pub accept_invalid_certs: bool,
+    /// `User-Agent` sent with every request, for aggregators that route
+    /// partner traffic by it.  Defaults to the HTTP library's own.
+    #[serde(default)]
+    pub user_agent: Option<String>,
+    /// Static headers sent with every request, such as an API version pin
+    /// or a partner identifier.
+    #[serde(default)]
+    pub headers: std::collections::BTreeMap<String, String>,
+}
+
+impl HttpClientConfig {
+    /// Default transport settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Send requests through `proxy`.
+    pub fn with_proxy(mut self, proxy: ProxyConfig) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// Trust an additional PEM-encoded root certificate.
+    pub fn with_root_certificate(mut self, pem: impl Into<String>) -> Self {
+        self.root_certificates.push(pem.into());
+        self
+    }
+
+    /// Read a PEM-encoded root certificate from `path` and trust it.
+    pub fn with_root_certificate_file(
+        self,
+        path: impl AsRef<std::path::Path>,
+    ) -> std::io::Result<Self> {
+        let pem = std::fs::read_to_string(path)?;
+        Ok(self.with_root_certificate(pem))
+    }
+
+    /// Disable TLS certificate verification (test use only).
+    pub fn danger_accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.accept_invalid_certs = accept;
+        self
+    }
+
+    /// Send `user_agent` as the `User-Agent` header.
+    pub fn with_user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Send `name: value` with every request, replacing any earlier value
+    /// for `name`.
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.insert(name.into(), value.into());
+        self
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Provider HTTP logging
+// ---------------------------------------------------------------------------
+
+/// One provider API call, as recorded by an [`HttpLogger`].
+///
+/// Bodies and the URL query string have already been redacted.
+#[derive(Debug, Clone, Serialize)]
+pub struct HttpExchange {
+    /// Provider that made the call (e.g. `"plivo"`).
+    pub provider: &'static str,
+    /// HTTP method.
+    pub method: &'static str,
+    /// Request URL.
+    pub url: String,
+    /// When the request was sent.
+    #[serde(with = "time::serde::rfc3339")]
+    pub started_at: OffsetDateTime,
+    /// Time until the response body was read, or the call failed.
+    pub latency: std::time::Duration,
+    /// Response status; `None` if no response arrived.
+    pub status: Option<u16>,
+    /// Request body.
+    pub request_body: Option<String>,
+    /// Response body.
+    pub response_body: Option<String>,
+    /// Transport error, if the call failed before a response arrived.
+    pub error: Option<String>,
+}
+
+/// Where an [`HttpLogger`] sends finished exchanges.
+///
+/// Implemented for closures, so `|e: &HttpExchange| println!("{:?}", e)`
+/// works as a sink.
+pub trait HttpLogSink: Send + Sync {
+    /// Record one exchange.
+    fn record(&self, exchange: &HttpExchange);
+}
+
+impl<F: Fn(&HttpExchange) + Send + Sync> HttpLogSink for F {
+    fn record(&self, exchange: &HttpExchange) {
+        self(exchange)
+    }
+}
+
+/// Field names whose values are always redacted.
+const SECRET_FIELDS: &[&str] = &[
+    "access_token",
+    "api_key",
+    "apikey",
+    "api_secret",
+    "auth_token",
+    "authorization",
+    "client_secret",
+    "password",
+    "secret",
+    "signature",
+    "token",
+];
+
+/// Field names that carry message content.
+const TEXT_FIELDS: &[&str] = &["body", "content", "message", "text"];
+
+/// Fields holding phone numbers, masked per the [`RedactionPolicy`].
+const NUMBER_FIELDS: &[&str] = &["dst", "from", "phone", "src", "to"];
+
+const HTTP_REDACTED: &str = "[REDACTED]";
+
+/// Opt-in transport-level logging for provider API calls.
+///
+/// Provider clients accept one through `with_http_logger`; each call is then
+/// reported to the sink with its method, URL, status, latency and bodies.
+/// Credentials in JSON bodies, form bodies and query strings are replaced
+/// with `[REDACTED]`, and phone numbers are logged as [`redact_number`]
+/// allows; message text can be redacted too with
+/// [`redact_message_text`](Self::redact_message_text).
+///
+/// ```rust,ignore
+/// let log = HttpLogger::new(|e: &HttpExchange| eprintln!("{} {} -> {:?}", e.method, e.url, e.status))
+///     .redact_message_text();
+/// let client = PlivoClient::from_env()?.with_http_logger(log);
+/// ```
+#[derive(Clone)]
+pub struct HttpLogger {
+    sink: Arc<dyn HttpLogSink>,
+    redacted: Vec<String>,
+    max_body_len: usize,
+}
+
+impl std::fmt::Debug for HttpLogger {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HttpLogger")
+            .field("redacted", &self.redacted)
+            .field("max_body_len", &self.max_body_len)
+            .finish_non_exhaustive()
+    }
+}
+
+impl HttpLogger {
+    /// Log to `sink`, redacting common credential fields and keeping up to
+    /// 4 KiB of each body.
+    pub fn new(sink: impl HttpLogSink + 'static) -> Self {
+        Self {
+            sink: Arc::new(sink),
+            redacted: SECRET_FIELDS.iter().map(|f| f.to_string()).collect(),
+            max_body_len: 4096,
+        }
+    }
+
+    /// Also redact the value of `field` (matched case-insensitively).
+    pub fn with_redacted_field(mut self, field: impl Into<String>) -> Self {
+        self.redacted.push(field.into().to_ascii_lowercase());
+        self
+    }
+
+    /// Redact message text (`text`, `body`, `message` and `content` fields).
+    pub fn redact_message_text(mut self) -> Self {
+        self.redacted
+            .extend(TEXT_FIELDS.iter().map(|f| f.to_string()));
+        self
+    }
+
+    /// Truncate logged bodies to `len` bytes.
+    pub fn with_max_body_len(mut self, len: usize) -> Self {
+        self.max_body_len = len;
+        self
+    }
+
+    /// Start timing a call.  `body` is the request body as sent.
+    pub fn start(
+        &self,
+        provider: &'static str,
+        method: &'static str,
+        url: &str,
+        body: Option<&str>,
+    ) -> PendingExchange {
+        PendingExchange {
+            logger: self.clone(),
+            started: std::time::Instant::now(),
+            exchange: HttpExchange {
+                provider,
+                method,
+                url: self.redact_url(url),
+                started_at: OffsetDateTime::now_utc(),
+                latency: std::time::Duration::ZERO,
+                status: None,
+                request_body: body.map(|b| self.redact_body(b)),
+                response_body: None,
+                error: None,
+            },
+        }
+    }
+
+    fn is_redacted(&self, field: &str) -> bool {
+        self.redacted.iter().any(|r| r.eq_ignore_ascii_case(field))
+    }
+
+    fn redact_url(&self, url: &str) -> String {
+        match url.split_once('?') {
+            Some((base, query)) => format!("{}?{}", base, self.redact_form(query)),
+            None => url.to_string(),
+        }
+    }
+
+    /// Redact a JSON or form-encoded body; anything else is kept as is.
+    fn redact_body(&self, body: &str) -> String {
+        let redacted = match serde_json::from_str::<serde_json::Value>(body) {
+            Ok(mut value) => {
+                self.redact_json(&mut value);
+                value.to_string()
+            }
+            Err(_) if is_form(body) => self.redact_form(body),
+            Err(_) => body.to_string(),
+        };
+        truncate_body(redacted, self.max_body_len)
+    }
+
+    fn redact_json(&self, value: &mut serde_json::Value) {
+        match value {
+            serde_json::Value::Object(map) => {
+                for (key, v) in map.iter_mut() {
+                    if self.is_redacted(key) {
+                        *v = serde_json::Value::String(HTTP_REDACTED.into());
+                    } else if is_number_field(key) {
+                        if let Some(number) = json_scalar(v) {
+                            *v = serde_json::Value::String(redact_number(&number).into_owned());
+                        }
+                    } else {
+                        self.redact_json(v);
+                    }
+                }
+            }
+            serde_json::Value::Array(items) => items.iter_mut().for_each(|v| self.redact_json(v)),
+            _ => {}
+        }
+    }
+
+    fn redact_form(&self, form: &str) -> String {
+        form.split('&')
+            .map(|pair| match pair.split_once('=') {
+                Some((key, _)) if self.is_redacted(key) => format!("{}={}", key, HTTP_REDACTED),
+                Some((key, value)) if is_number_field(key) => {
+                    let number = redact_number(&form_decode(value)).into_owned();
+                    format!("{}={}", key, form_encode(&number))
+                }
+                _ => pair.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+}
+
+fn is_number_field(field: &str) -> bool {
+    NUMBER_FIELDS.iter().any(|f| f.eq_ignore_ascii_case(field))
+}
+
+/// A JSON string or number as text; numbers are sometimes sent unquoted.
+fn json_scalar(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::String(s) => Some(s.clone()),
+        serde_json::Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// Decode an `application/x-www-form-urlencoded` value.
+fn form_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                i += 3;
+                continue;
+            }
+            (b'+', _) => out.push(b' '),
+            (b, _) => out.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Encode a value for an `application/x-www-form-urlencoded` body.
+fn form_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'*' | b'-' | b'.' | b'_' => {
+                (b as char).to_string()
+            }
+            b' ' => "+".to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// `key=value&...` with no whitespace.
+fn is_form(body: &str) -> bool {
+    body.contains('=') && !body.contains(char::is_whitespace)
+}
+
+fn truncate_body(mut body: String, max: usize) -> String {
+    if body.len() > max {
+        let mut end = max;
+        while !body.is_char_boundary(end) {
+            end -= 1;
+        }
+        body.truncate(end);
+        body.push_str("...");
+    }
+    body
+}
+
+/// An in-flight call started with [`HttpLogger::start`].
+#[derive(Debug)]
+pub struct PendingExchange {
+    logger: HttpLogger,
+    started: std::time::Instant,
+    exchange: HttpExchange,
+}
+
+impl PendingExchange {
+    /// Record the response.
+    pub fn finish(mut self, status: u16, body: &str) {
+        self.exchange.status = Some(status);
+        self.exchange.response_body = Some(self.logger.redact_body(body));
+        self.record();
+    }
+
+    /// Record a call that failed before a response arrived.
+    pub fn fail(mut self, error: &SmsError) {
+        self.exchange.error = Some(error.to_string());
+        self.record();
+    }
+
+    fn record(mut self) {
+        self.exchange.latency = self.started.elapsed();
+        self.logger.sink.record(&self.exchange);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Channel
+// ---------------------------------------------------------------------------
+
+/// The messaging channel a request or message travels over.
+///
+/// Aggregators often offer more than plain SMS through the same API.  Use
+/// [`SmsClient::supports`] to check whether a client can carry a channel.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Channel {
+    /// Plain text SMS.
+    #[default]
+    Sms,
+    /// Multimedia messaging.
+    Mms,
+    /// WhatsApp Business messaging.
+    WhatsApp,
+    /// Rich Communication Services.
+    Rcs,
+}
+
+impl Channel {
+    /// Lowercase name, e.g. `"whatsapp"`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Channel::Sms => "sms",
+            Channel::Mms => "mms",
+            Channel::WhatsApp => "whatsapp",
+            Channel::Rcs => "rcs",
+        }
+    }
+}
+
+impl std::fmt::Display for Channel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -155,7 +948,7 @@ impl HttpStatus {
 /// This is the type accepted by [`SmsClient::send`].  It borrows its string
 /// fields to avoid allocations on the hot path.  If you need an owned variant
 /// that can live across `.await` points, see [`OwnedSendRequest`].
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct SendRequest<'a> {
     /// E.164 destination phone number, e.g. `"+14155551234"`.
     pub to: &'a str,
@@ -163,6 +956,13 @@ pub struct SendRequest<'a> {
     pub from: &'a str,
     /// The message body (plain text).
     pub text: &'a str,
+    /// Channel to send over (default: [`Channel::Sms`]).
+    #[serde(default)]
+    pub channel: Channel,
+    /// Per-request deadline for the provider call.  Honored by the HTTP
+    /// providers and by `TimeoutClient`; `None` uses the client's default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<std::time::Duration>,
 }
 
 /// An owned variant of [`SendRequest`] for use in async contexts.
@@ -193,6 +993,12 @@ pub struct OwnedSendRequest {
     pub from: String,
     /// The message body (plain text).
     pub text: String,
+    /// Channel to send over (default: [`Channel::Sms`]).
+    #[serde(default)]
+    pub channel: Channel,
+    /// Per-request deadline for the provider call.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<std::time::Duration>,
 }
 
 impl OwnedSendRequest {
@@ -209,9 +1015,23 @@ impl OwnedSendRequest {
             to: to.into(),
             from: from.into(),
             text: text.into(),
+            channel: Channel::Sms,
+            timeout: None,
         }
     }
 
+    /// Send over `channel` instead of SMS.
+    pub fn with_channel(mut self, channel: Channel) -> Self {
+        self.channel = channel;
+        self
+    }
+
+    /// Give up on the provider call after `timeout`.
+    pub fn with_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
     /// Borrow this owned request as a [`SendRequest`] suitable for
     /// [`SmsClient::send`].
     pub fn as_ref(&self) -> SendRequest<'_> {
@@ -219,6 +1039,8 @@ impl OwnedSendRequest {
             to: &self.to,
             from: &self.from,
             text: &self.text,
+            channel: self.channel,
+            timeout: self.timeout,
         }
     }
 }
@@ -229,6 +1051,8 @@ impl<'a> From<SendRequest<'a>> for OwnedSendRequest {
             to: req.to.to_owned(),
             from: req.from.to_owned(),
             text: req.text.to_owned(),
+            channel: req.channel,
+            timeout: req.timeout,
         }
     }
 }
@@ -246,6 +1070,19 @@ pub struct SendResponse {
     pub id: String,
     /// Name of the provider that handled the send, e.g. `"plivo"`.
     pub provider: &'static str,
+    /// Channel the message was sent over.
+    #[serde(default)]
+    pub channel: Channel,
+    /// HTTP status of the provider's API response, for HTTP-based providers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http_status: Option<u16>,
+    /// The provider's ID for the API call itself (not the message), which
+    /// support teams ask for when investigating a send.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider_request_id: Option<String>,
+    /// Conversation the message belongs to; see [`thread_id`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thread_id: Option<String>,
     /// Raw JSON payload from the provider, useful for debugging / audit logs.
     pub raw: serde_json::Value,
 }
@@ -254,6 +1091,98 @@ pub struct SendResponse {
 // Inbound message
 // ---------------------------------------------------------------------------
 
+/// Serde helpers for optional timestamps.
+///
+/// Serializes `Option<OffsetDateTime>` as an RFC 3339 string (or `null`) and
+/// accepts RFC 3339, RFC 2822, `YYYY-MM-DD HH:MM:SS` (assumed UTC) and Unix
+/// epoch seconds or milliseconds when deserializing.
+///
+/// ```rust,ignore
+/// #[derive(Serialize, Deserialize)]
+/// struct Event {
+///     #[serde(default, with = "sms_core::timestamp")]
+///     at: Option<OffsetDateTime>,
+/// }
+/// ```
+pub mod timestamp {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use time::format_description::well_known::{Rfc2822, Rfc3339};
+    use time::OffsetDateTime;
+
+    /// Parse a timestamp in any of the accepted string formats.
+    pub fn parse(s: &str) -> Option<OffsetDateTime> {
+        let s = s.trim();
+        if let Ok(n) = s.parse::<i64>() {
+            return from_epoch(n);
+        }
+        OffsetDateTime::parse(s, &Rfc3339)
+            .or_else(|_| OffsetDateTime::parse(s, &Rfc2822))
+            .ok()
+            .or_else(|| {
+                // `YYYY-MM-DD HH:MM:SS` without an offset, as Plivo sends it.
+                let (date, clock) = s.split_once(' ')?;
+                OffsetDateTime::parse(&format!("{}T{}Z", date, clock), &Rfc3339).ok()
+            })
+    }
+
+    /// Interpret `n` as epoch milliseconds if it is too large to be seconds.
+    fn from_epoch(n: i64) -> Option<OffsetDateTime> {
+        if n > 100_000_000_000 {
+            OffsetDateTime::from_unix_timestamp_nanos(n as i128 * 1_000_000).ok()
+        } else {
+            OffsetDateTime::from_unix_timestamp(n).ok()
+        }
+    }
+
+    /// Serialize as an RFC 3339 string, or `null`.
+    pub fn serialize<S: Serializer>(
+        value: &Option<OffsetDateTime>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(ts) => {
+                let s = ts.format(&Rfc3339).map_err(serde::ser::Error::custom)?;
+                serializer.serialize_some(&s)
+            }
+            None => serializer.serialize_none(),
+        }
+    }
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Raw {
+        Epoch(i64),
+        Text(String),
+    }
+
+    /// Deserialize from any accepted format; `null` becomes `None`.
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<OffsetDateTime>, D::Error> {
+        match Option::<Raw>::deserialize(deserializer)? {
+            None => Ok(None),
+            Some(Raw::Epoch(n)) => from_epoch(n)
+                .map(Some)
+                .ok_or_else(|| serde::de::Error::custom(format!("timestamp out of range: {}", n))),
+            Some(Raw::Text(s)) if s.is_empty() => Ok(None),
+            Some(Raw::Text(s)) => parse(&s)
+                .map(Some)
+                .ok_or_else(|| serde::de::Error::custom(format!("unrecognized timestamp: {}", s))),
+        }
+    }
+}
+
+/// What kind of event an [`InboundMessage`] carries.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InboundDirection {
+    /// A message sent by a user to one of your numbers.
+    #[default]
+    Inbound,
+    /// A delivery status update for a message you sent.
+    DeliveryReport,
+}
+
 /// A provider-normalized inbound SMS message (e.g. a reply or MO message).
 ///
 /// Every provider adapter converts its native format into this common struct
@@ -269,34 +1198,121 @@ pub struct InboundMessage {
     /// The message body.
     pub text: String,
     /// When the message was sent/received (if the provider supplies it).
+    /// Serialized as RFC 3339; see [`timestamp`] for accepted input formats.
+    #[serde(default, with = "timestamp")]
     pub timestamp: Option<OffsetDateTime>,
     /// Which provider delivered this message, e.g. `"plivo"`.
     pub provider: &'static str,
+    /// Whether this is a user message or a delivery event.
+    #[serde(default)]
+    pub direction: InboundDirection,
+    /// Channel the message arrived on.
+    #[serde(default)]
+    pub channel: Channel,
+    /// Provider account the event belongs to (e.g. a Twilio Account SID), for
+    /// consumers that receive webhooks for several accounts.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider_account: Option<String>,
+    /// Conversation the message belongs to; see [`thread_id`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thread_id: Option<String>,
+    /// Why an inbound filter flagged this message as likely spam or abuse;
+    /// `None` if it wasn't flagged.  Handlers typically ignore flagged traffic.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flag: Option<String>,
     /// Raw provider payload for debugging.
     pub raw: serde_json::Value,
 }
 
-/// Result of webhook processing, containing both the message and response info.
-#[derive(Debug, Clone)]
-pub struct WebhookResult {
-    /// The parsed inbound message.
-    pub message: InboundMessage,
-    /// HTTP status to return to the provider's webhook caller.
-    pub status: u16,
+/// A media attachment referenced by an inbound MMS.
+///
+/// Providers host the media themselves; the URL usually needs the account's
+/// credentials to fetch.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MediaRef {
+    /// Where the provider hosts the media.
+    pub url: String,
+    /// MIME type as reported in the webhook, if any.
+    pub content_type: Option<String>,
 }
 
-/// A framework-agnostic webhook HTTP response.
+/// The outcome of processing one webhook call: what was parsed, whether it
+/// was verified, how long it took and what to answer.
 ///
-/// Framework adapters convert this into their native response type using the
-/// `ResponseConverter` trait defined in `sms-web-generic`.
-#[derive(Debug, Clone)]
-pub struct WebhookResponse {
-    /// HTTP status code to return.
-    pub status: HttpStatus,
+/// Returned by `WebhookProcessor::handle_webhook` in `sms-web-generic`, for
+/// applications that embed the processor and want more than the serialized
+/// [`WebhookResponse`].
+#[derive(Debug)]
+pub struct WebhookResult {
+    /// Provider name from the route, e.g. `"plivo"`.
+    pub provider: String,
+    /// Tenant from the route, for multi-tenant calls.
+    pub tenant: Option<String>,
+    /// Messages that passed the pipeline.  Empty if processing failed or
+    /// every message was dropped.
+    pub messages: Vec<InboundMessage>,
+    /// Whether the payload passed signature verification and replay checks.
+    /// `false` if processing stopped before verification.
+    pub verified: bool,
+    /// Why processing failed, if it did.
+    pub error: Option<WebhookError>,
+    /// Time spent processing the call.
+    pub elapsed: std::time::Duration,
+    /// The response to send back to the provider.
+    pub response: WebhookResponse,
+}
+
+impl WebhookResult {
+    /// Whether the call was processed without error.
+    pub fn is_success(&self) -> bool {
+        self.error.is_none()
+    }
+
+    /// The HTTP status of [`response`](Self::response).
+    pub fn status(&self) -> HttpStatus {
+        self.response.status
+    }
+
+    /// A short, stable label for the outcome, suitable as a log or metric
+    /// field: `"accepted"`, `"dropped"`, or the error kind such as
+    /// `"verification_failed"` or `"rate_limited"`.
+    pub fn outcome(&self) -> &'static str {
+        match &self.error {
+            None if self.messages.is_empty() => "dropped",
+            None => "accepted",
+            Some(WebhookError::ProviderNotFound(_)) => "provider_not_found",
+            Some(WebhookError::TenantNotFound(_)) => "tenant_not_found",
+            Some(WebhookError::VerificationFailed(_)) => "verification_failed",
+            Some(WebhookError::ParseError(_)) => "parse_error",
+            Some(WebhookError::SmsError(_)) => "sms_error",
+            Some(WebhookError::RateLimited(_)) => "rate_limited",
+        }
+    }
+
+    /// The message of a single-message payload; `None` for batches, failures
+    /// and dropped messages.
+    pub fn message(&self) -> Option<&InboundMessage> {
+        match self.messages.as_slice() {
+            [message] => Some(message),
+            _ => None,
+        }
+    }
+}
+
+/// A framework-agnostic webhook HTTP response.
+///
+/// Framework adapters convert this into their native response type using the
+/// `ResponseConverter` trait defined in `sms-web-generic`.
+#[derive(Debug, Clone)]
+pub struct WebhookResponse {
+    /// HTTP status code to return.
+    pub status: HttpStatus,
     /// Response body (JSON).
     pub body: String,
     /// The `Content-Type` header value.
     pub content_type: String,
+    /// Additional response headers, e.g. `Retry-After` or `Cache-Control`.
+    pub headers: Headers,
 }
 
 impl WebhookResponse {
@@ -306,6 +1322,18 @@ impl WebhookResponse {
             status: HttpStatus::Ok,
             body: serde_json::to_string(&message).unwrap_or_else(|_| "{}".to_string()),
             content_type: "application/json".to_string(),
+            headers: Vec::new(),
+        }
+    }
+
+    /// Build a 200 OK response with a provider-specific body, such as a
+    /// TwiML document that replies to the sender.
+    pub fn ack(content_type: impl Into<String>, body: impl Into<String>) -> Self {
+        Self {
+            status: HttpStatus::Ok,
+            body: body.into(),
+            content_type: content_type.into(),
+            headers: Vec::new(),
         }
     }
 
@@ -315,8 +1343,243 @@ impl WebhookResponse {
             status,
             body: format!(r#"{{"error": "{}"}}"#, message.replace('"', r#"\""#)),
             content_type: "application/json".to_string(),
+            headers: Vec::new(),
+        }
+    }
+
+    /// Build the error response for a failed send, with the status from
+    /// [`SmsError::http_status`] and the error message as the body.
+    pub fn from_sms_error(error: &SmsError) -> Self {
+        Self::error(error.http_status(), &error.to_string())
+    }
+
+    /// Build an RFC 7807 `application/problem+json` error response.
+    ///
+    /// `type` is `about:blank`, so `title` is the status's reason phrase and
+    /// `detail` carries `detail`.
+    pub fn problem(status: HttpStatus, detail: &str) -> Self {
+        let body = serde_json::json!({
+            "type": "about:blank",
+            "title": status.reason(),
+            "status": status.as_u16(),
+            "detail": detail,
+        });
+        Self {
+            status,
+            body: body.to_string(),
+            content_type: "application/problem+json".to_string(),
+            headers: Vec::new(),
         }
     }
+
+    /// Add a response header.
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Look up a response header by name, case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Conversation threading
+// ---------------------------------------------------------------------------
+
+/// Stable identifier for the conversation between one of your numbers and a
+/// remote party.
+///
+/// Both numbers are reduced to their lowercase letters and digits before
+/// hashing, so `"+1 (415) 555-1234"` and `"+14155551234"` land in the same
+/// thread.  The same pair always yields the same 16-hex-digit ID, across
+/// processes and releases, so it is safe to persist.
+pub fn thread_id(account_number: &str, remote_number: &str) -> String {
+    let normalized: String = account_number
+        .chars()
+        .chain(std::iter::once('|'))
+        .chain(remote_number.chars())
+        .filter(|c| c.is_alphanumeric() || *c == '|')
+        .flat_map(char::to_lowercase)
+        .collect();
+    format!("{:016x}", fnv1a(normalized.as_bytes()))
+}
+
+/// `number` in E.164 form, as far as that can be done without knowing the
+/// caller's country: spaces, dashes, dots and parentheses are dropped and an
+/// international `00` prefix becomes `+`.  Short codes and alphanumeric
+/// sender IDs only lose their formatting.
+///
+/// Use it to compare numbers typed by people with numbers reported by
+/// providers: `"+1 (555) 010-0000"` and `"+15550100000"` normalize alike.
+pub fn normalize_number(number: &str) -> String {
+    let stripped: String = number
+        .chars()
+        .filter(|c| !c.is_whitespace() && !matches!(c, '-' | '.' | '(' | ')'))
+        .collect();
+    match stripped.strip_prefix("00") {
+        Some(rest) if !rest.is_empty() && rest.bytes().all(|b| b.is_ascii_digit()) => {
+            format!("+{}", rest)
+        }
+        _ => stripped,
+    }
+}
+
+/// 64-bit FNV-1a: tiny, and unlike `DefaultHasher` its output is fixed.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+impl SendRequest<'_> {
+    /// The conversation this request belongs to: `from` is the account
+    /// number, `to` the remote party.
+    pub fn thread_id(&self) -> String {
+        thread_id(self.from, self.to)
+    }
+}
+
+impl SendResponse {
+    /// Set [`thread_id`](Self::thread_id) from `req` unless already set.
+    pub fn with_thread_id(mut self, req: &SendRequest<'_>) -> Self {
+        if self.thread_id.is_none() {
+            self.thread_id = Some(req.thread_id());
+        }
+        self
+    }
+}
+
+impl InboundMessage {
+    /// Set [`thread_id`](Self::thread_id) unless already set.
+    ///
+    /// For user messages `to` is the account number and `from` the remote
+    /// party; delivery reports describe an outbound message, so the roles are
+    /// swapped and the report joins the thread of the message it is about.
+    pub fn with_thread_id(mut self) -> Self {
+        if self.thread_id.is_none() {
+            let (account, remote) = match self.direction {
+                InboundDirection::Inbound => (&self.to, &self.from),
+                InboundDirection::DeliveryReport => (&self.from, &self.to),
+            };
+            self.thread_id = Some(thread_id(account, remote));
+        }
+        self
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Log redaction
+// ---------------------------------------------------------------------------
+
+/// How phone numbers appear in log output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NumberRedaction {
+    /// Log numbers in full.
+    Full,
+    /// Keep the first three and last two characters, e.g. `+14*******34`.
+    #[default]
+    Mask,
+    /// Replace numbers with `[redacted]`.
+    Omit,
+}
+
+/// How message text appears in log output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TextRedaction {
+    /// Log text in full.
+    Full,
+    /// Replace text with a stable hash, so identical messages can still be
+    /// matched up across log lines.
+    Hash,
+    /// Replace text with `[redacted]`.
+    #[default]
+    Omit,
+}
+
+/// What smskit's provider and webhook code may write to logs.
+///
+/// The process-wide policy is set with [`set_redaction_policy`]; log
+/// statements format numbers through [`redact_number`] and text through
+/// [`redact_text`].  By default numbers are masked and text is omitted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RedactionPolicy {
+    /// Treatment of phone numbers.
+    #[serde(default)]
+    pub numbers: NumberRedaction,
+    /// Treatment of message bodies.
+    #[serde(default)]
+    pub text: TextRedaction,
+}
+
+/// Placeholder logged in place of omitted values.
+const REDACTED: &str = "[redacted]";
+
+impl RedactionPolicy {
+    /// Log everything in full, e.g. in development.
+    pub const FULL: Self = Self {
+        numbers: NumberRedaction::Full,
+        text: TextRedaction::Full,
+    };
+
+    /// `number` as this policy allows it to be logged.
+    pub fn number<'a>(&self, number: &'a str) -> std::borrow::Cow<'a, str> {
+        match self.numbers {
+            NumberRedaction::Full => number.into(),
+            NumberRedaction::Omit => REDACTED.into(),
+            NumberRedaction::Mask => {
+                let len = number.chars().count();
+                let shown = |i: usize| len > 5 && (i < 3 || i + 2 >= len);
+                number
+                    .chars()
+                    .enumerate()
+                    .map(|(i, c)| if shown(i) { c } else { '*' })
+                    .collect::<String>()
+                    .into()
+            }
+        }
+    }
+
+    /// `text` as this policy allows it to be logged.
+    pub fn text<'a>(&self, text: &'a str) -> std::borrow::Cow<'a, str> {
+        match self.text {
+            TextRedaction::Full => text.into(),
+            TextRedaction::Hash => format!("[text {:016x}]", fnv1a(text.as_bytes())).into(),
+            TextRedaction::Omit => REDACTED.into(),
+        }
+    }
+}
+
+static REDACTION_POLICY: std::sync::RwLock<RedactionPolicy> =
+    std::sync::RwLock::new(RedactionPolicy {
+        numbers: NumberRedaction::Mask,
+        text: TextRedaction::Omit,
+    });
+
+/// Set the process-wide [`RedactionPolicy`].
+pub fn set_redaction_policy(policy: RedactionPolicy) {
+    *REDACTION_POLICY.write().unwrap_or_else(|e| e.into_inner()) = policy;
+}
+
+/// The process-wide [`RedactionPolicy`].
+pub fn redaction_policy() -> RedactionPolicy {
+    *REDACTION_POLICY.read().unwrap_or_else(|e| e.into_inner())
+}
+
+/// `number` as the process-wide policy allows it to be logged.
+pub fn redact_number(number: &str) -> std::borrow::Cow<'_, str> {
+    redaction_policy().number(number)
+}
+
+/// `text` as the process-wide policy allows it to be logged.
+pub fn redact_text(text: &str) -> std::borrow::Cow<'_, str> {
+    redaction_policy().text(text)
 }
 
 // ---------------------------------------------------------------------------
@@ -326,29 +1589,183 @@ impl WebhookResponse {
 /// The primary trait for sending SMS messages.
 ///
 /// Every provider crate (`sms-plivo`, `sms-aws-sns`, `sms-twilio`) implements
-/// this trait.  Because the trait is **object-safe** (`Send + Sync`, no
-/// associated types), you can use `Box<dyn SmsClient>` or
-/// `Arc<dyn SmsClient>` for dynamic dispatch — which is exactly what
-/// [`SmsRouter`] and [`FallbackClient`] do under the hood.
+/// this trait.  It uses native `async fn`, so calls through a concrete client
+/// don't box their future.  For dynamic dispatch use [`DynSmsClient`]:
+/// every `SmsClient` is one, and `dyn DynSmsClient` is itself an
+/// `SmsClient`, so `Arc<dyn DynSmsClient>` works wherever a client is
+/// expected — which is exactly what [`SmsRouter`] and [`FallbackClient`] do
+/// under the hood.
+///
+/// Implementations write the method as a plain `async fn`; the returned
+/// future must be `Send`.
 ///
 /// # Example
 ///
 /// ```rust,ignore
 /// use sms_core::{SmsClient, SendRequest};
 ///
-/// async fn send_otp(client: &dyn SmsClient) -> Result<String, sms_core::SmsError> {
+/// async fn send_otp(client: &impl SmsClient) -> Result<String, sms_core::SmsError> {
 ///     let resp = client.send(SendRequest {
 ///         to: "+14155551234",
 ///         from: "+10005551234",
 ///         text: "Your code is 123456",
+///         ..Default::default()
 ///     }).await?;
 ///     Ok(resp.id)
 /// }
 /// ```
-#[async_trait]
 pub trait SmsClient: Send + Sync {
     /// Send a single text SMS and return the provider's response.
-    async fn send(&self, req: SendRequest<'_>) -> Result<SendResponse, SmsError>;
+    fn send(
+        &self,
+        req: SendRequest<'_>,
+    ) -> impl Future<Output = Result<SendResponse, SmsError>> + Send;
+
+    /// Returns `true` if this client can send over `channel`.
+    ///
+    /// The default supports [`Channel::Sms`] only.
+    fn supports(&self, channel: Channel) -> bool {
+        channel == Channel::Sms
+    }
+
+    /// Longest message body, in characters, the provider accepts.
+    ///
+    /// `None` (the default) means no known limit.  Checked by
+    /// [`validate_text`] before a send.
+    fn max_text_len(&self) -> Option<usize> {
+        None
+    }
+}
+
+/// A boxed, `Send` future, as returned by [`DynSmsClient::send_dyn`].
+pub type BoxFuture<'a, T> = std::pin::Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Object-safe form of [`SmsClient`], for `Arc<dyn DynSmsClient>` and
+/// `Box<dyn DynSmsClient>`.
+///
+/// Implemented for every `SmsClient`; don't implement it directly.  The
+/// methods are suffixed `_dyn` so they never clash with the `SmsClient`
+/// ones — call `send`, `supports` and `max_text_len` as usual, since
+/// `dyn DynSmsClient` implements `SmsClient` too.  Only calls through the
+/// trait object box their future.
+pub trait DynSmsClient: Send + Sync {
+    /// [`SmsClient::send`], with the future boxed.
+    fn send_dyn<'a>(
+        &'a self,
+        req: SendRequest<'a>,
+    ) -> BoxFuture<'a, Result<SendResponse, SmsError>>;
+
+    /// [`SmsClient::supports`].
+    fn supports_dyn(&self, channel: Channel) -> bool;
+
+    /// [`SmsClient::max_text_len`].
+    fn max_text_len_dyn(&self) -> Option<usize>;
+}
+
+impl<T: SmsClient> DynSmsClient for T {
+    fn send_dyn<'a>(
+        &'a self,
+        req: SendRequest<'a>,
+    ) -> BoxFuture<'a, Result<SendResponse, SmsError>> {
+        Box::pin(self.send(req))
+    }
+
+    fn supports_dyn(&self, channel: Channel) -> bool {
+        self.supports(channel)
+    }
+
+    fn max_text_len_dyn(&self) -> Option<usize> {
+        self.max_text_len()
+    }
+}
+
+impl SmsClient for dyn DynSmsClient + '_ {
+    async fn send(&self, req: SendRequest<'_>) -> Result<SendResponse, SmsError> {
+        self.send_dyn(req).await
+    }
+
+    fn supports(&self, channel: Channel) -> bool {
+        self.supports_dyn(channel)
+    }
+
+    fn max_text_len(&self) -> Option<usize> {
+        self.max_text_len_dyn()
+    }
+}
+
+impl<T: SmsClient + ?Sized> SmsClient for Arc<T> {
+    async fn send(&self, req: SendRequest<'_>) -> Result<SendResponse, SmsError> {
+        (**self).send(req).await
+    }
+
+    fn supports(&self, channel: Channel) -> bool {
+        (**self).supports(channel)
+    }
+
+    fn max_text_len(&self) -> Option<usize> {
+        (**self).max_text_len()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Content validation
+// ---------------------------------------------------------------------------
+
+/// Reject message text no provider would deliver, before spending an API
+/// call on it.
+///
+/// Fails with [`SmsError::Invalid`] if `text` is empty or whitespace, longer
+/// than `max_len` characters, contains a control character other than
+/// `\n`, `\r` or `\t`, or contains U+FFFD — the replacement character left
+/// behind when invalid UTF-16 (such as a lone surrogate) was decoded lossily.
+pub fn validate_text(text: &str, max_len: Option<usize>) -> Result<(), SmsError> {
+    if text.trim().is_empty() {
+        return Err(SmsError::Invalid("message text is empty".into()));
+    }
+    if let Some(max) = max_len {
+        let len = text.chars().count();
+        if len > max {
+            return Err(SmsError::Invalid(format!(
+                "message text is {} characters; the limit is {}",
+                len, max
+            )));
+        }
+    }
+    if let Some(c) = text
+        .chars()
+        .find(|c| c.is_control() && !matches!(c, '\n' | '\r' | '\t'))
+    {
+        return Err(SmsError::Invalid(format!(
+            "message text contains control character U+{:04X}",
+            c as u32
+        )));
+    }
+    if text.contains(char::REPLACEMENT_CHARACTER) {
+        return Err(SmsError::Invalid(
+            "message text contains U+FFFD; was it decoded from invalid UTF-16?".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Decode UTF-16 message text, rejecting unpaired surrogates.
+///
+/// Use this instead of `String::from_utf16_lossy` for text coming from
+/// UTF-16 sources (JavaScript, Windows APIs), so a broken emoji is an error
+/// rather than a silently sent U+FFFD.
+pub fn text_from_utf16(units: &[u16]) -> Result<String, SmsError> {
+    char::decode_utf16(units.iter().copied())
+        .enumerate()
+        .map(|(i, c)| {
+            c.map_err(|e| {
+                SmsError::Invalid(format!(
+                    "unpaired UTF-16 surrogate 0x{:04X} at character {}",
+                    e.unpaired_surrogate(),
+                    i
+                ))
+            })
+        })
+        .collect()
 }
 
 // ---------------------------------------------------------------------------
@@ -357,8 +1774,115 @@ pub trait SmsClient: Send + Sync {
 
 /// Generate a random UUID v4 string, useful as a fallback message ID when the
 /// provider does not return one.
+///
+/// Equivalent to [`UuidV4::generate`]; clients that need time-ordered IDs take
+/// an [`IdGenerator`] instead.
 pub fn fallback_id() -> String {
-    Uuid::new_v4().to_string()
+    UuidV4.generate()
+}
+
+/// Produces locally assigned message IDs.
+///
+/// Used wherever smskit has to invent an ID (a provider response without one,
+/// a queued message).  Pick [`UuidV7`], [`Ulid`] or [`Snowflake`] when stored
+/// messages should sort by creation time.
+pub trait IdGenerator: Send + Sync + std::fmt::Debug {
+    /// Return a new unique ID.
+    fn generate(&self) -> String;
+}
+
+/// Random UUID v4 IDs (the default).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UuidV4;
+
+impl IdGenerator for UuidV4 {
+    fn generate(&self) -> String {
+        Uuid::new_v4().to_string()
+    }
+}
+
+/// Time-ordered UUID v7 IDs.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UuidV7;
+
+impl IdGenerator for UuidV7 {
+    fn generate(&self) -> String {
+        Uuid::now_v7().to_string()
+    }
+}
+
+/// Crockford base32 alphabet used by ULIDs.
+const CROCKFORD: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// 26-character ULIDs: a millisecond timestamp followed by 80 random bits.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Ulid;
+
+impl IdGenerator for Ulid {
+    fn generate(&self) -> String {
+        let random = u128::from_be_bytes(*Uuid::new_v4().as_bytes()) & ((1 << 80) - 1);
+        let value = ((unix_millis() as u128) << 80) | random;
+        (0..26)
+            .map(|i| CROCKFORD[((value >> (125 - 5 * i)) & 0x1f) as usize] as char)
+            .collect()
+    }
+}
+
+/// Twitter-style 64-bit snowflake IDs: 41 bits of milliseconds since
+/// 2020-01-01, a 10-bit worker ID and a 12-bit per-millisecond sequence.
+///
+/// Give each process a distinct `worker_id` to keep IDs unique across a fleet.
+#[derive(Debug)]
+pub struct Snowflake {
+    worker_id: u64,
+    state: std::sync::Mutex<(u64, u64)>,
+}
+
+impl Snowflake {
+    /// Custom epoch: 2020-01-01T00:00:00Z in Unix milliseconds.
+    const EPOCH_MS: u64 = 1_577_836_800_000;
+
+    /// Create a generator for `worker_id` (only the low 10 bits are used).
+    pub fn new(worker_id: u16) -> Self {
+        Self {
+            worker_id: u64::from(worker_id) & 0x3ff,
+            state: std::sync::Mutex::new((0, 0)),
+        }
+    }
+
+    /// Next raw 64-bit ID.
+    pub fn next_id(&self) -> u64 {
+        let mut state = self.state.lock().expect("snowflake state poisoned");
+        let (last_ms, seq) = &mut *state;
+        let mut now = unix_millis().saturating_sub(Self::EPOCH_MS).max(*last_ms);
+        if now == *last_ms {
+            *seq = (*seq + 1) & 0xfff;
+            if *seq == 0 {
+                // Sequence exhausted for this millisecond; wait for the next.
+                while now <= *last_ms {
+                    std::hint::spin_loop();
+                    now = unix_millis().saturating_sub(Self::EPOCH_MS);
+                }
+            }
+        } else {
+            *seq = 0;
+        }
+        *last_ms = now;
+        (now << 22) | (self.worker_id << 12) | *seq
+    }
+}
+
+impl IdGenerator for Snowflake {
+    fn generate(&self) -> String {
+        self.next_id().to_string()
+    }
+}
+
+fn unix_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
 }
 
 /// Lightweight header representation (`Vec<(name, value)>`) that avoids
@@ -374,7 +1898,6 @@ pub type Headers = Vec<(String, String)>;
 /// Each provider crate implements this trait on its client type, enabling the
 /// unified [`InboundRegistry`] and `WebhookProcessor` to handle any provider
 /// without compile-time knowledge of which ones are in use.
-#[async_trait]
 pub trait InboundWebhook: Send + Sync {
     /// A stable, lowercase identifier for this provider (e.g. `"plivo"`,
     /// `"twilio"`, `"aws-sns"`).  Used as the lookup key in
@@ -385,6 +1908,19 @@ pub trait InboundWebhook: Send + Sync {
     /// [`InboundMessage`].
     fn parse_inbound(&self, headers: &Headers, body: &[u8]) -> Result<InboundMessage, SmsError>;
 
+    /// Parse a payload that may carry several events, e.g. an SQS batch of
+    /// SNS notifications or an aggregator's batched delivery callback.
+    ///
+    /// The default treats the body as a single event via
+    /// [`parse_inbound`](Self::parse_inbound).
+    fn parse_inbound_batch(
+        &self,
+        headers: &Headers,
+        body: &[u8],
+    ) -> Result<Vec<InboundMessage>, SmsError> {
+        Ok(vec![self.parse_inbound(headers, body)?])
+    }
+
     /// Verify the cryptographic signature on the incoming request.
     ///
     /// The default implementation is a no-op (always succeeds).  Providers
@@ -394,6 +1930,54 @@ pub trait InboundWebhook: Send + Sync {
     }
 }
 
+// ---------------------------------------------------------------------------
+// Inbound polling trait
+// ---------------------------------------------------------------------------
+
+/// Pull-based source of inbound messages, for gateways with no webhooks
+/// (GSM modems, Gammu, some aggregators).
+///
+/// A polling runner calls [`poll`](Self::poll) periodically and feeds the
+/// results into the same dispatch pipeline as webhook-sourced messages.
+#[async_trait]
+pub trait InboundPoller: Send + Sync {
+    /// A stable, lowercase identifier for this gateway.
+    fn provider(&self) -> &'static str;
+
+    /// Fetch messages received since the previous call.
+    ///
+    /// Implementations track their own cursor (last seen ID, deleting read
+    /// messages from modem storage, ...) so each message is returned once.
+    async fn poll(&self) -> Result<Vec<InboundMessage>, SmsError>;
+}
+
+// ---------------------------------------------------------------------------
+// Account balance
+// ---------------------------------------------------------------------------
+
+/// Remaining prepaid credit on a provider account.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Balance {
+    /// Remaining credit, in `currency`.
+    pub amount: f64,
+    /// ISO 4217 currency code, e.g. `"USD"`.
+    pub currency: String,
+}
+
+impl std::fmt::Display for Balance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:.2} {}", self.amount, self.currency)
+    }
+}
+
+/// Providers that can report their account's remaining credit, so a
+/// monitor can warn before sends start failing for lack of funds.
+#[async_trait]
+pub trait BalanceSource: Send + Sync {
+    /// Fetch the current balance from the provider.
+    async fn balance(&self) -> Result<Balance, SmsError>;
+}
+
 // ---------------------------------------------------------------------------
 // InboundRegistry
 // ---------------------------------------------------------------------------
@@ -414,6 +1998,7 @@ pub trait InboundWebhook: Send + Sync {
 /// let registry = InboundRegistry::new()
 ///     .with(Arc::new(plivo_client))
 ///     .with(Arc::new(sns_client));
+/// // or: let registry = sms_core::registry![plivo_client, sns_client];
 ///
 /// // Later, in a request handler:
 /// if let Some(hook) = registry.get("plivo") {
@@ -435,17 +2020,121 @@ impl InboundRegistry {
 
     /// Register a provider.  The provider's [`InboundWebhook::provider()`]
     /// return value is used as the lookup key.
+    ///
+    /// The map is copied only if this registry has been cloned since the
+    /// last insert, so chaining `with` calls is linear in the number of
+    /// providers.
     pub fn with(mut self, hook: Arc<dyn InboundWebhook>) -> Self {
-        let mut m = (*self.map).clone();
-        m.insert(hook.provider(), hook);
-        self.map = Arc::new(m);
+        self.insert(hook);
         self
     }
 
+    /// Register a provider in place; see [`with`](Self::with).
+    pub fn insert(&mut self, hook: Arc<dyn InboundWebhook>) {
+        Arc::make_mut(&mut self.map).insert(hook.provider(), hook);
+    }
+
     /// Look up a registered provider by name.
     pub fn get(&self, provider: &str) -> Option<Arc<dyn InboundWebhook>> {
         self.map.get(provider).cloned()
     }
+
+    /// Names of the registered providers, in no particular order.
+    pub fn providers(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.map.keys().copied()
+    }
+
+    /// Iterate over the registered providers, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = &Arc<dyn InboundWebhook>> {
+        self.map.values()
+    }
+
+    /// Number of registered providers.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if no providers are registered.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
+
+impl FromIterator<Arc<dyn InboundWebhook>> for InboundRegistry {
+    fn from_iter<I: IntoIterator<Item = Arc<dyn InboundWebhook>>>(iter: I) -> Self {
+        let mut registry = Self::new();
+        registry.extend(iter);
+        registry
+    }
+}
+
+impl Extend<Arc<dyn InboundWebhook>> for InboundRegistry {
+    fn extend<I: IntoIterator<Item = Arc<dyn InboundWebhook>>>(&mut self, iter: I) {
+        let map = Arc::make_mut(&mut self.map);
+        for hook in iter {
+            map.insert(hook.provider(), hook);
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// TenantRegistry
+// ---------------------------------------------------------------------------
+
+/// Maps tenant names to their own [`InboundRegistry`].
+///
+/// Lets a single deployment serve many customers from
+/// `/webhooks/{tenant}/{provider}`: each tenant's registry holds provider
+/// clients built with that tenant's credentials and signing secrets, so
+/// verification for one customer never uses another customer's keys.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use sms_core::{InboundRegistry, TenantRegistry};
+///
+/// let tenants = TenantRegistry::new()
+///     .with("acme", InboundRegistry::new().with(Arc::new(acme_twilio)))
+///     .with("globex", InboundRegistry::new().with(Arc::new(globex_plivo)));
+///
+/// let hook = tenants.get("acme").and_then(|r| r.get("twilio"));
+/// ```
+#[derive(Default, Clone)]
+pub struct TenantRegistry {
+    map: Arc<HashMap<String, InboundRegistry>>,
+}
+
+impl TenantRegistry {
+    /// Create an empty tenant registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register (or replace) a tenant's provider registry.
+    pub fn with(mut self, tenant: impl Into<String>, registry: InboundRegistry) -> Self {
+        Arc::make_mut(&mut self.map).insert(tenant.into(), registry);
+        self
+    }
+
+    /// Look up a tenant's provider registry.
+    pub fn get(&self, tenant: &str) -> Option<&InboundRegistry> {
+        self.map.get(tenant)
+    }
+
+    /// Iterate over the registered tenant names.
+    pub fn tenants(&self) -> impl Iterator<Item = &str> {
+        self.map.keys().map(String::as_str)
+    }
+
+    /// Number of registered tenants.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if no tenants are registered.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -472,6 +2161,7 @@ impl InboundRegistry {
 ///     .with("plivo", plivo_client)
 ///     .with("aws-sns", sns_client)
 ///     .default_provider("plivo");
+/// // or: let router = sms_core::senders!["plivo" => plivo_client, "aws-sns" => sns_client];
 ///
 /// // Explicit dispatch:
 /// router.send_via("aws-sns", SendRequest { .. }).await?;
@@ -481,7 +2171,7 @@ impl InboundRegistry {
 /// ```
 #[derive(Clone)]
 pub struct SmsRouter {
-    providers: Arc<HashMap<String, Arc<dyn SmsClient>>>,
+    providers: Arc<HashMap<String, Arc<dyn DynSmsClient>>>,
     default: Option<String>,
 }
 
@@ -500,10 +2190,9 @@ impl SmsRouter {
     /// default (override with [`default_provider`](SmsRouter::default_provider)).
     pub fn with(mut self, name: impl Into<String>, client: impl SmsClient + 'static) -> Self {
         let name = name.into();
-        let mut m = (*self.providers).clone();
+        let m = Arc::make_mut(&mut self.providers);
         let first = m.is_empty();
         m.insert(name.clone(), Arc::new(client));
-        self.providers = Arc::new(m);
         if first {
             self.default = Some(name);
         }
@@ -511,12 +2200,11 @@ impl SmsRouter {
     }
 
     /// Register a provider that is already behind an `Arc`.
-    pub fn with_arc(mut self, name: impl Into<String>, client: Arc<dyn SmsClient>) -> Self {
+    pub fn with_arc(mut self, name: impl Into<String>, client: Arc<dyn DynSmsClient>) -> Self {
         let name = name.into();
-        let mut m = (*self.providers).clone();
+        let m = Arc::make_mut(&mut self.providers);
         let first = m.is_empty();
         m.insert(name.clone(), client);
-        self.providers = Arc::new(m);
         if first {
             self.default = Some(name);
         }
@@ -536,11 +2224,33 @@ impl SmsRouter {
         provider: &str,
         req: SendRequest<'_>,
     ) -> Result<SendResponse, SmsError> {
+        self.check(provider, &req)?;
+        let client = &self.providers[provider];
+        let thread = req.thread_id();
+        let mut resp = client.send(req).await?;
+        resp.thread_id.get_or_insert(thread);
+        Ok(resp)
+    }
+
+    /// Check that `provider` exists and would accept `req`, without
+    /// sending: the channel must be supported and the text valid for it.
+    pub fn check(&self, provider: &str, req: &SendRequest<'_>) -> Result<(), SmsError> {
         let client = self
             .providers
             .get(provider)
             .ok_or_else(|| SmsError::Invalid(format!("unknown provider: {}", provider)))?;
-        client.send(req).await
+        if !client.supports(req.channel) {
+            return Err(SmsError::Invalid(format!(
+                "provider {} does not support channel {}",
+                provider, req.channel
+            )));
+        }
+        validate_text(req.text, client.max_text_len())
+    }
+
+    /// Names of the registered providers, in no particular order.
+    pub fn providers(&self) -> impl Iterator<Item = &str> {
+        self.providers.keys().map(String::as_str)
     }
 
     /// Returns `true` if a provider with the given name is registered.
@@ -554,24 +2264,754 @@ impl SmsRouter {
     }
 }
 
-impl Default for SmsRouter {
-    fn default() -> Self {
-        Self::new()
-    }
-}
+impl Default for SmsRouter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SmsClient for SmsRouter {
+    /// Send through the default provider.
+    ///
+    /// Returns [`SmsError::Invalid`] if no default has been set.
+    async fn send(&self, req: SendRequest<'_>) -> Result<SendResponse, SmsError> {
+        let name = self
+            .default
+            .as_deref()
+            .ok_or_else(|| SmsError::Invalid("no default provider configured".into()))?;
+        self.send_via(name, req).await
+    }
+
+    /// Whether the default provider supports `channel`.
+    fn supports(&self, channel: Channel) -> bool {
+        self.default
+            .as_deref()
+            .and_then(|name| self.providers.get(name))
+            .is_some_and(|client| client.supports(channel))
+    }
+
+    /// The default provider's limit.
+    fn max_text_len(&self) -> Option<usize> {
+        self.default
+            .as_deref()
+            .and_then(|name| self.providers.get(name))
+            .and_then(|client| client.max_text_len())
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Construction macros
+// ---------------------------------------------------------------------------
+
+/// Build an [`InboundRegistry`] from a list of webhook providers.
+///
+/// Each client is wrapped in an `Arc` and registered under its
+/// [`InboundWebhook::provider()`] name.
+///
+/// ```rust,ignore
+/// let registry = sms_core::registry![plivo_client, sns_client];
+/// // same as
+/// let registry = InboundRegistry::new()
+///     .with(Arc::new(plivo_client))
+///     .with(Arc::new(sns_client));
+/// ```
+#[macro_export]
+macro_rules! registry {
+    ($($hook:expr),* $(,)?) => {
+        $crate::InboundRegistry::new()$(.with(::std::sync::Arc::new($hook)))*
+    };
+}
+
+/// Build an [`SmsRouter`] from `name => client` pairs.
+///
+/// The first pair becomes the default provider, as with
+/// [`SmsRouter::with`].
+///
+/// ```rust,ignore
+/// let router = sms_core::senders!["plivo" => plivo_client, "aws-sns" => sns_client];
+/// // same as
+/// let router = SmsRouter::new()
+///     .with("plivo", plivo_client)
+///     .with("aws-sns", sns_client);
+/// ```
+#[macro_export]
+macro_rules! senders {
+    ($($name:expr => $client:expr),* $(,)?) => {
+        $crate::SmsRouter::new()$(.with($name, $client))*
+    };
+}
+
+// ---------------------------------------------------------------------------
+// Provider plugins — constructors registered at link time
+// ---------------------------------------------------------------------------
+
+#[doc(hidden)]
+pub use inventory as __inventory;
+
+/// A provider built from configuration by a [`ProviderPlugin`]: its sending
+/// side, its webhook side, or both.
+#[derive(Clone, Default)]
+pub struct BuiltProvider {
+    /// The sender, if the provider can send.
+    pub client: Option<Arc<dyn DynSmsClient>>,
+    /// The webhook parser, if the provider receives webhooks.
+    pub webhook: Option<Arc<dyn InboundWebhook>>,
+}
+
+impl BuiltProvider {
+    /// A provider that both sends and receives through one client.
+    pub fn both<T: SmsClient + InboundWebhook + 'static>(provider: T) -> Self {
+        let provider = Arc::new(provider);
+        Self {
+            client: Some(provider.clone()),
+            webhook: Some(provider),
+        }
+    }
+
+    /// A send-only provider.
+    pub fn sender(client: impl SmsClient + 'static) -> Self {
+        Self {
+            client: Some(Arc::new(client)),
+            webhook: None,
+        }
+    }
+
+    /// A receive-only provider.
+    pub fn webhook(hook: impl InboundWebhook + 'static) -> Self {
+        Self {
+            client: None,
+            webhook: Some(Arc::new(hook)),
+        }
+    }
+}
+
+impl std::fmt::Debug for BuiltProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BuiltProvider")
+            .field("client", &self.client.is_some())
+            .field("webhook", &self.webhook.as_ref().map(|h| h.provider()))
+            .finish()
+    }
+}
+
+/// Builds a provider from its configuration section.
+pub type ProviderBuilder = fn(&serde_json::Value) -> Result<BuiltProvider, SmsError>;
+
+/// A provider constructor, keyed by the name of its configuration section.
+///
+/// Provider crates submit one with [`register_provider!`]; applications look
+/// them up with [`ProviderPlugin::find`] instead of matching on provider
+/// names.  Only crates that are linked into the binary are registered, so
+/// an otherwise unused provider crate needs a `use sms_foo as _;`.
+///
+/// ```rust,ignore
+/// let plugin = ProviderPlugin::find("plivo").expect("sms-plivo linked");
+/// let built = plugin.build(&serde_json::json!({
+///     "auth_id": "MA...",
+///     "auth_token": "...",
+/// }))?;
+/// ```
+pub struct ProviderPlugin {
+    name: &'static str,
+    builder: ProviderBuilder,
+}
+
+impl ProviderPlugin {
+    /// Create a plugin entry.  Use [`register_provider!`] rather than calling
+    /// this directly.
+    pub const fn new(name: &'static str, builder: ProviderBuilder) -> Self {
+        Self { name, builder }
+    }
+
+    /// The configuration section name, e.g. `"plivo"` or `"aws_sns"`.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Build the provider from its configuration section.
+    pub fn build(&self, settings: &serde_json::Value) -> Result<BuiltProvider, SmsError> {
+        (self.builder)(settings)
+    }
+
+    /// The plugin registered under `name`, if its crate is linked.
+    pub fn find(name: &str) -> Option<&'static ProviderPlugin> {
+        Self::all().find(|plugin| plugin.name == name)
+    }
+
+    /// Every registered plugin, in no particular order.
+    pub fn all() -> impl Iterator<Item = &'static ProviderPlugin> {
+        inventory::iter::<ProviderPlugin>.into_iter()
+    }
+}
+
+impl std::fmt::Debug for ProviderPlugin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProviderPlugin")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+inventory::collect!(ProviderPlugin);
+
+/// Deserialize a plugin's configuration section, reporting failures as
+/// [`SmsError::Invalid`] naming the section.
+pub fn provider_settings<T: serde::de::DeserializeOwned>(
+    name: &str,
+    settings: &serde_json::Value,
+) -> Result<T, SmsError> {
+    T::deserialize(settings).map_err(|e| SmsError::Invalid(format!("{} settings: {}", name, e)))
+}
+
+/// Register a [`ProviderPlugin`] for a configuration section.
+///
+/// ```rust,ignore
+/// fn build(settings: &serde_json::Value) -> Result<BuiltProvider, SmsError> {
+///     let s: MySettings = sms_core::provider_settings("acme", settings)?;
+///     Ok(BuiltProvider::both(AcmeClient::new(s.api_key)))
+/// }
+///
+/// sms_core::register_provider!("acme", build);
+/// ```
+#[macro_export]
+macro_rules! register_provider {
+    ($name:literal, $builder:path) => {
+        $crate::__inventory::submit! {
+            $crate::ProviderPlugin::new($name, $builder)
+        }
+    };
+}
+
+// ---------------------------------------------------------------------------
+// Declarative HTTP providers
+// ---------------------------------------------------------------------------
+
+/// Runtime support for [`define_http_provider!`](crate::define_http_provider).
+///
+/// Requires the `http-provider` feature, which pulls in `reqwest`.  The items
+/// here are what the generated code calls; most applications only need the
+/// macro itself.
+#[cfg(feature = "http-provider")]
+pub mod http_provider {
+    use crate::{
+        Channel, ExposeSecret, HttpClientConfig, HttpLogger, IdGenerator, InboundDirection,
+        InboundMessage, SendResponse, SmsError, UuidV4,
+    };
+    use serde_json::Value;
+    use std::time::Duration;
+
+    #[doc(hidden)]
+    pub use {reqwest, serde_json};
+
+    /// Default deadline for a single API call.
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+    /// How a request or webhook body is encoded.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum BodyEncoding {
+        /// `application/x-www-form-urlencoded`.
+        Form,
+        /// `application/json`.
+        Json,
+    }
+
+    /// How the send request authenticates.  Deliberately not `Debug`, since
+    /// it holds exposed secrets.
+    #[derive(Clone, Copy)]
+    pub enum Auth<'a> {
+        /// No credentials on the request (e.g. a key in the body).
+        None,
+        /// HTTP Basic auth.
+        Basic(&'a str, &'a str),
+        /// `Authorization: Bearer <token>`.
+        Bearer(&'a str),
+        /// A custom header such as `X-Api-Key`.
+        Header(&'static str, &'a str),
+    }
+
+    /// A send call assembled by the generated [`SmsClient`](crate::SmsClient)
+    /// implementation.
+    pub struct SendCall<'a> {
+        /// Provider name for the [`SendResponse`].
+        pub provider: &'static str,
+        /// Full endpoint URL.
+        pub url: String,
+        /// Request authentication.
+        pub auth: Auth<'a>,
+        /// Body encoding.
+        pub encoding: BodyEncoding,
+        /// Body fields in order.
+        pub fields: Vec<(&'static str, Value)>,
+        /// Dotted path to the message ID in the JSON response, e.g.
+        /// `"messages.0.id"`.
+        pub id_path: &'static str,
+        /// Deadline for the call.
+        pub timeout: Duration,
+        /// Channel the request was sent on, for the [`SendResponse`].
+        pub channel: Channel,
+        /// Conversation ID for the [`SendResponse`].
+        pub thread_id: String,
+        /// Logger to report the exchange to, if any.
+        pub logger: Option<&'a HttpLogger>,
+    }
+
+    /// Perform a send and map the response.
+    ///
+    /// A 429 becomes [`SmsError::RateLimited`], any other non-2xx
+    /// [`SmsError::Provider`].  If the ID is missing from the response a
+    /// UUID is generated.
+    pub async fn send(
+        http: &reqwest::Client,
+        call: SendCall<'_>,
+    ) -> Result<SendResponse, SmsError> {
+        let mut builder = http.post(&call.url).timeout(call.timeout);
+        builder = match call.auth {
+            Auth::None => builder,
+            Auth::Basic(user, password) => builder.basic_auth(user, Some(password)),
+            Auth::Bearer(token) => builder.bearer_auth(token),
+            Auth::Header(name, value) => builder.header(name, value),
+        };
+        builder = match call.encoding {
+            BodyEncoding::Form => {
+                let form: Vec<(&str, String)> = call
+                    .fields
+                    .iter()
+                    .map(|(key, value)| (*key, form_value(value)))
+                    .collect();
+                builder.form(&form)
+            }
+            BodyEncoding::Json => {
+                let body: serde_json::Map<String, Value> = call
+                    .fields
+                    .into_iter()
+                    .map(|(key, value)| (key.to_string(), value))
+                    .collect();
+                builder.json(&body)
+            }
+        };
+        let request = builder.build().map_err(transport_error)?;
+        let pending = call.logger.map(|log| {
+            let body = request.body().and_then(|b| b.as_bytes());
+            let body = body.map(String::from_utf8_lossy);
+            log.start(call.provider, "POST", &call.url, body.as_deref())
+        });
+        let res = match http.execute(request).await {
+            Ok(res) => res,
+            Err(e) => {
+                let err = transport_error(e);
+                if let Some(pending) = pending {
+                    pending.fail(&err);
+                }
+                return Err(err);
+            }
+        };
+
+        let status = res.status();
+        let retry_after = res
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(crate::parse_retry_after);
+        let text = res.text().await;
+        if let Some(pending) = pending {
+            pending.finish(status.as_u16(), text.as_deref().unwrap_or_default());
+        }
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Err(SmsError::RateLimited { retry_after });
+        }
+        if !status.is_success() {
+            let body = text.unwrap_or_default();
+            return Err(SmsError::Provider(format!("HTTP {}: {}", status, body)));
+        }
+
+        let raw_text = text.map_err(transport_error)?;
+        let raw: Value = serde_json::from_str(&raw_text)
+            .unwrap_or_else(|_| serde_json::json!({ "raw": raw_text }));
+        let id = json_path(&raw, call.id_path)
+            .and_then(|v| match v {
+                Value::String(s) => Some(s.clone()),
+                Value::Number(n) => Some(n.to_string()),
+                _ => None,
+            })
+            .unwrap_or_else(|| UuidV4.generate());
+
+        Ok(SendResponse {
+            id,
+            provider: call.provider,
+            channel: call.channel,
+            http_status: Some(status.as_u16()),
+            provider_request_id: None,
+            thread_id: Some(call.thread_id),
+            raw,
+        })
+    }
+
+    /// Build a `reqwest` client with the crate's defaults.
+    pub fn client() -> reqwest::Client {
+        reqwest::Client::new()
+    }
+
+    /// Build a `reqwest` client for a [`HttpClientConfig`].
+    pub fn client_with(config: &HttpClientConfig) -> Result<reqwest::Client, SmsError> {
+        let mut builder = reqwest::Client::builder();
+        if let Some(proxy) = &config.proxy {
+            let mut p = reqwest::Proxy::all(&proxy.url)
+                .map_err(|e| SmsError::Invalid(format!("proxy {}: {}", proxy.url, e)))?;
+            if let (Some(user), Some(pass)) = (&proxy.username, &proxy.password) {
+                p = p.basic_auth(user, pass.expose_secret());
+            }
+            if let Some(hosts) = &proxy.no_proxy {
+                p = p.no_proxy(reqwest::NoProxy::from_string(hosts));
+            }
+            builder = builder.proxy(p);
+        }
+        #[cfg(any(feature = "native-tls", feature = "rustls"))]
+        {
+            for pem in &config.root_certificates {
+                let cert = reqwest::Certificate::from_pem(pem.as_bytes())
+                    .map_err(|e| SmsError::Invalid(format!("root certificate: {}", e)))?;
+                builder = builder.add_root_certificate(cert);
+            }
+            if config.accept_invalid_certs {
+                builder = builder.danger_accept_invalid_certs(true);
+            }
+        }
+        #[cfg(not(any(feature = "native-tls", feature = "rustls")))]
+        if !config.root_certificates.is_empty() || config.accept_invalid_certs {
+            return Err(SmsError::Invalid(
+                "TLS settings need the native-tls or rustls feature".into(),
+            ));
+        }
+        if let Some(user_agent) = &config.user_agent {
+            builder = builder.user_agent(user_agent.as_str());
+        }
+        if !config.headers.is_empty() {
+            let mut headers = reqwest::header::HeaderMap::new();
+            for (name, value) in &config.headers {
+                let header = reqwest::header::HeaderName::from_bytes(name.as_bytes())
+                    .map_err(|e| SmsError::Invalid(format!("header {}: {}", name, e)))?;
+                let value = reqwest::header::HeaderValue::from_str(value)
+                    .map_err(|e| SmsError::Invalid(format!("header {}: {}", name, e)))?;
+                headers.insert(header, value);
+            }
+            builder = builder.default_headers(headers);
+        }
+        builder
+            .build()
+            .map_err(|e| SmsError::Invalid(format!("http client: {}", e)))
+    }
+
+    /// Look up a dotted path such as `"messages.0.id"` in a JSON value.
+    /// Numeric segments index into arrays.
+    pub fn json_path<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+        path.split('.')
+            .filter(|segment| !segment.is_empty())
+            .try_fold(value, |current, segment| match current {
+                Value::Array(items) => items.get(segment.parse::<usize>().ok()?),
+                _ => current.get(segment),
+            })
+    }
+
+    /// Where the generated [`InboundWebhook`](crate::InboundWebhook) finds
+    /// each message field.  Keys are dotted paths for JSON bodies and plain
+    /// field names for form bodies.
+    #[derive(Debug, Clone, Copy)]
+    pub struct InboundFields {
+        /// Sender number.
+        pub from: &'static str,
+        /// Destination number.
+        pub to: &'static str,
+        /// Message body.
+        pub text: &'static str,
+        /// Provider-assigned message ID, if the payload has one.
+        pub id: Option<&'static str>,
+    }
+
+    /// Parse a webhook body according to `fields`.
+    ///
+    /// The whole payload is kept in [`InboundMessage::raw`].
+    pub fn parse_inbound(
+        provider: &'static str,
+        encoding: BodyEncoding,
+        fields: &InboundFields,
+        body: &[u8],
+    ) -> Result<InboundMessage, SmsError> {
+        let raw: Value = match encoding {
+            BodyEncoding::Json => serde_json::from_slice(body)
+                .map_err(|e| SmsError::Invalid(format!("json decode: {}", e)))?,
+            BodyEncoding::Form => {
+                let pairs: Vec<(String, String)> = serde_urlencoded::from_bytes(body)
+                    .map_err(|e| SmsError::Invalid(format!("form decode: {}", e)))?;
+                Value::Object(
+                    pairs
+                        .into_iter()
+                        .map(|(key, value)| (key, Value::String(value)))
+                        .collect(),
+                )
+            }
+        };
+        let field = |path: &str| json_path(&raw, path).map(form_value);
+        let required = |path: &'static str| {
+            field(path).ok_or_else(|| SmsError::Invalid(format!("missing field: {}", path)))
+        };
+        Ok(InboundMessage {
+            id: fields.id.and_then(field),
+            from: required(fields.from)?,
+            to: required(fields.to)?,
+            text: required(fields.text)?,
+            timestamp: None,
+            provider,
+            direction: InboundDirection::Inbound,
+            channel: Channel::Sms,
+            provider_account: None,
+            thread_id: None,
+            flag: None,
+            raw,
+        }
+        .with_thread_id())
+    }
+
+    /// Strings as-is, everything else in its JSON form.
+    fn form_value(value: &Value) -> String {
+        match value {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        }
+    }
+
+    /// Map a reqwest failure to [`SmsError::Timeout`] or [`SmsError::Http`].
+    pub fn transport_error(e: reqwest::Error) -> SmsError {
+        if e.is_timeout() {
+            SmsError::Timeout(e.to_string())
+        } else {
+            SmsError::Http(e.to_string())
+        }
+    }
+}
+
+/// Generate an [`SmsClient`] (and optionally an [`InboundWebhook`]) for a
+/// simple HTTP provider from a declaration.
+///
+/// Requires the `http-provider` feature.  The generated struct holds each
+/// credential as a [`SecretString`], redacts them in `Debug`, and gets
+/// `new`, `from_env`, `with_base_url`, `with_timeout`, `with_http_config`
+/// and `with_http_logger`.  Credentials are
+/// in scope as `&str` locals inside `path` and the request fields.
+///
+/// `auth` is one of `none`, `basic(user, password)`, `bearer(token)` or
+/// `header("X-Api-Key", key)`.  Bodies are `form` or `json`.  `max_text_len`
+/// and `inbound` are optional.
+///
+/// ```rust,ignore
+/// sms_core::define_http_provider! {
+///     /// Textbelt REST client.
+///     pub struct TextbeltClient {
+///         provider: "textbelt",
+///         base_url: "https://textbelt.com",
+///         path: "/text",
+///         credentials: { api_key: "TEXTBELT_API_KEY" },
+///         auth: none,
+///         request(req): form {
+///             "phone" => req.to,
+///             "message" => req.text,
+///             "key" => api_key,
+///         },
+///         response_id: "textId",
+///         max_text_len: 160,
+///         inbound: json { from: "fromNumber", to: "toNumber", text: "text", id: "textId" },
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! define_http_provider {
+    (@auth $self:ident; none) => {
+        $crate::http_provider::Auth::None
+    };
+    (@auth $self:ident; basic($user:ident, $password:ident)) => {
+        $crate::http_provider::Auth::Basic(
+            $self.$user.expose_secret(),
+            $self.$password.expose_secret(),
+        )
+    };
+    (@auth $self:ident; bearer($token:ident)) => {
+        $crate::http_provider::Auth::Bearer($self.$token.expose_secret())
+    };
+    (@auth $self:ident; header($header:literal, $value:ident)) => {
+        $crate::http_provider::Auth::Header($header, $self.$value.expose_secret())
+    };
+    (@encoding form) => {
+        $crate::http_provider::BodyEncoding::Form
+    };
+    (@encoding json) => {
+        $crate::http_provider::BodyEncoding::Json
+    };
+    (@option) => {
+        None
+    };
+    (@option $value:expr) => {
+        Some($value)
+    };
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            provider: $provider:literal,
+            base_url: $base_url:literal,
+            path: $path:literal,
+            credentials: { $($cred:ident : $env:literal),+ $(,)? },
+            auth: $auth:ident $(($($auth_arg:tt)*))?,
+            request($req:ident): $encoding:ident { $($key:literal => $value:expr),* $(,)? },
+            response_id: $id_path:literal,
+            $(max_text_len: $max_len:expr,)?
+            $(inbound: $in_encoding:ident {
+                from: $in_from:literal,
+                to: $in_to:literal,
+                text: $in_text:literal
+                $(, id: $in_id:literal)?
+                $(,)?
+            },)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Clone)]
+        $vis struct $name {
+            $(
+                #[doc = concat!("Credential read from `", $env, "` by `from_env`.")]
+                pub $cred: $crate::SecretString,
+            )+
+            /// API base URL; override with `with_base_url` for testing.
+            pub base_url: ::std::string::String,
+            /// Deadline for each API call unless the request sets its own.
+            pub timeout: ::std::time::Duration,
+            http_log: ::std::option::Option<$crate::HttpLogger>,
+            http: $crate::http_provider::reqwest::Client,
+        }
+
+        impl ::std::fmt::Debug for $name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                f.debug_struct(stringify!($name))
+                    $(.field(stringify!($cred), &"[REDACTED]"))+
+                    .field("base_url", &self.base_url)
+                    .field("timeout", &self.timeout)
+                    .finish_non_exhaustive()
+            }
+        }
+
+        impl $name {
+            /// Create a client with explicit credentials against the
+            /// production API.
+            pub fn new($($cred: impl Into<::std::string::String>),+) -> Self {
+                Self {
+                    $($cred: $crate::SecretString::from($cred.into()),)+
+                    base_url: $base_url.to_string(),
+                    timeout: $crate::http_provider::DEFAULT_TIMEOUT,
+                    http_log: None,
+                    http: $crate::http_provider::client(),
+                }
+            }
+
+            /// Create a client from environment variables.
+            ///
+            /// Returns [`SmsError::Auth`]($crate::SmsError::Auth) if any is
+            /// missing.
+            pub fn from_env() -> Result<Self, $crate::SmsError> {
+                Ok(Self::new($(
+                    ::std::env::var($env).map_err(|_| {
+                        $crate::SmsError::Auth(concat!($env, " not set").into())
+                    })?
+                ),+))
+            }
+
+            /// Point the client at another base URL, e.g. a mock server.
+            pub fn with_base_url(mut self, base_url: impl Into<::std::string::String>) -> Self {
+                self.base_url = base_url.into();
+                self
+            }
+
+            /// Set the default deadline for each API call (default: 30
+            /// seconds).
+            pub fn with_timeout(mut self, timeout: ::std::time::Duration) -> Self {
+                self.timeout = timeout;
+                self
+            }
+
+            /// Rebuild the HTTP client with custom transport settings
+            /// (proxy, `User-Agent`, extra headers, etc.).
+            pub fn with_http_config(
+                mut self,
+                config: $crate::HttpClientConfig,
+            ) -> Result<Self, $crate::SmsError> {
+                self.http = $crate::http_provider::client_with(&config)?;
+                Ok(self)
+            }
+
+            /// Report every API call to `logger`.
+            pub fn with_http_logger(mut self, logger: $crate::HttpLogger) -> Self {
+                self.http_log = Some(logger);
+                self
+            }
+        }
+
+        impl $crate::SmsClient for $name {
+            async fn send(
+                &self,
+                $req: $crate::SendRequest<'_>,
+            ) -> Result<$crate::SendResponse, $crate::SmsError> {
+                use $crate::ExposeSecret as _;
+                $crate::validate_text($req.text, $crate::SmsClient::max_text_len(self))?;
+                $(
+                    #[allow(unused_variables)]
+                    let $cred: &str = self.$cred.expose_secret();
+                )+
+                let call = $crate::http_provider::SendCall {
+                    provider: $provider,
+                    url: format!("{}{}", self.base_url.trim_end_matches('/'), format_args!($path)),
+                    auth: $crate::define_http_provider!(@auth self; $auth $(($($auth_arg)*))?),
+                    encoding: $crate::define_http_provider!(@encoding $encoding),
+                    fields: vec![$((
+                        $key,
+                        $crate::http_provider::serde_json::to_value(&$value).unwrap_or_default(),
+                    )),*],
+                    id_path: $id_path,
+                    timeout: $req.timeout.unwrap_or(self.timeout),
+                    channel: $req.channel,
+                    thread_id: $req.thread_id(),
+                    logger: self.http_log.as_ref(),
+                };
+                $crate::http_provider::send(&self.http, call).await
+            }
+
+            fn max_text_len(&self) -> Option<usize> {
+                $crate::define_http_provider!(@option $($max_len)?)
+            }
+        }
 
-#[async_trait]
-impl SmsClient for SmsRouter {
-    /// Send through the default provider.
-    ///
-    /// Returns [`SmsError::Invalid`] if no default has been set.
-    async fn send(&self, req: SendRequest<'_>) -> Result<SendResponse, SmsError> {
-        let name = self
-            .default
-            .as_deref()
-            .ok_or_else(|| SmsError::Invalid("no default provider configured".into()))?;
-        self.send_via(name, req).await
-    }
+        $(
+            impl $crate::InboundWebhook for $name {
+                fn provider(&self) -> &'static str {
+                    $provider
+                }
+
+                fn parse_inbound(
+                    &self,
+                    _headers: &$crate::Headers,
+                    body: &[u8],
+                ) -> Result<$crate::InboundMessage, $crate::SmsError> {
+                    let fields = $crate::http_provider::InboundFields {
+                        from: $in_from,
+                        to: $in_to,
+                        text: $in_text,
+                        id: $crate::define_http_provider!(@option $($in_id)?),
+                    };
+                    $crate::http_provider::parse_inbound(
+                        $provider,
+                        $crate::define_http_provider!(@encoding $in_encoding),
+                        &fields,
+                        body,
+                    )
+                }
+            }
+        )?
+    };
 }
 
 // ---------------------------------------------------------------------------
@@ -602,7 +3042,7 @@ impl SmsClient for SmsRouter {
 /// let response = client.send(SendRequest { .. }).await?;
 /// ```
 pub struct FallbackClient {
-    providers: Vec<Arc<dyn SmsClient>>,
+    providers: Vec<Arc<dyn DynSmsClient>>,
 }
 
 impl FallbackClient {
@@ -610,13 +3050,13 @@ impl FallbackClient {
     ///
     /// Providers are tried in the order given.  The list must contain at
     /// least one provider.
-    pub fn new(providers: Vec<Arc<dyn SmsClient>>) -> Self {
+    pub fn new(providers: Vec<Arc<dyn DynSmsClient>>) -> Self {
         assert!(!providers.is_empty(), "FallbackClient requires at least one provider");
         Self { providers }
     }
 
     /// Convenience builder that wraps each client in an `Arc` for you.
-    pub fn from_clients(clients: Vec<Box<dyn SmsClient>>) -> Self {
+    pub fn from_clients(clients: Vec<Box<dyn DynSmsClient>>) -> Self {
         let providers = clients.into_iter().map(Arc::from).collect();
         Self { providers }
     }
@@ -632,28 +3072,197 @@ impl FallbackClient {
     }
 }
 
-#[async_trait]
 impl SmsClient for FallbackClient {
-    /// Try each provider in order.  Returns the first success or, if all
-    /// fail, an error summarizing every failure.
+    /// Try each provider that supports the request's channel in order.
+    /// Returns the first success or, if all fail, an error summarizing every
+    /// failure: [`SmsError::Http`] if every failure was
+    /// [transient](SmsError::is_transient), so callers can still tell that
+    /// retrying later may help, and [`SmsError::Provider`] otherwise.
     async fn send(&self, req: SendRequest<'_>) -> Result<SendResponse, SmsError> {
         let mut errors: Vec<String> = Vec::new();
+        let mut transient = true;
+
+        if !self.supports(req.channel) {
+            return Err(SmsError::Invalid(format!(
+                "no provider supports channel {}",
+                req.channel
+            )));
+        }
 
-        for provider in &self.providers {
+        for provider in self.providers.iter().filter(|p| p.supports(req.channel)) {
             match provider.send(req.clone()).await {
                 Ok(resp) => return Ok(resp),
                 Err(e) => {
+                    transient &= e.is_transient();
                     errors.push(e.to_string());
                 }
             }
         }
 
         // All providers failed — return a summary.
-        Err(SmsError::Provider(format!(
+        let summary = format!(
             "all {} providers failed: [{}]",
-            self.providers.len(),
+            errors.len(),
             errors.join("; ")
-        )))
+        );
+        Err(if transient {
+            SmsError::Http(summary)
+        } else {
+            SmsError::Provider(summary)
+        })
+    }
+
+    /// Whether any provider in the chain supports `channel`.
+    fn supports(&self, channel: Channel) -> bool {
+        self.providers.iter().any(|p| p.supports(channel))
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Property-test strategies for webhook parsers
+// ---------------------------------------------------------------------------
+
+/// [`proptest`] strategies for fuzzing [`InboundWebhook`] implementations.
+///
+/// Requires the `proptest` feature; provider crates enable it from their
+/// `dev-dependencies`.  The generators cover well-formed carrier payloads
+/// and the ways real traffic goes wrong: bodies cut short, stray bytes in
+/// another encoding, and header values no proxy should have let through.
+///
+/// ```rust,ignore
+/// use sms_core::strategies::*;
+///
+/// proptest! {
+///     #[test]
+///     fn never_panics(body in arbitrary_body(), headers in hostile_headers()) {
+///         check_total(&client, &headers, &body)?;
+///     }
+/// }
+/// ```
+#[cfg(feature = "proptest")]
+pub mod strategies {
+    use crate::{Headers, InboundWebhook};
+    use proptest::prelude::*;
+    use proptest::test_runner::TestCaseError;
+
+    /// E.164 numbers: `+`, a non-zero leading digit, 7 to 15 digits in all.
+    pub fn phone_number() -> impl Strategy<Value = String> {
+        "\\+[1-9][0-9]{6,14}"
+    }
+
+    /// Message text: plain GSM-ish ASCII, printable Unicode (emoji, RTL,
+    /// combining marks) or any string at all, control characters included.
+    pub fn message_text() -> impl Strategy<Value = String> {
+        prop_oneof!["[ -~]{0,160}", "\\PC{0,70}", any::<String>()]
+    }
+
+    /// Values a carrier might put in an optional field: empty, plausible,
+    /// or garbage.
+    pub fn field_value() -> impl Strategy<Value = String> {
+        prop_oneof![
+            Just(String::new()),
+            "[A-Za-z0-9-]{1,40}",
+            "[0-9]{4}-[0-9]{2}-[0-9]{2}[ T][0-9]{2}:[0-9]{2}:[0-9]{2}(Z|[+-][0-9]{2}:?[0-9]{2})?",
+            any::<String>(),
+        ]
+    }
+
+    /// Arbitrary bytes, not necessarily UTF-8.
+    pub fn arbitrary_body() -> impl Strategy<Value = Vec<u8>> {
+        prop::collection::vec(any::<u8>(), 0..512)
+    }
+
+    /// Fragments that break form or JSON decoding in interesting ways:
+    /// invalid UTF-8, Latin-1, dangling or bogus percent-escapes, NULs,
+    /// stray delimiters and unbalanced JSON.
+    pub fn adversarial_fragment() -> impl Strategy<Value = Vec<u8>> {
+        prop_oneof![
+            Just(b"\xff\xfe".to_vec()),
+            Just(b"\xc3".to_vec()),
+            Just(b"caf\xe9".to_vec()),
+            Just(b"\xed\xa0\x80".to_vec()),
+            Just(b"%".to_vec()),
+            Just(b"%zz".to_vec()),
+            Just(b"%E9".to_vec()),
+            Just(b"%00".to_vec()),
+            Just(b"\0".to_vec()),
+            Just(b"&&==&".to_vec()),
+            Just(b"\"}".to_vec()),
+            Just(b"{\"".to_vec()),
+            Just(b"\\u00".to_vec()),
+            Just(b"\\ud800".to_vec()),
+            prop::collection::vec(any::<u8>(), 1..8),
+        ]
+    }
+
+    /// `body` cut off at every possible length, as from a dropped
+    /// connection or an over-eager body limit.
+    pub fn truncated(body: Vec<u8>) -> impl Strategy<Value = Vec<u8>> {
+        (0..=body.len()).prop_map(move |len| body[..len].to_vec())
+    }
+
+    /// `body` with one [`adversarial_fragment`] spliced in at a random
+    /// position.
+    pub fn mangled(body: Vec<u8>) -> impl Strategy<Value = Vec<u8>> {
+        (0..=body.len(), adversarial_fragment()).prop_map(move |(at, fragment)| {
+            let mut out = body[..at].to_vec();
+            out.extend(fragment);
+            out.extend_from_slice(&body[at..]);
+            out
+        })
+    }
+
+    /// Header lists a misbehaving proxy or attacker might send: duplicated
+    /// and oddly-cased signature headers, empty names, CR/LF and control
+    /// characters in values, and very long values.
+    pub fn hostile_headers() -> impl Strategy<Value = Headers> {
+        let name = prop_oneof![
+            Just("X-Twilio-Signature".to_string()),
+            Just("x-twilio-signature".to_string()),
+            Just("X-Plivo-Signature-V3".to_string()),
+            Just("X-Plivo-Signature-V3-Nonce".to_string()),
+            Just("x-amz-sns-message-type".to_string()),
+            Just("Content-Type".to_string()),
+            Just(String::new()),
+            "\\PC{0,32}",
+        ];
+        let value = prop_oneof![
+            Just(String::new()),
+            "[A-Za-z0-9+/=]{0,64}",
+            "[\\x00-\\x1f\\x7f]{1,16}",
+            "\\PC{0,64}",
+            "a{1024,8192}",
+            any::<String>(),
+        ];
+        prop::collection::vec((name, value), 0..8)
+    }
+
+    /// Encode `fields` as `application/x-www-form-urlencoded`.
+    pub fn form_body(fields: &[(&str, &str)]) -> Vec<u8> {
+        serde_urlencoded::to_string(fields)
+            .expect("string pairs always encode")
+            .into_bytes()
+    }
+
+    /// The invariants every webhook must hold on any input: `verify`,
+    /// `parse_inbound` and `parse_inbound_batch` return rather than panic,
+    /// and anything they accept is attributed to the webhook's provider.
+    pub fn check_total(
+        webhook: &dyn InboundWebhook,
+        headers: &Headers,
+        body: &[u8],
+    ) -> Result<(), TestCaseError> {
+        let _ = webhook.verify(headers, body);
+        let provider = webhook.provider();
+        if let Ok(msg) = webhook.parse_inbound(headers, body) {
+            prop_assert_eq!(msg.provider, provider);
+        }
+        if let Ok(batch) = webhook.parse_inbound_batch(headers, body) {
+            for msg in batch {
+                prop_assert_eq!(msg.provider, provider);
+            }
+        }
+        Ok(())
     }
 }
 
@@ -699,10 +3308,13 @@ mod tests {
             to: "+1",
             from: "+2",
             text: "msg",
+            channel: Channel::Mms,
+            timeout: None,
         };
         let owned: OwnedSendRequest = borrowed.into();
         assert_eq!(owned.to, "+1");
         assert_eq!(owned.text, "msg");
+        assert_eq!(owned.channel, Channel::Mms);
     }
 
     #[test]
@@ -728,6 +3340,7 @@ mod tests {
         assert_eq!(HttpStatus::BadRequest.as_u16(), 400);
         assert_eq!(HttpStatus::Unauthorized.as_u16(), 401);
         assert_eq!(HttpStatus::NotFound.as_u16(), 404);
+        assert_eq!(HttpStatus::TooManyRequests.as_u16(), 429);
         assert_eq!(HttpStatus::InternalServerError.as_u16(), 500);
     }
 
@@ -742,6 +3355,11 @@ mod tests {
             text: "hi".into(),
             timestamp: None,
             provider: "test",
+            direction: InboundDirection::Inbound,
+            channel: Channel::Sms,
+            provider_account: None,
+            thread_id: None,
+            flag: None,
             raw: serde_json::json!({}),
         };
         let resp = WebhookResponse::success(msg);
@@ -750,12 +3368,292 @@ mod tests {
         assert_eq!(resp.content_type, "application/json");
     }
 
+    #[test]
+    fn inbound_message_metadata_defaults_when_absent() {
+        let json = r#"{"id":null,"from":"+1","to":"+2","text":"hi","timestamp":null,"provider":"test","raw":{}}"#;
+        let msg: InboundMessage = serde_json::from_str(json).unwrap();
+        assert_eq!(msg.direction, InboundDirection::Inbound);
+        assert_eq!(msg.channel, Channel::Sms);
+        assert_eq!(msg.provider_account, None);
+    }
+
+    #[test]
+    fn inbound_timestamp_serializes_as_rfc3339() {
+        let msg = InboundMessage {
+            id: None,
+            from: "+1".into(),
+            to: "+2".into(),
+            text: "hi".into(),
+            timestamp: Some(OffsetDateTime::from_unix_timestamp(1_700_000_000).unwrap()),
+            provider: "test",
+            direction: InboundDirection::Inbound,
+            channel: Channel::Sms,
+            provider_account: None,
+            thread_id: None,
+            flag: None,
+            raw: serde_json::json!({}),
+        };
+        let json = serde_json::to_value(&msg).unwrap();
+        assert_eq!(json["timestamp"], "2023-11-14T22:13:20Z");
+    }
+
+    #[test]
+    fn timestamp_accepts_several_formats() {
+        let expected = OffsetDateTime::from_unix_timestamp(1_700_000_000).unwrap();
+        for input in [
+            "2023-11-14T22:13:20Z",
+            "Tue, 14 Nov 2023 22:13:20 +0000",
+            "2023-11-14 22:13:20",
+            "1700000000",
+            "1700000000000",
+        ] {
+            assert_eq!(timestamp::parse(input), Some(expected), "{}", input);
+        }
+        assert_eq!(timestamp::parse("yesterday"), None);
+    }
+
+    #[test]
+    fn inbound_timestamp_deserializes_epoch_and_null() {
+        let json = r#"{"id":null,"from":"+1","to":"+2","text":"hi","timestamp":1700000000,"provider":"test","raw":{}}"#;
+        let msg: InboundMessage = serde_json::from_str(json).unwrap();
+        assert_eq!(msg.timestamp.unwrap().unix_timestamp(), 1_700_000_000);
+
+        let json = r#"{"id":null,"from":"+1","to":"+2","text":"hi","provider":"test","raw":{}}"#;
+        let msg: InboundMessage = serde_json::from_str(json).unwrap();
+        assert!(msg.timestamp.is_none());
+    }
+
+    #[test]
+    fn rate_limited_display_and_transience() {
+        let err = SmsError::RateLimited {
+            retry_after: Some(std::time::Duration::from_secs(30)),
+        };
+        assert_eq!(err.to_string(), "rate limited; retry after 30s");
+        assert!(err.is_transient());
+        assert_eq!(
+            SmsError::RateLimited { retry_after: None }.to_string(),
+            "rate limited"
+        );
+        assert!(SmsError::Timeout("slow".into()).is_transient());
+        assert!(!SmsError::Suppressed("opted out".into()).is_transient());
+        assert!(!SmsError::Invalid("bad".into()).is_transient());
+    }
+
+    #[test]
+    fn retry_after_parses_seconds() {
+        assert_eq!(
+            parse_retry_after(" 120 "),
+            Some(std::time::Duration::from_secs(120))
+        );
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"), None);
+    }
+
+    #[test]
+    fn proxy_config_builders() {
+        let proxy = ProxyConfig::new("http://proxy:3128")
+            .with_basic_auth("user", "pass")
+            .with_no_proxy("localhost,127.0.0.1");
+        let config = HttpClientConfig::new().with_proxy(proxy.clone());
+        assert_eq!(config.proxy, Some(proxy));
+        assert_eq!(
+            config.proxy.as_ref().unwrap().username.as_deref(),
+            Some("user")
+        );
+    }
+
+    #[test]
+    fn http_config_root_certificates_default_empty() {
+        let config: HttpClientConfig = serde_json::from_str("{}").unwrap();
+        assert!(config.root_certificates.is_empty());
+        assert!(!config.accept_invalid_certs);
+
+        let config = config
+            .with_root_certificate("-----BEGIN CERTIFICATE-----")
+            .danger_accept_invalid_certs(true);
+        assert_eq!(config.root_certificates.len(), 1);
+        assert!(config.accept_invalid_certs);
+    }
+
+    #[test]
+    fn http_config_user_agent_and_headers() {
+        let config: HttpClientConfig = serde_json::from_value(serde_json::json!({
+            "user_agent": "acme-partner/1.0",
+            "headers": { "X-Api-Version": "2024-06-01" },
+        }))
+        .unwrap();
+        assert_eq!(config.user_agent.as_deref(), Some("acme-partner/1.0"));
+
+        let built = HttpClientConfig::new()
+            .with_user_agent("acme-partner/1.0")
+            .with_header("X-Api-Version", "2023-01-01")
+            .with_header("X-Api-Version", "2024-06-01");
+        assert_eq!(built, config);
+    }
+
+    #[test]
+    fn http_logger_redacts_credentials_and_text() {
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = seen.clone();
+        let log = HttpLogger::new(move |e: &HttpExchange| sink.lock().unwrap().push(e.clone()))
+            .redact_message_text()
+            .with_max_body_len(128);
+
+        log.start(
+            "twilio",
+            "POST",
+            "https://api.example.com/send?api_key=k1&to=%2B1",
+            Some("To=%2B15551234567&Body=secret+code&Token=abc"),
+        )
+        .finish(
+            200,
+            r#"{"sid":"SM1","auth":{"password":"p"},"text":"hi","dst":14155551234}"#,
+        );
+        log.start("plivo", "POST", "https://api.example.com", Some("{}"))
+            .fail(&SmsError::Timeout("slow".into()));
+
+        let seen = seen.lock().unwrap();
+        let ok = &seen[0];
+        assert_eq!(
+            ok.url,
+            "https://api.example.com/send?api_key=[REDACTED]&to=**"
+        );
+        assert_eq!(
+            ok.request_body.as_deref(),
+            Some("To=%2B15*******67&Body=[REDACTED]&Token=[REDACTED]")
+        );
+        let response: serde_json::Value =
+            serde_json::from_str(ok.response_body.as_deref().unwrap()).unwrap();
+        assert_eq!(response["sid"], "SM1");
+        assert_eq!(response["auth"]["password"], "[REDACTED]");
+        assert_eq!(response["text"], "[REDACTED]");
+        assert_eq!(response["dst"], "141******34");
+        assert_eq!(ok.status, Some(200));
+
+        assert_eq!(seen[1].status, None);
+        assert_eq!(seen[1].error.as_deref(), Some("timed out: slow"));
+        assert_eq!(truncate_body("ééé".into(), 3), "é...");
+    }
+
+    #[test]
+    fn balance_display_shows_cents() {
+        let balance = Balance {
+            amount: 7.5,
+            currency: "USD".into(),
+        };
+        assert_eq!(balance.to_string(), "7.50 USD");
+    }
+
     #[test]
     fn webhook_response_error_escapes_quotes() {
         let resp = WebhookResponse::error(HttpStatus::BadRequest, r#"bad "input""#);
         assert!(resp.body.contains(r#"bad \"input\""#));
     }
 
+    #[test]
+    fn sms_errors_map_to_http_statuses() {
+        let cases = [
+            (SmsError::Invalid("x".into()), 400),
+            (SmsError::Auth("x".into()), 401),
+            (SmsError::Suppressed("x".into()), 422),
+            (SmsError::RateLimited { retry_after: None }, 429),
+            (SmsError::Unexpected("x".into()), 500),
+            (SmsError::Http("x".into()), 502),
+            (SmsError::Provider("x".into()), 502),
+            (SmsError::Timeout("x".into()), 504),
+        ];
+        for (error, code) in cases {
+            assert_eq!(error.http_status().as_u16(), code, "{}", error);
+        }
+
+        let resp = WebhookResponse::from_sms_error(&SmsError::Provider("no balance".into()));
+        assert_eq!(resp.status, HttpStatus::BadGateway);
+        assert!(resp.body.contains("no balance"));
+    }
+
+    #[test]
+    fn redaction_policy_masks_hashes_or_omits() {
+        let policy = RedactionPolicy::default();
+        assert_eq!(policy.number("+14155551234"), "+14*******34");
+        assert_eq!(policy.number("12345"), "*****");
+        assert_eq!(policy.text("my code is 1234"), "[redacted]");
+
+        let hashed = RedactionPolicy {
+            numbers: NumberRedaction::Omit,
+            text: TextRedaction::Hash,
+        };
+        assert_eq!(hashed.number("+14155551234"), "[redacted]");
+        assert_eq!(hashed.text("hello"), hashed.text("hello"));
+        assert_ne!(hashed.text("hello"), hashed.text("hullo"));
+        assert!(!hashed.text("hello").contains("hello"));
+
+        assert_eq!(RedactionPolicy::FULL.number("+1415"), "+1415");
+    }
+
+    #[test]
+    fn rejected_errors_expose_provider_code() {
+        let err = SmsError::from(ProviderError::new(
+            "twilio",
+            "21610",
+            ProviderErrorKind::OptedOut,
+            "Attempt to send to unsubscribed recipient",
+        ));
+        assert_eq!(
+            err.provider_error().map(|e| e.kind),
+            Some(ProviderErrorKind::OptedOut)
+        );
+        assert_eq!(err.http_status(), HttpStatus::UnprocessableEntity);
+        assert!(!err.is_transient());
+        assert!(err.to_string().contains("twilio error 21610"));
+        assert!(SmsError::Provider("x".into()).provider_error().is_none());
+
+        let throttled = ProviderError::new("twilio", "30001", ProviderErrorKind::Throttled, "");
+        assert!(SmsError::Rejected(throttled).is_transient());
+    }
+
+    #[test]
+    fn webhook_response_problem_is_rfc7807() {
+        let resp = WebhookResponse::problem(HttpStatus::Unauthorized, r#"bad "sig""#);
+        assert_eq!(resp.content_type, "application/problem+json");
+        let body: serde_json::Value = serde_json::from_str(&resp.body).unwrap();
+        assert_eq!(body["type"], "about:blank");
+        assert_eq!(body["title"], "Unauthorized");
+        assert_eq!(body["status"], 401);
+        assert_eq!(body["detail"], r#"bad "sig""#);
+    }
+
+    #[test]
+    fn webhook_response_carries_headers() {
+        let resp = WebhookResponse::error(HttpStatus::ServiceUnavailable, "draining")
+            .with_header("Retry-After", "30");
+        assert_eq!(resp.status.as_u16(), 503);
+        assert_eq!(resp.status.reason(), "Service Unavailable");
+        assert_eq!(resp.header("retry-after"), Some("30"));
+        assert_eq!(resp.header("cache-control"), None);
+        assert_eq!(HttpStatus::PayloadTooLarge.as_u16(), 413);
+    }
+
+    #[test]
+    fn webhook_error_maps_to_http_status() {
+        assert_eq!(
+            WebhookError::ProviderNotFound("x".into())
+                .http_status()
+                .as_u16(),
+            404
+        );
+        assert_eq!(
+            WebhookError::VerificationFailed("sig".into())
+                .http_status()
+                .as_u16(),
+            401
+        );
+        assert_eq!(
+            WebhookError::RateLimited(std::time::Duration::from_secs(1))
+                .http_status()
+                .as_u16(),
+            429
+        );
+    }
+
     // -- InboundRegistry tests --
 
     #[test]
@@ -764,6 +3662,51 @@ mod tests {
         assert!(reg.get("nonexistent").is_none());
     }
 
+    #[test]
+    fn inbound_registry_collects_and_copies_on_write() {
+        let reg: InboundRegistry = [
+            Arc::new(NamedHook("plivo")) as Arc<dyn InboundWebhook>,
+            Arc::new(NamedHook("twilio")),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(reg.len(), 2);
+        assert_eq!(reg.iter().count(), 2);
+
+        let snapshot = reg.clone();
+        let reg = reg.with(Arc::new(NamedHook("aws-sns")));
+        assert_eq!(reg.len(), 3);
+        assert_eq!(snapshot.len(), 2);
+        assert!(snapshot.get("aws-sns").is_none());
+    }
+
+    // -- TenantRegistry tests --
+
+    struct NamedHook(&'static str);
+
+    impl InboundWebhook for NamedHook {
+        fn provider(&self) -> &'static str {
+            self.0
+        }
+
+        fn parse_inbound(&self, _headers: &Headers, _body: &[u8]) -> Result<InboundMessage, SmsError> {
+            Err(SmsError::Invalid("unused".into()))
+        }
+    }
+
+    #[test]
+    fn tenant_registry_isolates_tenants() {
+        let tenants = TenantRegistry::new()
+            .with("acme", InboundRegistry::new().with(Arc::new(NamedHook("plivo"))))
+            .with("globex", InboundRegistry::new().with(Arc::new(NamedHook("twilio"))));
+
+        assert_eq!(tenants.len(), 2);
+        assert!(tenants.get("acme").unwrap().get("plivo").is_some());
+        assert!(tenants.get("acme").unwrap().get("twilio").is_none());
+        assert!(tenants.get("globex").unwrap().get("twilio").is_some());
+        assert!(tenants.get("initech").is_none());
+    }
+
     // -- SmsError display --
 
     #[test]
@@ -792,6 +3735,30 @@ mod tests {
         assert!(uuid::Uuid::parse_str(&id).is_ok());
     }
 
+    #[test]
+    fn uuid_v7_ids_are_version_7() {
+        let id = uuid::Uuid::parse_str(&UuidV7.generate()).unwrap();
+        assert_eq!(id.get_version_num(), 7);
+    }
+
+    #[test]
+    fn ulids_are_26_crockford_chars_and_sortable() {
+        let a = Ulid.generate();
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        let b = Ulid.generate();
+        assert_eq!(a.len(), 26);
+        assert!(a.bytes().all(|c| CROCKFORD.contains(&c)));
+        assert!(a[..10] < b[..10]);
+    }
+
+    #[test]
+    fn snowflakes_are_unique_and_increasing() {
+        let snowflake = Snowflake::new(7);
+        let ids: Vec<u64> = (0..5000).map(|_| snowflake.next_id()).collect();
+        assert!(ids.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!((ids[0] >> 12) & 0x3ff, 7);
+    }
+
     // -- SmsRouter tests --
 
     /// A mock client that always succeeds.
@@ -799,12 +3766,15 @@ mod tests {
         provider_name: &'static str,
     }
 
-    #[async_trait]
     impl SmsClient for MockClient {
         async fn send(&self, _req: SendRequest<'_>) -> Result<SendResponse, SmsError> {
             Ok(SendResponse {
                 id: "mock-id".into(),
                 provider: self.provider_name,
+                channel: Channel::Sms,
+                http_status: None,
+                provider_request_id: None,
+                thread_id: None,
                 raw: serde_json::json!({"mock": true}),
             })
         }
@@ -815,7 +3785,6 @@ mod tests {
         message: String,
     }
 
-    #[async_trait]
     impl SmsClient for FailingClient {
         async fn send(&self, _req: SendRequest<'_>) -> Result<SendResponse, SmsError> {
             Err(SmsError::Provider(self.message.clone()))
@@ -827,6 +3796,8 @@ mod tests {
             to: "+14155551234",
             from: "+10005551234",
             text: "test",
+            channel: Channel::Sms,
+            timeout: None,
         }
     }
 
@@ -840,6 +3811,49 @@ mod tests {
         assert_eq!(resp.provider, "beta");
     }
 
+    #[tokio::test]
+    async fn outbound_and_inbound_share_a_thread() {
+        let router = SmsRouter::new().with("alpha", MockClient { provider_name: "alpha" });
+        let req = test_request();
+        let resp = router.send_via("alpha", req.clone()).await.unwrap();
+        let thread = resp.thread_id.clone().unwrap();
+        assert_eq!(thread, thread_id(req.from, req.to));
+        assert_eq!(thread, thread_id("+1 (000) 555-1234", "+1 415 555 1234"));
+        assert_ne!(thread, thread_id(req.to, req.from));
+
+        let reply = InboundMessage {
+            id: None,
+            from: req.to.into(),
+            to: req.from.into(),
+            text: "reply".into(),
+            timestamp: None,
+            provider: "alpha",
+            direction: InboundDirection::Inbound,
+            channel: Channel::Sms,
+            provider_account: None,
+            thread_id: None,
+            flag: None,
+            raw: serde_json::json!({}),
+        };
+        let report = InboundMessage {
+            from: req.from.into(),
+            to: req.to.into(),
+            direction: InboundDirection::DeliveryReport,
+            ..reply.clone()
+        };
+        assert_eq!(reply.with_thread_id().thread_id, Some(thread.clone()));
+        assert_eq!(report.with_thread_id().thread_id, Some(thread));
+    }
+
+    #[test]
+    fn normalize_number_reaches_e164() {
+        assert_eq!(normalize_number("+1 (555) 010-0000"), "+15550100000");
+        assert_eq!(normalize_number("0044 7700.900000"), "+447700900000");
+        assert_eq!(normalize_number("+15550100000"), "+15550100000");
+        assert_eq!(normalize_number("12345"), "12345");
+        assert_eq!(normalize_number("ACME Ltd"), "ACMELtd");
+    }
+
     #[tokio::test]
     async fn router_send_via_unknown_provider_errors() {
         let router = SmsRouter::new()
@@ -886,6 +3900,152 @@ mod tests {
         assert!(!router.has_provider("twilio"));
     }
 
+    #[tokio::test]
+    async fn construction_macros_register_every_client() {
+        let registry = registry![NamedHook("plivo"), NamedHook("twilio"),];
+        assert_eq!(registry.len(), 2);
+        assert!(registry.get("twilio").is_some());
+
+        let router = senders![
+            "plivo" => MockClient { provider_name: "plivo" },
+            "twilio" => MockClient { provider_name: "twilio" },
+        ];
+        assert!(router.has_provider("twilio"));
+        let resp = router.send(test_request()).await.unwrap();
+        assert_eq!(resp.provider, "plivo");
+    }
+
+    // -- Provider plugin tests --
+
+    fn build_named_hook(settings: &serde_json::Value) -> Result<BuiltProvider, SmsError> {
+        #[derive(Deserialize)]
+        struct Settings {
+            send: bool,
+        }
+        let settings: Settings = provider_settings("test_plugin", settings)?;
+        if settings.send {
+            let client = MockClient {
+                provider_name: "test",
+            };
+            return Ok(BuiltProvider::sender(client));
+        }
+        Ok(BuiltProvider::webhook(NamedHook("test")))
+    }
+
+    crate::register_provider!("test_plugin", build_named_hook);
+
+    #[test]
+    fn registered_plugins_build_from_settings() {
+        let plugin = ProviderPlugin::find("test_plugin").unwrap();
+        assert!(ProviderPlugin::all().any(|p| p.name() == "test_plugin"));
+        assert!(ProviderPlugin::find("nope").is_none());
+
+        let hook = plugin.build(&serde_json::json!({ "send": false })).unwrap();
+        assert_eq!(hook.webhook.unwrap().provider(), "test");
+        assert!(hook.client.is_none());
+        let sender = plugin.build(&serde_json::json!({ "send": true })).unwrap();
+        assert!(sender.client.is_some());
+
+        let err = plugin.build(&serde_json::json!({})).unwrap_err();
+        assert!(err.to_string().contains("test_plugin settings"));
+    }
+
+    // -- Declarative HTTP provider tests --
+
+    #[cfg(feature = "http-provider")]
+    crate::define_http_provider! {
+        /// Test provider with basic auth and a JSON webhook.
+        struct DeclaredClient {
+            provider: "declared",
+            base_url: "https://api.example.com/",
+            path: "/v1/{account}/messages",
+            credentials: { account: "DECLARED_ACCOUNT", token: "DECLARED_TOKEN" },
+            auth: basic(account, token),
+            request(req): json { "to" => req.to, "from" => req.from, "body" => req.text },
+            response_id: "messages.0.id",
+            max_text_len: 480,
+            inbound: json { from: "sender", to: "recipient", text: "payload.text", id: "id" },
+        }
+    }
+
+    #[cfg(feature = "http-provider")]
+    #[test]
+    fn declared_provider_builds_client_and_webhook() {
+        let client = DeclaredClient::new("acct", "hunter2")
+            .with_base_url("http://localhost:9")
+            .with_timeout(std::time::Duration::from_secs(5))
+            .with_http_config(
+                HttpClientConfig::new()
+                    .with_user_agent("acme-partner/1.0")
+                    .with_header("X-Api-Version", "2024-06-01"),
+            )
+            .unwrap();
+        assert_eq!(client.base_url, "http://localhost:9");
+        assert_eq!(client.timeout.as_secs(), 5);
+        assert!(DeclaredClient::from_env().is_err());
+        assert_eq!(client.max_text_len(), Some(480));
+        assert!(!format!("{:?}", client).contains("hunter2"));
+
+        let body = br#"{"id":"m1","sender":"+1","recipient":"+2","payload":{"text":"hi"}}"#;
+        let msg = client.parse_inbound(&vec![], body).unwrap();
+        assert_eq!(msg.provider, "declared");
+        assert_eq!(msg.id.as_deref(), Some("m1"));
+        assert_eq!(msg.text, "hi");
+        let missing_text = client.parse_inbound(&vec![], br#"{"sender":"+1"}"#);
+        assert!(missing_text.is_err());
+    }
+
+    #[cfg(feature = "http-provider")]
+    #[tokio::test]
+    async fn declared_provider_reports_calls_to_the_logger() {
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = seen.clone();
+        let client = DeclaredClient::new("acct", "hunter2")
+            .with_base_url("http://127.0.0.1:9")
+            .with_http_logger(HttpLogger::new(move |e: &HttpExchange| {
+                sink.lock().unwrap().push(e.clone());
+            }));
+        let err = client.send(test_request()).await.unwrap_err();
+        assert!(err.is_transient());
+
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.len(), 1);
+        assert_eq!(seen[0].provider, "declared");
+        assert_eq!(seen[0].url, "http://127.0.0.1:9/v1/acct/messages");
+        let body = seen[0].request_body.as_deref().unwrap();
+        assert!(body.contains("+14*******34"));
+        assert!(!body.contains("+14155551234"));
+        assert!(seen[0].error.is_some());
+    }
+
+    #[cfg(feature = "http-provider")]
+    #[test]
+    fn http_provider_parses_form_and_paths() {
+        use http_provider::{BodyEncoding, InboundFields};
+        let raw = serde_json::json!({ "messages": [{ "id": 7 }] });
+        assert_eq!(
+            http_provider::json_path(&raw, "messages.0.id"),
+            Some(&serde_json::json!(7))
+        );
+        assert!(http_provider::json_path(&raw, "messages.1.id").is_none());
+
+        let fields = InboundFields {
+            from: "From",
+            to: "To",
+            text: "Body",
+            id: None,
+        };
+        let msg = http_provider::parse_inbound(
+            "form",
+            BodyEncoding::Form,
+            &fields,
+            b"From=%2B1&To=%2B2&Body=hello+there",
+        )
+        .unwrap();
+        assert_eq!(msg.text, "hello there");
+        assert!(msg.id.is_none());
+    }
+
     // -- FallbackClient tests --
 
     #[tokio::test]
@@ -921,6 +4081,141 @@ mod tests {
         assert!(msg.contains("err-b"));
     }
 
+    /// A client whose provider is unreachable.
+    struct UnreachableClient;
+
+    impl SmsClient for UnreachableClient {
+        async fn send(&self, _req: SendRequest<'_>) -> Result<SendResponse, SmsError> {
+            Err(SmsError::Timeout("no answer".into()))
+        }
+    }
+
+    #[tokio::test]
+    async fn fallback_keeps_transient_failures_transient() {
+        let down = FallbackClient::new(vec![
+            Arc::new(UnreachableClient),
+            Arc::new(UnreachableClient),
+        ]);
+        let err = down.send(test_request()).await.unwrap_err();
+        assert!(err.is_transient());
+        assert!(err.to_string().contains("all 2 providers failed"));
+
+        let mixed = FallbackClient::new(vec![
+            Arc::new(UnreachableClient),
+            Arc::new(FailingClient { message: "blocked".into() }),
+        ]);
+        let err = mixed.send(test_request()).await.unwrap_err();
+        assert!(matches!(err, SmsError::Provider(_)));
+    }
+
+    /// A client that only sends WhatsApp messages.
+    struct WhatsAppClient;
+
+    impl SmsClient for WhatsAppClient {
+        async fn send(&self, req: SendRequest<'_>) -> Result<SendResponse, SmsError> {
+            Ok(SendResponse {
+                id: "wa-id".into(),
+                provider: "whatsapp",
+                channel: req.channel,
+                http_status: None,
+                provider_request_id: None,
+                thread_id: None,
+                raw: serde_json::json!({}),
+            })
+        }
+
+        fn supports(&self, channel: Channel) -> bool {
+            channel == Channel::WhatsApp
+        }
+    }
+
+    #[tokio::test]
+    async fn router_rejects_unsupported_channel() {
+        let router = SmsRouter::new().with("alpha", MockClient { provider_name: "alpha" });
+        let req = SendRequest {
+            channel: Channel::WhatsApp,
+            ..test_request()
+        };
+        let err = router.send_via("alpha", req).await.unwrap_err();
+        assert!(err.to_string().contains("does not support channel whatsapp"));
+        assert!(!router.supports(Channel::WhatsApp));
+    }
+
+    #[test]
+    fn validate_text_rejects_undeliverable_text() {
+        assert!(validate_text("Hello\nworld", Some(11)).is_ok());
+        for (text, max) in [
+            (" \n", None),
+            ("Hello", Some(4)),
+            ("a\u{7}b", None),
+            ("a\u{FFFD}", None),
+        ] {
+            assert!(
+                matches!(validate_text(text, max), Err(SmsError::Invalid(_))),
+                "{:?}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn text_from_utf16_rejects_lone_surrogates() {
+        let units: Vec<u16> = "hi 👍".encode_utf16().collect();
+        assert_eq!(text_from_utf16(&units).unwrap(), "hi 👍");
+        let err = text_from_utf16(&units[..units.len() - 1]).unwrap_err();
+        assert!(err.to_string().contains("0xD83D"));
+    }
+
+    /// Accepts at most three characters.
+    struct TinyClient;
+
+    impl SmsClient for TinyClient {
+        async fn send(&self, _req: SendRequest<'_>) -> Result<SendResponse, SmsError> {
+            unreachable!("oversized text must be rejected before sending")
+        }
+
+        fn max_text_len(&self) -> Option<usize> {
+            Some(3)
+        }
+    }
+
+    #[tokio::test]
+    async fn router_validates_text_against_provider_limit() {
+        let router = SmsRouter::new().with("tiny", TinyClient);
+        assert_eq!(router.max_text_len(), Some(3));
+        let err = router.send(test_request()).await.unwrap_err();
+        assert!(err.to_string().contains("limit is 3"));
+    }
+
+    #[tokio::test]
+    async fn fallback_only_uses_providers_supporting_channel() {
+        let client = FallbackClient::new(vec![
+            Arc::new(MockClient { provider_name: "sms" }),
+            Arc::new(WhatsAppClient),
+        ]);
+        let req = SendRequest {
+            channel: Channel::WhatsApp,
+            ..test_request()
+        };
+        let resp = client.send(req).await.unwrap();
+        assert_eq!(resp.provider, "whatsapp");
+        assert_eq!(resp.channel, Channel::WhatsApp);
+
+        let rcs = SendRequest {
+            channel: Channel::Rcs,
+            ..test_request()
+        };
+        assert!(client.send(rcs).await.is_err());
+    }
+
+    #[test]
+    fn channel_serde_is_lowercase() {
+        assert_eq!(serde_json::to_string(&Channel::WhatsApp).unwrap(), "\"whatsapp\"");
+        let req: OwnedSendRequest =
+            serde_json::from_str(r#"{"to":"+1","from":"+2","text":"hi"}"#).unwrap();
+        assert_eq!(req.channel, Channel::Sms);
+    }
+
     #[test]
     fn fallback_len() {
         let client = FallbackClient::new(vec![
@@ -0,0 +1,468 @@
+//! # D7 Networks SMS Provider
+//!
+//! [D7 Networks](https://d7networks.com/) backend for the smskit
+//! multi-provider SMS abstraction, using the Messages API v1.
+//!
+//! ## Sending messages
+//!
+//! ```rust,ignore
+//! use sms_core::{SendRequest, SmsClient};
+//! use sms_d7networks::D7Client;
+//!
+//! let client = D7Client::new("YOUR_API_TOKEN");
+//! let response = client.send(SendRequest {
+//!     to: "+971501234567",
+//!     from: "SignOTP",
+//!     text: "Hello from D7!",
+//!     ..Default::default()
+//! }).await?;
+//! println!("Request ID: {}", response.id);
+//! ```
+//!
+//! ## Delivery reports
+//!
+//! D7 POSTs a JSON delivery report to the `report_url` of each send.  Set
+//! it with [`D7Client::with_report_url`] and route it to the unified
+//! webhook endpoint.
+
+use serde::{Deserialize, Serialize};
+use sms_core::{
+    Channel, ExposeSecret, Headers, HttpClientConfig, IdGenerator, InboundDirection,
+    InboundMessage, InboundWebhook, SecretString, SendRequest, SendResponse, SmsClient, SmsError,
+    UuidV4,
+};
+use sms_core::http_provider::transport_error;
+use std::sync::Arc;
+use std::time::Duration;
+
+const PROVIDER: &str = "d7networks";
+
+/// Production API base URL.
+const LIVE_URL: &str = "https://api.d7networks.com";
+
+/// Default deadline for a single API call.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// D7 Networks Messages API client.
+///
+/// Implements [`SmsClient`] for sending SMS and [`InboundWebhook`] for
+/// receiving delivery reports.
+#[derive(Clone)]
+pub struct D7Client {
+    /// API token, sent as a bearer token.  Zeroized on drop.
+    pub api_token: SecretString,
+    /// URL D7 POSTs delivery reports to, sent as `report_url`.
+    pub report_url: Option<String>,
+    /// API base URL; override with
+    /// [`with_base_url`](D7Client::with_base_url) for testing.
+    pub base_url: String,
+    /// Deadline for each API call unless the request sets its own
+    /// [`SendRequest::timeout`].
+    pub timeout: Duration,
+    id_generator: Arc<dyn IdGenerator>,
+    http: reqwest::Client,
+}
+
+impl std::fmt::Debug for D7Client {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("D7Client")
+            .field("api_token", &"[REDACTED]")
+            .field("report_url", &self.report_url)
+            .field("base_url", &self.base_url)
+            .field("timeout", &self.timeout)
+            .finish_non_exhaustive()
+    }
+}
+
+impl D7Client {
+    /// Create a client against the production API.
+    pub fn new(api_token: impl Into<String>) -> Self {
+        Self::with_base_url(api_token, LIVE_URL)
+    }
+
+    /// Create a client by reading credentials from environment variables.
+    ///
+    /// | Variable        | Maps to      |
+    /// |-----------------|--------------|
+    /// | `D7_API_TOKEN`  | `api_token`  |
+    /// | `D7_REPORT_URL` | `report_url` |
+    ///
+    /// Returns [`SmsError::Auth`] if `D7_API_TOKEN` is missing.
+    pub fn from_env() -> Result<Self, SmsError> {
+        let api_token = std::env::var("D7_API_TOKEN")
+            .map_err(|_| SmsError::Auth("D7_API_TOKEN not set".into()))?;
+        let client = Self::new(api_token);
+        Ok(match std::env::var("D7_REPORT_URL") {
+            Ok(url) => client.with_report_url(url),
+            Err(_) => client,
+        })
+    }
+
+    /// Create a client with a custom API base URL, e.g. a mock server.
+    pub fn with_base_url(api_token: impl Into<String>, base_url: impl Into<String>) -> Self {
+        Self {
+            api_token: SecretString::from(api_token.into()),
+            report_url: None,
+            base_url: base_url.into(),
+            timeout: DEFAULT_TIMEOUT,
+            id_generator: Arc::new(UuidV4),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Ask D7 to POST delivery reports for each send to `url`.
+    pub fn with_report_url(mut self, url: impl Into<String>) -> Self {
+        self.report_url = Some(url.into());
+        self
+    }
+
+    /// Set the default deadline for each API call (default: 30 seconds).
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Rebuild the HTTP client with custom transport settings (proxy,
+    /// `User-Agent`, extra headers, etc.).
+    pub fn with_http_config(mut self, config: HttpClientConfig) -> Result<Self, SmsError> {
+        self.http = sms_core::http_provider::client_with(&config)?;
+        Ok(self)
+    }
+
+    /// Generate IDs with `ids` when the response doesn't include one
+    /// (default: [`UuidV4`]).
+    pub fn with_id_generator(mut self, ids: impl IdGenerator + 'static) -> Self {
+        self.id_generator = Arc::new(ids);
+        self
+    }
+}
+
+/// Wire format for the `/messages/v1/send` request body.
+#[derive(Debug, Serialize)]
+struct D7SendRequest<'a> {
+    messages: [D7Message<'a>; 1],
+    message_globals: D7Globals<'a>,
+}
+
+#[derive(Debug, Serialize)]
+struct D7Message<'a> {
+    channel: &'a str,
+    recipients: [&'a str; 1],
+    content: &'a str,
+    msg_type: &'a str,
+    /// `text` for GSM-7, `unicode` otherwise.
+    data_coding: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+struct D7Globals<'a> {
+    #[serde(skip_serializing_if = "str::is_empty")]
+    originator: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    report_url: Option<&'a str>,
+}
+
+/// Typed view of the JSON returned from a send, available from
+/// [`SendResponse::raw`] via [`D7SendDetails::from_response`].
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct D7SendDetails {
+    /// Request ID, echoed in each delivery report.
+    pub request_id: String,
+    /// `accepted` on success.
+    #[serde(default)]
+    pub status: String,
+    /// Creation time, e.g. `"2024-01-15T10:30:00.123456+00:00"`.
+    #[serde(default)]
+    pub created_at: Option<String>,
+}
+
+impl D7SendDetails {
+    /// Parse the raw payload of a D7 Networks [`SendResponse`].
+    pub fn from_response(resp: &SendResponse) -> Result<Self, SmsError> {
+        if resp.provider != PROVIDER {
+            return Err(SmsError::Invalid(format!(
+                "expected a {} response, got {}",
+                PROVIDER, resp.provider
+            )));
+        }
+        serde_json::from_value(resp.raw.clone())
+            .map_err(|e| SmsError::Invalid(format!("d7networks send response: {}", e)))
+    }
+}
+
+impl SmsClient for D7Client {
+    async fn send(&self, req: SendRequest<'_>) -> Result<SendResponse, SmsError> {
+        sms_core::validate_text(req.text, self.max_text_len())?;
+        let url = format!("{}/messages/v1/send", self.base_url.trim_end_matches('/'));
+        let payload = D7SendRequest {
+            messages: [D7Message {
+                channel: "sms",
+                recipients: [req.to],
+                content: req.text,
+                msg_type: "text",
+                data_coding: if req.text.is_ascii() {
+                    "text"
+                } else {
+                    "unicode"
+                },
+            }],
+            message_globals: D7Globals {
+                originator: req.from,
+                report_url: self.report_url.as_deref(),
+            },
+        };
+        let res = self
+            .http
+            .post(&url)
+            .bearer_auth(self.api_token.expose_secret())
+            .header(reqwest::header::ACCEPT, "application/json")
+            .timeout(req.timeout.unwrap_or(self.timeout))
+            .json(&payload)
+            .send()
+            .await
+            .map_err(transport_error)?;
+
+        let status = res.status();
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = res
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(sms_core::parse_retry_after);
+            return Err(SmsError::RateLimited { retry_after });
+        }
+        let raw_text = res.text().await.map_err(transport_error)?;
+        if status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN {
+            return Err(SmsError::Auth(raw_text));
+        }
+        if status == reqwest::StatusCode::BAD_REQUEST
+            || status == reqwest::StatusCode::UNPROCESSABLE_ENTITY
+        {
+            return Err(SmsError::Invalid(raw_text));
+        }
+        if !status.is_success() {
+            return Err(SmsError::Provider(format!("HTTP {}: {}", status, raw_text)));
+        }
+
+        let raw: serde_json::Value = serde_json::from_str(&raw_text)
+            .map_err(|e| SmsError::Provider(format!("send response: {}: {}", e, raw_text)))?;
+        let id = raw
+            .get("request_id")
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+            .unwrap_or_else(|| self.id_generator.generate());
+        Ok(SendResponse {
+            id,
+            provider: PROVIDER,
+            channel: Channel::Sms,
+            http_status: Some(status.as_u16()),
+            provider_request_id: None,
+            thread_id: Some(req.thread_id()),
+            raw,
+        })
+    }
+}
+
+/// The JSON payload D7 POSTs to a send's `report_url`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct D7DeliveryReport {
+    /// Request ID returned by the send.
+    pub request_id: String,
+    /// Per-recipient message ID.
+    #[serde(default)]
+    pub message_id: Option<String>,
+    /// Destination number.
+    pub recipient: String,
+    /// `accepted`, `sent`, `delivered`, `undelivered`, `failed` or
+    /// `rejected`.
+    pub status: String,
+    /// Channel the message went out on, normally `sms`.
+    #[serde(default)]
+    pub channel: Option<String>,
+    /// Why delivery failed, for failure statuses.
+    #[serde(default)]
+    pub error_message: Option<String>,
+    /// Report time, e.g. `"2024-01-15T10:30:05.123456+00:00"`.
+    #[serde(default)]
+    pub time_stamp: Option<String>,
+}
+
+impl D7DeliveryReport {
+    /// Returns `true` for final failures (`undelivered`, `failed`,
+    /// `rejected`).
+    pub fn is_failure(&self) -> bool {
+        matches!(self.status.as_str(), "undelivered" | "failed" | "rejected")
+    }
+}
+
+impl From<D7DeliveryReport> for InboundMessage {
+    fn from(r: D7DeliveryReport) -> Self {
+        let timestamp = r.time_stamp.as_deref().and_then(sms_core::timestamp::parse);
+        let raw = serde_json::to_value(&r).unwrap_or_default();
+        InboundMessage {
+            id: Some(r.request_id),
+            from: "D7Networks".to_string(),
+            to: r.recipient,
+            text: format!("Delivery Status: {}", r.status),
+            timestamp,
+            provider: PROVIDER,
+            direction: InboundDirection::DeliveryReport,
+            channel: Channel::Sms,
+            provider_account: None,
+            thread_id: None,
+            flag: None,
+            raw,
+        }
+        .with_thread_id()
+    }
+}
+
+impl InboundWebhook for D7Client {
+    fn provider(&self) -> &'static str {
+        PROVIDER
+    }
+
+    fn parse_inbound(&self, _headers: &Headers, body: &[u8]) -> Result<InboundMessage, SmsError> {
+        let report: D7DeliveryReport = serde_json::from_slice(body)
+            .map_err(|e| SmsError::Invalid(format!("delivery report: {}", e)))?;
+        Ok(report.into())
+    }
+}
+
+/// The `d7networks` configuration section read by the provider plugin.
+#[derive(Deserialize)]
+struct D7Settings {
+    api_token: String,
+    report_url: Option<String>,
+    base_url: Option<String>,
+    #[serde(default)]
+    http: Option<HttpClientConfig>,
+}
+
+/// Build a [`D7Client`] from the `d7networks` section.
+fn build_plugin(settings: &serde_json::Value) -> Result<sms_core::BuiltProvider, SmsError> {
+    let s: D7Settings = sms_core::provider_settings(PROVIDER, settings)?;
+    let mut client = match s.base_url {
+        Some(base_url) => D7Client::with_base_url(s.api_token, base_url),
+        None => D7Client::new(s.api_token),
+    };
+    client.report_url = s.report_url;
+    let client = match s.http {
+        Some(http) => client.with_http_config(http)?,
+        None => client,
+    };
+    Ok(sms_core::BuiltProvider::both(client))
+}
+
+sms_core::register_provider!("d7networks", build_plugin);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn send_request_shape() {
+        let payload = D7SendRequest {
+            messages: [D7Message {
+                channel: "sms",
+                recipients: ["+971501234567"],
+                content: "Hello",
+                msg_type: "text",
+                data_coding: "text",
+            }],
+            message_globals: D7Globals {
+                originator: "SignOTP",
+                report_url: Some("https://example.com/dlr"),
+            },
+        };
+        assert_eq!(
+            serde_json::to_value(&payload).unwrap(),
+            json!({
+                "messages": [{
+                    "channel": "sms",
+                    "recipients": ["+971501234567"],
+                    "content": "Hello",
+                    "msg_type": "text",
+                    "data_coding": "text"
+                }],
+                "message_globals": {
+                    "originator": "SignOTP",
+                    "report_url": "https://example.com/dlr"
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn send_details_from_response() {
+        let resp = SendResponse {
+            id: "c5e4bd0c-6d39-4ab0-a9d3-3ec3a9b0f4b0".into(),
+            provider: PROVIDER,
+            channel: Channel::Sms,
+            http_status: Some(200),
+            provider_request_id: None,
+            thread_id: None,
+            raw: json!({
+                "request_id": "c5e4bd0c-6d39-4ab0-a9d3-3ec3a9b0f4b0",
+                "status": "accepted",
+                "created_at": "2024-01-15T10:30:00.123456+00:00"
+            }),
+        };
+        let details = D7SendDetails::from_response(&resp).unwrap();
+        assert_eq!(details.status, "accepted");
+        assert_eq!(details.request_id, resp.id);
+    }
+
+    #[test]
+    fn parses_delivery_report() {
+        let client = D7Client::new("token");
+        let body = br#"{
+            "request_id": "c5e4bd0c-6d39-4ab0-a9d3-3ec3a9b0f4b0",
+            "message_id": "m1",
+            "recipient": "+971501234567",
+            "status": "undelivered",
+            "channel": "sms",
+            "error_message": "Absent subscriber",
+            "time_stamp": "2024-01-15T10:30:05+00:00"
+        }"#;
+        let msg = client.parse_inbound(&Headers::new(), body).unwrap();
+        assert_eq!(msg.direction, InboundDirection::DeliveryReport);
+        assert_eq!(
+            msg.id.as_deref(),
+            Some("c5e4bd0c-6d39-4ab0-a9d3-3ec3a9b0f4b0")
+        );
+        assert_eq!(msg.to, "+971501234567");
+        assert_eq!(msg.text, "Delivery Status: undelivered");
+        assert!(msg.timestamp.is_some());
+
+        let report: D7DeliveryReport = serde_json::from_value(msg.raw).unwrap();
+        assert!(report.is_failure());
+        assert_eq!(report.error_message.as_deref(), Some("Absent subscriber"));
+    }
+
+    #[test]
+    fn rejects_malformed_webhooks() {
+        let client = D7Client::new("token");
+        assert!(
+            client
+                .parse_inbound(&Headers::new(), br#"{"status":"sent"}"#)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn plugin_builds_from_settings() {
+        let plugin = sms_core::ProviderPlugin::find("d7networks").unwrap();
+        let built = plugin.build(&json!({ "api_token": "token" })).unwrap();
+        assert_eq!(built.webhook.unwrap().provider(), "d7networks");
+        assert!(built.client.is_some());
+        assert!(plugin.build(&json!({})).is_err());
+    }
+
+    #[test]
+    fn debug_redacts_api_token() {
+        let debug = format!("{:?}", D7Client::new("hunter2"));
+        assert!(!debug.contains("hunter2"));
+    }
+}
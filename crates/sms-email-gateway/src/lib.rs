@@ -0,0 +1,456 @@
+//! # Email-to-SMS Gateway Provider
+//!
+//! Sends SMS for free through the email gateways many carriers run, where
+//! mail to `5551234567@vtext.com` arrives on the handset as a text.
+//! [`EmailGatewayClient`] maps each destination number to its carrier's
+//! gateway domain and sends over SMTP with [lettre](https://lettre.rs/).
+//!
+//! Delivery is best effort: there are no delivery reports, carriers throttle
+//! and filter gateway mail, and gateways are retired without notice (AT&T's
+//! closed in 2025).  That makes this a fallback, behind a paid provider in a
+//! router, rather than a primary route.
+//!
+//! ## Sending messages
+//!
+//! ```rust,ignore
+//! use sms_core::{SendRequest, SmsClient};
+//! use sms_email_gateway::EmailGatewayClient;
+//!
+//! let client = EmailGatewayClient::smtp("smtp.example.com", "user", "pass", "alerts@example.com")?
+//!     .with_route("+15551234567", "verizon")
+//!     .with_default_carrier("tmobile");
+//! let response = client.send(SendRequest {
+//!     to: "+15551234567",
+//!     from: "",
+//!     text: "Disk usage above 90%",
+//!     ..Default::default()
+//! }).await?;
+//! ```
+//!
+//! A carrier can't be derived from a number, so each destination needs a
+//! route (or the default carrier).  A destination that is already an email
+//! address, such as `5551234567@vtext.com`, is used as is.
+
+use lettre::message::Mailbox;
+use lettre::message::header::ContentType;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use serde::Deserialize;
+use sms_core::{Channel, IdGenerator, SendRequest, SendResponse, SmsClient, SmsError, UuidV4};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+const PROVIDER: &str = "email_gateway";
+
+/// Default deadline for connecting to the SMTP server and each command.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Default longest text; most gateways truncate or split past one SMS.
+const DEFAULT_MAX_LEN: usize = 160;
+
+/// Gateway domains of well-known US carriers, by the name routes use.
+pub const CARRIERS: &[(&str, &str)] = &[
+    ("boost", "sms.myboostmobile.com"),
+    ("cricket", "sms.cricketwireless.net"),
+    ("googlefi", "msg.fi.google.com"),
+    ("metropcs", "mymetropcs.com"),
+    ("tmobile", "tmomail.net"),
+    ("uscellular", "email.uscc.net"),
+    ("verizon", "vtext.com"),
+    ("virgin", "vmobl.com"),
+];
+
+/// Email-to-SMS gateway client.
+///
+/// Implements [`SmsClient`] only: gateways have no inbound side worth
+/// normalizing.
+#[derive(Clone)]
+pub struct EmailGatewayClient {
+    /// Sender mailbox for the gateway mail.
+    pub from: Mailbox,
+    /// Gateway domain per carrier name, seeded from [`CARRIERS`].
+    pub carriers: HashMap<String, String>,
+    /// Carrier (name or gateway domain) per destination, keyed by the
+    /// number's digits.
+    pub routes: HashMap<String, String>,
+    /// Carrier for destinations without a route (default: none, so they
+    /// fail).
+    pub default_carrier: Option<String>,
+    /// Longest text accepted (default: 160 characters).
+    pub max_len: usize,
+    id_generator: Arc<dyn IdGenerator>,
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+impl std::fmt::Debug for EmailGatewayClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EmailGatewayClient")
+            .field("from", &self.from.to_string())
+            .field("routes", &self.routes.len())
+            .field("default_carrier", &self.default_carrier)
+            .field("max_len", &self.max_len)
+            .finish_non_exhaustive()
+    }
+}
+
+impl EmailGatewayClient {
+    /// Create a client sending as `from` through `transport`.
+    ///
+    /// Returns [`SmsError::Invalid`] if `from` is not a valid mailbox.
+    pub fn new(
+        transport: AsyncSmtpTransport<Tokio1Executor>,
+        from: &str,
+    ) -> Result<Self, SmsError> {
+        let from = from
+            .parse::<Mailbox>()
+            .map_err(|e| SmsError::Invalid(format!("sender address {}: {}", from, e)))?;
+        Ok(Self {
+            from,
+            carriers: CARRIERS
+                .iter()
+                .map(|(name, domain)| (name.to_string(), domain.to_string()))
+                .collect(),
+            routes: HashMap::new(),
+            default_carrier: None,
+            max_len: DEFAULT_MAX_LEN,
+            id_generator: Arc::new(UuidV4),
+            transport,
+        })
+    }
+
+    /// Create a client for the SMTP relay at `host`, using implicit TLS on
+    /// port 465 and logging in with `username` and `password`.
+    pub fn smtp(
+        host: &str,
+        username: impl Into<String>,
+        password: impl Into<String>,
+        from: &str,
+    ) -> Result<Self, SmsError> {
+        let transport = AsyncSmtpTransport::<Tokio1Executor>::relay(host)
+            .map_err(|e| SmsError::Invalid(format!("SMTP host {}: {}", host, e)))?
+            .credentials(Credentials::new(username.into(), password.into()))
+            .timeout(Some(DEFAULT_TIMEOUT))
+            .build();
+        Self::new(transport, from)
+    }
+
+    /// Create a client by reading settings from environment variables.
+    ///
+    /// | Variable                      | Required | Notes |
+    /// |-------------------------------|----------|-------|
+    /// | `SMTP_HOST`                   | Yes      | Relay with implicit TLS |
+    /// | `SMTP_USERNAME`               | Yes      | |
+    /// | `SMTP_PASSWORD`               | Yes      | |
+    /// | `EMAIL_GATEWAY_FROM`          | Yes      | Sender mailbox |
+    /// | `EMAIL_GATEWAY_DEFAULT_CARRIER` | No     | Carrier name or domain |
+    ///
+    /// Returns [`SmsError::Auth`] if a required variable is missing.
+    pub fn from_env() -> Result<Self, SmsError> {
+        let var = |name: &str| {
+            std::env::var(name).map_err(|_| SmsError::Auth(format!("{} not set", name)))
+        };
+        let client = Self::smtp(
+            &var("SMTP_HOST")?,
+            var("SMTP_USERNAME")?,
+            var("SMTP_PASSWORD")?,
+            &var("EMAIL_GATEWAY_FROM")?,
+        )?;
+        Ok(match std::env::var("EMAIL_GATEWAY_DEFAULT_CARRIER") {
+            Ok(carrier) => client.with_default_carrier(carrier),
+            Err(_) => client,
+        })
+    }
+
+    /// Add or replace the gateway `domain` of carrier `name`.
+    pub fn with_carrier(mut self, name: impl Into<String>, domain: impl Into<String>) -> Self {
+        self.carriers.insert(name.into(), domain.into());
+        self
+    }
+
+    /// Send to `number` through `carrier`, a carrier name or gateway
+    /// domain.
+    pub fn with_route(mut self, number: &str, carrier: impl Into<String>) -> Self {
+        self.routes.insert(local_part(number), carrier.into());
+        self
+    }
+
+    /// Send to numbers without a route through `carrier`, a carrier name
+    /// or gateway domain.
+    pub fn with_default_carrier(mut self, carrier: impl Into<String>) -> Self {
+        self.default_carrier = Some(carrier.into());
+        self
+    }
+
+    /// Accept text up to `max_len` characters (default: 160).
+    pub fn with_max_len(mut self, max_len: usize) -> Self {
+        self.max_len = max_len;
+        self
+    }
+
+    /// Generate message IDs with `ids` (default: [`UuidV4`]).
+    pub fn with_id_generator(mut self, ids: impl IdGenerator + 'static) -> Self {
+        self.id_generator = Arc::new(ids);
+        self
+    }
+
+    /// The gateway address for `to`.
+    ///
+    /// Returns [`SmsError::Invalid`] if `to` has no route and there is no
+    /// default carrier, or the route names an unknown carrier.
+    pub fn gateway_address(&self, to: &str) -> Result<String, SmsError> {
+        if to.contains('@') {
+            return Ok(to.to_string());
+        }
+        let number = local_part(to);
+        if number.is_empty() {
+            return Err(SmsError::Invalid(format!("{} is not a phone number", to)));
+        }
+        let carrier = self
+            .routes
+            .get(&number)
+            .or(self.default_carrier.as_ref())
+            .ok_or_else(|| {
+                SmsError::Invalid(format!(
+                    "no carrier gateway for {}",
+                    sms_core::redact_number(to)
+                ))
+            })?;
+        let domain = match self.carriers.get(carrier) {
+            Some(domain) => domain,
+            None if carrier.contains('.') => carrier,
+            None => return Err(SmsError::Invalid(format!("unknown carrier {}", carrier))),
+        };
+        Ok(format!("{}@{}", number, domain))
+    }
+
+    /// Build the gateway mail for `text` to `address`.
+    fn message(&self, address: &str, text: &str, id: &str) -> Result<Message, SmsError> {
+        let to = address
+            .parse::<Mailbox>()
+            .map_err(|e| SmsError::Invalid(format!("gateway address {}: {}", address, e)))?;
+        Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .message_id(Some(format!("<{}@smskit>", id)))
+            .header(ContentType::TEXT_PLAIN)
+            .body(text.to_string())
+            .map_err(|e| SmsError::Invalid(format!("gateway mail: {}", e)))
+    }
+}
+
+/// The mailbox local part for `number`: its digits, without the `1`
+/// country code gateways don't expect on North American numbers.
+fn local_part(number: &str) -> String {
+    let digits: String = number.chars().filter(char::is_ascii_digit).collect();
+    match digits.strip_prefix('1') {
+        Some(national) if digits.len() == 11 => national.to_string(),
+        _ => digits,
+    }
+}
+
+/// Map an SMTP failure to an [`SmsError`].
+fn smtp_error(e: lettre::transport::smtp::Error) -> SmsError {
+    let code = e.status().map(|code| code.to_string());
+    match code.as_deref() {
+        _ if e.is_timeout() => SmsError::Timeout(e.to_string()),
+        Some("530" | "534" | "535") => SmsError::Auth(e.to_string()),
+        Some(_) if e.is_permanent() => SmsError::Invalid(format!("SMTP: {}", e)),
+        Some(_) => SmsError::Provider(format!("SMTP: {}", e)),
+        None => SmsError::Http(e.to_string()),
+    }
+}
+
+impl SmsClient for EmailGatewayClient {
+    async fn send(&self, req: SendRequest<'_>) -> Result<SendResponse, SmsError> {
+        sms_core::validate_text(req.text, self.max_text_len())?;
+        let address = self.gateway_address(req.to)?;
+        let id = self.id_generator.generate();
+        let message = self.message(&address, req.text, &id)?;
+        let sent = self.transport.send(message);
+        let response = match req.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, sent)
+                .await
+                .map_err(|_| SmsError::Timeout(format!("sending to {}", address)))?,
+            None => sent.await,
+        }
+        .map_err(smtp_error)?;
+
+        Ok(SendResponse {
+            raw: serde_json::json!({
+                "to": address,
+                "smtp_code": response.code().to_string(),
+                "smtp_response": response.message().collect::<Vec<_>>(),
+            }),
+            id,
+            provider: PROVIDER,
+            channel: Channel::Sms,
+            http_status: None,
+            provider_request_id: None,
+            thread_id: Some(req.thread_id()),
+        })
+    }
+
+    fn max_text_len(&self) -> Option<usize> {
+        Some(self.max_len)
+    }
+}
+
+/// The `email_gateway` configuration section read by the provider plugin.
+#[derive(Deserialize)]
+struct EmailGatewaySettings {
+    host: String,
+    port: Option<u16>,
+    username: String,
+    password: String,
+    from: String,
+    default_carrier: Option<String>,
+    #[serde(default)]
+    carriers: HashMap<String, String>,
+    #[serde(default)]
+    routes: HashMap<String, String>,
+    max_len: Option<usize>,
+}
+
+/// Build an [`EmailGatewayClient`] from the `email_gateway` section.
+fn build_plugin(settings: &serde_json::Value) -> Result<sms_core::BuiltProvider, SmsError> {
+    let s: EmailGatewaySettings = sms_core::provider_settings(PROVIDER, settings)?;
+    let mut transport = AsyncSmtpTransport::<Tokio1Executor>::relay(&s.host)
+        .map_err(|e| SmsError::Invalid(format!("SMTP host {}: {}", s.host, e)))?
+        .credentials(Credentials::new(s.username, s.password))
+        .timeout(Some(DEFAULT_TIMEOUT));
+    if let Some(port) = s.port {
+        transport = transport.port(port);
+    }
+    let mut client = EmailGatewayClient::new(transport.build(), &s.from)?;
+    for (name, domain) in s.carriers {
+        client = client.with_carrier(name, domain);
+    }
+    for (number, carrier) in s.routes {
+        client = client.with_route(&number, carrier);
+    }
+    if let Some(carrier) = s.default_carrier {
+        client = client.with_default_carrier(carrier);
+    }
+    if let Some(max_len) = s.max_len {
+        client = client.with_max_len(max_len);
+    }
+    Ok(sms_core::BuiltProvider::sender(client))
+}
+
+sms_core::register_provider!("email_gateway", build_plugin);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn client() -> EmailGatewayClient {
+        let transport = AsyncSmtpTransport::<Tokio1Executor>::unencrypted_localhost();
+        EmailGatewayClient::new(transport, "Alerts <alerts@example.com>").unwrap()
+    }
+
+    #[test]
+    fn resolves_gateway_addresses() {
+        let client = client()
+            .with_route("+1 (555) 123-4567", "verizon")
+            .with_route("+15559876543", "sms.example.net");
+        assert_eq!(
+            client.gateway_address("+15551234567").unwrap(),
+            "5551234567@vtext.com"
+        );
+        assert_eq!(
+            client.gateway_address("15559876543").unwrap(),
+            "5559876543@sms.example.net"
+        );
+        assert_eq!(
+            client.gateway_address("5550001111@tmomail.net").unwrap(),
+            "5550001111@tmomail.net"
+        );
+        assert!(matches!(
+            client.gateway_address("+15550001111"),
+            Err(SmsError::Invalid(_))
+        ));
+
+        let client = client.with_default_carrier("tmobile");
+        assert_eq!(
+            client.gateway_address("+15550001111").unwrap(),
+            "5550001111@tmomail.net"
+        );
+        let client = client.with_route("+15550002222", "nosuchcarrier");
+        assert!(client.gateway_address("+15550002222").is_err());
+    }
+
+    #[test]
+    fn custom_carriers_override_built_ins() {
+        let client = client()
+            .with_carrier("verizon", "vzwpix.com")
+            .with_default_carrier("verizon");
+        assert_eq!(
+            client.gateway_address("5551234567").unwrap(),
+            "5551234567@vzwpix.com"
+        );
+    }
+
+    #[test]
+    fn keeps_international_numbers_whole() {
+        assert_eq!(local_part("+447700900000"), "447700900000");
+        assert_eq!(local_part("+1 555 123 4567"), "5551234567");
+    }
+
+    #[test]
+    fn builds_plain_text_mail() {
+        let message = client()
+            .message("5551234567@vtext.com", "Disk full", "abc")
+            .unwrap();
+        let formatted = String::from_utf8(message.formatted()).unwrap();
+        assert!(formatted.contains("To: 5551234567@vtext.com"));
+        assert!(formatted.contains("Message-ID: <abc@smskit>"));
+        assert!(formatted.contains("Content-Type: text/plain"));
+        assert!(formatted.contains("Disk full"));
+    }
+
+    #[tokio::test]
+    async fn rejects_text_past_max_len() {
+        let client = client().with_default_carrier("verizon").with_max_len(10);
+        let req = SendRequest {
+            to: "+15551234567",
+            from: "",
+            text: "this is longer than ten",
+            ..Default::default()
+        };
+        assert!(matches!(client.send(req).await, Err(SmsError::Invalid(_))));
+    }
+
+    #[test]
+    fn plugin_builds_a_sender() {
+        let plugin = sms_core::ProviderPlugin::find("email_gateway").unwrap();
+        let built = plugin
+            .build(&json!({
+                "host": "smtp.example.com",
+                "port": 587,
+                "username": "user",
+                "password": "pass",
+                "from": "alerts@example.com",
+                "routes": { "+15551234567": "verizon" }
+            }))
+            .unwrap();
+        assert!(built.client.is_some());
+        assert!(built.webhook.is_none());
+        assert!(
+            plugin
+                .build(&json!({ "host": "smtp.example.com" }))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn debug_omits_transport_credentials() {
+        let client =
+            EmailGatewayClient::smtp("smtp.example.com", "user", "hunter2", "a@example.com")
+                .unwrap();
+        let debug = format!("{:?}", client);
+        assert!(debug.contains("a@example.com"));
+        assert!(!debug.contains("hunter2"));
+    }
+}
@@ -0,0 +1,662 @@
+//! # Generic HTTP Provider
+//!
+//! Many regional aggregators expose a single "send SMS" endpoint that takes
+//! the destination, sender and text in the URL or a small body and answers
+//! with a message ID somewhere in a JSON document.  [`HttpGenericClient`]
+//! covers them from configuration alone: a URL template, an [`AuthScheme`],
+//! an optional [`BodyTemplate`] and a JSONPath to the returned ID.
+//!
+//! ```rust,ignore
+//! use sms_core::SmsClient;
+//! use sms_http_generic::{AuthScheme, BodyTemplate, HttpGenericClient};
+//!
+//! let client = HttpGenericClient::new("https://api.example-sms.net/v2/messages")
+//!     .with_auth(AuthScheme::bearer("YOUR_TOKEN"))
+//!     .with_body(BodyTemplate::json(r#"{"to":"{to}","sender":"{from}","message":"{text}"}"#))
+//!     .with_id_path("$.data.messages[0].id");
+//! let response = client.send(req.as_ref()).await?;
+//! ```
+//!
+//! ## Templates
+//!
+//! The URL and body may contain these placeholders:
+//!
+//! | Placeholder   | Value                                   |
+//! |---------------|-----------------------------------------|
+//! | `{to}`        | [`SendRequest::to`]                     |
+//! | `{from}`      | [`SendRequest::from`]                   |
+//! | `{text}`      | [`SendRequest::text`]                   |
+//! | `{id}`        | The ID generated for this send          |
+//! | `{thread_id}` | [`SendRequest::thread_id`]              |
+//!
+//! Values are escaped for where they land: form-encoded in the URL and in
+//! [`BodyFormat::Form`] bodies, JSON-string-escaped (without the quotes) in
+//! [`BodyFormat::Json`] bodies, and inserted verbatim in
+//! [`BodyFormat::Text`] bodies.  Any other brace is copied through, so JSON
+//! templates need no escaping of their own.
+//!
+//! ## Response IDs
+//!
+//! The ID path accepts simple JSONPath (`$.messages[0].id`) or the dotted
+//! form used elsewhere in smskit (`messages.0.id`).  If the path is unset
+//! or the response lacks it, the generated ID is returned instead.
+
+use serde::Deserialize;
+use sms_core::{
+    Channel, ExposeSecret, HttpClientConfig, IdGenerator, SecretString, SendRequest, SendResponse,
+    SmsClient, SmsError, UuidV4,
+};
+use sms_core::http_provider::transport_error;
+use std::sync::Arc;
+use std::time::Duration;
+
+const PROVIDER: &str = "http_generic";
+
+/// Default deadline for a single API call.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// HTTP method for the send request.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum HttpMethod {
+    /// `GET`; usually paired with a URL-only template and no body.
+    Get,
+    /// `POST` (the default).
+    #[default]
+    Post,
+    /// `PUT`.
+    Put,
+}
+
+impl HttpMethod {
+    fn as_reqwest(self) -> reqwest::Method {
+        match self {
+            HttpMethod::Get => reqwest::Method::GET,
+            HttpMethod::Post => reqwest::Method::POST,
+            HttpMethod::Put => reqwest::Method::PUT,
+        }
+    }
+}
+
+/// How the send request authenticates.
+#[derive(Clone, Default, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AuthScheme {
+    /// No credentials, e.g. a key already baked into the URL template.
+    #[default]
+    None,
+    /// HTTP Basic auth.
+    Basic {
+        /// Username.
+        username: String,
+        /// Password.  Zeroized on drop.
+        password: SecretString,
+    },
+    /// `Authorization: Bearer <token>`.
+    Bearer {
+        /// Token.  Zeroized on drop.
+        token: SecretString,
+    },
+    /// A custom header such as `X-Api-Key`.
+    Header {
+        /// Header name.
+        name: String,
+        /// Header value.  Zeroized on drop.
+        value: SecretString,
+    },
+    /// A query parameter such as `?api_key=...`, appended to the URL.
+    Query {
+        /// Parameter name.
+        name: String,
+        /// Parameter value.  Zeroized on drop.
+        value: SecretString,
+    },
+}
+
+impl AuthScheme {
+    /// HTTP Basic auth.
+    pub fn basic(username: impl Into<String>, password: impl Into<String>) -> Self {
+        AuthScheme::Basic {
+            username: username.into(),
+            password: SecretString::from(password.into()),
+        }
+    }
+
+    /// Bearer token auth.
+    pub fn bearer(token: impl Into<String>) -> Self {
+        AuthScheme::Bearer {
+            token: SecretString::from(token.into()),
+        }
+    }
+
+    /// A key in a custom header.
+    pub fn header(name: impl Into<String>, value: impl Into<String>) -> Self {
+        AuthScheme::Header {
+            name: name.into(),
+            value: SecretString::from(value.into()),
+        }
+    }
+
+    /// A key in a query parameter.
+    pub fn query(name: impl Into<String>, value: impl Into<String>) -> Self {
+        AuthScheme::Query {
+            name: name.into(),
+            value: SecretString::from(value.into()),
+        }
+    }
+}
+
+impl std::fmt::Debug for AuthScheme {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuthScheme::None => f.write_str("None"),
+            AuthScheme::Basic { username, .. } => f
+                .debug_struct("Basic")
+                .field("username", username)
+                .field("password", &"[REDACTED]")
+                .finish(),
+            AuthScheme::Bearer { .. } => f
+                .debug_struct("Bearer")
+                .field("token", &"[REDACTED]")
+                .finish(),
+            AuthScheme::Header { name, .. } => f
+                .debug_struct("Header")
+                .field("name", name)
+                .field("value", &"[REDACTED]")
+                .finish(),
+            AuthScheme::Query { name, .. } => f
+                .debug_struct("Query")
+                .field("name", name)
+                .field("value", &"[REDACTED]")
+                .finish(),
+        }
+    }
+}
+
+/// How a [`BodyTemplate`] is escaped and labelled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BodyFormat {
+    /// `application/json`; values are JSON-string-escaped.
+    Json,
+    /// `application/x-www-form-urlencoded`; values are form-encoded.
+    Form,
+    /// `text/plain`; values are inserted verbatim.
+    Text,
+}
+
+impl BodyFormat {
+    fn content_type(self) -> &'static str {
+        match self {
+            BodyFormat::Json => "application/json",
+            BodyFormat::Form => "application/x-www-form-urlencoded",
+            BodyFormat::Text => "text/plain; charset=utf-8",
+        }
+    }
+}
+
+/// The request body, as a template with `{placeholder}` slots.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct BodyTemplate {
+    /// Template text.
+    pub template: String,
+    /// Escaping and `Content-Type`.
+    pub format: BodyFormat,
+}
+
+impl BodyTemplate {
+    /// A JSON body, e.g. `{"to":"{to}","text":"{text}"}`.
+    pub fn json(template: impl Into<String>) -> Self {
+        Self {
+            template: template.into(),
+            format: BodyFormat::Json,
+        }
+    }
+
+    /// A form body, e.g. `to={to}&text={text}`.
+    pub fn form(template: impl Into<String>) -> Self {
+        Self {
+            template: template.into(),
+            format: BodyFormat::Form,
+        }
+    }
+
+    /// A plain-text body.
+    pub fn text(template: impl Into<String>) -> Self {
+        Self {
+            template: template.into(),
+            format: BodyFormat::Text,
+        }
+    }
+}
+
+/// A configurable client for simple REST SMS APIs.
+///
+/// Implements [`SmsClient`] only; inbound webhooks vary too much between
+/// providers to describe generically, so route them through
+/// `sms-web-generic` with a custom parser.
+#[derive(Clone)]
+pub struct HttpGenericClient {
+    /// Endpoint URL template.
+    pub url_template: String,
+    /// HTTP method (default: `POST`).
+    pub method: HttpMethod,
+    /// Request authentication (default: none).
+    pub auth: AuthScheme,
+    /// Request body; `None` sends no body.
+    pub body: Option<BodyTemplate>,
+    /// JSONPath to the message ID in the response.
+    pub id_path: Option<String>,
+    /// Longest text the upstream accepts, if it is known.
+    pub max_len: Option<usize>,
+    /// Deadline for each API call unless the request sets its own
+    /// [`SendRequest::timeout`].
+    pub timeout: Duration,
+    id_generator: Arc<dyn IdGenerator>,
+    http: reqwest::Client,
+}
+
+impl std::fmt::Debug for HttpGenericClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HttpGenericClient")
+            .field("url_template", &self.url_template)
+            .field("method", &self.method)
+            .field("auth", &self.auth)
+            .field("body", &self.body)
+            .field("id_path", &self.id_path)
+            .field("max_len", &self.max_len)
+            .field("timeout", &self.timeout)
+            .finish_non_exhaustive()
+    }
+}
+
+impl HttpGenericClient {
+    /// Create a client that `POST`s to `url_template` with no auth and no
+    /// body.
+    pub fn new(url_template: impl Into<String>) -> Self {
+        Self {
+            url_template: url_template.into(),
+            method: HttpMethod::Post,
+            auth: AuthScheme::None,
+            body: None,
+            id_path: None,
+            max_len: None,
+            timeout: DEFAULT_TIMEOUT,
+            id_generator: Arc::new(UuidV4),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Create a client by reading its configuration from environment
+    /// variables.
+    ///
+    /// | Variable                 | Maps to                                  |
+    /// |--------------------------|------------------------------------------|
+    /// | `SMS_HTTP_URL`           | `url_template`                           |
+    /// | `SMS_HTTP_METHOD`        | `method` (optional)                      |
+    /// | `SMS_HTTP_BEARER_TOKEN`  | [`AuthScheme::Bearer`] (optional)        |
+    /// | `SMS_HTTP_BODY_JSON`     | [`BodyTemplate::json`] (optional)        |
+    /// | `SMS_HTTP_BODY_FORM`     | [`BodyTemplate::form`] (optional)        |
+    /// | `SMS_HTTP_ID_PATH`       | `id_path` (optional)                     |
+    ///
+    /// Returns [`SmsError::Auth`] if `SMS_HTTP_URL` is missing and
+    /// [`SmsError::Invalid`] for an unknown method.
+    pub fn from_env() -> Result<Self, SmsError> {
+        let url = std::env::var("SMS_HTTP_URL")
+            .map_err(|_| SmsError::Auth("SMS_HTTP_URL not set".into()))?;
+        let mut client = Self::new(url);
+        if let Ok(method) = std::env::var("SMS_HTTP_METHOD") {
+            client.method = match method.to_ascii_uppercase().as_str() {
+                "GET" => HttpMethod::Get,
+                "POST" => HttpMethod::Post,
+                "PUT" => HttpMethod::Put,
+                _ => return Err(SmsError::Invalid(format!("SMS_HTTP_METHOD: {}", method))),
+            };
+        }
+        if let Ok(token) = std::env::var("SMS_HTTP_BEARER_TOKEN") {
+            client.auth = AuthScheme::bearer(token);
+        }
+        if let Ok(template) = std::env::var("SMS_HTTP_BODY_JSON") {
+            client.body = Some(BodyTemplate::json(template));
+        } else if let Ok(template) = std::env::var("SMS_HTTP_BODY_FORM") {
+            client.body = Some(BodyTemplate::form(template));
+        }
+        client.id_path = std::env::var("SMS_HTTP_ID_PATH").ok();
+        Ok(client)
+    }
+
+    /// Use `method` for the send request (default: `POST`).
+    pub fn with_method(mut self, method: HttpMethod) -> Self {
+        self.method = method;
+        self
+    }
+
+    /// Authenticate with `auth` (default: [`AuthScheme::None`]).
+    pub fn with_auth(mut self, auth: AuthScheme) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    /// Send `body` with each request.
+    pub fn with_body(mut self, body: BodyTemplate) -> Self {
+        self.body = Some(body);
+        self
+    }
+
+    /// Read the message ID from `path` in the JSON response, e.g.
+    /// `$.messages[0].id`.
+    pub fn with_id_path(mut self, path: impl Into<String>) -> Self {
+        self.id_path = Some(path.into());
+        self
+    }
+
+    /// Reject texts longer than `max_len` characters before sending.
+    pub fn with_max_len(mut self, max_len: usize) -> Self {
+        self.max_len = Some(max_len);
+        self
+    }
+
+    /// Set the default deadline for each API call (default: 30 seconds).
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Rebuild the HTTP client with custom transport settings (proxy,
+    /// `User-Agent`, extra headers, etc.).
+    pub fn with_http_config(mut self, config: HttpClientConfig) -> Result<Self, SmsError> {
+        self.http = sms_core::http_provider::client_with(&config)?;
+        Ok(self)
+    }
+
+    /// Generate IDs with `ids` (default: [`UuidV4`]).
+    pub fn with_id_generator(mut self, ids: impl IdGenerator + 'static) -> Self {
+        self.id_generator = Arc::new(ids);
+        self
+    }
+
+    /// The URL for one send, with the query-parameter credential appended.
+    fn url(&self, vars: &Vars<'_>) -> String {
+        let mut url = render(&self.url_template, vars, form_escape);
+        if let AuthScheme::Query { name, value } = &self.auth {
+            url.push(if url.contains('?') { '&' } else { '?' });
+            url.push_str(&form_escape(name));
+            url.push('=');
+            url.push_str(&form_escape(value.expose_secret()));
+        }
+        url
+    }
+}
+
+impl SmsClient for HttpGenericClient {
+    async fn send(&self, req: SendRequest<'_>) -> Result<SendResponse, SmsError> {
+        sms_core::validate_text(req.text, self.max_text_len())?;
+        let id = self.id_generator.generate();
+        let thread_id = req.thread_id();
+        let vars = Vars {
+            to: req.to,
+            from: req.from,
+            text: req.text,
+            id: &id,
+            thread_id: &thread_id,
+        };
+
+        let mut builder = self
+            .http
+            .request(self.method.as_reqwest(), self.url(&vars))
+            .timeout(req.timeout.unwrap_or(self.timeout));
+        builder = match &self.auth {
+            AuthScheme::None | AuthScheme::Query { .. } => builder,
+            AuthScheme::Basic { username, password } => {
+                builder.basic_auth(username, Some(password.expose_secret()))
+            }
+            AuthScheme::Bearer { token } => builder.bearer_auth(token.expose_secret()),
+            AuthScheme::Header { name, value } => {
+                builder.header(name.as_str(), value.expose_secret())
+            }
+        };
+        if let Some(body) = &self.body {
+            let escape = match body.format {
+                BodyFormat::Json => json_escape,
+                BodyFormat::Form => form_escape,
+                BodyFormat::Text => verbatim,
+            };
+            builder = builder
+                .header(reqwest::header::CONTENT_TYPE, body.format.content_type())
+                .body(render(&body.template, &vars, escape));
+        }
+        let res = builder.send().await.map_err(transport_error)?;
+
+        let status = res.status();
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = res
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(sms_core::parse_retry_after);
+            return Err(SmsError::RateLimited { retry_after });
+        }
+        let raw_text = res.text().await.map_err(transport_error)?;
+        if status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN {
+            return Err(SmsError::Auth(raw_text));
+        }
+        if !status.is_success() {
+            return Err(SmsError::Provider(format!("HTTP {}: {}", status, raw_text)));
+        }
+
+        let raw: serde_json::Value = serde_json::from_str(&raw_text)
+            .unwrap_or_else(|_| serde_json::json!({ "raw": raw_text }));
+        let provider_id = self
+            .id_path
+            .as_deref()
+            .and_then(|path| response_id(&raw, path));
+
+        Ok(SendResponse {
+            id: provider_id.unwrap_or(id),
+            provider: PROVIDER,
+            channel: Channel::Sms,
+            http_status: Some(status.as_u16()),
+            provider_request_id: None,
+            thread_id: Some(thread_id),
+            raw,
+        })
+    }
+
+    fn max_text_len(&self) -> Option<usize> {
+        self.max_len
+    }
+}
+
+/// Placeholder values for one send.
+struct Vars<'a> {
+    to: &'a str,
+    from: &'a str,
+    text: &'a str,
+    id: &'a str,
+    thread_id: &'a str,
+}
+
+impl Vars<'_> {
+    fn get(&self, name: &str) -> Option<&str> {
+        match name {
+            "to" => Some(self.to),
+            "from" => Some(self.from),
+            "text" => Some(self.text),
+            "id" => Some(self.id),
+            "thread_id" => Some(self.thread_id),
+            _ => None,
+        }
+    }
+}
+
+/// Substitute `{name}` placeholders in one pass, so values containing
+/// braces are never expanded again.  Unknown names are copied through.
+fn render(template: &str, vars: &Vars<'_>, escape: fn(&str) -> String) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        out.push_str(&rest[..open]);
+        let after = &rest[open + 1..];
+        let value = after
+            .find('}')
+            .and_then(|close| Some((close, vars.get(&after[..close])?)));
+        match value {
+            Some((close, value)) => {
+                out.push_str(&escape(value));
+                rest = &after[close + 1..];
+            }
+            None => {
+                out.push('{');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn form_escape(value: &str) -> String {
+    form_urlencoded::byte_serialize(value.as_bytes()).collect()
+}
+
+fn json_escape(value: &str) -> String {
+    let quoted = serde_json::Value::from(value).to_string();
+    quoted[1..quoted.len() - 1].to_string()
+}
+
+fn verbatim(value: &str) -> String {
+    value.to_string()
+}
+
+/// Read the ID at a JSONPath (`$.a[0].b`) or dotted path (`a.0.b`).
+fn response_id(raw: &serde_json::Value, path: &str) -> Option<String> {
+    let dotted = path
+        .trim_start_matches('$')
+        .replace('[', ".")
+        .replace(']', "");
+    match sms_core::http_provider::json_path(raw, &dotted)? {
+        serde_json::Value::String(s) => Some(s.clone()),
+        serde_json::Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// The `http_generic` configuration section read by the provider plugin.
+#[derive(Deserialize)]
+struct HttpGenericSettings {
+    url: String,
+    #[serde(default)]
+    method: HttpMethod,
+    #[serde(default)]
+    auth: AuthScheme,
+    body: Option<BodyTemplate>,
+    id_path: Option<String>,
+    max_text_len: Option<usize>,
+}
+
+/// Build an [`HttpGenericClient`] from the `http_generic` section.
+fn build_plugin(settings: &serde_json::Value) -> Result<sms_core::BuiltProvider, SmsError> {
+    let s: HttpGenericSettings = sms_core::provider_settings(PROVIDER, settings)?;
+    let mut client = HttpGenericClient::new(s.url)
+        .with_method(s.method)
+        .with_auth(s.auth);
+    client.body = s.body;
+    client.id_path = s.id_path;
+    client.max_len = s.max_text_len;
+    Ok(sms_core::BuiltProvider::sender(client))
+}
+
+sms_core::register_provider!("http_generic", build_plugin);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn vars() -> Vars<'static> {
+        Vars {
+            to: "+15551230000",
+            from: "Acme & Co",
+            text: "Say \"hi\" {to}\n",
+            id: "abc",
+            thread_id: "t1",
+        }
+    }
+
+    #[test]
+    fn renders_each_format() {
+        let vars = vars();
+        assert_eq!(
+            render(
+                r#"{"to":"{to}","from":"{from}","text":"{text}","ref":"{id}"}"#,
+                &vars,
+                json_escape
+            ),
+            r#"{"to":"+15551230000","from":"Acme & Co","text":"Say \"hi\" {to}\n","ref":"abc"}"#
+        );
+        assert_eq!(
+            render("to={to}&from={from}", &vars, form_escape),
+            "to=%2B15551230000&from=Acme+%26+Co"
+        );
+        assert_eq!(
+            render("{thread_id} {unknown} {", &vars, verbatim),
+            "t1 {unknown} {"
+        );
+    }
+
+    #[test]
+    fn query_auth_is_appended() {
+        let vars = vars();
+        let client = HttpGenericClient::new("https://sms.example/send?to={to}")
+            .with_auth(AuthScheme::query("api_key", "k&y"));
+        assert_eq!(
+            client.url(&vars),
+            "https://sms.example/send?to=%2B15551230000&api_key=k%26y"
+        );
+        let client = HttpGenericClient::new("https://sms.example/send")
+            .with_auth(AuthScheme::query("key", "k"));
+        assert_eq!(client.url(&vars), "https://sms.example/send?key=k");
+    }
+
+    #[test]
+    fn reads_ids_by_path() {
+        let raw = json!({ "data": { "messages": [{ "id": 42 }, { "id": "b" }] } });
+        assert_eq!(
+            response_id(&raw, "$.data.messages[0].id").as_deref(),
+            Some("42")
+        );
+        assert_eq!(
+            response_id(&raw, "data.messages.1.id").as_deref(),
+            Some("b")
+        );
+        assert_eq!(response_id(&raw, "$.data.missing"), None);
+        assert_eq!(response_id(&raw, "$.data"), None);
+    }
+
+    #[test]
+    fn debug_redacts_credentials() {
+        let client = HttpGenericClient::new("https://sms.example/send")
+            .with_auth(AuthScheme::basic("user", "hunter2"));
+        let debug = format!("{:?}", client);
+        assert!(debug.contains("user"));
+        assert!(!debug.contains("hunter2"));
+    }
+
+    #[test]
+    fn plugin_builds_from_settings() {
+        let plugin = sms_core::ProviderPlugin::find("http_generic").unwrap();
+        let built = plugin
+            .build(&json!({
+                "url": "https://sms.example/send",
+                "method": "PUT",
+                "auth": { "type": "header", "name": "X-Api-Key", "value": "secret" },
+                "body": { "template": "to={to}&msg={text}", "format": "form" },
+                "id_path": "$.id",
+                "max_text_len": 459,
+            }))
+            .unwrap();
+        assert!(built.client.is_some());
+        assert!(built.webhook.is_none());
+        assert!(plugin.build(&json!({ "method": "GET" })).is_err());
+    }
+}
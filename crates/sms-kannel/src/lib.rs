@@ -0,0 +1,594 @@
+//! # Kannel Provider
+//!
+//! Backend for a self-hosted [Kannel](https://www.kannel.org/) gateway.
+//! [`KannelClient`] sends through the smsbox `sendsms` HTTP interface and
+//! parses the callbacks Kannel makes for received messages and delivery
+//! reports.
+//!
+//! ## Sending messages
+//!
+//! ```rust,ignore
+//! use sms_core::{SendRequest, SmsClient};
+//! use sms_kannel::KannelClient;
+//!
+//! let client = KannelClient::new("http://kannel.internal:13013", "tester", "foobar")
+//!     .with_dlr_url("https://app.example.com/webhooks/kannel");
+//! let response = client.send(SendRequest {
+//!     to: "+447700900000",
+//!     from: "MyBrand",
+//!     text: "Hello via Kannel!",
+//!     ..Default::default()
+//! }).await?;
+//! ```
+//!
+//! `sendsms` does not return a message ID, so the client generates one and
+//! embeds it in the `dlr-url` it hands Kannel; delivery reports come back
+//! carrying that ID.
+//!
+//! ## Webhooks
+//!
+//! Point an `sms-service` at the unified endpoint with either
+//!
+//! * `post-url`: Kannel POSTs the text as the body with the details in
+//!   `X-Kannel-*` headers, which [`InboundWebhook::parse_inbound`] reads
+//!   directly; or
+//! * `get-url` ending in [`GET_URL_QUERY`]: Kannel makes a GET request, and
+//!   the route must pass the query string as the body.
+//!
+//! Delivery reports always arrive as GET requests to the `dlr-url`, so the
+//! route handling them must likewise pass the query string as the body.
+
+use serde::{Deserialize, Serialize};
+use sms_core::{
+    Channel, ExposeSecret, Headers, HttpClientConfig, IdGenerator, InboundDirection,
+    InboundMessage, InboundWebhook, SecretString, SendRequest, SendResponse, SmsClient, SmsError,
+    UuidV4,
+};
+use sms_core::http_provider::transport_error;
+use std::sync::Arc;
+use std::time::Duration;
+
+const PROVIDER: &str = "kannel";
+
+/// Default deadline for a single API call.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Query string for an `sms-service` `get-url`, naming the fields
+/// [`KannelInbound`] reads.
+pub const GET_URL_QUERY: &str = "from=%p&to=%P&text=%a&ts=%T&smsc=%i&id=%I";
+
+/// Query string appended to the `dlr-url`, after the message ID, naming the
+/// fields [`KannelDeliveryReport`] reads.
+const DLR_URL_QUERY: &str = "type=%d&to=%p&smsc=%i&reply=%A&ts=%T";
+
+/// `dlr-mask` requesting every report type: delivered, failed, buffered,
+/// SMSC accepted and SMSC rejected.
+pub const DLR_MASK_ALL: u8 = 31;
+
+/// Kannel smsbox `sendsms` client.
+///
+/// Implements [`SmsClient`] for sending SMS and [`InboundWebhook`] for
+/// receiving inbound messages and delivery reports.
+#[derive(Clone)]
+pub struct KannelClient {
+    /// smsbox base URL, e.g. `http://kannel.internal:13013`.
+    pub base_url: String,
+    /// `sendsms-user` username.
+    pub username: String,
+    /// `sendsms-user` password.  Zeroized on drop.
+    pub password: SecretString,
+    /// SMSC to route through, for gateways with several (default: Kannel
+    /// picks).
+    pub smsc: Option<String>,
+    /// Webhook URL delivery reports are sent to (default: none, so no
+    /// reports are requested).
+    pub dlr_url: Option<String>,
+    /// Report types to request when [`dlr_url`](Self::dlr_url) is set
+    /// (default: [`DLR_MASK_ALL`]).
+    pub dlr_mask: u8,
+    /// Deadline for each API call unless the request sets its own
+    /// [`SendRequest::timeout`].
+    pub timeout: Duration,
+    id_generator: Arc<dyn IdGenerator>,
+    http: reqwest::Client,
+}
+
+impl std::fmt::Debug for KannelClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KannelClient")
+            .field("base_url", &self.base_url)
+            .field("username", &self.username)
+            .field("password", &"[REDACTED]")
+            .field("smsc", &self.smsc)
+            .field("dlr_url", &self.dlr_url)
+            .field("timeout", &self.timeout)
+            .finish_non_exhaustive()
+    }
+}
+
+impl KannelClient {
+    /// Create a client for the smsbox at `base_url`.
+    pub fn new(
+        base_url: impl Into<String>,
+        username: impl Into<String>,
+        password: impl Into<String>,
+    ) -> Self {
+        Self {
+            base_url: base_url.into(),
+            username: username.into(),
+            password: SecretString::from(password.into()),
+            smsc: None,
+            dlr_url: None,
+            dlr_mask: DLR_MASK_ALL,
+            timeout: DEFAULT_TIMEOUT,
+            id_generator: Arc::new(UuidV4),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Create a client by reading settings from environment variables.
+    ///
+    /// | Variable          | Required | Maps to    |
+    /// |-------------------|----------|------------|
+    /// | `KANNEL_URL`      | Yes      | `base_url` |
+    /// | `KANNEL_USERNAME` | Yes      | `username` |
+    /// | `KANNEL_PASSWORD` | Yes      | `password` |
+    /// | `KANNEL_SMSC`     | No       | `smsc`     |
+    /// | `KANNEL_DLR_URL`  | No       | `dlr_url`  |
+    ///
+    /// Returns [`SmsError::Auth`] if a required variable is missing.
+    pub fn from_env() -> Result<Self, SmsError> {
+        let base_url =
+            std::env::var("KANNEL_URL").map_err(|_| SmsError::Auth("KANNEL_URL not set".into()))?;
+        let username = std::env::var("KANNEL_USERNAME")
+            .map_err(|_| SmsError::Auth("KANNEL_USERNAME not set".into()))?;
+        let password = std::env::var("KANNEL_PASSWORD")
+            .map_err(|_| SmsError::Auth("KANNEL_PASSWORD not set".into()))?;
+        let mut client = Self::new(base_url, username, password);
+        if let Ok(smsc) = std::env::var("KANNEL_SMSC") {
+            client = client.with_smsc(smsc);
+        }
+        if let Ok(dlr_url) = std::env::var("KANNEL_DLR_URL") {
+            client = client.with_dlr_url(dlr_url);
+        }
+        Ok(client)
+    }
+
+    /// Route sends through the SMSC named `smsc`.
+    pub fn with_smsc(mut self, smsc: impl Into<String>) -> Self {
+        self.smsc = Some(smsc.into());
+        self
+    }
+
+    /// Request delivery reports, sent to `url`.
+    pub fn with_dlr_url(mut self, url: impl Into<String>) -> Self {
+        self.dlr_url = Some(url.into());
+        self
+    }
+
+    /// Request only the report types in `mask` (default: [`DLR_MASK_ALL`]).
+    pub fn with_dlr_mask(mut self, mask: u8) -> Self {
+        self.dlr_mask = mask;
+        self
+    }
+
+    /// Set the default deadline for each API call (default: 30 seconds).
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Rebuild the HTTP client with custom transport settings (proxy,
+    /// `User-Agent`, extra headers, etc.).
+    pub fn with_http_config(mut self, config: HttpClientConfig) -> Result<Self, SmsError> {
+        self.http = sms_core::http_provider::client_with(&config)?;
+        Ok(self)
+    }
+
+    /// Generate message IDs with `ids` (default: [`UuidV4`]).
+    pub fn with_id_generator(mut self, ids: impl IdGenerator + 'static) -> Self {
+        self.id_generator = Arc::new(ids);
+        self
+    }
+
+    /// The `dlr-url` for message `id`, if reports are requested.
+    fn dlr_url_for(&self, id: &str) -> Option<String> {
+        self.dlr_url.as_deref().map(|url| {
+            let sep = if url.contains('?') { '&' } else { '?' };
+            format!("{}{}id={}&{}", url, sep, id, DLR_URL_QUERY)
+        })
+    }
+}
+
+/// Query parameters of a `sendsms` request.
+#[derive(Debug, Serialize)]
+struct SendSmsQuery<'a> {
+    username: &'a str,
+    password: &'a str,
+    to: &'a str,
+    #[serde(skip_serializing_if = "str::is_empty")]
+    from: &'a str,
+    text: &'a str,
+    charset: &'static str,
+    /// `2` (UCS-2) for text outside ASCII; Kannel picks otherwise.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    coding: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    smsc: Option<&'a str>,
+    #[serde(rename = "dlr-mask", skip_serializing_if = "Option::is_none")]
+    dlr_mask: Option<u8>,
+    #[serde(rename = "dlr-url", skip_serializing_if = "Option::is_none")]
+    dlr_url: Option<String>,
+}
+
+impl SmsClient for KannelClient {
+    async fn send(&self, req: SendRequest<'_>) -> Result<SendResponse, SmsError> {
+        sms_core::validate_text(req.text, self.max_text_len())?;
+        let url = format!("{}/cgi-bin/sendsms", self.base_url.trim_end_matches('/'));
+        let id = self.id_generator.generate();
+        let dlr_url = self.dlr_url_for(&id);
+        let query = SendSmsQuery {
+            username: &self.username,
+            password: self.password.expose_secret(),
+            to: req.to,
+            from: req.from,
+            text: req.text,
+            charset: "UTF-8",
+            coding: (!req.text.is_ascii()).then_some(2),
+            smsc: self.smsc.as_deref(),
+            dlr_mask: dlr_url.as_ref().map(|_| self.dlr_mask),
+            dlr_url,
+        };
+        let res = self
+            .http
+            .get(&url)
+            .query(&query)
+            .timeout(req.timeout.unwrap_or(self.timeout))
+            .send()
+            .await
+            .map_err(transport_error)?;
+
+        let status = res.status();
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS
+            || status == reqwest::StatusCode::SERVICE_UNAVAILABLE
+        {
+            let retry_after = res
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(sms_core::parse_retry_after);
+            return Err(SmsError::RateLimited { retry_after });
+        }
+        let raw_text = res.text().await.map_err(transport_error)?;
+        if status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN {
+            return Err(SmsError::Auth(raw_text));
+        }
+        if status == reqwest::StatusCode::BAD_REQUEST {
+            return Err(SmsError::Invalid(raw_text));
+        }
+        if !status.is_success() {
+            return Err(SmsError::Provider(format!("HTTP {}: {}", status, raw_text)));
+        }
+
+        Ok(SendResponse {
+            raw: serde_json::json!({
+                "id": id,
+                "response": raw_text.trim(),
+                "smsc": self.smsc,
+            }),
+            id,
+            provider: PROVIDER,
+            channel: Channel::Sms,
+            http_status: Some(status.as_u16()),
+            provider_request_id: None,
+            thread_id: Some(req.thread_id()),
+        })
+    }
+}
+
+/// A received message, from a `get-url` query built with
+/// [`GET_URL_QUERY`] or the `X-Kannel-*` headers of a `post-url` request.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct KannelInbound {
+    /// Sender phone number (`%p`).
+    pub from: String,
+    /// Receiving number or short code (`%P`).
+    pub to: String,
+    /// Message body (`%a`).
+    pub text: String,
+    /// Receive time (`%T`, Unix seconds).
+    pub ts: Option<String>,
+    /// SMSC the message came in on (`%i`).
+    pub smsc: Option<String>,
+    /// Kannel's internal message ID (`%I`).
+    pub id: Option<String>,
+}
+
+/// A delivery report, from the query of the `dlr-url` the client sets.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct KannelDeliveryReport {
+    /// ID the client generated for the message.
+    pub id: String,
+    /// Report type (`%d`): `1` delivered, `2` failed, `4` buffered, `8`
+    /// accepted by the SMSC, `16` rejected by the SMSC.
+    #[serde(rename = "type")]
+    pub kind: u16,
+    /// Destination number of the original message (`%p`).
+    pub to: String,
+    /// SMSC that handled the message (`%i`).
+    pub smsc: Option<String>,
+    /// The SMSC's own report text (`%A`).
+    pub reply: Option<String>,
+    /// Report time (`%T`, Unix seconds).
+    pub ts: Option<String>,
+}
+
+impl KannelDeliveryReport {
+    /// `DELIVERED`, `FAILED`, `BUFFERED`, `SUBMITTED`, `REJECTED` or
+    /// `UNKNOWN`.
+    pub fn status_name(&self) -> &'static str {
+        match self.kind {
+            1 => "DELIVERED",
+            2 => "FAILED",
+            4 => "BUFFERED",
+            8 => "SUBMITTED",
+            16 => "REJECTED",
+            _ => "UNKNOWN",
+        }
+    }
+
+    /// Returns `true` for final failures (failed or rejected by the SMSC).
+    pub fn is_failure(&self) -> bool {
+        matches!(self.kind, 2 | 16)
+    }
+}
+
+impl From<KannelInbound> for InboundMessage {
+    fn from(m: KannelInbound) -> Self {
+        let timestamp = m.ts.as_deref().and_then(sms_core::timestamp::parse);
+        let raw = serde_json::to_value(&m).unwrap_or_default();
+        InboundMessage {
+            id: m.id,
+            from: m.from,
+            to: m.to,
+            text: m.text,
+            timestamp,
+            provider: PROVIDER,
+            direction: InboundDirection::Inbound,
+            channel: Channel::Sms,
+            provider_account: None,
+            thread_id: None,
+            flag: None,
+            raw,
+        }
+        .with_thread_id()
+    }
+}
+
+impl From<KannelDeliveryReport> for InboundMessage {
+    fn from(r: KannelDeliveryReport) -> Self {
+        let timestamp = r.ts.as_deref().and_then(sms_core::timestamp::parse);
+        let raw = serde_json::to_value(&r).unwrap_or_default();
+        InboundMessage {
+            text: format!("Delivery Status: {}", r.status_name()),
+            id: Some(r.id),
+            from: "Kannel".to_string(),
+            to: r.to,
+            timestamp,
+            provider: PROVIDER,
+            direction: InboundDirection::DeliveryReport,
+            channel: Channel::Sms,
+            provider_account: None,
+            thread_id: None,
+            flag: None,
+            raw,
+        }
+        .with_thread_id()
+    }
+}
+
+/// The value of header `name`, matched case-insensitively.
+fn header<'a>(headers: &'a Headers, name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(name))
+        .map(|(_, v)| v.as_str())
+}
+
+impl InboundWebhook for KannelClient {
+    fn provider(&self) -> &'static str {
+        PROVIDER
+    }
+
+    fn parse_inbound(&self, headers: &Headers, body: &[u8]) -> Result<InboundMessage, SmsError> {
+        if let Some(from) = header(headers, "x-kannel-from") {
+            let inbound = KannelInbound {
+                from: from.to_string(),
+                to: header(headers, "x-kannel-to")
+                    .unwrap_or_default()
+                    .to_string(),
+                text: String::from_utf8_lossy(body).into_owned(),
+                ts: header(headers, "x-kannel-time").map(str::to_string),
+                smsc: header(headers, "x-kannel-smsc").map(str::to_string),
+                id: None,
+            };
+            return Ok(inbound.into());
+        }
+
+        let fields: Vec<(String, String)> = serde_urlencoded::from_bytes(body)
+            .map_err(|e| SmsError::Invalid(format!("query decode: {}", e)))?;
+        if fields.iter().any(|(k, _)| k == "type") {
+            let report: KannelDeliveryReport = serde_urlencoded::from_bytes(body)
+                .map_err(|e| SmsError::Invalid(format!("delivery report: {}", e)))?;
+            Ok(report.into())
+        } else {
+            let inbound: KannelInbound = serde_urlencoded::from_bytes(body)
+                .map_err(|e| SmsError::Invalid(format!("inbound message: {}", e)))?;
+            Ok(inbound.into())
+        }
+    }
+}
+
+/// The `kannel` configuration section read by the provider plugin.
+#[derive(Deserialize)]
+struct KannelSettings {
+    base_url: String,
+    username: String,
+    password: String,
+    smsc: Option<String>,
+    dlr_url: Option<String>,
+    dlr_mask: Option<u8>,
+    #[serde(default)]
+    http: Option<HttpClientConfig>,
+}
+
+/// Build a [`KannelClient`] from the `kannel` section.
+fn build_plugin(settings: &serde_json::Value) -> Result<sms_core::BuiltProvider, SmsError> {
+    let s: KannelSettings = sms_core::provider_settings(PROVIDER, settings)?;
+    let mut client = KannelClient::new(s.base_url, s.username, s.password);
+    if let Some(smsc) = s.smsc {
+        client = client.with_smsc(smsc);
+    }
+    if let Some(dlr_url) = s.dlr_url {
+        client = client.with_dlr_url(dlr_url);
+    }
+    if let Some(mask) = s.dlr_mask {
+        client = client.with_dlr_mask(mask);
+    }
+    let client = match s.http {
+        Some(http) => client.with_http_config(http)?,
+        None => client,
+    };
+    Ok(sms_core::BuiltProvider::both(client))
+}
+
+sms_core::register_provider!("kannel", build_plugin);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn sendsms_query_is_encoded() {
+        let client = KannelClient::new("http://kannel:13013", "tester", "foobar")
+            .with_dlr_url("https://app.example.com/webhooks/kannel");
+        let query = SendSmsQuery {
+            username: "tester",
+            password: "foobar",
+            to: "+447700900000",
+            from: "",
+            text: "Grüße",
+            charset: "UTF-8",
+            coding: Some(2),
+            smsc: None,
+            dlr_mask: Some(DLR_MASK_ALL),
+            dlr_url: client.dlr_url_for("abc"),
+        };
+        let encoded = serde_urlencoded::to_string(&query).unwrap();
+        assert!(encoded.starts_with("username=tester&password=foobar&to=%2B447700900000&text=Gr"));
+        assert!(encoded.contains("&coding=2&dlr-mask=31&dlr-url=https"));
+        assert!(!encoded.contains("from="));
+    }
+
+    #[test]
+    fn dlr_url_carries_the_message_id() {
+        let client = KannelClient::new("http://kannel:13013", "u", "p");
+        assert_eq!(client.dlr_url_for("abc"), None);
+
+        let client = client.with_dlr_url("https://app.example.com/hook?tenant=a");
+        assert_eq!(
+            client.dlr_url_for("abc").unwrap(),
+            "https://app.example.com/hook?tenant=a&id=abc&type=%d&to=%p&smsc=%i&reply=%A&ts=%T"
+        );
+    }
+
+    #[test]
+    fn parses_get_url_inbound() {
+        let client = KannelClient::new("http://kannel:13013", "u", "p");
+        let body =
+            b"from=%2B447700900000&to=12345&text=Hello+there&ts=1705314600&smsc=vodafone&id=9f1c";
+        let msg = client.parse_inbound(&Headers::new(), body).unwrap();
+        assert_eq!(msg.direction, InboundDirection::Inbound);
+        assert_eq!(msg.from, "+447700900000");
+        assert_eq!(msg.to, "12345");
+        assert_eq!(msg.text, "Hello there");
+        assert_eq!(msg.id.as_deref(), Some("9f1c"));
+        assert_eq!(msg.timestamp.unwrap().unix_timestamp(), 1705314600);
+        assert_eq!(msg.raw["smsc"], "vodafone");
+    }
+
+    #[test]
+    fn parses_post_url_inbound() {
+        let client = KannelClient::new("http://kannel:13013", "u", "p");
+        let headers = vec![
+            ("X-Kannel-From".to_string(), "+447700900000".to_string()),
+            ("X-Kannel-To".to_string(), "12345".to_string()),
+            (
+                "X-Kannel-Time".to_string(),
+                "2024-01-15 10:30:00".to_string(),
+            ),
+        ];
+        let msg = client.parse_inbound(&headers, "Grüße".as_bytes()).unwrap();
+        assert_eq!(msg.from, "+447700900000");
+        assert_eq!(msg.to, "12345");
+        assert_eq!(msg.text, "Grüße");
+        assert!(msg.timestamp.is_some());
+    }
+
+    #[test]
+    fn parses_delivery_report() {
+        let client = KannelClient::new("http://kannel:13013", "u", "p");
+        let body = b"id=abc&type=16&to=%2B447700900000&smsc=vodafone&reply=NACK%2F0x0000000b&ts=1705314600";
+        let msg = client.parse_inbound(&Headers::new(), body).unwrap();
+        assert_eq!(msg.direction, InboundDirection::DeliveryReport);
+        assert_eq!(msg.id.as_deref(), Some("abc"));
+        assert_eq!(msg.to, "+447700900000");
+        assert_eq!(msg.text, "Delivery Status: REJECTED");
+
+        let report: KannelDeliveryReport = serde_json::from_value(msg.raw).unwrap();
+        assert!(report.is_failure());
+        assert_eq!(report.reply.as_deref(), Some("NACK/0x0000000b"));
+    }
+
+    #[test]
+    fn rejects_malformed_webhooks() {
+        let client = KannelClient::new("http://kannel:13013", "u", "p");
+        assert!(
+            client
+                .parse_inbound(&Headers::new(), b"text=orphan")
+                .is_err()
+        );
+        assert!(
+            client
+                .parse_inbound(&Headers::new(), b"id=abc&type=x&to=1")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn plugin_builds_from_settings() {
+        let plugin = sms_core::ProviderPlugin::find("kannel").unwrap();
+        let built = plugin
+            .build(&json!({
+                "base_url": "http://kannel:13013",
+                "username": "tester",
+                "password": "foobar",
+                "dlr_url": "https://app.example.com/webhooks/kannel"
+            }))
+            .unwrap();
+        assert_eq!(built.webhook.unwrap().provider(), "kannel");
+        assert!(built.client.is_some());
+        assert!(plugin.build(&json!({ "username": "tester" })).is_err());
+    }
+
+    #[test]
+    fn debug_redacts_password() {
+        let debug = format!(
+            "{:?}",
+            KannelClient::new("http://kannel", "tester", "hunter2")
+        );
+        assert!(debug.contains("tester"));
+        assert!(!debug.contains("hunter2"));
+    }
+}
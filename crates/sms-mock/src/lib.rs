@@ -0,0 +1,579 @@
+//! # Mock Provider
+//!
+//! Recording mock provider for application tests.
+//!
+//! [`MockClient`] accepts every send, records it, and lets a test state what
+//! it expected to be sent instead of string-matching captured requests by
+//! hand.  Responses and failures can be scripted per expectation or queued
+//! for the next sends.  It also parses a plain JSON webhook
+//! (`{"from", "to", "text"}`), so it can stand in for a provider's inbound
+//! side too, and builds those webhooks with [`MockClient::inbound_webhook`].
+//!
+//! ```rust,ignore
+//! use sms_mock::MockClient;
+//!
+//! let mock = MockClient::new();
+//! mock.expect_send().to("+15551234567").containing("code").times(1);
+//! mock.expect_send().to("+15550000000").fails_with(|| SmsError::Suppressed("opted out".into()));
+//!
+//! app.with_sender(mock.clone()).sign_up("+15551234567").await?;
+//! mock.verify();
+//! ```
+//!
+//! ## Webhooks
+//!
+//! Register the mock with a `WebhookProcessor` and feed it synthesized
+//! calls to test the inbound pipeline end to end:
+//!
+//! ```rust,ignore
+//! let processor = WebhookProcessor::new(InboundRegistry::new().with(Arc::new(mock.clone())));
+//! let hook = mock.inbound_webhook("+15551234567", "+15550000000", "STOP");
+//! let result = processor.handle_webhook(hook.provider, hook.headers, hook.body.into());
+//! assert_eq!(result.message().unwrap().text, "STOP");
+//! ```
+
+use serde::{Deserialize, Serialize};
+use sms_core::{
+    Channel, Headers, InboundDirection, InboundMessage, InboundWebhook, OwnedSendRequest,
+    SendRequest, SendResponse, SmsClient, SmsError,
+};
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+const PROVIDER: &str = "mock";
+
+type Predicate = Box<dyn Fn(&OwnedSendRequest) -> bool + Send + Sync>;
+type OutcomeFactory = Box<dyn Fn() -> Result<SendResponse, SmsError> + Send + Sync>;
+
+/// One `expect_send()` declaration.
+struct Expectation {
+    description: Vec<String>,
+    predicates: Vec<Predicate>,
+    times: Option<usize>,
+    matched: usize,
+    outcome: Option<OutcomeFactory>,
+}
+
+impl Expectation {
+    fn matches(&self, req: &OwnedSendRequest) -> bool {
+        self.predicates.iter().all(|p| p(req))
+    }
+
+    fn is_satisfied(&self) -> bool {
+        match self.times {
+            Some(times) => self.matched == times,
+            None => self.matched > 0,
+        }
+    }
+
+    fn describe(&self) -> String {
+        let what = match self.description.as_slice() {
+            [] => "any send".to_string(),
+            parts => parts.join(", "),
+        };
+        match self.times {
+            Some(times) => format!("{} (expected {}, got {})", what, times, self.matched),
+            None => format!("{} (expected at least 1, got {})", what, self.matched),
+        }
+    }
+}
+
+#[derive(Default)]
+struct State {
+    sent: Vec<OwnedSendRequest>,
+    expectations: Vec<Expectation>,
+    script: VecDeque<Result<SendResponse, SmsError>>,
+    next_id: u64,
+}
+
+/// An [`SmsClient`] that records every send and checks it against fluent
+/// expectations.
+///
+/// Clones share their recordings and expectations, so a test can keep one
+/// handle and pass the other to the code under test.
+#[derive(Clone)]
+pub struct MockClient {
+    provider: &'static str,
+    state: Arc<Mutex<State>>,
+}
+
+impl Default for MockClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for MockClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.state();
+        f.debug_struct("MockClient")
+            .field("provider", &self.provider)
+            .field("sent", &state.sent.len())
+            .field("expectations", &state.expectations.len())
+            .finish()
+    }
+}
+
+impl MockClient {
+    /// Create a mock that accepts every send.
+    pub fn new() -> Self {
+        Self {
+            provider: PROVIDER,
+            state: Arc::new(Mutex::new(State::default())),
+        }
+    }
+
+    /// Report `provider` in each [`SendResponse`] (default: `"mock"`).
+    pub fn with_provider_name(mut self, provider: &'static str) -> Self {
+        self.provider = provider;
+        self
+    }
+
+    /// Declare an expected send.  Refine it with the returned builder; with
+    /// no refinements it matches any send.
+    pub fn expect_send(&self) -> ExpectSend<'_> {
+        let mut state = self.state();
+        state.expectations.push(Expectation {
+            description: Vec::new(),
+            predicates: Vec::new(),
+            times: None,
+            matched: 0,
+            outcome: None,
+        });
+        ExpectSend {
+            mock: self,
+            index: state.expectations.len() - 1,
+        }
+    }
+
+    /// Fail the next send with `error`, whatever it is.  Queued outcomes are
+    /// used in order before any expectation's scripted outcome.
+    pub fn fail_next(&self, error: SmsError) {
+        self.state().script.push_back(Err(error));
+    }
+
+    /// Answer the next send with `response`, whatever it is.
+    pub fn respond_next(&self, response: SendResponse) {
+        self.state().script.push_back(Ok(response));
+    }
+
+    /// Answer the next sends with `outcomes`, in order, after any already
+    /// queued.
+    pub fn script(&self, outcomes: impl IntoIterator<Item = Result<SendResponse, SmsError>>) {
+        self.state().script.extend(outcomes);
+    }
+
+    /// Every send received so far, in order.
+    pub fn sent(&self) -> Vec<OwnedSendRequest> {
+        self.state().sent.clone()
+    }
+
+    /// Forget recorded sends, expectations and queued outcomes.
+    pub fn reset(&self) {
+        *self.state() = State::default();
+    }
+
+    /// Panic unless every expectation was met.
+    ///
+    /// The message lists each unmet expectation and every recorded send.
+    #[track_caller]
+    pub fn verify(&self) {
+        let state = self.state();
+        let unmet: Vec<String> = state
+            .expectations
+            .iter()
+            .filter(|e| !e.is_satisfied())
+            .map(|e| format!("  - {}", e.describe()))
+            .collect();
+        if unmet.is_empty() {
+            return;
+        }
+        let sent: Vec<String> = state
+            .sent
+            .iter()
+            .map(|r| format!("  - to {} from {}: {:?}", r.to, r.from, r.text))
+            .collect();
+        panic!(
+            "unmet SMS expectations:\n{}\nsends received:\n{}",
+            unmet.join("\n"),
+            if sent.is_empty() {
+                "  (none)".to_string()
+            } else {
+                sent.join("\n")
+            }
+        );
+    }
+
+    /// A webhook call delivering an inbound message, as the mock's
+    /// [`InboundWebhook`] side parses it.
+    pub fn inbound_webhook(
+        &self,
+        from: impl Into<String>,
+        to: impl Into<String>,
+        text: impl Into<String>,
+    ) -> MockWebhook {
+        self.webhook(MockInbound {
+            id: None,
+            from: from.into(),
+            to: to.into(),
+            text: Some(text.into()),
+            status: None,
+        })
+    }
+
+    /// A webhook call reporting `status` for message `id`, sent to `to`.
+    pub fn delivery_report_webhook(
+        &self,
+        id: impl Into<String>,
+        to: impl Into<String>,
+        status: impl Into<String>,
+    ) -> MockWebhook {
+        self.webhook(MockInbound {
+            id: Some(id.into()),
+            from: self.provider.to_string(),
+            to: to.into(),
+            text: None,
+            status: Some(status.into()),
+        })
+    }
+
+    fn webhook(&self, payload: MockInbound) -> MockWebhook {
+        MockWebhook {
+            provider: self.provider,
+            headers: vec![("Content-Type".to_string(), "application/json".to_string())],
+            body: serde_json::to_vec(&payload).unwrap_or_default(),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().expect("mock client poisoned")
+    }
+}
+
+/// A synthesized webhook call, ready for `WebhookProcessor::handle_webhook`.
+#[derive(Debug, Clone)]
+pub struct MockWebhook {
+    /// Provider name to route the call to.
+    pub provider: &'static str,
+    /// Request headers.
+    pub headers: Headers,
+    /// JSON request body.
+    pub body: Vec<u8>,
+}
+
+/// Builder returned by [`MockClient::expect_send`].
+///
+/// Each call narrows the expectation in place, so the builder can simply be
+/// dropped at the end of the chain.
+pub struct ExpectSend<'a> {
+    mock: &'a MockClient,
+    index: usize,
+}
+
+impl ExpectSend<'_> {
+    /// Only match sends to `number`.
+    pub fn to(self, number: impl Into<String>) -> Self {
+        let number = number.into();
+        self.describe(format!("to {}", number))
+            .predicate(move |r| r.to == number)
+    }
+
+    /// Only match sends from `number`.
+    pub fn from(self, number: impl Into<String>) -> Self {
+        let number = number.into();
+        self.describe(format!("from {}", number))
+            .predicate(move |r| r.from == number)
+    }
+
+    /// Only match sends whose text contains `needle`.
+    pub fn containing(self, needle: impl Into<String>) -> Self {
+        let needle = needle.into();
+        self.describe(format!("containing {:?}", needle))
+            .predicate(move |r| r.text.contains(&needle))
+    }
+
+    /// Only match sends whose text is exactly `text`.
+    pub fn text(self, text: impl Into<String>) -> Self {
+        let text = text.into();
+        self.describe(format!("text {:?}", text))
+            .predicate(move |r| r.text == text)
+    }
+
+    /// Only match sends over `channel`.
+    pub fn channel(self, channel: Channel) -> Self {
+        self.describe(format!("over {:?}", channel))
+            .predicate(move |r| r.channel == channel)
+    }
+
+    /// Only match sends accepted by `predicate`.
+    pub fn matching(
+        self,
+        predicate: impl Fn(&OwnedSendRequest) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.describe("matching a custom predicate".to_string())
+            .predicate(predicate)
+    }
+
+    /// Require exactly `times` matching sends (default: at least one).
+    pub fn times(self, times: usize) -> Self {
+        self.update(|e| e.times = Some(times));
+        self
+    }
+
+    /// Require that no send matches.
+    pub fn never(self) -> Self {
+        self.times(0)
+    }
+
+    /// Fail matching sends with the error `error` builds, after recording
+    /// them.
+    pub fn fails_with(self, error: impl Fn() -> SmsError + Send + Sync + 'static) -> Self {
+        self.update(|e| e.outcome = Some(Box::new(move || Err(error()))));
+        self
+    }
+
+    /// Answer matching sends with the response `response` builds, after
+    /// recording them.
+    pub fn responds_with(
+        self,
+        response: impl Fn() -> SendResponse + Send + Sync + 'static,
+    ) -> Self {
+        self.update(|e| e.outcome = Some(Box::new(move || Ok(response()))));
+        self
+    }
+
+    fn describe(self, part: String) -> Self {
+        self.update(|e| e.description.push(part));
+        self
+    }
+
+    fn predicate(
+        self,
+        predicate: impl Fn(&OwnedSendRequest) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.update(|e| e.predicates.push(Box::new(predicate)));
+        self
+    }
+
+    fn update(&self, f: impl FnOnce(&mut Expectation)) {
+        f(&mut self.mock.state().expectations[self.index]);
+    }
+}
+
+impl SmsClient for MockClient {
+    async fn send(&self, req: SendRequest<'_>) -> Result<SendResponse, SmsError> {
+        let owned = OwnedSendRequest::from(req.clone());
+        let mut state = self.state();
+        state.sent.push(owned.clone());
+
+        let mut scripted = None;
+        for expectation in state.expectations.iter_mut() {
+            if expectation.matches(&owned) {
+                expectation.matched += 1;
+                if scripted.is_none() {
+                    scripted = expectation.outcome.as_ref().map(|outcome| outcome());
+                }
+            }
+        }
+        if let Some(outcome) = state.script.pop_front().or(scripted) {
+            return outcome;
+        }
+
+        state.next_id += 1;
+        let id = format!("mock-{}", state.next_id);
+        Ok(SendResponse {
+            id: id.clone(),
+            provider: self.provider,
+            channel: req.channel,
+            http_status: None,
+            provider_request_id: None,
+            thread_id: Some(req.thread_id()),
+            raw: serde_json::json!({ "message_id": id }),
+        })
+    }
+
+    /// The mock accepts every channel.
+    fn supports(&self, _channel: Channel) -> bool {
+        true
+    }
+}
+
+/// Body accepted by the mock's webhook: an inbound message, or a delivery
+/// report when `status` is set.
+#[derive(Deserialize, Serialize)]
+struct MockInbound {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    from: String,
+    to: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    status: Option<String>,
+}
+
+impl InboundWebhook for MockClient {
+    fn provider(&self) -> &'static str {
+        self.provider
+    }
+
+    fn parse_inbound(&self, _headers: &Headers, body: &[u8]) -> Result<InboundMessage, SmsError> {
+        let raw: serde_json::Value =
+            serde_json::from_slice(body).map_err(|e| SmsError::Invalid(e.to_string()))?;
+        let msg = MockInbound::deserialize(&raw).map_err(|e| SmsError::Invalid(e.to_string()))?;
+        let (direction, text) = match (msg.status, msg.text) {
+            (Some(status), _) => (
+                InboundDirection::DeliveryReport,
+                format!("Delivery Status: {}", status),
+            ),
+            (None, Some(text)) => (InboundDirection::Inbound, text),
+            (None, None) => return Err(SmsError::Invalid("missing field `text`".into())),
+        };
+        Ok(InboundMessage {
+            id: msg.id,
+            from: msg.from,
+            to: msg.to,
+            text,
+            timestamp: None,
+            provider: self.provider,
+            direction,
+            channel: Channel::Sms,
+            provider_account: None,
+            thread_id: None,
+            flag: None,
+            raw,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn send(mock: &MockClient, to: &str, text: &str) -> Result<SendResponse, SmsError> {
+        mock.send(OwnedSendRequest::new(to, "+10005551234", text).as_ref())
+            .await
+    }
+
+    #[tokio::test]
+    async fn records_and_verifies_expectations() {
+        let mock = MockClient::new();
+        mock.expect_send()
+            .to("+15551234567")
+            .containing("code")
+            .times(1);
+        mock.expect_send().to("+15559999999").never();
+
+        let resp = send(&mock, "+15551234567", "Your code is 1234")
+            .await
+            .unwrap();
+        assert_eq!(resp.provider, "mock");
+        assert_eq!(resp.id, "mock-1");
+        assert_eq!(mock.sent().len(), 1);
+        mock.verify();
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "to +15551234567, containing \"code\" (expected 1, got 2)")]
+    async fn verify_reports_count_mismatch() {
+        let mock = MockClient::new();
+        mock.expect_send()
+            .to("+15551234567")
+            .containing("code")
+            .times(1);
+        send(&mock, "+15551234567", "code 1").await.unwrap();
+        send(&mock, "+15551234567", "code 2").await.unwrap();
+        mock.verify();
+    }
+
+    #[tokio::test]
+    async fn scripted_failures() {
+        let mock = MockClient::new();
+        mock.expect_send()
+            .to("+15550000000")
+            .fails_with(|| SmsError::Provider("carrier down".into()));
+        mock.fail_next(SmsError::Timeout("slow".into()));
+
+        let first = send(&mock, "+15551111111", "hi").await;
+        assert!(matches!(first, Err(SmsError::Timeout(_))));
+        assert!(send(&mock, "+15551111111", "hi").await.is_ok());
+        let scripted = send(&mock, "+15550000000", "hi").await;
+        assert!(matches!(scripted, Err(SmsError::Provider(_))));
+
+        // Failed sends are still recorded and counted.
+        assert_eq!(mock.sent().len(), 3);
+        mock.verify();
+        mock.reset();
+        assert!(mock.sent().is_empty());
+    }
+
+    #[test]
+    fn parses_plain_json_webhooks() {
+        let mock = MockClient::new().with_provider_name("twilio");
+        let body = br#"{"from":"+15551111111","to":"+15552222222","text":"STOP"}"#;
+        let msg = mock.parse_inbound(&Headers::new(), body).unwrap();
+        assert_eq!(msg.provider, "twilio");
+        assert_eq!(msg.from, "+15551111111");
+        assert_eq!(msg.text, "STOP");
+        assert!(mock.parse_inbound(&Headers::new(), b"{}").is_err());
+        let no_text = br#"{"from":"+15551111111","to":"+15552222222"}"#;
+        assert!(mock.parse_inbound(&Headers::new(), no_text).is_err());
+    }
+
+    #[tokio::test]
+    async fn scripted_responses() {
+        let canned = |id: &str| SendResponse {
+            id: id.to_string(),
+            provider: "twilio",
+            channel: Channel::Sms,
+            http_status: Some(201),
+            provider_request_id: None,
+            thread_id: None,
+            raw: serde_json::json!({ "sid": id }),
+        };
+        let mock = MockClient::new();
+        mock.expect_send()
+            .to("+15550000000")
+            .responds_with(move || canned("SM-expected"));
+        mock.script([
+            Ok(canned("SM-1")),
+            Err(SmsError::RateLimited { retry_after: None }),
+        ]);
+
+        assert_eq!(send(&mock, "+15551111111", "a").await.unwrap().id, "SM-1");
+        assert!(matches!(
+            send(&mock, "+15551111111", "b").await,
+            Err(SmsError::RateLimited { .. })
+        ));
+        assert_eq!(send(&mock, "+15551111111", "c").await.unwrap().id, "mock-1");
+        let expected = send(&mock, "+15550000000", "d").await.unwrap();
+        assert_eq!(expected.id, "SM-expected");
+        assert_eq!(expected.http_status, Some(201));
+        assert_eq!(mock.sent().len(), 4);
+    }
+
+    #[test]
+    fn synthesized_webhooks_run_through_the_processor() {
+        use sms_core::InboundRegistry;
+        use sms_web_generic::WebhookProcessor;
+
+        let mock = MockClient::new().with_provider_name("plivo");
+        let processor = WebhookProcessor::new(InboundRegistry::new().with(Arc::new(mock.clone())));
+
+        let hook = mock.inbound_webhook("+15551111111", "+15552222222", "STOP");
+        assert_eq!(hook.provider, "plivo");
+        let result = processor.handle_webhook(hook.provider, hook.headers, hook.body.into());
+        let message = result.message().unwrap();
+        assert_eq!(message.direction, InboundDirection::Inbound);
+        assert_eq!(message.from, "+15551111111");
+        assert_eq!(message.text, "STOP");
+
+        let hook = mock.delivery_report_webhook("mock-1", "+15552222222", "delivered");
+        let result = processor.handle_webhook(hook.provider, hook.headers, hook.body.into());
+        let report = result.message().unwrap();
+        assert_eq!(report.direction, InboundDirection::DeliveryReport);
+        assert_eq!(report.id.as_deref(), Some("mock-1"));
+        assert_eq!(report.text, "Delivery Status: delivered");
+    }
+}
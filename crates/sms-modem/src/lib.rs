@@ -0,0 +1,901 @@
+//! # GSM Modem Provider
+//!
+//! Sends and receives SMS through a GSM/LTE modem attached over a serial
+//! port, for on-premises and offline deployments with no SMS gateway in
+//! between.  [`ModemClient`] drives the modem with AT commands in PDU mode
+//! (`AT+CMGF=0`), sends with `AT+CMGS`, and runs a reader task that turns
+//! every `+CMT` (received message) and `+CDS` (status report) the modem
+//! pushes into a normalized [`InboundMessage`].
+//!
+//! ## Sending messages
+//!
+//! ```rust,ignore
+//! use sms_core::{SendRequest, SmsClient};
+//! use sms_modem::ModemClient;
+//!
+//! let client = ModemClient::new("/dev/ttyUSB0").with_pin("1234");
+//! let response = client.send(SendRequest {
+//!     to: "+447700900000",
+//!     from: "",
+//!     text: "Hello from the modem!",
+//!     ..Default::default()
+//! }).await?;
+//! ```
+//!
+//! Messages go out from the SIM's own number, so `from` is ignored.  Text
+//! outside the GSM 7-bit alphabet is sent as UCS-2, and long text as a
+//! concatenated message.  The port is opened and the modem initialized on
+//! first use; call [`ModemClient::open`] to do it eagerly.
+//!
+//! ## Receiving messages
+//!
+//! The reader task queues received messages (reassembling multipart ones)
+//! and status reports, and [`ModemClient`] implements [`InboundPoller`] to
+//! drain the queue:
+//!
+//! ```rust,ignore
+//! let runner = PollingRunner::new(Arc::new(client.clone()), processor)
+//!     .with_interval(Duration::from_secs(1));
+//! tokio::spawn(async move { runner.run().await });
+//! ```
+
+pub mod pdu;
+
+pub use pdu::{DeliverPdu, Received, StatusReport};
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use sms_core::{
+    Channel, ExposeSecret, InboundDirection, InboundMessage, InboundPoller, SecretString,
+    SendRequest, SendResponse, SmsClient, SmsError,
+};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadHalf, WriteHalf};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+const PROVIDER: &str = "modem";
+
+/// Default serial line speed.
+pub const DEFAULT_BAUD_RATE: u32 = 115_200;
+
+/// Default deadline for each AT command.  Sends wait on the network, so
+/// this is generous.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Most parts a concatenated message can have.
+const MAX_PARTS: usize = 255;
+
+/// `+CMS ERROR` codes this crate maps to specific [`SmsError`]s.
+const CMS_UNASSIGNED_NUMBER: u16 = 1;
+const CMS_CONGESTION: u16 = 42;
+const CMS_INVALID_PDU_PARAMETER: u16 = 304;
+const CMS_SIM_PIN_REQUIRED: u16 = 311;
+const CMS_SIM_PUK_REQUIRED: u16 = 316;
+const CMS_NETWORK_TIMEOUT: u16 = 332;
+
+/// A byte stream to a modem: a serial port, or anything else that speaks
+/// AT commands, such as a TCP serial bridge.
+pub trait ModemStream: AsyncRead + AsyncWrite + Send + Unpin {}
+
+impl<T: AsyncRead + AsyncWrite + Send + Unpin> ModemStream for T {}
+
+type BoxStream = Box<dyn ModemStream>;
+
+/// What the reader task passes to a waiting command.
+enum Line {
+    /// A response line.
+    Text(String),
+    /// The `> ` prompt for PDU input after `AT+CMGS`.
+    Prompt,
+}
+
+/// Handles onto an open port, cheap to clone out from under the session
+/// lock.
+#[derive(Clone)]
+struct Link {
+    writer: Arc<tokio::sync::Mutex<WriteHalf<BoxStream>>>,
+    /// Response lines; holding the lock serializes commands.
+    lines: Arc<tokio::sync::Mutex<mpsc::UnboundedReceiver<Line>>>,
+    alive: Arc<AtomicBool>,
+}
+
+impl Link {
+    async fn write(&self, bytes: &[u8]) -> Result<(), SmsError> {
+        let mut writer = self.writer.lock().await;
+        let result = match writer.write_all(bytes).await {
+            Ok(()) => writer.flush().await,
+            Err(e) => Err(e),
+        };
+        result.map_err(|e| {
+            self.alive.store(false, Ordering::Relaxed);
+            SmsError::Http(format!("modem I/O error: {}", e))
+        })
+    }
+}
+
+/// An open port and the reader task serving it.
+struct Session {
+    link: Link,
+    reader: JoinHandle<()>,
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        self.reader.abort();
+    }
+}
+
+/// State shared by every clone of a client.
+struct State {
+    session: tokio::sync::Mutex<Option<Session>>,
+    /// A stream handed to [`ModemClient::from_stream`], until first use.
+    stream: std::sync::Mutex<Option<BoxStream>>,
+    /// Concatenation reference for the next multipart send.
+    reference: AtomicU8,
+    inbound_tx: mpsc::UnboundedSender<InboundMessage>,
+    inbound_rx: tokio::sync::Mutex<mpsc::UnboundedReceiver<InboundMessage>>,
+}
+
+/// GSM modem client.
+///
+/// Implements [`SmsClient`] for sending and [`InboundPoller`] for received
+/// messages and status reports.  Clones share one open port.
+#[derive(Clone)]
+pub struct ModemClient {
+    /// Serial device path, e.g. `/dev/ttyUSB0` or `COM3`.
+    pub path: String,
+    /// Serial line speed (default: [`DEFAULT_BAUD_RATE`]).
+    pub baud_rate: u32,
+    /// The SIM's own number, reported as `to` on received messages
+    /// (default: empty).
+    pub number: String,
+    /// SIM PIN, entered at startup if the SIM asks for it.  Zeroized on
+    /// drop.
+    pub pin: Option<SecretString>,
+    /// Request status reports for sends (default: `true`).
+    pub status_reports: bool,
+    /// Deadline for each AT command (default: 30 seconds).
+    pub timeout: Duration,
+    state: Arc<State>,
+}
+
+impl std::fmt::Debug for ModemClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ModemClient")
+            .field("path", &self.path)
+            .field("baud_rate", &self.baud_rate)
+            .field("number", &self.number)
+            .field("pin", &self.pin.as_ref().map(|_| "[REDACTED]"))
+            .field("status_reports", &self.status_reports)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ModemClient {
+    /// Create a client for the modem at serial device `path`.  Nothing is
+    /// opened until the first send or poll, or [`open`](Self::open).
+    pub fn new(path: impl Into<String>) -> Self {
+        Self::create(path.into(), None)
+    }
+
+    /// Create a client that talks AT commands over `stream` instead of
+    /// opening a serial port.  The stream is used once: if it closes, the
+    /// client cannot reopen it.
+    pub fn from_stream(stream: impl ModemStream + 'static) -> Self {
+        Self::create(String::new(), Some(Box::new(stream)))
+    }
+
+    fn create(path: String, stream: Option<BoxStream>) -> Self {
+        let (inbound_tx, inbound_rx) = mpsc::unbounded_channel();
+        Self {
+            path,
+            baud_rate: DEFAULT_BAUD_RATE,
+            number: String::new(),
+            pin: None,
+            status_reports: true,
+            timeout: DEFAULT_TIMEOUT,
+            state: Arc::new(State {
+                session: tokio::sync::Mutex::new(None),
+                stream: std::sync::Mutex::new(stream),
+                reference: AtomicU8::new(0),
+                inbound_tx,
+                inbound_rx: tokio::sync::Mutex::new(inbound_rx),
+            }),
+        }
+    }
+
+    /// Create a client by reading settings from environment variables.
+    ///
+    /// | Variable          | Required | Notes |
+    /// |-------------------|----------|-------|
+    /// | `MODEM_PATH`      | Yes      | Serial device path |
+    /// | `MODEM_BAUD_RATE` | No       | Defaults to 115200 |
+    /// | `MODEM_PIN`       | No       | SIM PIN |
+    /// | `MODEM_NUMBER`    | No       | The SIM's own number |
+    ///
+    /// Returns [`SmsError::Auth`] if `MODEM_PATH` is missing.
+    pub fn from_env() -> Result<Self, SmsError> {
+        let path =
+            std::env::var("MODEM_PATH").map_err(|_| SmsError::Auth("MODEM_PATH not set".into()))?;
+        let mut client = Self::new(path);
+        if let Ok(baud) = std::env::var("MODEM_BAUD_RATE") {
+            client.baud_rate = baud.parse().map_err(|_| {
+                SmsError::Invalid(format!("MODEM_BAUD_RATE {} is not a number", baud))
+            })?;
+        }
+        if let Ok(pin) = std::env::var("MODEM_PIN") {
+            client = client.with_pin(pin);
+        }
+        if let Ok(number) = std::env::var("MODEM_NUMBER") {
+            client = client.with_number(number);
+        }
+        Ok(client)
+    }
+
+    /// Open the port at `baud_rate` instead of [`DEFAULT_BAUD_RATE`].
+    pub fn with_baud_rate(mut self, baud_rate: u32) -> Self {
+        self.baud_rate = baud_rate;
+        self
+    }
+
+    /// Report `number` as the recipient of received messages.
+    pub fn with_number(mut self, number: impl Into<String>) -> Self {
+        self.number = number.into();
+        self
+    }
+
+    /// Enter `pin` at startup if the SIM is locked.
+    pub fn with_pin(mut self, pin: impl Into<String>) -> Self {
+        self.pin = Some(SecretString::from(pin.into()));
+        self
+    }
+
+    /// Turn status report requests on or off (default: on).
+    pub fn with_status_reports(mut self, enabled: bool) -> Self {
+        self.status_reports = enabled;
+        self
+    }
+
+    /// Set the per-command deadline (default: 30 seconds).
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Open the port and initialize the modem now, if not already open.
+    pub async fn open(&self) -> Result<(), SmsError> {
+        self.link().await.map(|_| ())
+    }
+
+    /// Returns `true` while the port is open.
+    pub async fn is_open(&self) -> bool {
+        let session = self.state.session.lock().await;
+        session
+            .as_ref()
+            .is_some_and(|s| s.link.alive.load(Ordering::Relaxed))
+    }
+
+    /// Close the port.  The next send or poll reopens it.
+    pub async fn close(&self) {
+        if self.state.session.lock().await.take().is_some() {
+            info!("Modem {} closed", self.path);
+        }
+    }
+
+    /// The open port, opening it first if there is none.
+    async fn link(&self) -> Result<Link, SmsError> {
+        let mut session = self.state.session.lock().await;
+        if let Some(s) = session.as_ref() {
+            if s.link.alive.load(Ordering::Relaxed) {
+                return Ok(s.link.clone());
+            }
+            warn!("Modem {} went away; reopening", self.path);
+        }
+        *session = None;
+        let new = self.start().await?;
+        let link = new.link.clone();
+        *session = Some(new);
+        Ok(link)
+    }
+
+    async fn start(&self) -> Result<Session, SmsError> {
+        let supplied = self
+            .state
+            .stream
+            .lock()
+            .expect("modem stream lock poisoned")
+            .take();
+        let stream = match supplied {
+            Some(stream) => stream,
+            None => self.open_port()?,
+        };
+        let (reader, writer) = tokio::io::split(stream);
+        let (lines_tx, lines_rx) = mpsc::unbounded_channel();
+        let link = Link {
+            writer: Arc::new(tokio::sync::Mutex::new(writer)),
+            lines: Arc::new(tokio::sync::Mutex::new(lines_rx)),
+            alive: Arc::new(AtomicBool::new(true)),
+        };
+        let reader = tokio::spawn(read_loop(
+            reader,
+            lines_tx,
+            self.state.inbound_tx.clone(),
+            self.number.clone(),
+            link.alive.clone(),
+        ));
+        let session = Session {
+            link: link.clone(),
+            reader,
+        };
+        self.initialize(&link).await?;
+        info!("Modem {} ready", self.path);
+        Ok(session)
+    }
+
+    #[cfg(feature = "serial")]
+    fn open_port(&self) -> Result<BoxStream, SmsError> {
+        use tokio_serial::SerialPortBuilderExt;
+        let port = tokio_serial::new(&self.path, self.baud_rate)
+            .open_native_async()
+            .map_err(|e| SmsError::Http(format!("opening {}: {}", self.path, e)))?;
+        Ok(Box::new(port))
+    }
+
+    #[cfg(not(feature = "serial"))]
+    fn open_port(&self) -> Result<BoxStream, SmsError> {
+        Err(SmsError::Invalid(format!(
+            "cannot open {}: sms-modem was built without the `serial` feature",
+            self.path
+        )))
+    }
+
+    /// Turn echo off, unlock the SIM, and switch to PDU mode with received
+    /// messages and status reports routed straight to us.
+    async fn initialize(&self, link: &Link) -> Result<(), SmsError> {
+        self.command(link, "AT", None).await?;
+        self.command(link, "ATE0", None).await?;
+        if let Some(pin) = &self.pin {
+            let status = self.command(link, "AT+CPIN?", None).await?;
+            if status.iter().any(|l| l.contains("SIM PIN")) {
+                let unlock = format!("AT+CPIN=\"{}\"", pin.expose_secret());
+                self.command(link, &unlock, None).await?;
+            }
+        }
+        self.command(link, "AT+CMGF=0", None).await?;
+        self.command(link, "AT+CNMI=2,2,0,1,0", None).await?;
+        Ok(())
+    }
+
+    /// Run `command`, writing `pdu` at the `> ` prompt if given, and
+    /// collect the response lines up to the final `OK`.
+    async fn command(
+        &self,
+        link: &Link,
+        command: &str,
+        pdu: Option<&str>,
+    ) -> Result<Vec<String>, SmsError> {
+        let mut lines = link.lines.lock().await;
+        while lines.try_recv().is_ok() {}
+        link.write(format!("{}\r", command).as_bytes()).await?;
+
+        let exchange = async {
+            if let Some(pdu) = pdu {
+                loop {
+                    match lines.recv().await {
+                        Some(Line::Prompt) => break,
+                        Some(Line::Text(line)) => {
+                            if let Some(e) = final_error(&line) {
+                                return Err(e);
+                            }
+                        }
+                        None => return Err(closed()),
+                    }
+                }
+                link.write(format!("{}\x1a", pdu).as_bytes()).await?;
+            }
+            let mut response = Vec::new();
+            loop {
+                match lines.recv().await {
+                    Some(Line::Text(line)) if line == "OK" => return Ok(response),
+                    Some(Line::Text(line)) => match final_error(&line) {
+                        Some(e) => return Err(e),
+                        None => response.push(line),
+                    },
+                    Some(Line::Prompt) => {}
+                    None => return Err(closed()),
+                }
+            }
+        };
+        match tokio::time::timeout(self.timeout, exchange).await {
+            Ok(result) => result,
+            Err(_) => {
+                if pdu.is_some() {
+                    // Abandon PDU input so the modem accepts commands again.
+                    let _ = link.write(b"\x1b").await;
+                }
+                // The name only: `AT+CPIN=` carries the PIN.
+                let name = command.split('=').next().unwrap_or(command);
+                Err(SmsError::Timeout(format!("no response to {}", name)))
+            }
+        }
+    }
+}
+
+fn closed() -> SmsError {
+    SmsError::Http("modem connection closed".into())
+}
+
+/// The error a final result line stands for, if it is one.
+fn final_error(line: &str) -> Option<SmsError> {
+    if line == "ERROR" {
+        return Some(SmsError::Provider("modem returned ERROR".into()));
+    }
+    if let Some(code) = line.strip_prefix("+CMS ERROR:") {
+        return Some(cms_error(code.trim()));
+    }
+    line.strip_prefix("+CME ERROR:")
+        .map(|code| SmsError::Provider(format!("+CME ERROR: {}", code.trim())))
+}
+
+/// Map a `+CMS ERROR` code to an [`SmsError`].
+fn cms_error(code: &str) -> SmsError {
+    match code.parse::<u16>() {
+        Ok(CMS_CONGESTION) => SmsError::RateLimited { retry_after: None },
+        Ok(CMS_UNASSIGNED_NUMBER | CMS_INVALID_PDU_PARAMETER) => {
+            SmsError::Invalid(format!("+CMS ERROR: {}", code))
+        }
+        Ok(CMS_SIM_PIN_REQUIRED | CMS_SIM_PUK_REQUIRED) => {
+            SmsError::Auth(format!("SIM is locked (+CMS ERROR: {})", code))
+        }
+        Ok(CMS_NETWORK_TIMEOUT) => SmsError::Timeout("network timeout (+CMS ERROR: 332)".into()),
+        _ => SmsError::Provider(format!("+CMS ERROR: {}", code)),
+    }
+}
+
+/// The reader task: splits the modem's output into lines, passes command
+/// responses to the waiting command, and queues every `+CMT` and `+CDS`
+/// as an [`InboundMessage`].
+async fn read_loop(
+    mut reader: ReadHalf<BoxStream>,
+    lines: mpsc::UnboundedSender<Line>,
+    inbound: mpsc::UnboundedSender<InboundMessage>,
+    number: String,
+    alive: Arc<AtomicBool>,
+) {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 512];
+    let mut pdu_follows = false;
+    let mut parts = Reassembly::default();
+    loop {
+        match reader.read(&mut chunk).await {
+            Ok(0) => {
+                warn!("Modem reader stopping: end of stream");
+                break;
+            }
+            Ok(n) => buf.extend_from_slice(&chunk[..n]),
+            Err(e) => {
+                warn!("Modem reader stopping: {}", e);
+                break;
+            }
+        }
+        loop {
+            let blank = buf
+                .iter()
+                .take_while(|b| matches!(b, b'\r' | b'\n'))
+                .count();
+            buf.drain(..blank);
+            if buf.starts_with(b"> ") {
+                buf.drain(..2);
+                let _ = lines.send(Line::Prompt);
+                continue;
+            }
+            let Some(end) = buf.iter().position(|b| matches!(b, b'\r' | b'\n')) else {
+                break;
+            };
+            let line = String::from_utf8_lossy(&buf[..end]).trim().to_string();
+            buf.drain(..end);
+
+            if pdu_follows {
+                pdu_follows = false;
+                match pdu::decode(&line) {
+                    Ok(Received::Deliver(part)) => {
+                        if let Some((message, pdus)) = parts.add(part, line) {
+                            debug!("Modem received a message from {}", message.from);
+                            let _ = inbound.send(deliver_message(message, pdus, &number));
+                        }
+                    }
+                    Ok(Received::StatusReport(report)) => {
+                        let _ = inbound.send(report_message(report, line));
+                    }
+                    Err(e) => warn!("Undecodable PDU from modem: {}", e),
+                }
+            } else if line.starts_with("+CMT:") || line.starts_with("+CDS:") {
+                pdu_follows = true;
+            } else {
+                let _ = lines.send(Line::Text(line));
+            }
+        }
+    }
+    alive.store(false, Ordering::Relaxed);
+}
+
+/// A received part together with its PDU hex.
+type Part = (DeliverPdu, String);
+
+/// Parts of multipart messages received so far, by sender and reference.
+#[derive(Default)]
+struct Reassembly {
+    pending: HashMap<(String, u16), Vec<Option<Part>>>,
+}
+
+impl Reassembly {
+    /// Add a received part with its PDU hex, returning the whole message
+    /// and its PDUs once every part is in.
+    fn add(&mut self, part: DeliverPdu, hex: String) -> Option<(DeliverPdu, Vec<String>)> {
+        let concat = match part.concat {
+            Some(concat) if concat.total > 1 => concat,
+            _ => return Some((part, vec![hex])),
+        };
+        let key = (part.from.clone(), concat.reference);
+        let slots = self
+            .pending
+            .entry(key.clone())
+            .or_insert_with(|| vec![None; concat.total as usize]);
+        match slots.get_mut((concat.seq as usize).wrapping_sub(1)) {
+            Some(slot) => *slot = Some((part, hex)),
+            None => warn!("Dropping part {} of {}", concat.seq, concat.total),
+        }
+        if slots.iter().any(Option::is_none) {
+            return None;
+        }
+        let mut received = self.pending.remove(&key)?.into_iter().flatten();
+        let (mut message, first) = received.next()?;
+        let mut pdus = vec![first];
+        for (part, hex) in received {
+            message.text.push_str(&part.text);
+            pdus.push(hex);
+        }
+        Some((message, pdus))
+    }
+}
+
+fn deliver_message(message: DeliverPdu, pdus: Vec<String>, number: &str) -> InboundMessage {
+    InboundMessage {
+        id: None,
+        raw: serde_json::json!({
+            "pdus": pdus,
+            "smsc": message.smsc,
+        }),
+        from: message.from,
+        to: number.to_string(),
+        text: message.text,
+        timestamp: message.timestamp,
+        provider: PROVIDER,
+        direction: InboundDirection::Inbound,
+        channel: Channel::Sms,
+        provider_account: None,
+        thread_id: None,
+        flag: None,
+    }
+    .with_thread_id()
+}
+
+/// A status report as a delivery report; the id is the `+CMGS` reference
+/// of the message it is about.
+fn report_message(report: StatusReport, hex: String) -> InboundMessage {
+    InboundMessage {
+        id: Some(report.reference.to_string()),
+        from: "MODEM".to_string(),
+        to: report.recipient.clone(),
+        text: format!("Delivery Status: {}", report.status_name()),
+        timestamp: report.discharged,
+        provider: PROVIDER,
+        direction: InboundDirection::DeliveryReport,
+        channel: Channel::Sms,
+        provider_account: None,
+        thread_id: None,
+        flag: None,
+        raw: serde_json::json!({
+            "pdu": hex,
+            "status": report.status,
+        }),
+    }
+    .with_thread_id()
+}
+
+impl SmsClient for ModemClient {
+    /// Send as one `AT+CMGS` per part.  The response id is the modem's
+    /// message reference for the first part, which is what status reports
+    /// carry; it is only unique among the last 256 sends.
+    async fn send(&self, req: SendRequest<'_>) -> Result<SendResponse, SmsError> {
+        sms_core::validate_text(req.text, self.max_text_len())?;
+        if !req.to.bytes().any(|b| b.is_ascii_digit()) {
+            return Err(SmsError::Invalid(format!(
+                "{} is not a phone number",
+                req.to
+            )));
+        }
+        let reference = self.state.reference.fetch_add(1, Ordering::Relaxed);
+        let parts = pdu::encode_submit(req.to, req.text, self.status_reports, reference);
+        if parts.len() > MAX_PARTS {
+            return Err(SmsError::Invalid(format!(
+                "message needs {} parts; the limit is {}",
+                parts.len(),
+                MAX_PARTS
+            )));
+        }
+        let link = self.link().await?;
+        debug!(
+            "Modem sending {} part(s) to {}",
+            parts.len(),
+            sms_core::redact_number(req.to)
+        );
+        let mut references = Vec::with_capacity(parts.len());
+        for part in &parts {
+            let command = format!("AT+CMGS={}", part.tpdu_len);
+            let response = self.command(&link, &command, Some(&part.hex)).await?;
+            let mr = response
+                .iter()
+                .find_map(|line| line.strip_prefix("+CMGS:"))
+                .map(|mr| mr.trim().to_string())
+                .ok_or_else(|| SmsError::Unexpected("modem sent no +CMGS reference".into()))?;
+            references.push(mr);
+        }
+
+        Ok(SendResponse {
+            id: references[0].clone(),
+            raw: serde_json::json!({
+                "references": references,
+                "parts": parts.len(),
+                "path": self.path,
+            }),
+            provider: PROVIDER,
+            channel: Channel::Sms,
+            http_status: None,
+            provider_request_id: None,
+            thread_id: Some(req.thread_id()),
+        })
+    }
+}
+
+#[async_trait]
+impl InboundPoller for ModemClient {
+    fn provider(&self) -> &'static str {
+        PROVIDER
+    }
+
+    /// Drain the messages and status reports queued since the last poll,
+    /// then reopen the port if it has gone away.  A failed reopen is only
+    /// an error when there was nothing to return.
+    async fn poll(&self) -> Result<Vec<InboundMessage>, SmsError> {
+        let mut messages = Vec::new();
+        {
+            let mut rx = self.state.inbound_rx.lock().await;
+            while let Ok(message) = rx.try_recv() {
+                messages.push(message);
+            }
+        }
+        match self.open().await {
+            Err(e) if messages.is_empty() => Err(e),
+            Err(e) => {
+                warn!("Modem reopen failed: {}", e);
+                Ok(messages)
+            }
+            Ok(()) => Ok(messages),
+        }
+    }
+}
+
+/// The `modem` configuration section read by the provider plugin.
+#[derive(Deserialize)]
+struct ModemSettings {
+    path: String,
+    baud_rate: Option<u32>,
+    number: Option<String>,
+    pin: Option<String>,
+    #[serde(default = "default_true")]
+    status_reports: bool,
+    timeout_secs: Option<u64>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Build a [`ModemClient`] from the `modem` section.  The plugin only
+/// registers the sending side; hand a clone of the client to a polling
+/// runner to receive.
+fn build_plugin(settings: &serde_json::Value) -> Result<sms_core::BuiltProvider, SmsError> {
+    let s: ModemSettings = sms_core::provider_settings(PROVIDER, settings)?;
+    let mut client = ModemClient::new(s.path)
+        .with_baud_rate(s.baud_rate.unwrap_or(DEFAULT_BAUD_RATE))
+        .with_status_reports(s.status_reports);
+    if let Some(number) = s.number {
+        client = client.with_number(number);
+    }
+    if let Some(pin) = s.pin {
+        client = client.with_pin(pin);
+    }
+    if let Some(secs) = s.timeout_secs {
+        client = client.with_timeout(Duration::from_secs(secs));
+    }
+    Ok(sms_core::BuiltProvider::sender(client))
+}
+
+sms_core::register_provider!("modem", build_plugin);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use sms_core::OwnedSendRequest;
+
+    /// A modem that answers every command `OK`, answers one `AT+CMGS` PDU
+    /// with `cmgs_reply`, pushes `urcs`, and hangs up.  Returns the client
+    /// end and the commands and PDUs it saw.
+    fn fake_modem(
+        cmgs_reply: &'static str,
+        urcs: &'static str,
+    ) -> (ModemClient, JoinHandle<Vec<String>>) {
+        let (client_end, mut modem) = tokio::io::duplex(4096);
+        let task = tokio::spawn(async move {
+            let mut seen = Vec::new();
+            let mut line = Vec::new();
+            let mut byte = [0u8; 1];
+            while modem.read(&mut byte).await.unwrap_or(0) == 1 {
+                if !matches!(byte[0], b'\r' | 0x1A) {
+                    line.push(byte[0]);
+                    continue;
+                }
+                let text = String::from_utf8(std::mem::take(&mut line)).unwrap();
+                let reply = if byte[0] == 0x1A {
+                    cmgs_reply
+                } else if text.starts_with("AT+CMGS=") {
+                    "\r\n> "
+                } else {
+                    "\r\nOK\r\n"
+                };
+                seen.push(text);
+                modem.write_all(reply.as_bytes()).await.unwrap();
+                if byte[0] == 0x1A {
+                    modem.write_all(urcs.as_bytes()).await.unwrap();
+                    break;
+                }
+            }
+            seen
+        });
+        (ModemClient::from_stream(client_end), task)
+    }
+
+    /// Poll until `count` messages arrive or a second passes.
+    async fn poll_messages(client: &ModemClient, count: usize) -> Vec<InboundMessage> {
+        let mut messages = Vec::new();
+        for _ in 0..100 {
+            messages.extend(client.poll().await.unwrap_or_default());
+            if messages.len() >= count {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        messages
+    }
+
+    #[tokio::test]
+    async fn sends_and_receives() {
+        let (client, modem) = fake_modem(
+            "\r\n+CMGS: 17\r\n\r\nOK\r\n",
+            concat!(
+                "\r\n+CMT: ,30\r\n",
+                "00400B915155214365F70008421010214365000A0500032A020165E5672C\r\n",
+                "\r\n+CMT: ,28\r\n",
+                "00400B915155214365F7000842101021436500080500032A02020021\r\n",
+                "\r\n+CDS: 25\r\n",
+                "0006110B915155214365F7421010214365004210102153650000\r\n",
+            ),
+        );
+        let client = client.with_number("+15550000000");
+        let req = OwnedSendRequest::new("+15551234567", "", "hello");
+        let resp = client.send(req.as_ref()).await.unwrap();
+        assert_eq!(resp.id, "17");
+        assert_eq!(resp.provider, "modem");
+
+        let seen = modem.await.unwrap();
+        assert_eq!(seen[..4], ["AT", "ATE0", "AT+CMGF=0", "AT+CNMI=2,2,0,1,0"]);
+        assert_eq!(seen[4], "AT+CMGS=18");
+        assert_eq!(seen[5], "0021000B915155214365F7000005E8329BFD06");
+
+        let messages = poll_messages(&client, 2).await;
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].direction, InboundDirection::Inbound);
+        assert_eq!(messages[0].from, "+15551234567");
+        assert_eq!(messages[0].to, "+15550000000");
+        assert_eq!(messages[0].text, "日本!");
+        assert_eq!(messages[0].raw["pdus"].as_array().unwrap().len(), 2);
+        assert_eq!(messages[1].direction, InboundDirection::DeliveryReport);
+        assert_eq!(messages[1].id.as_deref(), Some("17"));
+        assert_eq!(messages[1].to, "+15551234567");
+        assert_eq!(messages[1].text, "Delivery Status: DELIVERED");
+    }
+
+    #[tokio::test]
+    async fn cms_errors_fail_the_send() {
+        let (client, modem) = fake_modem("\r\n+CMS ERROR: 42\r\n", "");
+        let req = OwnedSendRequest::new("+15551234567", "", "hello");
+        assert!(matches!(
+            client.send(req.as_ref()).await,
+            Err(SmsError::RateLimited { .. })
+        ));
+        modem.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn rejects_numbers_without_digits() {
+        let client = ModemClient::new("/dev/null");
+        let req = OwnedSendRequest::new("MyBrand", "", "hello");
+        assert!(matches!(
+            client.send(req.as_ref()).await,
+            Err(SmsError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn final_result_lines_map_to_errors() {
+        assert!(final_error("OK").is_none());
+        assert!(final_error("+CMGS: 3").is_none());
+        assert!(matches!(final_error("ERROR"), Some(SmsError::Provider(_))));
+        assert!(matches!(
+            final_error("+CMS ERROR: 304"),
+            Some(SmsError::Invalid(_))
+        ));
+        assert!(matches!(
+            final_error("+CMS ERROR: 311"),
+            Some(SmsError::Auth(_))
+        ));
+        assert!(matches!(
+            final_error("+CME ERROR: 10"),
+            Some(SmsError::Provider(_))
+        ));
+    }
+
+    #[test]
+    fn single_part_messages_pass_through_reassembly() {
+        let mut parts = Reassembly::default();
+        let part = DeliverPdu {
+            smsc: None,
+            from: "+15551234567".into(),
+            timestamp: None,
+            text: "hi".into(),
+            concat: None,
+        };
+        let (message, pdus) = parts.add(part, "00".into()).unwrap();
+        assert_eq!(message.text, "hi");
+        assert_eq!(pdus, ["00"]);
+    }
+
+    #[test]
+    fn plugin_builds_a_sender() {
+        let plugin = sms_core::ProviderPlugin::find("modem").unwrap();
+        let built = plugin
+            .build(&json!({
+                "path": "/dev/ttyUSB0",
+                "baud_rate": 9600,
+                "pin": "1234"
+            }))
+            .unwrap();
+        assert!(built.client.is_some());
+        assert!(built.webhook.is_none());
+        assert!(plugin.build(&json!({ "baud_rate": 9600 })).is_err());
+    }
+
+    #[test]
+    fn debug_redacts_pin() {
+        let debug = format!("{:?}", ModemClient::new("/dev/ttyUSB0").with_pin("8642"));
+        assert!(debug.contains("/dev/ttyUSB0"));
+        assert!(!debug.contains("8642"));
+    }
+}
@@ -0,0 +1,659 @@
+//! GSM 03.40 PDU-mode encoding.
+//!
+//! Modems in PDU mode (`AT+CMGF=0`) take outgoing messages as hex-encoded
+//! SMS-SUBMIT TPDUs and report incoming ones as SMS-DELIVER and
+//! SMS-STATUS-REPORT TPDUs, each prefixed by the SMSC address.  This module
+//! builds the former and parses the latter, including the GSM 7-bit default
+//! alphabet, UCS-2, and concatenated (multipart) messages.
+
+use sms_core::SmsError;
+use time::{Date, Month, OffsetDateTime, Time, UtcOffset};
+
+/// The GSM 03.38 default alphabet, indexed by septet.  Index 27 is the
+/// escape to the extension table.
+const GSM7_BASIC: &str = "@£$¥èéùìòÇ\nØø\rÅåΔ_ΦΓΛΩΠΨΣΘΞ\u{1b}ÆæßÉ !\"#¤%&'()*+,-./0123456789:;<=>?¡ABCDEFGHIJKLMNOPQRSTUVWXYZÄÖÑÜ§¿abcdefghijklmnopqrstuvwxyzäöñüà";
+
+/// Extension table characters, sent as escape + septet.
+const GSM7_EXTENSION: [(char, u8); 10] = [
+    ('\u{c}', 0x0A),
+    ('^', 0x14),
+    ('{', 0x28),
+    ('}', 0x29),
+    ('\\', 0x2F),
+    ('[', 0x3C),
+    ('~', 0x3D),
+    (']', 0x3E),
+    ('|', 0x40),
+    ('€', 0x65),
+];
+
+const ESCAPE: u8 = 0x1B;
+
+/// `TP-DCS` for the GSM 7-bit default alphabet.
+const DCS_GSM7: u8 = 0x00;
+/// `TP-DCS` for UCS-2.
+const DCS_UCS2: u8 = 0x08;
+
+/// Septets in a single-part GSM 7-bit message, and per part when split.
+const GSM7_SINGLE: usize = 160;
+const GSM7_PART: usize = 153;
+/// UTF-16 units in a single-part UCS-2 message, and per part when split.
+const UCS2_SINGLE: usize = 70;
+const UCS2_PART: usize = 67;
+
+/// An encoded SMS-SUBMIT, ready for `AT+CMGS`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubmitPdu {
+    /// Hex PDU including the (empty) SMSC prefix.
+    pub hex: String,
+    /// TPDU length in octets, excluding the SMSC prefix, as `AT+CMGS`
+    /// expects.
+    pub tpdu_len: usize,
+}
+
+/// Encode `text` to `to` as one SMS-SUBMIT per part.
+///
+/// Text that fits the GSM 7-bit alphabet is sent in it, anything else as
+/// UCS-2.  Longer messages are split with a concatenation header carrying
+/// `reference`.
+pub fn encode_submit(to: &str, text: &str, status_report: bool, reference: u8) -> Vec<SubmitPdu> {
+    let parts: Vec<(u8, Vec<u8>)> = match gsm7_septets(text) {
+        Some(septets) if septets.len() <= GSM7_SINGLE => vec![(DCS_GSM7, septets)],
+        Some(septets) => split(&septets, GSM7_PART, |s| s == ESCAPE)
+            .into_iter()
+            .map(|part| (DCS_GSM7, part))
+            .collect(),
+        None => {
+            let units: Vec<u16> = text.encode_utf16().collect();
+            let chunks = if units.len() <= UCS2_SINGLE {
+                vec![units]
+            } else {
+                split(&units, UCS2_PART, |u| (0xD800..0xDC00).contains(&u))
+            };
+            chunks
+                .into_iter()
+                .map(|units| {
+                    let bytes = units.iter().flat_map(|u| u.to_be_bytes()).collect();
+                    (DCS_UCS2, bytes)
+                })
+                .collect()
+        }
+    };
+
+    let total = parts.len();
+    parts
+        .into_iter()
+        .enumerate()
+        .map(|(i, (dcs, payload))| {
+            let udh =
+                (total > 1).then(|| vec![0x05, 0x00, 0x03, reference, total as u8, i as u8 + 1]);
+            submit(to, dcs, &payload, udh.as_deref(), status_report)
+        })
+        .collect()
+}
+
+/// Split `items` into chunks of at most `max`, never ending a chunk on an
+/// item for which `keep_with_next` holds (an escape or high surrogate).
+fn split<T: Copy>(items: &[T], max: usize, keep_with_next: impl Fn(T) -> bool) -> Vec<Vec<T>> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    while start < items.len() {
+        let mut end = (start + max).min(items.len());
+        if end < items.len() && keep_with_next(items[end - 1]) {
+            end -= 1;
+        }
+        parts.push(items[start..end].to_vec());
+        start = end;
+    }
+    parts
+}
+
+fn submit(to: &str, dcs: u8, payload: &[u8], udh: Option<&[u8]>, status_report: bool) -> SubmitPdu {
+    let mut first = 0x01; // SMS-SUBMIT, no validity period
+    if udh.is_some() {
+        first |= 0x40;
+    }
+    if status_report {
+        first |= 0x20;
+    }
+    let mut tpdu = vec![first, 0x00]; // TP-MR: let the modem assign it
+    tpdu.extend(encode_address(to));
+    tpdu.push(0x00); // TP-PID
+    tpdu.push(dcs);
+    let udh = udh.unwrap_or_default();
+    if dcs == DCS_GSM7 {
+        let (udl, ud) = pack_septets(udh, payload);
+        tpdu.push(udl as u8);
+        tpdu.extend(ud);
+    } else {
+        tpdu.push((udh.len() + payload.len()) as u8);
+        tpdu.extend_from_slice(udh);
+        tpdu.extend_from_slice(payload);
+    }
+    SubmitPdu {
+        hex: format!("00{}", to_hex(&tpdu)),
+        tpdu_len: tpdu.len(),
+    }
+}
+
+/// Map `text` to GSM 7-bit septets, or `None` if any character is outside
+/// the default alphabet and its extension table.
+pub fn gsm7_septets(text: &str) -> Option<Vec<u8>> {
+    let mut septets = Vec::with_capacity(text.len());
+    for c in text.chars() {
+        if c != '\u{1b}'
+            && let Some(i) = GSM7_BASIC.chars().position(|g| g == c)
+        {
+            septets.push(i as u8);
+            continue;
+        }
+        let (_, code) = GSM7_EXTENSION.iter().find(|(e, _)| *e == c)?;
+        septets.push(ESCAPE);
+        septets.push(*code);
+    }
+    Some(septets)
+}
+
+/// Map GSM 7-bit septets back to text.
+fn gsm7_text(septets: &[u8]) -> String {
+    let mut text = String::with_capacity(septets.len());
+    let mut iter = septets.iter();
+    while let Some(&s) = iter.next() {
+        if s == ESCAPE {
+            if let Some(&code) = iter.next() {
+                let c = GSM7_EXTENSION
+                    .iter()
+                    .find(|(_, e)| *e == code)
+                    .map_or(' ', |(c, _)| *c);
+                text.push(c);
+            }
+        } else {
+            text.push(GSM7_BASIC.chars().nth(s as usize).unwrap_or('?'));
+        }
+    }
+    text
+}
+
+/// Fill bits after a user data header of `udh_len` octets, so the text
+/// starts on a septet boundary.
+fn fill_bits(udh_len: usize) -> usize {
+    (7 - (udh_len * 8) % 7) % 7
+}
+
+/// Pack `udh` and `septets` into user data, returning `(TP-UDL, bytes)`;
+/// the length counts septets, header included.
+fn pack_septets(udh: &[u8], septets: &[u8]) -> (usize, Vec<u8>) {
+    let mut out = Vec::new();
+    let (mut acc, mut bits) = (0u32, 0usize);
+    let mut push = |value: u8, n: usize, out: &mut Vec<u8>| {
+        acc |= (value as u32 & ((1 << n) - 1)) << bits;
+        bits += n;
+        while bits >= 8 {
+            out.push(acc as u8);
+            acc >>= 8;
+            bits -= 8;
+        }
+    };
+    for &b in udh {
+        push(b, 8, &mut out);
+    }
+    let fill = if udh.is_empty() {
+        0
+    } else {
+        fill_bits(udh.len())
+    };
+    if fill > 0 {
+        push(0, fill, &mut out);
+    }
+    for &s in septets {
+        push(s, 7, &mut out);
+    }
+    if bits > 0 {
+        out.push(acc as u8);
+    }
+    let header_septets = (udh.len() * 8 + fill) / 7;
+    (header_septets + septets.len(), out)
+}
+
+/// Unpack `count` septets starting `skip_bits` into `ud`.
+fn unpack_septets(ud: &[u8], skip_bits: usize, count: usize) -> Vec<u8> {
+    (0..count)
+        .map(|i| {
+            let bit = skip_bits + i * 7;
+            let (byte, shift) = (bit / 8, bit % 8);
+            let lo = *ud.get(byte).unwrap_or(&0) as u16;
+            let hi = *ud.get(byte + 1).unwrap_or(&0) as u16;
+            (((hi << 8 | lo) >> shift) & 0x7F) as u8
+        })
+        .collect()
+}
+
+/// Encode a destination as `TP-DA`: digit count, type, swapped digits.
+fn encode_address(number: &str) -> Vec<u8> {
+    let international = number.starts_with('+');
+    let digits: Vec<u8> = number
+        .bytes()
+        .filter(u8::is_ascii_digit)
+        .map(|b| b - b'0')
+        .collect();
+    let mut out = vec![digits.len() as u8, if international { 0x91 } else { 0x81 }];
+    out.extend(
+        digits
+            .chunks(2)
+            .map(|pair| pair[0] | pair.get(1).copied().unwrap_or(0x0F) << 4),
+    );
+    out
+}
+
+/// Concatenation header of one part of a multipart message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Concat {
+    /// Reference shared by every part.
+    pub reference: u16,
+    /// Number of parts.
+    pub total: u8,
+    /// This part's 1-based position.
+    pub seq: u8,
+}
+
+/// A decoded SMS-DELIVER.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeliverPdu {
+    /// SMSC that delivered the message, if given.
+    pub smsc: Option<String>,
+    /// Sender number or alphanumeric ID.
+    pub from: String,
+    /// SMSC timestamp.
+    pub timestamp: Option<OffsetDateTime>,
+    /// Decoded text of this part.
+    pub text: String,
+    /// Concatenation header, for parts of multipart messages.
+    pub concat: Option<Concat>,
+}
+
+/// A decoded SMS-STATUS-REPORT.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusReport {
+    /// `TP-MR` of the message the report is about, as returned by
+    /// `+CMGS`.
+    pub reference: u8,
+    /// Recipient of that message.
+    pub recipient: String,
+    /// When the SMSC delivered (or gave up on) the message.
+    pub discharged: Option<OffsetDateTime>,
+    /// `TP-ST`: `0x00`–`0x1F` delivered, `0x20`–`0x3F` still trying,
+    /// anything higher failed.
+    pub status: u8,
+}
+
+impl StatusReport {
+    /// `"DELIVERED"`, `"PENDING"` or `"FAILED"`.
+    pub fn status_name(&self) -> &'static str {
+        match self.status {
+            0x00..=0x1F => "DELIVERED",
+            0x20..=0x3F => "PENDING",
+            _ => "FAILED",
+        }
+    }
+}
+
+/// A decoded incoming PDU.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Received {
+    /// A message from a handset.
+    Deliver(DeliverPdu),
+    /// A delivery report for a message we sent.
+    StatusReport(StatusReport),
+}
+
+/// Decode a hex PDU as reported by `+CMT`, `+CDS` or `+CMGL` in PDU mode,
+/// SMSC prefix included.
+pub fn decode(hex: &str) -> Result<Received, SmsError> {
+    let bytes = from_hex(hex.trim())?;
+    let mut r = Reader {
+        buf: &bytes,
+        pos: 0,
+    };
+    let smsc_len = r.u8()? as usize;
+    let smsc = if smsc_len == 0 {
+        None
+    } else {
+        let ty = r.u8()?;
+        let digits = semi_octets(r.bytes(smsc_len - 1)?);
+        Some(if ty & 0x70 == 0x10 {
+            format!("+{}", digits)
+        } else {
+            digits
+        })
+    };
+    let first = r.u8()?;
+    match first & 0x03 {
+        0x00 => decode_deliver(&mut r, first, smsc).map(Received::Deliver),
+        0x02 => {
+            let reference = r.u8()?;
+            let recipient = r.address()?;
+            r.bytes(7)?; // TP-SCTS
+            let discharged = timestamp(r.bytes(7)?);
+            let status = r.u8()?;
+            Ok(Received::StatusReport(StatusReport {
+                reference,
+                recipient,
+                discharged,
+                status,
+            }))
+        }
+        mti => Err(SmsError::Invalid(format!("unexpected PDU type {}", mti))),
+    }
+}
+
+fn decode_deliver(
+    r: &mut Reader<'_>,
+    first: u8,
+    smsc: Option<String>,
+) -> Result<DeliverPdu, SmsError> {
+    let from = r.address()?;
+    r.u8()?; // TP-PID
+    let dcs = r.u8()?;
+    let timestamp = timestamp(r.bytes(7)?);
+    let udl = r.u8()? as usize;
+    let ud = r.rest();
+
+    let (udh, concat) = if first & 0x40 != 0 {
+        let udh_len = *ud.first().ok_or_else(truncated)? as usize;
+        let udh = ud.get(1..1 + udh_len).ok_or_else(truncated)?;
+        (udh_len + 1, concat_header(udh))
+    } else {
+        (0, None)
+    };
+
+    let text = match alphabet(dcs) {
+        Alphabet::Gsm7 => {
+            let fill = if udh == 0 { 0 } else { fill_bits(udh) };
+            let header_septets = (udh * 8 + fill) / 7;
+            let count = udl.saturating_sub(header_septets);
+            gsm7_text(&unpack_septets(ud, udh * 8 + fill, count))
+        }
+        Alphabet::Ucs2 => {
+            let body = ud.get(udh..udl.min(ud.len())).unwrap_or_default();
+            let units: Vec<u16> = body
+                .chunks_exact(2)
+                .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        Alphabet::Eight => {
+            let body = ud.get(udh..udl.min(ud.len())).unwrap_or_default();
+            String::from_utf8_lossy(body).into_owned()
+        }
+    };
+
+    Ok(DeliverPdu {
+        smsc,
+        from,
+        timestamp,
+        text,
+        concat,
+    })
+}
+
+enum Alphabet {
+    Gsm7,
+    Eight,
+    Ucs2,
+}
+
+/// The alphabet a `TP-DCS` selects.
+fn alphabet(dcs: u8) -> Alphabet {
+    match dcs & 0xF0 {
+        0x00..=0x30 => match (dcs >> 2) & 0x03 {
+            1 => Alphabet::Eight,
+            2 => Alphabet::Ucs2,
+            _ => Alphabet::Gsm7,
+        },
+        0xE0 => Alphabet::Ucs2,
+        0xF0 if dcs & 0x04 != 0 => Alphabet::Eight,
+        _ => Alphabet::Gsm7,
+    }
+}
+
+/// Find the 8- or 16-bit concatenation element in a user data header.
+fn concat_header(mut udh: &[u8]) -> Option<Concat> {
+    while udh.len() >= 2 {
+        let (iei, len) = (udh[0], udh[1] as usize);
+        let data = udh.get(2..2 + len)?;
+        match (iei, data) {
+            (0x00, &[reference, total, seq]) => {
+                return Some(Concat {
+                    reference: reference as u16,
+                    total,
+                    seq,
+                });
+            }
+            (0x08, &[hi, lo, total, seq]) => {
+                return Some(Concat {
+                    reference: u16::from_be_bytes([hi, lo]),
+                    total,
+                    seq,
+                });
+            }
+            _ => udh = &udh[2 + len..],
+        }
+    }
+    None
+}
+
+/// Decode a `TP-SCTS`-style timestamp: swapped-digit year, month, day,
+/// hour, minute, second, and quarter-hour time zone.
+fn timestamp(b: &[u8]) -> Option<OffsetDateTime> {
+    let swap = |b: u8| (b & 0x0F) * 10 + (b >> 4);
+    let date = Date::from_calendar_date(
+        2000 + swap(b[0]) as i32,
+        Month::try_from(swap(b[1])).ok()?,
+        swap(b[2]),
+    )
+    .ok()?;
+    let time = Time::from_hms(swap(b[3]), swap(b[4]), swap(b[5])).ok()?;
+    let quarters = ((b[6] & 0x07) * 10 + (b[6] >> 4)) as i32;
+    let sign = if b[6] & 0x08 != 0 { -1 } else { 1 };
+    let offset = UtcOffset::from_whole_seconds(sign * quarters * 15 * 60).ok()?;
+    Some(date.with_time(time).assume_offset(offset))
+}
+
+/// Swapped-nibble digits, dropping the `F` pad.
+fn semi_octets(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .flat_map(|b| [b & 0x0F, b >> 4])
+        .filter_map(|n| match n {
+            0..=9 => Some((b'0' + n) as char),
+            0x0A => Some('*'),
+            0x0B => Some('#'),
+            _ => None,
+        })
+        .collect()
+}
+
+fn truncated() -> SmsError {
+    SmsError::Invalid("truncated PDU".into())
+}
+
+struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, n: usize) -> Result<&'a [u8], SmsError> {
+        let out = self.buf.get(self.pos..self.pos + n).ok_or_else(truncated)?;
+        self.pos += n;
+        Ok(out)
+    }
+
+    fn u8(&mut self) -> Result<u8, SmsError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn rest(&mut self) -> &'a [u8] {
+        let out = self.buf.get(self.pos..).unwrap_or_default();
+        self.pos = self.buf.len();
+        out
+    }
+
+    /// An originating or recipient address: digit count, type, digits.
+    fn address(&mut self) -> Result<String, SmsError> {
+        let len = self.u8()? as usize;
+        let ty = self.u8()?;
+        let bytes = self.bytes(len.div_ceil(2))?;
+        Ok(match ty & 0x70 {
+            0x50 => gsm7_text(&unpack_septets(bytes, 0, len * 4 / 7)),
+            0x10 => format!("+{}", &semi_octets(bytes)),
+            _ => semi_octets(bytes),
+        })
+    }
+}
+
+/// Uppercase hex, as modems expect.
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02X}", b)).collect()
+}
+
+fn from_hex(hex: &str) -> Result<Vec<u8>, SmsError> {
+    if !hex.len().is_multiple_of(2) {
+        return Err(SmsError::Invalid("odd-length PDU hex".into()));
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&hex[i..i + 2], 16)
+                .map_err(|_| SmsError::Invalid(format!("bad PDU hex at {}", i)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_gsm7_submit() {
+        // The classic "hellohello" example from GSM 03.40 tutorials.
+        let pdus = encode_submit("+46708251358", "hellohello", false, 0);
+        assert_eq!(pdus.len(), 1);
+        assert_eq!(
+            pdus[0].hex,
+            "0001000B916407281553F800000AE8329BFD4697D9EC37"
+        );
+        assert_eq!(pdus[0].tpdu_len, 22);
+    }
+
+    #[test]
+    fn encodes_ucs2_and_extension_characters() {
+        // "é" is in the GSM alphabet and "€" in its extension table.
+        assert_eq!(gsm7_septets("hé€"), Some(vec![0x68, 0x05, ESCAPE, 0x65]));
+
+        let pdus = encode_submit("+15551234567", "日本", true, 0);
+        // TP-SRR set, DCS UCS-2, four octets of user data.
+        assert_eq!(pdus[0].hex, "0021000B915155214365F700080465E5672C");
+    }
+
+    #[test]
+    fn splits_long_messages_with_concatenation_headers() {
+        let text = "a".repeat(200);
+        let pdus = encode_submit("+15551234567", &text, false, 7);
+        assert_eq!(pdus.len(), 2);
+        // First octet has TP-UDHI; the header names reference 7, part 1 of 2.
+        assert!(pdus[0].hex.starts_with("0041"));
+        assert!(pdus[0].hex.contains("050003070201"));
+        assert!(pdus[1].hex.contains("050003070202"));
+
+        // An escape is never stranded at the end of a part.
+        let septets = vec![b'a'; 152]
+            .into_iter()
+            .chain([ESCAPE, 0x65])
+            .collect::<Vec<_>>();
+        let parts = split(&septets, GSM7_PART, |s| s == ESCAPE);
+        assert_eq!(parts[0].len(), 152);
+        assert_eq!(parts[1], vec![ESCAPE, 0x65]);
+    }
+
+    #[test]
+    fn packing_round_trips() {
+        for udh in [&[][..], &[0x05, 0x00, 0x03, 0x01, 0x02, 0x01][..]] {
+            let septets = gsm7_septets("Hello {world}!").unwrap();
+            let (udl, ud) = pack_septets(udh, &septets);
+            let skip = if udh.is_empty() {
+                0
+            } else {
+                udh.len() * 8 + fill_bits(udh.len())
+            };
+            let count = udl - skip / 7;
+            assert_eq!(
+                gsm7_text(&unpack_septets(&ud, skip, count)),
+                "Hello {world}!"
+            );
+        }
+    }
+
+    #[test]
+    fn decodes_deliver() {
+        // SMSC +31624000000, from +31641600986, GSM 7-bit "How are you?".
+        let hex = "07911326040000F0040B911346610089F60000208062917314080CC8F71D14969741F977FD07";
+        let Received::Deliver(pdu) = decode(hex).unwrap() else {
+            panic!("expected SMS-DELIVER");
+        };
+        assert_eq!(pdu.smsc.as_deref(), Some("+31624000000"));
+        assert_eq!(pdu.from, "+31641600986");
+        assert_eq!(pdu.text, "How are you?");
+        assert_eq!(pdu.concat, None);
+        let ts = pdu.timestamp.unwrap();
+        assert_eq!((ts.year(), ts.month() as u8, ts.day()), (2002, 8, 26));
+    }
+
+    #[test]
+    fn decodes_concatenated_ucs2_deliver() {
+        let mut tpdu = vec![0x40, 0x0B, 0x91];
+        tpdu.extend(encode_address("+15551234567").split_off(2));
+        tpdu.extend([0x00, 0x08, 0x42, 0x10, 0x10, 0x21, 0x43, 0x65, 0x00]);
+        let ud = [0x05, 0x00, 0x03, 0x2A, 0x02, 0x01, 0x65, 0xE5, 0x67, 0x2C];
+        tpdu.push(ud.len() as u8);
+        tpdu.extend(ud);
+        let Received::Deliver(pdu) = decode(&format!("00{}", to_hex(&tpdu))).unwrap() else {
+            panic!("expected SMS-DELIVER");
+        };
+        assert_eq!(pdu.smsc, None);
+        assert_eq!(pdu.from, "+15551234567");
+        assert_eq!(pdu.text, "日本");
+        assert_eq!(
+            pdu.concat,
+            Some(Concat {
+                reference: 0x2A,
+                total: 2,
+                seq: 1
+            })
+        );
+    }
+
+    #[test]
+    fn decodes_status_report() {
+        let mut tpdu = vec![0x06, 0x11];
+        tpdu.extend(encode_address("+15551234567"));
+        tpdu.extend([0x42, 0x10, 0x10, 0x21, 0x43, 0x65, 0x00]);
+        tpdu.extend([0x42, 0x10, 0x10, 0x21, 0x53, 0x65, 0x00]);
+        tpdu.push(0x00);
+        let Received::StatusReport(report) = decode(&format!("00{}", to_hex(&tpdu))).unwrap()
+        else {
+            panic!("expected SMS-STATUS-REPORT");
+        };
+        assert_eq!(report.reference, 0x11);
+        assert_eq!(report.recipient, "+15551234567");
+        assert_eq!(report.status_name(), "DELIVERED");
+        assert!(report.discharged.is_some());
+    }
+
+    #[test]
+    fn rejects_malformed_hex() {
+        assert!(decode("0").is_err());
+        assert!(decode("zz").is_err());
+        assert!(decode("0004").is_err());
+    }
+}
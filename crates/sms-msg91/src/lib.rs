@@ -0,0 +1,727 @@
+//! # MSG91 SMS Provider
+//!
+//! [MSG91](https://msg91.com/) backend for the smskit multi-provider SMS
+//! abstraction, covering India's DLT-regulated routes.
+//!
+//! ## Sending messages
+//!
+//! Indian regulations require every commercial SMS to match a template
+//! registered on the DLT platform.  [`SmsClient::send`] uses MSG91's send
+//! API and tags each message with the client's DLT template ID:
+//!
+//! ```rust,ignore
+//! use sms_core::{SendRequest, SmsClient};
+//! use sms_msg91::Msg91Client;
+//!
+//! let client = Msg91Client::new("YOUR_AUTH_KEY", "ACMECO")
+//!     .with_dlt_template_id("1207161234567890123");
+//! let response = client.send(SendRequest {
+//!     to: "+919812345678",
+//!     text: "Your Acme OTP is 123456",
+//!     ..Default::default()
+//! }).await?;
+//! ```
+//!
+//! Templates registered as MSG91 flows are sent with
+//! [`Msg91Client::send_flow`], which fills the flow's variables server-side:
+//!
+//! ```rust,ignore
+//! use sms_msg91::FlowSend;
+//!
+//! let flow = FlowSend::new("64f1c0d2d6fc05123456789a", "+919812345678")
+//!     .with_var("otp", "123456");
+//! client.send_flow(&flow, None).await?;
+//! ```
+//!
+//! ## Delivery reports
+//!
+//! MSG91 POSTs delivery reports to the webhook configured in its panel,
+//! batched per request ID, either as JSON or form-encoded in a `data`
+//! field.  [`InboundWebhook::parse_inbound_batch`] returns one
+//! [`InboundMessage`] per destination.
+
+use serde::{Deserialize, Serialize};
+use sms_core::{
+    Channel, ExposeSecret, Headers, HttpClientConfig, IdGenerator, InboundDirection,
+    InboundMessage, InboundWebhook, SecretString, SendRequest, SendResponse, SmsClient, SmsError,
+    UuidV4,
+};
+use sms_core::http_provider::transport_error;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+const PROVIDER: &str = "msg91";
+
+/// Production API base URL.
+const LIVE_URL: &str = "https://api.msg91.com";
+
+/// Default deadline for a single API call.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Transactional route, the one DLT templates are registered for.
+pub const ROUTE_TRANSACTIONAL: &str = "4";
+
+/// Promotional route.
+pub const ROUTE_PROMOTIONAL: &str = "1";
+
+/// MSG91 REST API client.
+///
+/// Implements [`SmsClient`] for sending SMS and [`InboundWebhook`] for
+/// receiving delivery reports.
+#[derive(Clone)]
+pub struct Msg91Client {
+    /// API auth key, sent in the `authkey` header.  Zeroized on drop.
+    pub auth_key: SecretString,
+    /// Default six-character sender ID, used when a request leaves `from`
+    /// empty.
+    pub sender: String,
+    /// Route (default: [`ROUTE_TRANSACTIONAL`]).
+    pub route: String,
+    /// DLT template ID sent as `DLT_TE_ID` with every
+    /// [`send`](SmsClient::send).
+    pub dlt_template_id: Option<String>,
+    /// API base URL; override with
+    /// [`with_base_url`](Msg91Client::with_base_url) for testing.
+    pub base_url: String,
+    /// Deadline for each API call unless the request sets its own
+    /// [`SendRequest::timeout`].
+    pub timeout: Duration,
+    id_generator: Arc<dyn IdGenerator>,
+    http: reqwest::Client,
+}
+
+impl std::fmt::Debug for Msg91Client {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Msg91Client")
+            .field("auth_key", &"[REDACTED]")
+            .field("sender", &self.sender)
+            .field("route", &self.route)
+            .field("dlt_template_id", &self.dlt_template_id)
+            .field("base_url", &self.base_url)
+            .field("timeout", &self.timeout)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Msg91Client {
+    /// Create a client against the production API.
+    pub fn new(auth_key: impl Into<String>, sender: impl Into<String>) -> Self {
+        Self::with_base_url(auth_key, sender, LIVE_URL)
+    }
+
+    /// Create a client by reading credentials from environment variables.
+    ///
+    /// | Variable                | Maps to           |
+    /// |-------------------------|-------------------|
+    /// | `MSG91_AUTH_KEY`        | `auth_key`        |
+    /// | `MSG91_SENDER`          | `sender`          |
+    /// | `MSG91_DLT_TEMPLATE_ID` | `dlt_template_id` |
+    ///
+    /// Returns [`SmsError::Auth`] if `MSG91_AUTH_KEY` or `MSG91_SENDER` is
+    /// missing.
+    pub fn from_env() -> Result<Self, SmsError> {
+        let auth_key = std::env::var("MSG91_AUTH_KEY")
+            .map_err(|_| SmsError::Auth("MSG91_AUTH_KEY not set".into()))?;
+        let sender = std::env::var("MSG91_SENDER")
+            .map_err(|_| SmsError::Auth("MSG91_SENDER not set".into()))?;
+        let client = Self::new(auth_key, sender);
+        Ok(match std::env::var("MSG91_DLT_TEMPLATE_ID") {
+            Ok(id) => client.with_dlt_template_id(id),
+            Err(_) => client,
+        })
+    }
+
+    /// Create a client with a custom API base URL, e.g. a mock server.
+    pub fn with_base_url(
+        auth_key: impl Into<String>,
+        sender: impl Into<String>,
+        base_url: impl Into<String>,
+    ) -> Self {
+        Self {
+            auth_key: SecretString::from(auth_key.into()),
+            sender: sender.into(),
+            route: ROUTE_TRANSACTIONAL.to_string(),
+            dlt_template_id: None,
+            base_url: base_url.into(),
+            timeout: DEFAULT_TIMEOUT,
+            id_generator: Arc::new(UuidV4),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Send on `route` (default: [`ROUTE_TRANSACTIONAL`]).
+    pub fn with_route(mut self, route: impl Into<String>) -> Self {
+        self.route = route.into();
+        self
+    }
+
+    /// Tag every [`send`](SmsClient::send) with DLT template `id`.
+    pub fn with_dlt_template_id(mut self, id: impl Into<String>) -> Self {
+        self.dlt_template_id = Some(id.into());
+        self
+    }
+
+    /// Set the default deadline for each API call (default: 30 seconds).
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Rebuild the HTTP client with custom transport settings (proxy,
+    /// `User-Agent`, extra headers, etc.).
+    pub fn with_http_config(mut self, config: HttpClientConfig) -> Result<Self, SmsError> {
+        self.http = sms_core::http_provider::client_with(&config)?;
+        Ok(self)
+    }
+
+    /// Generate IDs with `ids` when the response doesn't include one
+    /// (default: [`UuidV4`]).
+    pub fn with_id_generator(mut self, ids: impl IdGenerator + 'static) -> Self {
+        self.id_generator = Arc::new(ids);
+        self
+    }
+
+    /// Send a message through a flow, MSG91's server-side template.  The
+    /// flow's DLT template and sender come from its configuration in the
+    /// MSG91 panel.
+    pub async fn send_flow(
+        &self,
+        flow: &FlowSend,
+        timeout: Option<Duration>,
+    ) -> Result<SendResponse, SmsError> {
+        let (status, raw, request_id) =
+            self.post("/api/v5/flow/", &flow.request(), timeout).await?;
+        Ok(SendResponse {
+            id: request_id.unwrap_or_else(|| self.id_generator.generate()),
+            provider: PROVIDER,
+            channel: Channel::Sms,
+            http_status: Some(status),
+            provider_request_id: None,
+            thread_id: None,
+            raw,
+        })
+    }
+
+    /// POST `payload` to `path` and return the HTTP status, the JSON reply
+    /// and MSG91's request ID.
+    async fn post(
+        &self,
+        path: &str,
+        payload: &impl Serialize,
+        timeout: Option<Duration>,
+    ) -> Result<(u16, serde_json::Value, Option<String>), SmsError> {
+        let url = format!("{}{}", self.base_url.trim_end_matches('/'), path);
+        let res = self
+            .http
+            .post(&url)
+            .header("authkey", self.auth_key.expose_secret())
+            .header(reqwest::header::ACCEPT, "application/json")
+            .timeout(timeout.unwrap_or(self.timeout))
+            .json(payload)
+            .send()
+            .await
+            .map_err(transport_error)?;
+
+        let status = res.status();
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = res
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(sms_core::parse_retry_after);
+            return Err(SmsError::RateLimited { retry_after });
+        }
+        let raw_text = res.text().await.map_err(transport_error)?;
+        if status == reqwest::StatusCode::UNAUTHORIZED {
+            return Err(SmsError::Auth(raw_text));
+        }
+        if !status.is_success() {
+            return Err(SmsError::Provider(format!("HTTP {}: {}", status, raw_text)));
+        }
+
+        let raw: serde_json::Value = serde_json::from_str(&raw_text)
+            .map_err(|e| SmsError::Provider(format!("send response: {}: {}", e, raw_text)))?;
+        let reply: Msg91Reply = serde_json::from_value(raw.clone())
+            .map_err(|e| SmsError::Provider(format!("send response: {}", e)))?;
+        if reply.kind != "success" {
+            return Err(reply.error());
+        }
+        Ok((
+            status.as_u16(),
+            raw,
+            Some(reply.message).filter(|m| !m.is_empty()),
+        ))
+    }
+}
+
+/// A send through a flow, for [`Msg91Client::send_flow`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FlowSend {
+    /// Flow (template) ID from the MSG91 panel.
+    pub template_id: String,
+    /// Destination number, with country code.
+    pub to: String,
+    /// Values for the flow's `##var##` placeholders.
+    pub variables: BTreeMap<String, String>,
+    /// Let MSG91 shorten and track links in the message.
+    pub short_url: bool,
+}
+
+impl FlowSend {
+    /// A send of flow `template_id` to `to` with no variables.
+    pub fn new(template_id: impl Into<String>, to: impl Into<String>) -> Self {
+        Self {
+            template_id: template_id.into(),
+            to: to.into(),
+            variables: BTreeMap::new(),
+            short_url: false,
+        }
+    }
+
+    /// Set the flow variable `name` to `value`.
+    pub fn with_var(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.variables.insert(name.into(), value.into());
+        self
+    }
+
+    /// Shorten and track links in the message (default: off).
+    pub fn with_short_url(mut self, enabled: bool) -> Self {
+        self.short_url = enabled;
+        self
+    }
+
+    fn request(&self) -> FlowRequest<'_> {
+        let mut recipient = BTreeMap::new();
+        recipient.insert("mobiles".to_string(), msisdn(&self.to).to_string());
+        recipient.extend(self.variables.clone());
+        FlowRequest {
+            template_id: &self.template_id,
+            short_url: if self.short_url { "1" } else { "0" },
+            recipients: vec![recipient],
+        }
+    }
+}
+
+/// Wire format for the v2 send-SMS request body.
+#[derive(Debug, Serialize)]
+struct SendSmsRequest<'a> {
+    sender: &'a str,
+    route: &'a str,
+    /// `0` means numbers already carry their country code.
+    country: &'a str,
+    #[serde(rename = "DLT_TE_ID", skip_serializing_if = "Option::is_none")]
+    dlt_te_id: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    unicode: Option<u8>,
+    sms: [SendSmsMessage<'a>; 1],
+}
+
+#[derive(Debug, Serialize)]
+struct SendSmsMessage<'a> {
+    message: &'a str,
+    to: [&'a str; 1],
+}
+
+/// Wire format for the v5 flow request body.
+#[derive(Debug, Serialize)]
+struct FlowRequest<'a> {
+    template_id: &'a str,
+    short_url: &'a str,
+    recipients: Vec<BTreeMap<String, String>>,
+}
+
+/// `{"type": "success" | "error", "message": ...}`, MSG91's reply to both
+/// send APIs.  On success `message` is the request ID.
+#[derive(Debug, Deserialize)]
+struct Msg91Reply {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    message: String,
+    #[serde(default)]
+    code: Option<serde_json::Value>,
+}
+
+impl Msg91Reply {
+    fn error(self) -> SmsError {
+        let code = self.code.map(|c| c.to_string()).unwrap_or_default();
+        // 418 is MSG91's "IP not whitelisted"; 301/302 are bad auth keys.
+        match code.trim_matches('"') {
+            "301" | "302" | "418" => SmsError::Auth(self.message),
+            _ => SmsError::Provider(self.message),
+        }
+    }
+}
+
+/// MSG91 wants numbers with country code but without the `+`.
+fn msisdn(number: &str) -> &str {
+    number.trim_start_matches('+')
+}
+
+impl SmsClient for Msg91Client {
+    async fn send(&self, req: SendRequest<'_>) -> Result<SendResponse, SmsError> {
+        sms_core::validate_text(req.text, self.max_text_len())?;
+        let payload = SendSmsRequest {
+            sender: if req.from.is_empty() {
+                &self.sender
+            } else {
+                req.from
+            },
+            route: &self.route,
+            country: "0",
+            dlt_te_id: self.dlt_template_id.as_deref(),
+            unicode: (!req.text.is_ascii()).then_some(1),
+            sms: [SendSmsMessage {
+                message: req.text,
+                to: [msisdn(req.to)],
+            }],
+        };
+        let (status, raw, request_id) = self.post("/api/v2/sendsms", &payload, req.timeout).await?;
+        Ok(SendResponse {
+            id: request_id.unwrap_or_else(|| self.id_generator.generate()),
+            provider: PROVIDER,
+            channel: Channel::Sms,
+            http_status: Some(status),
+            provider_request_id: None,
+            thread_id: Some(req.thread_id()),
+            raw,
+        })
+    }
+}
+
+/// One batch of a MSG91 delivery-report callback: every destination of
+/// one send request.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Msg91ReportBatch {
+    /// Request ID returned by the send.
+    pub request_id: String,
+    /// Sender ID of the original message.
+    #[serde(default)]
+    pub sender_id: Option<String>,
+    /// MSG91 account ID.
+    #[serde(default)]
+    pub user_id: Option<String>,
+    /// One entry per destination.
+    #[serde(default)]
+    pub report: Vec<Msg91ReportEntry>,
+}
+
+/// Delivery outcome for one destination.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Msg91ReportEntry {
+    /// Destination number, with country code.
+    pub number: String,
+    /// Numeric status; see [`Msg91DeliveryReport::status_name`].
+    pub status: String,
+    /// Status text, e.g. `"DELIVERED"`.
+    #[serde(default)]
+    pub desc: Option<String>,
+    /// Report time, e.g. `"2024-01-15 10:30:00"` (IST).
+    #[serde(default)]
+    pub date: Option<String>,
+}
+
+/// One destination's delivery report, flattened from its batch.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Msg91DeliveryReport {
+    /// Request ID returned by the send.
+    pub request_id: String,
+    /// Sender ID of the original message.
+    pub sender_id: Option<String>,
+    /// The per-destination outcome.
+    #[serde(flatten)]
+    pub entry: Msg91ReportEntry,
+}
+
+impl Msg91DeliveryReport {
+    /// MSG91's name for the numeric status.
+    pub fn status_name(&self) -> &'static str {
+        match self.entry.status.as_str() {
+            "1" => "Delivered",
+            "2" => "Failed",
+            "8" => "Submitted",
+            "9" => "NDNC",
+            "16" => "Rejected",
+            "17" => "Blocked",
+            "25" => "Rejected",
+            "26" => "DND",
+            _ => "Unknown",
+        }
+    }
+
+    /// Returns `true` for final failures: failed, rejected, or blocked by
+    /// the do-not-disturb registry.
+    pub fn is_failure(&self) -> bool {
+        matches!(
+            self.entry.status.as_str(),
+            "2" | "9" | "16" | "17" | "25" | "26"
+        )
+    }
+}
+
+impl Msg91ReportBatch {
+    /// Split the batch into one report per destination.
+    pub fn into_reports(self) -> impl Iterator<Item = Msg91DeliveryReport> {
+        let request_id = self.request_id;
+        let sender_id = self.sender_id;
+        self.report
+            .into_iter()
+            .map(move |entry| Msg91DeliveryReport {
+                request_id: request_id.clone(),
+                sender_id: sender_id.clone(),
+                entry,
+            })
+    }
+}
+
+impl From<Msg91DeliveryReport> for InboundMessage {
+    fn from(r: Msg91DeliveryReport) -> Self {
+        let timestamp = r.entry.date.as_deref().and_then(sms_core::timestamp::parse);
+        let status = r
+            .entry
+            .desc
+            .clone()
+            .unwrap_or_else(|| r.status_name().to_string());
+        let raw = serde_json::to_value(&r).unwrap_or_default();
+        InboundMessage {
+            id: Some(r.request_id),
+            from: "MSG91".to_string(),
+            to: r.entry.number,
+            text: format!("Delivery Status: {}", status),
+            timestamp,
+            provider: PROVIDER,
+            direction: InboundDirection::DeliveryReport,
+            channel: Channel::Sms,
+            provider_account: None,
+            thread_id: None,
+            flag: None,
+            raw,
+        }
+        .with_thread_id()
+    }
+}
+
+/// Decode a callback body: a JSON array of batches, a single batch, or
+/// either of those form-encoded in a `data` field.
+fn parse_batches(body: &[u8]) -> Result<Vec<Msg91ReportBatch>, SmsError> {
+    let decoded: Result<serde_json::Value, _> = if body.trim_ascii_start().starts_with(b"data=") {
+        let fields: Vec<(String, String)> = serde_urlencoded::from_bytes(body)
+            .map_err(|e| SmsError::Invalid(format!("form decode: {}", e)))?;
+        let data = fields
+            .into_iter()
+            .find(|(k, _)| k == "data")
+            .map(|(_, v)| v)
+            .unwrap_or_default();
+        serde_json::from_str(&data)
+    } else {
+        serde_json::from_slice(body)
+    };
+    let json = decoded.map_err(|e| SmsError::Invalid(format!("json decode: {}", e)))?;
+    match json {
+        serde_json::Value::Array(_) => serde_json::from_value(json),
+        _ => serde_json::from_value(json).map(|b| vec![b]),
+    }
+    .map_err(|e| SmsError::Invalid(format!("delivery report: {}", e)))
+}
+
+impl InboundWebhook for Msg91Client {
+    fn provider(&self) -> &'static str {
+        PROVIDER
+    }
+
+    /// Accepts a callback carrying exactly one report.
+    fn parse_inbound(&self, headers: &Headers, body: &[u8]) -> Result<InboundMessage, SmsError> {
+        let mut messages = self.parse_inbound_batch(headers, body)?;
+        match messages.len() {
+            1 => Ok(messages.remove(0)),
+            n => Err(SmsError::Invalid(format!(
+                "expected one delivery report, got {}; use parse_inbound_batch",
+                n
+            ))),
+        }
+    }
+
+    /// Accepts any callback, returning one message per destination.
+    fn parse_inbound_batch(
+        &self,
+        _headers: &Headers,
+        body: &[u8],
+    ) -> Result<Vec<InboundMessage>, SmsError> {
+        Ok(parse_batches(body)?
+            .into_iter()
+            .flat_map(Msg91ReportBatch::into_reports)
+            .map(InboundMessage::from)
+            .collect())
+    }
+}
+
+/// The `msg91` configuration section read by the provider plugin.
+#[derive(Deserialize)]
+struct Msg91Settings {
+    auth_key: String,
+    sender: String,
+    route: Option<String>,
+    dlt_template_id: Option<String>,
+    base_url: Option<String>,
+    #[serde(default)]
+    http: Option<HttpClientConfig>,
+}
+
+/// Build a [`Msg91Client`] from the `msg91` section.
+fn build_plugin(settings: &serde_json::Value) -> Result<sms_core::BuiltProvider, SmsError> {
+    let s: Msg91Settings = sms_core::provider_settings(PROVIDER, settings)?;
+    let mut client = match s.base_url {
+        Some(base_url) => Msg91Client::with_base_url(s.auth_key, s.sender, base_url),
+        None => Msg91Client::new(s.auth_key, s.sender),
+    };
+    if let Some(route) = s.route {
+        client = client.with_route(route);
+    }
+    client.dlt_template_id = s.dlt_template_id;
+    let client = match s.http {
+        Some(http) => client.with_http_config(http)?,
+        None => client,
+    };
+    Ok(sms_core::BuiltProvider::both(client))
+}
+
+sms_core::register_provider!("msg91", build_plugin);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn send_request_carries_dlt_template() {
+        let payload = SendSmsRequest {
+            sender: "ACMECO",
+            route: ROUTE_TRANSACTIONAL,
+            country: "0",
+            dlt_te_id: Some("1207161234567890123"),
+            unicode: None,
+            sms: [SendSmsMessage {
+                message: "Your OTP is 123456",
+                to: [msisdn("+919812345678")],
+            }],
+        };
+        assert_eq!(
+            serde_json::to_value(&payload).unwrap(),
+            json!({
+                "sender": "ACMECO",
+                "route": "4",
+                "country": "0",
+                "DLT_TE_ID": "1207161234567890123",
+                "sms": [{ "message": "Your OTP is 123456", "to": ["919812345678"] }]
+            })
+        );
+    }
+
+    #[test]
+    fn flow_variables_sit_beside_mobiles() {
+        let flow = FlowSend::new("tmpl1", "+919812345678").with_var("otp", "123456");
+        assert_eq!(
+            serde_json::to_value(flow.request()).unwrap(),
+            json!({
+                "template_id": "tmpl1",
+                "short_url": "0",
+                "recipients": [{ "mobiles": "919812345678", "otp": "123456" }]
+            })
+        );
+    }
+
+    #[test]
+    fn error_replies_map_to_errors() {
+        let reply: Msg91Reply = serde_json::from_value(
+            json!({ "type": "error", "message": "Authentication failure", "code": "301" }),
+        )
+        .unwrap();
+        assert!(matches!(reply.error(), SmsError::Auth(_)));
+        let reply: Msg91Reply =
+            serde_json::from_value(json!({ "type": "error", "message": "Invalid template" }))
+                .unwrap();
+        assert!(matches!(reply.error(), SmsError::Provider(_)));
+    }
+
+    const BATCH: &str = r#"[{"senderId":"ACMECO","requestId":"3163646a6b6e383932353131","userId":"12345","report":[{"date":"2024-01-15 10:30:00","number":"919812345678","status":"1","desc":"DELIVERED"},{"date":"2024-01-15 10:30:02","number":"919812345679","status":"26","desc":"DND"}]}]"#;
+
+    #[test]
+    fn parses_json_delivery_reports() {
+        let client = Msg91Client::new("key", "ACMECO");
+        let messages = client
+            .parse_inbound_batch(&Headers::new(), BATCH.as_bytes())
+            .unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].direction, InboundDirection::DeliveryReport);
+        assert_eq!(messages[0].id.as_deref(), Some("3163646a6b6e383932353131"));
+        assert_eq!(messages[0].to, "919812345678");
+        assert_eq!(messages[0].text, "Delivery Status: DELIVERED");
+        assert!(messages[0].timestamp.is_some());
+
+        let report: Msg91DeliveryReport = serde_json::from_value(messages[1].raw.clone()).unwrap();
+        assert!(report.is_failure());
+        assert_eq!(report.status_name(), "DND");
+
+        assert!(
+            client
+                .parse_inbound(&Headers::new(), BATCH.as_bytes())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn parses_form_encoded_delivery_reports() {
+        let client = Msg91Client::new("key", "ACMECO");
+        let body = format!(
+            "data={}",
+            form_urlencoded_value(
+                r#"{"requestId":"r1","report":[{"number":"919812345678","status":"2"}]}"#
+            )
+        );
+        let msg = client
+            .parse_inbound(&Headers::new(), body.as_bytes())
+            .unwrap();
+        assert_eq!(msg.id.as_deref(), Some("r1"));
+        assert_eq!(msg.text, "Delivery Status: Failed");
+    }
+
+    fn form_urlencoded_value(value: &str) -> String {
+        let encoded = serde_urlencoded::to_string([("v", value)]).unwrap();
+        encoded["v=".len()..].to_string()
+    }
+
+    #[test]
+    fn rejects_malformed_webhooks() {
+        let client = Msg91Client::new("key", "ACMECO");
+        assert!(
+            client
+                .parse_inbound_batch(&Headers::new(), b"not json")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn plugin_builds_from_settings() {
+        let plugin = sms_core::ProviderPlugin::find("msg91").unwrap();
+        let built = plugin
+            .build(&json!({
+                "auth_key": "key",
+                "sender": "ACMECO",
+                "dlt_template_id": "1207161234567890123",
+            }))
+            .unwrap();
+        assert_eq!(built.webhook.unwrap().provider(), "msg91");
+        assert!(built.client.is_some());
+        assert!(plugin.build(&json!({ "auth_key": "key" })).is_err());
+    }
+
+    #[test]
+    fn debug_redacts_auth_key() {
+        let debug = format!("{:?}", Msg91Client::new("hunter2", "ACMECO"));
+        assert!(debug.contains("ACMECO"));
+        assert!(!debug.contains("hunter2"));
+    }
+}
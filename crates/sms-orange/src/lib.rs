@@ -0,0 +1,657 @@
+//! # Orange SMS API Provider
+//!
+//! [Orange SMS API](https://developer.orange.com/apis/sms) backend for the
+//! smskit multi-provider SMS abstraction, covering Côte d'Ivoire, Senegal,
+//! Cameroon, Mali and the rest of Orange's Francophone African footprint.
+//!
+//! ## Sending messages
+//!
+//! ```rust,ignore
+//! use sms_core::{SendRequest, SmsClient};
+//! use sms_orange::OrangeClient;
+//!
+//! let client = OrangeClient::new("CLIENT_ID", "CLIENT_SECRET", "+2250000");
+//! let response = client.send(SendRequest {
+//!     to: "+2250701020304",
+//!     text: "Bonjour depuis Orange !",
+//!     ..Default::default()
+//! }).await?;
+//! println!("Message ID: {}", response.id);
+//! ```
+//!
+//! The sender address is the `tel:+{country code}0000` number of the
+//! contract's country; a registered sender name can be set with
+//! [`OrangeClient::with_sender_name`].
+//!
+//! ## Authentication
+//!
+//! Orange uses the OAuth2 client-credentials flow.  The client fetches an
+//! access token on the first send, reuses it until shortly before it
+//! expires, and fetches a new one if the API rejects it early.
+//!
+//! ## Delivery reports
+//!
+//! Orange POSTs a JSON `deliveryInfoNotification` to the URL registered
+//! through its delivery-receipt subscription API.
+
+use serde::{Deserialize, Serialize};
+use sms_core::{
+    Channel, ExposeSecret, Headers, HttpClientConfig, IdGenerator, InboundDirection,
+    InboundMessage, InboundWebhook, SecretString, SendRequest, SendResponse, SmsClient, SmsError,
+    UuidV4,
+};
+use sms_core::http_provider::transport_error;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const PROVIDER: &str = "orange";
+
+/// Production API base URL.
+const LIVE_URL: &str = "https://api.orange.com";
+
+/// Default deadline for a single API call.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Fetch a new token this long before the current one expires.
+const TOKEN_MARGIN: Duration = Duration::from_secs(60);
+
+/// An access token and when it stops being usable.
+struct CachedToken {
+    token: SecretString,
+    expires_at: Instant,
+}
+
+/// Orange SMS API client.
+///
+/// Implements [`SmsClient`] for sending SMS and [`InboundWebhook`] for
+/// receiving delivery reports.  Clones share one access token.
+#[derive(Clone)]
+pub struct OrangeClient {
+    /// OAuth2 client ID.
+    pub client_id: String,
+    /// OAuth2 client secret.  Zeroized on drop.
+    pub client_secret: SecretString,
+    /// Sender address, e.g. `"+2250000"`; the `tel:` prefix is optional.
+    pub sender_address: String,
+    /// Registered sender name shown instead of the sender address.
+    pub sender_name: Option<String>,
+    /// API base URL; override with
+    /// [`with_base_url`](OrangeClient::with_base_url) for testing.
+    pub base_url: String,
+    /// Deadline for each API call unless the request sets its own
+    /// [`SendRequest::timeout`].
+    pub timeout: Duration,
+    token: Arc<tokio::sync::Mutex<Option<CachedToken>>>,
+    id_generator: Arc<dyn IdGenerator>,
+    http: reqwest::Client,
+}
+
+impl std::fmt::Debug for OrangeClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OrangeClient")
+            .field("client_id", &self.client_id)
+            .field("client_secret", &"[REDACTED]")
+            .field("sender_address", &self.sender_address)
+            .field("sender_name", &self.sender_name)
+            .field("base_url", &self.base_url)
+            .field("timeout", &self.timeout)
+            .finish_non_exhaustive()
+    }
+}
+
+impl OrangeClient {
+    /// Create a client against the production API.
+    pub fn new(
+        client_id: impl Into<String>,
+        client_secret: impl Into<String>,
+        sender_address: impl Into<String>,
+    ) -> Self {
+        Self::with_base_url(client_id, client_secret, sender_address, LIVE_URL)
+    }
+
+    /// Create a client by reading credentials from environment variables.
+    ///
+    /// | Variable                | Maps to          |
+    /// |-------------------------|------------------|
+    /// | `ORANGE_CLIENT_ID`      | `client_id`      |
+    /// | `ORANGE_CLIENT_SECRET`  | `client_secret`  |
+    /// | `ORANGE_SENDER_ADDRESS` | `sender_address` |
+    /// | `ORANGE_SENDER_NAME`    | `sender_name`    |
+    ///
+    /// Returns [`SmsError::Auth`] if any but `ORANGE_SENDER_NAME` is
+    /// missing.
+    pub fn from_env() -> Result<Self, SmsError> {
+        let client_id = std::env::var("ORANGE_CLIENT_ID")
+            .map_err(|_| SmsError::Auth("ORANGE_CLIENT_ID not set".into()))?;
+        let client_secret = std::env::var("ORANGE_CLIENT_SECRET")
+            .map_err(|_| SmsError::Auth("ORANGE_CLIENT_SECRET not set".into()))?;
+        let sender_address = std::env::var("ORANGE_SENDER_ADDRESS")
+            .map_err(|_| SmsError::Auth("ORANGE_SENDER_ADDRESS not set".into()))?;
+        let client = Self::new(client_id, client_secret, sender_address);
+        Ok(match std::env::var("ORANGE_SENDER_NAME") {
+            Ok(name) => client.with_sender_name(name),
+            Err(_) => client,
+        })
+    }
+
+    /// Create a client with a custom API base URL, e.g. a mock server.
+    pub fn with_base_url(
+        client_id: impl Into<String>,
+        client_secret: impl Into<String>,
+        sender_address: impl Into<String>,
+        base_url: impl Into<String>,
+    ) -> Self {
+        Self {
+            client_id: client_id.into(),
+            client_secret: SecretString::from(client_secret.into()),
+            sender_address: sender_address.into(),
+            sender_name: None,
+            base_url: base_url.into(),
+            timeout: DEFAULT_TIMEOUT,
+            token: Arc::default(),
+            id_generator: Arc::new(UuidV4),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Show `name` as the sender instead of the sender address.  The name
+    /// must be registered with Orange first.
+    pub fn with_sender_name(mut self, name: impl Into<String>) -> Self {
+        self.sender_name = Some(name.into());
+        self
+    }
+
+    /// Set the default deadline for each API call (default: 30 seconds).
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Rebuild the HTTP client with custom transport settings (proxy,
+    /// `User-Agent`, extra headers, etc.).
+    pub fn with_http_config(mut self, config: HttpClientConfig) -> Result<Self, SmsError> {
+        self.http = sms_core::http_provider::client_with(&config)?;
+        Ok(self)
+    }
+
+    /// Generate IDs with `ids` when the response doesn't include one
+    /// (default: [`UuidV4`]).
+    pub fn with_id_generator(mut self, ids: impl IdGenerator + 'static) -> Self {
+        self.id_generator = Arc::new(ids);
+        self
+    }
+
+    /// Return the cached access token, fetching a new one if there is none
+    /// or it is about to expire.
+    async fn access_token(&self, timeout: Duration) -> Result<String, SmsError> {
+        let mut cached = self.token.lock().await;
+        let fresh = cached
+            .as_ref()
+            .filter(|token| Instant::now() + TOKEN_MARGIN < token.expires_at);
+        if let Some(token) = fresh {
+            return Ok(token.token.expose_secret().to_string());
+        }
+
+        let url = format!("{}/oauth/v3/token", self.base_url.trim_end_matches('/'));
+        let res = self
+            .http
+            .post(&url)
+            .basic_auth(&self.client_id, Some(self.client_secret.expose_secret()))
+            .header(reqwest::header::ACCEPT, "application/json")
+            .timeout(timeout)
+            .form(&[("grant_type", "client_credentials")])
+            .send()
+            .await
+            .map_err(transport_error)?;
+        let status = res.status();
+        let raw_text = res.text().await.map_err(transport_error)?;
+        if status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN {
+            return Err(SmsError::Auth(raw_text));
+        }
+        if !status.is_success() {
+            return Err(SmsError::Auth(format!(
+                "token: HTTP {}: {}",
+                status, raw_text
+            )));
+        }
+        let token: TokenResponse = serde_json::from_str(&raw_text)
+            .map_err(|e| SmsError::Auth(format!("token response: {}", e)))?;
+
+        let access_token = token.access_token.clone();
+        *cached = Some(CachedToken {
+            token: SecretString::from(token.access_token),
+            expires_at: Instant::now() + Duration::from_secs(token.expires_in),
+        });
+        Ok(access_token)
+    }
+
+    /// Drop the cached token so the next call fetches a fresh one.
+    async fn invalidate_token(&self) {
+        *self.token.lock().await = None;
+    }
+
+    /// The `tel:`-prefixed sender address.
+    fn sender(&self) -> String {
+        tel(&self.sender_address)
+    }
+}
+
+/// `+2250000` → `tel:+2250000`; already-prefixed addresses pass through.
+fn tel(number: &str) -> String {
+    if number.starts_with("tel:") {
+        number.to_string()
+    } else {
+        format!("tel:{}", number)
+    }
+}
+
+/// Percent-encode a `tel:` address for use as a path segment.
+fn path_segment(address: &str) -> String {
+    address.replace(':', "%3A").replace('+', "%2B")
+}
+
+/// The OAuth2 token endpoint's reply.
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    /// Lifetime in seconds.
+    #[serde(default = "default_expires_in")]
+    expires_in: u64,
+}
+
+fn default_expires_in() -> u64 {
+    3600
+}
+
+/// Wire format for the outbound-SMS request body.
+#[derive(Debug, Serialize)]
+struct OutboundRequest<'a> {
+    #[serde(rename = "outboundSMSMessageRequest")]
+    outbound_sms_message_request: OutboundMessage<'a>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct OutboundMessage<'a> {
+    address: String,
+    sender_address: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sender_name: Option<&'a str>,
+    #[serde(rename = "outboundSMSTextMessage")]
+    text: OutboundText<'a>,
+}
+
+#[derive(Debug, Serialize)]
+struct OutboundText<'a> {
+    message: &'a str,
+}
+
+/// Typed view of the JSON returned from a send, available from
+/// [`SendResponse::raw`] via [`OrangeSendDetails::from_response`].
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct OrangeSendDetails {
+    /// The accepted request, echoed back.
+    #[serde(rename = "outboundSMSMessageRequest")]
+    pub request: OrangeSendRequest,
+}
+
+/// The `outboundSMSMessageRequest` object of a send response.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrangeSendRequest {
+    /// Destination, as `tel:+...`.
+    #[serde(default)]
+    pub address: String,
+    /// Sender address, as `tel:+...`.
+    #[serde(default)]
+    pub sender_address: String,
+    /// Sender name, if one was used.
+    #[serde(default)]
+    pub sender_name: Option<String>,
+    /// URL of the created request; its last segment is the message ID.
+    #[serde(rename = "resourceURL", default)]
+    pub resource_url: Option<String>,
+}
+
+impl OrangeSendRequest {
+    /// The message ID: the last path segment of [`resource_url`](Self::resource_url).
+    pub fn message_id(&self) -> Option<&str> {
+        self.resource_url
+            .as_deref()
+            .and_then(|url| url.trim_end_matches('/').rsplit('/').next())
+            .filter(|id| !id.is_empty())
+    }
+}
+
+impl OrangeSendDetails {
+    /// Parse the raw payload of an Orange [`SendResponse`].
+    pub fn from_response(resp: &SendResponse) -> Result<Self, SmsError> {
+        if resp.provider != PROVIDER {
+            return Err(SmsError::Invalid(format!(
+                "expected a {} response, got {}",
+                PROVIDER, resp.provider
+            )));
+        }
+        serde_json::from_value(resp.raw.clone())
+            .map_err(|e| SmsError::Invalid(format!("orange send response: {}", e)))
+    }
+}
+
+impl SmsClient for OrangeClient {
+    async fn send(&self, req: SendRequest<'_>) -> Result<SendResponse, SmsError> {
+        sms_core::validate_text(req.text, self.max_text_len())?;
+        let timeout = req.timeout.unwrap_or(self.timeout);
+        let sender = self.sender();
+        let url = format!(
+            "{}/smsmessaging/v1/outbound/{}/requests",
+            self.base_url.trim_end_matches('/'),
+            path_segment(&sender)
+        );
+        let payload = OutboundRequest {
+            outbound_sms_message_request: OutboundMessage {
+                address: tel(req.to),
+                sender_address: &sender,
+                sender_name: self.sender_name.as_deref(),
+                text: OutboundText { message: req.text },
+            },
+        };
+
+        // A token can be revoked before it expires; fetch a fresh one once.
+        let mut retried = false;
+        let res = loop {
+            let token = self.access_token(timeout).await?;
+            let res = self
+                .http
+                .post(&url)
+                .bearer_auth(&token)
+                .header(reqwest::header::ACCEPT, "application/json")
+                .timeout(timeout)
+                .json(&payload)
+                .send()
+                .await
+                .map_err(transport_error)?;
+            if res.status() == reqwest::StatusCode::UNAUTHORIZED && !retried {
+                self.invalidate_token().await;
+                retried = true;
+                continue;
+            }
+            break res;
+        };
+
+        let status = res.status();
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = res
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(sms_core::parse_retry_after);
+            return Err(SmsError::RateLimited { retry_after });
+        }
+        let raw_text = res.text().await.map_err(transport_error)?;
+        if status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN {
+            return Err(SmsError::Auth(raw_text));
+        }
+        if status == reqwest::StatusCode::BAD_REQUEST {
+            return Err(SmsError::Invalid(raw_text));
+        }
+        if !status.is_success() {
+            return Err(SmsError::Provider(format!("HTTP {}: {}", status, raw_text)));
+        }
+
+        let raw: serde_json::Value = serde_json::from_str(&raw_text)
+            .map_err(|e| SmsError::Provider(format!("send response: {}: {}", e, raw_text)))?;
+        let details: OrangeSendDetails = serde_json::from_value(raw.clone())
+            .map_err(|e| SmsError::Provider(format!("send response: {}", e)))?;
+
+        Ok(SendResponse {
+            id: details
+                .request
+                .message_id()
+                .map(str::to_string)
+                .unwrap_or_else(|| self.id_generator.generate()),
+            provider: PROVIDER,
+            channel: Channel::Sms,
+            http_status: Some(status.as_u16()),
+            provider_request_id: None,
+            thread_id: Some(req.thread_id()),
+            raw,
+        })
+    }
+}
+
+/// The JSON payload Orange POSTs to a delivery-receipt subscription.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct OrangeDeliveryReport {
+    /// The notification itself.
+    #[serde(rename = "deliveryInfoNotification")]
+    pub notification: OrangeDeliveryNotification,
+}
+
+/// The `deliveryInfoNotification` object of a delivery report.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrangeDeliveryNotification {
+    /// Value given when the subscription was created.
+    #[serde(default)]
+    pub callback_data: Option<String>,
+    /// Outcome for one destination.
+    pub delivery_info: OrangeDeliveryInfo,
+}
+
+/// Delivery outcome for one destination.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrangeDeliveryInfo {
+    /// Destination, as `tel:+...`.
+    pub address: String,
+    /// `DeliveredToTerminal`, `DeliveredToNetwork`, `DeliveryUncertain`,
+    /// `DeliveryImpossible`, `MessageWaiting` or `DeliveryNotificationNotSupported`.
+    pub delivery_status: String,
+}
+
+impl OrangeDeliveryReport {
+    /// Returns `true` if the message could not be delivered.
+    pub fn is_failure(&self) -> bool {
+        self.notification.delivery_info.delivery_status == "DeliveryImpossible"
+    }
+}
+
+impl From<OrangeDeliveryReport> for InboundMessage {
+    fn from(r: OrangeDeliveryReport) -> Self {
+        let raw = serde_json::to_value(&r).unwrap_or_default();
+        let n = r.notification;
+        InboundMessage {
+            id: n.callback_data,
+            from: "Orange".to_string(),
+            to: n
+                .delivery_info
+                .address
+                .trim_start_matches("tel:")
+                .to_string(),
+            text: format!("Delivery Status: {}", n.delivery_info.delivery_status),
+            timestamp: None,
+            provider: PROVIDER,
+            direction: InboundDirection::DeliveryReport,
+            channel: Channel::Sms,
+            provider_account: None,
+            thread_id: None,
+            flag: None,
+            raw,
+        }
+        .with_thread_id()
+    }
+}
+
+impl InboundWebhook for OrangeClient {
+    fn provider(&self) -> &'static str {
+        PROVIDER
+    }
+
+    fn parse_inbound(&self, _headers: &Headers, body: &[u8]) -> Result<InboundMessage, SmsError> {
+        let report: OrangeDeliveryReport = serde_json::from_slice(body)
+            .map_err(|e| SmsError::Invalid(format!("delivery report: {}", e)))?;
+        Ok(report.into())
+    }
+}
+
+/// The `orange` configuration section read by the provider plugin.
+#[derive(Deserialize)]
+struct OrangeSettings {
+    client_id: String,
+    client_secret: String,
+    sender_address: String,
+    sender_name: Option<String>,
+    base_url: Option<String>,
+    #[serde(default)]
+    http: Option<HttpClientConfig>,
+}
+
+/// Build an [`OrangeClient`] from the `orange` section.
+fn build_plugin(settings: &serde_json::Value) -> Result<sms_core::BuiltProvider, SmsError> {
+    let s: OrangeSettings = sms_core::provider_settings(PROVIDER, settings)?;
+    let mut client = match s.base_url {
+        Some(base_url) => {
+            OrangeClient::with_base_url(s.client_id, s.client_secret, s.sender_address, base_url)
+        }
+        None => OrangeClient::new(s.client_id, s.client_secret, s.sender_address),
+    };
+    client.sender_name = s.sender_name;
+    let client = match s.http {
+        Some(http) => client.with_http_config(http)?,
+        None => client,
+    };
+    Ok(sms_core::BuiltProvider::both(client))
+}
+
+sms_core::register_provider!("orange", build_plugin);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn send_request_shape() {
+        let client = OrangeClient::new("id", "secret", "+2250000").with_sender_name("Acme");
+        let sender = client.sender();
+        let payload = OutboundRequest {
+            outbound_sms_message_request: OutboundMessage {
+                address: tel("+2250701020304"),
+                sender_address: &sender,
+                sender_name: client.sender_name.as_deref(),
+                text: OutboundText { message: "Bonjour" },
+            },
+        };
+        assert_eq!(
+            serde_json::to_value(&payload).unwrap(),
+            json!({
+                "outboundSMSMessageRequest": {
+                    "address": "tel:+2250701020304",
+                    "senderAddress": "tel:+2250000",
+                    "senderName": "Acme",
+                    "outboundSMSTextMessage": { "message": "Bonjour" }
+                }
+            })
+        );
+        assert_eq!(path_segment(&sender), "tel%3A%2B2250000");
+        assert_eq!(tel("tel:+2250000"), "tel:+2250000");
+    }
+
+    #[test]
+    fn message_id_from_resource_url() {
+        let resp = SendResponse {
+            id: "abc123".into(),
+            provider: PROVIDER,
+            channel: Channel::Sms,
+            http_status: Some(201),
+            provider_request_id: None,
+            thread_id: None,
+            raw: json!({
+                "outboundSMSMessageRequest": {
+                    "address": "tel:+2250701020304",
+                    "senderAddress": "tel:+2250000",
+                    "outboundSMSTextMessage": { "message": "Bonjour" },
+                    "resourceURL": "https://api.orange.com/smsmessaging/v1/outbound/tel:+2250000/requests/abc123"
+                }
+            }),
+        };
+        let details = OrangeSendDetails::from_response(&resp).unwrap();
+        assert_eq!(details.request.message_id(), Some("abc123"));
+        assert_eq!(OrangeSendRequest::default().message_id(), None);
+    }
+
+    #[tokio::test]
+    async fn cached_tokens_are_reused_until_near_expiry() {
+        // The base URL is unreachable, so any token fetch fails.
+        let client = OrangeClient::with_base_url("id", "secret", "+2250000", "http://127.0.0.1:9");
+        *client.token.lock().await = Some(CachedToken {
+            token: SecretString::from("cached".to_string()),
+            expires_at: Instant::now() + Duration::from_secs(600),
+        });
+        let token = client.access_token(Duration::from_secs(1)).await.unwrap();
+        assert_eq!(token, "cached");
+
+        *client.token.lock().await = Some(CachedToken {
+            token: SecretString::from("stale".to_string()),
+            expires_at: Instant::now() + Duration::from_secs(30),
+        });
+        assert!(client.access_token(Duration::from_secs(1)).await.is_err());
+
+        client.invalidate_token().await;
+        assert!(client.token.lock().await.is_none());
+    }
+
+    #[test]
+    fn parses_delivery_report() {
+        let client = OrangeClient::new("id", "secret", "+2250000");
+        let body = br#"{
+            "deliveryInfoNotification": {
+                "callbackData": "order-42",
+                "deliveryInfo": {
+                    "address": "tel:+2250701020304",
+                    "deliveryStatus": "DeliveryImpossible"
+                }
+            }
+        }"#;
+        let msg = client.parse_inbound(&Headers::new(), body).unwrap();
+        assert_eq!(msg.direction, InboundDirection::DeliveryReport);
+        assert_eq!(msg.id.as_deref(), Some("order-42"));
+        assert_eq!(msg.to, "+2250701020304");
+        assert_eq!(msg.text, "Delivery Status: DeliveryImpossible");
+
+        let report: OrangeDeliveryReport = serde_json::from_value(msg.raw).unwrap();
+        assert!(report.is_failure());
+    }
+
+    #[test]
+    fn rejects_malformed_webhooks() {
+        let client = OrangeClient::new("id", "secret", "+2250000");
+        assert!(
+            client
+                .parse_inbound(&Headers::new(), br#"{"deliveryInfo":{}}"#)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn plugin_builds_from_settings() {
+        let plugin = sms_core::ProviderPlugin::find("orange").unwrap();
+        let built = plugin
+            .build(&json!({
+                "client_id": "id",
+                "client_secret": "secret",
+                "sender_address": "+2250000",
+            }))
+            .unwrap();
+        assert_eq!(built.webhook.unwrap().provider(), "orange");
+        assert!(built.client.is_some());
+        assert!(plugin.build(&json!({ "client_id": "id" })).is_err());
+    }
+
+    #[test]
+    fn debug_redacts_client_secret() {
+        let debug = format!("{:?}", OrangeClient::new("id", "hunter2", "+2250000"));
+        assert!(debug.contains("+2250000"));
+        assert!(!debug.contains("hunter2"));
+    }
+}
@@ -0,0 +1,157 @@
+//! Plivo error codes.
+//!
+//! Plivo reports why a message failed as a numeric `ErrorCode` in its
+//! delivery-status callbacks.  [`PlivoErrorCode`] names the documented codes
+//! and classifies them as a [`ProviderErrorKind`].  Rejected API calls carry
+//! only an error string, so send failures remain
+//! [`SmsError::Provider`](sms_core::SmsError::Provider).
+
+use sms_core::{ProviderError, ProviderErrorKind};
+
+/// A Plivo message error code.  Codes smskit doesn't name are kept in `Other`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum PlivoErrorCode {
+    /// 10: the message content is invalid.
+    InvalidMessage,
+    /// 20: a network error occurred.
+    NetworkError,
+    /// 30: the message was detected as spam.
+    SpamDetected,
+    /// 40: the source number is invalid.
+    InvalidSourceNumber,
+    /// 50: the destination number is invalid.
+    InvalidDestinationNumber,
+    /// 60: a message loop was detected.
+    LoopDetected,
+    /// 70: the destination is permanently unavailable.
+    DestinationPermanentlyUnavailable,
+    /// 80: the destination is temporarily unavailable.
+    DestinationTemporarilyUnavailable,
+    /// 90: no route to the destination.
+    NoRouteAvailable,
+    /// 100: the carrier prohibited the message.
+    ProhibitedByCarrier,
+    /// 110: the message is too long.
+    MessageTooLong,
+    /// 200: the recipient replied STOP to this source number.
+    BlockedByStop,
+    /// 300: Plivo failed to dispatch the message.
+    FailedToDispatch,
+    /// 420: the message expired before it could be delivered.
+    MessageExpired,
+    /// 900: the account has insufficient credit.
+    InsufficientCredit,
+    /// 910: the account is disabled.
+    AccountDisabled,
+    /// 1000: unknown error.
+    UnknownError,
+    /// Any other code.
+    Other(u32),
+}
+
+impl PlivoErrorCode {
+    /// The variant for a numeric code.
+    pub fn from_code(code: u32) -> Self {
+        match code {
+            10 => Self::InvalidMessage,
+            20 => Self::NetworkError,
+            30 => Self::SpamDetected,
+            40 => Self::InvalidSourceNumber,
+            50 => Self::InvalidDestinationNumber,
+            60 => Self::LoopDetected,
+            70 => Self::DestinationPermanentlyUnavailable,
+            80 => Self::DestinationTemporarilyUnavailable,
+            90 => Self::NoRouteAvailable,
+            100 => Self::ProhibitedByCarrier,
+            110 => Self::MessageTooLong,
+            200 => Self::BlockedByStop,
+            300 => Self::FailedToDispatch,
+            420 => Self::MessageExpired,
+            900 => Self::InsufficientCredit,
+            910 => Self::AccountDisabled,
+            1000 => Self::UnknownError,
+            other => Self::Other(other),
+        }
+    }
+
+    /// The numeric code.
+    pub fn code(self) -> u32 {
+        match self {
+            Self::InvalidMessage => 10,
+            Self::NetworkError => 20,
+            Self::SpamDetected => 30,
+            Self::InvalidSourceNumber => 40,
+            Self::InvalidDestinationNumber => 50,
+            Self::LoopDetected => 60,
+            Self::DestinationPermanentlyUnavailable => 70,
+            Self::DestinationTemporarilyUnavailable => 80,
+            Self::NoRouteAvailable => 90,
+            Self::ProhibitedByCarrier => 100,
+            Self::MessageTooLong => 110,
+            Self::BlockedByStop => 200,
+            Self::FailedToDispatch => 300,
+            Self::MessageExpired => 420,
+            Self::InsufficientCredit => 900,
+            Self::AccountDisabled => 910,
+            Self::UnknownError => 1000,
+            Self::Other(code) => code,
+        }
+    }
+
+    /// The provider-independent classification of this code.
+    pub fn kind(self) -> ProviderErrorKind {
+        match self {
+            Self::InvalidMessage | Self::MessageTooLong => ProviderErrorKind::InvalidContent,
+            Self::SpamDetected | Self::LoopDetected | Self::ProhibitedByCarrier => {
+                ProviderErrorKind::Blocked
+            }
+            Self::InvalidSourceNumber => ProviderErrorKind::InvalidSender,
+            Self::InvalidDestinationNumber => ProviderErrorKind::InvalidDestination,
+            Self::DestinationPermanentlyUnavailable
+            | Self::DestinationTemporarilyUnavailable
+            | Self::NoRouteAvailable => ProviderErrorKind::UnreachableDestination,
+            Self::BlockedByStop => ProviderErrorKind::OptedOut,
+            Self::NetworkError
+            | Self::FailedToDispatch
+            | Self::MessageExpired
+            | Self::UnknownError => ProviderErrorKind::ProviderFailure,
+            Self::InsufficientCredit => ProviderErrorKind::InsufficientFunds,
+            Self::AccountDisabled => ProviderErrorKind::AccountSuspended,
+            Self::Other(_) => ProviderErrorKind::Other,
+        }
+    }
+
+    /// A [`ProviderError`] for this code with the given message.
+    pub fn to_provider_error(self, message: impl Into<String>) -> ProviderError {
+        ProviderError::new(
+            crate::PROVIDER,
+            self.code().to_string(),
+            self.kind(),
+            message,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_round_trip_and_classify() {
+        for code in [10, 50, 200, 900, 1000, 12345] {
+            assert_eq!(PlivoErrorCode::from_code(code).code(), code);
+        }
+        assert_eq!(
+            PlivoErrorCode::from_code(200).kind(),
+            ProviderErrorKind::OptedOut
+        );
+        assert_eq!(
+            PlivoErrorCode::from_code(910).kind(),
+            ProviderErrorKind::AccountSuspended
+        );
+        let err = PlivoErrorCode::InvalidDestinationNumber.to_provider_error("bad dst");
+        assert_eq!(err.code, "50");
+        assert_eq!(err.kind, ProviderErrorKind::InvalidDestination);
+    }
+}
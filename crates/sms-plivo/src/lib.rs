@@ -14,6 +14,7 @@
 //!     to: "+14155551234",
 //!     from: "+10005551234",
 //!     text: "Hello from Plivo!",
+//!     ..Default::default()
 //! }).await?;
 //! println!("Message ID: {}", response.id);
 //! ```
@@ -25,13 +26,36 @@
 //! ```
 //!
 //! Reads `PLIVO_AUTH_ID` and `PLIVO_AUTH_TOKEN` from the environment.
+//!
+//! ## Error codes
+//!
+//! [`PlivoInbound::error_code`] classifies the `ErrorCode` of failed
+//! delivery reports; see [`errors::PlivoErrorCode`].
+
+pub mod errors;
+
+pub use errors::PlivoErrorCode;
 
-use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
-use sms_core::{InboundMessage, SendRequest, SendResponse, SmsClient, SmsError};
+#[cfg(feature = "reqwest")]
+use sms_core::ExposeSecret;
+use sms_core::{
+    Channel, HttpClientConfig, HttpLogger, IdGenerator, InboundDirection, InboundMessage,
+    ProxyConfig, SecretString, SendRequest, SendResponse, SmsClient, SmsError, UuidV4,
+};
+#[cfg(feature = "reqwest")]
+use sms_core::http_provider::{client_with, transport_error};
+use std::sync::Arc;
+use std::time::Duration;
 
 const PROVIDER: &str = "plivo";
 
+/// Longest message body Plivo accepts, in characters.
+const MAX_TEXT_LEN: usize = 1600;
+
+/// Default deadline for a single API call.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
 /// Plivo REST API client.
 ///
 /// Implements [`SmsClient`] for sending SMS and [`InboundWebhook`] for
@@ -44,19 +68,40 @@ const PROVIDER: &str = "plivo";
 /// | [`PlivoClient::new`] | Provide credentials directly |
 /// | [`PlivoClient::from_env`] | Read `PLIVO_AUTH_ID` / `PLIVO_AUTH_TOKEN` from the environment |
 /// | [`PlivoClient::with_base_url`] | Override the API base URL (useful for testing) |
-#[derive(Clone, Debug)]
+/// | [`PlivoClient::with_timeout`] | Change the default per-call deadline |
+/// | [`PlivoClient::with_id_generator`] | Choose how fallback message IDs are generated |
+#[derive(Clone)]
 pub struct PlivoClient {
     /// Plivo Auth ID (account SID).
     pub auth_id: String,
-    /// Plivo Auth Token (used for Basic-auth on every request).
-    pub auth_token: String,
+    /// Plivo Auth Token (used for Basic-auth on every request).  Zeroized
+    /// on drop.
+    pub auth_token: SecretString,
     /// API base URL; override with [`with_base_url`](PlivoClient::with_base_url)
     /// for testing against a mock server.
     pub base_url: String,
+    /// Deadline for each API call unless the request sets its own
+    /// [`SendRequest::timeout`].
+    pub timeout: Duration,
+    #[cfg_attr(not(feature = "reqwest"), allow(dead_code))]
+    id_generator: Arc<dyn IdGenerator>,
+    #[cfg_attr(not(feature = "reqwest"), allow(dead_code))]
+    http_log: Option<HttpLogger>,
     #[cfg(feature = "reqwest")]
     http: reqwest::Client,
 }
 
+impl std::fmt::Debug for PlivoClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PlivoClient")
+            .field("auth_id", &self.auth_id)
+            .field("auth_token", &"[REDACTED]")
+            .field("base_url", &self.base_url)
+            .field("timeout", &self.timeout)
+            .finish_non_exhaustive()
+    }
+}
+
 impl PlivoClient {
     /// Create a new client with explicit credentials.
     ///
@@ -76,14 +121,19 @@ impl PlivoClient {
     /// |--------------------|---------------|
     /// | `PLIVO_AUTH_ID`    | `auth_id`     |
     /// | `PLIVO_AUTH_TOKEN` | `auth_token`  |
+    /// | `PLIVO_PROXY`      | proxy URL (optional, falls back to `SMSKIT_PROXY`) |
     ///
-    /// Returns [`SmsError::Auth`] if either variable is missing.
+    /// Returns [`SmsError::Auth`] if either credential is missing.
     pub fn from_env() -> Result<Self, SmsError> {
         let auth_id = std::env::var("PLIVO_AUTH_ID")
             .map_err(|_| SmsError::Auth("PLIVO_AUTH_ID not set".into()))?;
         let auth_token = std::env::var("PLIVO_AUTH_TOKEN")
             .map_err(|_| SmsError::Auth("PLIVO_AUTH_TOKEN not set".into()))?;
-        Ok(Self::new(auth_id, auth_token))
+        let client = Self::new(auth_id, auth_token);
+        match ProxyConfig::from_env("PLIVO") {
+            Some(proxy) => client.with_http_config(HttpClientConfig::new().with_proxy(proxy)),
+            None => Ok(client),
+        }
     }
 
     /// Create a client with a custom API base URL.
@@ -93,12 +143,54 @@ impl PlivoClient {
     pub fn with_base_url<S: Into<String>>(auth_id: S, auth_token: S, base_url: String) -> Self {
         Self {
             auth_id: auth_id.into(),
-            auth_token: auth_token.into(),
+            auth_token: SecretString::from(auth_token.into()),
             base_url,
+            timeout: DEFAULT_TIMEOUT,
+            id_generator: Arc::new(UuidV4),
+            http_log: None,
             #[cfg(feature = "reqwest")]
             http: reqwest::Client::new(),
         }
     }
+
+    /// Set the default deadline for each API call (default: 30 seconds).
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Rebuild the HTTP client with custom transport settings (proxy, extra
+    /// root certificates, etc.).
+    ///
+    /// Returns [`SmsError::Invalid`] if the settings are rejected, e.g. a
+    /// malformed proxy URL or certificate.
+    pub fn with_http_config(self, config: HttpClientConfig) -> Result<Self, SmsError> {
+        #[cfg(not(feature = "reqwest"))]
+        {
+            let _ = config;
+            Ok(self)
+        }
+        #[cfg(feature = "reqwest")]
+        {
+            Ok(Self {
+                http: client_with(&config)?,
+                ..self
+            })
+        }
+    }
+
+    /// Generate IDs with `ids` when Plivo's response doesn't include one
+    /// (default: [`UuidV4`]).
+    pub fn with_id_generator(mut self, ids: impl IdGenerator + 'static) -> Self {
+        self.id_generator = Arc::new(ids);
+        self
+    }
+
+    /// Report every API call, with redacted bodies, to `logger`.
+    pub fn with_http_logger(mut self, logger: HttpLogger) -> Self {
+        self.http_log = Some(logger);
+        self
+    }
 }
 
 /// Wire format for the Plivo send-message request body.
@@ -109,9 +201,9 @@ struct PlivoSendRequest<'a> {
     text: &'a str,
 }
 
-#[async_trait]
 impl SmsClient for PlivoClient {
     async fn send(&self, req: SendRequest<'_>) -> Result<SendResponse, SmsError> {
+        sms_core::validate_text(req.text, self.max_text_len())?;
         #[cfg(not(feature = "reqwest"))]
         {
             let _ = req;
@@ -129,27 +221,56 @@ impl SmsClient for PlivoClient {
                 dst: req.to,
                 text: req.text,
             };
-            let res = self
+            let pending = self.http_log.as_ref().map(|log| {
+                let body = serde_json::to_string(&payload).ok();
+                log.start(PROVIDER, "POST", &url, body.as_deref())
+            });
+            let res = match self
                 .http
-                .post(url)
-                .basic_auth(&self.auth_id, Some(&self.auth_token))
+                .post(&url)
+                .basic_auth(&self.auth_id, Some(self.auth_token.expose_secret()))
+                .timeout(req.timeout.unwrap_or(self.timeout))
                 .json(&payload)
                 .send()
                 .await
-                .map_err(|e| SmsError::Http(e.to_string()))?;
+            {
+                Ok(res) => res,
+                Err(e) => {
+                    let err = transport_error(e);
+                    if let Some(pending) = pending {
+                        pending.fail(&err);
+                    }
+                    return Err(err);
+                }
+            };
+
+            let status = res.status();
+            let retry_after = res
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(sms_core::parse_retry_after);
+            let text = res.text().await;
+            if let Some(pending) = pending {
+                pending.finish(status.as_u16(), text.as_deref().unwrap_or_default());
+            }
 
-            if !res.status().is_success() {
-                let status = res.status();
-                let body = res.text().await.unwrap_or_default();
+            if !status.is_success() {
+                if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                    return Err(SmsError::RateLimited { retry_after });
+                }
+                let body = text.unwrap_or_default();
                 return Err(SmsError::Provider(format!("HTTP {}: {}", status, body)));
             }
 
-            let raw_text = res
-                .text()
-                .await
-                .map_err(|e| SmsError::Http(e.to_string()))?;
+            let http_status = status.as_u16();
+            let raw_text = text.map_err(transport_error)?;
             let raw_json: serde_json::Value = serde_json::from_str(&raw_text)
                 .unwrap_or_else(|_| serde_json::json!({ "raw": raw_text }));
+            let provider_request_id = raw_json
+                .get("api_id")
+                .and_then(|v| v.as_str())
+                .map(String::from);
 
             let id = raw_json
                 .get("message_uuid")
@@ -157,15 +278,97 @@ impl SmsClient for PlivoClient {
                 .and_then(|arr| arr.first())
                 .and_then(|v| v.as_str())
                 .map(|s| s.to_string())
-                .unwrap_or_else(sms_core::fallback_id);
+                .unwrap_or_else(|| self.id_generator.generate());
 
             Ok(SendResponse {
                 id,
                 provider: PROVIDER,
+                channel: Channel::Sms,
+                http_status: Some(http_status),
+                provider_request_id,
+                thread_id: Some(req.thread_id()),
                 raw: raw_json,
             })
         }
     }
+
+    fn max_text_len(&self) -> Option<usize> {
+        Some(MAX_TEXT_LEN)
+    }
+}
+
+#[cfg(feature = "reqwest")]
+#[async_trait::async_trait]
+impl sms_core::BalanceSource for PlivoClient {
+    async fn balance(&self) -> Result<sms_core::Balance, SmsError> {
+        let url = format!(
+            "{}/v1/Account/{}/",
+            self.base_url.trim_end_matches('/'),
+            self.auth_id
+        );
+        let res = self
+            .http
+            .get(&url)
+            .basic_auth(&self.auth_id, Some(self.auth_token.expose_secret()))
+            .timeout(self.timeout)
+            .send()
+            .await
+            .map_err(transport_error)?;
+        let status = res.status();
+        let body = res.text().await.map_err(transport_error)?;
+        if !status.is_success() {
+            return Err(SmsError::Provider(format!("HTTP {}: {}", status, body)));
+        }
+        parse_balance(&body)
+    }
+}
+
+/// Read `cash_credits` from an account details response.  Plivo accounts
+/// are billed in US dollars.
+#[cfg(feature = "reqwest")]
+fn parse_balance(body: &str) -> Result<sms_core::Balance, SmsError> {
+    let json: serde_json::Value = serde_json::from_str(body)
+        .map_err(|e| SmsError::Provider(format!("account response: {}", e)))?;
+    let amount = match json.get("cash_credits") {
+        Some(serde_json::Value::String(s)) => s.parse().ok(),
+        Some(v) => v.as_f64(),
+        None => None,
+    }
+    .ok_or_else(|| SmsError::Provider("account response has no cash_credits".into()))?;
+    Ok(sms_core::Balance {
+        amount,
+        currency: "USD".into(),
+    })
+}
+
+/// Typed view of the JSON Plivo returns from a send, available from
+/// [`SendResponse::raw`] via [`PlivoSendDetails::from_response`].
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct PlivoSendDetails {
+    /// Plivo API request ID.
+    pub api_id: Option<String>,
+    /// Human-readable status, e.g. `"message(s) queued"`.
+    pub message: Option<String>,
+    /// One UUID per message part.
+    #[serde(default)]
+    pub message_uuid: Vec<String>,
+}
+
+impl PlivoSendDetails {
+    /// Parse the raw payload of a Plivo [`SendResponse`].
+    ///
+    /// Returns [`SmsError::Invalid`] if the response came from another
+    /// provider or does not match Plivo's format.
+    pub fn from_response(resp: &SendResponse) -> Result<Self, SmsError> {
+        if resp.provider != PROVIDER {
+            return Err(SmsError::Invalid(format!(
+                "expected a {} response, got {}",
+                PROVIDER, resp.provider
+            )));
+        }
+        serde_json::from_value(resp.raw.clone())
+            .map_err(|e| SmsError::Invalid(format!("plivo send response: {}", e)))
+    }
 }
 
 /// The raw form-encoded payload that Plivo POSTs to your webhook URL when an
@@ -195,11 +398,37 @@ pub struct PlivoInbound {
     pub extra: serde_json::Value,
 }
 
+impl PlivoInbound {
+    /// Recover the original Plivo payload from [`InboundMessage::raw`].
+    pub fn from_message(msg: &InboundMessage) -> Result<Self, SmsError> {
+        if msg.provider != PROVIDER {
+            return Err(SmsError::Invalid(format!(
+                "expected a {} message, got {}",
+                PROVIDER, msg.provider
+            )));
+        }
+        serde_json::from_value(msg.raw.clone())
+            .map_err(|e| SmsError::Invalid(format!("plivo inbound payload: {}", e)))
+    }
+
+    /// The `ErrorCode` of a failed delivery report, if any.  Plivo sends `0`
+    /// for messages that did not fail.
+    pub fn error_code(&self) -> Option<PlivoErrorCode> {
+        let code = match self.extra.get("ErrorCode")? {
+            serde_json::Value::String(s) => s.parse().ok()?,
+            other => u32::try_from(other.as_u64()?).ok()?,
+        };
+        (code != 0).then(|| PlivoErrorCode::from_code(code))
+    }
+}
+
 impl From<PlivoInbound> for InboundMessage {
     fn from(p: PlivoInbound) -> Self {
-        let ts = p.time.as_deref().and_then(|s| {
-            time::OffsetDateTime::parse(s, &time::format_description::well_known::Rfc3339).ok()
-        });
+        let ts = p.time.as_deref().and_then(sms_core::timestamp::parse);
+        let channel = match p.r#type.as_deref() {
+            Some(t) if t.eq_ignore_ascii_case("mms") => Channel::Mms,
+            _ => Channel::Sms,
+        };
         let raw = serde_json::to_value(&p).unwrap_or_default();
         InboundMessage {
             id: p.message_uuid.clone(),
@@ -208,8 +437,14 @@ impl From<PlivoInbound> for InboundMessage {
             text: p.text,
             timestamp: ts,
             provider: PROVIDER,
+            direction: InboundDirection::Inbound,
+            channel,
+            provider_account: None,
+            thread_id: None,
+            flag: None,
             raw,
         }
+        .with_thread_id()
     }
 }
 
@@ -245,21 +480,103 @@ impl InboundWebhook for PlivoClient {
     }
 }
 
+/// The `plivo` configuration section read by the provider plugin.
+#[derive(Deserialize)]
+struct PlivoSettings {
+    auth_id: String,
+    auth_token: String,
+    base_url: Option<String>,
+    #[serde(default)]
+    http: Option<HttpClientConfig>,
+}
+
+/// Build a [`PlivoClient`] from the `plivo` configuration section.
+fn build_plugin(settings: &serde_json::Value) -> Result<sms_core::BuiltProvider, SmsError> {
+    let s: PlivoSettings = sms_core::provider_settings("plivo", settings)?;
+    let client = match s.base_url {
+        Some(base_url) => PlivoClient::with_base_url(s.auth_id, s.auth_token, base_url),
+        None => PlivoClient::new(s.auth_id, s.auth_token),
+    };
+    let client = match s.http {
+        Some(http) => client.with_http_config(http)?,
+        None => client,
+    };
+    Ok(sms_core::BuiltProvider::both(client))
+}
+
+sms_core::register_provider!("plivo", build_plugin);
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
     use serde_json::json;
+    use sms_core::ExposeSecret;
+    use sms_core::strategies::*;
+
+    // -- Typed views --
+
+    #[test]
+    fn send_details_from_response() {
+        let resp = SendResponse {
+            id: "u1".into(),
+            provider: "plivo",
+            channel: Channel::Sms,
+            http_status: None,
+            provider_request_id: None,
+            thread_id: None,
+            raw: json!({
+                "api_id": "api-1",
+                "message": "message(s) queued",
+                "message_uuid": ["u1", "u2"]
+            }),
+        };
+        let details = PlivoSendDetails::from_response(&resp).unwrap();
+        assert_eq!(details.api_id.as_deref(), Some("api-1"));
+        assert_eq!(details.message_uuid, vec!["u1", "u2"]);
+
+        let other = SendResponse { provider: "twilio", ..resp };
+        assert!(PlivoSendDetails::from_response(&other).is_err());
+    }
+
+    #[test]
+    fn inbound_round_trips_through_raw() {
+        let client = PlivoClient::new("id", "token");
+        let body = b"From=%2B1&To=%2B2&Text=hi&Type=sms&MessageUUID=abc";
+        let msg = client.parse_inbound(&vec![], body).unwrap();
+        let inbound = PlivoInbound::from_message(&msg).unwrap();
+        assert_eq!(inbound.r#type.as_deref(), Some("sms"));
+        assert_eq!(inbound.message_uuid.as_deref(), Some("abc"));
+    }
 
     // -- Construction tests --
 
+    #[test]
+    fn plugin_builds_from_settings() {
+        let plugin = sms_core::ProviderPlugin::find("plivo").unwrap();
+        let built = plugin
+            .build(&json!({ "auth_id": "id", "auth_token": "token" }))
+            .unwrap();
+        assert_eq!(built.webhook.unwrap().provider(), "plivo");
+        assert!(built.client.is_some());
+        assert!(plugin.build(&json!({ "auth_id": "id" })).is_err());
+    }
+
     #[test]
     fn new_sets_production_base_url() {
         let client = PlivoClient::new("id", "token");
         assert_eq!(client.auth_id, "id");
-        assert_eq!(client.auth_token, "token");
+        assert_eq!(client.auth_token.expose_secret(), "token");
         assert_eq!(client.base_url, "https://api.plivo.com");
     }
 
+    #[test]
+    fn debug_redacts_auth_token() {
+        let debug = format!("{:?}", PlivoClient::new("id", "hunter2"));
+        assert!(debug.contains("id"));
+        assert!(!debug.contains("hunter2"));
+    }
+
     #[test]
     fn with_base_url_overrides() {
         let client = PlivoClient::with_base_url("id", "token", "http://localhost:9999".into());
@@ -290,7 +607,7 @@ mod tests {
         unsafe { std::env::set_var("PLIVO_AUTH_TOKEN", "test-token"); }
         let client = PlivoClient::from_env().unwrap();
         assert_eq!(client.auth_id, "test-id");
-        assert_eq!(client.auth_token, "test-token");
+        assert_eq!(client.auth_token.expose_secret(), "test-token");
 
         // cleanup
         unsafe {
@@ -363,6 +680,22 @@ mod tests {
         assert!(msg.timestamp.is_some());
     }
 
+    #[test]
+    fn error_code_from_delivery_report() {
+        let mut inbound = PlivoInbound {
+            from: "+1".into(),
+            to: "+2".into(),
+            text: String::new(),
+            r#type: None,
+            message_uuid: None,
+            time: None,
+            extra: json!({ "Status": "failed", "ErrorCode": "200" }),
+        };
+        assert_eq!(inbound.error_code(), Some(PlivoErrorCode::BlockedByStop));
+        inbound.extra = json!({ "Status": "delivered", "ErrorCode": "0" });
+        assert_eq!(inbound.error_code(), None);
+    }
+
     #[test]
     fn inbound_conversion_without_optional_fields() {
         let inbound = PlivoInbound {
@@ -395,6 +728,21 @@ mod tests {
         assert_eq!(msg.timestamp, None); // gracefully None
     }
 
+    #[test]
+    fn inbound_conversion_space_separated_timestamp() {
+        let inbound = PlivoInbound {
+            from: "+1".into(),
+            to: "+2".into(),
+            text: "hi".into(),
+            r#type: None,
+            message_uuid: None,
+            time: Some("2024-12-30 12:34:56".into()),
+            extra: json!({}),
+        };
+        let msg: InboundMessage = inbound.into();
+        assert_eq!(msg.timestamp.unwrap().unix_timestamp(), 1_735_562_096);
+    }
+
     // -- Webhook parse tests --
 
     #[test]
@@ -426,6 +774,77 @@ mod tests {
         assert_eq!(msg.from, "+1");
         assert_eq!(msg.to, "+2");
         assert_eq!(msg.text, "hi");
+        assert_eq!(msg.channel, Channel::Sms);
+    }
+
+    #[test]
+    fn parse_inbound_mms_type() {
+        let client = PlivoClient::new("id", "token");
+        let body = b"From=%2B1&To=%2B2&Text=pic&Type=mms";
+        let msg = client.parse_inbound(&vec![], body).unwrap();
+        assert_eq!(msg.channel, Channel::Mms);
+    }
+
+    #[cfg(feature = "reqwest")]
+    #[test]
+    fn http_config_accepts_proxy_and_rejects_bad_url() {
+        let proxy = ProxyConfig::new("http://proxy.internal:3128").with_basic_auth("u", "p");
+        let client = PlivoClient::new("id", "token")
+            .with_http_config(HttpClientConfig::new().with_proxy(proxy));
+        assert!(client.is_ok());
+
+        let bad = PlivoClient::new("id", "token")
+            .with_http_config(HttpClientConfig::new().with_proxy(ProxyConfig::new("not a url")));
+        assert!(matches!(bad, Err(SmsError::Invalid(_))));
+    }
+
+    #[cfg(feature = "reqwest")]
+    #[test]
+    fn parses_account_balance() {
+        let balance = parse_balance(r#"{"auth_id":"MA1","cash_credits":"23.45000"}"#).unwrap();
+        assert_eq!(balance.amount, 23.45);
+        assert_eq!(balance.currency, "USD");
+        assert!(parse_balance(r#"{"auth_id":"MA1"}"#).is_err());
+        assert!(parse_balance("<html>").is_err());
+    }
+
+    #[cfg(feature = "reqwest")]
+    #[test]
+    fn http_config_applies_user_agent_and_headers() {
+        let config = HttpClientConfig::new()
+            .with_user_agent("acme-partner/1.0")
+            .with_header("X-Partner-Id", "acme");
+        let client = PlivoClient::new("id", "token").with_http_config(config);
+        assert!(client.is_ok());
+
+        let bad = HttpClientConfig::new().with_header("X-Partner-Id", "line\nbreak");
+        let err = PlivoClient::new("id", "token")
+            .with_http_config(bad)
+            .unwrap_err();
+        assert!(err.to_string().contains("X-Partner-Id"));
+    }
+
+    #[cfg(feature = "reqwest")]
+    #[tokio::test]
+    async fn http_logger_records_failed_calls() {
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = seen.clone();
+        let logger =
+            HttpLogger::new(move |e: &sms_core::HttpExchange| sink.lock().unwrap().push(e.clone()));
+        let client = PlivoClient::with_base_url("MA1", "token", "http://127.0.0.1:9".into())
+            .with_http_logger(logger.redact_message_text());
+
+        let req = sms_core::OwnedSendRequest::new("+15551234567", "+10005551234", "code 1234");
+        assert!(client.send(req.as_ref()).await.is_err());
+
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.len(), 1);
+        assert_eq!(seen[0].url, "http://127.0.0.1:9/v1/Account/MA1/Message/");
+        assert_eq!(seen[0].status, None);
+        assert!(seen[0].error.is_some());
+        let body = seen[0].request_body.as_deref().unwrap();
+        assert!(body.contains("+15*******67") && !body.contains("+15551234567"));
+        assert!(!body.contains("code 1234"));
     }
 
     // -- Provider trait --
@@ -469,4 +888,55 @@ mod tests {
         let j = serde_json::to_value(&payload).unwrap();
         assert_eq!(j["dst"], "+14155551234");
     }
+
+    // -- Property tests --
+
+    fn inbound_form() -> impl Strategy<Value = (String, String, String, Vec<u8>)> {
+        let fields = (
+            phone_number(),
+            phone_number(),
+            message_text(),
+            field_value(),
+        );
+        fields.prop_map(|(from, to, text, time)| {
+            let body = form_body(&[
+                ("From", &from),
+                ("To", &to),
+                ("Text", &text),
+                ("Time", &time),
+                ("MessageUUID", "uuid-1"),
+            ]);
+            (from, to, text, body)
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn parse_inbound_is_total(body in arbitrary_body(), headers in hostile_headers()) {
+            check_total(&PlivoClient::new("MA123", "token"), &headers, &body)?;
+        }
+
+        #[test]
+        fn parse_inbound_round_trips((from, to, text, body) in inbound_form()) {
+            let msg = PlivoClient::new("MA123", "token")
+                .parse_inbound(&vec![], &body)
+                .unwrap();
+            prop_assert_eq!(&msg.from, &from);
+            prop_assert_eq!(&msg.to, &to);
+            prop_assert_eq!(&msg.text, &text);
+            let original = PlivoInbound::from_message(&msg).unwrap();
+            prop_assert_eq!(original.text, text);
+            prop_assert_eq!(original.message_uuid.as_deref(), Some("uuid-1"));
+        }
+
+        #[test]
+        fn damaged_forms_never_panic(
+            body in inbound_form().prop_flat_map(|(.., body)| {
+                prop_oneof![truncated(body.clone()), mangled(body)]
+            }),
+            headers in hostile_headers(),
+        ) {
+            check_total(&PlivoClient::new("MA123", "token"), &headers, &body)?;
+        }
+    }
 }
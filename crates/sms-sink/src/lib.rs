@@ -0,0 +1,364 @@
+//! # Sink Provider
+//!
+//! A provider that delivers nothing: [`SinkClient`] accepts every send and
+//! appends a JSON line describing it to a file, or logs it through
+//! `tracing`.  Configure it in place of a real provider to run staging
+//! environments without touching a carrier, then inspect what would have
+//! gone out.
+//!
+//! ```rust,ignore
+//! use sms_core::SmsClient;
+//! use sms_sink::SinkClient;
+//!
+//! let client = SinkClient::file("/var/log/app/sms.jsonl");
+//! client.send(req.as_ref()).await?;
+//!
+//! for record in sms_sink::read_records("/var/log/app/sms.jsonl")? {
+//!     println!("{} -> {}: {}", record.request.from, record.request.to, record.request.text);
+//! }
+//! ```
+//!
+//! Unlike `smskit::dry_run::DryRunClient`, which wraps a real provider and
+//! checks each send against that provider's limits, the sink stands alone
+//! and keeps a record of every send.
+
+use serde::{Deserialize, Serialize};
+use sms_core::{
+    Channel, IdGenerator, OwnedSendRequest, SendRequest, SendResponse, SmsClient, SmsError, UuidV4,
+};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use time::OffsetDateTime;
+use tokio::io::AsyncWriteExt;
+use tracing::info;
+
+const PROVIDER: &str = "sink";
+
+/// Where a [`SinkClient`] writes its records.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SinkTarget {
+    /// Append JSON lines to this file, creating it if needed.
+    File(PathBuf),
+    /// Log each record at `INFO` under the `sms_sink` target.
+    Tracing,
+}
+
+/// One would-be send, as written to the sink.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SinkRecord {
+    /// ID returned in the [`SendResponse`].
+    pub id: String,
+    /// When the send was accepted.
+    #[serde(default, with = "sms_core::timestamp")]
+    pub at: Option<OffsetDateTime>,
+    /// The request, with numbers and text redacted if the client redacts.
+    #[serde(flatten)]
+    pub request: OwnedSendRequest,
+}
+
+/// An [`SmsClient`] that records sends instead of delivering them.
+///
+/// Clones share one open file, so concurrent sends never interleave lines.
+#[derive(Clone)]
+pub struct SinkClient {
+    /// Where records go.
+    pub target: SinkTarget,
+    /// Apply the global redaction policy to numbers and text before
+    /// writing (default: `false`).
+    pub redact: bool,
+    id_generator: Arc<dyn IdGenerator>,
+    file: Arc<tokio::sync::Mutex<Option<tokio::fs::File>>>,
+}
+
+impl std::fmt::Debug for SinkClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SinkClient")
+            .field("target", &self.target)
+            .field("redact", &self.redact)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SinkClient {
+    /// Create a client writing to `target`.
+    pub fn new(target: SinkTarget) -> Self {
+        Self {
+            target,
+            redact: false,
+            id_generator: Arc::new(UuidV4),
+            file: Arc::default(),
+        }
+    }
+
+    /// Create a client appending JSON lines to `path`.  The file is opened
+    /// on the first send.
+    pub fn file(path: impl Into<PathBuf>) -> Self {
+        Self::new(SinkTarget::File(path.into()))
+    }
+
+    /// Create a client logging each send through `tracing`.
+    pub fn tracing() -> Self {
+        Self::new(SinkTarget::Tracing)
+    }
+
+    /// Create a client from the `SMS_SINK_PATH` environment variable,
+    /// falling back to [`tracing`](Self::tracing) if it is unset.
+    pub fn from_env() -> Self {
+        match std::env::var("SMS_SINK_PATH") {
+            Ok(path) => Self::file(path),
+            Err(_) => Self::tracing(),
+        }
+    }
+
+    /// Redact numbers and text per the global redaction policy before
+    /// writing (default: off).
+    pub fn with_redaction(mut self, enabled: bool) -> Self {
+        self.redact = enabled;
+        self
+    }
+
+    /// Generate message IDs with `ids` (default: [`UuidV4`]).
+    pub fn with_id_generator(mut self, ids: impl IdGenerator + 'static) -> Self {
+        self.id_generator = Arc::new(ids);
+        self
+    }
+
+    /// Remove every record of a send to or from `phone` from the sink file,
+    /// e.g. for a GDPR erasure request.  Returns how many were removed; a
+    /// `tracing` sink keeps nothing and always returns 0.
+    ///
+    /// Numbers are compared after [`sms_core::normalize_number`], so records
+    /// written with redaction on never match.
+    pub async fn forget_number(&self, phone: &str) -> Result<usize, SmsError> {
+        let SinkTarget::File(path) = &self.target else {
+            return Ok(0);
+        };
+        let io_error = |e: std::io::Error| SmsError::Http(format!("{}: {}", path.display(), e));
+        // Hold the file lock so no send appends while the file is rewritten.
+        let mut file = self.file.lock().await;
+        let contents = match tokio::fs::read_to_string(path).await {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(io_error(e)),
+        };
+        let phone = sms_core::normalize_number(phone);
+        let mut removed = 0;
+        let mut kept = String::with_capacity(contents.len());
+        for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+            let record: SinkRecord = serde_json::from_str(line)
+                .map_err(|e| SmsError::Invalid(format!("sink record: {}", e)))?;
+            let request = &record.request;
+            if sms_core::normalize_number(&request.to) == phone
+                || sms_core::normalize_number(&request.from) == phone
+            {
+                removed += 1;
+            } else {
+                kept.push_str(line);
+                kept.push('\n');
+            }
+        }
+        if removed > 0 {
+            // Reopen on the next send rather than append through a handle
+            // to the replaced file.
+            *file = None;
+            tokio::fs::write(path, kept).await.map_err(io_error)?;
+        }
+        Ok(removed)
+    }
+
+    fn record(&self, req: &SendRequest<'_>) -> SinkRecord {
+        let mut request = OwnedSendRequest::from(req.clone());
+        if self.redact {
+            request.to = sms_core::redact_number(&request.to).into_owned();
+            request.from = sms_core::redact_number(&request.from).into_owned();
+            request.text = sms_core::redact_text(&request.text).into_owned();
+        }
+        SinkRecord {
+            id: self.id_generator.generate(),
+            at: Some(OffsetDateTime::now_utc()),
+            request,
+        }
+    }
+
+    async fn append(&self, path: &Path, line: &str) -> Result<(), SmsError> {
+        let io_error = |e: std::io::Error| SmsError::Http(format!("{}: {}", path.display(), e));
+        let mut file = self.file.lock().await;
+        if file.is_none() {
+            let opened = tokio::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .await
+                .map_err(io_error)?;
+            *file = Some(opened);
+        }
+        let file = file.as_mut().expect("sink file opened above");
+        file.write_all(format!("{}\n", line).as_bytes())
+            .await
+            .map_err(io_error)?;
+        file.flush().await.map_err(io_error)
+    }
+}
+
+impl SmsClient for SinkClient {
+    async fn send(&self, req: SendRequest<'_>) -> Result<SendResponse, SmsError> {
+        sms_core::validate_text(req.text, self.max_text_len())?;
+        let record = self.record(&req);
+        let line = serde_json::to_string(&record)
+            .map_err(|e| SmsError::Unexpected(format!("sink record: {}", e)))?;
+        match &self.target {
+            SinkTarget::File(path) => self.append(path, &line).await?,
+            SinkTarget::Tracing => info!(target: "sms_sink", record = %line, "SMS not sent"),
+        }
+
+        Ok(SendResponse {
+            id: record.id.clone(),
+            provider: PROVIDER,
+            channel: req.channel,
+            http_status: None,
+            provider_request_id: None,
+            thread_id: Some(req.thread_id()),
+            raw: serde_json::to_value(&record).unwrap_or_default(),
+        })
+    }
+
+    /// The sink accepts every channel.
+    fn supports(&self, _channel: Channel) -> bool {
+        true
+    }
+}
+
+/// Read back every record in a sink file, oldest first.
+pub fn read_records(path: impl AsRef<Path>) -> Result<Vec<SinkRecord>, SmsError> {
+    let path = path.as_ref();
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| SmsError::Http(format!("{}: {}", path.display(), e)))?;
+    contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            serde_json::from_str(line).map_err(|e| SmsError::Invalid(format!("sink record: {}", e)))
+        })
+        .collect()
+}
+
+/// The `sink` configuration section read by the provider plugin.
+#[derive(Deserialize)]
+struct SinkSettings {
+    path: Option<PathBuf>,
+    #[serde(default)]
+    redact: bool,
+}
+
+/// Build a [`SinkClient`] from the `sink` section: a file sink if `path`
+/// is set, a `tracing` sink otherwise.
+fn build_plugin(settings: &serde_json::Value) -> Result<sms_core::BuiltProvider, SmsError> {
+    let s: SinkSettings = sms_core::provider_settings(PROVIDER, settings)?;
+    let client = match s.path {
+        Some(path) => SinkClient::file(path),
+        None => SinkClient::tracing(),
+    };
+    Ok(sms_core::BuiltProvider::sender(
+        client.with_redaction(s.redact),
+    ))
+}
+
+sms_core::register_provider!("sink", build_plugin);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn temp_path(name: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("sms-sink-{}-{}.jsonl", std::process::id(), name));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[tokio::test]
+    async fn appends_json_lines() {
+        let path = temp_path("append");
+        let client = SinkClient::file(&path);
+        let first = OwnedSendRequest::new("+15551230000", "+15559870000", "hello");
+        let second = OwnedSendRequest::new("+15551230001", "MyBrand", "Grüße\nzwei Zeilen")
+            .with_channel(Channel::WhatsApp);
+
+        let resp = client.send(first.as_ref()).await.unwrap();
+        assert_eq!(resp.provider, "sink");
+        assert_eq!(resp.raw["to"], "+15551230000");
+        client.clone().send(second.as_ref()).await.unwrap();
+
+        let records = read_records(&path).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].id, resp.id);
+        assert_eq!(records[0].request, first);
+        assert_eq!(records[1].request, second);
+        assert!(records[1].at.is_some());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn forget_number_rewrites_file() {
+        let path = temp_path("forget");
+        let client = SinkClient::file(&path);
+        let keep = OwnedSendRequest::new("+15551230001", "+15559870000", "keep");
+        client
+            .send(OwnedSendRequest::new("+15551230000", "+15559870000", "a").as_ref())
+            .await
+            .unwrap();
+        client.send(keep.as_ref()).await.unwrap();
+
+        assert_eq!(client.forget_number("+1 555 123 0000").await.unwrap(), 1);
+        client
+            .send(OwnedSendRequest::new("+15551230002", "+15559870000", "b").as_ref())
+            .await
+            .unwrap();
+
+        let records = read_records(&path).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].request, keep);
+        assert_eq!(client.forget_number("+15559870000").await.unwrap(), 2);
+        assert!(read_records(&path).unwrap().is_empty());
+        assert_eq!(SinkClient::tracing().forget_number("+1").await.unwrap(), 0);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn tracing_sink_accepts_sends() {
+        let client = SinkClient::tracing();
+        let req = OwnedSendRequest::new("+15551230000", "+15559870000", "hello");
+        let resp = client.send(req.as_ref()).await.unwrap();
+        assert_eq!(resp.raw["text"], "hello");
+    }
+
+    #[tokio::test]
+    async fn still_validates_text() {
+        let client = SinkClient::tracing();
+        let req = OwnedSendRequest::new("+15551230000", "+15559870000", "   ");
+        assert!(matches!(
+            client.send(req.as_ref()).await,
+            Err(SmsError::Invalid(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn unwritable_paths_fail_the_send() {
+        let client = SinkClient::file(std::env::temp_dir().join("no-such-dir/sms.jsonl"));
+        let req = OwnedSendRequest::new("+15551230000", "+15559870000", "hello");
+        assert!(matches!(
+            client.send(req.as_ref()).await,
+            Err(SmsError::Http(_))
+        ));
+    }
+
+    #[test]
+    fn plugin_picks_the_target() {
+        let plugin = sms_core::ProviderPlugin::find("sink").unwrap();
+        let built = plugin.build(&json!({ "path": "/tmp/sms.jsonl" })).unwrap();
+        assert!(built.client.is_some());
+        assert!(built.webhook.is_none());
+        assert!(plugin.build(&json!({})).unwrap().client.is_some());
+    }
+}
@@ -0,0 +1,816 @@
+//! # SMPP Provider
+//!
+//! SMPP 3.4 client for carrier-direct connections.  [`SmppClient`] keeps one
+//! long-lived bind to the SMSC, sends with `submit_sm`, and runs a listener
+//! task that answers keepalives and turns every `deliver_sm` — mobile
+//! originated messages and delivery receipts alike — into a normalized
+//! [`InboundMessage`].
+//!
+//! ## Sending messages
+//!
+//! ```rust,ignore
+//! use sms_core::{SendRequest, SmsClient};
+//! use sms_smpp::{BindMode, SmppClient};
+//!
+//! let client = SmppClient::new("smsc.example.net", "esme", "secret")
+//!     .with_bind_mode(BindMode::Transceiver);
+//! let response = client.send(SendRequest {
+//!     to: "+447700900000",
+//!     from: "MyBrand",
+//!     text: "Hello over SMPP!",
+//!     ..Default::default()
+//! }).await?;
+//! ```
+//!
+//! The bind is made on first use and remade after the connection drops;
+//! call [`SmppClient::bind`] to connect eagerly.
+//!
+//! ## Receiving messages
+//!
+//! SMPP has no webhooks: the SMSC pushes `deliver_sm` over the bind.  The
+//! listener task queues them, and [`SmppClient`] implements
+//! [`InboundPoller`] to drain the queue, so it plugs into the same polling
+//! runner as a GSM modem:
+//!
+//! ```rust,ignore
+//! let runner = PollingRunner::new(Arc::new(client.clone()), processor)
+//!     .with_interval(Duration::from_secs(1));
+//! tokio::spawn(async move { runner.run().await });
+//! ```
+
+pub mod pdu;
+
+pub use pdu::{Address, DeliverSm};
+
+use async_trait::async_trait;
+use pdu::{Pdu, SubmitSm};
+use serde::{Deserialize, Serialize};
+use sms_core::{
+    Channel, ExposeSecret, InboundDirection, InboundMessage, InboundPoller, SecretString,
+    SendRequest, SendResponse, SmsClient, SmsError,
+};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+use tokio::net::tcp::OwnedWriteHalf;
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+const PROVIDER: &str = "smpp";
+
+/// The IANA-registered SMPP port.
+pub const DEFAULT_PORT: u16 = 2775;
+
+/// Default time between `enquire_link` keepalives.
+const DEFAULT_ENQUIRE_LINK: Duration = Duration::from_secs(30);
+
+/// Default deadline for connecting and for each request's response.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// `command_status` values this crate maps to specific [`SmsError`]s.
+const ESME_RINVMSGLEN: u32 = 0x01;
+const ESME_RINVCMDID: u32 = 0x03;
+const ESME_RSYSERR: u32 = 0x08;
+const ESME_RINVSRCADR: u32 = 0x0A;
+const ESME_RINVDSTADR: u32 = 0x0B;
+const ESME_RBINDFAIL: u32 = 0x0D;
+const ESME_RINVPASWD: u32 = 0x0E;
+const ESME_RINVSYSID: u32 = 0x0F;
+const ESME_RMSGQFUL: u32 = 0x14;
+const ESME_RTHROTTLED: u32 = 0x58;
+
+/// How the client binds to the SMSC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BindMode {
+    /// Send only.
+    Transmitter,
+    /// Receive only.
+    Receiver,
+    /// Send and receive over one connection.
+    #[default]
+    Transceiver,
+}
+
+impl BindMode {
+    fn command_id(self) -> u32 {
+        match self {
+            BindMode::Transmitter => pdu::BIND_TRANSMITTER,
+            BindMode::Receiver => pdu::BIND_RECEIVER,
+            BindMode::Transceiver => pdu::BIND_TRANSCEIVER,
+        }
+    }
+
+    /// Returns `true` if this bind can `submit_sm`.
+    pub fn can_send(self) -> bool {
+        self != BindMode::Receiver
+    }
+
+    /// Returns `true` if this bind receives `deliver_sm`.
+    pub fn can_receive(self) -> bool {
+        self != BindMode::Transmitter
+    }
+}
+
+/// Requests awaiting a response, by sequence number.
+type Pending = Arc<Mutex<HashMap<u32, oneshot::Sender<Pdu>>>>;
+
+/// Handles onto a live bind, cheap to clone out from under the session lock.
+#[derive(Clone)]
+struct Link {
+    writer: Arc<tokio::sync::Mutex<OwnedWriteHalf>>,
+    pending: Pending,
+    alive: Arc<AtomicBool>,
+}
+
+impl Link {
+    async fn write(&self, pdu: &Pdu) -> Result<(), SmsError> {
+        let result = self.writer.lock().await.write_all(&pdu.encode()).await;
+        result.map_err(|e| {
+            self.alive.store(false, Ordering::Relaxed);
+            pdu::io_error(e)
+        })
+    }
+}
+
+/// A bound connection and the tasks serving it.
+struct Session {
+    link: Link,
+    tasks: [JoinHandle<()>; 2],
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+/// State shared by every clone of a client.
+struct State {
+    session: tokio::sync::Mutex<Option<Session>>,
+    sequence: Arc<AtomicU32>,
+    inbound_tx: mpsc::UnboundedSender<InboundMessage>,
+    inbound_rx: tokio::sync::Mutex<mpsc::UnboundedReceiver<InboundMessage>>,
+}
+
+/// SMPP 3.4 client.
+///
+/// Implements [`SmsClient`] for sending and [`InboundPoller`] for received
+/// messages and delivery receipts.  Clones share one bind.
+#[derive(Clone)]
+pub struct SmppClient {
+    /// SMSC host name or address.
+    pub host: String,
+    /// SMSC port (default: [`DEFAULT_PORT`]).
+    pub port: u16,
+    /// ESME `system_id`.
+    pub system_id: String,
+    /// ESME password.  Zeroized on drop.
+    pub password: SecretString,
+    /// `system_type` sent in the bind (default: empty).
+    pub system_type: String,
+    /// How to bind (default: [`BindMode::Transceiver`]).
+    pub bind_mode: BindMode,
+    /// Request delivery receipts for sends (default: `true`).
+    pub registered_delivery: bool,
+    /// Time between `enquire_link` keepalives (default: 30 seconds).
+    pub enquire_link_interval: Duration,
+    /// Deadline for connecting and for each response (default: 10 seconds).
+    pub timeout: Duration,
+    state: Arc<State>,
+}
+
+impl std::fmt::Debug for SmppClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SmppClient")
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("system_id", &self.system_id)
+            .field("password", &"[REDACTED]")
+            .field("bind_mode", &self.bind_mode)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SmppClient {
+    /// Create a client for the SMSC at `host` on [`DEFAULT_PORT`].  Nothing
+    /// connects until the first send or poll, or [`bind`](Self::bind).
+    pub fn new(
+        host: impl Into<String>,
+        system_id: impl Into<String>,
+        password: impl Into<String>,
+    ) -> Self {
+        let (inbound_tx, inbound_rx) = mpsc::unbounded_channel();
+        Self {
+            host: host.into(),
+            port: DEFAULT_PORT,
+            system_id: system_id.into(),
+            password: SecretString::from(password.into()),
+            system_type: String::new(),
+            bind_mode: BindMode::default(),
+            registered_delivery: true,
+            enquire_link_interval: DEFAULT_ENQUIRE_LINK,
+            timeout: DEFAULT_TIMEOUT,
+            state: Arc::new(State {
+                session: tokio::sync::Mutex::new(None),
+                sequence: Arc::new(AtomicU32::new(1)),
+                inbound_tx,
+                inbound_rx: tokio::sync::Mutex::new(inbound_rx),
+            }),
+        }
+    }
+
+    /// Create a client by reading connection settings from environment
+    /// variables.
+    ///
+    /// | Variable           | Required | Notes |
+    /// |--------------------|----------|-------|
+    /// | `SMPP_HOST`        | Yes      | |
+    /// | `SMPP_PORT`        | No       | Defaults to 2775 |
+    /// | `SMPP_SYSTEM_ID`   | Yes      | |
+    /// | `SMPP_PASSWORD`    | Yes      | |
+    ///
+    /// Returns [`SmsError::Auth`] if a required variable is missing.
+    pub fn from_env() -> Result<Self, SmsError> {
+        let host =
+            std::env::var("SMPP_HOST").map_err(|_| SmsError::Auth("SMPP_HOST not set".into()))?;
+        let system_id = std::env::var("SMPP_SYSTEM_ID")
+            .map_err(|_| SmsError::Auth("SMPP_SYSTEM_ID not set".into()))?;
+        let password = std::env::var("SMPP_PASSWORD")
+            .map_err(|_| SmsError::Auth("SMPP_PASSWORD not set".into()))?;
+        let mut client = Self::new(host, system_id, password);
+        if let Ok(port) = std::env::var("SMPP_PORT") {
+            client.port = port
+                .parse()
+                .map_err(|_| SmsError::Invalid(format!("SMPP_PORT {} is not a port", port)))?;
+        }
+        Ok(client)
+    }
+
+    /// Connect to `port` instead of [`DEFAULT_PORT`].
+    pub fn with_port(mut self, port: u16) -> Self {
+        self.port = port;
+        self
+    }
+
+    /// Bind as `mode` (default: [`BindMode::Transceiver`]).
+    pub fn with_bind_mode(mut self, mode: BindMode) -> Self {
+        self.bind_mode = mode;
+        self
+    }
+
+    /// Send `system_type` in the bind, for SMSCs that require one.
+    pub fn with_system_type(mut self, system_type: impl Into<String>) -> Self {
+        self.system_type = system_type.into();
+        self
+    }
+
+    /// Turn delivery receipt requests on or off (default: on).
+    pub fn with_registered_delivery(mut self, enabled: bool) -> Self {
+        self.registered_delivery = enabled;
+        self
+    }
+
+    /// Send `enquire_link` every `interval` (default: 30 seconds).
+    pub fn with_enquire_link_interval(mut self, interval: Duration) -> Self {
+        self.enquire_link_interval = interval;
+        self
+    }
+
+    /// Set the connect and response deadline (default: 10 seconds).
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Bind now, if not already bound.
+    pub async fn bind(&self) -> Result<(), SmsError> {
+        self.link().await.map(|_| ())
+    }
+
+    /// Returns `true` while the bind is up.
+    pub async fn is_bound(&self) -> bool {
+        let session = self.state.session.lock().await;
+        session
+            .as_ref()
+            .is_some_and(|s| s.link.alive.load(Ordering::Relaxed))
+    }
+
+    /// Unbind and close the connection.  The next send or poll rebinds.
+    pub async fn unbind(&self) -> Result<(), SmsError> {
+        let Some(session) = self.state.session.lock().await.take() else {
+            return Ok(());
+        };
+        if session.link.alive.load(Ordering::Relaxed) {
+            let unbind = Pdu::new(pdu::UNBIND, self.next_sequence(), Vec::new());
+            self.request(&session.link, unbind).await?;
+        }
+        info!("SMPP unbound from {}:{}", self.host, self.port);
+        Ok(())
+    }
+
+    fn next_sequence(&self) -> u32 {
+        next_sequence(&self.state.sequence)
+    }
+
+    /// The live bind, binding first if there is none.
+    async fn link(&self) -> Result<Link, SmsError> {
+        let mut session = self.state.session.lock().await;
+        if let Some(s) = session.as_ref() {
+            if s.link.alive.load(Ordering::Relaxed) {
+                return Ok(s.link.clone());
+            }
+            warn!("SMPP bind to {}:{} lost; rebinding", self.host, self.port);
+        }
+        let new = self.connect().await?;
+        let link = new.link.clone();
+        *session = Some(new);
+        Ok(link)
+    }
+
+    async fn connect(&self) -> Result<Session, SmsError> {
+        let stream = tokio::time::timeout(
+            self.timeout,
+            TcpStream::connect((self.host.as_str(), self.port)),
+        )
+        .await
+        .map_err(|_| SmsError::Timeout(format!("connecting to {}:{}", self.host, self.port)))?
+        .map_err(pdu::io_error)?;
+        let _ = stream.set_nodelay(true);
+        let (mut reader, mut writer) = stream.into_split();
+
+        let bind = Pdu::new(
+            self.bind_mode.command_id(),
+            self.next_sequence(),
+            pdu::bind_body(
+                &self.system_id,
+                self.password.expose_secret(),
+                &self.system_type,
+            ),
+        );
+        writer
+            .write_all(&bind.encode())
+            .await
+            .map_err(pdu::io_error)?;
+        let resp = tokio::time::timeout(self.timeout, Pdu::read(&mut reader))
+            .await
+            .map_err(|_| SmsError::Timeout("waiting for bind response".into()))??;
+        if resp.command_id != bind.command_id | pdu::RESPONSE || resp.sequence != bind.sequence {
+            return Err(SmsError::Provider(format!(
+                "unexpected bind response 0x{:08X}",
+                resp.command_id
+            )));
+        }
+        if resp.command_status != 0 {
+            return Err(status_error(resp.command_status));
+        }
+        info!(
+            "SMPP bound to {}:{} as {:?}",
+            self.host, self.port, self.bind_mode
+        );
+
+        let link = Link {
+            writer: Arc::new(tokio::sync::Mutex::new(writer)),
+            pending: Arc::default(),
+            alive: Arc::new(AtomicBool::new(true)),
+        };
+        let listener = tokio::spawn(listen(reader, link.clone(), self.state.inbound_tx.clone()));
+        let keepalive = tokio::spawn(keepalive(
+            link.clone(),
+            self.state.sequence.clone(),
+            self.enquire_link_interval,
+        ));
+        Ok(Session {
+            link,
+            tasks: [listener, keepalive],
+        })
+    }
+
+    /// Send `request` over `link` and wait for its response.
+    async fn request(&self, link: &Link, request: Pdu) -> Result<Pdu, SmsError> {
+        let (tx, rx) = oneshot::channel();
+        link.pending
+            .lock()
+            .expect("smpp pending lock poisoned")
+            .insert(request.sequence, tx);
+        if let Err(e) = link.write(&request).await {
+            link.pending
+                .lock()
+                .expect("smpp pending lock poisoned")
+                .remove(&request.sequence);
+            return Err(e);
+        }
+        match tokio::time::timeout(self.timeout, rx).await {
+            Ok(Ok(resp)) if resp.command_id == pdu::GENERIC_NACK => Err(SmsError::Provider(
+                format!("SMSC rejected the PDU (0x{:08X})", resp.command_status),
+            )),
+            Ok(Ok(resp)) => Ok(resp),
+            Ok(Err(_)) => Err(SmsError::Http("SMPP connection closed".into())),
+            Err(_) => {
+                link.pending
+                    .lock()
+                    .expect("smpp pending lock poisoned")
+                    .remove(&request.sequence);
+                Err(SmsError::Timeout(format!(
+                    "no response to SMPP command 0x{:08X}",
+                    request.command_id
+                )))
+            }
+        }
+    }
+}
+
+/// The next sequence number, wrapping within the 1..=0x7FFFFFFF range SMPP
+/// allows.
+fn next_sequence(sequence: &AtomicU32) -> u32 {
+    sequence.fetch_add(1, Ordering::Relaxed) % 0x7FFF_FFFF + 1
+}
+
+/// Map a non-zero `command_status` to an [`SmsError`].
+fn status_error(status: u32) -> SmsError {
+    match status {
+        ESME_RBINDFAIL | ESME_RINVPASWD | ESME_RINVSYSID => {
+            SmsError::Auth(format!("SMPP bind rejected (0x{:08X})", status))
+        }
+        ESME_RTHROTTLED | ESME_RMSGQFUL => SmsError::RateLimited { retry_after: None },
+        ESME_RINVMSGLEN | ESME_RINVSRCADR | ESME_RINVDSTADR => {
+            SmsError::Invalid(format!("SMPP command_status 0x{:08X}", status))
+        }
+        _ => SmsError::Provider(format!("SMPP command_status 0x{:08X}", status)),
+    }
+}
+
+/// The listener task: routes responses to waiting requests, answers the
+/// SMSC's requests, and queues every `deliver_sm` as an [`InboundMessage`].
+async fn listen(
+    mut reader: tokio::net::tcp::OwnedReadHalf,
+    link: Link,
+    inbound: mpsc::UnboundedSender<InboundMessage>,
+) {
+    loop {
+        let incoming = match Pdu::read(&mut reader).await {
+            Ok(pdu) => pdu,
+            Err(e) => {
+                warn!("SMPP listener stopping: {}", e);
+                break;
+            }
+        };
+        if incoming.is_response() {
+            let waiter = link
+                .pending
+                .lock()
+                .expect("smpp pending lock poisoned")
+                .remove(&incoming.sequence);
+            if let Some(waiter) = waiter {
+                let _ = waiter.send(incoming);
+            }
+            continue;
+        }
+        let reply = match incoming.command_id {
+            pdu::DELIVER_SM => match DeliverSm::decode(&incoming.body) {
+                Ok(sm) => {
+                    debug!("SMPP deliver_sm from {}", sm.source);
+                    let _ = inbound.send(to_inbound(sm));
+                    incoming.response(0, vec![0])
+                }
+                Err(e) => {
+                    warn!("Undecodable deliver_sm: {}", e);
+                    incoming.response(ESME_RSYSERR, vec![0])
+                }
+            },
+            pdu::ENQUIRE_LINK | pdu::UNBIND => incoming.response(0, Vec::new()),
+            _ => Pdu {
+                command_id: pdu::GENERIC_NACK,
+                command_status: ESME_RINVCMDID,
+                sequence: incoming.sequence,
+                body: Vec::new(),
+            },
+        };
+        if link.write(&reply).await.is_err() || incoming.command_id == pdu::UNBIND {
+            break;
+        }
+    }
+    link.alive.store(false, Ordering::Relaxed);
+    link.pending
+        .lock()
+        .expect("smpp pending lock poisoned")
+        .clear();
+}
+
+/// The keepalive task: sends `enquire_link` until the bind drops.
+async fn keepalive(link: Link, sequence: Arc<AtomicU32>, interval: Duration) {
+    loop {
+        tokio::time::sleep(interval).await;
+        if !link.alive.load(Ordering::Relaxed) {
+            break;
+        }
+        let ping = Pdu::new(pdu::ENQUIRE_LINK, next_sequence(&sequence), Vec::new());
+        if link.write(&ping).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// Normalize a `deliver_sm`: delivery receipts become delivery reports,
+/// anything else an inbound message.
+fn to_inbound(sm: DeliverSm) -> InboundMessage {
+    let raw = serde_json::to_value(&sm).unwrap_or_default();
+    match sm.receipt().filter(|_| sm.is_delivery_receipt()) {
+        Some((id, stat)) => InboundMessage {
+            id: Some(id),
+            // The receipt travels back from the original recipient to us.
+            from: sm.destination.to_string(),
+            to: sm.source.to_string(),
+            text: format!("Delivery Status: {}", stat),
+            timestamp: None,
+            provider: PROVIDER,
+            direction: InboundDirection::DeliveryReport,
+            channel: Channel::Sms,
+            provider_account: None,
+            thread_id: None,
+            flag: None,
+            raw,
+        },
+        None => InboundMessage {
+            id: None,
+            from: sm.source.to_string(),
+            to: sm.destination.to_string(),
+            text: sm.text,
+            timestamp: None,
+            provider: PROVIDER,
+            direction: InboundDirection::Inbound,
+            channel: Channel::Sms,
+            provider_account: None,
+            thread_id: None,
+            flag: None,
+            raw,
+        },
+    }
+    .with_thread_id()
+}
+
+impl SmsClient for SmppClient {
+    async fn send(&self, req: SendRequest<'_>) -> Result<SendResponse, SmsError> {
+        sms_core::validate_text(req.text, self.max_text_len())?;
+        if !self.bind_mode.can_send() {
+            return Err(SmsError::Invalid("a receiver bind cannot send".into()));
+        }
+        let submit = SubmitSm {
+            source: Address::guess(req.from),
+            destination: Address::guess(req.to),
+            registered_delivery: self.registered_delivery,
+            text: req.text.to_string(),
+        };
+        let link = self.link().await?;
+        let sequence = self.next_sequence();
+        debug!(
+            "SMPP submit_sm #{} to {}",
+            sequence,
+            sms_core::redact_number(req.to)
+        );
+        let resp = self
+            .request(&link, Pdu::new(pdu::SUBMIT_SM, sequence, submit.encode()?))
+            .await?;
+        if resp.command_status != 0 {
+            return Err(status_error(resp.command_status));
+        }
+        let id = pdu::submit_sm_resp_id(&resp.body)?;
+
+        Ok(SendResponse {
+            raw: serde_json::json!({
+                "message_id": id,
+                "sequence": sequence,
+                "host": self.host,
+            }),
+            id,
+            provider: PROVIDER,
+            channel: Channel::Sms,
+            http_status: None,
+            provider_request_id: None,
+            thread_id: Some(req.thread_id()),
+        })
+    }
+}
+
+#[async_trait]
+impl InboundPoller for SmppClient {
+    fn provider(&self) -> &'static str {
+        PROVIDER
+    }
+
+    /// Drain the `deliver_sm`s queued since the last poll, then rebind if
+    /// the bind has dropped.  A failed rebind is only an error when there
+    /// was nothing to return.
+    async fn poll(&self) -> Result<Vec<InboundMessage>, SmsError> {
+        let mut messages = Vec::new();
+        {
+            let mut rx = self.state.inbound_rx.lock().await;
+            while let Ok(message) = rx.try_recv() {
+                messages.push(message);
+            }
+        }
+        let rebind = if self.bind_mode.can_receive() {
+            self.bind().await
+        } else {
+            Ok(())
+        };
+        match rebind {
+            Err(e) if messages.is_empty() => Err(e),
+            Err(e) => {
+                warn!("SMPP rebind failed: {}", e);
+                Ok(messages)
+            }
+            Ok(()) => Ok(messages),
+        }
+    }
+}
+
+/// The `smpp` configuration section read by the provider plugin.
+#[derive(Deserialize)]
+struct SmppSettings {
+    host: String,
+    port: Option<u16>,
+    system_id: String,
+    password: String,
+    #[serde(default)]
+    system_type: String,
+    #[serde(default)]
+    bind_mode: BindMode,
+    #[serde(default = "default_true")]
+    registered_delivery: bool,
+    enquire_link_secs: Option<u64>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Build an [`SmppClient`] from the `smpp` section.  The plugin only
+/// registers the sending side; hand a clone of the client to a polling
+/// runner to receive.
+fn build_plugin(settings: &serde_json::Value) -> Result<sms_core::BuiltProvider, SmsError> {
+    let s: SmppSettings = sms_core::provider_settings(PROVIDER, settings)?;
+    let mut client = SmppClient::new(s.host, s.system_id, s.password)
+        .with_port(s.port.unwrap_or(DEFAULT_PORT))
+        .with_system_type(s.system_type)
+        .with_bind_mode(s.bind_mode)
+        .with_registered_delivery(s.registered_delivery);
+    if let Some(secs) = s.enquire_link_secs {
+        client = client.with_enquire_link_interval(Duration::from_secs(secs));
+    }
+    Ok(sms_core::BuiltProvider::sender(client))
+}
+
+sms_core::register_provider!("smpp", build_plugin);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use sms_core::OwnedSendRequest;
+    use tokio::net::TcpListener;
+
+    /// A one-connection SMSC that accepts the bind with `bind_status`,
+    /// answers one `submit_sm`, then pushes an MO message and a receipt.
+    async fn fake_smsc(bind_status: u32) -> (u16, JoinHandle<Vec<Pdu>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let task = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut seen = Vec::new();
+            let bind = Pdu::read(&mut socket).await.unwrap();
+            let resp = bind.response(bind_status, b"SMSC\0".to_vec());
+            socket.write_all(&resp.encode()).await.unwrap();
+            seen.push(bind);
+            if bind_status != 0 {
+                return seen;
+            }
+
+            let submit = Pdu::read(&mut socket).await.unwrap();
+            let resp = submit.response(0, b"msg-1\0".to_vec());
+            socket.write_all(&resp.encode()).await.unwrap();
+            seen.push(submit);
+
+            for (seq, esm_class, text) in [
+                (100, 0x00, &b"STOP"[..]),
+                (
+                    101,
+                    0x04,
+                    &b"id:msg-1 sub:001 dlvrd:001 stat:DELIVRD err:000"[..],
+                ),
+            ] {
+                let mut body = b"\0\x01\x01447700900000\0\x01\x01447700900111\0".to_vec();
+                body.extend_from_slice(&[esm_class, 0, 0, 0, 0, 1, 0, 0, 0, text.len() as u8]);
+                body.extend_from_slice(text);
+                let deliver = Pdu::new(pdu::DELIVER_SM, seq, body);
+                socket.write_all(&deliver.encode()).await.unwrap();
+                seen.push(Pdu::read(&mut socket).await.unwrap());
+            }
+            seen
+        });
+        (port, task)
+    }
+
+    #[tokio::test]
+    async fn sends_and_receives_over_one_bind() {
+        let (port, smsc) = fake_smsc(0).await;
+        let client = SmppClient::new("127.0.0.1", "esme", "secret").with_port(port);
+        let req = OwnedSendRequest::new("+447700900000", "MyBrand", "hello");
+        let resp = client.send(req.as_ref()).await.unwrap();
+        assert_eq!(resp.id, "msg-1");
+        assert_eq!(resp.provider, "smpp");
+
+        let seen = smsc.await.unwrap();
+        assert_eq!(seen[0].command_id, pdu::BIND_TRANSCEIVER);
+        assert!(seen[0].body.starts_with(b"esme\0secret\0"));
+        assert_eq!(seen[1].command_id, pdu::SUBMIT_SM);
+        assert_eq!(seen[2].command_id, pdu::DELIVER_SM | pdu::RESPONSE);
+        assert_eq!(seen[3].sequence, 101);
+
+        let messages = client.poll().await.unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].direction, InboundDirection::Inbound);
+        assert_eq!(messages[0].from, "+447700900000");
+        assert_eq!(messages[0].to, "+447700900111");
+        assert_eq!(messages[0].text, "STOP");
+        assert_eq!(messages[1].direction, InboundDirection::DeliveryReport);
+        assert_eq!(messages[1].id.as_deref(), Some("msg-1"));
+        assert_eq!(messages[1].text, "Delivery Status: DELIVRD");
+        assert_eq!(messages[1].from, "+447700900111");
+        assert_eq!(messages[1].to, "+447700900000");
+    }
+
+    #[tokio::test]
+    async fn rejected_bind_is_an_auth_error() {
+        let (port, smsc) = fake_smsc(ESME_RINVPASWD).await;
+        let client = SmppClient::new("127.0.0.1", "esme", "wrong").with_port(port);
+        assert!(matches!(client.bind().await, Err(SmsError::Auth(_))));
+        assert!(!client.is_bound().await);
+        smsc.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn receiver_binds_cannot_send() {
+        let client =
+            SmppClient::new("127.0.0.1", "esme", "secret").with_bind_mode(BindMode::Receiver);
+        let req = OwnedSendRequest::new("+447700900000", "", "hello");
+        assert!(matches!(
+            client.send(req.as_ref()).await,
+            Err(SmsError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn command_statuses_map_to_errors() {
+        assert!(matches!(
+            status_error(ESME_RTHROTTLED),
+            SmsError::RateLimited { .. }
+        ));
+        assert!(matches!(
+            status_error(ESME_RINVDSTADR),
+            SmsError::Invalid(_)
+        ));
+        assert!(matches!(status_error(ESME_RBINDFAIL), SmsError::Auth(_)));
+        assert!(matches!(status_error(0x45), SmsError::Provider(_)));
+    }
+
+    #[test]
+    fn sequence_numbers_wrap() {
+        let sequence = AtomicU32::new(0x7FFF_FFFE);
+        assert_eq!(next_sequence(&sequence), 0x7FFF_FFFF);
+        assert_eq!(next_sequence(&sequence), 1);
+    }
+
+    #[test]
+    fn plugin_builds_a_sender() {
+        let plugin = sms_core::ProviderPlugin::find("smpp").unwrap();
+        let built = plugin
+            .build(&json!({
+                "host": "smsc.example.net",
+                "system_id": "esme",
+                "password": "secret",
+                "bind_mode": "transmitter"
+            }))
+            .unwrap();
+        assert!(built.client.is_some());
+        assert!(built.webhook.is_none());
+        assert!(
+            plugin
+                .build(&json!({ "host": "smsc.example.net" }))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn debug_redacts_password() {
+        let debug = format!("{:?}", SmppClient::new("smsc", "esme", "hunter2"));
+        assert!(debug.contains("esme"));
+        assert!(!debug.contains("hunter2"));
+    }
+}
@@ -0,0 +1,594 @@
+//! SMPP 3.4 protocol data units.
+//!
+//! Only the PDUs an ESME needs for sending and receiving text are modelled:
+//! binds, `submit_sm`, `deliver_sm`, `enquire_link`, `unbind` and their
+//! responses.  Every PDU is a 16-byte header (length, command ID, status,
+//! sequence number) followed by a command-specific body.
+
+use serde::Serialize;
+use sms_core::SmsError;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// Bit set on the command ID of every response PDU.
+pub const RESPONSE: u32 = 0x8000_0000;
+
+/// `generic_nack`: the SMSC could not parse a request.
+pub const GENERIC_NACK: u32 = 0x8000_0000;
+/// `bind_receiver`.
+pub const BIND_RECEIVER: u32 = 0x0000_0001;
+/// `bind_transmitter`.
+pub const BIND_TRANSMITTER: u32 = 0x0000_0002;
+/// `submit_sm`: send a message.
+pub const SUBMIT_SM: u32 = 0x0000_0004;
+/// `deliver_sm`: an MO message or delivery receipt from the SMSC.
+pub const DELIVER_SM: u32 = 0x0000_0005;
+/// `unbind`: close the session.
+pub const UNBIND: u32 = 0x0000_0006;
+/// `bind_transceiver`.
+pub const BIND_TRANSCEIVER: u32 = 0x0000_0009;
+/// `enquire_link`: keepalive.
+pub const ENQUIRE_LINK: u32 = 0x0000_0015;
+
+/// SMPP 3.4, as sent in `interface_version`.
+pub const INTERFACE_VERSION: u8 = 0x34;
+
+/// `data_coding` for the SMSC default alphabet.
+pub const DATA_CODING_DEFAULT: u8 = 0x00;
+/// `data_coding` for Latin-1.
+pub const DATA_CODING_LATIN1: u8 = 0x03;
+/// `data_coding` for UCS-2 (UTF-16BE).
+pub const DATA_CODING_UCS2: u8 = 0x08;
+
+/// `esm_class` message-type bits marking an SMSC delivery receipt.
+const ESM_CLASS_DELIVERY_RECEIPT: u8 = 0x04;
+
+/// Longest `short_message`; longer bodies travel in `message_payload`.
+const MAX_SHORT_MESSAGE: usize = 254;
+
+/// Optional parameter tags this crate reads or writes.
+const TLV_RECEIPTED_MESSAGE_ID: u16 = 0x001E;
+const TLV_MESSAGE_PAYLOAD: u16 = 0x0424;
+const TLV_MESSAGE_STATE: u16 = 0x0427;
+
+/// Largest PDU accepted from the SMSC.
+const MAX_PDU_LEN: u32 = 64 * 1024;
+
+/// A raw PDU: header fields plus the undecoded body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pdu {
+    /// Command ID, e.g. [`SUBMIT_SM`] or `SUBMIT_SM | RESPONSE`.
+    pub command_id: u32,
+    /// `command_status`; `0` (`ESME_ROK`) on success.
+    pub command_status: u32,
+    /// Sequence number pairing a response with its request.
+    pub sequence: u32,
+    /// Command-specific body.
+    pub body: Vec<u8>,
+}
+
+impl Pdu {
+    /// A request PDU.
+    pub fn new(command_id: u32, sequence: u32, body: Vec<u8>) -> Self {
+        Self {
+            command_id,
+            command_status: 0,
+            sequence,
+            body,
+        }
+    }
+
+    /// The response to this PDU, with `status` and `body`.
+    pub fn response(&self, status: u32, body: Vec<u8>) -> Self {
+        Self {
+            command_id: self.command_id | RESPONSE,
+            command_status: status,
+            sequence: self.sequence,
+            body,
+        }
+    }
+
+    /// Returns `true` for response PDUs.
+    pub fn is_response(&self) -> bool {
+        self.command_id & RESPONSE != 0
+    }
+
+    /// Serialize the header and body.
+    pub fn encode(&self) -> Vec<u8> {
+        let len = 16 + self.body.len() as u32;
+        let mut out = Vec::with_capacity(len as usize);
+        out.extend_from_slice(&len.to_be_bytes());
+        out.extend_from_slice(&self.command_id.to_be_bytes());
+        out.extend_from_slice(&self.command_status.to_be_bytes());
+        out.extend_from_slice(&self.sequence.to_be_bytes());
+        out.extend_from_slice(&self.body);
+        out
+    }
+
+    /// Parse one complete PDU.
+    pub fn decode(buf: &[u8]) -> Result<Self, SmsError> {
+        let mut r = Reader::new(buf);
+        let len = r.u32()?;
+        if len as usize != buf.len() {
+            return Err(SmsError::Provider(format!(
+                "SMPP PDU length {} does not match {} bytes",
+                len,
+                buf.len()
+            )));
+        }
+        Ok(Self {
+            command_id: r.u32()?,
+            command_status: r.u32()?,
+            sequence: r.u32()?,
+            body: r.rest().to_vec(),
+        })
+    }
+
+    /// Read one PDU from `reader`.
+    pub async fn read<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Self, SmsError> {
+        let mut len = [0u8; 4];
+        reader.read_exact(&mut len).await.map_err(io_error)?;
+        let len = u32::from_be_bytes(len);
+        if !(16..=MAX_PDU_LEN).contains(&len) {
+            return Err(SmsError::Provider(format!(
+                "SMPP PDU length {} out of range",
+                len
+            )));
+        }
+        let mut buf = vec![0u8; len as usize];
+        buf[..4].copy_from_slice(&len.to_be_bytes());
+        reader.read_exact(&mut buf[4..]).await.map_err(io_error)?;
+        Self::decode(&buf)
+    }
+}
+
+/// Map a socket failure to [`SmsError::Http`], the crate-wide transport
+/// error.
+pub(crate) fn io_error(e: std::io::Error) -> SmsError {
+    SmsError::Http(format!("SMPP connection: {}", e))
+}
+
+/// Body of a `bind_transmitter`, `bind_receiver` or `bind_transceiver`.
+pub fn bind_body(system_id: &str, password: &str, system_type: &str) -> Vec<u8> {
+    let mut body = Vec::new();
+    put_cstr(&mut body, system_id);
+    put_cstr(&mut body, password);
+    put_cstr(&mut body, system_type);
+    body.push(INTERFACE_VERSION);
+    body.push(0); // addr_ton
+    body.push(0); // addr_npi
+    put_cstr(&mut body, ""); // address_range
+    body
+}
+
+/// A source or destination address with its type of number and numbering
+/// plan indicator.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Address {
+    /// Type of number, e.g. `1` (international) or `5` (alphanumeric).
+    pub ton: u8,
+    /// Numbering plan indicator, e.g. `1` (E.164).
+    pub npi: u8,
+    /// The address itself, without a leading `+`.
+    pub addr: String,
+}
+
+impl std::fmt::Display for Address {
+    /// International numbers get their `+` back; anything else is shown
+    /// as-is.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.ton == 1 {
+            write!(f, "+{}", self.addr)
+        } else {
+            f.write_str(&self.addr)
+        }
+    }
+}
+
+impl Address {
+    /// An address with TON/NPI chosen from its shape: international E.164
+    /// for digits, alphanumeric for anything else, and unknown (letting the
+    /// SMSC fill in its default) when empty.
+    pub fn guess(addr: &str) -> Self {
+        let digits = addr.trim_start_matches('+');
+        if addr.is_empty() {
+            Self {
+                ton: 0,
+                npi: 0,
+                addr: String::new(),
+            }
+        } else if digits.bytes().all(|b| b.is_ascii_digit()) {
+            Self {
+                ton: 1,
+                npi: 1,
+                addr: digits.to_string(),
+            }
+        } else {
+            Self {
+                ton: 5,
+                npi: 0,
+                addr: addr.to_string(),
+            }
+        }
+    }
+}
+
+/// Encode `text` for the wire, returning `(data_coding, bytes)`.
+///
+/// ASCII goes out in the SMSC default alphabet, anything else as UCS-2.
+pub fn encode_text(text: &str) -> (u8, Vec<u8>) {
+    if text.is_ascii() {
+        (DATA_CODING_DEFAULT, text.as_bytes().to_vec())
+    } else {
+        let bytes = text.encode_utf16().flat_map(u16::to_be_bytes).collect();
+        (DATA_CODING_UCS2, bytes)
+    }
+}
+
+/// Decode a message body sent with `data_coding`.
+pub fn decode_text(data_coding: u8, bytes: &[u8]) -> String {
+    match data_coding {
+        DATA_CODING_UCS2 => {
+            let units: Vec<u16> = bytes
+                .chunks_exact(2)
+                .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        DATA_CODING_LATIN1 => bytes.iter().map(|&b| b as char).collect(),
+        _ => String::from_utf8_lossy(bytes).into_owned(),
+    }
+}
+
+/// A `submit_sm` request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubmitSm {
+    /// Sender.
+    pub source: Address,
+    /// Recipient.
+    pub destination: Address,
+    /// Request an SMSC delivery receipt.
+    pub registered_delivery: bool,
+    /// Message text.
+    pub text: String,
+}
+
+impl SubmitSm {
+    /// Serialize the body.  Bodies too long for `short_message` are sent in
+    /// the `message_payload` optional parameter instead; bodies too long
+    /// for its 16-bit length are rejected.
+    pub fn encode(&self) -> Result<Vec<u8>, SmsError> {
+        let (data_coding, message) = encode_text(&self.text);
+        let mut body = Vec::new();
+        put_cstr(&mut body, ""); // service_type
+        body.push(self.source.ton);
+        body.push(self.source.npi);
+        put_cstr(&mut body, &self.source.addr);
+        body.push(self.destination.ton);
+        body.push(self.destination.npi);
+        put_cstr(&mut body, &self.destination.addr);
+        body.push(0); // esm_class
+        body.push(0); // protocol_id
+        body.push(0); // priority_flag
+        put_cstr(&mut body, ""); // schedule_delivery_time
+        put_cstr(&mut body, ""); // validity_period
+        body.push(u8::from(self.registered_delivery));
+        body.push(0); // replace_if_present_flag
+        body.push(data_coding);
+        body.push(0); // sm_default_msg_id
+        if message.len() <= MAX_SHORT_MESSAGE {
+            body.push(message.len() as u8);
+            body.extend_from_slice(&message);
+        } else {
+            let len = u16::try_from(message.len()).map_err(|_| {
+                SmsError::Invalid(format!(
+                    "message body of {} bytes exceeds the SMPP message_payload limit of {}",
+                    message.len(),
+                    u16::MAX
+                ))
+            })?;
+            body.push(0);
+            body.extend_from_slice(&TLV_MESSAGE_PAYLOAD.to_be_bytes());
+            body.extend_from_slice(&len.to_be_bytes());
+            body.extend_from_slice(&message);
+        }
+        Ok(body)
+    }
+}
+
+/// The `message_id` from a `submit_sm_resp` body.
+pub fn submit_sm_resp_id(body: &[u8]) -> Result<String, SmsError> {
+    Reader::new(body).cstr()
+}
+
+/// A decoded `deliver_sm`: a mobile-originated message or a delivery
+/// receipt.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DeliverSm {
+    /// Sender (the handset for MO, the original recipient for receipts).
+    pub source: Address,
+    /// Recipient (your number for MO, the original sender for receipts).
+    pub destination: Address,
+    /// `esm_class`; see [`is_delivery_receipt`](Self::is_delivery_receipt).
+    pub esm_class: u8,
+    /// `data_coding` of the body.
+    pub data_coding: u8,
+    /// Decoded body (for receipts, the `id:... stat:...` receipt text).
+    pub text: String,
+    /// `receipted_message_id` optional parameter, if present.
+    pub receipted_message_id: Option<String>,
+    /// `message_state` optional parameter, if present.
+    pub message_state: Option<u8>,
+}
+
+impl DeliverSm {
+    /// Parse a `deliver_sm` body.
+    pub fn decode(body: &[u8]) -> Result<Self, SmsError> {
+        let mut r = Reader::new(body);
+        r.cstr()?; // service_type
+        let source = Address {
+            ton: r.u8()?,
+            npi: r.u8()?,
+            addr: r.cstr()?,
+        };
+        let destination = Address {
+            ton: r.u8()?,
+            npi: r.u8()?,
+            addr: r.cstr()?,
+        };
+        let esm_class = r.u8()?;
+        r.u8()?; // protocol_id
+        r.u8()?; // priority_flag
+        r.cstr()?; // schedule_delivery_time
+        r.cstr()?; // validity_period
+        r.u8()?; // registered_delivery
+        r.u8()?; // replace_if_present_flag
+        let data_coding = r.u8()?;
+        r.u8()?; // sm_default_msg_id
+        let sm_length = r.u8()? as usize;
+        let mut message = r.bytes(sm_length)?.to_vec();
+
+        let mut receipted_message_id = None;
+        let mut message_state = None;
+        while !r.is_empty() {
+            let tag = r.u16()?;
+            let len = r.u16()? as usize;
+            let value = r.bytes(len)?;
+            match tag {
+                TLV_MESSAGE_PAYLOAD if message.is_empty() => message = value.to_vec(),
+                TLV_RECEIPTED_MESSAGE_ID => {
+                    receipted_message_id = Some(Reader::new(value).cstr()?);
+                }
+                TLV_MESSAGE_STATE => message_state = value.first().copied(),
+                _ => {}
+            }
+        }
+
+        Ok(Self {
+            source,
+            destination,
+            esm_class,
+            data_coding,
+            text: decode_text(data_coding, &message),
+            receipted_message_id,
+            message_state,
+        })
+    }
+
+    /// Returns `true` if this is an SMSC delivery receipt rather than a
+    /// mobile-originated message.
+    pub fn is_delivery_receipt(&self) -> bool {
+        self.esm_class & 0x3C == ESM_CLASS_DELIVERY_RECEIPT
+    }
+
+    /// The ID and final status (`DELIVRD`, `UNDELIV`, ...) of the message a
+    /// receipt is about, from the optional parameters or, failing those,
+    /// the conventional `id:... stat:...` receipt text.
+    pub fn receipt(&self) -> Option<(String, String)> {
+        let field = |name: &str| {
+            self.text
+                .split_whitespace()
+                .find_map(|part| part.strip_prefix(name))
+                .map(str::to_string)
+        };
+        let id = self.receipted_message_id.clone().or_else(|| field("id:"))?;
+        let stat = field("stat:")
+            .or_else(|| self.message_state.map(message_state_name))
+            .unwrap_or_else(|| "UNKNOWN".to_string());
+        Some((id, stat))
+    }
+}
+
+/// The receipt-text name of a `message_state` value.
+fn message_state_name(state: u8) -> String {
+    match state {
+        1 => "ENROUTE",
+        2 => "DELIVRD",
+        3 => "EXPIRED",
+        4 => "DELETED",
+        5 => "UNDELIV",
+        6 => "ACCEPTD",
+        8 => "REJECTD",
+        _ => "UNKNOWN",
+    }
+    .to_string()
+}
+
+fn put_cstr(out: &mut Vec<u8>, s: &str) {
+    out.extend_from_slice(s.as_bytes());
+    out.push(0);
+}
+
+/// Cursor over a PDU body.
+struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        Self { buf, pos: 0 }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pos >= self.buf.len()
+    }
+
+    fn bytes(&mut self, n: usize) -> Result<&'a [u8], SmsError> {
+        let end = self
+            .pos
+            .checked_add(n)
+            .filter(|&end| end <= self.buf.len())
+            .ok_or_else(|| SmsError::Provider("truncated SMPP PDU".into()))?;
+        let out = &self.buf[self.pos..end];
+        self.pos = end;
+        Ok(out)
+    }
+
+    fn rest(&mut self) -> &'a [u8] {
+        let out = &self.buf[self.pos.min(self.buf.len())..];
+        self.pos = self.buf.len();
+        out
+    }
+
+    fn u8(&mut self) -> Result<u8, SmsError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, SmsError> {
+        let b = self.bytes(2)?;
+        Ok(u16::from_be_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, SmsError> {
+        let b = self.bytes(4)?;
+        Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn cstr(&mut self) -> Result<String, SmsError> {
+        let rest = &self.buf[self.pos.min(self.buf.len())..];
+        let len = rest
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or_else(|| SmsError::Provider("unterminated SMPP string".into()))?;
+        let s = String::from_utf8_lossy(&rest[..len]).into_owned();
+        self.pos += len + 1;
+        Ok(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deliver_sm_body(esm_class: u8, data_coding: u8, message: &[u8], tlvs: &[u8]) -> Vec<u8> {
+        let mut body = Vec::new();
+        put_cstr(&mut body, "");
+        body.extend_from_slice(&[1, 1]);
+        put_cstr(&mut body, "447700900000");
+        body.extend_from_slice(&[1, 1]);
+        put_cstr(&mut body, "447700900111");
+        body.extend_from_slice(&[esm_class, 0, 0]);
+        put_cstr(&mut body, "");
+        put_cstr(&mut body, "");
+        body.extend_from_slice(&[0, 0, data_coding, 0, message.len() as u8]);
+        body.extend_from_slice(message);
+        body.extend_from_slice(tlvs);
+        body
+    }
+
+    #[test]
+    fn header_round_trips() {
+        let pdu = Pdu::new(ENQUIRE_LINK, 7, vec![]);
+        let bytes = pdu.encode();
+        assert_eq!(bytes, [0, 0, 0, 16, 0, 0, 0, 0x15, 0, 0, 0, 0, 0, 0, 0, 7]);
+        assert_eq!(Pdu::decode(&bytes).unwrap(), pdu);
+
+        let resp = pdu.response(0, vec![]);
+        assert!(resp.is_response());
+        assert_eq!(resp.command_id, ENQUIRE_LINK | RESPONSE);
+        assert_eq!(resp.sequence, 7);
+        assert!(Pdu::decode(&bytes[..12]).is_err());
+    }
+
+    #[tokio::test]
+    async fn reads_pdus_from_a_stream() {
+        let mut wire = Pdu::new(UNBIND, 1, vec![]).encode();
+        wire.extend(Pdu::new(ENQUIRE_LINK, 2, vec![]).encode());
+        let mut reader = wire.as_slice();
+        assert_eq!(Pdu::read(&mut reader).await.unwrap().command_id, UNBIND);
+        assert_eq!(Pdu::read(&mut reader).await.unwrap().sequence, 2);
+        assert!(Pdu::read(&mut reader).await.is_err());
+    }
+
+    #[test]
+    fn bind_body_layout() {
+        let body = bind_body("esme", "pw", "");
+        assert_eq!(body, b"esme\0pw\0\0\x34\0\0\0");
+    }
+
+    #[test]
+    fn submit_sm_chooses_encoding_and_payload() {
+        let submit = SubmitSm {
+            source: Address::guess("MyBrand"),
+            destination: Address::guess("+447700900000"),
+            registered_delivery: true,
+            text: "hi".into(),
+        };
+        let body = submit.encode().unwrap();
+        assert!(body.starts_with(b"\0\x05\0MyBrand\0\x01\x01447700900000\0"));
+        assert!(body.ends_with(&[1, 0, DATA_CODING_DEFAULT, 0, 2, b'h', b'i']));
+
+        let (coding, bytes) = encode_text("héllo");
+        assert_eq!(coding, DATA_CODING_UCS2);
+        assert_eq!(bytes, [0, b'h', 0, 0xE9, 0, b'l', 0, b'l', 0, b'o']);
+        assert_eq!(decode_text(coding, &bytes), "héllo");
+
+        let long = SubmitSm {
+            text: "x".repeat(300),
+            ..submit.clone()
+        };
+        let body = long.encode().unwrap();
+        let payload_at = body.len() - 300 - 4;
+        assert_eq!(body[payload_at - 1], 0); // sm_length
+        assert_eq!(&body[payload_at..payload_at + 4], &[0x04, 0x24, 0x01, 0x2C]);
+
+        let oversized = SubmitSm {
+            text: "x".repeat(usize::from(u16::MAX) + 1),
+            ..submit
+        };
+        assert!(matches!(oversized.encode(), Err(SmsError::Invalid(_))));
+    }
+
+    #[test]
+    fn decodes_mobile_originated_deliver_sm() {
+        let body = deliver_sm_body(0, DATA_CODING_UCS2, &[0, b'h', 0, b'i'], &[]);
+        let sm = DeliverSm::decode(&body).unwrap();
+        assert!(!sm.is_delivery_receipt());
+        assert_eq!(sm.source.addr, "447700900000");
+        assert_eq!(sm.destination.addr, "447700900111");
+        assert_eq!(sm.text, "hi");
+        assert_eq!(sm.receipt(), None);
+    }
+
+    #[test]
+    fn decodes_delivery_receipts() {
+        let text = b"id:abc123 sub:001 dlvrd:001 submit date:2401010000 done date:2401010001 stat:DELIVRD err:000 text:hi";
+        let sm = DeliverSm::decode(&deliver_sm_body(0x04, 0, text, &[])).unwrap();
+        assert!(sm.is_delivery_receipt());
+        assert_eq!(sm.receipt(), Some(("abc123".into(), "DELIVRD".into())));
+
+        let tlvs = [
+            0x00, 0x1E, 0x00, 0x04, b'x', b'y', b'z', 0, 0x04, 0x27, 0x00, 0x01, 5,
+        ];
+        let sm = DeliverSm::decode(&deliver_sm_body(0x04, 0, b"", &tlvs)).unwrap();
+        assert_eq!(sm.receipt(), Some(("xyz".into(), "UNDELIV".into())));
+    }
+
+    #[test]
+    fn truncated_bodies_are_errors() {
+        let body = deliver_sm_body(0, 0, b"hello", &[]);
+        assert!(DeliverSm::decode(&body[..body.len() - 2]).is_err());
+        assert!(submit_sm_resp_id(b"no-terminator").is_err());
+        assert_eq!(submit_sm_resp_id(b"msg-1\0").unwrap(), "msg-1");
+    }
+}
@@ -0,0 +1,200 @@
+//! Twilio error codes.
+//!
+//! Twilio reports failures as numeric codes: `2xxxx` codes in the JSON body
+//! of a rejected API call, `30xxx` codes in the `ErrorCode` field of status
+//! callbacks for messages the carrier failed to deliver.  [`TwilioErrorCode`]
+//! names the common ones and classifies them as a [`ProviderErrorKind`], so
+//! callers can branch on the kind instead of matching error strings:
+//!
+//! ```rust,ignore
+//! match client.send(req).await {
+//!     Err(e) if e.provider_error().is_some_and(|p| p.kind == ProviderErrorKind::OptedOut) => {
+//!         suppress(req.to)
+//!     }
+//!     other => other?,
+//! }
+//! ```
+
+use serde::Deserialize;
+use sms_core::{ProviderError, ProviderErrorKind};
+
+/// A Twilio error code.  Codes smskit doesn't name are kept in `Other`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum TwilioErrorCode {
+    /// 20003: authentication failed.
+    AuthenticationFailed,
+    /// 20005: the account is not active.
+    AccountNotActive,
+    /// 20429: too many concurrent requests.
+    TooManyRequests,
+    /// 21211: the `To` number is invalid.
+    InvalidToNumber,
+    /// 21212: the `From` number is invalid.
+    InvalidFromNumber,
+    /// 21408: SMS to the destination's region is not enabled on the account.
+    RegionNotEnabled,
+    /// 21606: the `From` number cannot send SMS.
+    FromNotSmsCapable,
+    /// 21610: the recipient replied STOP.
+    UnsubscribedRecipient,
+    /// 21612: the `To` number cannot currently be reached by SMS.
+    ToNotReachable,
+    /// 21614: the `To` number is not a mobile number.
+    ToNotMobile,
+    /// 21617: the body exceeds 1600 characters.
+    BodyTooLong,
+    /// 30001: the account's send queue overflowed.
+    QueueOverflow,
+    /// 30002: the account is suspended.
+    AccountSuspended,
+    /// 30003: the handset is unreachable.
+    UnreachableHandset,
+    /// 30004: the message was blocked.
+    MessageBlocked,
+    /// 30005: the handset is unknown to the carrier.
+    UnknownHandset,
+    /// 30006: landline or unreachable carrier.
+    LandlineOrUnreachableCarrier,
+    /// 30007: the carrier filtered the message.
+    CarrierFiltered,
+    /// 30008: unknown delivery failure.
+    UnknownError,
+    /// Any other code.
+    Other(u32),
+}
+
+impl TwilioErrorCode {
+    /// The variant for a numeric code.
+    pub fn from_code(code: u32) -> Self {
+        match code {
+            20003 => Self::AuthenticationFailed,
+            20005 => Self::AccountNotActive,
+            20429 => Self::TooManyRequests,
+            21211 => Self::InvalidToNumber,
+            21212 => Self::InvalidFromNumber,
+            21408 => Self::RegionNotEnabled,
+            21606 => Self::FromNotSmsCapable,
+            21610 => Self::UnsubscribedRecipient,
+            21612 => Self::ToNotReachable,
+            21614 => Self::ToNotMobile,
+            21617 => Self::BodyTooLong,
+            30001 => Self::QueueOverflow,
+            30002 => Self::AccountSuspended,
+            30003 => Self::UnreachableHandset,
+            30004 => Self::MessageBlocked,
+            30005 => Self::UnknownHandset,
+            30006 => Self::LandlineOrUnreachableCarrier,
+            30007 => Self::CarrierFiltered,
+            30008 => Self::UnknownError,
+            other => Self::Other(other),
+        }
+    }
+
+    /// The numeric code.
+    pub fn code(self) -> u32 {
+        match self {
+            Self::AuthenticationFailed => 20003,
+            Self::AccountNotActive => 20005,
+            Self::TooManyRequests => 20429,
+            Self::InvalidToNumber => 21211,
+            Self::InvalidFromNumber => 21212,
+            Self::RegionNotEnabled => 21408,
+            Self::FromNotSmsCapable => 21606,
+            Self::UnsubscribedRecipient => 21610,
+            Self::ToNotReachable => 21612,
+            Self::ToNotMobile => 21614,
+            Self::BodyTooLong => 21617,
+            Self::QueueOverflow => 30001,
+            Self::AccountSuspended => 30002,
+            Self::UnreachableHandset => 30003,
+            Self::MessageBlocked => 30004,
+            Self::UnknownHandset => 30005,
+            Self::LandlineOrUnreachableCarrier => 30006,
+            Self::CarrierFiltered => 30007,
+            Self::UnknownError => 30008,
+            Self::Other(code) => code,
+        }
+    }
+
+    /// The provider-independent classification of this code.
+    pub fn kind(self) -> ProviderErrorKind {
+        match self {
+            Self::AuthenticationFailed => ProviderErrorKind::Unauthorized,
+            Self::AccountNotActive | Self::AccountSuspended => ProviderErrorKind::AccountSuspended,
+            Self::TooManyRequests | Self::QueueOverflow => ProviderErrorKind::Throttled,
+            Self::InvalidToNumber | Self::ToNotMobile | Self::UnknownHandset => {
+                ProviderErrorKind::InvalidDestination
+            }
+            Self::InvalidFromNumber | Self::FromNotSmsCapable => ProviderErrorKind::InvalidSender,
+            Self::RegionNotEnabled => ProviderErrorKind::RegionNotPermitted,
+            Self::UnsubscribedRecipient => ProviderErrorKind::OptedOut,
+            Self::ToNotReachable
+            | Self::UnreachableHandset
+            | Self::LandlineOrUnreachableCarrier => ProviderErrorKind::UnreachableDestination,
+            Self::BodyTooLong => ProviderErrorKind::InvalidContent,
+            Self::MessageBlocked | Self::CarrierFiltered => ProviderErrorKind::Blocked,
+            Self::UnknownError => ProviderErrorKind::ProviderFailure,
+            Self::Other(_) => ProviderErrorKind::Other,
+        }
+    }
+
+    /// A [`ProviderError`] for this code with the given message.
+    pub fn to_provider_error(self, message: impl Into<String>) -> ProviderError {
+        ProviderError::new(
+            crate::PROVIDER,
+            self.code().to_string(),
+            self.kind(),
+            message,
+        )
+    }
+}
+
+#[derive(Deserialize)]
+struct ErrorBody {
+    code: u32,
+    #[serde(default)]
+    message: String,
+}
+
+/// Parse the JSON body of a rejected Twilio API call
+/// (`{"code": 21211, "message": "...", ...}`).
+pub fn parse_error_body(body: &str) -> Option<ProviderError> {
+    let body: ErrorBody = serde_json::from_str(body).ok()?;
+    Some(TwilioErrorCode::from_code(body.code).to_provider_error(body.message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_round_trip_and_classify() {
+        for code in [20003, 21211, 21610, 30003, 30007, 99999] {
+            assert_eq!(TwilioErrorCode::from_code(code).code(), code);
+        }
+        assert_eq!(
+            TwilioErrorCode::from_code(21610).kind(),
+            ProviderErrorKind::OptedOut
+        );
+        assert_eq!(
+            TwilioErrorCode::from_code(30002).kind(),
+            ProviderErrorKind::AccountSuspended
+        );
+        assert_eq!(
+            TwilioErrorCode::from_code(99999).kind(),
+            ProviderErrorKind::Other
+        );
+    }
+
+    #[test]
+    fn parses_api_error_body() {
+        let body = r#"{"code": 21211, "message": "The 'To' number +1 is not a valid phone number.", "more_info": "https://www.twilio.com/docs/errors/21211", "status": 400}"#;
+        let err = parse_error_body(body).unwrap();
+        assert_eq!(err.provider, "twilio");
+        assert_eq!(err.code, "21211");
+        assert_eq!(err.kind, ProviderErrorKind::InvalidDestination);
+        assert!(err.message.contains("not a valid phone number"));
+        assert!(parse_error_body("<html>Bad Gateway</html>").is_none());
+    }
+}
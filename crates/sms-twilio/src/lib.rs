@@ -14,6 +14,7 @@
 //!     to: "+14155551234",
 //!     from: "+10005551234",
 //!     text: "Hello from Twilio!",
+//!     ..Default::default()
 //! }).await?;
 //! println!("Message SID: {}", response.id);
 //! ```
@@ -26,22 +27,66 @@
 //!
 //! Reads `TWILIO_ACCOUNT_SID` and `TWILIO_AUTH_TOKEN` from the environment.
 //!
+//! ## Messaging Services
+//!
+//! Send through a Messaging Service instead of a fixed number by configuring
+//! its SID on the client (leave `from` empty to let Twilio pick the sender),
+//! or per message with [`TwilioSendOptions`], which also enables scheduled
+//! sends and link shortening:
+//!
+//! ```rust,ignore
+//! let client = TwilioClient::new("ACXXXXXXXX", "your_auth_token")
+//!     .with_messaging_service_sid("MGXXXXXXXX");
+//! let opts = TwilioSendOptions::new()
+//!     .send_at(time::OffsetDateTime::now_utc() + time::Duration::hours(2))
+//!     .shorten_urls(true);
+//! client.send_with_options(SendRequest { to: "+14155551234", from: "", text: "See https://example.com", ..Default::default() }, &opts).await?;
+//! ```
+//!
+//! ## Replying with TwiML
+//!
+//! [`twiml::MessagingResponse`] builds a `<Response><Message>` document that
+//! converts into a [`WebhookResponse`](sms_core::WebhookResponse), so webhook
+//! reply handlers can answer the sender inline.
+//!
 //! ## Webhook signature verification
 //!
 //! The [`InboundWebhook`](sms_core::InboundWebhook) implementation includes
 //! Twilio request signature verification using HMAC-SHA1.  Pass your webhook
 //! URL via [`TwilioClient::with_webhook_url`] to enable it.
+//!
+//! ## Error codes
+//!
+//! Rejected sends carrying a Twilio error code fail with
+//! [`SmsError::Rejected`]; see [`errors::TwilioErrorCode`] for the mapping.
+
+pub mod errors;
+pub mod twiml;
+
+pub use errors::TwilioErrorCode;
 
-use async_trait::async_trait;
 use hmac::{Hmac, Mac};
 use serde::{Deserialize, Serialize};
 use sha1::Sha1;
 use sms_core::{
-    Headers, InboundMessage, InboundWebhook, SendRequest, SendResponse, SmsClient, SmsError,
+    Channel, ExposeSecret, Headers, HttpClientConfig, HttpLogger, IdGenerator, InboundDirection,
+    InboundMessage, InboundWebhook, MediaRef, ProxyConfig, SecretString, SendRequest, SendResponse,
+    SmsClient, SmsError, UuidV4,
 };
+use sms_core::http_provider::{client_with, transport_error};
+use std::sync::Arc;
+use std::time::Duration;
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
 
 const PROVIDER: &str = "twilio";
 
+/// Longest message body Twilio accepts, in characters.
+const MAX_TEXT_LEN: usize = 1600;
+
+/// Default deadline for a single API call.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
 type HmacSha1 = Hmac<Sha1>;
 
 /// Twilio REST API client.
@@ -57,21 +102,52 @@ type HmacSha1 = Hmac<Sha1>;
 /// | [`TwilioClient::from_env`] | Read `TWILIO_ACCOUNT_SID` / `TWILIO_AUTH_TOKEN` from env |
 /// | [`TwilioClient::with_base_url`] | Override the API base URL (for testing) |
 /// | [`TwilioClient::with_webhook_url`] | Set the webhook URL for signature verification |
-#[derive(Clone, Debug)]
+/// | [`TwilioClient::with_previous_auth_token`] | Keep accepting webhooks signed with a rotated-out token |
+/// | [`TwilioClient::with_messaging_service_sid`] | Send through a Messaging Service by default |
+/// | [`TwilioClient::with_timeout`] | Change the default per-call deadline |
+/// | [`TwilioClient::with_id_generator`] | Choose how fallback message IDs are generated |
+#[derive(Clone)]
 pub struct TwilioClient {
     /// Twilio Account SID.
     pub account_sid: String,
     /// Twilio Auth Token (used for Basic auth and signature verification).
-    pub auth_token: String,
+    /// Zeroized on drop.
+    pub auth_token: SecretString,
+    /// Retired Auth Tokens still accepted when verifying webhook signatures,
+    /// so callbacks signed just before a rotation aren't rejected.  Never
+    /// used for API calls.
+    pub previous_auth_tokens: Vec<SecretString>,
     /// API base URL; override with [`with_base_url`](TwilioClient::with_base_url)
     /// for testing.
     pub base_url: String,
     /// Webhook URL used for signature verification. If `None`, signature
     /// verification is skipped.
     pub webhook_url: Option<String>,
+    /// Default Messaging Service SID (starts with `MG`).  When set, sends go
+    /// through the service and `from` may be left empty.
+    pub messaging_service_sid: Option<String>,
+    /// Deadline for each API call unless the request sets its own
+    /// [`SendRequest::timeout`].
+    pub timeout: Duration,
+    id_generator: Arc<dyn IdGenerator>,
+    http_log: Option<HttpLogger>,
     http: reqwest::Client,
 }
 
+impl std::fmt::Debug for TwilioClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TwilioClient")
+            .field("account_sid", &self.account_sid)
+            .field("auth_token", &"[REDACTED]")
+            .field("previous_auth_tokens", &self.previous_auth_tokens.len())
+            .field("base_url", &self.base_url)
+            .field("webhook_url", &self.webhook_url)
+            .field("messaging_service_sid", &self.messaging_service_sid)
+            .field("timeout", &self.timeout)
+            .finish_non_exhaustive()
+    }
+}
+
 impl TwilioClient {
     /// Create a new client with explicit credentials.
     ///
@@ -84,9 +160,14 @@ impl TwilioClient {
     pub fn new(account_sid: impl Into<String>, auth_token: impl Into<String>) -> Self {
         Self {
             account_sid: account_sid.into(),
-            auth_token: auth_token.into(),
+            auth_token: SecretString::from(auth_token.into()),
+            previous_auth_tokens: Vec::new(),
             base_url: "https://api.twilio.com".to_string(),
             webhook_url: None,
+            messaging_service_sid: None,
+            timeout: DEFAULT_TIMEOUT,
+            id_generator: Arc::new(UuidV4),
+            http_log: None,
             http: reqwest::Client::new(),
         }
     }
@@ -97,14 +178,19 @@ impl TwilioClient {
     /// |-----------------------|----------------|
     /// | `TWILIO_ACCOUNT_SID`  | `account_sid`  |
     /// | `TWILIO_AUTH_TOKEN`   | `auth_token`   |
+    /// | `TWILIO_PROXY`        | proxy URL (optional, falls back to `SMSKIT_PROXY`) |
     ///
-    /// Returns [`SmsError::Auth`] if either variable is missing.
+    /// Returns [`SmsError::Auth`] if either credential is missing.
     pub fn from_env() -> Result<Self, SmsError> {
         let account_sid = std::env::var("TWILIO_ACCOUNT_SID")
             .map_err(|_| SmsError::Auth("TWILIO_ACCOUNT_SID not set".into()))?;
         let auth_token = std::env::var("TWILIO_AUTH_TOKEN")
             .map_err(|_| SmsError::Auth("TWILIO_AUTH_TOKEN not set".into()))?;
-        Ok(Self::new(account_sid, auth_token))
+        let client = Self::new(account_sid, auth_token);
+        match ProxyConfig::from_env("TWILIO") {
+            Some(proxy) => client.with_http_config(HttpClientConfig::new().with_proxy(proxy)),
+            None => Ok(client),
+        }
     }
 
     /// Create a client with a custom API base URL.
@@ -126,10 +212,63 @@ impl TwilioClient {
         self
     }
 
+    /// Also accept webhook signatures made with `token`, a previous Auth
+    /// Token.
+    ///
+    /// Twilio lets a secondary token stay valid while the primary is
+    /// rotated; configure the new token as `auth_token` and the old one here
+    /// until every in-flight callback has arrived, then drop it.
+    pub fn with_previous_auth_token(mut self, token: impl Into<String>) -> Self {
+        self.previous_auth_tokens
+            .push(SecretString::from(token.into()));
+        self
+    }
+
+    /// Send through the given Messaging Service unless a message overrides
+    /// it with [`TwilioSendOptions::messaging_service_sid`].
+    pub fn with_messaging_service_sid(mut self, sid: impl Into<String>) -> Self {
+        self.messaging_service_sid = Some(sid.into());
+        self
+    }
+
+    /// Set the default deadline for each API call (default: 30 seconds).
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Rebuild the HTTP client with custom transport settings (proxy, extra
+    /// root certificates, etc.).
+    ///
+    /// Returns [`SmsError::Invalid`] if the settings are rejected, e.g. a
+    /// malformed proxy URL or certificate.
+    pub fn with_http_config(mut self, config: HttpClientConfig) -> Result<Self, SmsError> {
+        self.http = client_with(&config)?;
+        Ok(self)
+    }
+
+    /// Generate IDs with `ids` when Twilio's response doesn't include one
+    /// (default: [`UuidV4`]).
+    pub fn with_id_generator(mut self, ids: impl IdGenerator + 'static) -> Self {
+        self.id_generator = Arc::new(ids);
+        self
+    }
+
+    /// Report every API call, with redacted bodies, to `logger`.
+    pub fn with_http_logger(mut self, logger: HttpLogger) -> Self {
+        self.http_log = Some(logger);
+        self
+    }
+
     /// Compute the expected Twilio signature for a given URL and POST params.
     ///
     /// Algorithm: HMAC-SHA1(auth_token, url + sorted(key=value pairs)), base64-encoded.
+    #[cfg(test)]
     fn compute_signature(&self, url: &str, params: &[(String, String)]) -> String {
+        Self::sign(self.auth_token.expose_secret(), url, params)
+    }
+
+    fn sign(token: &str, url: &str, params: &[(String, String)]) -> String {
         let mut data = url.to_string();
         let mut sorted_params = params.to_vec();
         sorted_params.sort_by(|a, b| a.0.cmp(&b.0));
@@ -139,7 +278,7 @@ impl TwilioClient {
         }
 
         let mut mac =
-            HmacSha1::new_from_slice(self.auth_token.as_bytes()).expect("HMAC accepts any key size");
+            HmacSha1::new_from_slice(token.as_bytes()).expect("HMAC accepts any key size");
         mac.update(data.as_bytes());
         let result = mac.finalize();
         use base64::Engine;
@@ -147,51 +286,183 @@ impl TwilioClient {
     }
 }
 
+/// Twilio-specific options for [`TwilioClient::send_with_options`].
+///
+/// Scheduling and link shortening are Messaging Service features, so they
+/// require a Messaging Service SID here or on the client.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TwilioSendOptions {
+    /// Messaging Service to send through, overriding the client default.
+    pub messaging_service_sid: Option<String>,
+    /// Schedule the message for this time (15 minutes to 35 days ahead).
+    pub send_at: Option<OffsetDateTime>,
+    /// Replace links in the body with shortened, click-tracked links.
+    pub shorten_urls: bool,
+}
+
+impl TwilioSendOptions {
+    /// Options with every feature off.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Send through the given Messaging Service.
+    pub fn messaging_service_sid(mut self, sid: impl Into<String>) -> Self {
+        self.messaging_service_sid = Some(sid.into());
+        self
+    }
+
+    /// Schedule the message for `at` instead of sending it now.
+    pub fn send_at(mut self, at: OffsetDateTime) -> Self {
+        self.send_at = Some(at);
+        self
+    }
+
+    /// Enable or disable link shortening.
+    pub fn shorten_urls(mut self, enabled: bool) -> Self {
+        self.shorten_urls = enabled;
+        self
+    }
+}
+
 /// Wire format for the Twilio send-message request body (form-encoded).
-#[derive(Debug, Serialize)]
+#[derive(Debug, Default, Serialize)]
 struct TwilioSendPayload<'a> {
     #[serde(rename = "To")]
     to: &'a str,
-    #[serde(rename = "From")]
+    #[serde(rename = "From", skip_serializing_if = "str::is_empty")]
     from: &'a str,
     #[serde(rename = "Body")]
     body: &'a str,
+    #[serde(rename = "MessagingServiceSid", skip_serializing_if = "Option::is_none")]
+    messaging_service_sid: Option<&'a str>,
+    #[serde(rename = "ScheduleType", skip_serializing_if = "Option::is_none")]
+    schedule_type: Option<&'static str>,
+    #[serde(rename = "SendAt", skip_serializing_if = "Option::is_none")]
+    send_at: Option<String>,
+    #[serde(rename = "ShortenUrls", skip_serializing_if = "Option::is_none")]
+    shorten_urls: Option<bool>,
 }
 
-#[async_trait]
-impl SmsClient for TwilioClient {
-    async fn send(&self, req: SendRequest<'_>) -> Result<SendResponse, SmsError> {
+impl TwilioClient {
+    /// Send with Twilio-specific [`TwilioSendOptions`].
+    ///
+    /// [`SmsClient::send`] is equivalent to calling this with default options.
+    pub async fn send_with_options(
+        &self,
+        req: SendRequest<'_>,
+        opts: &TwilioSendOptions,
+    ) -> Result<SendResponse, SmsError> {
+        let payload = self.build_payload(&req, opts)?;
+        self.post_message(&payload, req.timeout.unwrap_or(self.timeout))
+            .await
+            .map(|resp| resp.with_thread_id(&req))
+    }
+
+    fn build_payload<'a>(
+        &'a self,
+        req: &SendRequest<'a>,
+        opts: &'a TwilioSendOptions,
+    ) -> Result<TwilioSendPayload<'a>, SmsError> {
+        sms_core::validate_text(req.text, Some(MAX_TEXT_LEN))?;
+        let service = opts
+            .messaging_service_sid
+            .as_deref()
+            .or(self.messaging_service_sid.as_deref());
+
+        if service.is_none() {
+            if req.from.is_empty() {
+                return Err(SmsError::Invalid(
+                    "`from` is required without a Messaging Service SID".into(),
+                ));
+            }
+            if opts.send_at.is_some() || opts.shorten_urls {
+                return Err(SmsError::Invalid(
+                    "scheduled sends and link shortening require a Messaging Service SID".into(),
+                ));
+            }
+        }
+
+        let send_at = opts
+            .send_at
+            .map(|at| at.format(&Rfc3339))
+            .transpose()
+            .map_err(|e| SmsError::Invalid(format!("send_at: {}", e)))?;
+
+        Ok(TwilioSendPayload {
+            to: req.to,
+            from: req.from,
+            body: req.text,
+            messaging_service_sid: service,
+            schedule_type: send_at.as_ref().map(|_| "fixed"),
+            send_at,
+            shorten_urls: opts.shorten_urls.then_some(true),
+        })
+    }
+
+    async fn post_message(
+        &self,
+        payload: &TwilioSendPayload<'_>,
+        timeout: Duration,
+    ) -> Result<SendResponse, SmsError> {
         let url = format!(
             "{}/2010-04-01/Accounts/{}/Messages.json",
             self.base_url.trim_end_matches('/'),
             self.account_sid
         );
 
-        let payload = TwilioSendPayload {
-            to: req.to,
-            from: req.from,
-            body: req.text,
-        };
-
-        let res = self
+        let pending = self.http_log.as_ref().map(|log| {
+            let body = serde_urlencoded::to_string(payload).ok();
+            log.start(PROVIDER, "POST", &url, body.as_deref())
+        });
+        let res = match self
             .http
             .post(&url)
-            .basic_auth(&self.account_sid, Some(&self.auth_token))
-            .form(&payload)
+            .basic_auth(&self.account_sid, Some(self.auth_token.expose_secret()))
+            .timeout(timeout)
+            .form(payload)
             .send()
             .await
-            .map_err(|e| SmsError::Http(e.to_string()))?;
+        {
+            Ok(res) => res,
+            Err(e) => {
+                let err = transport_error(e);
+                if let Some(pending) = pending {
+                    pending.fail(&err);
+                }
+                return Err(err);
+            }
+        };
 
-        if !res.status().is_success() {
-            let status = res.status();
-            let body = res.text().await.unwrap_or_default();
-            return Err(SmsError::Provider(format!("HTTP {}: {}", status, body)));
+        let status = res.status();
+        let retry_after = res
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(sms_core::parse_retry_after);
+        let provider_request_id = res
+            .headers()
+            .get("Twilio-Request-Id")
+            .and_then(|v| v.to_str().ok())
+            .map(String::from);
+        let text = res.text().await;
+        if let Some(pending) = pending {
+            pending.finish(status.as_u16(), text.as_deref().unwrap_or_default());
         }
 
-        let raw_text = res
-            .text()
-            .await
-            .map_err(|e| SmsError::Http(e.to_string()))?;
+        if !status.is_success() {
+            if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                return Err(SmsError::RateLimited { retry_after });
+            }
+            let body = text.unwrap_or_default();
+            return Err(match errors::parse_error_body(&body) {
+                Some(e) => SmsError::Rejected(e),
+                None => SmsError::Provider(format!("HTTP {}: {}", status, body)),
+            });
+        }
+
+        let http_status = status.as_u16();
+        let raw_text = text.map_err(transport_error)?;
         let raw_json: serde_json::Value = serde_json::from_str(&raw_text)
             .unwrap_or_else(|_| serde_json::json!({ "raw": raw_text }));
 
@@ -199,18 +470,121 @@ impl SmsClient for TwilioClient {
             .get("sid")
             .and_then(|v| v.as_str())
             .map(|s| s.to_string())
-            .unwrap_or_else(sms_core::fallback_id);
+            .unwrap_or_else(|| self.id_generator.generate());
 
         Ok(SendResponse {
             id,
             provider: PROVIDER,
+            channel: Channel::Sms,
+            http_status: Some(http_status),
+            provider_request_id,
+            thread_id: None,
             raw: raw_json,
         })
     }
 }
 
+impl SmsClient for TwilioClient {
+    async fn send(&self, req: SendRequest<'_>) -> Result<SendResponse, SmsError> {
+        self.send_with_options(req, &TwilioSendOptions::default()).await
+    }
+
+    fn max_text_len(&self) -> Option<usize> {
+        Some(MAX_TEXT_LEN)
+    }
+}
+
+#[async_trait::async_trait]
+impl sms_core::BalanceSource for TwilioClient {
+    async fn balance(&self) -> Result<sms_core::Balance, SmsError> {
+        let url = format!(
+            "{}/2010-04-01/Accounts/{}/Balance.json",
+            self.base_url.trim_end_matches('/'),
+            self.account_sid
+        );
+        let res = self
+            .http
+            .get(&url)
+            .basic_auth(&self.account_sid, Some(self.auth_token.expose_secret()))
+            .timeout(self.timeout)
+            .send()
+            .await
+            .map_err(transport_error)?;
+        let status = res.status();
+        let body = res.text().await.map_err(transport_error)?;
+        if !status.is_success() {
+            return Err(match errors::parse_error_body(&body) {
+                Some(e) => SmsError::Rejected(e),
+                None => SmsError::Provider(format!("HTTP {}: {}", status, body)),
+            });
+        }
+        parse_balance(&body)
+    }
+}
+
+/// Read a `Balance.json` response: `{"balance": "12.34", "currency": "USD"}`.
+fn parse_balance(body: &str) -> Result<sms_core::Balance, SmsError> {
+    #[derive(Deserialize)]
+    struct TwilioBalance {
+        balance: String,
+        currency: String,
+    }
+    let parsed: TwilioBalance = serde_json::from_str(body)
+        .map_err(|e| SmsError::Provider(format!("balance response: {}", e)))?;
+    let amount = parsed
+        .balance
+        .parse()
+        .map_err(|e| SmsError::Provider(format!("balance {:?}: {}", parsed.balance, e)))?;
+    Ok(sms_core::Balance {
+        amount,
+        currency: parsed.currency,
+    })
+}
+
+/// Typed view of the JSON Twilio returns from a send, available from
+/// [`SendResponse::raw`] via [`TwilioSendDetails::from_response`].
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct TwilioSendDetails {
+    /// Message SID.
+    pub sid: Option<String>,
+    /// Message status, e.g. `"queued"` or `"scheduled"`.
+    pub status: Option<String>,
+    /// Number of segments the body was split into.
+    pub num_segments: Option<String>,
+    /// Price of the message, once known.
+    pub price: Option<String>,
+    /// Currency of [`price`](Self::price).
+    pub price_unit: Option<String>,
+    /// Twilio error code, if the message failed.
+    pub error_code: Option<i64>,
+    /// Twilio error message, if the message failed.
+    pub error_message: Option<String>,
+    /// Messaging Service used for the send.
+    pub messaging_service_sid: Option<String>,
+    /// Creation time as reported by Twilio (RFC 2822).
+    pub date_created: Option<String>,
+}
+
+impl TwilioSendDetails {
+    /// Parse the raw payload of a Twilio [`SendResponse`].
+    ///
+    /// Returns [`SmsError::Invalid`] if the response came from another
+    /// provider or does not match Twilio's format.
+    pub fn from_response(resp: &SendResponse) -> Result<Self, SmsError> {
+        if resp.provider != PROVIDER {
+            return Err(SmsError::Invalid(format!(
+                "expected a {} response, got {}",
+                PROVIDER, resp.provider
+            )));
+        }
+        serde_json::from_value(resp.raw.clone())
+            .map_err(|e| SmsError::Invalid(format!("twilio send response: {}", e)))
+    }
+}
+
 /// The form-encoded payload that Twilio POSTs to your webhook URL when an
-/// inbound SMS arrives.
+/// inbound SMS arrives, or to a message's status callback URL when its
+/// delivery status changes.
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct TwilioInbound {
     /// Twilio message SID.
@@ -222,9 +596,16 @@ pub struct TwilioInbound {
     /// Destination number (your Twilio number).
     #[serde(rename = "To")]
     pub to: String,
-    /// Message body.
-    #[serde(rename = "Body")]
-    pub body: String,
+    /// Message body; absent from status callbacks.
+    #[serde(rename = "Body", default)]
+    pub body: Option<String>,
+    /// Delivery status (`sent`, `delivered`, `undelivered`, ...), present
+    /// only on status callbacks.
+    #[serde(rename = "MessageStatus", default)]
+    pub message_status: Option<String>,
+    /// Twilio error code of a failed delivery, e.g. `30007`.
+    #[serde(rename = "ErrorCode", default)]
+    pub error_code: Option<String>,
     /// Number of media attachments.
     #[serde(rename = "NumMedia")]
     pub num_media: Option<String>,
@@ -236,18 +617,89 @@ pub struct TwilioInbound {
     pub extra: serde_json::Value,
 }
 
+impl TwilioInbound {
+    /// Recover the original Twilio payload from [`InboundMessage::raw`].
+    pub fn from_message(msg: &InboundMessage) -> Result<Self, SmsError> {
+        if msg.provider != PROVIDER {
+            return Err(SmsError::Invalid(format!(
+                "expected a {} message, got {}",
+                PROVIDER, msg.provider
+            )));
+        }
+        serde_json::from_value(msg.raw.clone())
+            .map_err(|e| SmsError::Invalid(format!("twilio inbound payload: {}", e)))
+    }
+
+    /// The `ErrorCode` of a failed-delivery status callback, if any.
+    pub fn error_code(&self) -> Option<TwilioErrorCode> {
+        let code = self.error_code.as_deref()?.parse().ok()?;
+        Some(TwilioErrorCode::from_code(code))
+    }
+
+    /// The attachments listed in `MediaUrl{N}` / `MediaContentType{N}`.
+    ///
+    /// Fetching them requires the account's credentials when HTTP auth is
+    /// enforced on media URLs.
+    pub fn media(&self) -> Vec<MediaRef> {
+        let count = self
+            .num_media
+            .as_deref()
+            .and_then(|n| n.parse::<usize>().ok())
+            .unwrap_or(0)
+            // Never trust the count beyond the fields actually present.
+            .min(self.extra.as_object().map_or(0, |o| o.len()));
+        let field = |name: String| self.extra.get(name)?.as_str().map(str::to_string);
+        (0..count)
+            .filter_map(|i| {
+                Some(MediaRef {
+                    url: field(format!("MediaUrl{}", i))?,
+                    content_type: field(format!("MediaContentType{}", i)),
+                })
+            })
+            .collect()
+    }
+}
+
 impl From<TwilioInbound> for InboundMessage {
     fn from(t: TwilioInbound) -> Self {
         let raw = serde_json::to_value(&t).unwrap_or_default();
+        let has_media = t
+            .num_media
+            .as_deref()
+            .and_then(|n| n.parse::<u32>().ok())
+            .is_some_and(|n| n > 0);
+        let channel = if t.from.starts_with("whatsapp:") {
+            Channel::WhatsApp
+        } else if has_media {
+            Channel::Mms
+        } else {
+            Channel::Sms
+        };
+        let (direction, text) = match t.message_status {
+            Some(status) => {
+                let text = match t.error_code.as_deref().filter(|c| !c.is_empty()) {
+                    Some(code) => format!("Delivery Status: {} (error {})", status, code),
+                    None => format!("Delivery Status: {}", status),
+                };
+                (InboundDirection::DeliveryReport, text)
+            }
+            None => (InboundDirection::Inbound, t.body.unwrap_or_default()),
+        };
         InboundMessage {
             id: t.message_sid.clone(),
             from: t.from,
             to: t.to,
-            text: t.body,
+            text,
             timestamp: None, // Twilio doesn't include a timestamp in inbound webhooks
             provider: PROVIDER,
+            direction,
+            channel,
+            provider_account: t.account_sid,
+            thread_id: None,
+            flag: None,
             raw,
         }
+        .with_thread_id()
     }
 }
 
@@ -284,9 +736,11 @@ impl InboundWebhook for TwilioClient {
         let params: Vec<(String, String)> = serde_urlencoded::from_bytes(body)
             .map_err(|e| SmsError::Invalid(format!("form decode for verification: {}", e)))?;
 
-        let expected = self.compute_signature(webhook_url, &params);
+        let valid = std::iter::once(&self.auth_token)
+            .chain(&self.previous_auth_tokens)
+            .any(|token| Self::sign(token.expose_secret(), webhook_url, &params) == signature);
 
-        if expected == signature {
+        if valid {
             Ok(())
         } else {
             Err(SmsError::Auth("invalid Twilio signature".into()))
@@ -294,10 +748,50 @@ impl InboundWebhook for TwilioClient {
     }
 }
 
+/// The `twilio` configuration section read by the provider plugin.
+#[derive(Deserialize)]
+struct TwilioSettings {
+    account_sid: String,
+    auth_token: String,
+    #[serde(default)]
+    previous_auth_tokens: Vec<String>,
+    webhook_url: Option<String>,
+    messaging_service_sid: Option<String>,
+    base_url: Option<String>,
+    #[serde(default)]
+    http: Option<HttpClientConfig>,
+}
+
+/// Build a [`TwilioClient`] from the `twilio` configuration section.
+fn build_plugin(settings: &serde_json::Value) -> Result<sms_core::BuiltProvider, SmsError> {
+    let s: TwilioSettings = sms_core::provider_settings("twilio", settings)?;
+    let mut client = TwilioClient::new(s.account_sid, s.auth_token);
+    for token in s.previous_auth_tokens {
+        client = client.with_previous_auth_token(token);
+    }
+    if let Some(url) = s.webhook_url {
+        client = client.with_webhook_url(url);
+    }
+    if let Some(sid) = s.messaging_service_sid {
+        client = client.with_messaging_service_sid(sid);
+    }
+    if let Some(base_url) = s.base_url {
+        client = client.with_base_url(base_url);
+    }
+    if let Some(http) = s.http {
+        client = client.with_http_config(http)?;
+    }
+    Ok(sms_core::BuiltProvider::both(client))
+}
+
+sms_core::register_provider!("twilio", build_plugin);
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
     use serde_json::json;
+    use sms_core::strategies::*;
 
     // -- Construction tests --
 
@@ -305,11 +799,20 @@ mod tests {
     fn new_sets_production_base_url() {
         let client = TwilioClient::new("AC123", "token");
         assert_eq!(client.account_sid, "AC123");
-        assert_eq!(client.auth_token, "token");
+        assert_eq!(client.auth_token.expose_secret(), "token");
         assert_eq!(client.base_url, "https://api.twilio.com");
         assert!(client.webhook_url.is_none());
     }
 
+    #[test]
+    fn debug_redacts_auth_tokens() {
+        let client = TwilioClient::new("AC123", "hunter2").with_previous_auth_token("letmein");
+        let debug = format!("{:?}", client);
+        assert!(debug.contains("AC123"));
+        assert!(!debug.contains("hunter2"));
+        assert!(!debug.contains("letmein"));
+    }
+
     #[test]
     fn with_base_url_overrides() {
         let client = TwilioClient::new("AC123", "token")
@@ -348,7 +851,7 @@ mod tests {
         unsafe { std::env::set_var("TWILIO_AUTH_TOKEN", "test-token"); }
         let client = TwilioClient::from_env().unwrap();
         assert_eq!(client.account_sid, "AC-test");
-        assert_eq!(client.auth_token, "test-token");
+        assert_eq!(client.auth_token.expose_secret(), "test-token");
 
         // cleanup
         unsafe {
@@ -365,6 +868,7 @@ mod tests {
             to: "+14155551234",
             from: "+10005551234",
             body: "Hello!",
+            ..Default::default()
         };
         let encoded = serde_urlencoded::to_string(&payload).unwrap();
         assert!(encoded.contains("To=%2B14155551234"));
@@ -372,6 +876,114 @@ mod tests {
         assert!(encoded.contains("Body=Hello%21"));
     }
 
+    // -- Messaging Service options --
+
+    fn req<'a>(from: &'a str) -> SendRequest<'a> {
+        SendRequest {
+            to: "+14155551234",
+            from,
+            text: "Hi",
+            channel: Channel::Sms,
+            timeout: None,
+        }
+    }
+
+    #[test]
+    fn messaging_service_replaces_from() {
+        let client = TwilioClient::new("AC123", "token").with_messaging_service_sid("MG123");
+        let opts = TwilioSendOptions::new();
+        let payload = client.build_payload(&req(""), &opts).unwrap();
+        let encoded = serde_urlencoded::to_string(&payload).unwrap();
+        assert!(encoded.contains("MessagingServiceSid=MG123"));
+        assert!(!encoded.contains("From="));
+    }
+
+    #[test]
+    fn per_message_service_overrides_client_default() {
+        let client = TwilioClient::new("AC123", "token").with_messaging_service_sid("MG123");
+        let opts = TwilioSendOptions::new().messaging_service_sid("MG999");
+        let payload = client.build_payload(&req(""), &opts).unwrap();
+        assert_eq!(payload.messaging_service_sid, Some("MG999"));
+    }
+
+    #[test]
+    fn scheduled_send_with_short_links() {
+        let client = TwilioClient::new("AC123", "token");
+        let at = OffsetDateTime::from_unix_timestamp(1_704_067_200).unwrap();
+        let opts = TwilioSendOptions::new()
+            .messaging_service_sid("MG123")
+            .send_at(at)
+            .shorten_urls(true);
+        let payload = client.build_payload(&req(""), &opts).unwrap();
+        let encoded = serde_urlencoded::to_string(&payload).unwrap();
+        assert!(encoded.contains("ScheduleType=fixed"));
+        assert!(encoded.contains("SendAt=2024-01-01T00%3A00%3A00Z"));
+        assert!(encoded.contains("ShortenUrls=true"));
+    }
+
+    #[test]
+    fn service_features_require_service_sid() {
+        let client = TwilioClient::new("AC123", "token");
+        let opts = TwilioSendOptions::new().shorten_urls(true);
+        assert!(client.build_payload(&req("+1000"), &opts).is_err());
+        assert!(client.build_payload(&req(""), &TwilioSendOptions::new()).is_err());
+        assert!(client.build_payload(&req("+1000"), &TwilioSendOptions::new()).is_ok());
+    }
+
+    #[test]
+    fn rejects_invalid_text_before_sending() {
+        let client = TwilioClient::new("AC123", "token");
+        let long = "a".repeat(MAX_TEXT_LEN + 1);
+        let options = TwilioSendOptions::new();
+        for text in ["", "nul\0byte", long.as_str()] {
+            let request = SendRequest {
+                text,
+                ..req("+1000")
+            };
+            let err = client.build_payload(&request, &options);
+            assert!(matches!(err, Err(SmsError::Invalid(_))), "{:?}", text);
+        }
+    }
+
+    // -- Typed views --
+
+    #[test]
+    fn send_details_from_response() {
+        let resp = SendResponse {
+            id: "SM1".into(),
+            provider: "twilio",
+            channel: Channel::Sms,
+            http_status: None,
+            provider_request_id: None,
+            thread_id: None,
+            raw: json!({
+                "sid": "SM1",
+                "status": "queued",
+                "num_segments": "2",
+                "price": null,
+                "error_code": null,
+                "messaging_service_sid": "MG1"
+            }),
+        };
+        let details = TwilioSendDetails::from_response(&resp).unwrap();
+        assert_eq!(details.status.as_deref(), Some("queued"));
+        assert_eq!(details.num_segments.as_deref(), Some("2"));
+        assert_eq!(details.messaging_service_sid.as_deref(), Some("MG1"));
+        assert!(details.price.is_none());
+    }
+
+    #[test]
+    fn inbound_view_rejects_other_providers() {
+        let client = TwilioClient::new("AC123", "token");
+        let body = b"MessageSid=SM1&From=%2B1&To=%2B2&Body=hi&NumMedia=1";
+        let mut msg = client.parse_inbound(&vec![], body).unwrap();
+        let inbound = TwilioInbound::from_message(&msg).unwrap();
+        assert_eq!(inbound.num_media.as_deref(), Some("1"));
+
+        msg.provider = "plivo";
+        assert!(TwilioInbound::from_message(&msg).is_err());
+    }
+
     // -- Send response ID extraction --
 
     #[test]
@@ -404,7 +1016,9 @@ mod tests {
             message_sid: Some("SM123".into()),
             from: "+15550001111".into(),
             to: "+15550002222".into(),
-            body: "Hello".into(),
+            body: Some("Hello".into()),
+            message_status: None,
+            error_code: None,
             num_media: Some("0".into()),
             account_sid: Some("AC123".into()),
             extra: json!({}),
@@ -424,7 +1038,9 @@ mod tests {
             message_sid: None,
             from: "+1".into(),
             to: "+2".into(),
-            body: "hi".into(),
+            body: Some("hi".into()),
+            message_status: None,
+            error_code: None,
             num_media: None,
             account_sid: None,
             extra: json!({}),
@@ -434,6 +1050,38 @@ mod tests {
         assert_eq!(msg.provider, "twilio");
     }
 
+    #[test]
+    fn status_callback_error_code() {
+        let client = TwilioClient::new("AC123", "token");
+        // A real status callback: no `Body`, just the status fields.
+        let body = b"SmsSid=SM1&SmsStatus=undelivered&MessageStatus=undelivered\
+            &To=%2B15558675310&MessageSid=SM1&AccountSid=AC123&From=%2B15017250604\
+            &ApiVersion=2010-04-01&ErrorCode=30007";
+        let msg = client.parse_inbound(&vec![], body).unwrap();
+        assert_eq!(msg.direction, InboundDirection::DeliveryReport);
+        assert_eq!(msg.id.as_deref(), Some("SM1"));
+        assert_eq!(msg.to, "+15558675310");
+        assert_eq!(msg.text, "Delivery Status: undelivered (error 30007)");
+        let inbound = TwilioInbound::from_message(&msg).unwrap();
+        assert_eq!(inbound.body, None);
+        assert_eq!(inbound.message_status.as_deref(), Some("undelivered"));
+        assert_eq!(inbound.error_code(), Some(TwilioErrorCode::CarrierFiltered));
+
+        let delivered = b"MessageSid=SM2&MessageStatus=delivered&From=%2B1&To=%2B2";
+        let msg = client.parse_inbound(&vec![], delivered).unwrap();
+        assert_eq!(msg.direction, InboundDirection::DeliveryReport);
+        assert_eq!(msg.text, "Delivery Status: delivered");
+    }
+
+    #[test]
+    fn parses_account_balance() {
+        let body = r#"{"currency":"EUR","balance":"-0.50","account_sid":"AC1"}"#;
+        let balance = parse_balance(body).unwrap();
+        assert_eq!(balance.amount, -0.5);
+        assert_eq!(balance.currency, "EUR");
+        assert!(parse_balance(r#"{"currency":"USD","balance":"n/a"}"#).is_err());
+    }
+
     // -- Webhook parse tests --
 
     #[test]
@@ -463,6 +1111,54 @@ mod tests {
         let msg = client.parse_inbound(&vec![], body).unwrap();
         assert_eq!(msg.from, "+1");
         assert_eq!(msg.text, "hi");
+        assert_eq!(msg.channel, Channel::Sms);
+        assert_eq!(msg.direction, InboundDirection::Inbound);
+    }
+
+    #[test]
+    fn parse_inbound_detects_channel_and_account() {
+        let client = TwilioClient::new("AC123", "token");
+        let body = b"From=whatsapp%3A%2B1&To=whatsapp%3A%2B2&Body=hi&AccountSid=AC123";
+        let msg = client.parse_inbound(&vec![], body).unwrap();
+        assert_eq!(msg.channel, Channel::WhatsApp);
+        assert_eq!(msg.provider_account.as_deref(), Some("AC123"));
+
+        let body = b"From=%2B1&To=%2B2&Body=&NumMedia=2";
+        let msg = client.parse_inbound(&vec![], body).unwrap();
+        assert_eq!(msg.channel, Channel::Mms);
+    }
+
+    #[test]
+    fn inbound_lists_media() {
+        let client = TwilioClient::new("AC123", "token");
+        let body = b"From=%2B1&To=%2B2&Body=&NumMedia=2\
+            &MediaUrl0=https%3A%2F%2Fapi.twilio.com%2Fm0&MediaContentType0=image%2Fjpeg\
+            &MediaUrl1=https%3A%2F%2Fapi.twilio.com%2Fm1";
+        let msg = client.parse_inbound(&Headers::new(), body).unwrap();
+        let media = TwilioInbound::from_message(&msg).unwrap().media();
+        assert_eq!(media.len(), 2);
+        assert_eq!(media[0].url, "https://api.twilio.com/m0");
+        assert_eq!(media[0].content_type.as_deref(), Some("image/jpeg"));
+        assert_eq!(media[1].content_type, None);
+    }
+
+    #[test]
+    fn http_config_accepts_proxy_and_rejects_bad_url() {
+        let proxy = ProxyConfig::new("http://proxy.internal:3128").with_no_proxy("localhost");
+        let client = TwilioClient::new("AC123", "token")
+            .with_http_config(HttpClientConfig::new().with_proxy(proxy));
+        assert!(client.is_ok());
+
+        let bad = TwilioClient::new("AC123", "token")
+            .with_http_config(HttpClientConfig::new().with_proxy(ProxyConfig::new("not a url")));
+        assert!(matches!(bad, Err(SmsError::Invalid(_))));
+    }
+
+    #[test]
+    fn http_config_rejects_malformed_certificate() {
+        let config = HttpClientConfig::new().with_root_certificate("not a certificate");
+        let result = TwilioClient::new("AC123", "token").with_http_config(config);
+        assert!(matches!(result, Err(SmsError::Invalid(_))));
     }
 
     // -- Provider trait --
@@ -473,6 +1169,24 @@ mod tests {
         assert_eq!(InboundWebhook::provider(&client), "twilio");
     }
 
+    #[test]
+    fn plugin_builds_from_settings() {
+        let plugin = sms_core::ProviderPlugin::find("twilio").unwrap();
+        let built = plugin
+            .build(&json!({
+                "account_sid": "AC123",
+                "auth_token": "token",
+                "previous_auth_tokens": ["old"],
+                "webhook_url": "https://example.com/webhooks/twilio"
+            }))
+            .unwrap();
+        let hook = built.webhook.unwrap();
+        assert_eq!(hook.provider(), "twilio");
+        // A webhook URL is configured, so unsigned payloads are rejected.
+        assert!(hook.verify(&vec![], b"From=%2B1").is_err());
+        assert!(plugin.build(&json!({ "auth_token": "token" })).is_err());
+    }
+
     // -- Signature verification --
 
     #[test]
@@ -535,6 +1249,20 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn verify_accepts_previous_auth_token() {
+        let body = b"Body=hi&From=%2B1&To=%2B2";
+        let params: Vec<(String, String)> = serde_urlencoded::from_bytes(body).unwrap();
+        let url = "https://example.com/webhook";
+        let old_sig = TwilioClient::sign("old-token", url, &params);
+        let headers = vec![("X-Twilio-Signature".to_string(), old_sig)];
+
+        let rotated = TwilioClient::new("AC123", "new-token").with_webhook_url(url);
+        assert!(rotated.verify(&headers, body).is_err());
+        let rotating = rotated.with_previous_auth_token("old-token");
+        assert!(rotating.verify(&headers, body).is_ok());
+    }
+
     // -- Serde roundtrip --
 
     #[test]
@@ -543,7 +1271,9 @@ mod tests {
             message_sid: Some("SM123".into()),
             from: "+1".into(),
             to: "+2".into(),
-            body: "msg".into(),
+            body: Some("msg".into()),
+            message_status: None,
+            error_code: None,
             num_media: Some("0".into()),
             account_sid: Some("AC123".into()),
             extra: json!({}),
@@ -564,8 +1294,63 @@ mod tests {
             to: borrowed.to,
             from: borrowed.from,
             body: borrowed.text,
+            ..Default::default()
         };
         let encoded = serde_urlencoded::to_string(&payload).unwrap();
         assert!(encoded.contains("To=%2B14155551234"));
     }
+
+    // -- Property tests --
+
+    fn signed_client() -> TwilioClient {
+        TwilioClient::new("AC123", "token").with_webhook_url("https://example.com/webhooks/twilio")
+    }
+
+    fn inbound_form() -> impl Strategy<Value = (String, String, String, Vec<u8>)> {
+        let fields = (
+            phone_number(),
+            phone_number(),
+            message_text(),
+            field_value(),
+        );
+        fields.prop_map(|(from, to, text, num_media)| {
+            let body = form_body(&[
+                ("MessageSid", "SM123"),
+                ("From", &from),
+                ("To", &to),
+                ("Body", &text),
+                ("NumMedia", &num_media),
+            ]);
+            (from, to, text, body)
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn parse_inbound_is_total(body in arbitrary_body(), headers in hostile_headers()) {
+            check_total(&signed_client(), &headers, &body)?;
+        }
+
+        #[test]
+        fn parse_inbound_round_trips((from, to, text, body) in inbound_form()) {
+            let msg = signed_client().parse_inbound(&vec![], &body).unwrap();
+            prop_assert_eq!(&msg.from, &from);
+            prop_assert_eq!(&msg.to, &to);
+            prop_assert_eq!(&msg.text, &text);
+            prop_assert_ne!(msg.channel, Channel::WhatsApp);
+            let original = TwilioInbound::from_message(&msg).unwrap();
+            prop_assert_eq!(original.body.as_deref(), Some(text.as_str()));
+            prop_assert!(original.media().is_empty());
+        }
+
+        #[test]
+        fn damaged_forms_never_panic(
+            body in inbound_form().prop_flat_map(|(.., body)| {
+                prop_oneof![truncated(body.clone()), mangled(body)]
+            }),
+            headers in hostile_headers(),
+        ) {
+            check_total(&signed_client(), &headers, &body)?;
+        }
+    }
 }
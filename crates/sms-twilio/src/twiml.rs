@@ -0,0 +1,187 @@
+//! Minimal TwiML builder for replying to inbound messages inline.
+//!
+//! Twilio treats the body of a webhook response as TwiML, so a handler can
+//! answer the sender without a separate API call:
+//!
+//! ```rust,ignore
+//! use sms_twilio::twiml::MessagingResponse;
+//!
+//! let processor = WebhookProcessor::new(registry).with_reply_handler(|msg| {
+//!     (msg.provider == "twilio")
+//!         .then(|| MessagingResponse::new().message("Thanks, we got it!").into())
+//! });
+//! ```
+
+use sms_core::WebhookResponse;
+use std::fmt;
+
+/// Content type Twilio expects for TwiML responses.
+pub const TWIML_CONTENT_TYPE: &str = "text/xml";
+
+/// A single `<Message>` verb.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Message {
+    /// Message body.
+    pub body: String,
+    /// Recipient, when replying to someone other than the sender.
+    pub to: Option<String>,
+    /// Sender, when not the number that received the message.
+    pub from: Option<String>,
+    /// Media URLs to attach (MMS).
+    pub media: Vec<String>,
+}
+
+impl Message {
+    /// A reply to the sender with the given body.
+    pub fn new(body: impl Into<String>) -> Self {
+        Self {
+            body: body.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Send the reply to `to` instead of the original sender.
+    pub fn to(mut self, to: impl Into<String>) -> Self {
+        self.to = Some(to.into());
+        self
+    }
+
+    /// Send the reply from `from`.
+    pub fn from(mut self, from: impl Into<String>) -> Self {
+        self.from = Some(from.into());
+        self
+    }
+
+    /// Attach a media URL.
+    pub fn media(mut self, url: impl Into<String>) -> Self {
+        self.media.push(url.into());
+        self
+    }
+}
+
+/// A TwiML `<Response>` document containing zero or more `<Message>`s.
+///
+/// An empty response acknowledges the webhook without replying.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MessagingResponse {
+    messages: Vec<Message>,
+}
+
+impl MessagingResponse {
+    /// An empty response.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a reply to the sender.
+    pub fn message(self, body: impl Into<String>) -> Self {
+        self.push(Message::new(body))
+    }
+
+    /// Add a fully specified [`Message`].
+    pub fn push(mut self, message: Message) -> Self {
+        self.messages.push(message);
+        self
+    }
+
+    /// Render the TwiML document.
+    pub fn to_xml(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl fmt::Display for MessagingResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(r#"<?xml version="1.0" encoding="UTF-8"?><Response>"#)?;
+        for m in &self.messages {
+            f.write_str("<Message")?;
+            if let Some(to) = &m.to {
+                write!(f, r#" to="{}""#, escape(to))?;
+            }
+            if let Some(from) = &m.from {
+                write!(f, r#" from="{}""#, escape(from))?;
+            }
+            f.write_str(">")?;
+            if m.media.is_empty() {
+                f.write_str(&escape(&m.body))?;
+            } else {
+                write!(f, "<Body>{}</Body>", escape(&m.body))?;
+                for url in &m.media {
+                    write!(f, "<Media>{}</Media>", escape(url))?;
+                }
+            }
+            f.write_str("</Message>")?;
+        }
+        f.write_str("</Response>")
+    }
+}
+
+impl From<MessagingResponse> for WebhookResponse {
+    fn from(response: MessagingResponse) -> Self {
+        WebhookResponse::ack(TWIML_CONTENT_TYPE, response.to_xml())
+    }
+}
+
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROLOG: &str = r#"<?xml version="1.0" encoding="UTF-8"?>"#;
+
+    #[test]
+    fn empty_response() {
+        assert_eq!(
+            MessagingResponse::new().to_xml(),
+            format!("{}<Response></Response>", PROLOG)
+        );
+    }
+
+    #[test]
+    fn simple_reply_is_escaped() {
+        let xml = MessagingResponse::new().message("Tom & Jerry <3").to_xml();
+        assert_eq!(
+            xml,
+            format!(
+                "{}<Response><Message>Tom &amp; Jerry &lt;3</Message></Response>",
+                PROLOG
+            )
+        );
+    }
+
+    #[test]
+    fn message_with_attributes_and_media() {
+        let xml = MessagingResponse::new()
+            .push(
+                Message::new("pic")
+                    .to("+15550001")
+                    .from("+15550002")
+                    .media("https://example.com/a.png"),
+            )
+            .to_xml();
+        assert!(xml.contains(r#"<Message to="+15550001" from="+15550002">"#));
+        assert!(xml.contains("<Body>pic</Body><Media>https://example.com/a.png</Media>"));
+    }
+
+    #[test]
+    fn converts_to_webhook_response() {
+        let resp: WebhookResponse = MessagingResponse::new().message("hi").into();
+        assert_eq!(resp.status.as_u16(), 200);
+        assert_eq!(resp.content_type, "text/xml");
+        assert!(resp.body.contains("<Message>hi</Message>"));
+    }
+}
@@ -1,11 +1,25 @@
-use actix_web::{web, HttpRequest, HttpResponse, Result};
+use actix_web::body::MessageBody;
+use actix_web::dev::{Payload, ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use actix_web::{web, HttpMessage, HttpRequest, HttpResponse, Result};
 use bytes::Bytes;
-use sms_core::{Headers, InboundRegistry};
+use sms_core::{Headers, HttpStatus, WebhookResponse};
 use sms_web_generic::{HeaderConverter, ResponseConverter, WebhookProcessor};
+use std::sync::Arc;
 
 #[derive(Clone)]
 pub struct AppData {
-    pub registry: InboundRegistry,
+    /// The webhook pipeline, built once and shared by every request.
+    pub processor: Arc<WebhookProcessor>,
+}
+
+impl AppData {
+    /// Serve webhooks through `processor`.
+    pub fn new(processor: WebhookProcessor) -> Self {
+        Self {
+            processor: Arc::new(processor),
+        }
+    }
 }
 
 /// Actix-web-specific header converter
@@ -38,6 +52,14 @@ impl ResponseConverter for ActixResponseConverter {
             actix_web::http::StatusCode::from_u16(response.status.as_u16())
                 .unwrap_or(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR),
         );
+        for (name, value) in response.headers {
+            if let (Ok(name), Ok(value)) = (
+                actix_web::http::header::HeaderName::from_bytes(name.as_bytes()),
+                actix_web::http::header::HeaderValue::from_str(&value),
+            ) {
+                builder.append_header((name, value));
+            }
+        }
 
         builder
             .content_type(response.content_type.as_str())
@@ -53,26 +75,215 @@ pub async fn unified_webhook(
     data: web::Data<AppData>,
 ) -> Result<HttpResponse> {
     let provider = path.into_inner();
-    let processor = WebhookProcessor::new(data.registry.clone());
+    let processor = &data.processor;
+    let generic_headers = ActixHeaderConverter::to_generic_headers(&req);
+    let response = processor.process_webhook(&provider, generic_headers, body);
+    Ok(ActixResponseConverter::from_webhook_response(response))
+}
+
+/// Multi-tenant webhook handler for Actix-web
+pub async fn tenant_webhook(
+    path: web::Path<(String, String)>,
+    req: HttpRequest,
+    body: Bytes,
+    data: web::Data<AppData>,
+) -> Result<HttpResponse> {
+    let (tenant, provider) = path.into_inner();
+    let processor = &data.processor;
     let generic_headers = ActixHeaderConverter::to_generic_headers(&req);
-    let response = processor.process_webhook(&provider, generic_headers, &body);
+    let response = processor.process_tenant_webhook(&tenant, &provider, generic_headers, body);
     Ok(ActixResponseConverter::from_webhook_response(response))
 }
 
+/// The raw request body, attached to the request extensions by
+/// [`verify_signature`] once the provider's signature check has passed.
+///
+/// Handlers that take `web::ReqData<VerifiedBody>` can only ever run behind
+/// the middleware, so they never see an unverified payload.
+#[derive(Debug, Clone)]
+pub struct VerifiedBody(pub Bytes);
+
+/// Middleware that runs the registered provider's `verify()` before the
+/// wrapped handler.
+///
+/// The provider (and tenant, on multi-tenant routes) is taken from the
+/// `{provider}` / `{tenant}` path segments and looked up in [`AppData`].
+/// Unknown providers are rejected with 404 and failed verification with 401;
+/// otherwise the body is stored as a [`VerifiedBody`] extension and the
+/// request continues with its payload intact.
+///
+/// ```rust,ignore
+/// use actix_web::middleware::from_fn;
+///
+/// App::new().app_data(web::Data::new(app_data)).service(
+///     web::resource("/webhooks/{provider}")
+///         .wrap(from_fn(verify_signature))
+///         .route(web::post().to(my_handler)),
+/// );
+/// ```
+pub async fn verify_signature(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<impl MessageBody>> {
+    let Some(data) = req.app_data::<web::Data<AppData>>().cloned() else {
+        return Ok(reject(
+            req,
+            HttpStatus::InternalServerError,
+            "webhook registry not configured",
+        ));
+    };
+
+    let provider = req
+        .match_info()
+        .get("provider")
+        .unwrap_or_default()
+        .to_string();
+    let registry = match req.match_info().get("tenant") {
+        Some(tenant) => match data.processor.tenants().get(tenant) {
+            Some(registry) => registry,
+            None => return Ok(reject(req, HttpStatus::NotFound, "unknown tenant")),
+        },
+        None => data.processor.registry(),
+    };
+    let Some(hook) = registry.get(&provider) else {
+        return Ok(reject(req, HttpStatus::NotFound, "unknown provider"));
+    };
+
+    let body = req.extract::<Bytes>().await?;
+    let headers = ActixHeaderConverter::to_generic_headers(req.request());
+    if let Err(e) = hook.verify(&headers, &body) {
+        return Ok(reject(
+            req,
+            HttpStatus::Unauthorized,
+            &format!("verification failed: {}", e),
+        ));
+    }
+
+    req.set_payload(Payload::from(body.clone()));
+    req.extensions_mut().insert(VerifiedBody(body));
+    next.call(req)
+        .await
+        .map(ServiceResponse::map_into_left_body)
+}
+
+fn reject<B>(
+    req: ServiceRequest,
+    status: HttpStatus,
+    message: &str,
+) -> ServiceResponse<actix_web::body::EitherBody<B>> {
+    let response =
+        ActixResponseConverter::from_webhook_response(WebhookResponse::error(status, message));
+    req.into_response(response).map_into_right_body()
+}
+
 /// Helper function to configure Actix routes
 pub fn configure_routes(cfg: &mut web::ServiceConfig) {
-    cfg.route("/webhooks/{provider}", web::post().to(unified_webhook));
+    cfg.route("/webhooks/{provider}", web::post().to(unified_webhook))
+        .route("/webhooks/{tenant}/{provider}", web::post().to(tenant_webhook));
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use actix_web::middleware::from_fn;
     use actix_web::{test, App};
+    use sms_core::{
+        Channel, InboundDirection, InboundMessage, InboundRegistry, InboundWebhook, SmsError,
+    };
+
+    /// Accepts only requests carrying `x-signature: ok`.
+    struct SignedProvider;
+
+    impl InboundWebhook for SignedProvider {
+        fn provider(&self) -> &'static str {
+            "signed"
+        }
+
+        fn parse_inbound(&self, _headers: &Headers, body: &[u8]) -> Result<InboundMessage, SmsError> {
+            Ok(InboundMessage {
+                id: None,
+                from: "+1111".into(),
+                to: "+2222".into(),
+                text: String::from_utf8_lossy(body).into_owned(),
+                timestamp: None,
+                provider: "signed",
+                direction: InboundDirection::Inbound,
+                channel: Channel::Sms,
+                provider_account: None,
+                thread_id: None,
+                flag: None,
+                raw: serde_json::json!({}),
+            })
+        }
+
+        fn verify(&self, headers: &Headers, _body: &[u8]) -> Result<(), SmsError> {
+            match headers.iter().find(|(k, _)| k == "x-signature") {
+                Some((_, v)) if v == "ok" => Ok(()),
+                _ => Err(SmsError::Auth("bad signature".into())),
+            }
+        }
+    }
+
+    async fn echo(body: web::ReqData<VerifiedBody>) -> HttpResponse {
+        HttpResponse::Ok().body(body.into_inner().0)
+    }
+
+    fn signed_app_data() -> web::Data<AppData> {
+        web::Data::new(AppData::new(WebhookProcessor::new(
+            InboundRegistry::new().with(Arc::new(SignedProvider)),
+        )))
+    }
+
+    #[actix_web::test]
+    async fn middleware_passes_verified_body_to_handler() {
+        let app = test::init_service(
+            App::new().app_data(signed_app_data()).service(
+                web::resource("/webhooks/{provider}")
+                    .wrap(from_fn(verify_signature))
+                    .route(web::post().to(echo)),
+            ),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/webhooks/signed")
+            .insert_header(("x-signature", "ok"))
+            .set_payload("hello")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+        assert_eq!(test::read_body(resp).await, "hello");
+    }
+
+    #[actix_web::test]
+    async fn middleware_rejects_bad_signature_and_unknown_provider() {
+        let app = test::init_service(
+            App::new().app_data(signed_app_data()).service(
+                web::resource("/webhooks/{provider}")
+                    .wrap(from_fn(verify_signature))
+                    .route(web::post().to(echo)),
+            ),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/webhooks/signed")
+            .insert_header(("x-signature", "forged"))
+            .set_payload("hello")
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 401);
+
+        let req = test::TestRequest::post()
+            .uri("/webhooks/unknown")
+            .set_payload("hello")
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 404);
+    }
 
     #[actix_web::test]
     async fn webhook_route_compiles() {
         let registry = InboundRegistry::new();
-        let app_data = AppData { registry };
+        let app_data = AppData::new(WebhookProcessor::new(registry));
 
         let _app = test::init_service(
             App::new()
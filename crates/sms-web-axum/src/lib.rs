@@ -1,15 +1,115 @@
 use axum::{
     extract::{Path, State},
     http::HeaderMap,
-    response::IntoResponse,
+    response::{
+        IntoResponse,
+        sse::{Event, KeepAlive, Sse},
+    },
 };
 use bytes::Bytes;
-use sms_core::{Headers, InboundRegistry};
+use futures_util::stream::{self, Stream};
+use sms_core::{Headers, InboundDirection, InboundMessage};
 use sms_web_generic::{HeaderConverter, ResponseConverter, WebhookProcessor};
+use std::convert::Infallible;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+/// tower-http `TraceLayer` hooks that tag webhook spans with provider,
+/// message id and outcome; add `.layer(webhook_trace_layer())` to the router.
+pub use sms_web_generic::{WebhookMakeSpan, WebhookOnResponse, webhook_trace_layer};
 
 #[derive(Clone)]
 pub struct AppState {
-    pub registry: InboundRegistry,
+    /// The webhook pipeline, built once and shared by every request.
+    pub processor: Arc<WebhookProcessor>,
+    /// Live feed of processed messages, served by [`inbound_events`].
+    pub events: Option<InboundStream>,
+}
+
+impl AppState {
+    /// Serve webhooks through `processor`, with no event stream.
+    pub fn new(processor: WebhookProcessor) -> Self {
+        Self {
+            processor: Arc::new(processor),
+            events: None,
+        }
+    }
+
+    /// Publish every processed message to `events`.
+    pub fn with_events(mut self, events: InboundStream) -> Self {
+        let publisher = events.clone();
+        let processor =
+            Arc::unwrap_or_clone(self.processor).with_listener(move |msg| publisher.publish(msg));
+        self.processor = Arc::new(processor);
+        self.events = Some(events);
+        self
+    }
+}
+
+/// Fan-out of normalized inbound messages and delivery reports to live
+/// subscribers such as dashboards.
+///
+/// Subscribers that fall more than `capacity` messages behind skip ahead and
+/// receive a `lagged` event with the number of messages they missed.
+#[derive(Debug, Clone)]
+pub struct InboundStream {
+    sender: broadcast::Sender<InboundMessage>,
+}
+
+impl Default for InboundStream {
+    fn default() -> Self {
+        Self::new(256)
+    }
+}
+
+impl InboundStream {
+    /// Create a stream that buffers up to `capacity` messages per subscriber.
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        Self { sender }
+    }
+
+    /// Send `message` to every current subscriber.
+    pub fn publish(&self, message: &InboundMessage) {
+        // No subscribers is not an error; the message is simply dropped.
+        let _ = self.sender.send(message.clone());
+    }
+
+    /// Subscribe to messages published from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<InboundMessage> {
+        self.sender.subscribe()
+    }
+
+    /// Number of connected subscribers.
+    pub fn subscriber_count(&self) -> usize {
+        self.sender.receiver_count()
+    }
+}
+
+fn sse_events(
+    receiver: broadcast::Receiver<InboundMessage>,
+) -> impl Stream<Item = Result<Event, Infallible>> {
+    stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            let event = match receiver.recv().await {
+                Ok(message) => {
+                    let kind = match message.direction {
+                        InboundDirection::Inbound => "message",
+                        InboundDirection::DeliveryReport => "delivery_report",
+                    };
+                    match Event::default().event(kind).json_data(&message) {
+                        Ok(event) => event,
+                        Err(_) => continue,
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    Event::default().event("lagged").data(missed.to_string())
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            };
+            return Some((Ok(event), receiver));
+        }
+    })
 }
 
 /// Axum-specific header converter
@@ -41,11 +141,21 @@ impl ResponseConverter for AxumResponseConverter {
         let status = axum::http::StatusCode::from_u16(response.status.as_u16())
             .unwrap_or(axum::http::StatusCode::INTERNAL_SERVER_ERROR);
 
-        // For JSON responses, just return the body as-is since it's already JSON formatted
-        match response.content_type.as_str() {
-            "application/json" => (status, response.body).into_response(),
-            _ => (status, response.body).into_response(),
+        let mut res = (
+            status,
+            [(axum::http::header::CONTENT_TYPE, response.content_type)],
+            response.body,
+        )
+            .into_response();
+        for (name, value) in response.headers {
+            if let (Ok(name), Ok(value)) = (
+                axum::http::HeaderName::from_bytes(name.as_bytes()),
+                axum::http::HeaderValue::from_str(&value),
+            ) {
+                res.headers_mut().append(name, value);
+            }
         }
+        res
     }
 }
 
@@ -56,8 +166,101 @@ pub async fn unified_webhook(
     headers: HeaderMap,
     body: Bytes,
 ) -> impl IntoResponse {
-    let processor = WebhookProcessor::new(state.registry);
+    let processor = &state.processor;
     let generic_headers = AxumHeaderConverter::to_generic_headers(&headers);
-    let response = processor.process_webhook(&provider, generic_headers, &body);
+    let response = processor.process_webhook(&provider, generic_headers, body);
     AxumResponseConverter::from_webhook_response(response)
 }
+
+/// Multi-tenant handler: POST /webhooks/:tenant/:provider
+pub async fn tenant_webhook(
+    State(state): State<AppState>,
+    Path((tenant, provider)): Path<(String, String)>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> impl IntoResponse {
+    let processor = &state.processor;
+    let generic_headers = AxumHeaderConverter::to_generic_headers(&headers);
+    let response = processor.process_tenant_webhook(&tenant, &provider, generic_headers, body);
+    AxumResponseConverter::from_webhook_response(response)
+}
+
+/// Server-sent events handler: GET /events
+///
+/// Streams every processed message as JSON, with the SSE event name
+/// `message` or `delivery_report`.  Returns 404 when the state has no
+/// [`InboundStream`].
+pub async fn inbound_events(State(state): State<AppState>) -> axum::response::Response {
+    match state.events {
+        Some(events) => Sse::new(sse_events(events.subscribe()))
+            .keep_alive(KeepAlive::default())
+            .into_response(),
+        None => axum::http::StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::StreamExt;
+    use sms_core::{Channel, InboundRegistry};
+
+    fn message(direction: InboundDirection) -> InboundMessage {
+        InboundMessage {
+            id: Some("m1".into()),
+            from: "+1111".into(),
+            to: "+2222".into(),
+            text: "hi".into(),
+            timestamp: None,
+            provider: "fake",
+            direction,
+            channel: Channel::Sms,
+            provider_account: None,
+            thread_id: None,
+            flag: None,
+            raw: serde_json::json!({}),
+        }
+    }
+
+    #[tokio::test]
+    async fn stream_delivers_published_messages() {
+        let events = InboundStream::new(8);
+        let mut receiver = events.subscribe();
+        assert_eq!(events.subscriber_count(), 1);
+
+        events.publish(&message(InboundDirection::DeliveryReport));
+        let received = receiver.recv().await.unwrap();
+        assert_eq!(received.direction, InboundDirection::DeliveryReport);
+    }
+
+    #[test]
+    fn shared_processor_publishes_events() {
+        let events = InboundStream::new(8);
+        let mut receiver = events.subscribe();
+        let state =
+            AppState::new(WebhookProcessor::new(InboundRegistry::new())).with_events(events);
+        let clone = state.clone();
+        assert!(Arc::ptr_eq(&state.processor, &clone.processor));
+
+        clone
+            .processor
+            .dispatch(&message(InboundDirection::Inbound));
+        assert_eq!(receiver.try_recv().unwrap().text, "hi");
+    }
+
+    #[tokio::test]
+    async fn sse_stream_reports_lag() {
+        let events = InboundStream::new(1);
+        let receiver = events.subscribe();
+        events.publish(&message(InboundDirection::Inbound));
+        events.publish(&message(InboundDirection::Inbound));
+
+        let mut sse = Box::pin(sse_events(receiver));
+        // The first message was overwritten; the subscriber is told so and
+        // then receives the latest one.
+        assert!(sse.next().await.is_some());
+        assert!(sse.next().await.is_some());
+        drop(events);
+        assert!(sse.next().await.is_none());
+    }
+}
@@ -0,0 +1,308 @@
+//! Inbound spam and abuse filtering.
+//!
+//! [`InboundFilter`]s run on every user message after parsing and text
+//! normalization.  A filter can let the message through, flag it — the
+//! reason is recorded in [`InboundMessage::flag`] so handlers can ignore it —
+//! or drop it, in which case it is acknowledged to the provider but never
+//! reaches listeners or reply handlers.
+//!
+//! ```rust,ignore
+//! use sms_web_generic::{KeywordFilter, LinkFilter, SenderRateFilter, WebhookProcessor};
+//!
+//! let processor = WebhookProcessor::new(registry)
+//!     .with_filter(KeywordFilter::new(["crypto giveaway"]).dropping())
+//!     .with_filter(LinkFilter::new())
+//!     .with_filter(SenderRateFilter::new(10, Duration::from_secs(60)));
+//! ```
+
+use sms_core::InboundMessage;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// What an [`InboundFilter`] decided about a message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterVerdict {
+    /// Deliver the message normally.
+    Pass,
+    /// Deliver the message with [`InboundMessage::flag`] set to the reason.
+    Flag(String),
+    /// Acknowledge the message but don't deliver it.
+    Drop(String),
+}
+
+/// Decides whether an inbound message looks like spam or abuse.
+///
+/// Filters run in registration order; the first `Drop` wins, otherwise the
+/// first `Flag` reason is recorded.  Delivery reports are not filtered.
+pub trait InboundFilter: Send + Sync {
+    /// Judge `message`.
+    fn check(&self, message: &InboundMessage) -> FilterVerdict;
+}
+
+impl<F> InboundFilter for F
+where
+    F: Fn(&InboundMessage) -> FilterVerdict + Send + Sync,
+{
+    fn check(&self, message: &InboundMessage) -> FilterVerdict {
+        self(message)
+    }
+}
+
+/// Flags (or drops) messages containing any of a list of terms,
+/// case-insensitively.
+#[derive(Debug, Clone)]
+pub struct KeywordFilter {
+    terms: Vec<String>,
+    drop: bool,
+}
+
+impl KeywordFilter {
+    /// Flag messages containing any of `terms`.
+    pub fn new<I, S>(terms: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            terms: terms.into_iter().map(|t| t.into().to_lowercase()).collect(),
+            drop: false,
+        }
+    }
+
+    /// Drop matching messages instead of flagging them.
+    pub fn dropping(mut self) -> Self {
+        self.drop = true;
+        self
+    }
+}
+
+impl InboundFilter for KeywordFilter {
+    fn check(&self, message: &InboundMessage) -> FilterVerdict {
+        let text = message.text.to_lowercase();
+        match self.terms.iter().find(|term| text.contains(term.as_str())) {
+            Some(term) => verdict(self.drop, format!("blocked term: {}", term)),
+            None => FilterVerdict::Pass,
+        }
+    }
+}
+
+/// Hosts of common URL shorteners, which [`LinkFilter`] treats as links
+/// even without a scheme.
+pub const DEFAULT_SHORTENERS: &[&str] = &[
+    "bit.ly",
+    "tinyurl.com",
+    "t.co",
+    "goo.gl",
+    "ow.ly",
+    "is.gd",
+    "cutt.ly",
+    "rb.gy",
+];
+
+/// Flags (or drops) messages containing links.
+///
+/// Matches `http://`, `https://` and `www.` URLs and bare links to the
+/// configured URL shorteners.
+#[derive(Debug, Clone)]
+pub struct LinkFilter {
+    shorteners: Vec<String>,
+    drop: bool,
+}
+
+impl Default for LinkFilter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LinkFilter {
+    /// Flag links, treating [`DEFAULT_SHORTENERS`] as links.
+    pub fn new() -> Self {
+        Self {
+            shorteners: DEFAULT_SHORTENERS.iter().map(|s| s.to_string()).collect(),
+            drop: false,
+        }
+    }
+
+    /// Treat links to `hosts` (instead of the defaults) as shortened links.
+    pub fn with_shorteners<I, S>(mut self, hosts: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.shorteners = hosts.into_iter().map(|h| h.into().to_lowercase()).collect();
+        self
+    }
+
+    /// Drop messages with links instead of flagging them.
+    pub fn dropping(mut self) -> Self {
+        self.drop = true;
+        self
+    }
+
+    fn is_link(&self, word: &str) -> bool {
+        let word = word.to_lowercase();
+        word.starts_with("http://")
+            || word.starts_with("https://")
+            || word.starts_with("www.")
+            || self.shorteners.iter().any(|host| {
+                word.strip_prefix(host.as_str())
+                    .is_some_and(|rest| rest.starts_with('/'))
+            })
+    }
+}
+
+impl InboundFilter for LinkFilter {
+    fn check(&self, message: &InboundMessage) -> FilterVerdict {
+        if message
+            .text
+            .split_whitespace()
+            .any(|word| self.is_link(word))
+        {
+            verdict(self.drop, "contains a link".into())
+        } else {
+            FilterVerdict::Pass
+        }
+    }
+}
+
+/// Flags (or drops) messages from senders exceeding a message rate.
+///
+/// Keeps a sliding window of arrival times per sender; the message that
+/// pushes a sender over `max_messages` within `window`, and every one after
+/// it until the window clears, is caught.
+#[derive(Debug)]
+pub struct SenderRateFilter {
+    max_messages: usize,
+    window: Duration,
+    drop: bool,
+    arrivals: Mutex<HashMap<String, VecDeque<Instant>>>,
+}
+
+impl SenderRateFilter {
+    /// Flag senders sending more than `max_messages` per `window`.
+    pub fn new(max_messages: usize, window: Duration) -> Self {
+        Self {
+            max_messages,
+            window,
+            drop: false,
+            arrivals: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Drop over-limit messages instead of flagging them.
+    pub fn dropping(mut self) -> Self {
+        self.drop = true;
+        self
+    }
+}
+
+impl InboundFilter for SenderRateFilter {
+    fn check(&self, message: &InboundMessage) -> FilterVerdict {
+        let now = Instant::now();
+        let mut arrivals = self.arrivals.lock().expect("rate filter poisoned");
+        // Forget idle senders so the map doesn't grow without bound.
+        arrivals.retain(|_, times| {
+            times
+                .back()
+                .is_some_and(|last| now.duration_since(*last) < self.window)
+        });
+        let times = arrivals.entry(message.from.clone()).or_default();
+        while times
+            .front()
+            .is_some_and(|first| now.duration_since(*first) >= self.window)
+        {
+            times.pop_front();
+        }
+        times.push_back(now);
+        if times.len() > self.max_messages {
+            verdict(
+                self.drop,
+                format!(
+                    "sender exceeded {} messages per {:?}",
+                    self.max_messages, self.window
+                ),
+            )
+        } else {
+            FilterVerdict::Pass
+        }
+    }
+}
+
+fn verdict(drop: bool, reason: String) -> FilterVerdict {
+    if drop {
+        FilterVerdict::Drop(reason)
+    } else {
+        FilterVerdict::Flag(reason)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sms_core::{Channel, InboundDirection};
+
+    fn message(from: &str, text: &str) -> InboundMessage {
+        InboundMessage {
+            id: None,
+            from: from.into(),
+            to: "+2".into(),
+            text: text.into(),
+            timestamp: None,
+            provider: "test",
+            direction: InboundDirection::Inbound,
+            channel: Channel::Sms,
+            provider_account: None,
+            thread_id: None,
+            flag: None,
+            raw: serde_json::Value::Null,
+        }
+    }
+
+    #[test]
+    fn keyword_filter() {
+        let filter = KeywordFilter::new(["Free Money"]);
+        assert_eq!(filter.check(&message("+1", "hello")), FilterVerdict::Pass);
+        assert!(matches!(
+            filter.check(&message("+1", "get FREE MONEY now")),
+            FilterVerdict::Flag(_)
+        ));
+        assert!(matches!(
+            filter.dropping().check(&message("+1", "free money")),
+            FilterVerdict::Drop(_)
+        ));
+    }
+
+    #[test]
+    fn link_filter() {
+        let filter = LinkFilter::new();
+        for text in ["see https://x.example", "www.example.com", "bit.ly/abc"] {
+            assert!(
+                matches!(filter.check(&message("+1", text)), FilterVerdict::Flag(_)),
+                "{}",
+                text
+            );
+        }
+        for text in ["see you at 5", "bit.lyrical", "email me at a@b.co"] {
+            assert_eq!(
+                filter.check(&message("+1", text)),
+                FilterVerdict::Pass,
+                "{}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn sender_rate_filter() {
+        let filter = SenderRateFilter::new(2, Duration::from_secs(60));
+        assert_eq!(filter.check(&message("+1", "a")), FilterVerdict::Pass);
+        assert_eq!(filter.check(&message("+1", "b")), FilterVerdict::Pass);
+        assert!(matches!(
+            filter.check(&message("+1", "c")),
+            FilterVerdict::Flag(_)
+        ));
+        assert_eq!(filter.check(&message("+9", "a")), FilterVerdict::Pass);
+    }
+}
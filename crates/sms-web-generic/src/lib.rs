@@ -9,29 +9,260 @@
 //! Framework adapters (`sms-web-axum`, `sms-web-warp`, etc.) convert their
 //! native request/response types to/from the generic types defined here
 //! using [`HeaderConverter`] and [`ResponseConverter`].
+//!
+//! Entry points take the body as a [`Bytes`], so adapters hand over the
+//! framework's own buffer rather than copying it; providers then parse
+//! straight from a borrowed slice of that buffer.
+
+pub mod filter;
+pub mod normalize;
+pub mod replay;
+pub mod trace;
+
+pub use filter::{FilterVerdict, InboundFilter, KeywordFilter, LinkFilter, SenderRateFilter};
+pub use normalize::TextNormalizer;
+pub use replay::{ReplayError, ReplayGuard};
+#[cfg(feature = "tower-http")]
+pub use trace::{WebhookMakeSpan, WebhookOnResponse, WebhookTraceLayer, webhook_trace_layer};
+
+pub use bytes::Bytes;
 
 use sms_core::{
-    Headers, HttpStatus, InboundMessage, InboundRegistry, WebhookError, WebhookResponse,
+    Headers, InboundDirection, InboundMessage, InboundRegistry, TenantRegistry, WebhookError,
+    WebhookResponse, WebhookResult,
 };
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Throttling hook consulted by [`WebhookProcessor`] before any other work.
+///
+/// Implementations decide how to key the request (typically provider plus
+/// client IP taken from the proxy headers) and return the suggested
+/// retry-after duration when the caller is over its limit.  `smskit`'s
+/// `RateLimiter` implements this trait.
+pub trait WebhookRateLimiter: Send + Sync {
+    /// Consume one request for this webhook call, or return `Err(retry_after)`
+    /// if it should be rejected.
+    fn check_webhook(&self, provider: &str, headers: &Headers) -> Result<(), Duration>;
+
+    /// Like [`check_webhook`](Self::check_webhook), for calls arriving on a
+    /// multi-tenant route.  The default ignores the tenant.
+    fn check_tenant_webhook(
+        &self,
+        tenant: &str,
+        provider: &str,
+        headers: &Headers,
+    ) -> Result<(), Duration> {
+        let _ = tenant;
+        self.check_webhook(provider, headers)
+    }
+
+    /// Like [`check_webhook`](Self::check_webhook) and
+    /// [`check_tenant_webhook`](Self::check_tenant_webhook), for adapters
+    /// that have already resolved the caller's IP, e.g. from the TCP peer
+    /// address.  The default passes it on as an `X-Forwarded-For` header.
+    fn check_client_webhook(
+        &self,
+        tenant: Option<&str>,
+        provider: &str,
+        client_ip: IpAddr,
+    ) -> Result<(), Duration> {
+        let headers = vec![("x-forwarded-for".to_string(), client_ip.to_string())];
+        match tenant {
+            Some(t) => self.check_tenant_webhook(t, provider, &headers),
+            None => self.check_webhook(provider, &headers),
+        }
+    }
+}
+
+/// Builds an inline reply (e.g. TwiML) for a successfully parsed message.
+///
+/// Returning `None` falls back to the default JSON acknowledgement.
+pub type ReplyHandler = dyn Fn(&InboundMessage) -> Option<WebhookResponse> + Send + Sync;
+
+/// Observes every successfully processed message, e.g. to feed a live
+/// event stream.
+pub type MessageListener = dyn Fn(&InboundMessage) + Send + Sync;
 
 /// Framework-agnostic webhook processor.
 ///
 /// Holds an [`InboundRegistry`] and drives the full inbound pipeline:
 ///
-/// 1. Look up the provider in the registry.
-/// 2. Verify the webhook signature (if the provider implements it).
-/// 3. Parse the raw body into an [`InboundMessage`].
-/// 4. Return a [`WebhookResponse`] that the framework adapter can convert
-///    into its native response type.
+/// 1. Look up the provider in the registry (or in the tenant's registry for
+///    multi-tenant `/webhooks/{tenant}/{provider}` routes).
+/// 2. Apply the rate limit (if a [`WebhookRateLimiter`] is configured).
+/// 3. Verify the webhook signature (if the provider implements it).
+/// 4. Parse the raw body into one or more [`InboundMessage`]s.
+/// 5. Reject stale payloads and drop duplicates (if a [`ReplayGuard`] is
+///    configured).  Duplicates are still acknowledged so the provider stops
+///    retrying.  Header timestamps and nonces apply to the whole payload;
+///    in a multi-record payload only records with a repeated message nonce
+///    are dropped.
+/// 6. Clean up message text (if a [`TextNormalizer`] is configured) and
+///    assign conversation thread IDs.
+/// 7. Run [`InboundFilter`]s, flagging or dropping suspected spam.
+/// 8. Notify any [`MessageListener`]s.
+/// 9. Return a [`WebhookResponse`] that the framework adapter can convert
+///    into its native response type — the [`ReplyHandler`]'s response if one
+///    is configured and returns `Some`, otherwise the message as JSON.
+///    Multi-record payloads are acknowledged together as
+///    `{"count": n, "messages": [...]}`.
+///
+/// [`process_webhook`](Self::process_webhook) returns only the response;
+/// [`handle_webhook`](Self::handle_webhook) returns a [`WebhookResult`] that
+/// also carries the accepted messages, verification status, error and
+/// timing.
 #[derive(Clone)]
 pub struct WebhookProcessor {
     registry: InboundRegistry,
+    tenants: TenantRegistry,
+    replay_guard: Option<Arc<ReplayGuard>>,
+    rate_limiter: Option<Arc<dyn WebhookRateLimiter>>,
+    reply_handler: Option<Arc<ReplyHandler>>,
+    listeners: Vec<Arc<MessageListener>>,
+    normalizer: Option<TextNormalizer>,
+    filters: Vec<Arc<dyn InboundFilter>>,
+    test_mode: bool,
+    problem_details: bool,
 }
 
 impl WebhookProcessor {
     /// Create a processor backed by the given provider registry.
     pub fn new(registry: InboundRegistry) -> Self {
-        Self { registry }
+        Self {
+            registry,
+            tenants: TenantRegistry::new(),
+            replay_guard: None,
+            rate_limiter: None,
+            reply_handler: None,
+            listeners: Vec::new(),
+            normalizer: None,
+            filters: Vec::new(),
+            test_mode: false,
+            problem_details: false,
+        }
+    }
+
+    /// Serve multi-tenant routes from `tenants`; see
+    /// [`process_tenant_webhook`](Self::process_tenant_webhook).
+    pub fn with_tenants(mut self, tenants: TenantRegistry) -> Self {
+        self.tenants = tenants;
+        self
+    }
+
+    /// The providers served on single-tenant routes.
+    pub fn registry(&self) -> &InboundRegistry {
+        &self.registry
+    }
+
+    /// The per-tenant registries served on multi-tenant routes.
+    pub fn tenants(&self) -> &TenantRegistry {
+        &self.tenants
+    }
+
+    /// Whether `provider` is served for `tenant` (or on single-tenant routes
+    /// when `tenant` is `None`).
+    ///
+    /// Adapters that rate-limit ahead of the processor check this first, so
+    /// calls to unknown providers never create limiter buckets.
+    pub fn has_provider(&self, tenant: Option<&str>, provider: &str) -> bool {
+        let registry = match tenant {
+            Some(t) => self.tenants.get(t),
+            None => Some(&self.registry),
+        };
+        registry.is_some_and(|r| r.get(provider).is_some())
+    }
+
+    /// Throttle webhook calls through `limiter` before they reach a provider.
+    pub fn with_rate_limiter(mut self, limiter: Arc<dyn WebhookRateLimiter>) -> Self {
+        self.rate_limiter = Some(limiter);
+        self
+    }
+
+    /// Enable timestamp-freshness and nonce checks on every verified webhook.
+    pub fn with_replay_protection(mut self, guard: ReplayGuard) -> Self {
+        self.replay_guard = Some(Arc::new(guard));
+        self
+    }
+
+    /// Reply inline to inbound messages, e.g. with a TwiML `<Message>` for
+    /// Twilio-style providers.
+    pub fn with_reply_handler<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(&InboundMessage) -> Option<WebhookResponse> + Send + Sync + 'static,
+    {
+        self.reply_handler = Some(Arc::new(handler));
+        self
+    }
+
+    /// Call `listener` with every message that passes the pipeline.
+    ///
+    /// Listeners run synchronously on the request path, so they should only
+    /// hand the message off (e.g. to a channel) rather than do real work.
+    pub fn with_listener<F>(mut self, listener: F) -> Self
+    where
+        F: Fn(&InboundMessage) + Send + Sync + 'static,
+    {
+        self.listeners.push(Arc::new(listener));
+        self
+    }
+
+    /// Report errors as RFC 7807 `application/problem+json` documents
+    /// instead of `{"error": "..."}`; see [`WebhookResponse::problem`].
+    pub fn with_problem_details(mut self, enabled: bool) -> Self {
+        self.problem_details = enabled;
+        self
+    }
+
+    /// Serve `/webhooks/{provider}/test` as a dry run of the pipeline; see
+    /// [`test_webhook`](Self::test_webhook).
+    ///
+    /// The test route shares its shape with the multi-tenant route, so while
+    /// test mode is on a provider can't be registered under the name `test`
+    /// for any tenant.  Leave it off in production.
+    pub fn with_test_mode(mut self, enabled: bool) -> Self {
+        self.test_mode = enabled;
+        self
+    }
+
+    /// Run verification and parsing for `provider` and report the outcome
+    /// without acting on it.
+    ///
+    /// The response is always 200 (404 for unknown providers/tenants) with a
+    /// JSON body of the form
+    /// `{"provider", "verified", "verification_error", "message", "parse_error"}`.
+    /// Parsing is attempted even if verification fails, and rate limiting,
+    /// replay protection, listeners and reply handlers are all skipped.
+    pub fn test_webhook(
+        &self,
+        tenant: Option<&str>,
+        provider: &str,
+        headers: Headers,
+        body: Bytes,
+    ) -> WebhookResponse {
+        let registry = match tenant {
+            Some(t) => match self.tenants.get(t) {
+                Some(registry) => registry,
+                None => return self.error_to_response(&WebhookError::TenantNotFound(t.into())),
+            },
+            None => &self.registry,
+        };
+        let Some(hook) = registry.get(provider) else {
+            return self.error_to_response(&WebhookError::ProviderNotFound(provider.into()));
+        };
+
+        let verification = hook.verify(&headers, &body);
+        let parsed = hook.parse_inbound(&headers, &body);
+        let report = serde_json::json!({
+            "provider": provider,
+            "tenant": tenant,
+            "verified": verification.is_ok(),
+            "verification_error": verification.err().map(|e| e.to_string()),
+            "message": parsed.as_ref().ok(),
+            "parse_error": parsed.as_ref().err().map(|e| e.to_string()),
+        });
+        WebhookResponse::ack("application/json", report.to_string())
     }
 
     /// Process an incoming webhook request and return a framework-agnostic response.
@@ -41,48 +272,226 @@ impl WebhookProcessor {
         &self,
         provider: &str,
         headers: Headers,
-        body: &[u8],
+        body: Bytes,
     ) -> WebhookResponse {
-        match self.process_webhook_internal(provider, headers, body) {
-            Ok(message) => WebhookResponse::success(message),
-            Err(e) => self.error_to_response(e),
+        self.handle_webhook(provider, headers, body).response
+    }
+
+    /// Like [`process_webhook`](Self::process_webhook), returning the full
+    /// [`WebhookResult`] rather than only the response.
+    pub fn handle_webhook(&self, provider: &str, headers: Headers, body: Bytes) -> WebhookResult {
+        self.handle(None, provider, headers, &body)
+    }
+
+    /// Process a webhook addressed to a specific tenant
+    /// (`/webhooks/{tenant}/{provider}`).
+    ///
+    /// The provider is looked up in that tenant's registry only, so the
+    /// tenant's own credentials are used for verification.
+    pub fn process_tenant_webhook(
+        &self,
+        tenant: &str,
+        provider: &str,
+        headers: Headers,
+        body: Bytes,
+    ) -> WebhookResponse {
+        if self.test_mode && provider == "test" {
+            // `/webhooks/{provider}/test` arrives on the tenant route.
+            return self.test_webhook(None, tenant, headers, body);
+        }
+        self.handle_tenant_webhook(tenant, provider, headers, body)
+            .response
+    }
+
+    /// Like [`process_tenant_webhook`](Self::process_tenant_webhook),
+    /// returning the full [`WebhookResult`].  The test route is not served
+    /// here.
+    pub fn handle_tenant_webhook(
+        &self,
+        tenant: &str,
+        provider: &str,
+        headers: Headers,
+        body: Bytes,
+    ) -> WebhookResult {
+        self.handle(Some(tenant), provider, headers, &body)
+    }
+
+    /// Clean up the text of every inbound message with `normalizer` before
+    /// listeners and reply handlers see it.
+    pub fn with_text_normalizer(mut self, normalizer: TextNormalizer) -> Self {
+        self.normalizer = Some(normalizer);
+        self
+    }
+
+    /// Run `filter` on every inbound user message; see [`filter`].
+    pub fn with_filter(mut self, filter: impl InboundFilter + 'static) -> Self {
+        self.filters.push(Arc::new(filter));
+        self
+    }
+
+    /// Apply the text normalizer (if any), assign a thread ID and run the
+    /// filters — the treatment every webhook message gets before it is
+    /// dispatched.  Returns `None` if a filter dropped the message.
+    pub fn prepare(&self, mut message: InboundMessage) -> Option<InboundMessage> {
+        if let Some(normalizer) = &self.normalizer {
+            normalizer.apply(&mut message);
+        }
+        if message.direction == InboundDirection::Inbound {
+            for filter in &self.filters {
+                match filter.check(&message) {
+                    FilterVerdict::Pass => {}
+                    FilterVerdict::Flag(reason) => {
+                        message.flag.get_or_insert(reason);
+                    }
+                    FilterVerdict::Drop(reason) => {
+                        tracing::debug!(
+                            "Dropped inbound {} message from {}: {}",
+                            message.provider,
+                            sms_core::redact_number(&message.from),
+                            reason
+                        );
+                        return None;
+                    }
+                }
+            }
+        }
+        Some(message.with_thread_id())
+    }
+
+    /// Hand a message that didn't arrive by webhook — e.g. one fetched by an
+    /// `InboundPoller` — to the listeners, exactly as if it had.  Pass it
+    /// through [`prepare`](Self::prepare) first for the same clean-up.
+    pub fn dispatch(&self, message: &InboundMessage) {
+        for listener in &self.listeners {
+            listener(message);
         }
     }
 
+    fn handle(
+        &self,
+        tenant: Option<&str>,
+        provider: &str,
+        headers: Headers,
+        body: &[u8],
+    ) -> WebhookResult {
+        let started = Instant::now();
+        let outcome = self.process_webhook_internal(tenant, provider, headers, body);
+        let verified = matches!(outcome, Ok(_) | Err(WebhookError::ParseError(_)));
+        let (messages, response, error) = match outcome {
+            Ok(messages) => {
+                let messages: Vec<_> = messages
+                    .into_iter()
+                    .filter_map(|m| self.prepare(m))
+                    .collect();
+                for message in &messages {
+                    self.dispatch(message);
+                }
+                let response = self.success_response(&messages);
+                (messages, response, None)
+            }
+            Err(e) => (Vec::new(), self.error_to_response(&e), Some(e)),
+        };
+        let result = WebhookResult {
+            provider: provider.to_string(),
+            tenant: tenant.map(str::to_string),
+            messages,
+            verified,
+            error,
+            elapsed: started.elapsed(),
+            response,
+        };
+        trace::record_outcome(&result);
+        result
+    }
+
+    fn success_response(&self, messages: &[InboundMessage]) -> WebhookResponse {
+        let [message] = messages else {
+            let body = serde_json::json!({ "count": messages.len(), "messages": messages });
+            return WebhookResponse::ack("application/json", body.to_string());
+        };
+        self.reply_handler
+            .as_ref()
+            .and_then(|handler| handler(message))
+            .unwrap_or_else(|| WebhookResponse::success(message.clone()))
+    }
+
     fn process_webhook_internal(
         &self,
+        tenant: Option<&str>,
         provider: &str,
         headers: Headers,
         body: &[u8],
-    ) -> Result<InboundMessage, WebhookError> {
-        let hook = self
-            .registry
+    ) -> Result<Vec<InboundMessage>, WebhookError> {
+        let registry = match tenant {
+            Some(t) => self
+                .tenants
+                .get(t)
+                .ok_or_else(|| WebhookError::TenantNotFound(t.to_string()))?,
+            None => &self.registry,
+        };
+
+        let hook = registry
             .get(provider)
             .ok_or_else(|| WebhookError::ProviderNotFound(provider.to_string()))?;
 
+        // Only known tenants and providers reach the limiter, so request
+        // paths can't mint new buckets.
+        if let Some(limiter) = &self.rate_limiter {
+            match tenant {
+                Some(t) => limiter.check_tenant_webhook(t, provider, &headers),
+                None => limiter.check_webhook(provider, &headers),
+            }
+            .map_err(WebhookError::RateLimited)?;
+        }
+
         hook.verify(&headers, body)
             .map_err(|e| WebhookError::VerificationFailed(e.to_string()))?;
 
-        hook.parse_inbound(&headers, body)
-            .map_err(|e| WebhookError::ParseError(e.to_string()))
-    }
+        let mut messages = hook
+            .parse_inbound_batch(&headers, body)
+            .map_err(|e| WebhookError::ParseError(e.to_string()))?;
 
-    fn error_to_response(&self, error: WebhookError) -> WebhookResponse {
-        match error {
-            WebhookError::ProviderNotFound(_) => {
-                WebhookResponse::error(HttpStatus::NotFound, "unknown provider")
+        if let Some(guard) = &self.replay_guard {
+            let scope = match tenant {
+                Some(t) => format!("{}/{}", t, provider),
+                None => provider.to_string(),
+            };
+            match guard.check_payload(&scope, &headers, &mut messages) {
+                Ok(()) => {}
+                Err(ReplayError::Duplicate(nonce)) => {
+                    tracing::debug!("Acknowledged duplicate {} webhook {}", provider, nonce);
+                    messages.clear();
+                }
+                Err(e) => return Err(WebhookError::VerificationFailed(e.to_string())),
             }
-            WebhookError::VerificationFailed(msg) => WebhookResponse::error(
-                HttpStatus::Unauthorized,
-                &format!("verification failed: {}", msg),
+        }
+
+        Ok(messages)
+    }
+
+    fn error_to_response(&self, error: &WebhookError) -> WebhookResponse {
+        let status = error.http_status();
+        let detail = match error {
+            WebhookError::ProviderNotFound(_) => "unknown provider".into(),
+            WebhookError::TenantNotFound(_) => "unknown tenant".into(),
+            WebhookError::VerificationFailed(msg) => format!("verification failed: {}", msg),
+            WebhookError::ParseError(msg) => format!("parse error: {}", msg),
+            WebhookError::RateLimited(retry_after) => format!(
+                "rate limit exceeded; retry after {}s",
+                retry_after.as_secs()
             ),
-            WebhookError::ParseError(msg) => {
-                WebhookResponse::error(HttpStatus::BadRequest, &format!("parse error: {}", msg))
+            WebhookError::SmsError(e) => format!("SMS error: {}", e),
+        };
+        let response = if self.problem_details {
+            WebhookResponse::problem(status, &detail)
+        } else {
+            WebhookResponse::error(status, &detail)
+        };
+        match error {
+            WebhookError::RateLimited(retry_after) => {
+                response.with_header("Retry-After", retry_after.as_secs().max(1).to_string())
             }
-            WebhookError::SmsError(e) => WebhookResponse::error(
-                HttpStatus::InternalServerError,
-                &format!("SMS error: {}", e),
-            ),
+            _ => response,
         }
     }
 }
@@ -115,7 +524,7 @@ macro_rules! implement_webhook_handler {
             processor: &WebhookProcessor,
             provider: String,
             headers: impl Into<Headers>,
-            body: &[u8],
+            body: $crate::Bytes,
         ) -> $response_type {
             let response = processor.process_webhook(&provider, headers.into(), body);
             <$response_type as ResponseConverter>::from_webhook_response(response)
@@ -126,7 +535,10 @@ macro_rules! implement_webhook_handler {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use sms_core::{InboundMessage, InboundRegistry, InboundWebhook, SmsError};
+    use sms_core::{
+        Channel, HttpStatus, InboundDirection, InboundMessage, InboundRegistry, InboundWebhook,
+        SmsError,
+    };
 
     /// A fake provider for testing the processor pipeline.
     struct FakeProvider;
@@ -146,6 +558,11 @@ mod tests {
                 text,
                 timestamp: None,
                 provider: "fake",
+                direction: InboundDirection::Inbound,
+                channel: Channel::Sms,
+                provider_account: None,
+                thread_id: None,
+                flag: None,
                 raw: serde_json::json!({}),
             })
         }
@@ -192,7 +609,7 @@ mod tests {
     #[test]
     fn unknown_provider_returns_404() {
         let processor = processor_with(vec![]);
-        let response = processor.process_webhook("unknown", vec![], b"test");
+        let response = processor.process_webhook("unknown", vec![], Bytes::from_static(b"test"));
         assert_eq!(response.status.as_u16(), 404);
         assert!(response.body.contains("unknown provider"));
     }
@@ -200,7 +617,7 @@ mod tests {
     #[test]
     fn known_provider_returns_200() {
         let processor = processor_with(vec![std::sync::Arc::new(FakeProvider)]);
-        let response = processor.process_webhook("fake", vec![], b"hello");
+        let response = processor.process_webhook("fake", vec![], Bytes::from_static(b"hello"));
         assert_eq!(response.status.as_u16(), 200);
         assert!(response.body.contains("fake-id"));
         assert!(response.body.contains("hello"));
@@ -209,7 +626,11 @@ mod tests {
     #[test]
     fn verification_failure_returns_401() {
         let processor = processor_with(vec![std::sync::Arc::new(FailVerifyProvider)]);
-        let response = processor.process_webhook("fail-verify", vec![], b"data");
+        let response = processor.process_webhook(
+            "fail-verify",
+            vec![],
+            Bytes::from_static(b"data"),
+        );
         assert_eq!(response.status.as_u16(), 401);
         assert!(response.body.contains("verification failed"));
     }
@@ -217,7 +638,7 @@ mod tests {
     #[test]
     fn parse_failure_returns_400() {
         let processor = processor_with(vec![std::sync::Arc::new(FailParseProvider)]);
-        let response = processor.process_webhook("fail-parse", vec![], b"data");
+        let response = processor.process_webhook("fail-parse", vec![], Bytes::from_static(b"data"));
         assert_eq!(response.status.as_u16(), 400);
         assert!(response.body.contains("parse error"));
     }
@@ -225,10 +646,388 @@ mod tests {
     #[test]
     fn content_type_is_json() {
         let processor = processor_with(vec![std::sync::Arc::new(FakeProvider)]);
-        let response = processor.process_webhook("fake", vec![], b"msg");
+        let response = processor.process_webhook("fake", vec![], Bytes::from_static(b"msg"));
         assert_eq!(response.content_type, "application/json");
     }
 
+    #[test]
+    fn duplicate_webhook_is_acknowledged_and_dropped() {
+        let seen = Arc::new(std::sync::Mutex::new(0));
+        let sink = seen.clone();
+        let processor = processor_with(vec![std::sync::Arc::new(FakeProvider)])
+            .with_replay_protection(
+                ReplayGuard::new(std::time::Duration::from_secs(300)).with_message_nonce(),
+            )
+            .with_listener(move |_| *sink.lock().unwrap() += 1);
+        let first = processor.process_webhook("fake", vec![], Bytes::from_static(b"hello"));
+        assert_eq!(first.status.as_u16(), 200);
+        let replay = processor.handle_webhook("fake", vec![], Bytes::from_static(b"hello"));
+        assert_eq!(replay.response.status.as_u16(), 200);
+        assert!(replay.messages.is_empty());
+        assert!(replay.error.is_none());
+        assert_eq!(*seen.lock().unwrap(), 1);
+    }
+
+    #[test]
+    fn stale_webhook_returns_401() {
+        let processor = processor_with(vec![std::sync::Arc::new(FakeProvider)])
+            .with_replay_protection(
+                ReplayGuard::new(std::time::Duration::from_secs(300))
+                    .with_timestamp_header("X-Timestamp"),
+            );
+        let headers = vec![("X-Timestamp".to_string(), "1700000000".to_string())];
+        let response = processor.process_webhook("fake", headers, Bytes::from_static(b"hello"));
+        assert_eq!(response.status.as_u16(), 401);
+        assert!(response.body.contains("old"));
+    }
+
+    /// Allows a fixed number of calls, then rejects.
+    struct CountingLimiter {
+        remaining: std::sync::Mutex<u32>,
+    }
+
+    impl WebhookRateLimiter for CountingLimiter {
+        fn check_webhook(&self, _provider: &str, _headers: &Headers) -> Result<(), Duration> {
+            let mut remaining = self.remaining.lock().unwrap();
+            if *remaining == 0 {
+                return Err(Duration::from_secs(7));
+            }
+            *remaining -= 1;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn rate_limited_webhook_returns_429() {
+        let limiter = Arc::new(CountingLimiter {
+            remaining: std::sync::Mutex::new(1),
+        });
+        let processor =
+            processor_with(vec![Arc::new(FakeProvider)]).with_rate_limiter(limiter);
+        let first = processor.process_webhook("fake", vec![], Bytes::from_static(b"a"));
+        assert_eq!(first.status.as_u16(), 200);
+        let limited = processor.process_webhook("fake", vec![], Bytes::from_static(b"b"));
+        assert_eq!(limited.status.as_u16(), 429);
+        assert!(limited.body.contains("retry after 7s"));
+        assert_eq!(limited.header("retry-after"), Some("7"));
+    }
+
+    #[test]
+    fn unknown_provider_is_not_rate_limited() {
+        let limiter = Arc::new(CountingLimiter {
+            remaining: std::sync::Mutex::new(1),
+        });
+        let processor =
+            processor_with(vec![Arc::new(FakeProvider)]).with_rate_limiter(limiter.clone());
+        assert!(!processor.has_provider(None, "nope"));
+        let missing = processor.process_webhook("nope", vec![], Bytes::from_static(b"a"));
+        assert_eq!(missing.status.as_u16(), 404);
+        assert_eq!(*limiter.remaining.lock().unwrap(), 1);
+    }
+
+    #[test]
+    fn reply_handler_overrides_success_body() {
+        let processor = processor_with(vec![Arc::new(FakeProvider)]).with_reply_handler(|msg| {
+            (msg.text == "help").then(|| WebhookResponse::ack("text/xml", "<Response/>"))
+        });
+
+        let reply = processor.process_webhook("fake", vec![], Bytes::from_static(b"help"));
+        assert_eq!(reply.status.as_u16(), 200);
+        assert_eq!(reply.content_type, "text/xml");
+        assert_eq!(reply.body, "<Response/>");
+
+        let default = processor.process_webhook("fake", vec![], Bytes::from_static(b"other"));
+        assert_eq!(default.content_type, "application/json");
+    }
+
+    #[test]
+    fn listeners_see_only_successful_messages() {
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = seen.clone();
+        let processor = processor_with(vec![Arc::new(FakeProvider), Arc::new(FailParseProvider)])
+            .with_listener(move |msg| sink.lock().unwrap().push(msg.text.clone()));
+
+        processor.process_webhook("fake", vec![], Bytes::from_static(b"one"));
+        processor.process_webhook("fail-parse", vec![], Bytes::from_static(b"two"));
+        processor.process_webhook("fake", vec![], Bytes::from_static(b"three"));
+
+        assert_eq!(*seen.lock().unwrap(), vec!["one", "three"]);
+    }
+
+    #[test]
+    fn normalizer_cleans_text_before_listeners() {
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = seen.clone();
+        let processor = processor_with(vec![Arc::new(FakeProvider)])
+            .with_text_normalizer(TextNormalizer::default())
+            .with_listener(move |msg| sink.lock().unwrap().push(msg.text.clone()));
+
+        let resp = processor.process_webhook(
+            "fake",
+            vec![],
+            Bytes::from_static(b"  Cafe\xcc\x81\r\n "),
+        );
+        assert_eq!(resp.status, HttpStatus::Ok);
+        assert_eq!(*seen.lock().unwrap(), vec!["Café"]);
+    }
+
+    #[test]
+    fn filters_flag_or_drop_before_listeners() {
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = seen.clone();
+        let processor = processor_with(vec![Arc::new(FakeProvider)])
+            .with_filter(KeywordFilter::new(["casino"]).dropping())
+            .with_filter(LinkFilter::new())
+            .with_listener(move |msg| {
+                sink.lock()
+                    .unwrap()
+                    .push((msg.text.clone(), msg.flag.is_some()))
+            });
+
+        processor.process_webhook("fake", vec![], Bytes::from_static(b"hello"));
+        processor.process_webhook(
+            "fake",
+            vec![],
+            Bytes::from_static(b"win at https://casino.example"),
+        );
+        let resp = processor.process_webhook(
+            "fake",
+            vec![],
+            Bytes::from_static(b"see https://x.example"),
+        );
+        assert_eq!(resp.status, HttpStatus::Ok);
+        assert!(resp.body.contains("contains a link"));
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![
+                ("hello".to_string(), false),
+                ("see https://x.example".to_string(), true)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_mode_reports_without_side_effects() {
+        let seen = Arc::new(std::sync::Mutex::new(0));
+        let sink = seen.clone();
+        let processor = processor_with(vec![Arc::new(FakeProvider)])
+            .with_listener(move |_| *sink.lock().unwrap() += 1)
+            .with_test_mode(true);
+
+        let response = processor.process_tenant_webhook(
+            "fake",
+            "test",
+            vec![],
+            Bytes::from_static(b"hello"),
+        );
+        assert_eq!(response.status.as_u16(), 200);
+        let report: serde_json::Value = serde_json::from_str(&response.body).unwrap();
+        assert_eq!(report["verified"], true);
+        assert_eq!(report["message"]["text"], "hello");
+        assert!(report["parse_error"].is_null());
+        assert_eq!(*seen.lock().unwrap(), 0);
+    }
+
+    /// Parses like [`FakeProvider`] but never verifies.
+    struct UnsignedProvider;
+
+    impl InboundWebhook for UnsignedProvider {
+        fn provider(&self) -> &'static str {
+            "unsigned"
+        }
+
+        fn parse_inbound(&self, headers: &Headers, body: &[u8]) -> Result<InboundMessage, SmsError> {
+            FakeProvider.parse_inbound(headers, body)
+        }
+
+        fn verify(&self, _headers: &Headers, _body: &[u8]) -> Result<(), SmsError> {
+            Err(SmsError::Auth("bad signature".into()))
+        }
+    }
+
+    #[test]
+    fn test_webhook_reports_failed_verification() {
+        let processor = processor_with(vec![Arc::new(UnsignedProvider)]);
+        let response = processor.test_webhook(None, "unsigned", vec![], Bytes::from_static(b"x"));
+        assert_eq!(response.status.as_u16(), 200);
+        let report: serde_json::Value = serde_json::from_str(&response.body).unwrap();
+        assert_eq!(report["verified"], false);
+        assert!(report["verification_error"].as_str().unwrap().contains("bad signature"));
+        assert_eq!(report["message"]["text"], "x");
+    }
+
+    #[test]
+    fn test_route_is_a_tenant_route_without_test_mode() {
+        let processor = processor_with(vec![Arc::new(FakeProvider)]);
+        let response = processor.process_tenant_webhook(
+            "fake",
+            "test",
+            vec![],
+            Bytes::from_static(b"hello"),
+        );
+        assert_eq!(response.status.as_u16(), 404);
+        assert!(response.body.contains("unknown tenant"));
+    }
+
+    /// Treats each line of the body as a separate message.
+    struct LineBatchProvider;
+
+    impl InboundWebhook for LineBatchProvider {
+        fn provider(&self) -> &'static str {
+            "lines"
+        }
+
+        fn parse_inbound(&self, headers: &Headers, body: &[u8]) -> Result<InboundMessage, SmsError> {
+            FakeProvider.parse_inbound(headers, body)
+        }
+
+        fn parse_inbound_batch(
+            &self,
+            headers: &Headers,
+            body: &[u8],
+        ) -> Result<Vec<InboundMessage>, SmsError> {
+            body.split(|b| *b == b'\n')
+                .map(|line| {
+                    let mut msg = self.parse_inbound(headers, line)?;
+                    msg.id = Some(msg.text.clone());
+                    Ok(msg)
+                })
+                .collect()
+        }
+    }
+
+    #[test]
+    fn batch_payload_is_acknowledged_in_one_response() {
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = seen.clone();
+        let processor = processor_with(vec![Arc::new(LineBatchProvider)])
+            .with_listener(move |msg| sink.lock().unwrap().push(msg.text.clone()));
+
+        let response = processor.process_webhook("lines", vec![], Bytes::from_static(b"a\nb\nc"));
+        assert_eq!(response.status.as_u16(), 200);
+        let body: serde_json::Value = serde_json::from_str(&response.body).unwrap();
+        assert_eq!(body["count"], 3);
+        assert_eq!(*seen.lock().unwrap(), vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn batch_drops_replayed_records() {
+        let processor = processor_with(vec![Arc::new(LineBatchProvider)]).with_replay_protection(
+            ReplayGuard::new(std::time::Duration::from_secs(300)).with_message_nonce(),
+        );
+
+        processor.process_webhook("lines", vec![], Bytes::from_static(b"a\nb"));
+        let response = processor.process_webhook("lines", vec![], Bytes::from_static(b"b\nc"));
+        assert_eq!(response.status.as_u16(), 200);
+        // Only "c" survives, so it is acknowledged as a single message.
+        let body: serde_json::Value = serde_json::from_str(&response.body).unwrap();
+        assert_eq!(body["text"], "c");
+    }
+
+    #[test]
+    fn batch_with_nonce_header_is_checked_as_one_payload() {
+        let processor = processor_with(vec![Arc::new(LineBatchProvider)]).with_replay_protection(
+            ReplayGuard::new(std::time::Duration::from_secs(300))
+                .with_nonce_header("X-Nonce")
+                .with_timestamp_header("X-Timestamp"),
+        );
+        let now = time::OffsetDateTime::now_utc().unix_timestamp().to_string();
+        let headers = vec![
+            ("x-nonce".to_string(), "n-1".to_string()),
+            ("x-timestamp".to_string(), now),
+        ];
+
+        let body = Bytes::from_static(b"a\nb\nc");
+        let first = processor.handle_webhook("lines", headers.clone(), body.clone());
+        assert_eq!(first.messages.len(), 3);
+
+        let replay = processor.handle_webhook("lines", headers, body);
+        assert_eq!(replay.response.status.as_u16(), 200);
+        assert!(replay.messages.is_empty());
+
+        let stale = vec![
+            ("x-nonce".to_string(), "n-2".to_string()),
+            ("x-timestamp".to_string(), "1700000000".to_string()),
+        ];
+        let response = processor.process_webhook("lines", stale, Bytes::from_static(b"d\ne"));
+        assert_eq!(response.status.as_u16(), 401);
+    }
+
+    #[test]
+    fn problem_details_errors() {
+        let processor = processor_with(vec![]).with_problem_details(true);
+        let response = processor.process_webhook("unknown", vec![], Bytes::from_static(b"x"));
+        assert_eq!(response.status.as_u16(), 404);
+        assert_eq!(response.content_type, "application/problem+json");
+        let problem: serde_json::Value = serde_json::from_str(&response.body).unwrap();
+        assert_eq!(problem["type"], "about:blank");
+        assert_eq!(problem["title"], "Not Found");
+        assert_eq!(problem["status"], 404);
+        assert_eq!(problem["detail"], "unknown provider");
+    }
+
+    #[test]
+    fn handle_webhook_reports_structured_outcome() {
+        let processor = processor_with(vec![Arc::new(FakeProvider), Arc::new(FailParseProvider)]);
+        let ok = processor.handle_webhook("fake", vec![], Bytes::from_static(b"hello"));
+        assert!(ok.is_success() && ok.verified);
+        assert_eq!(ok.message().unwrap().text, "hello");
+        assert_eq!(ok.status(), HttpStatus::Ok);
+        assert_eq!(ok.outcome(), "accepted");
+
+        let parse = processor.handle_webhook("fail-parse", vec![], Bytes::from_static(b"x"));
+        assert!(parse.verified);
+        assert!(matches!(parse.error, Some(WebhookError::ParseError(_))));
+        assert!(parse.messages.is_empty());
+        assert_eq!(parse.status(), HttpStatus::BadRequest);
+        assert_eq!(parse.outcome(), "parse_error");
+
+        let unknown = processor.handle_webhook("nope", vec![], Bytes::from_static(b"x"));
+        assert!(!unknown.verified);
+        assert_eq!(unknown.provider, "nope");
+        assert_eq!(unknown.status(), HttpStatus::NotFound);
+        assert_eq!(unknown.outcome(), "provider_not_found");
+    }
+
+    #[test]
+    fn tenant_webhook_uses_tenant_registry() {
+        let tenants = TenantRegistry::new()
+            .with("acme", InboundRegistry::new().with(Arc::new(FakeProvider)))
+            .with("globex", InboundRegistry::new().with(Arc::new(FailVerifyProvider)));
+        let processor = processor_with(vec![]).with_tenants(tenants);
+
+        let ok = processor.process_tenant_webhook(
+            "acme",
+            "fake",
+            vec![],
+            Bytes::from_static(b"hi"),
+        );
+        assert_eq!(ok.status.as_u16(), 200);
+
+        // Provider registered for another tenant is not visible.
+        let missing = processor.process_tenant_webhook(
+            "globex",
+            "fake",
+            vec![],
+            Bytes::from_static(b"hi"),
+        );
+        assert_eq!(missing.status.as_u16(), 404);
+        assert!(missing.body.contains("unknown provider"));
+
+        let no_tenant = processor.process_tenant_webhook(
+            "initech",
+            "fake",
+            vec![],
+            Bytes::from_static(b"hi"),
+        );
+        assert_eq!(no_tenant.status.as_u16(), 404);
+        assert!(no_tenant.body.contains("unknown tenant"));
+
+        // Tenant providers are not reachable from the single-tenant route.
+        let untenanted = processor.process_webhook("fake", vec![], Bytes::from_static(b"hi"));
+        assert_eq!(untenanted.status.as_u16(), 404);
+    }
+
     #[test]
     fn processor_passes_headers_to_provider() {
         // FakeProvider ignores headers, but we verify the pipeline doesn't
@@ -238,7 +1037,7 @@ mod tests {
             ("X-Custom".to_string(), "value".to_string()),
             ("Content-Type".to_string(), "application/json".to_string()),
         ];
-        let response = processor.process_webhook("fake", headers, b"body");
+        let response = processor.process_webhook("fake", headers, Bytes::from_static(b"body"));
         assert_eq!(response.status.as_u16(), 200);
     }
 }
@@ -0,0 +1,135 @@
+//! Inbound text clean-up.
+//!
+//! Carriers deliver message bodies in surprisingly inconsistent shapes:
+//! decomposed accents, stray NULs and byte-order marks, `\r\n` line endings,
+//! trailing padding.  [`TextNormalizer`] tidies the text of every inbound
+//! message before it reaches listeners and reply handlers, so application
+//! code can compare and store it as-is.
+//!
+//! ```rust,ignore
+//! use sms_web_generic::{TextNormalizer, WebhookProcessor};
+//!
+//! let processor = WebhookProcessor::new(registry)
+//!     .with_text_normalizer(TextNormalizer::default().with_trim(false));
+//! ```
+
+use sms_core::InboundMessage;
+use unicode_normalization::UnicodeNormalization;
+
+/// Configurable clean-up of inbound message text.
+///
+/// Every step is enabled by default.  Delivery reports are left untouched,
+/// since their text is generated by the provider.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextNormalizer {
+    nfc: bool,
+    strip_controls: bool,
+    trim: bool,
+}
+
+impl Default for TextNormalizer {
+    fn default() -> Self {
+        Self {
+            nfc: true,
+            strip_controls: true,
+            trim: true,
+        }
+    }
+}
+
+impl TextNormalizer {
+    /// Apply Unicode Normalization Form C, composing e.g. `e` + U+0301 into `é`.
+    pub fn with_nfc(mut self, enabled: bool) -> Self {
+        self.nfc = enabled;
+        self
+    }
+
+    /// Drop control characters and byte-order marks.  Line breaks and tabs
+    /// are kept, with `\r\n` and lone `\r` turned into `\n`.
+    pub fn with_strip_controls(mut self, enabled: bool) -> Self {
+        self.strip_controls = enabled;
+        self
+    }
+
+    /// Remove leading and trailing whitespace.
+    pub fn with_trim(mut self, enabled: bool) -> Self {
+        self.trim = enabled;
+        self
+    }
+
+    /// Return the normalized form of `text`.
+    pub fn normalize(&self, text: &str) -> String {
+        let mut out = if self.strip_controls {
+            strip_controls(text)
+        } else {
+            text.to_string()
+        };
+        if self.nfc {
+            out = out.nfc().collect();
+        }
+        if self.trim {
+            let trimmed = out.trim();
+            if trimmed.len() != out.len() {
+                out = trimmed.to_string();
+            }
+        }
+        out
+    }
+
+    /// Normalize `message.text` in place.
+    pub fn apply(&self, message: &mut InboundMessage) {
+        if message.direction == sms_core::InboundDirection::Inbound {
+            message.text = self.normalize(&message.text);
+        }
+    }
+}
+
+fn strip_controls(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\r' => {
+                chars.next_if_eq(&'\n');
+                out.push('\n');
+            }
+            '\n' | '\t' => out.push(c),
+            '\u{feff}' => {}
+            c if c.is_control() => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cleans_messy_text() {
+        let normalizer = TextNormalizer::default();
+        assert_eq!(
+            normalizer.normalize("\u{feff}  Cafe\u{301}\0 ok\r\nbye\r \u{7}"),
+            "Café ok\nbye"
+        );
+        assert_eq!(
+            normalizer.normalize("line\tone\nline two"),
+            "line\tone\nline two"
+        );
+    }
+
+    #[test]
+    fn steps_can_be_disabled() {
+        let text = " e\u{301}\0 ";
+        let none = TextNormalizer::default()
+            .with_nfc(false)
+            .with_strip_controls(false)
+            .with_trim(false);
+        assert_eq!(none.normalize(text), text);
+        assert_eq!(
+            TextNormalizer::default().with_trim(false).normalize(text),
+            " é "
+        );
+    }
+}
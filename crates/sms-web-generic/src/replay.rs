@@ -0,0 +1,504 @@
+//! Webhook replay-attack protection.
+//!
+//! A valid signature only proves a payload came from the provider at *some*
+//! point; an attacker who captures a signed webhook can replay it forever.
+//! [`ReplayGuard`] closes that gap by rejecting payloads whose timestamp is
+//! outside a freshness window and by remembering recently seen nonces.
+//!
+//! The timestamp and nonce are read from the first configured header that is
+//! present.  Message fields are only used when asked for:
+//! [`InboundMessage::timestamp`] is usually the send or delivery time, so a
+//! late delivery report would look stale, and a provider reuses one message
+//! ID for every status callback about that message.  With
+//! [`with_message_nonce`](ReplayGuard::with_message_nonce) the nonce is the
+//! message ID together with the direction and text, which for a delivery
+//! report carries the status.
+//!
+//! A payload whose nonce was already seen is a duplicate delivery, not an
+//! attack: `WebhookProcessor` acknowledges it with a 200 and drops it, so
+//! the provider stops retrying.
+//!
+//! ```rust,ignore
+//! use sms_web_generic::{ReplayGuard, WebhookProcessor};
+//!
+//! let guard = ReplayGuard::new(Duration::from_secs(300))
+//!     .with_nonce_header("X-Plivo-Signature-V3-Nonce");
+//! let processor = WebhookProcessor::new(registry).with_replay_protection(guard);
+//! ```
+
+use sms_core::{Headers, InboundMessage};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
+
+/// How often expired nonces are swept from memory.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Reasons a webhook is rejected as a possible replay.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ReplayError {
+    /// The payload carried no timestamp but one is required.
+    #[error("missing webhook timestamp")]
+    MissingTimestamp,
+
+    /// The payload timestamp is older than the freshness window.
+    #[error("webhook timestamp is {age_secs}s old")]
+    Stale {
+        /// Age of the payload in seconds.
+        age_secs: i64,
+    },
+
+    /// The payload timestamp is further in the future than the allowed skew.
+    #[error("webhook timestamp is in the future")]
+    FromFuture,
+
+    /// The nonce has already been seen within the freshness window.
+    ///
+    /// `WebhookProcessor` acknowledges duplicates instead of rejecting them.
+    #[error("duplicate webhook nonce: {0}")]
+    Duplicate(String),
+}
+
+#[derive(Debug)]
+struct NonceCache {
+    /// Nonce key → time after which it may be forgotten.
+    seen: HashMap<String, OffsetDateTime>,
+    last_prune: OffsetDateTime,
+}
+
+/// Timestamp-freshness and nonce checks for inbound webhooks.
+///
+/// Nonces are only recorded for payloads that have already passed signature
+/// verification, so unauthenticated traffic cannot fill the cache.
+#[derive(Debug)]
+pub struct ReplayGuard {
+    max_age: Duration,
+    max_future_skew: Duration,
+    require_timestamp: bool,
+    timestamp_headers: Vec<String>,
+    nonce_headers: Vec<String>,
+    message_timestamp: bool,
+    message_nonce: bool,
+    nonces: Mutex<NonceCache>,
+}
+
+impl ReplayGuard {
+    /// Reject payloads older than `max_age` (and nonces seen within it).
+    ///
+    /// Payloads up to 30 seconds in the future are tolerated to allow for
+    /// clock skew; override with [`with_max_future_skew`](Self::with_max_future_skew).
+    pub fn new(max_age: Duration) -> Self {
+        Self {
+            max_age,
+            max_future_skew: Duration::from_secs(30),
+            require_timestamp: false,
+            timestamp_headers: Vec::new(),
+            nonce_headers: Vec::new(),
+            message_timestamp: false,
+            message_nonce: false,
+            nonces: Mutex::new(NonceCache {
+                seen: HashMap::new(),
+                last_prune: OffsetDateTime::now_utc(),
+            }),
+        }
+    }
+
+    /// Read the payload timestamp from this header (RFC 3339 or Unix
+    /// seconds/milliseconds).  May be called several times; the first header
+    /// present wins.
+    pub fn with_timestamp_header(mut self, name: impl Into<String>) -> Self {
+        self.timestamp_headers.push(name.into());
+        self
+    }
+
+    /// Read the nonce from this header.  May be called several times; the
+    /// first header present wins.
+    pub fn with_nonce_header(mut self, name: impl Into<String>) -> Self {
+        self.nonce_headers.push(name.into());
+        self
+    }
+
+    /// Fall back to [`InboundMessage::timestamp`] when no timestamp header is
+    /// present.  Only enable this for providers that stamp the webhook with
+    /// the time it was sent; a delivery time makes late reports look stale.
+    pub fn with_message_timestamp(mut self) -> Self {
+        self.message_timestamp = true;
+        self
+    }
+
+    /// Fall back to the provider message ID, direction and text as the nonce
+    /// when no nonce header is present.  Successive status callbacks for one
+    /// message differ in text, so only a repeat of the same callback counts
+    /// as a duplicate.
+    pub fn with_message_nonce(mut self) -> Self {
+        self.message_nonce = true;
+        self
+    }
+
+    /// Reject payloads that carry no timestamp at all (default: accept them
+    /// and rely on nonce tracking alone).
+    pub fn require_timestamp(mut self, require: bool) -> Self {
+        self.require_timestamp = require;
+        self
+    }
+
+    /// Maximum amount a payload timestamp may be ahead of the local clock.
+    pub fn with_max_future_skew(mut self, skew: Duration) -> Self {
+        self.max_future_skew = skew;
+        self
+    }
+
+    /// Check a verified, parsed webhook and record its nonce.
+    pub fn check(
+        &self,
+        provider: &str,
+        headers: &Headers,
+        message: &InboundMessage,
+    ) -> Result<(), ReplayError> {
+        self.check_at(OffsetDateTime::now_utc(), provider, headers, message)
+    }
+
+    /// Check a verified payload of one or more records and record its
+    /// nonces, dropping records that are duplicates.
+    ///
+    /// Header timestamps and nonces describe the whole HTTP request, so they
+    /// are checked once: a stale, future or missing timestamp fails the
+    /// payload, and a repeated header nonce makes the whole payload a
+    /// [`ReplayError::Duplicate`].  Only message-derived nonces (see
+    /// [`with_message_nonce`](Self::with_message_nonce)) are checked per
+    /// record, and a record whose nonce was already seen is removed from
+    /// `messages`.  Nothing is recorded for a rejected payload.
+    pub fn check_payload(
+        &self,
+        provider: &str,
+        headers: &Headers,
+        messages: &mut Vec<InboundMessage>,
+    ) -> Result<(), ReplayError> {
+        self.check_payload_at(OffsetDateTime::now_utc(), provider, headers, messages)
+    }
+
+    fn check_at(
+        &self,
+        now: OffsetDateTime,
+        provider: &str,
+        headers: &Headers,
+        message: &InboundMessage,
+    ) -> Result<(), ReplayError> {
+        self.check_timestamp(now, headers, Some(message))?;
+        match self.nonce(headers, message) {
+            Some(nonce) => self.record(now, provider, nonce),
+            None => Ok(()),
+        }
+    }
+
+    fn check_payload_at(
+        &self,
+        now: OffsetDateTime,
+        provider: &str,
+        headers: &Headers,
+        messages: &mut Vec<InboundMessage>,
+    ) -> Result<(), ReplayError> {
+        let header_timestamp = find_header(headers, &self.timestamp_headers).and_then(parse_timestamp);
+        if header_timestamp.is_some() || messages.is_empty() {
+            self.check_timestamp(now, headers, None)?;
+        } else {
+            for message in messages.iter() {
+                self.check_timestamp(now, headers, Some(message))?;
+            }
+        }
+
+        if let Some(nonce) = find_header(headers, &self.nonce_headers) {
+            return self.record(now, provider, nonce.to_string());
+        }
+        messages.retain(|message| match self.nonce(headers, message) {
+            Some(nonce) => match self.record(now, provider, nonce) {
+                Ok(()) => true,
+                Err(e) => {
+                    tracing::debug!("Dropped {} record: {}", provider, e);
+                    false
+                }
+            },
+            None => true,
+        });
+        Ok(())
+    }
+
+    /// Freshness check against the header timestamp, falling back to the
+    /// message's own timestamp when enabled.
+    fn check_timestamp(
+        &self,
+        now: OffsetDateTime,
+        headers: &Headers,
+        message: Option<&InboundMessage>,
+    ) -> Result<(), ReplayError> {
+        let timestamp = find_header(headers, &self.timestamp_headers)
+            .and_then(parse_timestamp)
+            .or(message
+                .and_then(|m| m.timestamp)
+                .filter(|_| self.message_timestamp));
+
+        match timestamp {
+            Some(ts) => {
+                let age = now - ts;
+                if age > self.max_age {
+                    return Err(ReplayError::Stale {
+                        age_secs: age.whole_seconds(),
+                    });
+                }
+                if -age > self.max_future_skew {
+                    return Err(ReplayError::FromFuture);
+                }
+                Ok(())
+            }
+            None if self.require_timestamp => Err(ReplayError::MissingTimestamp),
+            None => Ok(()),
+        }
+    }
+
+    fn nonce(&self, headers: &Headers, message: &InboundMessage) -> Option<String> {
+        match find_header(headers, &self.nonce_headers) {
+            Some(nonce) => Some(nonce.to_string()),
+            None => match &message.id {
+                Some(id) if self.message_nonce => {
+                    Some(format!("{}:{:?}:{}", id, message.direction, message.text))
+                }
+                _ => None,
+            },
+        }
+    }
+
+    /// Remember `nonce` for `provider`, failing if it was already seen.
+    fn record(
+        &self,
+        now: OffsetDateTime,
+        provider: &str,
+        nonce: String,
+    ) -> Result<(), ReplayError> {
+        let key = format!("{}:{}", provider, nonce);
+
+        let mut cache = self.nonces.lock().expect("nonce cache poisoned");
+        if now - cache.last_prune > PRUNE_INTERVAL {
+            cache.seen.retain(|_, expires| *expires > now);
+            cache.last_prune = now;
+        }
+        match cache.seen.get(&key) {
+            Some(expires) if *expires > now => Err(ReplayError::Duplicate(nonce)),
+            _ => {
+                cache.seen.insert(key, now + self.max_age);
+                Ok(())
+            }
+        }
+    }
+
+    /// Number of nonces currently remembered.
+    pub fn tracked_nonces(&self) -> usize {
+        self.nonces.lock().expect("nonce cache poisoned").seen.len()
+    }
+}
+
+fn find_header<'a>(headers: &'a Headers, names: &[String]) -> Option<&'a str> {
+    names.iter().find_map(|name| {
+        headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    })
+}
+
+/// Parse an RFC 3339 timestamp or a Unix epoch in seconds or milliseconds.
+fn parse_timestamp(value: &str) -> Option<OffsetDateTime> {
+    let value = value.trim();
+    if let Ok(ts) = OffsetDateTime::parse(value, &Rfc3339) {
+        return Some(ts);
+    }
+    let n: i64 = value.parse().ok()?;
+    if n > 100_000_000_000 {
+        OffsetDateTime::from_unix_timestamp_nanos(n as i128 * 1_000_000).ok()
+    } else {
+        OffsetDateTime::from_unix_timestamp(n).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sms_core::{Channel, InboundDirection};
+
+    fn message(id: Option<&str>, timestamp: Option<OffsetDateTime>) -> InboundMessage {
+        InboundMessage {
+            id: id.map(String::from),
+            from: "+1".into(),
+            to: "+2".into(),
+            text: "hi".into(),
+            timestamp,
+            provider: "fake",
+            direction: InboundDirection::Inbound,
+            channel: Channel::Sms,
+            provider_account: None,
+            thread_id: None,
+            flag: None,
+            raw: serde_json::json!({}),
+        }
+    }
+
+    #[test]
+    fn fresh_message_passes() {
+        let guard = ReplayGuard::new(Duration::from_secs(300));
+        let msg = message(Some("m1"), Some(OffsetDateTime::now_utc()));
+        assert!(guard.check("fake", &vec![], &msg).is_ok());
+    }
+
+    #[test]
+    fn stale_message_rejected() {
+        let guard = ReplayGuard::new(Duration::from_secs(300)).with_message_timestamp();
+        let old = OffsetDateTime::now_utc() - time::Duration::minutes(10);
+        let err = guard
+            .check("fake", &vec![], &message(None, Some(old)))
+            .unwrap_err();
+        assert!(matches!(err, ReplayError::Stale { .. }));
+    }
+
+    #[test]
+    fn future_message_rejected() {
+        let guard = ReplayGuard::new(Duration::from_secs(300)).with_message_timestamp();
+        let future = OffsetDateTime::now_utc() + time::Duration::minutes(5);
+        let err = guard
+            .check("fake", &vec![], &message(None, Some(future)))
+            .unwrap_err();
+        assert_eq!(err, ReplayError::FromFuture);
+    }
+
+    #[test]
+    fn message_fields_ignored_by_default() {
+        let guard = ReplayGuard::new(Duration::from_secs(300));
+        // A delivery report that arrives long after the message was sent.
+        let old = OffsetDateTime::now_utc() - time::Duration::hours(2);
+        let msg = message(Some("m1"), Some(old));
+        assert!(guard.check("fake", &vec![], &msg).is_ok());
+        assert!(guard.check("fake", &vec![], &msg).is_ok());
+        assert_eq!(guard.tracked_nonces(), 0);
+    }
+
+    #[test]
+    fn duplicate_nonce_rejected() {
+        let guard = ReplayGuard::new(Duration::from_secs(300)).with_message_nonce();
+        let msg = message(Some("m1"), None);
+        assert!(guard.check("fake", &vec![], &msg).is_ok());
+        let err = guard.check("fake", &vec![], &msg).unwrap_err();
+        assert_eq!(err, ReplayError::Duplicate("m1:Inbound:hi".into()));
+        // Same ID from a different provider is a different nonce.
+        assert!(guard.check("other", &vec![], &msg).is_ok());
+    }
+
+    #[test]
+    fn status_callbacks_for_one_message_are_distinct() {
+        let guard = ReplayGuard::new(Duration::from_secs(300)).with_message_nonce();
+        let mut report = message(Some("SM1"), None);
+        report.direction = InboundDirection::DeliveryReport;
+        report.text = "sent".into();
+        assert!(guard.check("fake", &vec![], &report).is_ok());
+        report.text = "delivered".into();
+        assert!(guard.check("fake", &vec![], &report).is_ok());
+        assert!(guard.check("fake", &vec![], &report).is_err());
+    }
+
+    #[test]
+    fn header_values_take_precedence() {
+        let guard = ReplayGuard::new(Duration::from_secs(300))
+            .with_timestamp_header("X-Timestamp")
+            .with_nonce_header("X-Nonce");
+        let now = OffsetDateTime::now_utc().unix_timestamp().to_string();
+        let headers = vec![
+            ("x-timestamp".to_string(), now),
+            ("x-nonce".to_string(), "n-1".to_string()),
+        ];
+        let msg = message(Some("m1"), None);
+        assert!(guard.check("fake", &headers, &msg).is_ok());
+        assert_eq!(
+            guard.check("fake", &headers, &msg).unwrap_err(),
+            ReplayError::Duplicate("n-1".into())
+        );
+    }
+
+    #[test]
+    fn missing_timestamp_when_required() {
+        let guard = ReplayGuard::new(Duration::from_secs(300)).require_timestamp(true);
+        let err = guard
+            .check("fake", &vec![], &message(None, None))
+            .unwrap_err();
+        assert_eq!(err, ReplayError::MissingTimestamp);
+    }
+
+    #[test]
+    fn expired_nonces_are_pruned() {
+        let guard = ReplayGuard::new(Duration::from_secs(10)).with_message_nonce();
+        let start = OffsetDateTime::now_utc();
+        let msg = message(Some("m1"), None);
+        guard.check_at(start, "fake", &vec![], &msg).unwrap();
+        assert_eq!(guard.tracked_nonces(), 1);
+
+        // Well past both the nonce expiry and the prune interval.
+        let later = start + time::Duration::minutes(5);
+        guard
+            .check_at(later, "fake", &vec![], &message(Some("m2"), None))
+            .unwrap();
+        assert_eq!(guard.tracked_nonces(), 1);
+        guard.check_at(later, "fake", &vec![], &msg).unwrap();
+    }
+
+    #[test]
+    fn header_nonce_is_checked_once_per_payload() {
+        let guard = ReplayGuard::new(Duration::from_secs(300)).with_nonce_header("X-Nonce");
+        let headers = vec![("x-nonce".to_string(), "n-1".to_string())];
+        let mut batch = vec![message(Some("m1"), None), message(Some("m2"), None)];
+        guard.check_payload("fake", &headers, &mut batch).unwrap();
+        assert_eq!(batch.len(), 2);
+        assert_eq!(guard.tracked_nonces(), 1);
+
+        let mut replay = vec![message(Some("m3"), None)];
+        assert_eq!(
+            guard.check_payload("fake", &headers, &mut replay).unwrap_err(),
+            ReplayError::Duplicate("n-1".into())
+        );
+    }
+
+    #[test]
+    fn stale_record_fails_the_whole_payload() {
+        let guard = ReplayGuard::new(Duration::from_secs(300))
+            .with_message_timestamp()
+            .with_message_nonce();
+        let old = OffsetDateTime::now_utc() - time::Duration::minutes(10);
+        let mut batch = vec![message(Some("m1"), None), message(Some("m2"), Some(old))];
+        let err = guard.check_payload("fake", &vec![], &mut batch).unwrap_err();
+        assert!(matches!(err, ReplayError::Stale { .. }));
+        assert_eq!(guard.tracked_nonces(), 0);
+    }
+
+    #[test]
+    fn message_nonces_drop_only_repeated_records() {
+        let guard = ReplayGuard::new(Duration::from_secs(300)).with_message_nonce();
+        let mut first = vec![message(Some("m1"), None)];
+        guard.check_payload("fake", &vec![], &mut first).unwrap();
+
+        let mut batch = vec![message(Some("m1"), None), message(Some("m2"), None)];
+        guard.check_payload("fake", &vec![], &mut batch).unwrap();
+        let ids: Vec<_> = batch.iter().map(|m| m.id.as_deref()).collect();
+        assert_eq!(ids, vec![Some("m2")]);
+    }
+
+    #[test]
+    fn parses_timestamp_formats() {
+        assert!(parse_timestamp("2024-01-01T00:00:00Z").is_some());
+        assert_eq!(
+            parse_timestamp("1700000000").unwrap().unix_timestamp(),
+            1_700_000_000
+        );
+        assert_eq!(
+            parse_timestamp("1700000000000").unwrap().unix_timestamp(),
+            1_700_000_000
+        );
+        assert!(parse_timestamp("yesterday").is_none());
+    }
+}
@@ -0,0 +1,142 @@
+//! Structured request tracing for webhook routes.
+//!
+//! [`WebhookProcessor`](crate::WebhookProcessor) records the outcome of every
+//! call on the current span: `sms.provider`, `sms.tenant`, `sms.message_id`,
+//! `sms.message_count` and `sms.outcome` (see
+//! [`WebhookResult::outcome`]).  Fields the span does not declare are
+//! ignored, so this costs nothing when no span is active.
+//!
+//! With the `tower-http` feature, [`WebhookMakeSpan`] and
+//! [`WebhookOnResponse`] plug into tower-http's `TraceLayer` to open a span
+//! that declares those fields, and to log one event per response with its
+//! status and latency.  [`webhook_trace_layer`] wires both up:
+//!
+//! ```rust,ignore
+//! use sms_web_axum::webhook_trace_layer;
+//!
+//! let app = Router::new()
+//!     .route("/webhooks/{provider}", post(unified_webhook))
+//!     .layer(webhook_trace_layer())
+//!     .with_state(state);
+//! ```
+
+use sms_core::WebhookResult;
+
+/// Record the outcome of a processed webhook on the current span.
+pub(crate) fn record_outcome(result: &WebhookResult) {
+    let span = tracing::Span::current();
+    span.record("sms.provider", result.provider.as_str());
+    if let Some(tenant) = &result.tenant {
+        span.record("sms.tenant", tenant.as_str());
+    }
+    if let Some(id) = result.message().and_then(|m| m.id.as_deref()) {
+        span.record("sms.message_id", id);
+    }
+    span.record("sms.message_count", result.messages.len());
+    span.record("sms.outcome", result.outcome());
+}
+
+#[cfg(feature = "tower-http")]
+pub use self::layer::{WebhookMakeSpan, WebhookOnResponse, WebhookTraceLayer, webhook_trace_layer};
+
+#[cfg(feature = "tower-http")]
+mod layer {
+    use std::time::Duration;
+    use tower_http::trace::{
+        DefaultOnRequest, HttpMakeClassifier, MakeSpan, OnResponse, TraceLayer,
+    };
+    use tracing::Span;
+    use tracing::field::Empty;
+
+    /// A `TraceLayer` configured with [`WebhookMakeSpan`] and
+    /// [`WebhookOnResponse`].
+    pub type WebhookTraceLayer =
+        TraceLayer<HttpMakeClassifier, WebhookMakeSpan, DefaultOnRequest, WebhookOnResponse>;
+
+    /// Build a tower-http `TraceLayer` for webhook routes.
+    pub fn webhook_trace_layer() -> WebhookTraceLayer {
+        TraceLayer::new_for_http()
+            .make_span_with(WebhookMakeSpan)
+            .on_response(WebhookOnResponse)
+    }
+
+    /// Opens an `INFO` span named `webhook` for each request.
+    ///
+    /// The provider and tenant are taken from a `/webhooks/{provider}` or
+    /// `/webhooks/{tenant}/{provider}` path when the route matches; the
+    /// processor fills in the rest once the payload has been handled.
+    #[derive(Debug, Clone, Copy, Default)]
+    pub struct WebhookMakeSpan;
+
+    impl<B> MakeSpan<B> for WebhookMakeSpan {
+        fn make_span(&mut self, request: &http::Request<B>) -> Span {
+            let span = tracing::info_span!(
+                "webhook",
+                http.method = %request.method(),
+                http.path = request.uri().path(),
+                http.status = Empty,
+                latency_ms = Empty,
+                sms.provider = Empty,
+                sms.tenant = Empty,
+                sms.message_id = Empty,
+                sms.message_count = Empty,
+                sms.outcome = Empty,
+            );
+            if let Some((tenant, provider)) = parse_route(request.uri().path()) {
+                span.record("sms.provider", provider);
+                if let Some(tenant) = tenant {
+                    span.record("sms.tenant", tenant);
+                }
+            }
+            span
+        }
+    }
+
+    /// Records the status and latency on the span and logs one event per
+    /// response: `ERROR` for 5xx, `WARN` for 4xx, `INFO` otherwise.
+    #[derive(Debug, Clone, Copy, Default)]
+    pub struct WebhookOnResponse;
+
+    impl<B> OnResponse<B> for WebhookOnResponse {
+        fn on_response(self, response: &http::Response<B>, latency: Duration, span: &Span) {
+            let status = response.status().as_u16();
+            let latency_ms = latency.as_millis() as u64;
+            span.record("http.status", status);
+            span.record("latency_ms", latency_ms);
+            if response.status().is_server_error() {
+                tracing::error!(parent: span, status, latency_ms, "webhook response");
+            } else if response.status().is_client_error() {
+                tracing::warn!(parent: span, status, latency_ms, "webhook response");
+            } else {
+                tracing::info!(parent: span, status, latency_ms, "webhook response");
+            }
+        }
+    }
+
+    /// Split `.../webhooks/{provider}` or `.../webhooks/{tenant}/{provider}`.
+    fn parse_route(path: &str) -> Option<(Option<&str>, &str)> {
+        let (_, rest) = path.rsplit_once("/webhooks/")?;
+        let mut segments = rest.split('/').filter(|s| !s.is_empty());
+        match (segments.next(), segments.next(), segments.next()) {
+            (Some(provider), None, None) => Some((None, provider)),
+            (Some(tenant), Some(provider), None) => Some((Some(tenant), provider)),
+            _ => None,
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn parses_webhook_routes() {
+            assert_eq!(parse_route("/webhooks/plivo"), Some((None, "plivo")));
+            assert_eq!(
+                parse_route("/api/webhooks/acme/twilio"),
+                Some((Some("acme"), "twilio"))
+            );
+            assert_eq!(parse_route("/webhooks/a/b/c"), None);
+            assert_eq!(parse_route("/health"), None);
+        }
+    }
+}
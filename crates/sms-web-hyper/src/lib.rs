@@ -1,8 +1,22 @@
-use http_body_util::{BodyExt, Full};
+use http_body_util::{BodyExt, Full, LengthLimitError, Limited};
 use hyper::{HeaderMap, Request, Response, StatusCode, Uri};
-use sms_core::{Headers, InboundRegistry};
+use hyper_util::rt::{TokioExecutor, TokioIo, TokioTimer};
+use hyper_util::server::conn::auto;
+use sms_core::Headers;
 use sms_web_generic::{HeaderConverter, ResponseConverter, WebhookProcessor};
 use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::Semaphore;
+use tracing::{debug, warn};
+
+/// tower-http `TraceLayer` hooks that tag webhook spans with provider,
+/// message id and outcome, for services built with `tower::ServiceBuilder`.
+pub use sms_web_generic::{WebhookMakeSpan, WebhookOnResponse, webhook_trace_layer};
+
+/// Default body size limit, matching the Rocket adapter's `2.megabytes()`.
+pub const DEFAULT_MAX_BODY_BYTES: usize = 2 * 1024 * 1024;
 
 type HyperServiceFuture = std::pin::Pin<
     Box<dyn std::future::Future<Output = Result<Response<Full<bytes::Bytes>>, Infallible>> + Send>,
@@ -10,7 +24,17 @@ type HyperServiceFuture = std::pin::Pin<
 
 #[derive(Clone)]
 pub struct AppState {
-    pub registry: InboundRegistry,
+    /// The webhook pipeline, built once and shared by every request.
+    pub processor: Arc<WebhookProcessor>,
+}
+
+impl AppState {
+    /// Serve webhooks through `processor`.
+    pub fn new(processor: WebhookProcessor) -> Self {
+        Self {
+            processor: Arc::new(processor),
+        }
+    }
 }
 
 /// Hyper-specific header converter
@@ -42,11 +66,20 @@ impl ResponseConverter for HyperResponseConverter {
         let status = StatusCode::from_u16(response.status.as_u16())
             .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
 
-        Response::builder()
+        let mut res = Response::builder()
             .status(status)
             .header("content-type", response.content_type)
             .body(Full::new(bytes::Bytes::from(response.body)))
-            .unwrap()
+            .unwrap();
+        for (name, value) in response.headers {
+            if let (Ok(name), Ok(value)) = (
+                hyper::header::HeaderName::from_bytes(name.as_bytes()),
+                hyper::header::HeaderValue::from_str(&value),
+            ) {
+                res.headers_mut().append(name, value);
+            }
+        }
+        res
     }
 }
 
@@ -58,14 +91,113 @@ fn extract_provider_from_path(uri: &Uri) -> Option<String> {
         .map(|stripped| stripped.to_string())
 }
 
+/// Extract optional tenant and provider from URI path
+///
+/// Accepts `/webhooks/{provider}` and `/webhooks/{tenant}/{provider}`.
+fn extract_route_from_path(uri: &Uri) -> Option<(Option<String>, String)> {
+    let rest = extract_provider_from_path(uri)?;
+    match rest.split_once('/') {
+        Some((tenant, provider)) if !tenant.is_empty() && !provider.contains('/') => {
+            Some((Some(tenant.to_string()), provider.to_string()))
+        }
+        Some(_) => None,
+        None => Some((None, rest)),
+    }
+}
+
+/// Connection limits and timeouts applied by [`serve`].
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    /// Maximum number of connections served at once; further connections
+    /// wait in the listen backlog until a slot frees up.
+    pub max_connections: usize,
+    /// How long a client may take to send the request headers (HTTP/1).
+    pub header_read_timeout: Duration,
+    /// How long a client may take to send the request body.
+    pub body_read_timeout: Duration,
+    /// Largest accepted request body; longer bodies get a 413.
+    pub max_body_bytes: usize,
+    /// Upper bound on a single connection's lifetime, keep-alive included.
+    pub connection_timeout: Option<Duration>,
+    /// Accept HTTP/2 (prior knowledge) as well as HTTP/1.1.
+    pub http2: bool,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            max_connections: 1024,
+            header_read_timeout: Duration::from_secs(10),
+            body_read_timeout: Duration::from_secs(30),
+            max_body_bytes: DEFAULT_MAX_BODY_BYTES,
+            connection_timeout: Some(Duration::from_secs(300)),
+            http2: true,
+        }
+    }
+}
+
+impl ServerConfig {
+    /// Create a config with the default limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum number of concurrent connections (minimum 1).
+    pub fn with_max_connections(mut self, max: usize) -> Self {
+        self.max_connections = max.max(1);
+        self
+    }
+
+    /// Set the header read timeout.
+    pub fn with_header_read_timeout(mut self, timeout: Duration) -> Self {
+        self.header_read_timeout = timeout;
+        self
+    }
+
+    /// Set the body read timeout.
+    pub fn with_body_read_timeout(mut self, timeout: Duration) -> Self {
+        self.body_read_timeout = timeout;
+        self
+    }
+
+    /// Set the request body size limit.
+    pub fn with_max_body_bytes(mut self, max: usize) -> Self {
+        self.max_body_bytes = max;
+        self
+    }
+
+    /// Set (or remove, with `None`) the per-connection lifetime limit.
+    pub fn with_connection_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.connection_timeout = timeout;
+        self
+    }
+
+    /// Enable or disable HTTP/2.
+    pub fn with_http2(mut self, enabled: bool) -> Self {
+        self.http2 = enabled;
+        self
+    }
+}
+
 /// Unified webhook handler for raw Hyper
+///
+/// Bodies over [`DEFAULT_MAX_BODY_BYTES`] are answered with a 413.
 pub async fn handle_webhook(
     req: Request<hyper::body::Incoming>,
     state: AppState,
 ) -> Result<Response<Full<bytes::Bytes>>, Infallible> {
-    // Extract provider from path
-    let provider = match extract_provider_from_path(req.uri()) {
-        Some(p) => p,
+    handle_webhook_inner(req, state, None, DEFAULT_MAX_BODY_BYTES).await
+}
+
+async fn handle_webhook_inner(
+    req: Request<hyper::body::Incoming>,
+    state: AppState,
+    body_read_timeout: Option<Duration>,
+    max_body_bytes: usize,
+) -> Result<Response<Full<bytes::Bytes>>, Infallible> {
+    // Extract tenant (if any) and provider from path
+    let (tenant, provider) = match extract_route_from_path(req.uri()) {
+        Some(route) => route,
         None => {
             return Ok(Response::builder()
                 .status(StatusCode::NOT_FOUND)
@@ -78,9 +210,34 @@ pub async fn handle_webhook(
     // Get headers before consuming the request
     let generic_headers = HyperHeaderConverter::to_generic_headers(req.headers());
 
-    // Read the body
-    let body_bytes = match req.collect().await {
+    // Read the body, stopping once it exceeds the limit
+    let body = Limited::new(req.into_body(), max_body_bytes);
+    let collected = match body_read_timeout {
+        Some(timeout) => match tokio::time::timeout(timeout, body.collect()).await {
+            Ok(result) => result,
+            Err(_) => {
+                return Ok(Response::builder()
+                    .status(StatusCode::REQUEST_TIMEOUT)
+                    .header("content-type", "application/json")
+                    .body(Full::new(bytes::Bytes::from(
+                        r#"{"error":"Timed out reading body"}"#,
+                    )))
+                    .unwrap());
+            }
+        },
+        None => body.collect().await,
+    };
+    let body_bytes = match collected {
         Ok(collected) => collected.to_bytes(),
+        Err(e) if e.is::<LengthLimitError>() => {
+            return Ok(Response::builder()
+                .status(StatusCode::PAYLOAD_TOO_LARGE)
+                .header("content-type", "application/json")
+                .body(Full::new(bytes::Bytes::from(
+                    r#"{"error":"Request body too large"}"#,
+                )))
+                .unwrap());
+        }
         Err(_) => {
             return Ok(Response::builder()
                 .status(StatusCode::BAD_REQUEST)
@@ -92,8 +249,13 @@ pub async fn handle_webhook(
         }
     };
 
-    let processor = WebhookProcessor::new(state.registry);
-    let response = processor.process_webhook(&provider, generic_headers, &body_bytes);
+    let processor = &state.processor;
+    let response = match tenant {
+        Some(tenant) => {
+            processor.process_tenant_webhook(&tenant, &provider, generic_headers, body_bytes)
+        }
+        None => processor.process_webhook(&provider, generic_headers, body_bytes),
+    };
     Ok(HyperResponseConverter::from_webhook_response(response))
 }
 
@@ -107,9 +269,137 @@ pub fn make_service(
     }
 }
 
+/// Serve webhooks on `listener` until the listener fails.
+///
+/// Unlike wiring [`make_service`] into a bare accept loop, this applies the
+/// limits in `config`: a cap on concurrent connections, a body size limit,
+/// header, body and connection timeouts, and HTTP/1.1 + HTTP/2 protocol
+/// detection.
+///
+/// ```rust,ignore
+/// let listener = TcpListener::bind("0.0.0.0:3000").await?;
+/// sms_web_hyper::serve(listener, state, ServerConfig::default()).await?;
+/// ```
+pub async fn serve(
+    listener: TcpListener,
+    state: AppState,
+    config: ServerConfig,
+) -> std::io::Result<()> {
+    let slots = Arc::new(Semaphore::new(config.max_connections));
+
+    let mut builder = auto::Builder::new(TokioExecutor::new());
+    builder
+        .http1()
+        .timer(TokioTimer::new())
+        .header_read_timeout(config.header_read_timeout);
+    builder.http2().timer(TokioTimer::new());
+    if !config.http2 {
+        builder = builder.http1_only();
+    }
+
+    loop {
+        let permit = slots
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("semaphore is never closed");
+        let (stream, peer) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                warn!("Failed to accept connection: {}", e);
+                continue;
+            }
+        };
+
+        let builder = builder.clone();
+        let state = state.clone();
+        let body_read_timeout = config.body_read_timeout;
+        let max_body_bytes = config.max_body_bytes;
+        let connection_timeout = config.connection_timeout;
+        tokio::spawn(async move {
+            let _permit = permit;
+            let service = hyper::service::service_fn(move |req| {
+                handle_webhook_inner(req, state.clone(), Some(body_read_timeout), max_body_bytes)
+            });
+            let conn = builder.serve_connection(TokioIo::new(stream), service);
+            let result = match connection_timeout {
+                Some(limit) => match tokio::time::timeout(limit, conn).await {
+                    Ok(result) => result,
+                    Err(_) => {
+                        debug!("Closing connection from {} after {:?}", peer, limit);
+                        return;
+                    }
+                },
+                None => conn.await,
+            };
+            if let Err(e) = result {
+                debug!("Error serving connection from {}: {}", peer, e);
+            }
+        });
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use sms_core::InboundRegistry;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[test]
+    fn server_config_builders() {
+        let config = ServerConfig::new()
+            .with_max_connections(0)
+            .with_body_read_timeout(Duration::from_secs(5))
+            .with_max_body_bytes(64)
+            .with_connection_timeout(None)
+            .with_http2(false);
+        assert_eq!(config.max_connections, 1);
+        assert_eq!(config.body_read_timeout, Duration::from_secs(5));
+        assert_eq!(config.max_body_bytes, 64);
+        assert_eq!(config.connection_timeout, None);
+        assert!(!config.http2);
+    }
+
+    #[tokio::test]
+    async fn serve_answers_http1_requests() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let state = AppState::new(WebhookProcessor::new(InboundRegistry::new()));
+        tokio::spawn(serve(listener, state, ServerConfig::default()));
+
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        stream
+            .write_all(
+                b"POST /webhooks/unknown HTTP/1.1\r\nhost: localhost\r\n\
+                  content-length: 0\r\nconnection: close\r\n\r\n",
+            )
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 404"), "{}", response);
+    }
+
+    #[tokio::test]
+    async fn serve_rejects_oversized_bodies() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let state = AppState::new(WebhookProcessor::new(InboundRegistry::new()));
+        tokio::spawn(serve(listener, state, ServerConfig::new().with_max_body_bytes(4)));
+
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        stream
+            .write_all(
+                b"POST /webhooks/unknown HTTP/1.1\r\nhost: localhost\r\n\
+                  transfer-encoding: chunked\r\nconnection: close\r\n\r\n\
+                  5\r\nhello\r\n0\r\n\r\n",
+            )
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 413"), "{}", response);
+    }
 
     #[test]
     fn extract_provider_works() {
@@ -120,10 +410,25 @@ mod tests {
         assert_eq!(extract_provider_from_path(&uri), None);
     }
 
+    #[test]
+    fn extract_route_handles_tenants() {
+        let uri = "/webhooks/plivo".parse::<Uri>().unwrap();
+        assert_eq!(extract_route_from_path(&uri), Some((None, "plivo".to_string())));
+
+        let uri = "/webhooks/acme/plivo".parse::<Uri>().unwrap();
+        assert_eq!(
+            extract_route_from_path(&uri),
+            Some((Some("acme".to_string()), "plivo".to_string()))
+        );
+
+        let uri = "/webhooks/a/b/c".parse::<Uri>().unwrap();
+        assert_eq!(extract_route_from_path(&uri), None);
+    }
+
     #[tokio::test]
     async fn hyper_service_compiles() {
         let registry = InboundRegistry::new();
-        let state = AppState { registry };
+        let state = AppState::new(WebhookProcessor::new(registry));
         let _service = make_service(state);
     }
 }
@@ -4,12 +4,23 @@ use poem::{
     Request, Response, Result,
 };
 use bytes::Bytes;
-use sms_core::{Headers, InboundRegistry};
+use sms_core::Headers;
 use sms_web_generic::{HeaderConverter, ResponseConverter, WebhookProcessor};
+use std::sync::Arc;
 
 #[derive(Clone)]
 pub struct AppState {
-    pub registry: InboundRegistry,
+    /// The webhook pipeline, built once and shared by every request.
+    pub processor: Arc<WebhookProcessor>,
+}
+
+impl AppState {
+    /// Serve webhooks through `processor`.
+    pub fn new(processor: WebhookProcessor) -> Self {
+        Self {
+            processor: Arc::new(processor),
+        }
+    }
 }
 
 /// Poem-specific header converter
@@ -41,10 +52,19 @@ impl ResponseConverter for PoemResponseConverter {
         let status = StatusCode::from_u16(response.status.as_u16())
             .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
 
-        Response::builder()
+        let mut res = Response::builder()
             .status(status)
             .header("content-type", response.content_type)
-            .body(response.body)
+            .body(response.body);
+        for (name, value) in response.headers {
+            if let (Ok(name), Ok(value)) = (
+                poem::http::HeaderName::from_bytes(name.as_bytes()),
+                poem::http::HeaderValue::from_str(&value),
+            ) {
+                res.headers_mut().append(name, value);
+            }
+        }
+        res
     }
 }
 
@@ -55,19 +75,33 @@ pub async fn unified_webhook(
     body: Bytes,
     Data(state): Data<&AppState>,
 ) -> Result<Response> {
-    let processor = WebhookProcessor::new(state.registry.clone());
+    let processor = &state.processor;
+    let generic_headers = PoemHeaderConverter::to_generic_headers(req.headers());
+    let response = processor.process_webhook(&provider, generic_headers, body);
+    Ok(PoemResponseConverter::from_webhook_response(response))
+}
+
+/// Multi-tenant webhook handler for Poem
+pub async fn tenant_webhook(
+    req: &Request,
+    Path((tenant, provider)): Path<(String, String)>,
+    body: Bytes,
+    Data(state): Data<&AppState>,
+) -> Result<Response> {
+    let processor = &state.processor;
     let generic_headers = PoemHeaderConverter::to_generic_headers(req.headers());
-    let response = processor.process_webhook(&provider, generic_headers, &body);
+    let response = processor.process_tenant_webhook(&tenant, &provider, generic_headers, body);
     Ok(PoemResponseConverter::from_webhook_response(response))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use sms_core::InboundRegistry;
 
     #[test]
     fn poem_types_compile() {
         let registry = InboundRegistry::new();
-        let _state = AppState { registry };
+        let _state = AppState::new(WebhookProcessor::new(registry));
     }
 }
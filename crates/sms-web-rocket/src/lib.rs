@@ -4,13 +4,24 @@
 //! processing.
 
 use rocket::{http::Status, Request, State};
-use sms_core::{Headers, InboundRegistry};
+use sms_core::Headers;
 use sms_web_generic::{ResponseConverter, WebhookProcessor};
+use std::sync::Arc;
 
-/// Shared application state holding the provider registry.
+/// Shared application state holding the webhook processor.
 #[derive(Clone)]
 pub struct AppState {
-    pub registry: InboundRegistry,
+    /// The webhook pipeline, built once and shared by every request.
+    pub processor: Arc<WebhookProcessor>,
+}
+
+impl AppState {
+    /// Serve webhooks through `processor`.
+    pub fn new(processor: WebhookProcessor) -> Self {
+        Self {
+            processor: Arc::new(processor),
+        }
+    }
 }
 
 /// Raw body data extractor for Rocket.
@@ -58,27 +69,50 @@ impl<'r> rocket::request::FromRequest<'r> for ExtractedHeaders {
     }
 }
 
+/// A webhook response as a Rocket responder, including any extra headers
+/// such as `Retry-After`.
+#[derive(Debug)]
+pub struct RocketWebhookResponse {
+    pub status: Status,
+    pub content_type: rocket::http::ContentType,
+    /// Headers beyond `Content-Type`.
+    pub headers: Headers,
+    pub body: String,
+}
+
+impl<'r> rocket::response::Responder<'r, 'static> for RocketWebhookResponse {
+    fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
+        let mut res = rocket::Response::build_from((self.content_type, self.body).respond_to(req)?);
+        res.status(self.status);
+        for (name, value) in self.headers {
+            res.raw_header_adjoin(name, value);
+        }
+        res.ok()
+    }
+}
+
 /// Rocket-specific response converter.
 pub struct RocketResponseConverter;
 
 impl ResponseConverter for RocketResponseConverter {
-    type ResponseType = (Status, (rocket::http::ContentType, String));
+    type ResponseType = RocketWebhookResponse;
 
     fn from_webhook_response(response: sms_core::WebhookResponse) -> Self::ResponseType {
-        let status = match response.status.as_u16() {
-            200 => Status::Ok,
-            400 => Status::BadRequest,
-            401 => Status::Unauthorized,
-            404 => Status::NotFound,
-            _ => Status::InternalServerError,
-        };
+        let status =
+            Status::from_code(response.status.as_u16()).unwrap_or(Status::InternalServerError);
 
         let content_type = match response.content_type.as_str() {
             "application/json" => rocket::http::ContentType::JSON,
+            "text/xml" | "application/xml" => rocket::http::ContentType::XML,
             _ => rocket::http::ContentType::Plain,
         };
 
-        (status, (content_type, response.body))
+        RocketWebhookResponse {
+            status,
+            content_type,
+            headers: response.headers,
+            body: response.body,
+        }
     }
 }
 
@@ -92,20 +126,37 @@ pub fn unified_webhook(
     body: RawBody,
     extracted: ExtractedHeaders,
     state: &State<AppState>,
-) -> (Status, (rocket::http::ContentType, String)) {
-    let processor = WebhookProcessor::new(state.registry.clone());
-    let response = processor.process_webhook(&provider, extracted.0, &body.0);
+) -> RocketWebhookResponse {
+    let processor = &state.processor;
+    let response = processor.process_webhook(&provider, extracted.0, body.0.into());
+    RocketResponseConverter::from_webhook_response(response)
+}
+
+/// Multi-tenant webhook handler for Rocket.
+///
+/// Looks the provider up in the registry of the tenant named in the path.
+#[rocket::post("/webhooks/<tenant>/<provider>", data = "<body>")]
+pub fn tenant_webhook(
+    tenant: String,
+    provider: String,
+    body: RawBody,
+    extracted: ExtractedHeaders,
+    state: &State<AppState>,
+) -> RocketWebhookResponse {
+    let processor = &state.processor;
+    let response = processor.process_tenant_webhook(&tenant, &provider, extracted.0, body.0.into());
     RocketResponseConverter::from_webhook_response(response)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use sms_core::InboundRegistry;
 
     #[test]
     fn rocket_types_compile() {
         let registry = InboundRegistry::new();
-        let _state = AppState { registry };
+        let _state = AppState::new(WebhookProcessor::new(registry));
     }
 
     #[test]
@@ -114,17 +165,22 @@ mod tests {
             status: sms_core::HttpStatus::Ok,
             body: "{}".into(),
             content_type: "application/json".into(),
+            headers: vec![("Cache-Control".into(), "no-store".into())],
         };
-        let (status, (ct, body)) = RocketResponseConverter::from_webhook_response(resp);
-        assert_eq!(status, Status::Ok);
-        assert_eq!(ct, rocket::http::ContentType::JSON);
-        assert_eq!(body, "{}");
+        let resp = RocketResponseConverter::from_webhook_response(resp);
+        assert_eq!(resp.status, Status::Ok);
+        assert_eq!(resp.content_type, rocket::http::ContentType::JSON);
+        assert_eq!(
+            resp.headers,
+            vec![("Cache-Control".into(), "no-store".into())]
+        );
+        assert_eq!(resp.body, "{}");
     }
 
     #[test]
     fn response_converter_handles_error_status() {
         let resp = sms_core::WebhookResponse::error(sms_core::HttpStatus::NotFound, "not found");
-        let (status, _) = RocketResponseConverter::from_webhook_response(resp);
-        assert_eq!(status, Status::NotFound);
+        let resp = RocketResponseConverter::from_webhook_response(resp);
+        assert_eq!(resp.status, Status::NotFound);
     }
 }
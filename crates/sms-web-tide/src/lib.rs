@@ -1,10 +1,25 @@
-use sms_core::{Headers, InboundRegistry};
+use async_std::io::ReadExt;
+use sms_core::Headers;
 use sms_web_generic::{HeaderConverter, ResponseConverter, WebhookProcessor};
-use tide::{Request, Response, Result, StatusCode};
+use std::sync::Arc;
+use tide::{Next, Request, Response, Result, StatusCode};
+
+/// Default body size limit, matching the Rocket adapter's `2.megabytes()`.
+pub const DEFAULT_MAX_BODY_BYTES: usize = 2 * 1024 * 1024;
 
 #[derive(Clone)]
 pub struct AppState {
-    pub registry: InboundRegistry,
+    /// The webhook pipeline, built once and shared by every request.
+    pub processor: Arc<WebhookProcessor>,
+}
+
+impl AppState {
+    /// Serve webhooks through `processor`.
+    pub fn new(processor: WebhookProcessor) -> Self {
+        Self {
+            processor: Arc::new(processor),
+        }
+    }
 }
 
 /// Tide-specific header converter
@@ -40,13 +55,17 @@ impl ResponseConverter for TideResponseConverter {
         let mut res = Response::new(status);
         res.set_body(response.body);
 
-        // Parse content type, defaulting to application/json
-        let content_type = if response.content_type == "application/json" {
-            tide::http::mime::JSON
-        } else {
-            tide::http::mime::PLAIN
+        let content_type = match response.content_type.as_str() {
+            "application/json" => tide::http::mime::JSON,
+            "text/xml" | "application/xml" => tide::http::mime::XML,
+            _ => tide::http::mime::PLAIN,
         };
         res.set_content_type(content_type);
+        for (name, value) in response.headers {
+            if let Ok(name) = name.parse::<tide::http::headers::HeaderName>() {
+                res.append_header(name, value.as_str());
+            }
+        }
         Ok(res)
     }
 }
@@ -55,26 +74,189 @@ impl ResponseConverter for TideResponseConverter {
 pub async fn unified_webhook(mut req: Request<AppState>) -> Result<Response> {
     let provider = req.param("provider")?.to_string();
     let body = req.body_bytes().await?;
-    let processor = WebhookProcessor::new(req.state().registry.clone());
+    let processor = &req.state().processor;
     let generic_headers = TideHeaderConverter::to_generic_headers(&req);
-    let response = processor.process_webhook(&provider, generic_headers, &body);
+    let response = processor.process_webhook(&provider, generic_headers, body.into());
     TideResponseConverter::from_webhook_response(response)
 }
 
+/// Multi-tenant webhook handler for Tide
+pub async fn tenant_webhook(mut req: Request<AppState>) -> Result<Response> {
+    let tenant = req.param("tenant")?.to_string();
+    let provider = req.param("provider")?.to_string();
+    let body = req.body_bytes().await?;
+    let processor = &req.state().processor;
+    let generic_headers = TideHeaderConverter::to_generic_headers(&req);
+    let response = processor.process_tenant_webhook(&tenant, &provider, generic_headers, body.into());
+    TideResponseConverter::from_webhook_response(response)
+}
+
+/// Middleware that bounds webhook bodies before any handler buffers them.
+///
+/// Requests whose body exceeds `max_body_bytes` get 413; requests with a
+/// `Content-Type` outside `content_types` get 415.  Requests without a
+/// `Content-Type`, or any type when `content_types` is empty, are let
+/// through.
+#[derive(Debug, Clone)]
+pub struct BodyGuard {
+    /// Largest accepted body, in bytes.
+    pub max_body_bytes: usize,
+    /// Accepted media types (without parameters such as `charset`).
+    pub content_types: Vec<String>,
+}
+
+impl Default for BodyGuard {
+    fn default() -> Self {
+        Self {
+            max_body_bytes: DEFAULT_MAX_BODY_BYTES,
+            content_types: vec![
+                "application/x-www-form-urlencoded".into(),
+                "application/json".into(),
+                "text/plain".into(),
+            ],
+        }
+    }
+}
+
+impl BodyGuard {
+    /// Create a guard with [`DEFAULT_MAX_BODY_BYTES`] and the media types
+    /// used by the bundled providers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the body size limit.
+    pub fn with_max_body_bytes(mut self, max: usize) -> Self {
+        self.max_body_bytes = max;
+        self
+    }
+
+    /// Also accept `content_type`.
+    pub fn allow_content_type(mut self, content_type: impl Into<String>) -> Self {
+        self.content_types.push(content_type.into());
+        self
+    }
+
+    /// Replace the accepted media types; an empty list accepts any type.
+    pub fn with_content_types<I, S>(mut self, content_types: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.content_types = content_types.into_iter().map(Into::into).collect();
+        self
+    }
+
+    fn accepts(&self, content_type: &str) -> bool {
+        self.content_types.is_empty()
+            || self
+                .content_types
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(content_type))
+    }
+}
+
+#[tide::utils::async_trait]
+impl<State: Clone + Send + Sync + 'static> tide::Middleware<State> for BodyGuard {
+    async fn handle(&self, mut req: Request<State>, next: Next<'_, State>) -> Result {
+        if req
+            .content_type()
+            .is_some_and(|mime| !self.accepts(mime.essence()))
+        {
+            return Ok(Response::new(StatusCode::UnsupportedMediaType));
+        }
+        if req.len().is_some_and(|len| len > self.max_body_bytes) {
+            return Ok(Response::new(StatusCode::PayloadTooLarge));
+        }
+
+        // Content-Length may be absent (chunked) or wrong, so cap the read too.
+        let mut body = Vec::new();
+        req.take_body()
+            .take(self.max_body_bytes as u64 + 1)
+            .read_to_end(&mut body)
+            .await?;
+        if body.len() > self.max_body_bytes {
+            return Ok(Response::new(StatusCode::PayloadTooLarge));
+        }
+        req.set_body(body);
+        Ok(next.run(req).await)
+    }
+}
+
 /// Helper function to configure Tide routes
+///
+/// Routes are guarded by [`BodyGuard::default()`]; use
+/// [`configure_routes_with_guard`] to change the limits.
 pub fn configure_routes(app: &mut tide::Server<AppState>) {
-    app.at("/webhooks/:provider").post(unified_webhook);
+    configure_routes_with_guard(app, BodyGuard::default());
+}
+
+/// Configure the webhook routes behind a custom [`BodyGuard`].
+pub fn configure_routes_with_guard(app: &mut tide::Server<AppState>, guard: BodyGuard) {
+    app.at("/webhooks/:provider")
+        .with(guard.clone())
+        .post(unified_webhook);
+    app.at("/webhooks/:tenant/:provider")
+        .with(guard)
+        .post(tenant_webhook);
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use sms_core::InboundRegistry;
 
     #[test]
     fn tide_types_compile() {
         let registry = InboundRegistry::new();
-        let _state = AppState { registry };
+        let _state = AppState::new(WebhookProcessor::new(registry));
         // let mut app = tide::with_state(state);
         // configure_routes(&mut app);
     }
+
+    fn guarded_app(guard: BodyGuard) -> tide::Server<AppState> {
+        let mut app =
+            tide::with_state(AppState::new(WebhookProcessor::new(InboundRegistry::new())));
+        configure_routes_with_guard(&mut app, guard);
+        app
+    }
+
+    fn post(content_type: &str, body: &str) -> tide::http::Request {
+        let url = tide::http::Url::parse("http://localhost/webhooks/unknown").unwrap();
+        let mut req = tide::http::Request::new(tide::http::Method::Post, url);
+        req.insert_header("content-type", content_type);
+        req.set_body(body);
+        req
+    }
+
+    #[async_std::test]
+    async fn body_guard_rejects_oversized_body() {
+        let app = guarded_app(BodyGuard::new().with_max_body_bytes(4));
+        let res: tide::http::Response = app
+            .respond(post("application/x-www-form-urlencoded", "a=12345"))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::PayloadTooLarge);
+    }
+
+    #[async_std::test]
+    async fn body_guard_rejects_unexpected_content_type() {
+        let app = guarded_app(BodyGuard::new());
+        let res: tide::http::Response = app
+            .respond(post("application/octet-stream", "x"))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::UnsupportedMediaType);
+    }
+
+    #[async_std::test]
+    async fn body_guard_passes_valid_requests_through() {
+        let app = guarded_app(BodyGuard::new());
+        let res: tide::http::Response = app
+            .respond(post("application/json; charset=utf-8", "{}"))
+            .await
+            .unwrap();
+        // Reaches the handler, which has no provider registered.
+        assert_eq!(res.status(), StatusCode::NotFound);
+    }
 }
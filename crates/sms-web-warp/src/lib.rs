@@ -1,11 +1,29 @@
-use bytes::Bytes;
-use sms_core::{Headers, InboundRegistry};
-use sms_web_generic::{HeaderConverter, ResponseConverter, WebhookProcessor};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use futures_util::{Stream, StreamExt};
+use sms_core::{Headers, HttpStatus, WebhookError, WebhookResponse};
+use sms_web_generic::{HeaderConverter, ResponseConverter, WebhookProcessor, WebhookRateLimiter};
+use std::convert::Infallible;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use warp::http::header::{HeaderName, HeaderValue};
 use warp::{http::HeaderMap, hyper::StatusCode, Filter, Rejection, Reply};
 
+/// Default body size limit, matching the Rocket adapter's `2.megabytes()`.
+pub const DEFAULT_MAX_BODY_BYTES: u64 = 2 * 1024 * 1024;
+
 #[derive(Clone)]
 pub struct AppState {
-    pub registry: InboundRegistry,
+    /// The webhook pipeline, built once and shared by every request.
+    pub processor: Arc<WebhookProcessor>,
+}
+
+impl AppState {
+    /// Serve webhooks through `processor`.
+    pub fn new(processor: WebhookProcessor) -> Self {
+        Self {
+            processor: Arc::new(processor),
+        }
+    }
 }
 
 /// Warp-specific header converter
@@ -37,11 +55,20 @@ impl ResponseConverter for WarpResponseConverter {
         let status = StatusCode::from_u16(response.status.as_u16())
             .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
 
-        warp::reply::with_status(
+        let mut res = warp::reply::with_status(
             warp::reply::with_header(response.body, "content-type", response.content_type),
             status,
         )
-        .into_response()
+        .into_response();
+        for (name, value) in response.headers {
+            if let (Ok(name), Ok(value)) = (
+                HeaderName::from_bytes(name.as_bytes()),
+                HeaderValue::from_str(&value),
+            ) {
+                res.headers_mut().append(name, value);
+            }
+        }
+        res
     }
 }
 
@@ -52,33 +79,466 @@ pub async fn unified_webhook_handler(
     body: Bytes,
     state: AppState,
 ) -> Result<warp::reply::Response, Rejection> {
-    let processor = WebhookProcessor::new(state.registry);
+    let processor = &state.processor;
+    let generic_headers = WarpHeaderConverter::to_generic_headers(&headers);
+    let response = processor.process_webhook(&provider, generic_headers, body);
+    Ok(WarpResponseConverter::from_webhook_response(response))
+}
+
+/// Multi-tenant webhook handler for Warp
+pub async fn tenant_webhook_handler(
+    tenant: String,
+    provider: String,
+    headers: HeaderMap,
+    body: Bytes,
+    state: AppState,
+) -> Result<warp::reply::Response, Rejection> {
+    let processor = &state.processor;
     let generic_headers = WarpHeaderConverter::to_generic_headers(&headers);
-    let response = processor.process_webhook(&provider, generic_headers, &body);
+    let response = processor.process_tenant_webhook(&tenant, &provider, generic_headers, body);
     Ok(WarpResponseConverter::from_webhook_response(response))
 }
 
 /// Helper function to create a Warp filter for SMS webhooks
+///
+/// Serves both `/webhooks/{provider}` and `/webhooks/{tenant}/{provider}`.
+/// Bodies over [`DEFAULT_MAX_BODY_BYTES`] are rejected; finish the route tree
+/// with `.recover(handle_rejection)` to answer that, and warp's own
+/// rejections, with JSON errors:
+///
+/// ```rust,ignore
+/// let routes = webhook_filter(state).or(other_routes).recover(handle_rejection);
+/// ```
 pub fn webhook_filter(
     state: AppState,
 ) -> impl warp::Filter<Extract = (warp::reply::Response,), Error = Rejection> + Clone {
-    warp::path!("webhooks" / String)
+    let tenant_state = state.clone();
+    let single = warp::path!("webhooks" / String)
+        .and(warp::post())
+        .and(warp::header::headers_cloned())
+        .and(limited_body(DEFAULT_MAX_BODY_BYTES))
+        .and(warp::any().map(move || state.clone()))
+        .and_then(unified_webhook_handler);
+    let tenant = warp::path!("webhooks" / String / String)
+        .and(warp::post())
+        .and(warp::header::headers_cloned())
+        .and(limited_body(DEFAULT_MAX_BODY_BYTES))
+        .and(warp::any().map(move || tenant_state.clone()))
+        .and_then(tenant_webhook_handler);
+    single.or(tenant).unify()
+}
+
+/// A rejection raised by this crate's filters.  [`handle_rejection`] turns
+/// it into the same JSON error the other adapters return.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum WebhookRejection {
+    /// The body is longer than `limit` bytes.
+    PayloadTooLarge { limit: u64 },
+    /// Webhook processing failed before a response was built.
+    Webhook(WebhookError),
+}
+
+impl warp::reject::Reject for WebhookRejection {}
+
+impl From<WebhookError> for WebhookRejection {
+    fn from(error: WebhookError) -> Self {
+        Self::Webhook(error)
+    }
+}
+
+/// Reject requests whose `Content-Length` exceeds `max_bytes` with
+/// [`WebhookRejection::PayloadTooLarge`], before the body is buffered.
+///
+/// Requests without a `Content-Length` (chunked uploads) are let through;
+/// use [`limited_body`] to also cap what is actually read.
+pub fn body_limit(max_bytes: u64) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    warp::header::optional::<u64>("content-length")
+        .and_then(move |length: Option<u64>| async move {
+            match length {
+                Some(length) if length > max_bytes => {
+                    Err(warp::reject::custom(WebhookRejection::PayloadTooLarge {
+                        limit: max_bytes,
+                    }))
+                }
+                _ => Ok(()),
+            }
+        })
+        .untuple_one()
+}
+
+/// Buffer the request body, rejecting it with
+/// [`WebhookRejection::PayloadTooLarge`] once it exceeds `max_bytes`.
+///
+/// Unlike [`body_limit`] followed by `warp::body::bytes()`, this also caps
+/// chunked bodies and bodies longer than their `Content-Length`.
+pub fn limited_body(max_bytes: u64) -> impl Filter<Extract = (Bytes,), Error = Rejection> + Clone {
+    body_limit(max_bytes)
+        .and(warp::body::stream())
+        .and_then(move |body| read_limited(body, max_bytes))
+}
+
+async fn read_limited<S, B>(body: S, max_bytes: u64) -> Result<Bytes, Rejection>
+where
+    S: Stream<Item = Result<B, warp::Error>>,
+    B: Buf,
+{
+    let mut body = std::pin::pin!(body);
+    let mut buffer = BytesMut::new();
+    while let Some(chunk) = body.next().await {
+        let chunk = chunk.map_err(|e| {
+            warp::reject::custom(WebhookRejection::Webhook(WebhookError::ParseError(
+                format!("failed to read body: {}", e),
+            )))
+        })?;
+        if (buffer.len() + chunk.remaining()) as u64 > max_bytes {
+            return Err(warp::reject::custom(WebhookRejection::PayloadTooLarge {
+                limit: max_bytes,
+            }));
+        }
+        buffer.put(chunk);
+    }
+    Ok(buffer.freeze())
+}
+
+/// Turn a rejection into a JSON error response, for `Filter::recover`.
+///
+/// Handles [`WebhookRejection`]s and warp's built-in rejections (unknown
+/// route, wrong method, bad or missing headers, unsupported media type);
+/// anything else becomes a 500.
+pub async fn handle_rejection(rejection: Rejection) -> Result<warp::reply::Response, Infallible> {
+    let response = if rejection.is_not_found() {
+        WebhookResponse::error(HttpStatus::NotFound, "not found")
+    } else if let Some(rejection) = rejection.find::<WebhookRejection>() {
+        match rejection {
+            WebhookRejection::PayloadTooLarge { limit } => WebhookResponse::error(
+                HttpStatus::PayloadTooLarge,
+                &format!("body exceeds {} bytes", limit),
+            ),
+            WebhookRejection::Webhook(error) => {
+                let response = WebhookResponse::error(error.http_status(), &error.to_string());
+                match error {
+                    WebhookError::RateLimited(retry_after) => response
+                        .with_header("retry-after", retry_after.as_secs().max(1).to_string()),
+                    _ => response,
+                }
+            }
+        }
+    } else if let Some(e) = rejection.find::<warp::reject::PayloadTooLarge>() {
+        WebhookResponse::error(HttpStatus::PayloadTooLarge, &e.to_string())
+    } else if let Some(e) = rejection.find::<warp::reject::UnsupportedMediaType>() {
+        WebhookResponse::error(HttpStatus::UnsupportedMediaType, &e.to_string())
+    } else if let Some(e) = rejection.find::<warp::reject::MethodNotAllowed>() {
+        WebhookResponse::error(HttpStatus::MethodNotAllowed, &e.to_string())
+    } else if let Some(e) = rejection.find::<warp::reject::InvalidHeader>() {
+        WebhookResponse::error(HttpStatus::BadRequest, &e.to_string())
+    } else if let Some(e) = rejection.find::<warp::reject::MissingHeader>() {
+        WebhookResponse::error(HttpStatus::BadRequest, &e.to_string())
+    } else if let Some(e) = rejection.find::<warp::reject::LengthRequired>() {
+        WebhookResponse::error(HttpStatus::BadRequest, &e.to_string())
+    } else {
+        WebhookResponse::error(HttpStatus::InternalServerError, "internal error")
+    };
+    Ok(WarpResponseConverter::from_webhook_response(response))
+}
+
+/// Extract the caller's IP address.
+///
+/// With `trusted_proxy_hops` of zero the TCP peer address is used and no
+/// header is consulted, so callers can't choose their own rate-limit key.
+/// Behind `n` reverse proxies the client is the `n`th `X-Forwarded-For`
+/// entry from the right, or `X-Real-IP` when there is no `X-Forwarded-For`.
+/// A request with fewer entries than trusted hops bypassed the proxies and
+/// is keyed on its peer address.
+pub fn client_ip(
+    trusted_proxy_hops: usize,
+) -> impl Filter<Extract = (Option<IpAddr>,), Error = Rejection> + Clone {
+    warp::header::optional::<String>("x-forwarded-for")
+        .and(warp::header::optional::<String>("x-real-ip"))
+        .and(warp::addr::remote())
+        .map(
+            move |forwarded: Option<String>,
+                  real_ip: Option<String>,
+                  remote: Option<SocketAddr>| {
+                let peer = remote.map(|addr| addr.ip());
+                if trusted_proxy_hops == 0 {
+                    return peer;
+                }
+                proxied_ip(forwarded, real_ip, trusted_proxy_hops).or(peer)
+            },
+        )
+}
+
+fn proxied_ip(
+    forwarded: Option<String>,
+    real_ip: Option<String>,
+    trusted_proxy_hops: usize,
+) -> Option<IpAddr> {
+    let Some(forwarded) = forwarded else {
+        return real_ip?.trim().parse().ok();
+    };
+    let entries: Vec<&str> = forwarded
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .collect();
+    let index = entries.len().checked_sub(trusted_proxy_hops)?;
+    entries[index].parse().ok()
+}
+
+/// Consult `limiter` for a webhook call from `ip`.
+///
+/// A resolved IP goes through [`WebhookRateLimiter::check_client_webhook`],
+/// so `smskit`'s `RateLimiter` keys on it without trusting any header.  On
+/// rejection the returned response is a 429 with a `Retry-After` header.
+pub fn check_rate_limit(
+    limiter: &dyn WebhookRateLimiter,
+    tenant: Option<&str>,
+    provider: &str,
+    ip: Option<IpAddr>,
+) -> Result<(), Box<warp::reply::Response>> {
+    let result = match (ip, tenant) {
+        (Some(ip), _) => limiter.check_client_webhook(tenant, provider, ip),
+        (None, Some(t)) => limiter.check_tenant_webhook(t, provider, &Headers::new()),
+        (None, None) => limiter.check_webhook(provider, &Headers::new()),
+    };
+    result.map_err(|retry_after| {
+        let secs = retry_after.as_secs().max(1);
+        let response = WebhookResponse::error(
+            HttpStatus::TooManyRequests,
+            &format!("rate limit exceeded; retry after {}s", secs),
+        )
+        .with_header("retry-after", secs.to_string());
+        Box::new(WarpResponseConverter::from_webhook_response(response))
+    })
+}
+
+/// Rate-limited variant of [`unified_webhook_handler`].
+pub async fn rate_limited_webhook_handler(
+    provider: String,
+    ip: Option<IpAddr>,
+    headers: HeaderMap,
+    body: Bytes,
+    state: AppState,
+    limiter: Arc<dyn WebhookRateLimiter>,
+) -> Result<warp::reply::Response, Rejection> {
+    if state.processor.has_provider(None, &provider)
+        && let Err(response) = check_rate_limit(limiter.as_ref(), None, &provider, ip)
+    {
+        return Ok(*response);
+    }
+    unified_webhook_handler(provider, headers, body, state).await
+}
+
+/// Rate-limited variant of [`tenant_webhook_handler`].
+pub async fn rate_limited_tenant_webhook_handler(
+    tenant: String,
+    provider: String,
+    ip: Option<IpAddr>,
+    headers: HeaderMap,
+    body: Bytes,
+    state: AppState,
+    limiter: Arc<dyn WebhookRateLimiter>,
+) -> Result<warp::reply::Response, Rejection> {
+    if state.processor.has_provider(Some(&tenant), &provider)
+        && let Err(response) = check_rate_limit(limiter.as_ref(), Some(&tenant), &provider, ip)
+    {
+        return Ok(*response);
+    }
+    tenant_webhook_handler(tenant, provider, headers, body, state).await
+}
+
+/// Like [`webhook_filter`], but throttles each call through `limiter`
+/// (keyed on [`client_ip`]) before the webhook is processed.
+///
+/// `trusted_proxy_hops` is the number of reverse proxies in front of the
+/// server; leave it at zero unless every request passes through them.
+pub fn rate_limited_webhook_filter(
+    state: AppState,
+    limiter: Arc<dyn WebhookRateLimiter>,
+    trusted_proxy_hops: usize,
+) -> impl warp::Filter<Extract = (warp::reply::Response,), Error = Rejection> + Clone {
+    let tenant_state = state.clone();
+    let tenant_limiter = limiter.clone();
+    let single = warp::path!("webhooks" / String)
         .and(warp::post())
+        .and(client_ip(trusted_proxy_hops))
         .and(warp::header::headers_cloned())
-        .and(warp::body::bytes())
+        .and(limited_body(DEFAULT_MAX_BODY_BYTES))
         .and(warp::any().map(move || state.clone()))
-        .and_then(unified_webhook_handler)
+        .and(warp::any().map(move || limiter.clone()))
+        .and_then(rate_limited_webhook_handler);
+    let tenant = warp::path!("webhooks" / String / String)
+        .and(warp::post())
+        .and(client_ip(trusted_proxy_hops))
+        .and(warp::header::headers_cloned())
+        .and(limited_body(DEFAULT_MAX_BODY_BYTES))
+        .and(warp::any().map(move || tenant_state.clone()))
+        .and(warp::any().map(move || tenant_limiter.clone()))
+        .and_then(rate_limited_tenant_webhook_handler);
+    single.or(tenant).unify()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use sms_core::InboundRegistry;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    /// Allows one call per key, where the key is the forwarded IP.
+    #[derive(Default)]
+    struct OncePerIp {
+        seen: Mutex<Vec<String>>,
+    }
+
+    impl WebhookRateLimiter for OncePerIp {
+        fn check_webhook(&self, _provider: &str, headers: &Headers) -> Result<(), Duration> {
+            let ip = headers
+                .iter()
+                .find(|(k, _)| k == "x-forwarded-for")
+                .map(|(_, v)| v.clone())
+                .unwrap_or_default();
+            let mut seen = self.seen.lock().unwrap();
+            if seen.contains(&ip) {
+                return Err(Duration::from_secs(30));
+            }
+            seen.push(ip);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn client_ip_ignores_headers_without_trusted_proxies() {
+        let peer: SocketAddr = "198.51.100.9:5000".parse().unwrap();
+        let ip = warp::test::request()
+            .remote_addr(peer)
+            .header("x-forwarded-for", "203.0.113.7")
+            .header("x-real-ip", "203.0.113.7")
+            .filter(&client_ip(0))
+            .await
+            .unwrap();
+        assert_eq!(ip, Some(peer.ip()));
+    }
+
+    #[tokio::test]
+    async fn client_ip_trusts_entries_added_by_proxies() {
+        let peer: SocketAddr = "10.0.0.1:5000".parse().unwrap();
+        let ip = warp::test::request()
+            .remote_addr(peer)
+            .header("x-forwarded-for", "192.0.2.66, 203.0.113.7")
+            .filter(&client_ip(1))
+            .await
+            .unwrap();
+        assert_eq!(ip, Some("203.0.113.7".parse().unwrap()));
+
+        let ip = warp::test::request()
+            .remote_addr(peer)
+            .header("x-real-ip", "203.0.113.8")
+            .filter(&client_ip(1))
+            .await
+            .unwrap();
+        assert_eq!(ip, Some("203.0.113.8".parse().unwrap()));
+
+        // Fewer entries than hops: the request skipped a proxy.
+        let ip = warp::test::request()
+            .remote_addr(peer)
+            .header("x-forwarded-for", "203.0.113.7")
+            .filter(&client_ip(2))
+            .await
+            .unwrap();
+        assert_eq!(ip, Some(peer.ip()));
+    }
+
+    #[tokio::test]
+    async fn rate_limited_filter_returns_429_with_retry_after() {
+        let state = AppState::new(WebhookProcessor::new(InboundRegistry::new()));
+        let filter = rate_limited_webhook_filter(state, Arc::new(OncePerIp::default()), 0);
+        let peer: SocketAddr = "192.0.2.1:4000".parse().unwrap();
+
+        let first = warp::test::request()
+            .method("POST")
+            .path("/webhooks/plivo")
+            .remote_addr(peer)
+            .reply(&filter)
+            .await;
+        assert_eq!(first.status(), StatusCode::NOT_FOUND);
+
+        let second = warp::test::request()
+            .method("POST")
+            .path("/webhooks/plivo")
+            .remote_addr(peer)
+            .reply(&filter)
+            .await;
+        assert_eq!(second.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(second.headers()["retry-after"], "30");
+    }
+
+    #[tokio::test]
+    async fn body_is_capped_without_content_length() {
+        let chunks = || {
+            futures_util::stream::iter([
+                Ok::<_, warp::Error>(Bytes::from_static(b"abc")),
+                Ok(Bytes::from_static(b"def")),
+            ])
+        };
+        assert_eq!(read_limited(chunks(), 6).await.unwrap(), "abcdef");
+        let rejection = read_limited(chunks(), 4).await.unwrap_err();
+        assert!(matches!(
+            rejection.find::<WebhookRejection>(),
+            Some(WebhookRejection::PayloadTooLarge { limit: 4 })
+        ));
+    }
+
+    #[tokio::test]
+    async fn rejections_become_json_errors() {
+        let state = AppState::new(WebhookProcessor::new(InboundRegistry::new()));
+        let routes = webhook_filter(state).recover(handle_rejection);
+
+        let too_large = warp::test::request()
+            .method("POST")
+            .path("/webhooks/plivo")
+            .header("content-length", (DEFAULT_MAX_BODY_BYTES + 1).to_string())
+            .reply(&routes)
+            .await;
+        assert_eq!(too_large.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(too_large.headers()["content-type"], "application/json");
+
+        let wrong_method = warp::test::request()
+            .method("GET")
+            .path("/webhooks/plivo")
+            .reply(&routes)
+            .await;
+        assert_eq!(wrong_method.status(), StatusCode::METHOD_NOT_ALLOWED);
+
+        let unknown_route = warp::test::request()
+            .method("POST")
+            .path("/elsewhere")
+            .reply(&routes)
+            .await;
+        assert_eq!(unknown_route.status(), StatusCode::NOT_FOUND);
+        let body: serde_json::Value = serde_json::from_slice(unknown_route.body()).unwrap();
+        assert_eq!(body["error"], "not found");
+    }
+
+    #[tokio::test]
+    async fn custom_rejection_carries_retry_after() {
+        let limited = warp::any()
+            .and_then(|| async {
+                Err::<String, _>(warp::reject::custom(WebhookRejection::from(
+                    WebhookError::RateLimited(Duration::from_secs(12)),
+                )))
+            })
+            .recover(handle_rejection);
+        let res = warp::test::request().reply(&limited).await;
+        assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(res.headers()["retry-after"], "12");
+    }
 
     #[tokio::test]
     async fn webhook_filter_compiles() {
         let registry = InboundRegistry::new();
-        let state = AppState { registry };
+        let state = AppState::new(WebhookProcessor::new(registry));
         let _filter = webhook_filter(state);
     }
 }
@@ -0,0 +1,82 @@
+//! Send a templated message to every recipient in a CSV or JSONL file.
+//!
+//! Providers come from the usual layered configuration (`config/*.toml` and
+//! `SMSKIT__*` variables); set `SMSKIT__DRY_RUN=true` to rehearse.
+//!
+//! ```text
+//! cargo run --example bulk_send -- --file recipients.csv --from +15550001111 \
+//!     --template "Hi {name}, your code is {code}" --errors errors.csv
+//! ```
+use smskit::bulk::{BulkImport, parse_csv, parse_jsonl};
+use smskit::config::AppConfig;
+use smskit::outbox::{Outbox, OutboxWorker};
+use std::sync::Arc;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let file = arg_or_env("--file", "SMS_BULK_FILE");
+    let from = arg_or_env("--from", "SMS_FROM");
+    let template = arg_or_env("--template", "SMS_TEXT");
+    let errors_path = arg("--errors");
+
+    let text = std::fs::read_to_string(&file)?;
+    let (rows, mut errors) = if file.ends_with(".jsonl") {
+        parse_jsonl(&text)
+    } else {
+        parse_csv(&text)
+    };
+
+    let outbox = Outbox::new();
+    let report = BulkImport::new(from, template)
+        .on_progress(|p| {
+            eprint!(
+                "\rqueued {}/{} ({} rejected)",
+                p.queued, p.total, p.rejected
+            )
+        })
+        .run(rows, &outbox)
+        .await;
+    eprintln!();
+
+    let router = AppConfig::load()?.build_router()?;
+    let worker = OutboxWorker::new(outbox, Arc::new(router));
+    let (mut sent, mut failed) = (0, 0);
+    while let Some((_, result)) = worker.send_next().await {
+        match result {
+            Ok(_) => sent += 1,
+            Err(_) => failed += 1,
+        }
+        eprint!("\rsent {} / failed {}", sent, failed);
+    }
+    eprintln!();
+
+    errors.extend(report.errors.iter().cloned());
+    println!(
+        "{} sent, {} failed, {} duplicates skipped, {} rows rejected",
+        sent,
+        failed,
+        report.duplicates,
+        errors.len()
+    );
+    if let Some(path) = errors_path {
+        errors.sort_by_key(|e| e.line);
+        let report = smskit::bulk::BulkReport {
+            errors,
+            ..Default::default()
+        };
+        std::fs::write(path, report.error_csv())?;
+    }
+    Ok(())
+}
+
+fn arg(flag: &str) -> Option<String> {
+    let args: Vec<String> = std::env::args().collect();
+    let idx = args.iter().position(|a| a == flag)?;
+    args.get(idx + 1).cloned()
+}
+
+fn arg_or_env(flag: &str, env_key: &str) -> String {
+    arg(flag)
+        .or_else(|| std::env::var(env_key).ok())
+        .unwrap_or_else(|| panic!("missing {} (arg {} or env {})", flag, flag, env_key))
+}
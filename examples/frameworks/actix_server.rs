@@ -1,15 +1,15 @@
 //! Example SMS webhook server using Actix-web
-use std::sync::Arc;
 use actix_web::{web, App, HttpServer};
-use sms_core::InboundRegistry;
+use sms_core::registry;
 use sms_plivo::PlivoClient;
+use sms_web_generic::WebhookProcessor;
 use sms_web_actix::{configure_routes, AppData};
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     let plivo = PlivoClient::new("auth_id", "auth_token");
-    let registry = InboundRegistry::new().with(Arc::new(plivo));
-    let app_data = AppData { registry };
+    let registry = registry![plivo];
+    let app_data = AppData::new(WebhookProcessor::new(registry));
 
     println!("Actix-web SMS webhook server listening on http://localhost:3000");
     println!("Send webhooks to: POST http://localhost:3000/webhooks/plivo");
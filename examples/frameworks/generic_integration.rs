@@ -1,10 +1,9 @@
 //! Example showing how to integrate smskit with any web framework
 //! This demonstrates the framework-agnostic approach using the generic processor
 
-use sms_core::InboundRegistry;
+use sms_core::registry;
 use sms_plivo::PlivoClient;
 use sms_web_generic::WebhookProcessor;
-use std::sync::Arc;
 
 // Simulated request from any web framework
 struct GenericRequest {
@@ -34,13 +33,13 @@ impl From<sms_core::WebhookResponse> for GenericResponse {
 async fn handle_sms_webhook(req: GenericRequest) -> GenericResponse {
     // 1. Set up your SMS providers (this would typically be done once at startup)
     let plivo = PlivoClient::new("your_auth_id", "your_auth_token");
-    let registry = InboundRegistry::new().with(Arc::new(plivo));
+    let registry = registry![plivo];
 
     // 2. Create the processor
     let processor = WebhookProcessor::new(registry);
 
     // 3. Process the webhook - framework agnostic!
-    let response = processor.process_webhook(&req.provider, req.headers, &req.body);
+    let response = processor.process_webhook(&req.provider, req.headers, req.body.into());
 
     // 4. Convert to your framework's response type
     response.into()
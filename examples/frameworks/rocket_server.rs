@@ -1,20 +1,20 @@
 //! Example SMS webhook server using Rocket
-use std::sync::Arc;
 use rocket::State;
-use sms_core::InboundRegistry;
+use sms_core::registry;
 use sms_plivo::PlivoClient;
-use sms_web_rocket::{unified_webhook, AppState};
+use sms_web_generic::WebhookProcessor;
+use sms_web_rocket::{tenant_webhook, unified_webhook, AppState};
 
 #[rocket::launch]
 fn rocket() -> _ {
     let plivo = PlivoClient::new("auth_id", "auth_token");
-    let registry = InboundRegistry::new().with(Arc::new(plivo));
-    let state = AppState { registry };
+    let registry = registry![plivo];
+    let state = AppState::new(WebhookProcessor::new(registry));
 
     println!("Rocket SMS webhook server will start on http://localhost:8000");
     println!("Send webhooks to: POST http://localhost:8000/webhooks/plivo");
 
     rocket::build()
         .manage(state)
-        .mount("/", rocket::routes![unified_webhook])
+        .mount("/", rocket::routes![unified_webhook, tenant_webhook])
 }
@@ -1,14 +1,14 @@
 //! Example SMS webhook server using Tide
-use std::sync::Arc;
-use sms_core::InboundRegistry;
+use sms_core::registry;
 use sms_plivo::PlivoClient;
+use sms_web_generic::WebhookProcessor;
 use sms_web_tide::{configure_routes, AppState};
 
 #[async_std::main]
 async fn main() -> tide::Result<()> {
     let plivo = PlivoClient::new("auth_id", "auth_token");
-    let registry = InboundRegistry::new().with(Arc::new(plivo));
-    let state = AppState { registry };
+    let registry = registry![plivo];
+    let state = AppState::new(WebhookProcessor::new(registry));
 
     let mut app = tide::with_state(state);
     configure_routes(&mut app);
@@ -1,15 +1,15 @@
 //! Example SMS webhook server using Warp
-use sms_core::InboundRegistry;
+use sms_core::registry;
 use sms_plivo::PlivoClient;
+use sms_web_generic::WebhookProcessor;
 use sms_web_warp::{webhook_filter, AppState};
-use std::sync::Arc;
 use warp::Filter;
 
 #[tokio::main]
 async fn main() {
     let plivo = PlivoClient::new("auth_id", "auth_token");
-    let registry = InboundRegistry::new().with(Arc::new(plivo));
-    let state = AppState { registry };
+    let registry = registry![plivo];
+    let state = AppState::new(WebhookProcessor::new(registry));
 
     let routes = webhook_filter(state).with(warp::log("webhooks"));
 
@@ -1,5 +1,5 @@
 //! Send an SMS using the Plivo backend.
-use sms_core::{SendRequest, SmsClient};
+use sms_core::{Channel, SendRequest, SmsClient};
 use sms_plivo::PlivoClient;
 
 use std::env;
@@ -18,6 +18,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             to: &to,
             from: &from,
             text: &text,
+            channel: Channel::Sms,
+            timeout: None,
         })
         .await?;
     println!(
@@ -31,10 +33,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
 fn arg_or_env(flag: &str, env_key: &str) -> String {
     let args: Vec<String> = std::env::args().collect();
-    if let Some(idx) = args.iter().position(|a| a == flag) {
-        if idx + 1 < args.len() {
-            return args[idx + 1].clone();
-        }
+    if let Some(idx) = args.iter().position(|a| a == flag)
+        && idx + 1 < args.len()
+    {
+        return args[idx + 1].clone();
     }
     env::var(env_key)
         .unwrap_or_else(|_| panic!("missing {} (arg {} or env {})", flag, flag, env_key))
@@ -1,20 +1,23 @@
 //! Run a tiny Axum server that exposes a unified webhook endpoint for multiple providers.
-//! For now we register only Plivo. Add others by calling `.with(Arc::new(ProviderClient{...}))`.
+//! For now we register only Plivo. Add others by listing them in `registry![...]`.
 
-use std::sync::Arc;
-use axum::{routing::post, Router};
-use sms_core::InboundRegistry;
-use sms_web_axum::{unified_webhook, AppState};
+use axum::{routing::{get, post}, Router};
+use sms_core::registry;
+use sms_web_axum::{inbound_events, tenant_webhook, unified_webhook, AppState, InboundStream};
 use sms_plivo::PlivoClient;
+use sms_web_generic::WebhookProcessor;
 
 #[tokio::main]
 async fn main() {
     let plivo = PlivoClient::with_base_url("auth_id", "auth_token", "https://api.plivo.com".into());
-    let registry = InboundRegistry::new().with(Arc::new(plivo));
-    let state = AppState { registry };
+    let registry = registry![plivo];
+    let processor = WebhookProcessor::new(registry);
+    let state = AppState::new(processor).with_events(InboundStream::default());
 
     let app = Router::new()
         .route("/webhooks/:provider", post(unified_webhook))
+        .route("/webhooks/:tenant/:provider", post(tenant_webhook))
+        .route("/events", get(inbound_events))
         .with_state(state);
 
     let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await.unwrap();
@@ -0,0 +1,357 @@
+//! Token-protected admin and introspection endpoints.
+//!
+//! [`AdminApi`] answers read-only `GET` requests about a running deployment:
+//! registered providers, recent messages from a [`MessageStore`], per-provider
+//! health from [`SmsMetrics`], [`RateLimiter`] state and [`Outbox`] depth.
+//! It is framework-agnostic — [`handle`](AdminApi::handle) returns a
+//! [`WebhookResponse`] that any adapter's `ResponseConverter` can turn into a
+//! native response — and [`router`](AdminApi::router) mounts it on Axum.
+//!
+//! | Route                          | Body                                         |
+//! |--------------------------------|----------------------------------------------|
+//! | `GET /admin/providers`         | Provider names, globally and per tenant      |
+//! | `GET /admin/messages?limit=N`  | The `N` most recent stored messages (50)     |
+//! | `GET /admin/health`            | Send/failure counters and error rate         |
+//! | `GET /admin/rate-limits`       | [`RateLimiterStatus`](crate::rate_limiter::RateLimiterStatus) |
+//! | `GET /admin/queue`             | Outbox depth, total, per priority, deferred  |
+//!
+//! Every request must carry `Authorization: Bearer <token>`.  Numbers and
+//! text in `/admin/messages` are redacted like log output, following the
+//! process-wide [`RedactionPolicy`](sms_core::RedactionPolicy).
+//!
+//! ```rust,ignore
+//! use smskit::admin::AdminApi;
+//!
+//! let token = config.security.admin_token.as_ref().unwrap().expose_secret();
+//! let admin = AdminApi::new(token, registry)
+//!     .with_store(Arc::new(store))
+//!     .with_metrics(metrics.clone());
+//! let app = webhook_routes.merge(admin.router());
+//! ```
+
+use crate::metrics::SmsMetrics;
+use crate::outbox::{Outbox, Priority};
+use crate::rate_limiter::RateLimiter;
+use crate::store::MessageStore;
+use serde_json::{Value, json};
+use sms_core::{
+    ExposeSecret, Headers, HttpStatus, InboundRegistry, SecretString, TenantRegistry,
+    WebhookResponse, redact_number, redact_text,
+};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// Number of messages returned by `/admin/messages` without `?limit=`.
+pub const DEFAULT_MESSAGE_LIMIT: usize = 50;
+
+/// Read-only admin endpoints guarded by a bearer token.
+///
+/// Sources that are not configured report `null` (or 404 for
+/// `/admin/messages`) rather than failing the whole API.
+#[derive(Clone)]
+pub struct AdminApi {
+    token: SecretString,
+    registry: InboundRegistry,
+    tenants: TenantRegistry,
+    store: Option<Arc<dyn MessageStore>>,
+    metrics: Option<SmsMetrics>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    outbox: Option<Outbox>,
+}
+
+impl AdminApi {
+    /// Create an API that accepts `token` and reports on `registry`.
+    pub fn new(token: impl Into<String>, registry: InboundRegistry) -> Self {
+        Self {
+            token: SecretString::from(token.into()),
+            registry,
+            tenants: TenantRegistry::new(),
+            store: None,
+            metrics: None,
+            rate_limiter: None,
+            outbox: None,
+        }
+    }
+
+    /// Also list the providers registered for each tenant.
+    pub fn with_tenants(mut self, tenants: TenantRegistry) -> Self {
+        self.tenants = tenants;
+        self
+    }
+
+    /// Serve recent messages from `store`.
+    pub fn with_store(mut self, store: Arc<dyn MessageStore>) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    /// Report provider health from `metrics`.
+    pub fn with_metrics(mut self, metrics: SmsMetrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Report the state of `limiter`.
+    pub fn with_rate_limiter(mut self, limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = Some(limiter);
+        self
+    }
+
+    /// Report the depth of `outbox`.
+    pub fn with_outbox(mut self, outbox: Outbox) -> Self {
+        self.outbox = Some(outbox);
+        self
+    }
+
+    /// Answer one admin request.
+    ///
+    /// `path` may include a query string.  Returns 401 without a valid
+    /// token, 404 for unknown routes and 405 for non-`GET` methods.
+    pub async fn handle(&self, method: &str, path: &str, headers: &Headers) -> WebhookResponse {
+        if !self.authorized(headers) {
+            return WebhookResponse::error(HttpStatus::Unauthorized, "invalid admin token");
+        }
+        if !method.eq_ignore_ascii_case("GET") {
+            return WebhookResponse::error(
+                HttpStatus::MethodNotAllowed,
+                "admin endpoints are read-only",
+            )
+            .with_header("Allow", "GET");
+        }
+
+        let (route, query) = path.split_once('?').unwrap_or((path, ""));
+        match route.trim_end_matches('/') {
+            "/admin/providers" => json_response(self.providers()),
+            "/admin/messages" => self.messages(query).await,
+            "/admin/health" => json_response(self.health()),
+            "/admin/rate-limits" => {
+                json_response(json!(self.rate_limiter.as_ref().map(|l| l.status())))
+            }
+            "/admin/queue" => json_response(self.queue().await),
+            _ => WebhookResponse::error(HttpStatus::NotFound, "unknown admin route"),
+        }
+    }
+
+    /// Mount the admin routes on an Axum router.
+    pub fn router<S: Clone + Send + Sync + 'static>(self) -> axum::Router<S> {
+        use axum::extract::{OriginalUri, State};
+        use axum::http::HeaderMap;
+        use axum::response::Response;
+        use sms_web_axum::{AxumHeaderConverter, AxumResponseConverter};
+        use sms_web_generic::{HeaderConverter, ResponseConverter};
+
+        async fn admin(
+            State(api): State<Arc<AdminApi>>,
+            OriginalUri(uri): OriginalUri,
+            headers: HeaderMap,
+        ) -> Response {
+            let headers = AxumHeaderConverter::to_generic_headers(&headers);
+            let path = uri.path_and_query().map_or(uri.path(), |pq| pq.as_str());
+            AxumResponseConverter::from_webhook_response(api.handle("GET", path, &headers).await)
+        }
+
+        axum::Router::new()
+            .route("/admin/{*route}", axum::routing::get(admin))
+            .with_state(Arc::new(self))
+    }
+
+    fn authorized(&self, headers: &Headers) -> bool {
+        let Some(presented) = headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case("authorization"))
+            .and_then(|(_, v)| v.strip_prefix("Bearer "))
+        else {
+            return false;
+        };
+        let token = self.token.expose_secret();
+        !token.is_empty() && constant_time_eq(presented.as_bytes(), token.as_bytes())
+    }
+
+    fn providers(&self) -> Value {
+        let mut providers: Vec<_> = self.registry.providers().collect();
+        providers.sort_unstable();
+        let tenants: BTreeMap<_, _> = self
+            .tenants
+            .tenants()
+            .filter_map(|t| {
+                let mut names: Vec<_> = self.tenants.get(t)?.providers().collect();
+                names.sort_unstable();
+                Some((t, names))
+            })
+            .collect();
+        json!({ "providers": providers, "tenants": tenants })
+    }
+
+    async fn messages(&self, query: &str) -> WebhookResponse {
+        let Some(store) = &self.store else {
+            return WebhookResponse::error(HttpStatus::NotFound, "no message store configured");
+        };
+        let limit = query
+            .split('&')
+            .find_map(|pair| pair.strip_prefix("limit="))
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_MESSAGE_LIMIT);
+
+        match store.recent(limit).await {
+            Ok(mut messages) => {
+                for message in &mut messages {
+                    message.from = redact_number(&message.from).into_owned();
+                    message.to = redact_number(&message.to).into_owned();
+                    message.text = redact_text(&message.text).into_owned();
+                }
+                json_response(json!({ "messages": messages }))
+            }
+            Err(e) => WebhookResponse::error(
+                HttpStatus::InternalServerError,
+                &format!("store query failed: {}", e),
+            ),
+        }
+    }
+
+    fn health(&self) -> Value {
+        let Some(metrics) = &self.metrics else {
+            return Value::Null;
+        };
+        let providers: BTreeMap<_, _> = metrics
+            .snapshot()
+            .into_iter()
+            .map(|(provider, c)| {
+                let attempts = c.sent + c.failed;
+                let error_rate = if attempts == 0 {
+                    0.0
+                } else {
+                    c.failed as f64 / attempts as f64
+                };
+                let entry = json!({
+                    "sent": c.sent,
+                    "failed": c.failed,
+                    "delivery_reports": c.delivery_reports,
+                    "inbound": c.inbound,
+                    "error_rate": error_rate,
+                });
+                (provider, entry)
+            })
+            .collect();
+        json!({ "providers": providers })
+    }
+
+    async fn queue(&self) -> Value {
+        let Some(outbox) = &self.outbox else {
+            return Value::Null;
+        };
+        let mut by_priority = BTreeMap::new();
+        for priority in [
+            Priority::Bulk,
+            Priority::Normal,
+            Priority::Alert,
+            Priority::Critical,
+        ] {
+            by_priority.insert(
+                json!(priority).as_str().unwrap_or_default().to_string(),
+                outbox.len_by_priority(priority).await,
+            );
+        }
+        json!({
+            "total": outbox.len().await,
+            "by_priority": by_priority,
+            "deferred": outbox.deferred_len().await,
+        })
+    }
+}
+
+fn json_response(body: Value) -> WebhookResponse {
+    WebhookResponse::ack("application/json", body.to_string())
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::{InMemoryMessageStore, MessageDirection, StoredMessage};
+    use sms_core::OwnedSendRequest;
+    use time::OffsetDateTime;
+
+    fn auth() -> Headers {
+        vec![("Authorization".into(), "Bearer s3cret".into())]
+    }
+
+    fn body(response: &WebhookResponse) -> Value {
+        serde_json::from_str(&response.body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn rejects_missing_or_wrong_token() {
+        let api = AdminApi::new("s3cret", InboundRegistry::new());
+        let none = api.handle("GET", "/admin/providers", &Headers::new()).await;
+        assert_eq!(none.status, HttpStatus::Unauthorized);
+
+        let wrong = vec![("authorization".into(), "Bearer nope".into())];
+        let wrong = api.handle("GET", "/admin/providers", &wrong).await;
+        assert_eq!(wrong.status, HttpStatus::Unauthorized);
+
+        let ok = api.handle("GET", "/admin/providers", &auth()).await;
+        assert_eq!(ok.status, HttpStatus::Ok);
+    }
+
+    #[tokio::test]
+    async fn rejects_writes_with_405() {
+        let api = AdminApi::new("s3cret", InboundRegistry::new());
+        let resp = api.handle("POST", "/admin/providers", &auth()).await;
+        assert_eq!(resp.status, HttpStatus::MethodNotAllowed);
+        assert_eq!(resp.header("allow"), Some("GET"));
+    }
+
+    #[tokio::test]
+    async fn lists_recent_messages_newest_first() {
+        let store = InMemoryMessageStore::new();
+        for (i, id) in ["a", "b", "c"].into_iter().enumerate() {
+            store
+                .save(StoredMessage {
+                    id: id.into(),
+                    direction: MessageDirection::Inbound,
+                    from: "+15551234567".into(),
+                    to: "+2".into(),
+                    text: id.into(),
+                    provider: "plivo".into(),
+                    timestamp: OffsetDateTime::from_unix_timestamp(1_700_000_000 + i as i64)
+                        .unwrap(),
+                })
+                .await
+                .unwrap();
+        }
+        let api = AdminApi::new("s3cret", InboundRegistry::new()).with_store(Arc::new(store));
+
+        let response = api.handle("GET", "/admin/messages?limit=2", &auth()).await;
+        let messages = body(&response)["messages"].as_array().unwrap().clone();
+        let ids: Vec<_> = messages
+            .iter()
+            .map(|m| m["id"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(ids, vec!["c", "b"]);
+        assert_eq!(messages[0]["from"], "+15*******67");
+        assert_eq!(messages[0]["text"], "[redacted]");
+    }
+
+    #[tokio::test]
+    async fn reports_queue_depth_and_unknown_routes() {
+        let outbox = Outbox::new();
+        outbox
+            .enqueue(OwnedSendRequest::new("+1", "+2", "otp"), Priority::Critical)
+            .await;
+        let api = AdminApi::new("s3cret", InboundRegistry::new()).with_outbox(outbox);
+
+        let queue = body(&api.handle("GET", "/admin/queue", &auth()).await);
+        assert_eq!(queue["total"], 1);
+        assert_eq!(queue["by_priority"]["critical"], 1);
+        assert_eq!(queue["deferred"], 0);
+
+        let health = api.handle("GET", "/admin/health", &auth()).await;
+        assert_eq!(body(&health), Value::Null);
+
+        let missing = api.handle("GET", "/admin/nope", &auth()).await;
+        assert_eq!(missing.status, HttpStatus::NotFound);
+    }
+}
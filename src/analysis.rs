@@ -0,0 +1,170 @@
+//! Pre-send message analysis.
+//!
+//! [`analyze`] works out how a message body will be encoded on the wire, how
+//! many segments it will be split into and roughly what it will cost per
+//! provider, so UIs can warn before a stray emoji triples the bill.  Prices
+//! come from a [`PriceTable`]; see [`crate::pricing`] for per-country rates.
+//!
+//! ```rust,ignore
+//! use smskit::analysis::{Encoding, analyze};
+//!
+//! let report = analyze("Your code is 1234 👍");
+//! if report.encoding == Encoding::Ucs2 {
+//!     println!("non-GSM characters: {:?}", report.non_gsm_chars);
+//! }
+//! ```
+
+pub use crate::pricing::PriceTable;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Characters in the GSM 03.38 basic character set (one septet each).
+const GSM_BASIC: &str = "@£$¥èéùìòÇ\nØø\rÅåΔ_ΦΓΛΩΠΨΣΘΞÆæßÉ !\"#¤%&'()*+,-./0123456789:;<=>?\
+¡ABCDEFGHIJKLMNOPQRSTUVWXYZÄÖÑÜ§¿abcdefghijklmnopqrstuvwxyzäöñüà";
+
+/// Characters in the GSM 03.38 extension table (escape + septet, two each).
+const GSM_EXTENDED: &str = "^{}\\[~]|€\u{000C}";
+
+/// On-the-wire encoding of an SMS body.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Encoding {
+    /// 7-bit GSM default alphabet: 160 characters per single message.
+    Gsm7,
+    /// UCS-2 (UTF-16): 70 characters per single message.
+    Ucs2,
+}
+
+impl Encoding {
+    /// Code units that fit in a single, unsplit message.
+    pub fn single_segment_units(self) -> usize {
+        match self {
+            Encoding::Gsm7 => 160,
+            Encoding::Ucs2 => 70,
+        }
+    }
+
+    /// Code units per segment once a message is split (the UDH takes the rest).
+    pub fn multipart_segment_units(self) -> usize {
+        match self {
+            Encoding::Gsm7 => 153,
+            Encoding::Ucs2 => 67,
+        }
+    }
+}
+
+/// Result of [`analyze`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MessageAnalysis {
+    /// Encoding the message will be sent with.
+    pub encoding: Encoding,
+    /// Number of SMS segments the message will be split into.
+    pub segments: usize,
+    /// Characters that forced UCS-2 encoding, in order of first appearance.
+    pub non_gsm_chars: Vec<char>,
+    /// Estimated total cost per provider (segments × per-segment price).
+    pub estimated_cost_per_provider: BTreeMap<String, f64>,
+}
+
+impl MessageAnalysis {
+    /// Returns `true` if the message will be split into more than one segment.
+    pub fn is_multipart(&self) -> bool {
+        self.segments > 1
+    }
+}
+
+/// Analyze `text` using the default [`PriceTable`].
+pub fn analyze(text: &str) -> MessageAnalysis {
+    analyze_with(text, &PriceTable::default())
+}
+
+/// Analyze `text`, estimating costs from `prices`.
+pub fn analyze_with(text: &str, prices: &PriceTable) -> MessageAnalysis {
+    let mut non_gsm_chars = Vec::new();
+    let mut septets = 0;
+    for c in text.chars() {
+        if GSM_BASIC.contains(c) {
+            septets += 1;
+        } else if GSM_EXTENDED.contains(c) {
+            septets += 2;
+        } else if !non_gsm_chars.contains(&c) {
+            non_gsm_chars.push(c);
+        }
+    }
+
+    let (encoding, units) = if non_gsm_chars.is_empty() {
+        (Encoding::Gsm7, septets)
+    } else {
+        (Encoding::Ucs2, text.encode_utf16().count())
+    };
+
+    let segments = if units <= encoding.single_segment_units() {
+        1
+    } else {
+        units.div_ceil(encoding.multipart_segment_units())
+    };
+
+    let estimated_cost_per_provider = prices
+        .iter()
+        .map(|(provider, price)| (provider.to_string(), price * segments as f64))
+        .collect();
+
+    MessageAnalysis {
+        encoding,
+        segments,
+        non_gsm_chars,
+        estimated_cost_per_provider,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_ascii_is_gsm7_single_segment() {
+        let a = analyze("Hello, world!");
+        assert_eq!(a.encoding, Encoding::Gsm7);
+        assert_eq!(a.segments, 1);
+        assert!(a.non_gsm_chars.is_empty());
+    }
+
+    #[test]
+    fn gsm7_segment_boundaries() {
+        assert_eq!(analyze(&"a".repeat(160)).segments, 1);
+        assert_eq!(analyze(&"a".repeat(161)).segments, 2);
+        assert_eq!(analyze(&"a".repeat(306)).segments, 2);
+        assert_eq!(analyze(&"a".repeat(307)).segments, 3);
+    }
+
+    #[test]
+    fn extended_chars_count_double() {
+        let a = analyze(&"€".repeat(80));
+        assert_eq!(a.encoding, Encoding::Gsm7);
+        assert_eq!(a.segments, 1);
+        assert_eq!(analyze(&"€".repeat(81)).segments, 2);
+    }
+
+    #[test]
+    fn emoji_forces_ucs2() {
+        let a = analyze("Hi 👍👍");
+        assert_eq!(a.encoding, Encoding::Ucs2);
+        assert_eq!(a.non_gsm_chars, vec!['👍']);
+        assert_eq!(analyze(&"й".repeat(70)).segments, 1);
+        assert_eq!(analyze(&"й".repeat(71)).segments, 2);
+    }
+
+    #[test]
+    fn empty_text_is_one_segment() {
+        assert_eq!(analyze("").segments, 1);
+    }
+
+    #[test]
+    fn cost_scales_with_segments() {
+        let prices = PriceTable::empty().with_price("acme", 0.01);
+        let a = analyze_with(&"a".repeat(200), &prices);
+        assert_eq!(a.segments, 2);
+        assert_eq!(a.estimated_cost_per_provider.len(), 1);
+        assert!((a.estimated_cost_per_provider["acme"] - 0.02).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,262 @@
+//! Attachment storage.
+//!
+//! [`AttachmentStore`] is the extension point for keeping binary message
+//! payloads — MMS media fetched by [`MediaDownloader`](crate::media::MediaDownloader)
+//! today, outbound media tomorrow — in one place regardless of provider.
+//! [`LocalAttachmentStore`] writes to a directory; with the `s3-attachments`
+//! feature, [`S3AttachmentStore`] writes to a bucket.
+//!
+//! ```rust,ignore
+//! use smskit::attachment::LocalAttachmentStore;
+//!
+//! let store = Arc::new(LocalAttachmentStore::new("/var/lib/smskit/media"));
+//! let downloader = MediaDownloader::new().with_store(store);
+//! for attachment in downloader.download_and_store(&message).await? {
+//!     println!("{} ({} bytes) at {}", attachment.content_type, attachment.size, attachment.url);
+//! }
+//! ```
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sms_core::SmsError;
+use std::path::{Component, Path, PathBuf};
+use tracing::debug;
+
+/// Reference to an attachment saved in an [`AttachmentStore`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoredAttachment {
+    /// Key the attachment was stored under.
+    pub key: String,
+    /// Where it lives, e.g. `file:///var/media/a/b` or `s3://bucket/a/b`.
+    pub url: String,
+    /// MIME type.
+    pub content_type: String,
+    /// Size in bytes.
+    pub size: usize,
+}
+
+/// Storage for binary message attachments.
+///
+/// Keys are `/`-separated relative paths such as `twilio/SM123/0`.
+#[async_trait]
+pub trait AttachmentStore: Send + Sync {
+    /// Store `bytes` under `key`, replacing anything already there.
+    async fn put(
+        &self,
+        key: &str,
+        content_type: &str,
+        bytes: Vec<u8>,
+    ) -> Result<StoredAttachment, SmsError>;
+
+    /// Read the attachment stored under `key`.
+    async fn get(&self, key: &str) -> Result<Vec<u8>, SmsError>;
+
+    /// Remove the attachment stored under `key`, if any.
+    async fn delete(&self, key: &str) -> Result<(), SmsError>;
+}
+
+/// Reject keys that are empty or could escape the store's root.
+fn check_key(key: &str) -> Result<(), SmsError> {
+    let valid = !key.is_empty()
+        && Path::new(key)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)));
+    if valid {
+        Ok(())
+    } else {
+        Err(SmsError::Invalid(format!(
+            "invalid attachment key: {}",
+            key
+        )))
+    }
+}
+
+/// An [`AttachmentStore`] backed by a local directory.
+#[derive(Debug, Clone)]
+pub struct LocalAttachmentStore {
+    root: PathBuf,
+}
+
+impl LocalAttachmentStore {
+    /// Store attachments under `root`, which is created on first write.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// The directory attachments are written to.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn path(&self, key: &str) -> Result<PathBuf, SmsError> {
+        check_key(key)?;
+        Ok(self.root.join(key))
+    }
+}
+
+fn io_error(key: &str, e: std::io::Error) -> SmsError {
+    SmsError::Unexpected(format!("attachment {}: {}", key, e))
+}
+
+#[async_trait]
+impl AttachmentStore for LocalAttachmentStore {
+    async fn put(
+        &self,
+        key: &str,
+        content_type: &str,
+        bytes: Vec<u8>,
+    ) -> Result<StoredAttachment, SmsError> {
+        let path = self.path(key)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|e| io_error(key, e))?;
+        }
+        let size = bytes.len();
+        tokio::fs::write(&path, bytes)
+            .await
+            .map_err(|e| io_error(key, e))?;
+        debug!("Stored {} bytes at {}", size, path.display());
+        Ok(StoredAttachment {
+            key: key.to_string(),
+            url: format!("file://{}", path.display()),
+            content_type: content_type.to_string(),
+            size,
+        })
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, SmsError> {
+        tokio::fs::read(self.path(key)?)
+            .await
+            .map_err(|e| io_error(key, e))
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), SmsError> {
+        match tokio::fs::remove_file(self.path(key)?).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(io_error(key, e)),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// An [`AttachmentStore`] backed by an S3 bucket.
+#[cfg(feature = "s3-attachments")]
+#[derive(Debug, Clone)]
+pub struct S3AttachmentStore {
+    client: aws_sdk_s3::Client,
+    bucket: String,
+    prefix: String,
+}
+
+#[cfg(feature = "s3-attachments")]
+impl S3AttachmentStore {
+    /// Store attachments in `bucket`.
+    pub fn new(client: aws_sdk_s3::Client, bucket: impl Into<String>) -> Self {
+        Self {
+            client,
+            bucket: bucket.into(),
+            prefix: String::new(),
+        }
+    }
+
+    /// Prepend `prefix` (e.g. `"media/"`) to every object key.
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    fn object_key(&self, key: &str) -> Result<String, SmsError> {
+        check_key(key)?;
+        Ok(format!("{}{}", self.prefix, key))
+    }
+}
+
+#[cfg(feature = "s3-attachments")]
+#[async_trait]
+impl AttachmentStore for S3AttachmentStore {
+    async fn put(
+        &self,
+        key: &str,
+        content_type: &str,
+        bytes: Vec<u8>,
+    ) -> Result<StoredAttachment, SmsError> {
+        let object_key = self.object_key(key)?;
+        let size = bytes.len();
+        self.client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(&object_key)
+            .content_type(content_type)
+            .body(bytes.into())
+            .send()
+            .await
+            .map_err(|e| SmsError::Http(format!("S3 upload failed: {}", e)))?;
+        Ok(StoredAttachment {
+            key: key.to_string(),
+            url: format!("s3://{}/{}", self.bucket, object_key),
+            content_type: content_type.to_string(),
+            size,
+        })
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, SmsError> {
+        let object = self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(self.object_key(key)?)
+            .send()
+            .await
+            .map_err(|e| SmsError::Http(format!("S3 download failed: {}", e)))?;
+        let body = object
+            .body
+            .collect()
+            .await
+            .map_err(|e| SmsError::Http(format!("S3 download failed: {}", e)))?;
+        Ok(body.into_bytes().to_vec())
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), SmsError> {
+        self.client
+            .delete_object()
+            .bucket(&self.bucket)
+            .key(self.object_key(key)?)
+            .send()
+            .await
+            .map_err(|e| SmsError::Http(format!("S3 delete failed: {}", e)))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_keys_escaping_the_root() {
+        assert!(check_key("twilio/SM1/0").is_ok());
+        for key in ["", "../etc/passwd", "/etc/passwd", "a/../../b", "./a"] {
+            assert!(check_key(key).is_err(), "{}", key);
+        }
+    }
+
+    #[tokio::test]
+    async fn local_store_round_trip() {
+        let root =
+            std::env::temp_dir().join(format!("smskit-attachments-{}", sms_core::fallback_id()));
+        let store = LocalAttachmentStore::new(&root);
+
+        let stored = store
+            .put("twilio/SM1/0", "image/png", b"png".to_vec())
+            .await
+            .unwrap();
+        assert_eq!(stored.size, 3);
+        assert!(stored.url.starts_with("file://"));
+        assert_eq!(store.get("twilio/SM1/0").await.unwrap(), b"png");
+
+        store.delete("twilio/SM1/0").await.unwrap();
+        store.delete("twilio/SM1/0").await.unwrap();
+        assert!(store.get("twilio/SM1/0").await.is_err());
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
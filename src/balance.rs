@@ -0,0 +1,329 @@
+//! Low-balance alerting.
+//!
+//! [`BalanceMonitor`] periodically asks each watched provider for its
+//! remaining credit ([`BalanceSource`]) and compares it against that
+//! provider's [`BalanceThresholds`].  Registered callbacks hear about every
+//! change of [`BalanceLevel`]: when an account drops to low, drops further
+//! to critical, or is topped up and recovers.  A level that holds across
+//! checks is reported once, so a long campaign doesn't page every minute.
+//!
+//! ```rust,ignore
+//! use smskit::balance::{BalanceMonitor, BalanceThresholds};
+//!
+//! let monitor = BalanceMonitor::new(Duration::from_secs(300))
+//!     .watch("twilio", twilio.clone(), BalanceThresholds::new(50.0, 10.0))
+//!     .watch("plivo", plivo.clone(), BalanceThresholds::new(20.0, 5.0))
+//!     .on_alert(|alert| page(format!("{}", alert)));
+//! tokio::spawn(async move { monitor.run().await });
+//! ```
+
+use crate::runtime::Interval;
+use serde::{Deserialize, Serialize};
+use sms_core::{Balance, BalanceSource};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Credit levels at which a provider account counts as low or critical.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BalanceThresholds {
+    /// At or below this amount the account is [`BalanceLevel::Low`].
+    pub low: f64,
+    /// At or below this amount the account is [`BalanceLevel::Critical`].
+    pub critical: f64,
+}
+
+impl BalanceThresholds {
+    /// Low at or below `low`, critical at or below `critical`.
+    pub fn new(low: f64, critical: f64) -> Self {
+        Self { low, critical }
+    }
+
+    /// The level `amount` falls into.
+    pub fn level(&self, amount: f64) -> BalanceLevel {
+        if amount <= self.critical {
+            BalanceLevel::Critical
+        } else if amount <= self.low {
+            BalanceLevel::Low
+        } else {
+            BalanceLevel::Healthy
+        }
+    }
+}
+
+/// How close an account is to running dry, from best to worst.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum BalanceLevel {
+    /// Above the low threshold.
+    #[default]
+    Healthy,
+    /// At or below the low threshold.
+    Low,
+    /// At or below the critical threshold.
+    Critical,
+}
+
+/// A change of [`BalanceLevel`] reported to [`BalanceMonitor::on_alert`]
+/// callbacks.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BalanceAlert {
+    /// Provider name, as given to [`BalanceMonitor::watch`].
+    pub provider: String,
+    /// The balance that triggered the alert.
+    pub balance: Balance,
+    /// The new level.
+    pub level: BalanceLevel,
+    /// The level before this check.
+    pub previous: BalanceLevel,
+}
+
+impl BalanceAlert {
+    /// Returns `true` if the account moved back up to a better level.
+    pub fn is_recovery(&self) -> bool {
+        self.level < self.previous
+    }
+}
+
+impl fmt::Display for BalanceAlert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let what = match self.level {
+            BalanceLevel::Healthy => "recovered",
+            BalanceLevel::Low => "low",
+            BalanceLevel::Critical => "critical",
+        };
+        write!(f, "{} balance {}: {}", self.provider, what, self.balance)
+    }
+}
+
+/// Callback invoked when a provider's balance level changes.
+pub type AlertCallback = dyn Fn(&BalanceAlert) + Send + Sync;
+
+#[derive(Clone)]
+struct Watched {
+    provider: String,
+    source: Arc<dyn BalanceSource>,
+    thresholds: BalanceThresholds,
+}
+
+#[derive(Debug, Default)]
+struct MonitorState {
+    levels: BTreeMap<String, BalanceLevel>,
+    balances: BTreeMap<String, Balance>,
+}
+
+/// Periodic balance checker for one or more provider accounts.
+///
+/// Cloning yields another handle to the same state, so one copy can run in
+/// the background while another serves [`balances`](Self::balances).
+#[derive(Clone)]
+pub struct BalanceMonitor {
+    watched: Vec<Watched>,
+    callbacks: Vec<Arc<AlertCallback>>,
+    interval: Duration,
+    state: Arc<Mutex<MonitorState>>,
+}
+
+impl fmt::Debug for BalanceMonitor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let providers: Vec<&str> = self.watched.iter().map(|w| w.provider.as_str()).collect();
+        f.debug_struct("BalanceMonitor")
+            .field("providers", &providers)
+            .field("interval", &self.interval)
+            .finish_non_exhaustive()
+    }
+}
+
+impl BalanceMonitor {
+    /// Check every `interval` once [`run`](Self::run) is started.
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            watched: Vec::new(),
+            callbacks: Vec::new(),
+            interval,
+            state: Arc::default(),
+        }
+    }
+
+    /// Watch `source`'s balance under the name `provider`.
+    pub fn watch(
+        mut self,
+        provider: impl Into<String>,
+        source: impl BalanceSource + 'static,
+        thresholds: BalanceThresholds,
+    ) -> Self {
+        self.watched.push(Watched {
+            provider: provider.into(),
+            source: Arc::new(source),
+            thresholds,
+        });
+        self
+    }
+
+    /// Call `callback` whenever a provider's balance level changes.
+    pub fn on_alert(mut self, callback: impl Fn(&BalanceAlert) + Send + Sync + 'static) -> Self {
+        self.callbacks.push(Arc::new(callback));
+        self
+    }
+
+    /// The last balance fetched for each provider.
+    pub fn balances(&self) -> BTreeMap<String, Balance> {
+        self.lock().balances.clone()
+    }
+
+    /// The current level of `provider`, [`BalanceLevel::Healthy`] until its
+    /// first successful check.
+    pub fn level(&self, provider: &str) -> BalanceLevel {
+        self.lock()
+            .levels
+            .get(provider)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Check every watched provider once and return the alerts raised.
+    ///
+    /// A provider whose balance can't be fetched keeps its previous level;
+    /// the failure is logged.
+    pub async fn check(&self) -> Vec<BalanceAlert> {
+        let mut alerts = Vec::new();
+        for watched in self.watched.iter() {
+            let balance = match watched.source.balance().await {
+                Ok(balance) => balance,
+                Err(e) => {
+                    warn!("Balance check for {} failed: {}", watched.provider, e);
+                    continue;
+                }
+            };
+            let level = watched.thresholds.level(balance.amount);
+
+            let previous = {
+                let mut state = self.lock();
+                state
+                    .balances
+                    .insert(watched.provider.clone(), balance.clone());
+                state
+                    .levels
+                    .insert(watched.provider.clone(), level)
+                    .unwrap_or_default()
+            };
+
+            if level != previous {
+                alerts.push(BalanceAlert {
+                    provider: watched.provider.clone(),
+                    balance,
+                    level,
+                    previous,
+                });
+            }
+        }
+
+        for alert in &alerts {
+            if alert.is_recovery() {
+                info!("{}", alert);
+            } else {
+                warn!("{}", alert);
+            }
+            for callback in &self.callbacks {
+                callback(alert);
+            }
+        }
+        alerts
+    }
+
+    /// Run forever, calling [`check`](Self::check) every interval.
+    pub async fn run(&self) {
+        let mut ticks = Interval::new(self.interval);
+        loop {
+            ticks.tick().await;
+            self.check().await;
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MonitorState> {
+        self.state.lock().expect("balance monitor poisoned")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use sms_core::SmsError;
+    use std::collections::VecDeque;
+
+    /// Returns scripted balances in order.
+    struct ScriptedBalance(Mutex<VecDeque<Result<f64, SmsError>>>);
+
+    impl ScriptedBalance {
+        fn new(amounts: Vec<Result<f64, SmsError>>) -> Self {
+            Self(Mutex::new(amounts.into()))
+        }
+    }
+
+    #[async_trait]
+    impl BalanceSource for ScriptedBalance {
+        async fn balance(&self) -> Result<Balance, SmsError> {
+            let amount = self
+                .0
+                .lock()
+                .unwrap()
+                .pop_front()
+                .expect("script exhausted")?;
+            Ok(Balance {
+                amount,
+                currency: "USD".into(),
+            })
+        }
+    }
+
+    #[test]
+    fn thresholds_classify_amounts() {
+        let t = BalanceThresholds::new(50.0, 10.0);
+        assert_eq!(t.level(50.01), BalanceLevel::Healthy);
+        assert_eq!(t.level(50.0), BalanceLevel::Low);
+        assert_eq!(t.level(10.0), BalanceLevel::Critical);
+        assert_eq!(t.level(-3.0), BalanceLevel::Critical);
+    }
+
+    #[tokio::test]
+    async fn alerts_once_per_level_change() {
+        let source = ScriptedBalance::new(vec![
+            Ok(100.0),
+            Ok(40.0),
+            Ok(35.0),
+            Err(SmsError::Http("down".into())),
+            Ok(5.0),
+            Ok(500.0),
+        ]);
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let log = seen.clone();
+        let monitor = BalanceMonitor::new(Duration::from_secs(60))
+            .watch("twilio", source, BalanceThresholds::new(50.0, 10.0))
+            .on_alert(move |alert| log.lock().unwrap().push(alert.to_string()));
+
+        assert!(monitor.check().await.is_empty());
+        assert_eq!(monitor.check().await[0].level, BalanceLevel::Low);
+        assert!(monitor.check().await.is_empty());
+        // A failed check keeps the previous level.
+        assert!(monitor.check().await.is_empty());
+        assert_eq!(monitor.level("twilio"), BalanceLevel::Low);
+        assert_eq!(monitor.check().await[0].level, BalanceLevel::Critical);
+        let recovered = monitor.check().await;
+        assert!(recovered[0].is_recovery());
+        assert_eq!(monitor.balances()["twilio"].amount, 500.0);
+
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![
+                "twilio balance low: 40.00 USD",
+                "twilio balance critical: 5.00 USD",
+                "twilio balance recovered: 500.00 USD",
+            ]
+        );
+    }
+}
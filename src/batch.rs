@@ -0,0 +1,254 @@
+//! Bounded-concurrency batch sending.
+//!
+//! [`ConcurrentSender`] sends many requests through one [`SmsClient`] with a
+//! cap on in-flight sends and an optional deadline for the whole batch, so
+//! callers don't have to hand-roll `buffer_unordered` loops around providers.
+//!
+//! ```rust,ignore
+//! use smskit::batch::ConcurrentSender;
+//!
+//! let sender = ConcurrentSender::new(Arc::new(client))
+//!     .with_concurrency(20)
+//!     .with_timeout(Duration::from_secs(30));
+//! let outcome = sender.send_all(requests).await;
+//! println!("{} sent, {} failed", outcome.succeeded(), outcome.failed());
+//! ```
+
+use futures::stream::{FuturesUnordered, StreamExt};
+use sms_core::{DynSmsClient, OwnedSendRequest, SendResponse, SmsClient, SmsError};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+use tracing::warn;
+
+/// Default maximum number of in-flight sends.
+pub const DEFAULT_CONCURRENCY: usize = 10;
+
+/// Sends batches of requests with a concurrency limit and overall timeout.
+#[derive(Clone)]
+pub struct ConcurrentSender {
+    client: Arc<dyn DynSmsClient>,
+    concurrency: usize,
+    timeout: Option<Duration>,
+}
+
+impl ConcurrentSender {
+    /// Create a sender with [`DEFAULT_CONCURRENCY`] and no timeout.
+    pub fn new(client: Arc<dyn DynSmsClient>) -> Self {
+        Self {
+            client,
+            concurrency: DEFAULT_CONCURRENCY,
+            timeout: None,
+        }
+    }
+
+    /// Set the maximum number of sends in flight at once (minimum 1).
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Set a deadline for the whole batch.  Sends still pending when it
+    /// expires are abandoned and reported as errors.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Send every request and collect the results in input order.
+    pub async fn send_all<I>(&self, requests: I) -> BatchOutcome
+    where
+        I: IntoIterator<Item = OwnedSendRequest>,
+    {
+        let start = Instant::now();
+        let semaphore = Arc::new(Semaphore::new(self.concurrency));
+        let mut tasks = FuturesUnordered::new();
+
+        for (idx, req) in requests.into_iter().enumerate() {
+            let semaphore = semaphore.clone();
+            let client = self.client.clone();
+            tasks.push(async move {
+                let _permit = semaphore
+                    .acquire_owned()
+                    .await
+                    .expect("semaphore is never closed");
+                (idx, client.send(req.as_ref()).await)
+            });
+        }
+
+        let mut slots: Vec<Option<Result<SendResponse, SmsError>>> =
+            (0..tasks.len()).map(|_| None).collect();
+
+        let drain = async {
+            while let Some((idx, result)) = tasks.next().await {
+                slots[idx] = Some(result);
+            }
+        };
+
+        let timed_out = match self.timeout {
+            Some(timeout) => crate::runtime::timeout(timeout, drain).await.is_err(),
+            None => {
+                drain.await;
+                false
+            }
+        };
+
+        if timed_out {
+            warn!(
+                "Batch send timed out with {} of {} requests pending",
+                slots.iter().filter(|s| s.is_none()).count(),
+                slots.len()
+            );
+        }
+
+        let results = slots
+            .into_iter()
+            .map(|slot| {
+                slot.unwrap_or_else(|| {
+                    Err(SmsError::Http(
+                        "batch timed out before send completed".into(),
+                    ))
+                })
+            })
+            .collect();
+
+        BatchOutcome {
+            results,
+            timed_out,
+            elapsed: start.elapsed(),
+        }
+    }
+}
+
+/// Aggregated results of a [`ConcurrentSender::send_all`] call.
+#[derive(Debug)]
+pub struct BatchOutcome {
+    /// One result per request, in the same order the requests were given.
+    pub results: Vec<Result<SendResponse, SmsError>>,
+    /// `true` if the batch deadline expired before every send completed.
+    pub timed_out: bool,
+    /// Wall-clock time the batch took.
+    pub elapsed: Duration,
+}
+
+impl BatchOutcome {
+    /// Number of successful sends.
+    pub fn succeeded(&self) -> usize {
+        self.results.iter().filter(|r| r.is_ok()).count()
+    }
+
+    /// Number of failed (or abandoned) sends.
+    pub fn failed(&self) -> usize {
+        self.results.iter().filter(|r| r.is_err()).count()
+    }
+
+    /// Returns `true` if every send succeeded.
+    pub fn all_succeeded(&self) -> bool {
+        self.results.iter().all(|r| r.is_ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sms_core::{Channel, SendRequest};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Sleeps on every send and tracks the peak number of concurrent calls.
+    #[derive(Default)]
+    struct SlowClient {
+        delay: Duration,
+        in_flight: AtomicUsize,
+        peak: AtomicUsize,
+    }
+
+    impl SmsClient for SlowClient {
+        async fn send(&self, req: SendRequest<'_>) -> Result<SendResponse, SmsError> {
+            let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(self.delay).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+
+            if req.text == "fail" {
+                return Err(SmsError::Provider("scripted failure".into()));
+            }
+            Ok(SendResponse {
+                id: req.text.to_string(),
+                provider: "slow",
+                channel: Channel::Sms,
+                http_status: None,
+                provider_request_id: None,
+                thread_id: None,
+                raw: serde_json::json!({}),
+            })
+        }
+    }
+
+    fn requests(texts: &[&str]) -> Vec<OwnedSendRequest> {
+        texts
+            .iter()
+            .map(|t| OwnedSendRequest::new("+1", "+2", *t))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn results_are_in_input_order() {
+        let client = Arc::new(SlowClient::default());
+        let outcome = ConcurrentSender::new(client)
+            .send_all(requests(&["a", "b", "c"]))
+            .await;
+        let ids: Vec<_> = outcome
+            .results
+            .iter()
+            .map(|r| r.as_ref().unwrap().id.clone())
+            .collect();
+        assert_eq!(ids, vec!["a", "b", "c"]);
+        assert!(outcome.all_succeeded());
+        assert!(!outcome.timed_out);
+    }
+
+    #[tokio::test]
+    async fn respects_concurrency_limit() {
+        let client = Arc::new(SlowClient {
+            delay: Duration::from_millis(10),
+            ..Default::default()
+        });
+        let texts: Vec<String> = (0..12).map(|i| i.to_string()).collect();
+        let reqs = texts
+            .iter()
+            .map(|t| OwnedSendRequest::new("+1", "+2", t.as_str()));
+
+        let outcome = ConcurrentSender::new(client.clone())
+            .with_concurrency(3)
+            .send_all(reqs)
+            .await;
+
+        assert_eq!(outcome.succeeded(), 12);
+        assert!(client.peak.load(Ordering::SeqCst) <= 3);
+    }
+
+    #[tokio::test]
+    async fn aggregates_failures() {
+        let client = Arc::new(SlowClient::default());
+        let outcome = ConcurrentSender::new(client)
+            .send_all(requests(&["ok", "fail", "ok"]))
+            .await;
+        assert_eq!(outcome.succeeded(), 2);
+        assert_eq!(outcome.failed(), 1);
+        assert!(outcome.results[1].is_err());
+    }
+
+    #[tokio::test]
+    async fn timeout_abandons_pending_sends() {
+        let client = Arc::new(SlowClient {
+            delay: Duration::from_secs(5),
+            ..Default::default()
+        });
+        let outcome = ConcurrentSender::new(client)
+            .with_timeout(Duration::from_millis(20))
+            .send_all(requests(&["a", "b"]))
+            .await;
+        assert!(outcome.timed_out);
+        assert_eq!(outcome.failed(), 2);
+    }
+}
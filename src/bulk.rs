@@ -0,0 +1,393 @@
+//! Bulk recipient import.
+//!
+//! [`parse_csv`] and [`parse_jsonl`] read a recipient list — one number per
+//! row plus any template variables — into [`BulkRow`]s.  [`BulkImport`]
+//! renders the message template for each row, rejects invalid and duplicate
+//! numbers, and queues the rest in an [`Outbox`], reporting progress as it
+//! goes and returning a [`BulkReport`] with one [`RowError`] per rejected
+//! row.
+//!
+//! ```rust,ignore
+//! use smskit::bulk::{BulkImport, parse_csv};
+//!
+//! let (rows, parse_errors) = parse_csv(&std::fs::read_to_string("recipients.csv")?);
+//! let report = BulkImport::new("+15550001111", "Hi {name}, your code is {code}")
+//!     .on_progress(|p| eprintln!("{}/{}", p.processed, p.total))
+//!     .run(rows, &outbox)
+//!     .await;
+//! std::fs::write("errors.csv", report.error_csv())?;
+//! ```
+
+use crate::optout::NUMBER_HEADERS;
+use crate::outbox::{Outbox, Priority};
+use crate::screening::calling_code;
+use sms_core::{OwnedSendRequest, SmsError, normalize_number, validate_text};
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+use tracing::info;
+
+/// One recipient read from an import file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BulkRow {
+    /// 1-based line number in the source file.
+    pub line: usize,
+    /// Destination number as written in the file.
+    pub to: String,
+    /// Template variables, keyed by column name.
+    pub vars: BTreeMap<String, String>,
+}
+
+/// Why a row was not queued.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RowError {
+    /// 1-based line number in the source file.
+    pub line: usize,
+    /// Destination number, if the row had one.
+    pub to: String,
+    /// What was wrong with the row.
+    pub reason: String,
+}
+
+/// Progress reported to [`BulkImport::on_progress`] callbacks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BulkProgress {
+    /// Rows handled so far.
+    pub processed: usize,
+    /// Rows in the import.
+    pub total: usize,
+    /// Rows queued so far.
+    pub queued: usize,
+    /// Rows rejected so far.
+    pub rejected: usize,
+}
+
+/// Outcome of [`BulkImport::run`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BulkReport {
+    /// Outbox queue IDs of the queued messages, in row order.
+    pub queued: Vec<String>,
+    /// Rows skipped because their number appeared earlier in the file.
+    pub duplicates: usize,
+    /// Rows rejected, in row order.
+    pub errors: Vec<RowError>,
+}
+
+impl BulkReport {
+    /// The rejected rows as CSV (`line,to,reason`) with a header row.
+    pub fn error_csv(&self) -> String {
+        let mut out = String::from("line,to,reason\n");
+        for err in &self.errors {
+            out.push_str(&format!(
+                "{},{},\"{}\"\n",
+                err.line,
+                err.to,
+                err.reason.replace('"', "\"\"")
+            ));
+        }
+        out
+    }
+}
+
+type ProgressCallback = dyn Fn(&BulkProgress) + Send + Sync;
+
+/// Renders a template for each [`BulkRow`] and queues the results.
+#[derive(Clone)]
+pub struct BulkImport {
+    from: String,
+    template: String,
+    priority: Priority,
+    callbacks: Vec<Arc<ProgressCallback>>,
+}
+
+impl BulkImport {
+    /// Send `template` from `from` to every row.
+    ///
+    /// `{name}` in the template is replaced by the row's `name` variable.
+    pub fn new(from: impl Into<String>, template: impl Into<String>) -> Self {
+        Self {
+            from: from.into(),
+            template: template.into(),
+            priority: Priority::Bulk,
+            callbacks: Vec::new(),
+        }
+    }
+
+    /// Queue messages at `priority` (default [`Priority::Bulk`]).
+    pub fn with_priority(mut self, priority: Priority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Call `callback` after each row is handled.
+    pub fn on_progress(mut self, callback: impl Fn(&BulkProgress) + Send + Sync + 'static) -> Self {
+        self.callbacks.push(Arc::new(callback));
+        self
+    }
+
+    /// Validate, deduplicate and queue `rows` in `outbox`.
+    pub async fn run(&self, rows: Vec<BulkRow>, outbox: &Outbox) -> BulkReport {
+        let mut report = BulkReport::default();
+        let mut seen = HashSet::new();
+        let total = rows.len();
+
+        for (i, row) in rows.into_iter().enumerate() {
+            let to = normalize_number(&row.to);
+            match self.prepare(&to, &row) {
+                Ok(_) if !seen.insert(to.clone()) => report.duplicates += 1,
+                Ok(text) => {
+                    let request = OwnedSendRequest::new(to, self.from.clone(), text);
+                    report
+                        .queued
+                        .push(outbox.enqueue(request, self.priority).await);
+                }
+                Err(e) => report.errors.push(RowError {
+                    line: row.line,
+                    to: row.to,
+                    reason: e.to_string(),
+                }),
+            }
+
+            let progress = BulkProgress {
+                processed: i + 1,
+                total,
+                queued: report.queued.len(),
+                rejected: report.errors.len(),
+            };
+            for callback in &self.callbacks {
+                callback(&progress);
+            }
+        }
+
+        info!(
+            "Bulk import queued {} messages ({} duplicates, {} rejected)",
+            report.queued.len(),
+            report.duplicates,
+            report.errors.len()
+        );
+        report
+    }
+
+    fn prepare(&self, to: &str, row: &BulkRow) -> Result<String, SmsError> {
+        if calling_code(to).is_none() {
+            return Err(SmsError::Invalid(format!(
+                "{:?} is not an E.164 number",
+                row.to
+            )));
+        }
+        let text = render(&self.template, &row.vars)?;
+        validate_text(&text, None)?;
+        Ok(text)
+    }
+}
+
+/// Replace each `{name}` in `template` with `vars["name"]`.
+///
+/// Returns [`SmsError::Invalid`] naming the first variable with no value.
+pub fn render(template: &str, vars: &BTreeMap<String, String>) -> Result<String, SmsError> {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        let name = &rest[start + 1..start + len];
+        let value = vars
+            .get(name)
+            .ok_or_else(|| SmsError::Invalid(format!("missing template variable {:?}", name)))?;
+        out.push_str(&rest[..start]);
+        out.push_str(value);
+        rest = &rest[start + len + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Read recipients from CSV with a header row.
+///
+/// The number column is the first whose header is a usual name for one
+/// (`phone`, `number`, `to`, ...); every other column becomes a template
+/// variable.  Rows with the wrong number of fields are returned as errors.
+pub fn parse_csv(text: &str) -> (Vec<BulkRow>, Vec<RowError>) {
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty());
+    let Some((_, header)) = lines.next() else {
+        return (Vec::new(), Vec::new());
+    };
+    let headers: Vec<String> = split_csv(header).map(str::to_string).collect();
+    let column = number_column(headers.iter().map(String::as_str));
+
+    let mut rows = Vec::new();
+    let mut errors = Vec::new();
+    for (i, line) in lines {
+        let fields: Vec<&str> = split_csv(line).collect();
+        if fields.len() != headers.len() {
+            errors.push(RowError {
+                line: i + 1,
+                to: fields.get(column).unwrap_or(&"").to_string(),
+                reason: format!("expected {} fields, found {}", headers.len(), fields.len()),
+            });
+            continue;
+        }
+        let vars = headers
+            .iter()
+            .zip(&fields)
+            .enumerate()
+            .filter(|(j, _)| *j != column)
+            .map(|(_, (k, v))| (k.clone(), v.to_string()))
+            .collect();
+        rows.push(BulkRow {
+            line: i + 1,
+            to: fields[column].to_string(),
+            vars,
+        });
+    }
+    (rows, errors)
+}
+
+/// Read recipients from JSON Lines, one object per line.
+///
+/// The number is taken from the first key that is a usual name for one;
+/// every other key becomes a template variable.
+pub fn parse_jsonl(text: &str) -> (Vec<BulkRow>, Vec<RowError>) {
+    let mut rows = Vec::new();
+    let mut errors = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let object = match serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(line)
+        {
+            Ok(object) => object,
+            Err(e) => {
+                errors.push(RowError {
+                    line: i + 1,
+                    to: String::new(),
+                    reason: e.to_string(),
+                });
+                continue;
+            }
+        };
+        let mut vars: BTreeMap<String, String> = object
+            .into_iter()
+            .map(|(k, v)| match v {
+                serde_json::Value::String(s) => (k, s),
+                other => (k, other.to_string()),
+            })
+            .collect();
+        let key = NUMBER_HEADERS
+            .iter()
+            .find(|h| vars.contains_key(**h))
+            .copied();
+        match key.and_then(|k| vars.remove(k)) {
+            Some(to) => rows.push(BulkRow {
+                line: i + 1,
+                to,
+                vars,
+            }),
+            None => errors.push(RowError {
+                line: i + 1,
+                to: String::new(),
+                reason: "no phone number field".into(),
+            }),
+        }
+    }
+    (rows, errors)
+}
+
+fn split_csv(line: &str) -> impl Iterator<Item = &str> {
+    line.split(',')
+        .map(|field| field.trim().trim_matches('"').trim())
+}
+
+fn number_column<'a>(headers: impl Iterator<Item = &'a str>) -> usize {
+    headers
+        .map(|h| h.to_lowercase().replace(' ', "_"))
+        .position(|h| NUMBER_HEADERS.contains(&h.as_str()))
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    const CSV: &str = "name,Phone Number,code\n\
+        Ada,+1 (555) 123-0000,1234\n\
+        Bob,not a number,5678\n\
+        Ada again,+15551230000,9999\n\
+        Cy,+447700900000\n\
+        Di,+447700900001,4321\n";
+
+    #[test]
+    fn parses_csv_with_header_and_vars() {
+        let (rows, errors) = parse_csv(CSV);
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows[0].line, 2);
+        assert_eq!(rows[0].to, "+1 (555) 123-0000");
+        assert_eq!(rows[0].vars["name"], "Ada");
+        assert_eq!(rows[0].vars["code"], "1234");
+        assert!(!rows[0].vars.contains_key("Phone Number"));
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].line, 5);
+    }
+
+    #[test]
+    fn parses_jsonl() {
+        let text = "{\"to\":\"+15551230000\",\"name\":\"Ada\",\"code\":1234}\n\nnot json\n{\"name\":\"Bob\"}\n";
+        let (rows, errors) = parse_jsonl(text);
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].to, "+15551230000");
+        assert_eq!(rows[0].vars["code"], "1234");
+        assert_eq!(
+            errors.iter().map(|e| e.line).collect::<Vec<_>>(),
+            vec![3, 4]
+        );
+    }
+
+    #[test]
+    fn renders_templates() {
+        let vars = BTreeMap::from([("name".to_string(), "Ada".to_string())]);
+        assert_eq!(render("Hi {name}!", &vars).unwrap(), "Hi Ada!");
+        assert_eq!(render("no vars {", &vars).unwrap(), "no vars {");
+        assert!(render("Hi {nmae}", &vars).is_err());
+    }
+
+    #[tokio::test]
+    async fn queues_valid_unique_rows_and_reports_the_rest() {
+        let (rows, _) = parse_csv(CSV);
+        let outbox = Outbox::new();
+        let progress = Arc::new(Mutex::new(Vec::new()));
+        let log = progress.clone();
+        let report = BulkImport::new("+15550001111", "Hi {name}, code {code}")
+            .on_progress(move |p| log.lock().unwrap().push(*p))
+            .run(rows, &outbox)
+            .await;
+
+        assert_eq!(report.queued.len(), 2);
+        assert_eq!(report.duplicates, 1);
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.errors[0].line, 3);
+        assert_eq!(outbox.len_by_priority(Priority::Bulk).await, 2);
+
+        let first = outbox.pop().await.unwrap();
+        assert_eq!(first.request.to, "+15551230000");
+        assert_eq!(first.request.text, "Hi Ada, code 1234");
+
+        let progress = progress.lock().unwrap();
+        assert_eq!(progress.len(), 4);
+        assert_eq!(
+            progress[3],
+            BulkProgress {
+                processed: 4,
+                total: 4,
+                queued: 2,
+                rejected: 1
+            }
+        );
+
+        let csv = report.error_csv();
+        assert!(csv.starts_with("line,to,reason\n3,not a number,"));
+    }
+}
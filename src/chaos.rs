@@ -0,0 +1,256 @@
+//! Fault injection for chaos testing.
+//!
+//! [`ChaosClient`] wraps any [`SmsClient`] and, with configurable
+//! probabilities, replaces the real call with a timeout, an HTTP 429, or a
+//! provider response that can't be parsed.  Use it in staging to exercise retry,
+//! failover, and circuit-breaker layers against realistic failures.
+//!
+//! ```rust,ignore
+//! use smskit::chaos::{ChaosClient, ChaosConfig};
+//!
+//! let client = ChaosClient::new(plivo_client, ChaosConfig {
+//!     timeout_rate: 0.05,
+//!     rate_limit_rate: 0.10,
+//!     ..Default::default()
+//! });
+//! ```
+
+use crate::runtime::sleep;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use sms_core::{Channel, SendRequest, SendResponse, SmsClient, SmsError, redact_number};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tracing::warn;
+
+/// Fault probabilities for a [`ChaosClient`].
+///
+/// Each rate is a probability in `0.0..=1.0`.  At most one fault is injected
+/// per call; the rates are checked in field order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChaosConfig {
+    /// Probability that a call hangs for `timeout_delay` and then fails.
+    pub timeout_rate: f64,
+    /// How long an injected timeout stalls before failing.
+    pub timeout_delay: Duration,
+    /// Probability that a call fails with HTTP 429 Too Many Requests.
+    pub rate_limit_rate: f64,
+    /// Probability that a call fails because the provider's body can't be
+    /// parsed.
+    pub malformed_rate: f64,
+}
+
+impl Default for ChaosConfig {
+    fn default() -> Self {
+        Self {
+            timeout_rate: 0.0,
+            timeout_delay: Duration::from_secs(30),
+            rate_limit_rate: 0.0,
+            malformed_rate: 0.0,
+        }
+    }
+}
+
+/// The fault chosen for a single call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Fault {
+    Timeout,
+    RateLimited,
+    Malformed,
+}
+
+/// An [`SmsClient`] decorator that injects faults around an inner client.
+pub struct ChaosClient<C> {
+    inner: C,
+    config: ChaosConfig,
+    enabled: AtomicBool,
+    rng: Mutex<StdRng>,
+}
+
+impl<C: SmsClient> ChaosClient<C> {
+    /// Wrap `inner`, injecting faults according to `config`.
+    pub fn new(inner: C, config: ChaosConfig) -> Self {
+        Self {
+            inner,
+            config,
+            enabled: AtomicBool::new(true),
+            rng: Mutex::new(StdRng::from_entropy()),
+        }
+    }
+
+    /// Seed the random number generator for reproducible runs.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = Mutex::new(StdRng::seed_from_u64(seed));
+        self
+    }
+
+    /// Turn fault injection on or off at runtime.  While disabled every call
+    /// goes straight to the inner client.
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Returns `true` if faults are currently being injected.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Borrow the wrapped client.
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    fn pick_fault(&self) -> Option<Fault> {
+        if !self.is_enabled() {
+            return None;
+        }
+        let mut rng = self.rng.lock().expect("chaos rng poisoned");
+        let mut hit = |rate: f64| rng.gen_bool(rate.clamp(0.0, 1.0));
+        if hit(self.config.timeout_rate) {
+            Some(Fault::Timeout)
+        } else if hit(self.config.rate_limit_rate) {
+            Some(Fault::RateLimited)
+        } else if hit(self.config.malformed_rate) {
+            Some(Fault::Malformed)
+        } else {
+            None
+        }
+    }
+}
+
+impl<C: SmsClient> SmsClient for ChaosClient<C> {
+    async fn send(&self, req: SendRequest<'_>) -> Result<SendResponse, SmsError> {
+        match self.pick_fault() {
+            None => self.inner.send(req).await,
+            Some(Fault::Timeout) => {
+                warn!(
+                    "Chaos: injecting timeout for send to {}",
+                    redact_number(req.to)
+                );
+                sleep(self.config.timeout_delay).await;
+                Err(SmsError::Timeout("operation timed out (injected)".into()))
+            }
+            Some(Fault::RateLimited) => {
+                warn!(
+                    "Chaos: injecting HTTP 429 for send to {}",
+                    redact_number(req.to)
+                );
+                Err(SmsError::RateLimited { retry_after: None })
+            }
+            Some(Fault::Malformed) => {
+                warn!(
+                    "Chaos: injecting malformed response for send to {}",
+                    redact_number(req.to)
+                );
+                // What a real client reports when the provider answers 200
+                // with a body it can't parse, e.g. an HTML error page.
+                Err(SmsError::Provider(
+                    "invalid JSON in provider response: <html><body>502 Bad Gateway</body>".into(),
+                ))
+            }
+        }
+    }
+
+    fn supports(&self, channel: Channel) -> bool {
+        self.inner.supports(channel)
+    }
+
+    fn max_text_len(&self) -> Option<usize> {
+        self.inner.max_text_len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct OkClient;
+
+    impl SmsClient for OkClient {
+        async fn send(&self, _req: SendRequest<'_>) -> Result<SendResponse, SmsError> {
+            Ok(SendResponse {
+                id: "real".into(),
+                provider: "ok",
+                channel: Channel::Sms,
+                http_status: None,
+                provider_request_id: None,
+                thread_id: None,
+                raw: serde_json::json!({}),
+            })
+        }
+    }
+
+    fn req() -> SendRequest<'static> {
+        SendRequest {
+            to: "+1",
+            from: "+2",
+            text: "chaos",
+            channel: Channel::Sms,
+            timeout: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn no_faults_by_default() {
+        let client = ChaosClient::new(OkClient, ChaosConfig::default());
+        for _ in 0..10 {
+            assert_eq!(client.send(req()).await.unwrap().id, "real");
+        }
+    }
+
+    #[tokio::test]
+    async fn injects_rate_limit() {
+        let client = ChaosClient::new(
+            OkClient,
+            ChaosConfig {
+                rate_limit_rate: 1.0,
+                ..Default::default()
+            },
+        );
+        let err = client.send(req()).await.unwrap_err();
+        assert!(matches!(err, SmsError::RateLimited { .. }));
+    }
+
+    #[tokio::test]
+    async fn injects_timeout_after_delay() {
+        let client = ChaosClient::new(
+            OkClient,
+            ChaosConfig {
+                timeout_rate: 1.0,
+                timeout_delay: Duration::from_millis(5),
+                ..Default::default()
+            },
+        );
+        let err = client.send(req()).await.unwrap_err();
+        assert!(matches!(err, SmsError::Timeout(_)));
+    }
+
+    #[tokio::test]
+    async fn injects_malformed_response() {
+        let client = ChaosClient::new(
+            OkClient,
+            ChaosConfig {
+                malformed_rate: 1.0,
+                ..Default::default()
+            },
+        );
+        let err = client.send(req()).await.unwrap_err();
+        assert!(matches!(&err, SmsError::Provider(msg) if msg.contains("invalid JSON")));
+    }
+
+    #[tokio::test]
+    async fn disabled_passes_through() {
+        let client = ChaosClient::new(
+            OkClient,
+            ChaosConfig {
+                rate_limit_rate: 1.0,
+                ..Default::default()
+            },
+        );
+        client.set_enabled(false);
+        assert_eq!(client.send(req()).await.unwrap().id, "real");
+        assert!(!client.is_enabled());
+    }
+}
@@ -1,6 +1,24 @@
+use crate::dry_run::DryRunClient;
+use crate::mock::MockClient;
+use crate::screening::DestinationRules;
 use config::{Config, ConfigError, Environment, File};
-use serde::{Deserialize, Serialize};
+use serde::de::{DeserializeOwned, Error as _};
+use serde::ser::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+use sms_core::{
+    BuiltProvider, DynSmsClient, ExposeSecret, HttpClientConfig, InboundRegistry, InboundWebhook,
+    ProviderPlugin, RedactionPolicy, SecretString, SmsError, SmsRouter,
+};
+use std::collections::BTreeMap;
 use std::env;
+use std::fmt;
+use std::sync::Arc;
+
+// Link the bundled providers so their plugins are registered.
+use sms_aws_sns as _;
+use sms_plivo as _;
+use sms_twilio as _;
 
 /// Application configuration
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -15,6 +33,26 @@ pub struct AppConfig {
     pub logging: LoggingConfig,
     /// Rate limiting configuration
     pub rate_limit: RateLimitConfig,
+    /// Outbound destination blocklist and allowlist
+    #[serde(default)]
+    pub destinations: DestinationRules,
+    /// Validate and log sends without calling any provider
+    #[serde(default)]
+    pub dry_run: bool,
+    /// `live` (default) or `test`, which swaps every provider for a mock
+    #[serde(default)]
+    pub mode: Mode,
+}
+
+/// Whether configured providers are real or mocked.
+#[derive(Debug, Default, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    /// Send through the configured providers.
+    #[default]
+    Live,
+    /// Build a [`MockClient`] under each configured provider's name instead.
+    Test,
 }
 
 /// Server configuration
@@ -37,6 +75,150 @@ pub struct ProvidersConfig {
     pub twilio: Option<TwilioConfig>,
     /// AWS SNS configuration
     pub aws_sns: Option<AwsSnsConfig>,
+    /// Sections for any other compiled-in provider, keyed by its plugin
+    /// name and passed to the plugin as-is
+    #[serde(flatten)]
+    pub other: BTreeMap<String, ProviderSection>,
+}
+
+impl ProvidersConfig {
+    /// Every configured provider as `(plugin name, settings)`, with
+    /// credentials kept in the section for the plugin to read.
+    pub fn sections(&self) -> Result<Vec<(String, ProviderSection)>, SmsError> {
+        let mut sections = Vec::new();
+        if let Some(plivo) = &self.plivo {
+            let settings = PlivoSection {
+                auth_id: &plivo.auth_id,
+                auth_token: plivo.auth_token.expose_secret(),
+                http: HttpSection(&plivo.http),
+            };
+            sections.push(("plivo".to_string(), ProviderSection::new(&settings)?));
+        }
+        if let Some(twilio) = &self.twilio {
+            let settings = TwilioSection {
+                account_sid: &twilio.account_sid,
+                auth_token: twilio.auth_token.expose_secret(),
+                previous_auth_tokens: twilio
+                    .previous_auth_tokens
+                    .iter()
+                    .map(|token| token.expose_secret())
+                    .collect(),
+                http: HttpSection(&twilio.http),
+            };
+            sections.push(("twilio".to_string(), ProviderSection::new(&settings)?));
+        }
+        if let Some(sns) = &self.aws_sns {
+            let settings = AwsSnsSection {
+                region: &sns.region,
+                access_key_id: &sns.access_key_id,
+                secret_access_key: sns.secret_access_key.expose_secret(),
+            };
+            sections.push(("aws_sns".to_string(), ProviderSection::new(&settings)?));
+        }
+        sections.extend(self.other.iter().map(|(k, v)| (k.clone(), v.clone())));
+        Ok(sections)
+    }
+}
+
+/// One provider's configuration section, as handed to its plugin.
+///
+/// A section may hold any credential the provider needs, so it is kept as
+/// a [`SecretString`] (zeroized on drop), prints as `[REDACTED]` and
+/// serializes with every value replaced by [`REDACTED`].  Only
+/// [`settings`](Self::settings) reads the credentials back.
+#[derive(Clone)]
+pub struct ProviderSection(SecretString);
+
+impl ProviderSection {
+    /// Serialize `settings` straight into a section.
+    pub fn new(settings: &impl Serialize) -> Result<Self, SmsError> {
+        serde_json::to_string(settings)
+            .map(|json| Self(SecretString::from(json)))
+            .map_err(|e| SmsError::Invalid(format!("provider settings: {}", e)))
+    }
+
+    /// Deserialize the section, credentials included, into `T`.
+    pub fn settings<T: DeserializeOwned>(&self) -> Result<T, SmsError> {
+        serde_json::from_str(self.0.expose_secret())
+            .map_err(|e| SmsError::Invalid(format!("provider settings: {}", e)))
+    }
+}
+
+impl fmt::Debug for ProviderSection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ProviderSection").field(&REDACTED).finish()
+    }
+}
+
+impl Serialize for ProviderSection {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        let settings: Value = self.settings().map_err(S::Error::custom)?;
+        redact_values(settings).serialize(s)
+    }
+}
+
+impl<'de> Deserialize<'de> for ProviderSection {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        Self::new(&Value::deserialize(d)?).map_err(D::Error::custom)
+    }
+}
+
+/// Keep the shape of a section, so it shows which keys are set, but none
+/// of its values.
+fn redact_values(value: Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(key, value)| (key, redact_values(value)))
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.into_iter().map(redact_values).collect()),
+        Value::Null => Value::Null,
+        _ => Value::String(REDACTED.to_string()),
+    }
+}
+
+// The typed configs serialize with credentials redacted; these borrow
+// them exposed, just long enough to write the plugin's section.
+
+#[derive(Serialize)]
+struct PlivoSection<'a> {
+    auth_id: &'a str,
+    auth_token: &'a str,
+    http: HttpSection<'a>,
+}
+
+#[derive(Serialize)]
+struct TwilioSection<'a> {
+    account_sid: &'a str,
+    auth_token: &'a str,
+    previous_auth_tokens: Vec<&'a str>,
+    http: HttpSection<'a>,
+}
+
+#[derive(Serialize)]
+struct AwsSnsSection<'a> {
+    region: &'a str,
+    access_key_id: &'a str,
+    secret_access_key: &'a str,
+}
+
+/// Transport settings with the proxy password that
+/// [`HttpClientConfig`]'s own serialization redacts.
+struct HttpSection<'a>(&'a Option<HttpClientConfig>);
+
+impl Serialize for HttpSection<'_> {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        let Some(http) = self.0 else {
+            return s.serialize_none();
+        };
+        let mut value = serde_json::to_value(http).map_err(S::Error::custom)?;
+        let password = http.proxy.as_ref().and_then(|p| p.password.as_ref());
+        if let (Some(password), Some(proxy)) = (password, value.get_mut("proxy")) {
+            proxy["password"] = Value::String(password.expose_secret().to_string());
+        }
+        value.serialize(s)
+    }
 }
 
 /// Plivo provider configuration
@@ -45,9 +227,13 @@ pub struct PlivoConfig {
     /// Plivo Auth ID
     pub auth_id: String,
     /// Plivo Auth Token
-    pub auth_token: String,
+    #[serde(serialize_with = "redact")]
+    pub auth_token: SecretString,
     /// Webhook signature validation (default: true)
     pub verify_signatures: bool,
+    /// Transport settings: proxy, `User-Agent`, extra headers
+    #[serde(default)]
+    pub http: Option<HttpClientConfig>,
 }
 
 /// Twilio provider configuration
@@ -56,9 +242,17 @@ pub struct TwilioConfig {
     /// Twilio Account SID
     pub account_sid: String,
     /// Twilio Auth Token
-    pub auth_token: String,
+    #[serde(serialize_with = "redact")]
+    pub auth_token: SecretString,
+    /// Previous Auth Tokens still accepted for webhook signatures while a
+    /// rotation completes
+    #[serde(default, serialize_with = "redact_all")]
+    pub previous_auth_tokens: Vec<SecretString>,
     /// Webhook signature validation (default: true)
     pub verify_signatures: bool,
+    /// Transport settings: proxy, `User-Agent`, extra headers
+    #[serde(default)]
+    pub http: Option<HttpClientConfig>,
 }
 
 /// AWS SNS provider configuration
@@ -67,7 +261,8 @@ pub struct AwsSnsConfig {
     /// AWS Access Key ID
     pub access_key_id: String,
     /// AWS Secret Access Key
-    pub secret_access_key: String,
+    #[serde(serialize_with = "redact")]
+    pub secret_access_key: SecretString,
     /// AWS Region
     pub region: String,
 }
@@ -81,8 +276,20 @@ pub struct SecurityConfig {
     pub max_body_size: usize,
     /// Request timeout in seconds (default: 30)
     pub request_timeout: u64,
+    /// Bearer token for the admin endpoints; they are disabled when unset
+    #[serde(default, serialize_with = "redact_opt")]
+    pub admin_token: Option<SecretString>,
 }
 
+/// Written in place of every credential when a config is serialized.
+pub use sms_core::redact_secret::REDACTED;
+
+// Serializing a config (to log it, or to show it on a debug endpoint) must
+// never leak credentials, so they are written as `REDACTED`.
+use sms_core::redact_secret::{
+    serialize as redact, serialize_all as redact_all, serialize_opt as redact_opt,
+};
+
 /// Logging configuration
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct LoggingConfig {
@@ -90,6 +297,17 @@ pub struct LoggingConfig {
     pub level: String,
     /// Log format: json or pretty (default: json)
     pub format: String,
+    /// How phone numbers and message text may appear in logs (default:
+    /// numbers masked, text omitted)
+    #[serde(default)]
+    pub redaction: RedactionPolicy,
+}
+
+impl LoggingConfig {
+    /// Install [`redaction`](Self::redaction) as the process-wide policy.
+    pub fn apply_redaction(&self) {
+        sms_core::set_redaction_policy(self.redaction);
+    }
 }
 
 /// Rate limiting configuration
@@ -119,6 +337,7 @@ impl Default for SecurityConfig {
             verify_signatures: true,
             max_body_size: 1024 * 1024, // 1MB
             request_timeout: 30,
+            admin_token: None,
         }
     }
 }
@@ -128,6 +347,7 @@ impl Default for LoggingConfig {
         Self {
             level: "info".to_string(),
             format: "json".to_string(),
+            redaction: RedactionPolicy::default(),
         }
     }
 }
@@ -161,6 +381,67 @@ impl AppConfig {
 
         s.try_deserialize()
     }
+
+    /// Build a webhook registry holding every configured provider that
+    /// receives webhooks.
+    ///
+    /// Providers are instantiated through their [`ProviderPlugin`], so any
+    /// compiled-in provider crate works without changes here.  Returns
+    /// [`SmsError::Invalid`] for a section with no registered plugin.
+    pub fn build_registry(&self) -> Result<InboundRegistry, SmsError> {
+        let mut registry = InboundRegistry::new();
+        for (name, section) in self.providers.sections()? {
+            if let Some(hook) = self.build_provider(&name, &section)?.webhook {
+                registry = registry.with(hook);
+            }
+        }
+        Ok(registry)
+    }
+
+    /// Build a router holding every configured provider that can send,
+    /// keyed by plugin name.  The first configured provider is the default.
+    ///
+    /// With [`dry_run`](Self::dry_run) set, each provider is wrapped in a
+    /// [`DryRunClient`] so routing and validation run but nothing is sent.
+    pub fn build_router(&self) -> Result<SmsRouter, SmsError> {
+        let mut router = SmsRouter::new();
+        for (name, section) in self.providers.sections()? {
+            if let Some(mut client) = self.build_provider(&name, &section)?.client {
+                if self.dry_run {
+                    client = Arc::new(DryRunClient::new(client));
+                }
+                router = router.with_arc(name, client);
+            }
+        }
+        Ok(router)
+    }
+
+    /// Instantiate one provider through its plugin, substituting a mock in
+    /// [`Mode::Test`].
+    fn build_provider(
+        &self,
+        name: &str,
+        section: &ProviderSection,
+    ) -> Result<BuiltProvider, SmsError> {
+        let plugin = ProviderPlugin::find(name).ok_or_else(|| {
+            SmsError::Invalid(format!("no provider plugin registered for {:?}", name))
+        })?;
+        let built = plugin.build(&section.settings()?)?;
+        if self.mode == Mode::Live {
+            return Ok(built);
+        }
+
+        // Keep the real provider's webhook key so routing is unchanged.
+        let provider = built
+            .webhook
+            .as_ref()
+            .map_or("mock", |hook| hook.provider());
+        let mock = Arc::new(MockClient::new().with_provider_name(provider));
+        Ok(BuiltProvider {
+            client: built.client.map(|_| mock.clone() as Arc<dyn DynSmsClient>),
+            webhook: built.webhook.map(|_| mock as Arc<dyn InboundWebhook>),
+        })
+    }
 }
 
 impl Default for AppConfig {
@@ -171,10 +452,14 @@ impl Default for AppConfig {
                 plivo: None,
                 twilio: None,
                 aws_sns: None,
+                other: BTreeMap::new(),
             },
             security: SecurityConfig::default(),
             logging: LoggingConfig::default(),
             rate_limit: RateLimitConfig::default(),
+            destinations: DestinationRules::default(),
+            dry_run: false,
+            mode: Mode::Live,
         }
     }
 }
@@ -197,6 +482,7 @@ mod tests {
         assert!(cfg.verify_signatures);
         assert_eq!(cfg.max_body_size, 1024 * 1024);
         assert_eq!(cfg.request_timeout, 30);
+        assert!(cfg.admin_token.is_none());
     }
 
     #[test]
@@ -204,6 +490,15 @@ mod tests {
         let cfg = LoggingConfig::default();
         assert_eq!(cfg.level, "info");
         assert_eq!(cfg.format, "json");
+        assert_eq!(cfg.redaction, RedactionPolicy::default());
+    }
+
+    #[test]
+    fn redaction_policy_deserializes_with_defaults() {
+        let json = r#"{"level": "debug", "format": "pretty", "redaction": {"text": "hash"}}"#;
+        let cfg: LoggingConfig = serde_json::from_str(json).unwrap();
+        assert_eq!(cfg.redaction.numbers, sms_core::NumberRedaction::Mask);
+        assert_eq!(cfg.redaction.text, sms_core::TextRedaction::Hash);
     }
 
     #[test]
@@ -222,6 +517,79 @@ mod tests {
         assert!(cfg.providers.aws_sns.is_none());
     }
 
+    #[test]
+    fn build_registry_uses_provider_plugins() {
+        let mut cfg = AppConfig::default();
+        assert!(cfg.build_registry().unwrap().is_empty());
+
+        cfg.providers.plivo = Some(PlivoConfig {
+            auth_id: "MA1".into(),
+            auth_token: SecretString::from("token"),
+            verify_signatures: true,
+            http: Some(HttpClientConfig::new().with_user_agent("acme/1.0")),
+        });
+        let json = r#"{"account_sid":"AC1","auth_token":"t","verify_signatures":true}"#;
+        cfg.providers.twilio = Some(serde_json::from_str(json).unwrap());
+        let registry = cfg.build_registry().unwrap();
+        assert!(registry.get("plivo").is_some());
+        assert!(registry.get("twilio").is_some());
+        assert!(cfg.build_router().unwrap().has_provider("twilio"));
+
+        cfg.providers.other.insert(
+            "carrier-pigeon".into(),
+            ProviderSection::new(&serde_json::json!({})).unwrap(),
+        );
+        let Err(err) = cfg.build_registry() else {
+            panic!("unknown provider should be rejected");
+        };
+        assert!(err.to_string().contains("carrier-pigeon"));
+    }
+
+    #[tokio::test]
+    async fn dry_run_wraps_every_routed_provider() {
+        let json = r#"{"account_sid":"AC1","auth_token":"t","verify_signatures":true}"#;
+        let mut cfg = AppConfig {
+            dry_run: true,
+            ..AppConfig::default()
+        };
+        cfg.providers.twilio = Some(serde_json::from_str(json).unwrap());
+        let router = cfg.build_router().unwrap();
+        let req = sms_core::OwnedSendRequest::new("+15551230000", "+15559870000", "hi");
+        let resp = router.send_via("twilio", req.as_ref()).await.unwrap();
+        assert_eq!(resp.provider, crate::dry_run::DRY_RUN_PROVIDER);
+    }
+
+    #[tokio::test]
+    async fn test_mode_substitutes_mocks_under_the_same_keys() {
+        let cfg: AppConfig = serde_json::from_value(serde_json::json!({
+            "server": ServerConfig::default(),
+            "providers": {
+                "twilio": {"account_sid": "AC1", "auth_token": "t", "verify_signatures": true}
+            },
+            "security": SecurityConfig::default(),
+            "logging": LoggingConfig::default(),
+            "rate_limit": RateLimitConfig::default(),
+            "mode": "test"
+        }))
+        .unwrap();
+        assert_eq!(cfg.mode, Mode::Test);
+
+        let router = cfg.build_router().unwrap();
+        let req = sms_core::OwnedSendRequest::new("+15551230000", "+15559870000", "hi");
+        let resp = router.send_via("twilio", req.as_ref()).await.unwrap();
+        assert_eq!(resp.provider, "twilio");
+        assert!(resp.id.starts_with("mock-"));
+
+        let hook = cfg.build_registry().unwrap().get("twilio").unwrap();
+        let body = br#"{"from":"+15559870000","to":"+15551230000","text":"hi"}"#;
+        assert_eq!(
+            hook.parse_inbound(&sms_core::Headers::new(), body)
+                .unwrap()
+                .text,
+            "hi"
+        );
+    }
+
     #[test]
     fn app_config_serde_roundtrip() {
         let cfg = AppConfig::default();
@@ -230,4 +598,58 @@ mod tests {
         assert_eq!(deser.server.port, 3000);
         assert_eq!(deser.security.max_body_size, 1024 * 1024);
     }
+
+    #[test]
+    fn credentials_are_redacted_when_serialized() {
+        let json = r#"{"account_sid":"AC1","auth_token":"s3cr3t","previous_auth_tokens":["0ld-t0ken"],"verify_signatures":true}"#;
+        let cfg: TwilioConfig = serde_json::from_str(json).unwrap();
+        assert_eq!(cfg.auth_token.expose_secret(), "s3cr3t");
+        assert_eq!(cfg.previous_auth_tokens[0].expose_secret(), "0ld-t0ken");
+
+        let out = serde_json::to_value(&cfg).unwrap();
+        assert_eq!(out["account_sid"], "AC1");
+        assert_eq!(out["auth_token"], REDACTED);
+        assert_eq!(out["previous_auth_tokens"][0], REDACTED);
+        let debug = format!("{:?}", cfg);
+        assert!(!debug.contains("s3cr3t") && !debug.contains("0ld-t0ken"));
+    }
+
+    #[test]
+    fn proxy_password_is_redacted_but_reaches_the_plugin() {
+        let json = r#"{"auth_id":"MA1","auth_token":"t","verify_signatures":true,
+            "http":{"proxy":{"url":"http://proxy:3128","username":"u","password":"pr0xy"}}}"#;
+        let plivo: PlivoConfig = serde_json::from_str(json).unwrap();
+
+        let out = serde_json::to_value(&plivo).unwrap();
+        assert_eq!(out["http"]["proxy"]["password"], REDACTED);
+        assert!(!format!("{:?}", plivo).contains("pr0xy"));
+
+        let providers = ProvidersConfig {
+            plivo: Some(plivo),
+            twilio: None,
+            aws_sns: None,
+            other: BTreeMap::new(),
+        };
+        let (_, section) = &providers.sections().unwrap()[0];
+        let settings: Value = section.settings().unwrap();
+        assert_eq!(settings["http"]["proxy"]["password"], "pr0xy");
+    }
+
+    #[test]
+    fn other_provider_sections_are_redacted() {
+        let json = r#"{"sink":{"path":"/tmp/sms.jsonl"},"acme":{"api_key":"k3y","opts":{"retries":3,"region":null}}}"#;
+        let providers: ProvidersConfig = serde_json::from_str(json).unwrap();
+
+        let out = serde_json::to_value(&providers).unwrap();
+        assert_eq!(out["acme"]["api_key"], REDACTED);
+        assert_eq!(out["acme"]["opts"]["retries"], REDACTED);
+        assert!(out["acme"]["opts"]["region"].is_null());
+        assert!(!format!("{:?}", providers).contains("k3y"));
+
+        let sections = providers.sections().unwrap();
+        let (name, section) = &sections[0];
+        assert_eq!(name, "acme");
+        let settings: Value = section.settings().unwrap();
+        assert_eq!(settings["api_key"], "k3y");
+    }
 }
@@ -0,0 +1,323 @@
+//! Send metadata carried through to delivery reports.
+//!
+//! Providers echo only their own message ID in delivery reports, so
+//! [`DeliveryCorrelator`] keeps the tags an application attached to each
+//! send — tenant, campaign, order number — keyed by that ID, and hands them
+//! back with the matching delivery report.  [`CorrelatedClient`] records
+//! them as part of the send.
+//!
+//! ```rust,ignore
+//! use smskit::correlation::{CorrelatedClient, DeliveryCorrelator};
+//!
+//! let correlator = DeliveryCorrelator::default()
+//!     .on_delivery(|d| route_to_tenant(&d.metadata["tenant"], &d.message));
+//! let processor = WebhookProcessor::new(registry).with_listener(correlator.listener());
+//!
+//! let client = CorrelatedClient::new(twilio, correlator.clone());
+//! client
+//!     .send_with_metadata(req, [("tenant", "acme"), ("campaign", "spring")])
+//!     .await?;
+//! ```
+
+use crate::clock::{Clock, SystemClock};
+use sms_core::{
+    Channel, InboundDirection, InboundMessage, SendRequest, SendResponse, SmsClient, SmsError,
+    normalize_number,
+};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How long metadata is kept after a send when no TTL is configured.
+/// Delivery reports for SMS rarely arrive later than three days.
+pub const DEFAULT_CORRELATION_TTL: Duration = Duration::from_secs(72 * 3600);
+
+/// Tags attached to a send.
+pub type Metadata = BTreeMap<String, String>;
+
+/// A delivery report matched to the metadata of its send.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CorrelatedDelivery {
+    /// The delivery report.
+    pub message: InboundMessage,
+    /// Metadata recorded when the message was sent.
+    pub metadata: Metadata,
+}
+
+type DeliveryCallback = dyn Fn(&CorrelatedDelivery) + Send + Sync;
+
+/// What is kept per send: its metadata, recipient and send time.
+struct Entry {
+    metadata: Metadata,
+    to: String,
+    sent_at: Instant,
+}
+
+/// Matches delivery reports to the metadata of the sends they report on.
+///
+/// Metadata is kept until `ttl` after the send, so every status update for a
+/// message (queued, sent, delivered) is correlated.  Cloning yields another
+/// handle to the same entries and callbacks.
+#[derive(Clone)]
+pub struct DeliveryCorrelator {
+    entries: Arc<Mutex<HashMap<String, Entry>>>,
+    ttl: Duration,
+    callbacks: Vec<Arc<DeliveryCallback>>,
+    clock: Arc<dyn Clock>,
+}
+
+impl Default for DeliveryCorrelator {
+    fn default() -> Self {
+        Self::new(DEFAULT_CORRELATION_TTL)
+    }
+}
+
+impl DeliveryCorrelator {
+    /// Keep metadata for `ttl` after each send.
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            entries: Arc::default(),
+            ttl,
+            callbacks: Vec::new(),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Call `callback` for every delivery report that matches a send.
+    pub fn on_delivery(
+        mut self,
+        callback: impl Fn(&CorrelatedDelivery) + Send + Sync + 'static,
+    ) -> Self {
+        self.callbacks.push(Arc::new(callback));
+        self
+    }
+
+    /// Read time from `clock` instead of the system clock.
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Remember `metadata` for `req`, which `resp` reports as sent.
+    pub fn record_send(&self, req: &SendRequest<'_>, resp: &SendResponse, metadata: Metadata) {
+        let now = self.clock.now();
+        let mut entries = self.lock();
+        entries.retain(|_, entry| now.saturating_duration_since(entry.sent_at) < self.ttl);
+        entries.insert(
+            resp.id.clone(),
+            Entry {
+                metadata,
+                to: normalize_number(req.to),
+                sent_at: now,
+            },
+        );
+    }
+
+    /// The metadata recorded for `message_id`, if it hasn't expired.
+    pub fn metadata(&self, message_id: &str) -> Option<Metadata> {
+        let now = self.clock.now();
+        self.lock()
+            .get(message_id)
+            .filter(|entry| now.saturating_duration_since(entry.sent_at) < self.ttl)
+            .map(|entry| entry.metadata.clone())
+    }
+
+    /// Drop the metadata of every send to `phone`, e.g. for a GDPR erasure
+    /// request.  Returns how many sends were forgotten.
+    pub fn forget_number(&self, phone: &str) -> usize {
+        let phone = normalize_number(phone);
+        let mut entries = self.lock();
+        let before = entries.len();
+        entries.retain(|_, entry| entry.to != phone);
+        before - entries.len()
+    }
+
+    /// Match a delivery report to its send, calling the
+    /// [`on_delivery`](Self::on_delivery) callbacks on a match.
+    ///
+    /// Returns `None` for inbound messages, reports without an ID and reports
+    /// for unknown or expired sends.
+    pub fn correlate(&self, message: &InboundMessage) -> Option<CorrelatedDelivery> {
+        if message.direction != InboundDirection::DeliveryReport {
+            return None;
+        }
+        let metadata = self.metadata(message.id.as_deref()?)?;
+        let delivery = CorrelatedDelivery {
+            message: message.clone(),
+            metadata,
+        };
+        for callback in &self.callbacks {
+            callback(&delivery);
+        }
+        Some(delivery)
+    }
+
+    /// A webhook listener that correlates every message it sees, for
+    /// `WebhookProcessor::with_listener`.
+    pub fn listener(&self) -> impl Fn(&InboundMessage) + Send + Sync + 'static {
+        let correlator = self.clone();
+        move |message| {
+            correlator.correlate(message);
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Entry>> {
+        self.entries.lock().expect("delivery correlator poisoned")
+    }
+}
+
+/// Wraps an [`SmsClient`] and records send metadata in a
+/// [`DeliveryCorrelator`].
+///
+/// Plain [`SmsClient::send`] calls record no metadata.
+pub struct CorrelatedClient<C> {
+    inner: C,
+    correlator: DeliveryCorrelator,
+}
+
+impl<C: SmsClient> CorrelatedClient<C> {
+    /// Send through `inner`, recording metadata in `correlator`.
+    pub fn new(inner: C, correlator: DeliveryCorrelator) -> Self {
+        Self { inner, correlator }
+    }
+
+    /// The correlator metadata is recorded in.
+    pub fn correlator(&self) -> &DeliveryCorrelator {
+        &self.correlator
+    }
+
+    /// Send `req` and, if it succeeds, remember `metadata` for its delivery
+    /// reports.
+    pub async fn send_with_metadata<I, K, V>(
+        &self,
+        req: SendRequest<'_>,
+        metadata: I,
+    ) -> Result<SendResponse, SmsError>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        let resp = self.inner.send(req.clone()).await?;
+        let metadata = metadata
+            .into_iter()
+            .map(|(k, v)| (k.into(), v.into()))
+            .collect();
+        self.correlator.record_send(&req, &resp, metadata);
+        Ok(resp)
+    }
+}
+
+impl<C: SmsClient> SmsClient for CorrelatedClient<C> {
+    async fn send(&self, req: SendRequest<'_>) -> Result<SendResponse, SmsError> {
+        self.inner.send(req).await
+    }
+
+    fn supports(&self, channel: Channel) -> bool {
+        self.inner.supports(channel)
+    }
+
+    fn max_text_len(&self) -> Option<usize> {
+        self.inner.max_text_len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use crate::simulation::SimulatedClient;
+
+    fn report(id: &str) -> InboundMessage {
+        InboundMessage {
+            id: Some(id.into()),
+            from: "+2".into(),
+            to: "+1".into(),
+            text: "delivered".into(),
+            timestamp: None,
+            provider: "sim",
+            direction: InboundDirection::DeliveryReport,
+            channel: Channel::Sms,
+            provider_account: None,
+            thread_id: None,
+            flag: None,
+            raw: serde_json::Value::Null,
+        }
+    }
+
+    #[tokio::test]
+    async fn delivery_reports_carry_send_metadata() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = seen.clone();
+        let correlator = DeliveryCorrelator::default()
+            .on_delivery(move |d| sink.lock().unwrap().push(d.metadata["tenant"].clone()));
+        let client = CorrelatedClient::new(SimulatedClient::new(), correlator.clone());
+        let req = SendRequest {
+            to: "+1",
+            from: "+2",
+            text: "hi",
+            ..Default::default()
+        };
+        let resp = client
+            .send_with_metadata(req, [("tenant", "acme")])
+            .await
+            .unwrap();
+
+        let listener = correlator.listener();
+        listener(&report(&resp.id));
+        listener(&report(&resp.id));
+        listener(&report("unknown"));
+        let mut inbound = report(&resp.id);
+        inbound.direction = InboundDirection::Inbound;
+        listener(&inbound);
+        assert_eq!(*seen.lock().unwrap(), vec!["acme", "acme"]);
+    }
+
+    #[test]
+    fn metadata_expires_after_ttl() {
+        let clock = ManualClock::new();
+        let correlator = DeliveryCorrelator::new(Duration::from_secs(60)).with_clock(clock.clone());
+        let resp = SendResponse {
+            id: "m1".into(),
+            provider: "sim",
+            channel: Channel::Sms,
+            http_status: None,
+            provider_request_id: None,
+            thread_id: None,
+            raw: serde_json::Value::Null,
+        };
+        correlator.record_send(
+            &SendRequest::default(),
+            &resp,
+            Metadata::from([("k".into(), "v".into())]),
+        );
+        assert!(correlator.correlate(&report("m1")).is_some());
+        clock.advance(Duration::from_secs(61));
+        assert!(correlator.metadata("m1").is_none());
+        assert!(correlator.correlate(&report("m1")).is_none());
+    }
+
+    #[tokio::test]
+    async fn forget_number_drops_metadata_for_recipient() {
+        let correlator = DeliveryCorrelator::default();
+        let client = CorrelatedClient::new(SimulatedClient::new(), correlator.clone());
+        let req = |to| SendRequest {
+            to,
+            from: "+2",
+            text: "hi",
+            ..Default::default()
+        };
+        let forgotten = client
+            .send_with_metadata(req("+1 555 0100"), [("tenant", "acme")])
+            .await
+            .unwrap();
+        let kept = client
+            .send_with_metadata(req("+15550199"), [("tenant", "acme")])
+            .await
+            .unwrap();
+
+        assert_eq!(correlator.forget_number("+15550100"), 1);
+        assert!(correlator.metadata(&forgotten.id).is_none());
+        assert!(correlator.metadata(&kept.id).is_some());
+    }
+}
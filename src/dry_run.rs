@@ -0,0 +1,137 @@
+//! Dry-run sending for staging and migration rehearsals.
+//!
+//! [`DryRunClient`] wraps any [`SmsClient`] and does everything a send would
+//! except call the provider: it checks the channel and text against the
+//! wrapped client's limits, analyzes encoding and segments, logs the send,
+//! and returns a synthetic [`SendResponse`].  Setting `dry_run = true` in
+//! [`AppConfig`](crate::config::AppConfig) wraps every provider built by
+//! `build_router()`, so routing runs exactly as in production.
+//!
+//! ```rust,ignore
+//! use smskit::dry_run::DryRunClient;
+//!
+//! let client = DryRunClient::new(plivo);
+//! let resp = client.send(req.as_ref()).await?;   // nothing leaves the process
+//! assert_eq!(resp.provider, "dry-run");
+//! ```
+
+use crate::analysis::analyze;
+use sms_core::{
+    Channel, SendRequest, SendResponse, SmsClient, SmsError, redact_number, validate_text,
+};
+use std::sync::atomic::{AtomicU64, Ordering};
+use tracing::info;
+
+/// Provider name reported in dry-run responses.
+pub const DRY_RUN_PROVIDER: &str = "dry-run";
+
+/// Wraps an [`SmsClient`] and validates sends without delivering them.
+pub struct DryRunClient<C> {
+    inner: C,
+    sends: AtomicU64,
+}
+
+impl<C: SmsClient> DryRunClient<C> {
+    /// Validate sends against `inner`'s limits instead of sending them.
+    pub fn new(inner: C) -> Self {
+        Self {
+            inner,
+            sends: AtomicU64::new(0),
+        }
+    }
+
+    /// Number of sends accepted so far.
+    pub fn sends(&self) -> u64 {
+        self.sends.load(Ordering::Relaxed)
+    }
+
+    /// Borrow the wrapped client.
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+}
+
+impl<C: SmsClient> SmsClient for DryRunClient<C> {
+    async fn send(&self, req: SendRequest<'_>) -> Result<SendResponse, SmsError> {
+        if !self.inner.supports(req.channel) {
+            return Err(SmsError::Invalid(format!(
+                "channel {} is not supported",
+                req.channel
+            )));
+        }
+        validate_text(req.text, self.inner.max_text_len())?;
+
+        let analysis = analyze(req.text);
+        let n = self.sends.fetch_add(1, Ordering::Relaxed) + 1;
+        info!(
+            "Dry run: would send {} segment(s) ({:?}) to {}",
+            analysis.segments,
+            analysis.encoding,
+            redact_number(req.to)
+        );
+        Ok(SendResponse {
+            id: format!("dry-run-{}", n),
+            provider: DRY_RUN_PROVIDER,
+            channel: req.channel,
+            http_status: None,
+            provider_request_id: None,
+            thread_id: Some(req.thread_id()),
+            raw: serde_json::json!({
+                "dry_run": true,
+                "encoding": analysis.encoding,
+                "segments": analysis.segments,
+            }),
+        })
+    }
+
+    fn supports(&self, channel: Channel) -> bool {
+        self.inner.supports(channel)
+    }
+
+    fn max_text_len(&self) -> Option<usize> {
+        self.inner.max_text_len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sms_core::OwnedSendRequest;
+
+    struct UnreachableClient;
+
+    impl SmsClient for UnreachableClient {
+        async fn send(&self, _req: SendRequest<'_>) -> Result<SendResponse, SmsError> {
+            panic!("dry run must not reach the provider");
+        }
+
+        fn max_text_len(&self) -> Option<usize> {
+            Some(10)
+        }
+    }
+
+    #[tokio::test]
+    async fn returns_synthetic_response_without_sending() {
+        let client = DryRunClient::new(UnreachableClient);
+        let req = OwnedSendRequest::new("+15551230000", "+15559870000", "hello");
+        let resp = client.send(req.as_ref()).await.unwrap();
+        assert_eq!(resp.id, "dry-run-1");
+        assert_eq!(resp.provider, DRY_RUN_PROVIDER);
+        assert_eq!(resp.raw["segments"], 1);
+        assert_eq!(resp.raw["encoding"], "gsm7");
+        assert_eq!(client.sends(), 1);
+    }
+
+    #[tokio::test]
+    async fn still_validates_against_inner_limits() {
+        let client = DryRunClient::new(UnreachableClient);
+        let long = OwnedSendRequest::new("+1", "+2", "far too long for this one");
+        assert!(matches!(
+            client.send(long.as_ref()).await,
+            Err(SmsError::Invalid(_))
+        ));
+        let whatsapp = OwnedSendRequest::new("+1", "+2", "hi").with_channel(Channel::WhatsApp);
+        assert!(client.send(whatsapp.as_ref()).await.is_err());
+        assert_eq!(client.sends(), 0);
+    }
+}
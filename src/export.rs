@@ -0,0 +1,202 @@
+//! Compliance archiving and analytics export for a [`MessageStore`].
+//!
+//! [`export_messages`] writes the messages in a [`DateRange`] as JSON Lines
+//! or CSV to any [`std::io::Write`].  With the `s3-export` feature,
+//! [`export_to_s3`] uploads the same output as a single object.
+//!
+//! ```rust,ignore
+//! use smskit::export::{export_messages, ExportFormat};
+//! use smskit::store::DateRange;
+//!
+//! let file = std::fs::File::create("2024-01.csv")?;
+//! let count = export_messages(&store, &DateRange::all().since(jan).until(feb), ExportFormat::Csv, file).await?;
+//! ```
+
+use crate::store::{DateRange, MessageStore, StoredMessage};
+use sms_core::SmsError;
+use std::io::Write;
+use time::format_description::well_known::Rfc3339;
+use tracing::info;
+
+/// Output format for [`export_messages`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// One JSON object per line.
+    JsonLines,
+    /// RFC 4180 CSV with a header row.
+    Csv,
+}
+
+impl ExportFormat {
+    /// MIME type of the exported data.
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::JsonLines => "application/x-ndjson",
+            ExportFormat::Csv => "text/csv",
+        }
+    }
+
+    /// Conventional file extension, without the dot.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::JsonLines => "jsonl",
+            ExportFormat::Csv => "csv",
+        }
+    }
+}
+
+const CSV_HEADER: &str = "id,direction,timestamp,provider,from,to,text";
+
+/// Write every message in `range` to `writer` and return how many were
+/// written.
+pub async fn export_messages<S, W>(
+    store: &S,
+    range: &DateRange,
+    format: ExportFormat,
+    mut writer: W,
+) -> Result<usize, SmsError>
+where
+    S: MessageStore + ?Sized,
+    W: Write,
+{
+    let messages = store.query(range).await?;
+    let io_err = |e: std::io::Error| SmsError::Unexpected(format!("export failed: {}", e));
+
+    if format == ExportFormat::Csv {
+        writeln!(writer, "{}", CSV_HEADER).map_err(io_err)?;
+    }
+    for message in &messages {
+        let line = match format {
+            ExportFormat::JsonLines => {
+                serde_json::to_string(message).map_err(|e| SmsError::Unexpected(e.to_string()))?
+            }
+            ExportFormat::Csv => csv_row(message)?,
+        };
+        writeln!(writer, "{}", line).map_err(io_err)?;
+    }
+    writer.flush().map_err(io_err)?;
+
+    info!("Exported {} messages as {:?}", messages.len(), format);
+    Ok(messages.len())
+}
+
+fn csv_row(m: &StoredMessage) -> Result<String, SmsError> {
+    let timestamp = m
+        .timestamp
+        .format(&Rfc3339)
+        .map_err(|e| SmsError::Unexpected(e.to_string()))?;
+    let fields = [
+        m.id.as_str(),
+        m.direction.as_str(),
+        timestamp.as_str(),
+        m.provider.as_str(),
+        m.from.as_str(),
+        m.to.as_str(),
+        m.text.as_str(),
+    ];
+    Ok(fields
+        .iter()
+        .map(|f| csv_escape(f))
+        .collect::<Vec<_>>()
+        .join(","))
+}
+
+fn csv_escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Export messages in `range` and upload them to `s3://{bucket}/{key}`.
+#[cfg(feature = "s3-export")]
+pub async fn export_to_s3<S>(
+    store: &S,
+    range: &DateRange,
+    format: ExportFormat,
+    client: &aws_sdk_s3::Client,
+    bucket: &str,
+    key: &str,
+) -> Result<usize, SmsError>
+where
+    S: MessageStore + ?Sized,
+{
+    let mut buf = Vec::new();
+    let count = export_messages(store, range, format, &mut buf).await?;
+    client
+        .put_object()
+        .bucket(bucket)
+        .key(key)
+        .content_type(format.content_type())
+        .body(buf.into())
+        .send()
+        .await
+        .map_err(|e| SmsError::Http(format!("S3 upload failed: {}", e)))?;
+    info!("Uploaded {} messages to s3://{}/{}", count, bucket, key);
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::{InMemoryMessageStore, MessageDirection};
+    use time::OffsetDateTime;
+
+    async fn store_with(texts: &[(&str, i64)]) -> InMemoryMessageStore {
+        let store = InMemoryMessageStore::new();
+        for (text, ts) in texts {
+            store
+                .save(StoredMessage {
+                    id: format!("id-{}", ts),
+                    direction: MessageDirection::Inbound,
+                    from: "+1".into(),
+                    to: "+2".into(),
+                    text: text.to_string(),
+                    provider: "plivo".into(),
+                    timestamp: OffsetDateTime::from_unix_timestamp(*ts).unwrap(),
+                })
+                .await
+                .unwrap();
+        }
+        store
+    }
+
+    #[tokio::test]
+    async fn exports_json_lines() {
+        let store = store_with(&[("hello", 0), ("world", 60)]).await;
+        let mut out = Vec::new();
+        let count = export_messages(&store, &DateRange::all(), ExportFormat::JsonLines, &mut out)
+            .await
+            .unwrap();
+        assert_eq!(count, 2);
+
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<_> = text.lines().collect();
+        assert_eq!(lines.len(), 2);
+        let first: StoredMessage = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(first.text, "hello");
+    }
+
+    #[tokio::test]
+    async fn exports_escaped_csv_in_range() {
+        let store = store_with(&[("too early", 0), ("hi, \"you\"", 100), ("too late", 200)]).await;
+        let range = DateRange::all()
+            .since(OffsetDateTime::from_unix_timestamp(50).unwrap())
+            .until(OffsetDateTime::from_unix_timestamp(150).unwrap());
+        let mut out = Vec::new();
+        let count = export_messages(&store, &range, ExportFormat::Csv, &mut out)
+            .await
+            .unwrap();
+        assert_eq!(count, 1);
+
+        let text = String::from_utf8(out).unwrap();
+        let mut lines = text.lines();
+        assert_eq!(lines.next(), Some(CSV_HEADER));
+        assert_eq!(
+            lines.next(),
+            Some("id-100,inbound,1970-01-01T00:01:40Z,plivo,+1,+2,\"hi, \"\"you\"\"\"")
+        );
+        assert_eq!(lines.next(), None);
+    }
+}
@@ -0,0 +1,325 @@
+//! Signed fan-out of normalized events to downstream HTTP consumers.
+//!
+//! [`EventForwarder`] POSTs each [`InboundMessage`] as JSON to every
+//! configured endpoint.  Each request carries an [`SIGNATURE_HEADER`] of the
+//! form `t=<unix seconds>,v1=<hex hmac>[,v1=...]`, where every `v1` is
+//! HMAC-SHA256 over `"<t>.<body>"` with one of the endpoint's secrets.
+//! Consumers check it with [`EventVerifier`], the same way smskit checks
+//! provider webhook signatures.
+//!
+//! To rotate a secret, sign with both the new and the old one
+//! ([`EventSigner::with_secret`]) until every consumer has switched over.
+//!
+//! ```rust,ignore
+//! use smskit::fanout::{EventForwarder, EventSigner, EventVerifier};
+//!
+//! let forwarder = EventForwarder::new()
+//!     .with_endpoint("https://crm.example.com/sms", EventSigner::new("whsec_new"));
+//! forwarder.forward(&message).await;
+//!
+//! // On the consumer side:
+//! EventVerifier::new("whsec_new").verify(&headers, &body)?;
+//! ```
+
+use futures::future::join_all;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use sms_core::{ExposeSecret, Headers, InboundMessage, SecretString, SmsError};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{debug, warn};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Header carrying the timestamp and signatures of a forwarded event.
+pub const SIGNATURE_HEADER: &str = "X-Smskit-Signature";
+
+/// Default clock skew [`EventVerifier`] tolerates (five minutes).
+pub const DEFAULT_TOLERANCE: Duration = Duration::from_secs(300);
+
+/// Signs outgoing event bodies with one or more shared secrets.
+#[derive(Debug, Clone)]
+pub struct EventSigner {
+    secrets: Vec<SecretString>,
+}
+
+impl EventSigner {
+    /// Sign with `secret`.
+    pub fn new(secret: impl Into<String>) -> Self {
+        Self {
+            secrets: vec![SecretString::from(secret.into())],
+        }
+    }
+
+    /// Also sign with `secret`, e.g. the previous one during a rotation.
+    pub fn with_secret(mut self, secret: impl Into<String>) -> Self {
+        self.secrets.push(SecretString::from(secret.into()));
+        self
+    }
+
+    /// The [`SIGNATURE_HEADER`] value for `body` sent now.
+    pub fn sign(&self, body: &[u8]) -> String {
+        self.sign_at(body, unix_now())
+    }
+
+    /// The [`SIGNATURE_HEADER`] value for `body` sent at `timestamp`
+    /// (Unix seconds).
+    pub fn sign_at(&self, body: &[u8], timestamp: u64) -> String {
+        let mut header = format!("t={timestamp}");
+        for secret in &self.secrets {
+            header.push_str(",v1=");
+            header.push_str(&to_hex(
+                &mac(secret, timestamp, body).finalize().into_bytes(),
+            ));
+        }
+        header
+    }
+}
+
+/// Checks [`SIGNATURE_HEADER`] on events received from an [`EventForwarder`].
+#[derive(Debug, Clone)]
+pub struct EventVerifier {
+    secrets: Vec<SecretString>,
+    tolerance: Duration,
+}
+
+impl EventVerifier {
+    /// Accept events signed with `secret`.
+    pub fn new(secret: impl Into<String>) -> Self {
+        Self {
+            secrets: vec![SecretString::from(secret.into())],
+            tolerance: DEFAULT_TOLERANCE,
+        }
+    }
+
+    /// Also accept events signed with `secret`.
+    pub fn with_secret(mut self, secret: impl Into<String>) -> Self {
+        self.secrets.push(SecretString::from(secret.into()));
+        self
+    }
+
+    /// Reject events whose timestamp is further than `tolerance` from now
+    /// (default [`DEFAULT_TOLERANCE`]).
+    pub fn with_tolerance(mut self, tolerance: Duration) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Verify `body` against the [`SIGNATURE_HEADER`] in `headers`.
+    pub fn verify(&self, headers: &Headers, body: &[u8]) -> Result<(), SmsError> {
+        let header = headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(SIGNATURE_HEADER))
+            .map(|(_, v)| v.as_str())
+            .ok_or_else(|| SmsError::Auth(format!("missing {SIGNATURE_HEADER} header")))?;
+        self.verify_at(header, body, unix_now())
+    }
+
+    /// Verify `body` against a [`SIGNATURE_HEADER`] value as of `now`
+    /// (Unix seconds).
+    pub fn verify_at(&self, header: &str, body: &[u8], now: u64) -> Result<(), SmsError> {
+        let mut timestamp = None;
+        let mut signatures = Vec::new();
+        for part in header.split(',') {
+            match part.trim().split_once('=') {
+                Some(("t", t)) => timestamp = t.parse::<u64>().ok(),
+                Some(("v1", sig)) => signatures.extend(from_hex(sig)),
+                _ => {}
+            }
+        }
+        let timestamp =
+            timestamp.ok_or_else(|| SmsError::Auth("signature has no timestamp".into()))?;
+        if now.abs_diff(timestamp) > self.tolerance.as_secs() {
+            return Err(SmsError::Auth(
+                "signature timestamp outside tolerance".into(),
+            ));
+        }
+
+        let matched = self.secrets.iter().any(|secret| {
+            signatures
+                .iter()
+                .any(|sig| mac(secret, timestamp, body).verify_slice(sig).is_ok())
+        });
+        if matched {
+            Ok(())
+        } else {
+            Err(SmsError::Auth("event signature mismatch".into()))
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Endpoint {
+    url: String,
+    signer: EventSigner,
+}
+
+/// POSTs normalized events, signed, to every configured endpoint.
+#[derive(Debug, Clone, Default)]
+pub struct EventForwarder {
+    endpoints: Vec<Endpoint>,
+    http: reqwest::Client,
+}
+
+impl EventForwarder {
+    /// A forwarder with no endpoints.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forward events to `url`, signed by `signer`.
+    pub fn with_endpoint(mut self, url: impl Into<String>, signer: EventSigner) -> Self {
+        self.endpoints.push(Endpoint {
+            url: url.into(),
+            signer,
+        });
+        self
+    }
+
+    /// POST `message` to every endpoint concurrently.
+    ///
+    /// Returns one result per endpoint, in the order they were added.
+    pub async fn forward(&self, message: &InboundMessage) -> Vec<Result<(), SmsError>> {
+        let body = match serde_json::to_vec(message) {
+            Ok(body) => body,
+            Err(e) => {
+                let err = SmsError::Unexpected(format!("serializing event: {e}"));
+                return vec![Err(err); self.endpoints.len()];
+            }
+        };
+        join_all(self.endpoints.iter().map(|ep| self.post(ep, &body))).await
+    }
+
+    async fn post(&self, endpoint: &Endpoint, body: &[u8]) -> Result<(), SmsError> {
+        let resp = self
+            .http
+            .post(&endpoint.url)
+            .header("content-type", "application/json")
+            .header(SIGNATURE_HEADER, endpoint.signer.sign(body))
+            .body(body.to_vec())
+            .send()
+            .await
+            .map_err(|e| SmsError::Http(e.to_string()))?;
+        let status = resp.status();
+        if !status.is_success() {
+            warn!("Event forward to {} failed: {}", endpoint.url, status);
+            return Err(SmsError::Provider(format!("HTTP {}", status)));
+        }
+        debug!("Forwarded event to {}", endpoint.url);
+        Ok(())
+    }
+}
+
+fn mac(secret: &SecretString, timestamp: u64, body: &[u8]) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(secret.expose_secret().as_bytes())
+        .expect("HMAC accepts any key size");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    mac
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn from_hex(s: &str) -> Option<Vec<u8>> {
+    s.as_bytes()
+        .chunks(2)
+        .map(|pair| match pair {
+            [hi, lo] => Some((hex_digit(*hi)? << 4) | hex_digit(*lo)?),
+            _ => None,
+        })
+        .collect()
+}
+
+fn hex_digit(c: u8) -> Option<u8> {
+    (c as char).to_digit(16).map(|d| d as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BODY: &[u8] = br#"{"from":"+15551230000","text":"hi"}"#;
+
+    #[test]
+    fn signed_event_verifies() {
+        let header = EventSigner::new("s3cret").sign_at(BODY, 1_700_000_000);
+        assert!(header.starts_with("t=1700000000,v1="));
+        EventVerifier::new("s3cret")
+            .verify_at(&header, BODY, 1_700_000_010)
+            .unwrap();
+    }
+
+    #[test]
+    fn tampered_body_and_wrong_secret_are_rejected() {
+        let header = EventSigner::new("s3cret").sign_at(BODY, 1_700_000_000);
+        let verifier = EventVerifier::new("s3cret");
+        assert!(matches!(
+            verifier.verify_at(&header, b"{}", 1_700_000_000),
+            Err(SmsError::Auth(_))
+        ));
+        assert!(
+            EventVerifier::new("other")
+                .verify_at(&header, BODY, 1_700_000_000)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn stale_timestamp_is_rejected() {
+        let header = EventSigner::new("s3cret").sign_at(BODY, 1_700_000_000);
+        let verifier = EventVerifier::new("s3cret").with_tolerance(Duration::from_secs(60));
+        assert!(verifier.verify_at(&header, BODY, 1_700_000_061).is_err());
+        assert!(verifier.verify_at(&header, BODY, 1_699_999_940).is_ok());
+    }
+
+    #[test]
+    fn rotation_accepts_old_or_new_secret() {
+        let header = EventSigner::new("new")
+            .with_secret("old")
+            .sign_at(BODY, 1_700_000_000);
+        assert_eq!(header.matches("v1=").count(), 2);
+        for secret in ["new", "old"] {
+            EventVerifier::new(secret)
+                .verify_at(&header, BODY, 1_700_000_000)
+                .unwrap();
+        }
+
+        // A consumer that has already moved on still accepts the old signer.
+        let old_only = EventSigner::new("old").sign_at(BODY, 1_700_000_000);
+        EventVerifier::new("new")
+            .with_secret("old")
+            .verify_at(&old_only, BODY, 1_700_000_000)
+            .unwrap();
+    }
+
+    #[test]
+    fn verify_reads_header_case_insensitively() {
+        let header = EventSigner::new("s3cret").sign(BODY);
+        let headers: Headers = vec![("x-smskit-signature".into(), header)];
+        EventVerifier::new("s3cret").verify(&headers, BODY).unwrap();
+        assert!(
+            EventVerifier::new("s3cret")
+                .verify(&Headers::new(), BODY)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn hex_round_trips() {
+        assert_eq!(
+            from_hex(&to_hex(&[0, 1, 0xab, 0xff])).unwrap(),
+            [0, 1, 0xab, 0xff]
+        );
+        assert!(from_hex("abc").is_none());
+        assert!(from_hex("zz").is_none());
+    }
+}
@@ -0,0 +1,291 @@
+//! Pre-send content filtering.
+//!
+//! A [`ContentFilter`] inspects the text of every outgoing message and may
+//! rewrite it (append a mandatory opt-out footer, mask a word) or reject the
+//! send outright (prohibited content under carrier rules).  Filters run in
+//! order as a [`FilterChain`]; [`TenantFilters`] picks a chain per tenant and
+//! [`FilteredClient`] applies it in front of any [`SmsClient`].
+//!
+//! ```rust,ignore
+//! use smskit::filter::{BlockedTerms, FilterChain, FilteredClient, OptOutFooter, TenantFilters};
+//!
+//! let filters = TenantFilters::new(FilterChain::new().with(OptOutFooter::new("Reply STOP to opt out")))
+//!     .with_tenant("acme", FilterChain::new().with(BlockedTerms::new(["casino"])));
+//! let client = FilteredClient::new(plivo, filters).for_tenant("acme");
+//! client.send(req).await?;
+//! ```
+
+use sms_core::{Channel, SendRequest, SendResponse, SmsClient, SmsError, redact_number};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::debug;
+
+/// Inspects, rewrites or rejects outgoing message text.
+pub trait ContentFilter: Send + Sync {
+    /// Return the (possibly rewritten) text, or an error to reject the send.
+    fn apply(&self, req: &SendRequest<'_>, text: String) -> Result<String, SmsError>;
+}
+
+impl<F> ContentFilter for F
+where
+    F: Fn(&SendRequest<'_>, String) -> Result<String, SmsError> + Send + Sync,
+{
+    fn apply(&self, req: &SendRequest<'_>, text: String) -> Result<String, SmsError> {
+        self(req, text)
+    }
+}
+
+/// Rejects messages containing any of a list of terms (case-insensitive).
+#[derive(Debug, Clone)]
+pub struct BlockedTerms {
+    terms: Vec<String>,
+}
+
+impl BlockedTerms {
+    /// Block every term in `terms`.
+    pub fn new<I, S>(terms: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            terms: terms.into_iter().map(|t| t.into().to_lowercase()).collect(),
+        }
+    }
+}
+
+impl ContentFilter for BlockedTerms {
+    fn apply(&self, _req: &SendRequest<'_>, text: String) -> Result<String, SmsError> {
+        let lower = text.to_lowercase();
+        match self.terms.iter().find(|t| lower.contains(t.as_str())) {
+            Some(term) => Err(SmsError::Invalid(format!(
+                "message contains blocked term \"{}\"",
+                term
+            ))),
+            None => Ok(text),
+        }
+    }
+}
+
+/// Appends an opt-out footer unless the message already ends with it.
+#[derive(Debug, Clone)]
+pub struct OptOutFooter {
+    footer: String,
+}
+
+impl OptOutFooter {
+    /// Append `footer` on its own line.
+    pub fn new(footer: impl Into<String>) -> Self {
+        Self {
+            footer: footer.into(),
+        }
+    }
+}
+
+impl ContentFilter for OptOutFooter {
+    fn apply(&self, _req: &SendRequest<'_>, text: String) -> Result<String, SmsError> {
+        if text.trim_end().ends_with(&self.footer) {
+            return Ok(text);
+        }
+        Ok(format!("{}\n{}", text.trim_end(), self.footer))
+    }
+}
+
+/// An ordered list of filters; each sees the output of the one before.
+#[derive(Clone, Default)]
+pub struct FilterChain {
+    filters: Vec<Arc<dyn ContentFilter>>,
+}
+
+impl FilterChain {
+    /// An empty chain, which passes text through unchanged.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a filter to the chain.
+    pub fn with<F: ContentFilter + 'static>(mut self, filter: F) -> Self {
+        self.filters.push(Arc::new(filter));
+        self
+    }
+
+    /// Number of filters in the chain.
+    pub fn len(&self) -> usize {
+        self.filters.len()
+    }
+
+    /// Returns `true` if the chain has no filters.
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+
+    /// Run every filter over the request's text.
+    pub fn apply(&self, req: &SendRequest<'_>) -> Result<String, SmsError> {
+        self.filters
+            .iter()
+            .try_fold(req.text.to_string(), |text, f| f.apply(req, text))
+    }
+}
+
+/// Filter chains keyed by tenant, with a default for unknown tenants.
+#[derive(Clone, Default)]
+pub struct TenantFilters {
+    default: FilterChain,
+    tenants: HashMap<String, FilterChain>,
+}
+
+impl TenantFilters {
+    /// Use `default` for every tenant without its own chain.
+    pub fn new(default: FilterChain) -> Self {
+        Self {
+            default,
+            tenants: HashMap::new(),
+        }
+    }
+
+    /// Give `tenant` its own chain, replacing the default for that tenant.
+    pub fn with_tenant(mut self, tenant: impl Into<String>, chain: FilterChain) -> Self {
+        self.tenants.insert(tenant.into(), chain);
+        self
+    }
+
+    /// The chain that applies to `tenant` (or the default for `None`).
+    pub fn chain(&self, tenant: Option<&str>) -> &FilterChain {
+        tenant
+            .and_then(|t| self.tenants.get(t))
+            .unwrap_or(&self.default)
+    }
+}
+
+impl From<FilterChain> for TenantFilters {
+    fn from(chain: FilterChain) -> Self {
+        Self::new(chain)
+    }
+}
+
+/// Wraps an [`SmsClient`] and runs content filters before every send.
+pub struct FilteredClient<C> {
+    inner: C,
+    filters: Arc<TenantFilters>,
+    tenant: Option<String>,
+}
+
+impl<C: SmsClient> FilteredClient<C> {
+    /// Filter sends through `inner` with the default chain of `filters`.
+    pub fn new(inner: C, filters: impl Into<TenantFilters>) -> Self {
+        Self {
+            inner,
+            filters: Arc::new(filters.into()),
+            tenant: None,
+        }
+    }
+
+    /// Apply the chain configured for `tenant`.
+    pub fn for_tenant(mut self, tenant: impl Into<String>) -> Self {
+        self.tenant = Some(tenant.into());
+        self
+    }
+}
+
+impl<C: SmsClient> SmsClient for FilteredClient<C> {
+    async fn send(&self, req: SendRequest<'_>) -> Result<SendResponse, SmsError> {
+        let chain = self.filters.chain(self.tenant.as_deref());
+        let text = chain.apply(&req).inspect_err(|e| {
+            debug!(
+                "Content filter rejected message to {}: {}",
+                redact_number(req.to),
+                e
+            );
+        })?;
+        self.inner.send(SendRequest { text: &text, ..req }).await
+    }
+
+    fn supports(&self, channel: Channel) -> bool {
+        self.inner.supports(channel)
+    }
+
+    fn max_text_len(&self) -> Option<usize> {
+        self.inner.max_text_len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Records the text of every message it sends.
+    #[derive(Default)]
+    struct RecordingClient {
+        sent: Mutex<Vec<String>>,
+    }
+
+    impl SmsClient for RecordingClient {
+        async fn send(&self, req: SendRequest<'_>) -> Result<SendResponse, SmsError> {
+            self.sent.lock().unwrap().push(req.text.to_string());
+            Ok(SendResponse {
+                id: "id".into(),
+                provider: "recording",
+                channel: Channel::Sms,
+                http_status: None,
+                provider_request_id: None,
+                thread_id: None,
+                raw: serde_json::json!({}),
+            })
+        }
+    }
+
+    fn req(text: &str) -> SendRequest<'_> {
+        SendRequest {
+            to: "+1",
+            from: "+2",
+            text,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn footer_is_appended_once() {
+        let footer = OptOutFooter::new("Reply STOP to opt out");
+        let once = footer.apply(&req("Sale!"), "Sale!".into()).unwrap();
+        assert_eq!(once, "Sale!\nReply STOP to opt out");
+        let twice = footer.apply(&req(&once), once.clone()).unwrap();
+        assert_eq!(twice, once);
+    }
+
+    #[test]
+    fn blocked_terms_are_case_insensitive() {
+        let filter = BlockedTerms::new(["casino"]);
+        let err = filter
+            .apply(&req("Visit our CASINO"), "Visit our CASINO".into())
+            .unwrap_err();
+        assert!(matches!(err, SmsError::Invalid(_)));
+        assert!(filter.apply(&req("hello"), "hello".into()).is_ok());
+    }
+
+    fn shout(_req: &SendRequest<'_>, text: String) -> Result<String, SmsError> {
+        Ok(text.to_uppercase())
+    }
+
+    #[test]
+    fn chain_runs_in_order() {
+        let chain = FilterChain::new()
+            .with(shout)
+            .with(OptOutFooter::new("stop"));
+        assert_eq!(chain.apply(&req("hi")).unwrap(), "HI\nstop");
+    }
+
+    #[tokio::test]
+    async fn client_uses_tenant_chain() {
+        let filters = TenantFilters::new(FilterChain::new().with(OptOutFooter::new("STOP")))
+            .with_tenant("acme", FilterChain::new().with(BlockedTerms::new(["win"])));
+
+        let client = FilteredClient::new(RecordingClient::default(), filters.clone());
+        client.send(req("hello")).await.unwrap();
+        assert_eq!(client.inner.sent.lock().unwrap()[0], "hello\nSTOP");
+
+        let acme = FilteredClient::new(RecordingClient::default(), filters).for_tenant("acme");
+        assert!(acme.send(req("you win")).await.is_err());
+        assert!(acme.inner.sent.lock().unwrap().is_empty());
+    }
+}
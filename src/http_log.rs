@@ -0,0 +1,167 @@
+//! Sinks for provider HTTP logging.
+//!
+//! Provider clients report each API call to an
+//! [`HttpLogger`](sms_core::HttpLogger) once one is set with
+//! `with_http_logger`.  This module provides two sinks for it:
+//! [`TracingHttpLog`] emits one `tracing` event per call, and
+//! [`HttpLogBuffer`] keeps the most recent calls in memory, e.g. to attach
+//! to a delivery dispute with an aggregator.
+//!
+//! ```rust,ignore
+//! use smskit::http_log::{HttpLogBuffer, TracingHttpLog};
+//!
+//! let client = TwilioClient::from_env()?
+//!     .with_http_logger(HttpLogger::new(TracingHttpLog).redact_message_text());
+//!
+//! let recent = HttpLogBuffer::new(500);
+//! let client = PlivoClient::from_env()?.with_http_logger(HttpLogger::new(recent.clone()));
+//! ```
+
+use sms_core::{HttpExchange, HttpLogSink};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+/// Logs each call as a `tracing` event with target `smskit::http`:
+/// `DEBUG` for successful responses, `WARN` for error statuses and `ERROR`
+/// for calls that got no response.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TracingHttpLog;
+
+impl HttpLogSink for TracingHttpLog {
+    fn record(&self, e: &HttpExchange) {
+        let latency_ms = e.latency.as_millis() as u64;
+        let request_body = e.request_body.as_deref().unwrap_or_default();
+        let response_body = e.response_body.as_deref().unwrap_or_default();
+        match (e.status, &e.error) {
+            (Some(status), _) if status < 400 => tracing::debug!(
+                target: "smskit::http",
+                provider = e.provider,
+                method = e.method,
+                url = %e.url,
+                status,
+                latency_ms,
+                request_body,
+                response_body,
+                "provider call"
+            ),
+            (Some(status), _) => tracing::warn!(
+                target: "smskit::http",
+                provider = e.provider,
+                method = e.method,
+                url = %e.url,
+                status,
+                latency_ms,
+                request_body,
+                response_body,
+                "provider call failed"
+            ),
+            (None, error) => tracing::error!(
+                target: "smskit::http",
+                provider = e.provider,
+                method = e.method,
+                url = %e.url,
+                latency_ms,
+                request_body,
+                error = error.as_deref().unwrap_or_default(),
+                "provider call got no response"
+            ),
+        }
+    }
+}
+
+/// Keeps the most recent calls in memory, oldest dropped first.
+///
+/// Clones share the same buffer, so keep one handle and give the other to
+/// the [`HttpLogger`](sms_core::HttpLogger).
+#[derive(Debug, Clone)]
+pub struct HttpLogBuffer {
+    capacity: usize,
+    entries: Arc<Mutex<VecDeque<HttpExchange>>>,
+}
+
+impl HttpLogBuffer {
+    /// Keep up to `capacity` calls.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Arc::default(),
+        }
+    }
+
+    /// The buffered calls, oldest first.
+    pub fn entries(&self) -> Vec<HttpExchange> {
+        self.lock().iter().cloned().collect()
+    }
+
+    /// Buffered calls made by `provider`, oldest first.
+    pub fn for_provider(&self, provider: &str) -> Vec<HttpExchange> {
+        self.lock()
+            .iter()
+            .filter(|e| e.provider == provider)
+            .cloned()
+            .collect()
+    }
+
+    /// Number of buffered calls.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Returns `true` if nothing is buffered.
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Drop every buffered call.
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, VecDeque<HttpExchange>> {
+        self.entries.lock().expect("http log buffer poisoned")
+    }
+}
+
+impl HttpLogSink for HttpLogBuffer {
+    fn record(&self, exchange: &HttpExchange) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut entries = self.lock();
+        if entries.len() == self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back(exchange.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sms_core::{HttpLogger, SmsError};
+
+    #[test]
+    fn buffer_keeps_most_recent_calls() {
+        let buffer = HttpLogBuffer::new(2);
+        let log = HttpLogger::new(buffer.clone());
+        log.start("plivo", "POST", "https://plivo/1", None)
+            .finish(200, "{}");
+        log.start("twilio", "POST", "https://twilio/2", None)
+            .finish(400, r#"{"code":21211}"#);
+        log.start("plivo", "POST", "https://plivo/3", Some(r#"{"token":"t"}"#))
+            .fail(&SmsError::Http("refused".into()));
+
+        let entries = buffer.entries();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].url, "https://twilio/2");
+        assert_eq!(
+            entries[1].request_body.as_deref(),
+            Some(r#"{"token":"[REDACTED]"}"#)
+        );
+        assert_eq!(buffer.for_provider("plivo").len(), 1);
+
+        TracingHttpLog.record(&entries[1]);
+        buffer.clear();
+        assert!(buffer.is_empty());
+    }
+}
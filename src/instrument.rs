@@ -0,0 +1,168 @@
+//! Standardized tracing spans for sends.
+//!
+//! [`InstrumentedClient`] wraps any [`SmsClient`] and runs every send inside
+//! an `sms.send` span with the same fields regardless of provider, so
+//! dashboards and log queries don't depend on each provider's log wording:
+//!
+//! | field         | value                                             |
+//! |---------------|---------------------------------------------------|
+//! | `provider`    | label given to [`InstrumentedClient::new`]        |
+//! | `channel`     | [`Channel`] of the request                        |
+//! | `country`     | E.164 calling code of the destination, e.g. `44`  |
+//! | `segments`    | segment count from [`analyze`]                    |
+//! | `latency_ms`  | time the provider call took                       |
+//! | `outcome`     | `"sent"` or `"failed"`                            |
+//! | `message_id`  | provider message ID on success                    |
+//! | `error`       | error message on failure                          |
+//!
+//! ```rust,ignore
+//! use smskit::instrument::InstrumentedClient;
+//!
+//! let client = InstrumentedClient::new("twilio", twilio);
+//! client.send(req).await?;
+//! ```
+
+use crate::analysis::analyze;
+use sms_core::{Channel, SendRequest, SendResponse, SmsClient, SmsError};
+use std::time::Instant;
+use tracing::{Instrument, field, info_span};
+
+/// Country calling codes that are one digit long.
+const ONE_DIGIT_CODES: &[&str] = &["1", "7"];
+
+/// Country calling codes that are two digits long.
+const TWO_DIGIT_CODES: &[&str] = &[
+    "20", "27", "30", "31", "32", "33", "34", "36", "39", "40", "41", "43", "44", "45", "46", "47",
+    "48", "49", "51", "52", "53", "54", "55", "56", "57", "58", "60", "61", "62", "63", "64", "65",
+    "66", "81", "82", "84", "86", "90", "91", "92", "93", "94", "95", "98",
+];
+
+/// The E.164 country calling code of `number`, e.g. `"44"` for
+/// `"+447700900123"`.  Returns `None` for numbers without a leading `+`.
+pub fn calling_code(number: &str) -> Option<&str> {
+    let digits = number.strip_prefix('+')?;
+    if digits.len() < 4 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    if ONE_DIGIT_CODES.contains(&&digits[..1]) {
+        Some(&digits[..1])
+    } else if TWO_DIGIT_CODES.contains(&&digits[..2]) {
+        Some(&digits[..2])
+    } else {
+        Some(&digits[..3])
+    }
+}
+
+/// Wraps an [`SmsClient`] and emits an `sms.send` span for every send.
+pub struct InstrumentedClient<C> {
+    provider: String,
+    inner: C,
+}
+
+impl<C: SmsClient> InstrumentedClient<C> {
+    /// Instrument sends through `inner` under the `provider` label.
+    pub fn new(provider: impl Into<String>, inner: C) -> Self {
+        Self {
+            provider: provider.into(),
+            inner,
+        }
+    }
+}
+
+impl<C: SmsClient> SmsClient for InstrumentedClient<C> {
+    async fn send(&self, req: SendRequest<'_>) -> Result<SendResponse, SmsError> {
+        let span = info_span!(
+            "sms.send",
+            provider = %self.provider,
+            channel = %req.channel,
+            country = calling_code(req.to).unwrap_or("unknown"),
+            segments = analyze(req.text).segments,
+            latency_ms = field::Empty,
+            outcome = field::Empty,
+            message_id = field::Empty,
+            error = field::Empty,
+        );
+
+        let start = Instant::now();
+        let result = self.inner.send(req).instrument(span.clone()).await;
+        span.record("latency_ms", start.elapsed().as_millis() as u64);
+        match &result {
+            Ok(resp) => {
+                span.record("outcome", "sent");
+                span.record("message_id", resp.id.as_str());
+            }
+            Err(e) => {
+                span.record("outcome", "failed");
+                span.record("error", field::display(e));
+            }
+        }
+        result
+    }
+
+    fn supports(&self, channel: Channel) -> bool {
+        self.inner.supports(channel)
+    }
+
+    fn max_text_len(&self) -> Option<usize> {
+        self.inner.max_text_len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct EchoClient;
+
+    impl SmsClient for EchoClient {
+        async fn send(&self, req: SendRequest<'_>) -> Result<SendResponse, SmsError> {
+            if req.text == "fail" {
+                return Err(SmsError::Provider("scripted failure".into()));
+            }
+            Ok(SendResponse {
+                id: "id-1".into(),
+                provider: "echo",
+                channel: req.channel,
+                http_status: None,
+                provider_request_id: None,
+                thread_id: None,
+                raw: serde_json::json!({}),
+            })
+        }
+    }
+
+    #[test]
+    fn calling_codes() {
+        assert_eq!(calling_code("+14155551234"), Some("1"));
+        assert_eq!(calling_code("+447700900123"), Some("44"));
+        assert_eq!(calling_code("+353861234567"), Some("353"));
+        assert_eq!(calling_code("+79161234567"), Some("7"));
+        assert_eq!(calling_code("4155551234"), None);
+        assert_eq!(calling_code("+12"), None);
+    }
+
+    #[tokio::test]
+    async fn passes_results_through() {
+        let client = InstrumentedClient::new("echo", EchoClient);
+        let ok = client
+            .send(SendRequest {
+                to: "+14155551234",
+                from: "+1",
+                text: "hi",
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(ok.id, "id-1");
+
+        let err = client
+            .send(SendRequest {
+                to: "+1",
+                from: "+2",
+                text: "fail",
+                ..Default::default()
+            })
+            .await;
+        assert!(err.is_err());
+    }
+}
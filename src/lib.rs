@@ -11,7 +11,37 @@
 //! - **`from_env()` constructors**: Read credentials from environment variables
 //! - **Framework agnostic**: Works with Axum, Warp, Actix, Rocket, Poem, Hyper, Tide
 //! - **Webhook processing**: Unified inbound webhook handling with signature verification
+//! - **Inbound polling**: Periodic polling for gateways without webhooks, dispatched the same way
 //! - **Rate limiting**: Built-in per-provider rate limiting
+//! - **Content filtering**: Per-tenant filter chains that rewrite or reject outgoing text
+//! - **Destination screening**: Runtime-updatable number/prefix blocklists and allowlists,
+//!   plus a country allowlist against toll fraud
+//! - **Tracing**: Consistent `sms.send` spans across every provider
+//! - **HTTP logging**: Opt-in, redacted logs of provider API calls to tracing or an in-memory buffer
+//! - **Message analysis**: Encoding, segment count and cost estimates before sending
+//! - **Send previews**: Routing, segments, cost and rule checks for a send, without sending it
+//! - **Pricing**: Per-provider, per-country price tables for least-cost routing and budgets
+//! - **Balance alerts**: Periodic credit checks with callbacks when an account runs low
+//! - **Opt-out lists**: STOP-list enforcement, imported and synced from SNS and CSV exports
+//! - **Sender pools**: Round-robin sender numbers with each recipient pinned to one `from`
+//! - **Priority outbox**: Queued sends drained highest-priority first
+//! - **Store-and-forward**: Sends parked in the outbox while every provider is down, resent on recovery
+//! - **Bulk import**: CSV/JSONL recipient lists rendered, deduplicated and queued with a per-row error report
+//! - **Batch sending**: Bounded-concurrency sends with an overall deadline
+//! - **Timeouts**: Per-client and per-request deadlines on provider calls
+//! - **Runtime choice**: Background tasks run on tokio, or on async-std with the `async-std` feature
+//! - **Dry run**: Validated, analyzed and logged sends that never reach the provider
+//! - **Simulation**: A fake provider with configurable latency and failures
+//! - **Chaos testing**: Fault injection around any real client
+//! - **Mock provider**: Recorded sends with fluent expectations and scripted failures for tests
+//! - **Message store**: Pluggable message persistence with JSONL/CSV export
+//! - **MMS media**: Authenticated, size- and type-limited download of inbound attachments
+//! - **Attachment storage**: Local-filesystem and S3 homes for binary payloads
+//! - **Metrics**: Per-provider counters for scraping or pushing to a Pushgateway/OTLP collector
+//! - **Delivery correlation**: Send-time tags (tenant, campaign) returned with each delivery report
+//! - **SLA tracking**: Rolling-window delivery latency and failure rates with breach callbacks
+//! - **Event fan-out**: HMAC-signed forwarding of inbound events to downstream HTTP consumers
+//! - **Admin endpoints**: Token-protected introspection of providers, messages, limits and queues
 //! - **Configuration**: Layered TOML + env var configuration
 //!
 //! ## Quick Start
@@ -31,6 +61,7 @@
 //!         to: "+1234567890",
 //!         from: "+0987654321",
 //!         text: "Hello from SMS Kit!",
+//!         ..Default::default()
 //!     }).await?;
 //!
 //!     println!("Message sent with ID: {}", response.id);
@@ -91,8 +122,39 @@
 //! let config = AppConfig::load()?;
 //! ```
 
+pub mod admin;
+pub mod analysis;
+pub mod attachment;
+pub mod balance;
+pub mod batch;
+pub mod bulk;
+pub mod chaos;
+pub mod clock;
 pub mod config;
+pub mod correlation;
+pub mod dry_run;
+pub mod export;
+pub mod fanout;
+pub mod filter;
+pub mod http_log;
+pub mod instrument;
+pub mod media;
+pub mod metrics;
+pub mod mock;
+pub mod optout;
+pub mod outbox;
+pub mod polling;
+pub mod preview;
+pub mod pricing;
 pub mod rate_limiter;
+pub mod runtime;
+pub mod screening;
+pub mod sender_pool;
+pub mod simulation;
+pub mod sla;
+pub mod store;
+pub mod store_forward;
+pub mod timeout;
 
 pub use config::*;
 
@@ -101,12 +163,60 @@ pub use config::*;
 /// Pulls in everything from `sms_core` (traits, request/response types, errors)
 /// plus the configuration and rate-limiting types from this crate.
 pub mod prelude {
+    pub use crate::admin::AdminApi;
+    pub use crate::analysis::{Encoding, MessageAnalysis, PriceTable, analyze, analyze_with};
+    #[cfg(feature = "s3-attachments")]
+    pub use crate::attachment::S3AttachmentStore;
+    pub use crate::attachment::{AttachmentStore, LocalAttachmentStore, StoredAttachment};
+    pub use crate::balance::{BalanceAlert, BalanceLevel, BalanceMonitor, BalanceThresholds};
+    pub use crate::batch::{BatchOutcome, ConcurrentSender};
+    pub use crate::bulk::{BulkImport, BulkReport, BulkRow};
+    pub use crate::chaos::{ChaosClient, ChaosConfig};
+    pub use crate::clock::{Clock, ManualClock, SystemClock};
     pub use crate::config::{
         AppConfig, LoggingConfig, ProvidersConfig, SecurityConfig, ServerConfig,
     };
+    pub use crate::correlation::{
+        CorrelatedClient, CorrelatedDelivery, DeliveryCorrelator, Metadata,
+    };
+    pub use crate::dry_run::DryRunClient;
+    pub use crate::export::{ExportFormat, export_messages};
+    pub use crate::fanout::{EventForwarder, EventSigner, EventVerifier};
+    pub use crate::filter::{
+        BlockedTerms, ContentFilter, FilterChain, FilteredClient, OptOutFooter, TenantFilters,
+    };
+    pub use crate::http_log::{HttpLogBuffer, TracingHttpLog};
+    pub use crate::instrument::InstrumentedClient;
+    pub use crate::media::{Media, MediaAuth, MediaDownloader, media_refs};
+    pub use crate::metrics::{MeteredClient, MetricsPusher, PushTarget, SmsMetrics};
+    pub use crate::mock::{ExpectSend, MockClient, MockWebhook};
+    pub use crate::optout::{
+        CsvOptOutSource, InMemoryOptOutStore, OptOut, OptOutClient, OptOutSource, OptOutStore,
+        OptOutSync, SyncReport,
+    };
+    pub use crate::outbox::{Outbox, OutboxEntry, OutboxState, OutboxWorker, Priority};
+    pub use crate::polling::PollingRunner;
+    pub use crate::preview::{Previewer, SendPreview};
+    pub use crate::pricing::estimate_cost;
     pub use crate::rate_limiter::{
-        DefaultKeyGenerator, KeyGenerator, RateLimitMiddleware, RateLimitResult, RateLimiter,
+        BucketStatus, DefaultKeyGenerator, KeyGenerator, RateLimitMiddleware, RateLimitResult,
+        RateLimiter, RateLimiterStatus,
+    };
+    #[cfg(feature = "async-std")]
+    pub use crate::runtime::CompatClient;
+    pub use crate::screening::{
+        CountryAllowlist, DestinationRules, DestinationStore, InMemoryDestinationStore,
+        ScreenedClient, calling_code,
+    };
+    pub use crate::sender_pool::{PooledClient, SenderPool};
+    pub use crate::simulation::{LatencyDistribution, SimulatedClient};
+    pub use crate::sla::{BreachKind, SlaBreach, SlaKey, SlaStats, SlaThresholds, SlaTracker};
+    pub use crate::store::{
+        DateRange, ForgetMode, ForgetReport, InMemoryMessageStore, MessageDirection,
+        MessageStore, StoredMessage,
     };
+    pub use crate::store_forward::StoreAndForward;
+    pub use crate::timeout::TimeoutClient;
     // Re-export everything from sms-core, which now includes:
     //   SmsClient, SendRequest, OwnedSendRequest, SendResponse,
     //   SmsRouter, FallbackClient, InboundWebhook, InboundRegistry, etc.
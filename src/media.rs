@@ -0,0 +1,320 @@
+//! Inbound MMS media downloading.
+//!
+//! Providers host MMS attachments themselves and only put URLs in the
+//! webhook.  [`MediaDownloader`] fetches them with the right credentials
+//! for each provider, refusing anything over the size limit or outside the
+//! allowed content types, and hands the bytes back as [`Media`] — or, with
+//! an [`AttachmentStore`], saves them and hands back [`StoredAttachment`]
+//! references.
+//!
+//! ```rust,ignore
+//! use smskit::media::{MediaAuth, MediaDownloader};
+//!
+//! let downloader = MediaDownloader::new()
+//!     .with_auth("twilio", MediaAuth::basic(account_sid, auth_token))
+//!     .with_max_bytes(2 * 1024 * 1024);
+//! for media in downloader.download_all(&message).await? {
+//!     save(media.content_type, media.bytes);
+//! }
+//! ```
+
+use crate::attachment::{AttachmentStore, StoredAttachment};
+use sms_core::{ExposeSecret, InboundMessage, MediaRef, SecretString, SmsError};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::debug;
+
+/// Default largest attachment [`MediaDownloader`] accepts (5 MiB, the
+/// common carrier MMS ceiling).
+pub const DEFAULT_MAX_MEDIA_BYTES: usize = 5 * 1024 * 1024;
+
+/// Content types accepted by default; `type/*` matches any subtype.
+pub const DEFAULT_MEDIA_TYPES: &[&str] = &[
+    "image/*",
+    "audio/*",
+    "video/*",
+    "text/vcard",
+    "text/x-vcard",
+    "application/pdf",
+];
+
+/// Credentials for fetching a provider's media URLs.
+///
+/// Passwords and tokens are zeroized on drop.
+#[derive(Debug, Clone)]
+pub enum MediaAuth {
+    /// HTTP Basic auth, e.g. Twilio's account SID and auth token.
+    Basic {
+        /// User name.
+        username: String,
+        /// Password.
+        password: SecretString,
+    },
+    /// A bearer token.
+    Bearer(SecretString),
+}
+
+impl MediaAuth {
+    /// HTTP Basic credentials.
+    pub fn basic(username: impl Into<String>, password: impl Into<String>) -> Self {
+        Self::Basic {
+            username: username.into(),
+            password: SecretString::from(password.into()),
+        }
+    }
+
+    /// A bearer token.
+    pub fn bearer(token: impl Into<String>) -> Self {
+        Self::Bearer(SecretString::from(token.into()))
+    }
+}
+
+/// A downloaded attachment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Media {
+    /// The URL it was fetched from.
+    pub url: String,
+    /// MIME type from the response (or the webhook, if the response had none).
+    pub content_type: String,
+    /// The attachment itself.
+    pub bytes: Vec<u8>,
+}
+
+/// The media attachments of an inbound message.
+///
+/// Understands Twilio's `MediaUrl{N}` fields; other providers report none.
+pub fn media_refs(msg: &InboundMessage) -> Vec<MediaRef> {
+    match msg.provider {
+        "twilio" => sms_twilio::TwilioInbound::from_message(msg)
+            .map(|inbound| inbound.media())
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    }
+}
+
+/// Fetches inbound MMS media with per-provider credentials and limits.
+#[derive(Clone)]
+pub struct MediaDownloader {
+    http: reqwest::Client,
+    auth: HashMap<String, MediaAuth>,
+    max_bytes: usize,
+    content_types: Vec<String>,
+    store: Option<Arc<dyn AttachmentStore>>,
+}
+
+impl Default for MediaDownloader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MediaDownloader {
+    /// A downloader with [`DEFAULT_MAX_MEDIA_BYTES`] and
+    /// [`DEFAULT_MEDIA_TYPES`], and no credentials.
+    pub fn new() -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            auth: HashMap::new(),
+            max_bytes: DEFAULT_MAX_MEDIA_BYTES,
+            content_types: DEFAULT_MEDIA_TYPES.iter().map(|t| t.to_string()).collect(),
+            store: None,
+        }
+    }
+
+    /// Authenticate requests for `provider`'s media with `auth`.
+    pub fn with_auth(mut self, provider: impl Into<String>, auth: MediaAuth) -> Self {
+        self.auth.insert(provider.into(), auth);
+        self
+    }
+
+    /// Refuse attachments larger than `max_bytes`.
+    pub fn with_max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Only accept these content types (`type/*` matches any subtype).
+    pub fn with_content_types<I, S>(mut self, types: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.content_types = types.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Save downloads to `store` in [`download_and_store`](Self::download_and_store).
+    pub fn with_store(mut self, store: Arc<dyn AttachmentStore>) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    /// Returns `true` if `content_type` (parameters ignored) is allowed.
+    pub fn allows(&self, content_type: &str) -> bool {
+        let mime = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+        self.content_types
+            .iter()
+            .any(|allowed| match allowed.strip_suffix("/*") {
+                Some(kind) => mime.split('/').next() == Some(kind),
+                None => mime == allowed.to_ascii_lowercase(),
+            })
+    }
+
+    /// Download every attachment of `msg`.
+    pub async fn download_all(&self, msg: &InboundMessage) -> Result<Vec<Media>, SmsError> {
+        let mut downloaded = Vec::new();
+        for media in media_refs(msg) {
+            downloaded.push(self.download(msg.provider, &media).await?);
+        }
+        Ok(downloaded)
+    }
+
+    /// Download every attachment of `msg` into the configured store, under
+    /// `{provider}/{message id}/{index}`.
+    ///
+    /// Fails with [`SmsError::Invalid`] if no store is configured.
+    pub async fn download_and_store(
+        &self,
+        msg: &InboundMessage,
+    ) -> Result<Vec<StoredAttachment>, SmsError> {
+        let store = self
+            .store
+            .as_ref()
+            .ok_or_else(|| SmsError::Invalid("no attachment store configured".into()))?;
+        let id = msg.id.clone().unwrap_or_else(sms_core::fallback_id);
+        let mut stored = Vec::new();
+        for (i, media) in self.download_all(msg).await?.into_iter().enumerate() {
+            let key = format!("{}/{}/{}", msg.provider, id, i);
+            stored.push(store.put(&key, &media.content_type, media.bytes).await?);
+        }
+        Ok(stored)
+    }
+
+    /// Download one attachment hosted by `provider`.
+    ///
+    /// Fails with [`SmsError::Invalid`] if the content type isn't allowed or
+    /// the body exceeds the size limit; the body is read incrementally, so
+    /// an oversized download is abandoned as soon as it crosses the limit.
+    pub async fn download(&self, provider: &str, media: &MediaRef) -> Result<Media, SmsError> {
+        if let Some(ct) = media.content_type.as_deref().filter(|ct| !self.allows(ct)) {
+            return Err(SmsError::Invalid(format!(
+                "media type {} is not allowed",
+                ct
+            )));
+        }
+
+        let mut request = self.http.get(&media.url);
+        request = match self.auth.get(provider) {
+            Some(MediaAuth::Basic { username, password }) => {
+                request.basic_auth(username, Some(password.expose_secret()))
+            }
+            Some(MediaAuth::Bearer(token)) => request.bearer_auth(token.expose_secret()),
+            None => request,
+        };
+        let mut resp = request
+            .send()
+            .await
+            .map_err(|e| SmsError::Http(e.to_string()))?;
+        let status = resp.status();
+        if !status.is_success() {
+            return Err(SmsError::Provider(format!(
+                "media download {}: HTTP {}",
+                media.url, status
+            )));
+        }
+
+        let content_type = resp
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+            .or_else(|| media.content_type.clone())
+            .unwrap_or_else(|| "application/octet-stream".into());
+        if !self.allows(&content_type) {
+            return Err(SmsError::Invalid(format!(
+                "media type {} is not allowed",
+                content_type
+            )));
+        }
+        if resp
+            .content_length()
+            .is_some_and(|len| len > self.max_bytes as u64)
+        {
+            return Err(self.too_large(&media.url));
+        }
+
+        let mut bytes = Vec::new();
+        while let Some(chunk) = resp
+            .chunk()
+            .await
+            .map_err(|e| SmsError::Http(e.to_string()))?
+        {
+            if bytes.len() + chunk.len() > self.max_bytes {
+                return Err(self.too_large(&media.url));
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+
+        debug!("Downloaded {} bytes of {} media", bytes.len(), provider);
+        Ok(Media {
+            url: media.url.clone(),
+            content_type,
+            bytes,
+        })
+    }
+
+    fn too_large(&self, url: &str) -> SmsError {
+        SmsError::Invalid(format!(
+            "media {} exceeds the {}-byte limit",
+            url, self.max_bytes
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sms_core::{Headers, InboundWebhook};
+
+    #[test]
+    fn content_type_matching() {
+        let downloader = MediaDownloader::new();
+        assert!(downloader.allows("image/jpeg"));
+        assert!(downloader.allows("IMAGE/PNG; charset=binary"));
+        assert!(downloader.allows("text/vcard"));
+        assert!(!downloader.allows("text/html"));
+        assert!(!downloader.allows("application/x-msdownload"));
+
+        let only_png = downloader.with_content_types(["image/png"]);
+        assert!(only_png.allows("image/png"));
+        assert!(!only_png.allows("image/jpeg"));
+    }
+
+    #[test]
+    fn finds_twilio_media() {
+        let twilio = sms_twilio::TwilioClient::new("AC123", "token");
+        let body = b"From=%2B1&To=%2B2&Body=&NumMedia=1&MediaUrl0=https%3A%2F%2Fx%2Fm0";
+        let msg = twilio.parse_inbound(&Headers::new(), body).unwrap();
+        let refs = media_refs(&msg);
+        assert_eq!(refs.len(), 1);
+        assert_eq!(refs[0].url, "https://x/m0");
+    }
+
+    #[tokio::test]
+    async fn rejects_disallowed_type_before_fetching() {
+        let media = MediaRef {
+            url: "http://127.0.0.1:9/never-fetched".into(),
+            content_type: Some("application/x-msdownload".into()),
+        };
+        let err = MediaDownloader::new()
+            .download("twilio", &media)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, SmsError::Invalid(_)));
+    }
+}
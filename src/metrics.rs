@@ -0,0 +1,520 @@
+//! Send and delivery metrics with scrape and push export.
+//!
+//! [`SmsMetrics`] counts sends, failures, delivery reports and inbound
+//! messages per provider.  Long-running services expose
+//! [`render_prometheus`](SmsMetrics::render_prometheus) on a scrape endpoint;
+//! Lambda functions and batch jobs, which exit before any scrape happens,
+//! hand the same metrics to a [`MetricsPusher`] and call
+//! [`push`](MetricsPusher::push) on flush or shutdown.  With
+//! [`with_rate_limiter`](SmsMetrics::with_rate_limiter), the scrape output
+//! also carries rate-limit bucket counts and rejections per provider.
+//!
+//! ```rust,ignore
+//! use smskit::metrics::{MeteredClient, MetricsPusher, PushTarget, SmsMetrics};
+//!
+//! let metrics = SmsMetrics::new();
+//! let client = MeteredClient::new("plivo", plivo, metrics.clone());
+//! client.send(req).await?;
+//!
+//! MetricsPusher::new(metrics, PushTarget::pushgateway("http://pushgateway:9091", "nightly-batch"))
+//!     .push()
+//!     .await?;
+//! ```
+
+use crate::rate_limiter::RateLimiter;
+use sms_core::{Channel, SendRequest, SendResponse, SmsClient, SmsError};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{debug, warn};
+
+/// Counters for a single provider.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProviderCounters {
+    /// Messages accepted by the provider.
+    pub sent: u64,
+    /// Sends that returned an error.
+    pub failed: u64,
+    /// Delivery reports received.
+    pub delivery_reports: u64,
+    /// Inbound messages received.
+    pub inbound: u64,
+}
+
+/// Shared per-provider SMS counters.
+///
+/// Cloning yields another handle to the same counters.
+#[derive(Debug, Clone, Default)]
+pub struct SmsMetrics {
+    counters: Arc<Mutex<BTreeMap<String, ProviderCounters>>>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+}
+
+impl SmsMetrics {
+    /// Create an empty set of counters.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Include `limiter`'s buckets, aggregated per provider, in
+    /// [`render_prometheus`](Self::render_prometheus).
+    ///
+    /// Buckets are keyed by client address, so they are never exported one
+    /// by one: that would give every caller its own time series.
+    pub fn with_rate_limiter(mut self, limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = Some(limiter);
+        self
+    }
+
+    /// Record the outcome of a send through `provider`.
+    pub fn record_send(&self, provider: &str, result: &Result<SendResponse, SmsError>) {
+        self.update(provider, |c| match result {
+            Ok(_) => c.sent += 1,
+            Err(_) => c.failed += 1,
+        });
+    }
+
+    /// Record a delivery report from `provider`.
+    pub fn record_delivery_report(&self, provider: &str) {
+        self.update(provider, |c| c.delivery_reports += 1);
+    }
+
+    /// Record an inbound message from `provider`.
+    pub fn record_inbound(&self, provider: &str) {
+        self.update(provider, |c| c.inbound += 1);
+    }
+
+    /// Current counters for one provider.
+    pub fn get(&self, provider: &str) -> ProviderCounters {
+        self.lock().get(provider).copied().unwrap_or_default()
+    }
+
+    /// Current counters for every provider seen so far.
+    pub fn snapshot(&self) -> BTreeMap<String, ProviderCounters> {
+        self.lock().clone()
+    }
+
+    /// Render the counters in the Prometheus text exposition format.
+    pub fn render_prometheus(&self) -> String {
+        let snapshot = self.snapshot();
+        let mut out = String::new();
+        for (name, help, field) in METRICS {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} counter", name);
+            for (provider, counters) in &snapshot {
+                let _ = writeln!(
+                    out,
+                    "{}{{provider=\"{}\"}} {}",
+                    name,
+                    escape_label(provider),
+                    field(counters)
+                );
+            }
+        }
+        if let Some(limiter) = &self.rate_limiter {
+            render_rate_limiter(&mut out, limiter);
+        }
+        out
+    }
+
+    fn update(&self, provider: &str, f: impl FnOnce(&mut ProviderCounters)) {
+        f(self.lock().entry(provider.to_string()).or_default());
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<String, ProviderCounters>> {
+        self.counters.lock().expect("metrics poisoned")
+    }
+}
+
+fn render_rate_limiter(out: &mut String, limiter: &RateLimiter) {
+    let status = limiter.status();
+    let mut per_provider: BTreeMap<&str, BucketTotals> = BTreeMap::new();
+    for bucket in &status.buckets {
+        let provider = bucket.key.split(':').next().unwrap_or_default();
+        let totals = per_provider.entry(provider).or_default();
+        totals.buckets += 1;
+        totals.exhausted += u64::from(bucket.remaining == 0);
+        totals.rejections += bucket.rejections;
+    }
+
+    for (name, help, kind, field) in BUCKET_METRICS {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} {}", name, kind);
+        for (provider, totals) in &per_provider {
+            let _ = writeln!(
+                out,
+                "{}{{provider=\"{}\"}} {}",
+                name,
+                escape_label(provider),
+                field(totals)
+            );
+        }
+    }
+    let _ = writeln!(
+        out,
+        "# HELP smskit_rate_limit_rejections_total Checks rejected by the rate limiter."
+    );
+    let _ = writeln!(out, "# TYPE smskit_rate_limit_rejections_total counter");
+    let _ = writeln!(
+        out,
+        "smskit_rate_limit_rejections_total {}",
+        status.rejections
+    );
+}
+
+/// Escape a Prometheus label value.
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Rate-limit buckets of one provider, summed.
+#[derive(Default)]
+struct BucketTotals {
+    buckets: u64,
+    exhausted: u64,
+    rejections: u64,
+}
+
+type BucketField = fn(&BucketTotals) -> u64;
+
+const BUCKET_METRICS: [(&str, &str, &str, BucketField); 3] = [
+    (
+        "smskit_rate_limit_buckets",
+        "Rate-limit buckets currently tracked.",
+        "gauge",
+        |t| t.buckets,
+    ),
+    (
+        "smskit_rate_limit_exhausted_buckets",
+        "Rate-limit buckets that are out of tokens.",
+        "gauge",
+        |t| t.exhausted,
+    ),
+    (
+        "smskit_rate_limit_bucket_rejections_total",
+        "Checks rejected by currently tracked rate-limit buckets.",
+        "counter",
+        |t| t.rejections,
+    ),
+];
+
+type CounterField = fn(&ProviderCounters) -> u64;
+
+const METRICS: [(&str, &str, CounterField); 4] = [
+    (
+        "smskit_messages_sent_total",
+        "Messages accepted by the provider.",
+        |c| c.sent,
+    ),
+    (
+        "smskit_send_failures_total",
+        "Sends that returned an error.",
+        |c| c.failed,
+    ),
+    (
+        "smskit_delivery_reports_total",
+        "Delivery reports received.",
+        |c| c.delivery_reports,
+    ),
+    (
+        "smskit_inbound_messages_total",
+        "Inbound messages received.",
+        |c| c.inbound,
+    ),
+];
+
+/// Wraps an [`SmsClient`] and records every send in [`SmsMetrics`].
+pub struct MeteredClient<C> {
+    provider: String,
+    inner: C,
+    metrics: SmsMetrics,
+}
+
+impl<C: SmsClient> MeteredClient<C> {
+    /// Record sends through `inner` under the `provider` label.
+    pub fn new(provider: impl Into<String>, inner: C, metrics: SmsMetrics) -> Self {
+        Self {
+            provider: provider.into(),
+            inner,
+            metrics,
+        }
+    }
+
+    /// The metrics this client records into.
+    pub fn metrics(&self) -> &SmsMetrics {
+        &self.metrics
+    }
+}
+
+impl<C: SmsClient> SmsClient for MeteredClient<C> {
+    async fn send(&self, req: SendRequest<'_>) -> Result<SendResponse, SmsError> {
+        let result = self.inner.send(req).await;
+        self.metrics.record_send(&self.provider, &result);
+        result
+    }
+
+    fn supports(&self, channel: Channel) -> bool {
+        self.inner.supports(channel)
+    }
+
+    fn max_text_len(&self) -> Option<usize> {
+        self.inner.max_text_len()
+    }
+}
+
+/// Where [`MetricsPusher`] sends metrics.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PushTarget {
+    /// A Prometheus Pushgateway; metrics are `PUT` to
+    /// `{url}/metrics/job/{job}`.
+    Pushgateway { url: String, job: String },
+    /// An OTLP/HTTP collector; metrics are `POST`ed as JSON to
+    /// `{endpoint}/v1/metrics`.
+    Otlp {
+        endpoint: String,
+        service_name: String,
+    },
+}
+
+impl PushTarget {
+    /// Push to a Prometheus Pushgateway under the given job name.
+    pub fn pushgateway(url: impl Into<String>, job: impl Into<String>) -> Self {
+        Self::Pushgateway {
+            url: url.into(),
+            job: job.into(),
+        }
+    }
+
+    /// Push to an OTLP/HTTP collector as `service_name`.
+    pub fn otlp(endpoint: impl Into<String>, service_name: impl Into<String>) -> Self {
+        Self::Otlp {
+            endpoint: endpoint.into(),
+            service_name: service_name.into(),
+        }
+    }
+}
+
+/// Pushes [`SmsMetrics`] to a Pushgateway or OTLP collector.
+///
+/// Call [`push`](Self::push) at the end of a Lambda invocation or batch job,
+/// or periodically from short-lived workers.
+#[derive(Debug, Clone)]
+pub struct MetricsPusher {
+    metrics: SmsMetrics,
+    target: PushTarget,
+    http: reqwest::Client,
+}
+
+impl MetricsPusher {
+    /// Create a pusher for `metrics`.
+    pub fn new(metrics: SmsMetrics, target: PushTarget) -> Self {
+        Self {
+            metrics,
+            target,
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Send the current counters to the configured target.
+    pub async fn push(&self) -> Result<(), SmsError> {
+        let request = match &self.target {
+            PushTarget::Pushgateway { url, job } => self
+                .http
+                .put(format!("{}/metrics/job/{}", url.trim_end_matches('/'), job))
+                .header("content-type", "text/plain; version=0.0.4")
+                .body(self.metrics.render_prometheus()),
+            PushTarget::Otlp {
+                endpoint,
+                service_name,
+            } => self
+                .http
+                .post(format!("{}/v1/metrics", endpoint.trim_end_matches('/')))
+                .json(&otlp_payload(&self.metrics, service_name)),
+        };
+
+        let resp = request
+            .send()
+            .await
+            .map_err(|e| SmsError::Http(e.to_string()))?;
+        let status = resp.status();
+        if !status.is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            warn!("Metrics push to {:?} failed: {}", self.target, status);
+            return Err(SmsError::Provider(format!("HTTP {}: {}", status, body)));
+        }
+        debug!("Pushed metrics to {:?}", self.target);
+        Ok(())
+    }
+}
+
+/// Build an OTLP/HTTP JSON `ExportMetricsServiceRequest` of cumulative sums.
+fn otlp_payload(metrics: &SmsMetrics, service_name: &str) -> serde_json::Value {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos()
+        .to_string();
+    let snapshot = metrics.snapshot();
+
+    let otlp_metrics: Vec<_> = METRICS
+        .iter()
+        .map(|(name, help, field)| {
+            let points: Vec<_> = snapshot
+                .iter()
+                .map(|(provider, counters)| {
+                    serde_json::json!({
+                        "attributes": [
+                            { "key": "provider", "value": { "stringValue": provider } }
+                        ],
+                        "timeUnixNano": now,
+                        "asInt": field(counters).to_string(),
+                    })
+                })
+                .collect();
+            serde_json::json!({
+                "name": name,
+                "description": help,
+                "sum": {
+                    "dataPoints": points,
+                    // AGGREGATION_TEMPORALITY_CUMULATIVE
+                    "aggregationTemporality": 2,
+                    "isMonotonic": true,
+                },
+            })
+        })
+        .collect();
+
+    serde_json::json!({
+        "resourceMetrics": [{
+            "resource": {
+                "attributes": [
+                    { "key": "service.name", "value": { "stringValue": service_name } }
+                ]
+            },
+            "scopeMetrics": [{
+                "scope": { "name": "smskit" },
+                "metrics": otlp_metrics,
+            }]
+        }]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct EchoClient;
+
+    impl SmsClient for EchoClient {
+        async fn send(&self, req: SendRequest<'_>) -> Result<SendResponse, SmsError> {
+            if req.text == "fail" {
+                return Err(SmsError::Provider("nope".into()));
+            }
+            Ok(SendResponse {
+                id: "1".into(),
+                provider: "echo",
+                channel: Channel::Sms,
+                http_status: None,
+                provider_request_id: None,
+                thread_id: None,
+                raw: serde_json::json!({}),
+            })
+        }
+    }
+
+    fn req(text: &str) -> SendRequest<'_> {
+        SendRequest {
+            to: "+1",
+            from: "+2",
+            text,
+            channel: Channel::Sms,
+            timeout: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn metered_client_counts_sends() {
+        let metrics = SmsMetrics::new();
+        let client = MeteredClient::new("echo", EchoClient, metrics.clone());
+        client.send(req("hi")).await.unwrap();
+        client.send(req("hi")).await.unwrap();
+        assert!(client.send(req("fail")).await.is_err());
+
+        let counters = metrics.get("echo");
+        assert_eq!(counters.sent, 2);
+        assert_eq!(counters.failed, 1);
+    }
+
+    #[test]
+    fn renders_prometheus_text() {
+        let metrics = SmsMetrics::new();
+        metrics.record_delivery_report("plivo");
+        metrics.record_inbound("twilio");
+
+        let text = metrics.render_prometheus();
+        assert!(text.contains("# TYPE smskit_delivery_reports_total counter"));
+        assert!(text.contains("smskit_delivery_reports_total{provider=\"plivo\"} 1"));
+        assert!(text.contains("smskit_inbound_messages_total{provider=\"twilio\"} 1"));
+        assert!(text.contains("smskit_messages_sent_total{provider=\"plivo\"} 0"));
+    }
+
+    #[test]
+    fn renders_rate_limiter_buckets() {
+        let limiter = Arc::new(RateLimiter::new(crate::rate_limiter::RateLimitConfig {
+            max_requests: 1,
+            window_seconds: 60,
+            ..Default::default()
+        }));
+        limiter.check("plivo:a");
+        limiter.check("plivo:a");
+        limiter.check("plivo:b");
+
+        let text = SmsMetrics::new()
+            .with_rate_limiter(limiter)
+            .render_prometheus();
+        assert!(text.contains("smskit_rate_limit_buckets{provider=\"plivo\"} 2"));
+        assert!(text.contains("smskit_rate_limit_exhausted_buckets{provider=\"plivo\"} 2"));
+        assert!(text.contains("smskit_rate_limit_bucket_rejections_total{provider=\"plivo\"} 1"));
+        assert!(text.contains("smskit_rate_limit_rejections_total 1"));
+        assert!(!text.contains("plivo:a"));
+    }
+
+    #[test]
+    fn escapes_label_values() {
+        let metrics = SmsMetrics::new();
+        metrics.record_inbound("a\"b\\c\nd");
+        let text = metrics.render_prometheus();
+        assert!(text.contains("smskit_inbound_messages_total{provider=\"a\\\"b\\\\c\\nd\"} 1"));
+    }
+
+    #[test]
+    fn otlp_payload_has_one_point_per_provider() {
+        let metrics = SmsMetrics::new();
+        metrics.record_send(
+            "plivo",
+            &Ok(SendResponse {
+                id: "1".into(),
+                provider: "plivo",
+                channel: Channel::Sms,
+                http_status: None,
+                provider_request_id: None,
+                thread_id: None,
+                raw: serde_json::json!({}),
+            }),
+        );
+        metrics.record_inbound("twilio");
+
+        let payload = otlp_payload(&metrics, "lambda");
+        let scope = &payload["resourceMetrics"][0]["scopeMetrics"][0];
+        assert_eq!(scope["metrics"].as_array().unwrap().len(), 4);
+        let sent = &scope["metrics"][0];
+        assert_eq!(sent["name"], "smskit_messages_sent_total");
+        let points = sent["sum"]["dataPoints"].as_array().unwrap();
+        assert_eq!(points.len(), 2);
+        assert_eq!(points[0]["asInt"], "1");
+    }
+}
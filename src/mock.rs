@@ -0,0 +1,6 @@
+//! Recording mock provider for application tests.
+//!
+//! Re-exported from the [`sms_mock`] crate, which provider crates and test
+//! suites can depend on without pulling in the rest of smskit.
+
+pub use sms_mock::*;
@@ -0,0 +1,494 @@
+//! Opt-out (STOP) lists.
+//!
+//! [`OptOutStore`] records recipients who asked not to be messaged, and
+//! [`OptOutClient`] refuses sends to them.  Existing opt-outs can be imported
+//! from an [`OptOutSource`] — the SNS account opt-out list, or a suppression
+//! export from another aggregator via [`CsvOptOutSource`] — and
+//! [`OptOutSync`] keeps the store and its sources in step on an interval, so
+//! migrating to smskit doesn't lose compliance state.
+//!
+//! ```rust,ignore
+//! use smskit::optout::{CsvOptOutSource, InMemoryOptOutStore, OptOutClient, OptOutSync};
+//!
+//! let store = Arc::new(InMemoryOptOutStore::new());
+//! let sync = OptOutSync::new(store.clone())
+//!     .with_source(Arc::new(sns_client.clone()))
+//!     .with_source(Arc::new(CsvOptOutSource::new("old-aggregator", "suppressions.csv")));
+//! sync.sync_once().await?;
+//! tokio::spawn(async move { sync.run().await });
+//!
+//! let client = OptOutClient::new(twilio, store);
+//! ```
+
+use crate::runtime::Interval;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sms_core::{Channel, SendRequest, SendResponse, SmsClient, SmsError, normalize_number};
+use std::collections::{BTreeMap, HashSet};
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use time::OffsetDateTime;
+use tracing::{debug, info, warn};
+
+/// Default time between [`OptOutSync`] runs.
+pub const DEFAULT_SYNC_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// A recipient who opted out.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OptOut {
+    /// The recipient's number, as returned by [`normalize_number`].
+    pub number: String,
+    /// Where the opt-out came from: a provider name, an import source, or
+    /// e.g. `"stop-keyword"`.
+    pub source: String,
+    /// When the opt-out was recorded.
+    #[serde(with = "time::serde::rfc3339")]
+    pub opted_out_at: OffsetDateTime,
+}
+
+impl OptOut {
+    /// An opt-out of `number` from `source`, recorded now.
+    pub fn new(number: &str, source: impl Into<String>) -> Self {
+        Self {
+            number: normalize_number(number),
+            source: source.into(),
+            opted_out_at: OffsetDateTime::now_utc(),
+        }
+    }
+}
+
+/// Persistent set of opted-out recipients.
+///
+/// Numbers are compared after [`normalize_number`].
+#[async_trait]
+pub trait OptOutStore: Send + Sync {
+    /// Record an opt-out.  Returns `false` if the number was already opted out.
+    async fn opt_out(&self, entry: OptOut) -> Result<bool, SmsError>;
+
+    /// Remove `number` from the list, e.g. after it texts START.  Returns
+    /// `false` if it wasn't on it.
+    async fn opt_in(&self, number: &str) -> Result<bool, SmsError>;
+
+    /// Whether `number` has opted out.
+    async fn is_opted_out(&self, number: &str) -> Result<bool, SmsError>;
+
+    /// Every opt-out.
+    async fn list(&self) -> Result<Vec<OptOut>, SmsError>;
+
+    /// Erase every record of `number`, e.g. for a GDPR erasure request.
+    /// Returns `false` if there was none.  The number can be messaged again
+    /// afterwards, so only erase numbers that asked to be forgotten.
+    async fn forget_number(&self, number: &str) -> Result<bool, SmsError> {
+        self.opt_in(number).await
+    }
+}
+
+/// An [`OptOutStore`] held in process memory.
+///
+/// Cloning yields another handle to the same list.
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryOptOutStore {
+    entries: Arc<RwLock<BTreeMap<String, OptOut>>>,
+}
+
+impl InMemoryOptOutStore {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of opted-out recipients.
+    pub fn len(&self) -> usize {
+        self.entries.read().expect("opt-out store poisoned").len()
+    }
+
+    /// Returns `true` if nobody has opted out.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[async_trait]
+impl OptOutStore for InMemoryOptOutStore {
+    async fn opt_out(&self, entry: OptOut) -> Result<bool, SmsError> {
+        let mut entries = self.entries.write().expect("opt-out store poisoned");
+        if entries.contains_key(&entry.number) {
+            return Ok(false);
+        }
+        entries.insert(entry.number.clone(), entry);
+        Ok(true)
+    }
+
+    async fn opt_in(&self, number: &str) -> Result<bool, SmsError> {
+        let mut entries = self.entries.write().expect("opt-out store poisoned");
+        Ok(entries.remove(&normalize_number(number)).is_some())
+    }
+
+    async fn is_opted_out(&self, number: &str) -> Result<bool, SmsError> {
+        let entries = self.entries.read().expect("opt-out store poisoned");
+        Ok(entries.contains_key(&normalize_number(number)))
+    }
+
+    async fn list(&self) -> Result<Vec<OptOut>, SmsError> {
+        let entries = self.entries.read().expect("opt-out store poisoned");
+        Ok(entries.values().cloned().collect())
+    }
+}
+
+/// An external opt-out list: a provider account or an exported file.
+#[async_trait]
+pub trait OptOutSource: Send + Sync {
+    /// Name recorded as [`OptOut::source`] for numbers imported from here.
+    fn name(&self) -> &str;
+
+    /// Every number the source holds as opted out.
+    async fn fetch(&self) -> Result<Vec<String>, SmsError>;
+
+    /// Add locally recorded opt-outs to the source.  Returns how many were
+    /// written.  Read-only sources keep the default, which writes nothing.
+    async fn push(&self, numbers: &[String]) -> Result<usize, SmsError> {
+        let _ = numbers;
+        Ok(0)
+    }
+}
+
+/// The account-wide SNS opt-out list.  SNS offers no API to add numbers to
+/// it, so the source is read-only.
+#[async_trait]
+impl OptOutSource for sms_aws_sns::AwsSnsClient {
+    fn name(&self) -> &str {
+        "aws-sns"
+    }
+
+    async fn fetch(&self) -> Result<Vec<String>, SmsError> {
+        self.opted_out_numbers().await
+    }
+}
+
+/// A suppression list exported as CSV, read from disk on every fetch.
+///
+/// The number column is the one headed `phone`, `phone_number`, `number`,
+/// `msisdn`, `mobile`, `destination`, `recipient` or `to`; without such a
+/// header the first column is used.  Rows without digits are skipped.
+#[derive(Debug, Clone)]
+pub struct CsvOptOutSource {
+    name: String,
+    path: PathBuf,
+}
+
+impl CsvOptOutSource {
+    /// Read opt-outs from the CSV file at `path`, recording them as coming
+    /// from `name`.
+    pub fn new(name: impl Into<String>, path: impl Into<PathBuf>) -> Self {
+        Self {
+            name: name.into(),
+            path: path.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl OptOutSource for CsvOptOutSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn fetch(&self) -> Result<Vec<String>, SmsError> {
+        let text = tokio::fs::read_to_string(&self.path)
+            .await
+            .map_err(|e| SmsError::Unexpected(format!("reading {}: {}", self.path.display(), e)))?;
+        Ok(parse_csv(&text))
+    }
+}
+
+/// Header names recognised as the phone number column.
+pub(crate) const NUMBER_HEADERS: &[&str] = &[
+    "phone",
+    "phone_number",
+    "number",
+    "msisdn",
+    "mobile",
+    "destination",
+    "recipient",
+    "to",
+];
+
+/// Extract the phone numbers from a CSV suppression export.
+pub fn parse_csv(text: &str) -> Vec<String> {
+    let rows: Vec<Vec<&str>> = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            line.split(',')
+                .map(|field| field.trim().trim_matches('"').trim())
+                .collect()
+        })
+        .collect();
+    let Some(first) = rows.first() else {
+        return Vec::new();
+    };
+    let column = first
+        .iter()
+        .position(|h| NUMBER_HEADERS.contains(&h.to_lowercase().replace(' ', "_").as_str()))
+        .unwrap_or(0);
+    rows.iter()
+        .filter_map(|row| row.get(column))
+        .filter(|field| field.bytes().any(|b| b.is_ascii_digit()))
+        .map(|field| normalize_number(field))
+        .collect()
+}
+
+/// Copy every opt-out held by `source` into `store`.  Returns how many
+/// numbers were new to the store.
+pub async fn import(store: &dyn OptOutStore, source: &dyn OptOutSource) -> Result<usize, SmsError> {
+    let mut imported = 0;
+    for number in source.fetch().await? {
+        if store.opt_out(OptOut::new(&number, source.name())).await? {
+            imported += 1;
+        }
+    }
+    if imported > 0 {
+        info!("Imported {} opt-outs from {}", imported, source.name());
+    }
+    Ok(imported)
+}
+
+/// Outcome of one [`OptOutSync::sync_once`] run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct SyncReport {
+    /// Numbers copied from sources into the store.
+    pub imported: usize,
+    /// Numbers copied from the store into sources.
+    pub pushed: usize,
+    /// Sources that failed; see the log for details.
+    pub failed_sources: usize,
+}
+
+/// Periodic two-way synchronisation between an [`OptOutStore`] and its
+/// [`OptOutSource`]s.
+///
+/// Each run imports every source's opt-outs into the store, then pushes the
+/// store's opt-outs that a source lacks back to it.  Opt-ins are never
+/// synced: re-subscribing a number must be done deliberately on each side.
+pub struct OptOutSync {
+    store: Arc<dyn OptOutStore>,
+    sources: Vec<Arc<dyn OptOutSource>>,
+    interval: Duration,
+}
+
+impl OptOutSync {
+    /// Synchronise `store` every [`DEFAULT_SYNC_INTERVAL`].
+    pub fn new(store: Arc<dyn OptOutStore>) -> Self {
+        Self {
+            store,
+            sources: Vec::new(),
+            interval: DEFAULT_SYNC_INTERVAL,
+        }
+    }
+
+    /// Synchronise with `source` too.
+    pub fn with_source(mut self, source: Arc<dyn OptOutSource>) -> Self {
+        self.sources.push(source);
+        self
+    }
+
+    /// Wait `interval` between runs.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Synchronise once.  A failing source is logged and skipped; errors
+    /// from the store abort the run.
+    pub async fn sync_once(&self) -> Result<SyncReport, SmsError> {
+        let mut report = SyncReport::default();
+        for source in &self.sources {
+            let remote: HashSet<String> = match source.fetch().await {
+                Ok(numbers) => numbers.iter().map(|n| normalize_number(n)).collect(),
+                Err(e) => {
+                    warn!("Fetching opt-outs from {} failed: {}", source.name(), e);
+                    report.failed_sources += 1;
+                    continue;
+                }
+            };
+            for number in &remote {
+                if self
+                    .store
+                    .opt_out(OptOut::new(number, source.name()))
+                    .await?
+                {
+                    report.imported += 1;
+                }
+            }
+            let missing: Vec<String> = self
+                .store
+                .list()
+                .await?
+                .into_iter()
+                .map(|entry| entry.number)
+                .filter(|number| !remote.contains(number))
+                .collect();
+            if missing.is_empty() {
+                continue;
+            }
+            match source.push(&missing).await {
+                Ok(pushed) => report.pushed += pushed,
+                Err(e) => {
+                    warn!("Pushing opt-outs to {} failed: {}", source.name(), e);
+                    report.failed_sources += 1;
+                }
+            }
+        }
+        debug!(
+            "Opt-out sync imported {}, pushed {}",
+            report.imported, report.pushed
+        );
+        Ok(report)
+    }
+
+    /// Synchronise forever.  Failed runs are logged and retried on the next
+    /// tick.
+    ///
+    /// Spawn this as a background task:
+    /// ```rust,ignore
+    /// tokio::spawn(async move { sync.run().await });
+    /// ```
+    pub async fn run(&self) {
+        let mut ticks = Interval::new(self.interval);
+        loop {
+            ticks.tick().await;
+            if let Err(e) = self.sync_once().await {
+                warn!("Opt-out sync failed: {}", e);
+            }
+        }
+    }
+}
+
+/// Wraps an [`SmsClient`] and refuses sends to opted-out recipients with
+/// [`SmsError::Suppressed`].
+pub struct OptOutClient<C> {
+    inner: C,
+    store: Arc<dyn OptOutStore>,
+}
+
+impl<C: SmsClient> OptOutClient<C> {
+    /// Send through `inner`, checking recipients against `store`.
+    pub fn new(inner: C, store: Arc<dyn OptOutStore>) -> Self {
+        Self { inner, store }
+    }
+}
+
+impl<C: SmsClient> SmsClient for OptOutClient<C> {
+    async fn send(&self, req: SendRequest<'_>) -> Result<SendResponse, SmsError> {
+        if self.store.is_opted_out(req.to).await? {
+            return Err(SmsError::Suppressed(format!(
+                "recipient {} has opted out",
+                req.to
+            )));
+        }
+        self.inner.send(req).await
+    }
+
+    fn supports(&self, channel: Channel) -> bool {
+        self.inner.supports(channel)
+    }
+
+    fn max_text_len(&self) -> Option<usize> {
+        self.inner.max_text_len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// An in-memory provider list that accepts pushes.
+    #[derive(Default)]
+    struct FakeSource {
+        numbers: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl OptOutSource for FakeSource {
+        fn name(&self) -> &str {
+            "fake"
+        }
+
+        async fn fetch(&self) -> Result<Vec<String>, SmsError> {
+            Ok(self.numbers.lock().unwrap().clone())
+        }
+
+        async fn push(&self, numbers: &[String]) -> Result<usize, SmsError> {
+            self.numbers.lock().unwrap().extend_from_slice(numbers);
+            Ok(numbers.len())
+        }
+    }
+
+    #[test]
+    fn parses_suppression_exports() {
+        let with_header =
+            "email,Phone Number,reason\na@b.c,\"+1 (415) 555-0100\",STOP\nx,,bounce\n";
+        assert_eq!(parse_csv(with_header), vec!["+14155550100"]);
+        let bare = "+447700900001\n+447700900002\n";
+        assert_eq!(parse_csv(bare), vec!["+447700900001", "+447700900002"]);
+        assert!(parse_csv("").is_empty());
+    }
+
+    #[tokio::test]
+    async fn sync_imports_and_pushes() {
+        let store = Arc::new(InMemoryOptOutStore::new());
+        store
+            .opt_out(OptOut::new("+1 555 0001", "stop-keyword"))
+            .await
+            .unwrap();
+        let source = Arc::new(FakeSource::default());
+        source.numbers.lock().unwrap().push("+15550002".into());
+        let sync = OptOutSync::new(store.clone()).with_source(source.clone());
+
+        let report = sync.sync_once().await.unwrap();
+        assert_eq!(report.imported, 1);
+        assert_eq!(report.pushed, 1);
+        assert_eq!(store.len(), 2);
+        assert!(store.is_opted_out("+15550002").await.unwrap());
+        assert_eq!(
+            *source.numbers.lock().unwrap(),
+            vec!["+15550002", "+15550001"]
+        );
+
+        assert_eq!(sync.sync_once().await.unwrap(), SyncReport::default());
+    }
+
+    #[tokio::test]
+    async fn client_refuses_opted_out_recipients() {
+        let store = Arc::new(InMemoryOptOutStore::new());
+        store.opt_out(OptOut::new("+1", "test")).await.unwrap();
+        let client = OptOutClient::new(crate::simulation::SimulatedClient::new(), store.clone());
+        let req = |to| SendRequest {
+            to,
+            from: "+2",
+            text: "hi",
+            ..Default::default()
+        };
+        assert!(matches!(
+            client.send(req("+1")).await,
+            Err(SmsError::Suppressed(_))
+        ));
+        assert!(client.send(req("+3")).await.is_ok());
+        assert!(store.opt_in("+1").await.unwrap());
+        assert!(client.send(req("+1")).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn forget_number_matches_any_formatting() {
+        let store = InMemoryOptOutStore::new();
+        store
+            .opt_out(OptOut::new("+44 7700 900001", "stop-keyword"))
+            .await
+            .unwrap();
+        assert!(store.forget_number("00447700900001").await.unwrap());
+        assert!(store.is_empty());
+        assert!(!store.forget_number("+447700900001").await.unwrap());
+    }
+}
@@ -0,0 +1,466 @@
+//! Priority-ordered outbound message queue.
+//!
+//! [`Outbox`] holds queued sends and [`OutboxWorker`] drains it through any
+//! [`SmsClient`], pacing sends at a configurable rate.  Workers always take
+//! the highest [`Priority`] first, so one-time passcodes are never stuck
+//! behind a bulk marketing campaign.
+//!
+//! Messages can also be parked in the [`Deferred`](OutboxState::Deferred)
+//! state, where workers leave them alone until something (usually
+//! [`StoreAndForward`](crate::store_forward::StoreAndForward)) takes them back
+//! out once a provider is reachable again.
+//!
+//! ```rust,ignore
+//! use smskit::outbox::{Outbox, OutboxWorker, Priority};
+//!
+//! let outbox = Outbox::new();
+//! outbox.enqueue(OwnedSendRequest::new("+1555", "+1666", "Sale!"), Priority::Bulk).await;
+//! outbox.enqueue(OwnedSendRequest::new("+1555", "+1666", "Code: 1234"), Priority::Critical).await;
+//!
+//! let worker = OutboxWorker::new(outbox.clone(), Arc::new(client))
+//!     .with_pacing(Duration::from_millis(100));
+//! tokio::spawn(async move { worker.run().await });
+//! ```
+
+use crate::runtime::sleep;
+use serde::{Deserialize, Serialize};
+use sms_core::{
+    DynSmsClient, IdGenerator, OwnedSendRequest, SendResponse, SmsClient, SmsError, UuidV4,
+    normalize_number,
+};
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, Notify};
+use tracing::{debug, warn};
+
+/// Delivery priority of a queued message.
+///
+/// Variants are ordered from lowest to highest, so `Priority::Critical >
+/// Priority::Bulk`.  Messages with equal priority are sent in the order they
+/// were enqueued.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    /// Marketing and other bulk campaign traffic.
+    Bulk,
+    /// Regular application traffic.
+    #[default]
+    Normal,
+    /// Operational alerts and notifications.
+    Alert,
+    /// Time-critical traffic such as one-time passcodes.
+    Critical,
+}
+
+/// Whether an [`OutboxEntry`] is waiting for a worker or parked.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutboxState {
+    /// Waiting for an [`OutboxWorker`].
+    #[default]
+    Queued,
+    /// Parked until providers recover; workers skip it.
+    Deferred,
+}
+
+/// A message waiting in the [`Outbox`].
+#[derive(Debug, Clone)]
+pub struct OutboxEntry {
+    /// Locally assigned queue ID.
+    pub id: String,
+    /// The message to send.
+    pub request: OwnedSendRequest,
+    /// Delivery priority.
+    pub priority: Priority,
+    /// Queued or deferred.
+    pub state: OutboxState,
+    /// When the message was enqueued.
+    pub enqueued_at: Instant,
+    /// Failed attempts to send a deferred message so far.
+    pub attempts: u32,
+    seq: u64,
+}
+
+/// Heap ordering: higher priority first, then lower sequence number (FIFO).
+impl Ord for OutboxEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+impl PartialOrd for OutboxEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for OutboxEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.seq == other.seq
+    }
+}
+
+impl Eq for OutboxEntry {}
+
+#[derive(Debug, Default)]
+struct OutboxInner {
+    heap: BinaryHeap<OutboxEntry>,
+    deferred: BinaryHeap<OutboxEntry>,
+    next_seq: u64,
+}
+
+impl OutboxInner {
+    fn push(
+        &mut self,
+        id: String,
+        request: OwnedSendRequest,
+        priority: Priority,
+        state: OutboxState,
+    ) {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        let entry = OutboxEntry {
+            id,
+            request,
+            priority,
+            state,
+            enqueued_at: Instant::now(),
+            attempts: 0,
+            seq,
+        };
+        match state {
+            OutboxState::Queued => self.heap.push(entry),
+            OutboxState::Deferred => self.deferred.push(entry),
+        }
+    }
+}
+
+/// A shared, priority-ordered queue of outbound messages.
+///
+/// Cloning an `Outbox` yields another handle to the same queue, so producers
+/// and [`OutboxWorker`]s can each hold their own copy.
+#[derive(Debug, Clone)]
+pub struct Outbox {
+    inner: Arc<Mutex<OutboxInner>>,
+    notify: Arc<Notify>,
+    ids: Arc<dyn IdGenerator>,
+}
+
+impl Default for Outbox {
+    fn default() -> Self {
+        Self {
+            inner: Arc::default(),
+            notify: Arc::default(),
+            ids: Arc::new(UuidV4),
+        }
+    }
+}
+
+impl Outbox {
+    /// Create an empty outbox.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Assign queue IDs with `ids` instead of random UUIDs.
+    pub fn with_id_generator(mut self, ids: impl IdGenerator + 'static) -> Self {
+        self.ids = Arc::new(ids);
+        self
+    }
+
+    /// Queue a message and return its queue ID.
+    pub async fn enqueue(&self, request: OwnedSendRequest, priority: Priority) -> String {
+        let id = self.ids.generate();
+        self.inner
+            .lock()
+            .await
+            .push(id.clone(), request, priority, OutboxState::Queued);
+
+        debug!("Enqueued message {} with priority {:?}", id, priority);
+        self.notify.notify_one();
+        id
+    }
+
+    /// Park a message in the deferred state and return its queue ID.
+    ///
+    /// Deferred messages are not counted by [`len`](Self::len) and are never
+    /// handed to an [`OutboxWorker`]; take them back with
+    /// [`pop_deferred`](Self::pop_deferred).
+    pub async fn defer(&self, request: OwnedSendRequest, priority: Priority) -> String {
+        let id = self.ids.generate();
+        self.inner
+            .lock()
+            .await
+            .push(id.clone(), request, priority, OutboxState::Deferred);
+        debug!("Deferred message {} with priority {:?}", id, priority);
+        id
+    }
+
+    /// Remove and return the highest-priority deferred message, if any.
+    pub async fn pop_deferred(&self) -> Option<OutboxEntry> {
+        self.inner.lock().await.deferred.pop()
+    }
+
+    /// Put a message taken with [`pop_deferred`](Self::pop_deferred) back,
+    /// keeping its place in line.
+    pub(crate) async fn restore_deferred(&self, entry: OutboxEntry) {
+        self.inner.lock().await.deferred.push(entry);
+    }
+
+    /// Number of deferred messages.
+    pub async fn deferred_len(&self) -> usize {
+        self.inner.lock().await.deferred.len()
+    }
+
+    /// Remove and return the highest-priority message, if any.
+    pub async fn pop(&self) -> Option<OutboxEntry> {
+        self.inner.lock().await.heap.pop()
+    }
+
+    /// Number of queued messages.
+    pub async fn len(&self) -> usize {
+        self.inner.lock().await.heap.len()
+    }
+
+    /// Returns `true` if nothing is queued.
+    pub async fn is_empty(&self) -> bool {
+        self.inner.lock().await.heap.is_empty()
+    }
+
+    /// Number of queued messages with the given priority.
+    pub async fn len_by_priority(&self, priority: Priority) -> usize {
+        self.inner
+            .lock()
+            .await
+            .heap
+            .iter()
+            .filter(|e| e.priority == priority)
+            .count()
+    }
+
+    /// Drop every queued and deferred message to or from `phone`, e.g. for
+    /// a GDPR erasure request.  Returns how many were dropped.
+    pub async fn forget_number(&self, phone: &str) -> usize {
+        let phone = normalize_number(phone);
+        let keep = |e: &OutboxEntry| {
+            normalize_number(&e.request.to) != phone && normalize_number(&e.request.from) != phone
+        };
+        let mut inner = self.inner.lock().await;
+        let before = inner.heap.len() + inner.deferred.len();
+        inner.heap.retain(keep);
+        inner.deferred.retain(keep);
+        before - inner.heap.len() - inner.deferred.len()
+    }
+
+    /// Wait until a message is enqueued.
+    async fn wait(&self) {
+        self.notify.notified().await;
+    }
+}
+
+/// Drains an [`Outbox`] through an [`SmsClient`], highest priority first.
+pub struct OutboxWorker {
+    outbox: Outbox,
+    client: Arc<dyn DynSmsClient>,
+    pacing: Duration,
+}
+
+impl OutboxWorker {
+    /// Create a worker that sends without any delay between messages.
+    pub fn new(outbox: Outbox, client: Arc<dyn DynSmsClient>) -> Self {
+        Self {
+            outbox,
+            client,
+            pacing: Duration::ZERO,
+        }
+    }
+
+    /// Wait `pacing` between consecutive sends.
+    pub fn with_pacing(mut self, pacing: Duration) -> Self {
+        self.pacing = pacing;
+        self
+    }
+
+    /// Send the highest-priority queued message.
+    ///
+    /// Returns `None` if the outbox is empty.
+    pub async fn send_next(&self) -> Option<(OutboxEntry, Result<SendResponse, SmsError>)> {
+        let entry = self.outbox.pop().await?;
+        let result = self.client.send(entry.request.as_ref()).await;
+        match &result {
+            Ok(resp) => debug!(
+                "Sent queued message {} ({:?}) as {}",
+                entry.id, entry.priority, resp.id
+            ),
+            Err(e) => warn!("Queued message {} failed: {}", entry.id, e),
+        }
+        Some((entry, result))
+    }
+
+    /// Run forever, draining the outbox as messages arrive.
+    ///
+    /// Spawn this as a background task:
+    /// ```rust,ignore
+    /// tokio::spawn(async move { worker.run().await });
+    /// ```
+    pub async fn run(&self) {
+        loop {
+            match self.send_next().await {
+                Some(_) => {
+                    if !self.pacing.is_zero() {
+                        sleep(self.pacing).await;
+                    }
+                }
+                None => self.outbox.wait().await,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sms_core::{Channel, SendRequest};
+    use std::sync::Mutex as StdMutex;
+
+    /// Records the text of every message it sends.
+    #[derive(Default)]
+    struct RecordingClient {
+        sent: StdMutex<Vec<String>>,
+    }
+
+    impl SmsClient for RecordingClient {
+        async fn send(&self, req: SendRequest<'_>) -> Result<SendResponse, SmsError> {
+            self.sent.lock().unwrap().push(req.text.to_string());
+            Ok(SendResponse {
+                id: "id".into(),
+                provider: "recording",
+                channel: Channel::Sms,
+                http_status: None,
+                provider_request_id: None,
+                thread_id: None,
+                raw: serde_json::json!({}),
+            })
+        }
+    }
+
+    fn msg(text: &str) -> OwnedSendRequest {
+        OwnedSendRequest::new("+1", "+2", text)
+    }
+
+    #[test]
+    fn priority_ordering() {
+        assert!(Priority::Critical > Priority::Alert);
+        assert!(Priority::Alert > Priority::Normal);
+        assert!(Priority::Normal > Priority::Bulk);
+        assert_eq!(Priority::default(), Priority::Normal);
+    }
+
+    #[tokio::test]
+    async fn pops_highest_priority_first() {
+        let outbox = Outbox::new();
+        outbox.enqueue(msg("bulk"), Priority::Bulk).await;
+        outbox.enqueue(msg("otp"), Priority::Critical).await;
+        outbox.enqueue(msg("alert"), Priority::Alert).await;
+
+        assert_eq!(outbox.pop().await.unwrap().request.text, "otp");
+        assert_eq!(outbox.pop().await.unwrap().request.text, "alert");
+        assert_eq!(outbox.pop().await.unwrap().request.text, "bulk");
+        assert!(outbox.pop().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn uses_configured_id_generator() {
+        let outbox = Outbox::new().with_id_generator(sms_core::Snowflake::new(1));
+        let a: u64 = outbox
+            .enqueue(msg("a"), Priority::Normal)
+            .await
+            .parse()
+            .unwrap();
+        let b: u64 = outbox
+            .enqueue(msg("b"), Priority::Normal)
+            .await
+            .parse()
+            .unwrap();
+        assert!(a < b);
+    }
+
+    #[tokio::test]
+    async fn equal_priority_is_fifo() {
+        let outbox = Outbox::new();
+        for i in 0..5 {
+            outbox.enqueue(msg(&i.to_string()), Priority::Normal).await;
+        }
+        for i in 0..5 {
+            assert_eq!(outbox.pop().await.unwrap().request.text, i.to_string());
+        }
+    }
+
+    #[tokio::test]
+    async fn len_by_priority_counts() {
+        let outbox = Outbox::new();
+        outbox.enqueue(msg("a"), Priority::Bulk).await;
+        outbox.enqueue(msg("b"), Priority::Bulk).await;
+        outbox.enqueue(msg("c"), Priority::Critical).await;
+        assert_eq!(outbox.len().await, 3);
+        assert_eq!(outbox.len_by_priority(Priority::Bulk).await, 2);
+        assert_eq!(outbox.len_by_priority(Priority::Alert).await, 0);
+    }
+
+    #[tokio::test]
+    async fn deferred_messages_are_kept_apart() {
+        let outbox = Outbox::new();
+        outbox.defer(msg("later"), Priority::Normal).await;
+        outbox.defer(msg("otp"), Priority::Critical).await;
+        assert!(outbox.is_empty().await);
+        assert!(outbox.pop().await.is_none());
+        assert_eq!(outbox.deferred_len().await, 2);
+
+        let entry = outbox.pop_deferred().await.unwrap();
+        assert_eq!(entry.request.text, "otp");
+        assert_eq!(entry.state, OutboxState::Deferred);
+        outbox.restore_deferred(entry).await;
+        assert_eq!(outbox.pop_deferred().await.unwrap().request.text, "otp");
+        assert_eq!(outbox.pop_deferred().await.unwrap().request.text, "later");
+    }
+
+    #[tokio::test]
+    async fn forget_number_drops_queued_and_deferred() {
+        let outbox = Outbox::new();
+        outbox.enqueue(msg("a"), Priority::Normal).await;
+        outbox.defer(msg("b"), Priority::Normal).await;
+        outbox
+            .enqueue(OwnedSendRequest::new("+3", "+2", "c"), Priority::Normal)
+            .await;
+
+        assert_eq!(outbox.forget_number(" +1").await, 2);
+        assert_eq!(outbox.len().await, 1);
+        assert_eq!(outbox.deferred_len().await, 0);
+    }
+
+    #[tokio::test]
+    async fn worker_drains_critical_before_bulk() {
+        let outbox = Outbox::new();
+        for i in 0..3 {
+            outbox
+                .enqueue(msg(&format!("bulk-{}", i)), Priority::Bulk)
+                .await;
+        }
+        outbox.enqueue(msg("otp"), Priority::Critical).await;
+
+        let client = Arc::new(RecordingClient::default());
+        let worker = OutboxWorker::new(outbox.clone(), client.clone());
+        while worker.send_next().await.is_some() {}
+
+        assert!(outbox.is_empty().await);
+        let sent = client.sent.lock().unwrap();
+        assert_eq!(sent[0], "otp");
+        assert_eq!(sent.len(), 4);
+    }
+}
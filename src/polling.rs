@@ -0,0 +1,149 @@
+//! Pull-based inbound messages.
+//!
+//! [`PollingRunner`] calls an [`InboundPoller`] on a fixed interval and
+//! dispatches every message it returns through a [`WebhookProcessor`]'s
+//! listeners, so gateways without webhooks (GSM modems, Gammu, some
+//! aggregators) feed the same pipeline as webhook-based providers.
+//!
+//! ```rust,ignore
+//! use smskit::polling::PollingRunner;
+//!
+//! let processor = WebhookProcessor::new(registry).with_listener(handle_message);
+//! let runner = PollingRunner::new(Arc::new(modem), processor.clone())
+//!     .with_interval(Duration::from_secs(5));
+//! tokio::spawn(async move { runner.run().await });
+//! ```
+
+use crate::runtime::Interval;
+use sms_core::{InboundPoller, SmsError};
+use sms_web_generic::WebhookProcessor;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// Default time between polls.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Periodically polls an [`InboundPoller`] and dispatches what it finds.
+pub struct PollingRunner {
+    poller: Arc<dyn InboundPoller>,
+    processor: WebhookProcessor,
+    interval: Duration,
+}
+
+impl PollingRunner {
+    /// Poll `poller` every [`DEFAULT_POLL_INTERVAL`], dispatching through
+    /// `processor`.
+    pub fn new(poller: Arc<dyn InboundPoller>, processor: WebhookProcessor) -> Self {
+        Self {
+            poller,
+            processor,
+            interval: DEFAULT_POLL_INTERVAL,
+        }
+    }
+
+    /// Wait `interval` between polls.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Poll once and dispatch the results.  Returns how many messages were
+    /// dispatched.
+    pub async fn poll_once(&self) -> Result<usize, SmsError> {
+        let messages = self.poller.poll().await?;
+        let mut count = 0;
+        for message in messages
+            .into_iter()
+            .filter_map(|m| self.processor.prepare(m))
+        {
+            self.processor.dispatch(&message);
+            count += 1;
+        }
+        if count > 0 {
+            debug!(
+                "Dispatched {} polled messages from {}",
+                count,
+                self.poller.provider()
+            );
+        }
+        Ok(count)
+    }
+
+    /// Poll forever.  Failed polls are logged and retried on the next tick.
+    ///
+    /// Spawn this as a background task:
+    /// ```rust,ignore
+    /// tokio::spawn(async move { runner.run().await });
+    /// ```
+    pub async fn run(&self) {
+        let mut ticks = Interval::new(self.interval);
+        loop {
+            ticks.tick().await;
+            if let Err(e) = self.poll_once().await {
+                warn!("Polling {} failed: {}", self.poller.provider(), e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use sms_core::{InboundMessage, InboundRegistry};
+    use std::sync::Mutex;
+
+    /// Returns each queued batch once, then nothing.
+    struct ScriptedPoller {
+        batches: Mutex<Vec<Result<Vec<InboundMessage>, SmsError>>>,
+    }
+
+    #[async_trait]
+    impl InboundPoller for ScriptedPoller {
+        fn provider(&self) -> &'static str {
+            "modem"
+        }
+
+        async fn poll(&self) -> Result<Vec<InboundMessage>, SmsError> {
+            self.batches.lock().unwrap().pop().unwrap_or(Ok(Vec::new()))
+        }
+    }
+
+    fn message(text: &str) -> InboundMessage {
+        InboundMessage {
+            id: None,
+            from: "+1".into(),
+            to: "+2".into(),
+            text: text.into(),
+            timestamp: None,
+            provider: "modem",
+            direction: Default::default(),
+            channel: Default::default(),
+            provider_account: None,
+            thread_id: None,
+            flag: None,
+            raw: serde_json::Value::Null,
+        }
+    }
+
+    #[tokio::test]
+    async fn polled_messages_reach_listeners() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = seen.clone();
+        let processor = WebhookProcessor::new(InboundRegistry::new())
+            .with_listener(move |msg| sink.lock().unwrap().push(msg.text.clone()));
+        let poller = ScriptedPoller {
+            batches: Mutex::new(vec![
+                Err(SmsError::Http("modem offline".into())),
+                Ok(vec![message("a"), message("b")]),
+            ]),
+        };
+        let runner = PollingRunner::new(Arc::new(poller), processor);
+
+        assert_eq!(runner.poll_once().await.unwrap(), 2);
+        assert!(runner.poll_once().await.is_err());
+        assert_eq!(runner.poll_once().await.unwrap(), 0);
+        assert_eq!(*seen.lock().unwrap(), vec!["a", "b"]);
+    }
+}
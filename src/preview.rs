@@ -0,0 +1,245 @@
+//! Pre-send previews.
+//!
+//! [`Previewer`] runs everything a send would decide up front — number
+//! normalization, destination and country rules, provider routing, encoding
+//! and segment analysis, and cost estimation — and returns it as a
+//! [`SendPreview`] without sending, so a UI can show "this will cost 3
+//! segments via plivo" before the user confirms.
+//!
+//! ```rust,ignore
+//! use smskit::preview::Previewer;
+//!
+//! let previewer = Previewer::new(router)
+//!     .with_prices(prices)
+//!     .with_countries(CountryAllowlist::only(["1", "44"]))
+//!     .with_least_cost_routing();
+//! let preview = previewer.preview(req.as_ref());
+//! println!("{preview}");   // "3 segments (Gsm7) via plivo, ~$0.0150"
+//! ```
+
+use crate::analysis::{MessageAnalysis, analyze_with};
+use crate::pricing::PriceTable;
+use crate::screening::{CountryAllowlist, DestinationRules, calling_code};
+use serde::{Serialize, Serializer};
+use sms_core::{SendRequest, SmsError, SmsRouter, normalize_number};
+use std::fmt;
+
+/// What sending a request would do.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SendPreview {
+    /// Destination with formatting stripped.
+    pub to: String,
+    /// Destination country calling code, e.g. `"44"`.
+    pub country_code: Option<String>,
+    /// Provider the send would go through, if one would accept it.
+    pub provider: Option<String>,
+    /// Encoding, segment count and per-provider list prices.
+    pub analysis: MessageAnalysis,
+    /// Estimated cost through [`provider`](Self::provider), priced for the
+    /// destination country.
+    pub estimated_cost: Option<f64>,
+    /// Why the send would be refused, if it would.
+    #[serde(serialize_with = "error_message")]
+    pub rejection: Option<SmsError>,
+}
+
+impl SendPreview {
+    /// Returns `true` if the send would go ahead.
+    pub fn is_sendable(&self) -> bool {
+        self.rejection.is_none()
+    }
+}
+
+fn error_message<S: Serializer>(err: &Option<SmsError>, s: S) -> Result<S::Ok, S::Error> {
+    match err {
+        Some(err) => s.collect_str(err),
+        None => s.serialize_none(),
+    }
+}
+
+impl fmt::Display for SendPreview {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(err) = &self.rejection {
+            return write!(f, "not sendable: {}", err);
+        }
+        let plural = if self.analysis.segments == 1 { "" } else { "s" };
+        write!(
+            f,
+            "{} segment{} ({:?})",
+            self.analysis.segments, plural, self.analysis.encoding
+        )?;
+        if let Some(provider) = &self.provider {
+            write!(f, " via {}", provider)?;
+        }
+        if let Some(cost) = self.estimated_cost {
+            write!(f, ", ~${:.4}", cost)?;
+        }
+        Ok(())
+    }
+}
+
+/// Builds [`SendPreview`]s against a router and the rules a send would face.
+pub struct Previewer {
+    router: SmsRouter,
+    prices: PriceTable,
+    destinations: DestinationRules,
+    countries: Option<CountryAllowlist>,
+    least_cost: bool,
+}
+
+impl Previewer {
+    /// Preview sends through `router`'s default provider, with default
+    /// prices and no destination rules.
+    pub fn new(router: SmsRouter) -> Self {
+        Self {
+            router,
+            prices: PriceTable::default(),
+            destinations: DestinationRules::default(),
+            countries: None,
+            least_cost: false,
+        }
+    }
+
+    /// Estimate costs from `prices`.
+    pub fn with_prices(mut self, prices: PriceTable) -> Self {
+        self.prices = prices;
+        self
+    }
+
+    /// Apply a destination blocklist/allowlist.
+    pub fn with_destinations(mut self, rules: DestinationRules) -> Self {
+        self.destinations = rules;
+        self
+    }
+
+    /// Apply a destination country allowlist.
+    pub fn with_countries(mut self, countries: CountryAllowlist) -> Self {
+        self.countries = Some(countries);
+        self
+    }
+
+    /// Route to the cheapest provider that would accept the send, instead
+    /// of the router's default.
+    pub fn with_least_cost_routing(mut self) -> Self {
+        self.least_cost = true;
+        self
+    }
+
+    /// Preview `req` as routed by this previewer.
+    pub fn preview(&self, req: SendRequest<'_>) -> SendPreview {
+        let to = normalize_number(req.to);
+        let req = SendRequest { to: &to, ..req };
+        let provider = if self.least_cost {
+            let mut candidates: Vec<_> = self
+                .router
+                .providers()
+                .filter(|p| self.router.check(p, &req).is_ok())
+                .collect();
+            candidates.sort_unstable();
+            self.prices
+                .cheapest(&req, candidates)
+                .map(|(p, _)| p.to_string())
+        } else {
+            self.router.default_provider_name().map(str::to_string)
+        };
+        self.build(req, provider)
+    }
+
+    /// Preview `req` sent through `provider`.
+    pub fn preview_via(&self, provider: &str, req: SendRequest<'_>) -> SendPreview {
+        let to = normalize_number(req.to);
+        self.build(SendRequest { to: &to, ..req }, Some(provider.to_string()))
+    }
+
+    fn build(&self, req: SendRequest<'_>, provider: Option<String>) -> SendPreview {
+        let rejection = self.check(&req, provider.as_deref()).err();
+        SendPreview {
+            to: req.to.to_string(),
+            country_code: calling_code(req.to).map(str::to_string),
+            estimated_cost: provider
+                .as_deref()
+                .and_then(|p| self.prices.estimate_cost(&req, p)),
+            analysis: analyze_with(req.text, &self.prices),
+            provider,
+            rejection,
+        }
+    }
+
+    fn check(&self, req: &SendRequest<'_>, provider: Option<&str>) -> Result<(), SmsError> {
+        self.destinations.check(req.to)?;
+        if let Some(countries) = &self.countries {
+            countries.check(req)?;
+        }
+        let provider =
+            provider.ok_or_else(|| SmsError::Invalid("no provider would accept this".into()))?;
+        self.router.check(provider, req)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockClient;
+    use sms_core::OwnedSendRequest;
+
+    fn router() -> SmsRouter {
+        SmsRouter::new()
+            .with("twilio", MockClient::new())
+            .with("plivo", MockClient::new())
+    }
+
+    #[test]
+    fn previews_default_route_with_cost() {
+        let prices = PriceTable::empty()
+            .with_price("twilio", 0.01)
+            .with_price("plivo", 0.005);
+        let previewer = Previewer::new(router()).with_prices(prices);
+        let req = OwnedSendRequest::new("+1 (555) 123-0000", "+15559870000", "hello");
+        let preview = previewer.preview(req.as_ref());
+
+        assert_eq!(preview.to, "+15551230000");
+        assert_eq!(preview.country_code.as_deref(), Some("1"));
+        assert_eq!(preview.provider.as_deref(), Some("twilio"));
+        assert_eq!(preview.analysis.segments, 1);
+        assert_eq!(preview.estimated_cost, Some(0.01));
+        assert!(preview.is_sendable());
+        assert_eq!(preview.to_string(), "1 segment (Gsm7) via twilio, ~$0.0100");
+    }
+
+    #[test]
+    fn least_cost_routing_picks_cheapest_provider() {
+        let prices = PriceTable::empty()
+            .with_price("twilio", 0.01)
+            .with_country_price("plivo", "44", 0.002)
+            .with_price("plivo", 0.02);
+        let previewer = Previewer::new(router())
+            .with_prices(prices)
+            .with_least_cost_routing();
+        let text = "x".repeat(200);
+        let req = OwnedSendRequest::new("+447700900000", "+15559870000", &text);
+        let preview = previewer.preview(req.as_ref());
+
+        assert_eq!(preview.provider.as_deref(), Some("plivo"));
+        assert_eq!(preview.analysis.segments, 2);
+        assert_eq!(preview.estimated_cost, Some(0.004));
+    }
+
+    #[test]
+    fn rejections_are_reported_not_returned() {
+        let previewer = Previewer::new(router())
+            .with_destinations(DestinationRules::new().block("+1900*"))
+            .with_countries(CountryAllowlist::only(["1"]));
+
+        let blocked = OwnedSendRequest::new("+19005550000", "+15559870000", "hi");
+        let preview = previewer.preview(blocked.as_ref());
+        assert!(matches!(preview.rejection, Some(SmsError::Suppressed(_))));
+        assert!(preview.to_string().starts_with("not sendable"));
+
+        let abroad = OwnedSendRequest::new("+447700900000", "+15559870000", "hi");
+        assert!(!previewer.preview(abroad.as_ref()).is_sendable());
+
+        let unknown = OwnedSendRequest::new("+15551230000", "+15559870000", "hi");
+        let preview = previewer.preview_via("carrier-pigeon", unknown.as_ref());
+        assert!(matches!(preview.rejection, Some(SmsError::Invalid(_))));
+    }
+}
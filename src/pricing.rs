@@ -0,0 +1,180 @@
+//! Per-provider, per-country message pricing.
+//!
+//! [`PriceTable`] holds a per-segment price for each provider, optionally
+//! overridden per destination country, and [`estimate_cost`] prices a
+//! request before it is sent — so routing can pick the cheapest provider
+//! and budgets can be enforced without waiting for delivery reports.
+//!
+//! ```rust,ignore
+//! use smskit::pricing::PriceTable;
+//!
+//! let prices = PriceTable::default()
+//!     .with_price("plivo", 0.0045)
+//!     .with_country_price("twilio", "44", 0.0463);
+//! let (provider, cost) = prices.cheapest(&req, ["plivo", "twilio"]).unwrap();
+//! router.send_via(provider, req).await?;
+//! ```
+
+use crate::analysis::analyze;
+use crate::screening::calling_code;
+use serde::{Deserialize, Serialize};
+use sms_core::SendRequest;
+use std::collections::BTreeMap;
+
+/// Per-segment prices used to estimate cost, keyed by provider name and,
+/// optionally, destination calling code.
+///
+/// The defaults are approximate US list prices in USD and only meant for
+/// warnings; other countries fall back to them unless overridden.  Load your
+/// negotiated rates with [`with_price`](PriceTable::with_price) and
+/// [`with_country_price`](PriceTable::with_country_price).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PriceTable {
+    per_segment: BTreeMap<String, f64>,
+    #[serde(default)]
+    per_country: BTreeMap<String, BTreeMap<String, f64>>,
+}
+
+impl Default for PriceTable {
+    fn default() -> Self {
+        Self::empty()
+            .with_price("aws-sns", 0.00645)
+            .with_price("plivo", 0.0050)
+            .with_price("twilio", 0.0079)
+    }
+}
+
+impl PriceTable {
+    /// A table with no prices.
+    pub fn empty() -> Self {
+        Self {
+            per_segment: BTreeMap::new(),
+            per_country: BTreeMap::new(),
+        }
+    }
+
+    /// Set the per-segment price for a provider.
+    pub fn with_price(mut self, provider: impl Into<String>, per_segment: f64) -> Self {
+        self.per_segment.insert(provider.into(), per_segment);
+        self
+    }
+
+    /// Set the per-segment price for a provider when sending to the country
+    /// with calling code `code` (e.g. `"44"`).
+    pub fn with_country_price(
+        mut self,
+        provider: impl Into<String>,
+        code: impl Into<String>,
+        per_segment: f64,
+    ) -> Self {
+        self.per_country
+            .entry(provider.into())
+            .or_default()
+            .insert(code.into(), per_segment);
+        self
+    }
+
+    /// The default per-segment price for a provider, if known.
+    pub fn price(&self, provider: &str) -> Option<f64> {
+        self.per_segment.get(provider).copied()
+    }
+
+    /// The per-segment price for a provider sending to `code`, falling back
+    /// to its default price.
+    pub fn country_price(&self, provider: &str, code: &str) -> Option<f64> {
+        self.per_country
+            .get(provider)
+            .and_then(|countries| countries.get(code))
+            .copied()
+            .or_else(|| self.price(provider))
+    }
+
+    /// Providers with a default price and that price, in name order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, f64)> {
+        self.per_segment
+            .iter()
+            .map(|(p, price)| (p.as_str(), *price))
+    }
+
+    /// Estimated cost of sending `req` through `provider`: segment count
+    /// times the price for the destination country.
+    ///
+    /// Returns `None` if the provider has no price.
+    pub fn estimate_cost(&self, req: &SendRequest<'_>, provider: &str) -> Option<f64> {
+        let price = match calling_code(req.to) {
+            Some(code) => self.country_price(provider, code),
+            None => self.price(provider),
+        }?;
+        Some(price * analyze(req.text).segments as f64)
+    }
+
+    /// The cheapest of `providers` for `req`, with its estimated cost.
+    ///
+    /// Providers without a price are skipped; ties go to the earlier one.
+    pub fn cheapest<'p>(
+        &self,
+        req: &SendRequest<'_>,
+        providers: impl IntoIterator<Item = &'p str>,
+    ) -> Option<(&'p str, f64)> {
+        providers
+            .into_iter()
+            .filter_map(|p| Some((p, self.estimate_cost(req, p)?)))
+            .fold(None, |best, (p, cost)| match best {
+                Some((_, best_cost)) if best_cost <= cost => best,
+                _ => Some((p, cost)),
+            })
+    }
+}
+
+/// Estimate the cost of sending `req` through `provider` using the default
+/// [`PriceTable`].
+pub fn estimate_cost(req: &SendRequest<'_>, provider: &str) -> Option<f64> {
+    PriceTable::default().estimate_cost(req, provider)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn req<'a>(to: &'a str, text: &'a str) -> SendRequest<'a> {
+        SendRequest {
+            to,
+            from: "+15550000",
+            text,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn country_price_overrides_default() {
+        let prices = PriceTable::empty()
+            .with_price("acme", 0.01)
+            .with_country_price("acme", "44", 0.05);
+        let long = "a".repeat(200);
+        let us = prices.estimate_cost(&req("+14155551234", &long), "acme");
+        let uk = prices.estimate_cost(&req("+447700900000", &long), "acme");
+        assert!((us.unwrap() - 0.02).abs() < 1e-9);
+        assert!((uk.unwrap() - 0.10).abs() < 1e-9);
+        assert!(prices.estimate_cost(&req("+1", "hi"), "nope").is_none());
+    }
+
+    #[test]
+    fn cheapest_picks_lowest_estimate() {
+        let prices = PriceTable::empty()
+            .with_price("a", 0.01)
+            .with_price("b", 0.02)
+            .with_country_price("b", "44", 0.005);
+        let to_us = req("+14155551234", "hi");
+        let to_uk = req("+447700900000", "hi");
+        assert_eq!(prices.cheapest(&to_us, ["a", "b", "c"]).unwrap().0, "a");
+        assert_eq!(prices.cheapest(&to_uk, ["a", "b", "c"]).unwrap().0, "b");
+        assert!(prices.cheapest(&to_us, ["c"]).is_none());
+    }
+
+    #[test]
+    fn deserializes_without_country_prices() {
+        let prices: PriceTable =
+            serde_json::from_str(r#"{"per_segment": {"plivo": 0.004}}"#).unwrap();
+        assert_eq!(prices.country_price("plivo", "44"), Some(0.004));
+    }
+}
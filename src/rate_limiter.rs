@@ -1,10 +1,15 @@
+use crate::clock::{Clock, SystemClock};
+use crate::runtime::sleep;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
+use sms_core::{Channel, SendRequest, SendResponse, SmsClient, SmsError};
+use sms_web_generic::WebhookRateLimiter;
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::fmt::Debug;
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
-use tokio::sync::Mutex;
-use tokio::time::sleep;
 use tracing::{debug, warn};
 
 /// Configuration for rate limiting.
@@ -18,6 +23,13 @@ pub struct RateLimitConfig {
     pub enabled: bool,
     /// Per-provider rate limits (overrides global settings).
     pub per_provider: HashMap<String, ProviderRateLimit>,
+    /// Per-tenant caps and quotas, applied on top of the provider limits.
+    #[serde(default)]
+    pub per_tenant: HashMap<String, TenantRateLimit>,
+    /// Number of reverse proxies in front of the webhook server whose
+    /// `X-Forwarded-For` entries are trusted; see [`DefaultKeyGenerator`].
+    #[serde(default)]
+    pub trusted_proxy_hops: usize,
 }
 
 /// Per-provider rate limit override.
@@ -27,6 +39,25 @@ pub struct ProviderRateLimit {
     pub window_seconds: u64,
 }
 
+/// Per-tenant rate cap and message quota.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TenantRateLimit {
+    /// Maximum number of requests per window for the whole tenant.
+    pub max_requests: u32,
+    /// Window duration in seconds.
+    pub window_seconds: u64,
+    /// Total messages allowed per quota period (`None` = unlimited).
+    #[serde(default)]
+    pub quota: Option<u64>,
+    /// Quota period in seconds (default: 30 days).
+    #[serde(default = "default_quota_period")]
+    pub quota_period_seconds: u64,
+}
+
+fn default_quota_period() -> u64 {
+    30 * 24 * 60 * 60
+}
+
 impl Default for RateLimitConfig {
     fn default() -> Self {
         Self {
@@ -34,10 +65,43 @@ impl Default for RateLimitConfig {
             window_seconds: 60,
             enabled: true,
             per_provider: HashMap::new(),
+            per_tenant: HashMap::new(),
+            trusted_proxy_hops: 0,
         }
     }
 }
 
+/// Point-in-time view of a [`RateLimiter`], from [`RateLimiter::status`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RateLimiterStatus {
+    /// Whether limiting is enabled at all.
+    pub enabled: bool,
+    /// Number of keys with a token bucket.
+    pub tracked_keys: usize,
+    /// Keys that are currently out of tokens, sorted.
+    pub exhausted_keys: Vec<String>,
+    /// Quota used per tenant in the current period.
+    pub tenant_usage: std::collections::BTreeMap<String, u64>,
+    /// Every token bucket, sorted by key.
+    pub buckets: Vec<BucketStatus>,
+    /// Checks rejected since the limiter was created, including tenant
+    /// quota rejections and buckets since cleaned up.
+    pub rejections: u64,
+}
+
+/// One token bucket in a [`RateLimiterStatus`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BucketStatus {
+    /// The rate-limit key, e.g. `"plivo:1.2.3.4"`.
+    pub key: String,
+    /// Tokens left right now.
+    pub remaining: u32,
+    /// Tokens the bucket holds when full.
+    pub capacity: u32,
+    /// Checks this bucket has rejected.
+    pub rejections: u64,
+}
+
 /// Rate limiter implementation using a token-bucket algorithm.
 ///
 /// Each unique key (typically `"provider:identifier"`) gets its own bucket.
@@ -46,6 +110,16 @@ impl Default for RateLimitConfig {
 pub struct RateLimiter {
     config: RateLimitConfig,
     buckets: Arc<Mutex<HashMap<String, TokenBucket>>>,
+    quotas: Arc<Mutex<HashMap<String, QuotaUsage>>>,
+    rejections: Arc<AtomicU64>,
+    clock: Arc<dyn Clock>,
+    key_generator: Arc<dyn KeyGenerator>,
+}
+
+#[derive(Debug)]
+struct QuotaUsage {
+    used: u64,
+    period_start: Instant,
 }
 
 #[derive(Debug)]
@@ -54,21 +128,23 @@ struct TokenBucket {
     last_refill: Instant,
     max_tokens: u32,
     refill_rate: f64, // tokens per second
+    rejections: u64,
 }
 
 impl TokenBucket {
-    fn new(max_tokens: u32, window_seconds: u64) -> Self {
+    fn new(max_tokens: u32, window_seconds: u64, now: Instant) -> Self {
         let refill_rate = max_tokens as f64 / window_seconds as f64;
         Self {
             tokens: max_tokens,
-            last_refill: Instant::now(),
+            last_refill: now,
             max_tokens,
             refill_rate,
+            rejections: 0,
         }
     }
 
-    fn try_consume(&mut self) -> bool {
-        self.refill();
+    fn try_consume(&mut self, now: Instant) -> bool {
+        self.refill(now);
 
         if self.tokens > 0 {
             self.tokens -= 1;
@@ -78,32 +154,70 @@ impl TokenBucket {
         }
     }
 
-    fn refill(&mut self) {
-        let now = Instant::now();
+    fn refill(&mut self, now: Instant) {
+        (self.tokens, self.last_refill) = self.refilled(now);
+    }
+
+    /// Tokens and refill time as of `now`, without updating the bucket.
+    ///
+    /// Only the time that was turned into whole tokens is consumed, so
+    /// frequent checks don't discard fractional progress toward the next
+    /// token.
+    fn refilled(&self, now: Instant) -> (u32, Instant) {
         let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        let tokens_to_add = (elapsed * self.refill_rate).floor() as u32;
+        if tokens_to_add == 0 {
+            return (self.tokens, self.last_refill);
+        }
 
-        if elapsed > 0.0 {
-            let tokens_to_add = (elapsed * self.refill_rate).floor() as u32;
-            self.tokens = (self.tokens + tokens_to_add).min(self.max_tokens);
-            self.last_refill = now;
+        let tokens = self.tokens.saturating_add(tokens_to_add);
+        if tokens >= self.max_tokens {
+            return (self.max_tokens, now);
         }
+        let used = Duration::from_secs_f64(tokens_to_add as f64 / self.refill_rate);
+        (tokens, (self.last_refill + used).min(now))
     }
 }
 
 impl RateLimiter {
     /// Create a new rate limiter with the given configuration.
     pub fn new(config: RateLimitConfig) -> Self {
+        let key_generator = Arc::new(DefaultKeyGenerator::new(config.trusted_proxy_hops));
         Self {
             config,
             buckets: Arc::new(Mutex::new(HashMap::new())),
+            quotas: Arc::new(Mutex::new(HashMap::new())),
+            rejections: Arc::new(AtomicU64::new(0)),
+            clock: Arc::new(SystemClock),
+            key_generator,
         }
     }
 
+    /// Key webhook calls with `key_generator` instead of a
+    /// [`DefaultKeyGenerator`] built from
+    /// [`trusted_proxy_hops`](RateLimitConfig::trusted_proxy_hops).
+    pub fn with_key_generator(mut self, key_generator: impl KeyGenerator + 'static) -> Self {
+        self.key_generator = Arc::new(key_generator);
+        self
+    }
+
+    /// Measure time with `clock` instead of the system clock.
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
     /// Check if a request should be rate limited.
     ///
     /// Returns [`RateLimitResult::Allowed`] if the request can proceed, or
     /// [`RateLimitResult::Limited`] with a suggested retry-after duration.
     pub async fn check_rate_limit(&self, key: &str) -> RateLimitResult {
+        self.check(key)
+    }
+
+    /// Synchronous form of [`check_rate_limit`](Self::check_rate_limit), for
+    /// callers such as the webhook pipeline that cannot `.await`.
+    pub fn check(&self, key: &str) -> RateLimitResult {
         if !self.config.enabled {
             return RateLimitResult::Allowed;
         }
@@ -115,16 +229,129 @@ impl RateLimiter {
                 (self.config.max_requests, self.config.window_seconds)
             };
 
-        let mut buckets = self.buckets.lock().await;
+        self.consume(key, max_requests, window_seconds)
+    }
+
+    /// Check a tenant's overall cap and consume one unit of its quota.
+    ///
+    /// Tenants without an entry in [`RateLimitConfig::per_tenant`] are always
+    /// allowed.  When the quota is used up the result is
+    /// [`RateLimitResult::Limited`] until the current quota period ends.
+    pub fn check_tenant(&self, tenant: &str) -> RateLimitResult {
+        self.check_tenant_then(tenant, || RateLimitResult::Allowed)
+    }
+
+    /// Check a tenant's cap and quota, then `next`; the quota is only
+    /// charged once both pass, so a call rejected by `next` stays free.
+    fn check_tenant_then(
+        &self,
+        tenant: &str,
+        next: impl FnOnce() -> RateLimitResult,
+    ) -> RateLimitResult {
+        if !self.config.enabled {
+            return RateLimitResult::Allowed;
+        }
+        let Some(limit) = self.config.per_tenant.get(tenant) else {
+            return next();
+        };
+
+        let period = Duration::from_secs(limit.quota_period_seconds);
+        let now = self.clock.now();
+        let mut quotas = self.quotas.lock().expect("rate limiter poisoned");
+        let usage = quotas.entry(tenant.to_string()).or_insert_with(|| QuotaUsage {
+            used: 0,
+            period_start: now,
+        });
+        if now.duration_since(usage.period_start) >= period {
+            usage.used = 0;
+            usage.period_start = now;
+        }
+        if limit.quota.is_some_and(|quota| usage.used >= quota) {
+            warn!("Quota exhausted for tenant: {}", tenant);
+            self.rejections.fetch_add(1, Ordering::Relaxed);
+            let retry_after = period.saturating_sub(now.duration_since(usage.period_start));
+            return RateLimitResult::Limited { retry_after };
+        }
+
+        let result = match self.consume(
+            &format!("tenant/{}", tenant),
+            limit.max_requests,
+            limit.window_seconds,
+        ) {
+            RateLimitResult::Allowed => next(),
+            limited => limited,
+        };
+        if matches!(result, RateLimitResult::Allowed) {
+            usage.used += 1;
+        }
+        result
+    }
+
+    /// Snapshot of the limiter's state, e.g. for an admin endpoint.
+    pub fn status(&self) -> RateLimiterStatus {
+        let now = self.clock.now();
+        let buckets = self.buckets.lock().expect("rate limiter poisoned");
+        let mut exhausted_keys = Vec::new();
+        let mut bucket_status = Vec::with_capacity(buckets.len());
+        for (key, bucket) in buckets.iter() {
+            let (remaining, _) = bucket.refilled(now);
+            if remaining == 0 {
+                exhausted_keys.push(key.clone());
+            }
+            bucket_status.push(BucketStatus {
+                key: key.clone(),
+                remaining,
+                capacity: bucket.max_tokens,
+                rejections: bucket.rejections,
+            });
+        }
+        exhausted_keys.sort();
+        bucket_status.sort_by(|a, b| a.key.cmp(&b.key));
+        let tracked_keys = buckets.len();
+        drop(buckets);
+
+        let tenant_usage = self
+            .quotas
+            .lock()
+            .expect("rate limiter poisoned")
+            .iter()
+            .map(|(tenant, usage)| (tenant.clone(), usage.used))
+            .collect();
+
+        RateLimiterStatus {
+            enabled: self.config.enabled,
+            tracked_keys,
+            exhausted_keys,
+            tenant_usage,
+            buckets: bucket_status,
+            rejections: self.rejections.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Messages counted against a tenant's quota in the current period.
+    pub fn tenant_usage(&self, tenant: &str) -> u64 {
+        self.quotas
+            .lock()
+            .expect("rate limiter poisoned")
+            .get(tenant)
+            .map(|u| u.used)
+            .unwrap_or(0)
+    }
+
+    fn consume(&self, key: &str, max_requests: u32, window_seconds: u64) -> RateLimitResult {
+        let now = self.clock.now();
+        let mut buckets = self.buckets.lock().expect("rate limiter poisoned");
         let bucket = buckets
             .entry(key.to_string())
-            .or_insert_with(|| TokenBucket::new(max_requests, window_seconds));
+            .or_insert_with(|| TokenBucket::new(max_requests, window_seconds, now));
 
-        if bucket.try_consume() {
+        if bucket.try_consume(now) {
             debug!("Rate limit check passed for key: {}", key);
             RateLimitResult::Allowed
         } else {
             warn!("Rate limit exceeded for key: {}", key);
+            bucket.rejections += 1;
+            self.rejections.fetch_add(1, Ordering::Relaxed);
             let retry_after = self.calculate_retry_after(bucket);
             RateLimitResult::Limited { retry_after }
         }
@@ -157,8 +384,8 @@ impl RateLimiter {
         loop {
             sleep(cleanup_interval).await;
 
-            let mut buckets = self.buckets.lock().await;
-            let now = Instant::now();
+            let mut buckets = self.buckets.lock().expect("rate limiter poisoned");
+            let now = self.clock.now();
 
             buckets.retain(|key, bucket| {
                 let idle_time = now.duration_since(bucket.last_refill);
@@ -197,35 +424,196 @@ pub trait RateLimitMiddleware: Send + Sync {
 }
 
 /// Generates rate-limit keys from request context.
-pub trait KeyGenerator {
+pub trait KeyGenerator: Send + Sync + Debug {
     /// Build a key from provider name and identifier (e.g. phone number, IP).
     fn generate_key(&self, provider: &str, identifier: &str) -> String {
         format!("{}:{}", provider, identifier)
     }
 
-    /// Extract the client IP from standard proxy headers.
+    /// Extract the client IP from the request headers, or `None` if they
+    /// don't identify the client reliably.
+    fn extract_client_ip(&self, headers: &sms_core::Headers) -> Option<String>;
+}
+
+/// Default key generator implementation.
+///
+/// Every proxy appends the address it received the request from to
+/// `X-Forwarded-For`, so only the entries added by proxies you run can be
+/// trusted; anything to their left was supplied by the client.  With `n`
+/// trusted hops the client IP is the `n`th entry from the right.  Behind a
+/// proxy that sets `CF-Connecting-IP` or `X-Real-IP` instead, those are
+/// used when `X-Forwarded-For` is absent.
+///
+/// With zero hops (the default) no header is trusted and
+/// [`extract_client_ip`](KeyGenerator::extract_client_ip) returns `None`;
+/// adapters that know the TCP peer address pass it through
+/// [`WebhookRateLimiter::check_client_webhook`] instead.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultKeyGenerator {
+    trusted_proxy_hops: usize,
+}
+
+impl DefaultKeyGenerator {
+    /// Trust the `X-Forwarded-For` entries added by `trusted_proxy_hops`
+    /// proxies.
+    pub fn new(trusted_proxy_hops: usize) -> Self {
+        Self { trusted_proxy_hops }
+    }
+}
+
+impl KeyGenerator for DefaultKeyGenerator {
     fn extract_client_ip(&self, headers: &sms_core::Headers) -> Option<String> {
-        for (name, value) in headers {
-            match name.to_lowercase().as_str() {
-                "x-forwarded-for" => return Some(value.split(',').next()?.trim().to_string()),
-                "x-real-ip" => return Some(value.clone()),
-                "cf-connecting-ip" => return Some(value.clone()),
-                _ => continue,
-            }
+        if self.trusted_proxy_hops == 0 {
+            return None;
         }
-        None
+        let header = |wanted: &str| {
+            headers
+                .iter()
+                .filter(|(name, _)| name.eq_ignore_ascii_case(wanted))
+                .map(|(_, value)| value.as_str())
+                .collect::<Vec<_>>()
+        };
+        let forwarded: Vec<&str> = header("x-forwarded-for")
+            .into_iter()
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .collect();
+        if forwarded.is_empty() {
+            return header("cf-connecting-ip")
+                .into_iter()
+                .chain(header("x-real-ip"))
+                .next()
+                .map(|value| value.trim().to_string());
+        }
+        // Fewer entries than trusted hops means the request bypassed a
+        // proxy, so none of them can be relied on.
+        let index = forwarded.len().checked_sub(self.trusted_proxy_hops)?;
+        Some(forwarded[index].to_string())
     }
 }
 
-/// Default key generator implementation.
-pub struct DefaultKeyGenerator;
+/// Throttles webhook calls per provider and client IP.
+///
+/// The key is `"{provider}:{client_ip}"`, built by the limiter's
+/// [`KeyGenerator`], so per-provider overrides in
+/// [`RateLimitConfig::per_provider`] apply.  Requests whose client IP can't
+/// be determined share a single `"{provider}:unknown"` bucket.
+impl WebhookRateLimiter for RateLimiter {
+    fn check_webhook(&self, provider: &str, headers: &sms_core::Headers) -> Result<(), Duration> {
+        into_result(self.check_webhook_key(provider, headers))
+    }
+
+    fn check_client_webhook(
+        &self,
+        tenant: Option<&str>,
+        provider: &str,
+        client_ip: IpAddr,
+    ) -> Result<(), Duration> {
+        let key = self
+            .key_generator
+            .generate_key(provider, &client_ip.to_string());
+        into_result(match tenant {
+            Some(tenant) => self.check_tenant_then(tenant, || self.check(&key)),
+            None => self.check(&key),
+        })
+    }
+
+    /// Applies the tenant's cap and quota from [`RateLimitConfig::per_tenant`]
+    /// on top of the usual per-provider, per-IP check.
+    fn check_tenant_webhook(
+        &self,
+        tenant: &str,
+        provider: &str,
+        headers: &sms_core::Headers,
+    ) -> Result<(), Duration> {
+        into_result(self.check_tenant_then(tenant, || self.check_webhook_key(provider, headers)))
+    }
+}
+
+impl RateLimiter {
+    fn check_webhook_key(&self, provider: &str, headers: &sms_core::Headers) -> RateLimitResult {
+        let ip = self
+            .key_generator
+            .extract_client_ip(headers)
+            .unwrap_or_else(|| "unknown".to_string());
+        self.check(&self.key_generator.generate_key(provider, &ip))
+    }
+}
+
+fn into_result(result: RateLimitResult) -> Result<(), Duration> {
+    match result {
+        RateLimitResult::Allowed => Ok(()),
+        RateLimitResult::Limited { retry_after } => Err(retry_after),
+    }
+}
+
+/// Wraps an [`SmsClient`] and applies a tenant's cap and quota from
+/// [`RateLimitConfig::per_tenant`] to every send.
+///
+/// A send over the cap or quota fails with [`SmsError::RateLimited`] before
+/// reaching the provider.  With [`with_provider`](Self::with_provider), the
+/// provider's own limit from [`RateLimitConfig::per_provider`] is applied
+/// as well; the quota is only charged when every check passes.
+pub struct RateLimitedClient<C> {
+    inner: C,
+    limiter: Arc<RateLimiter>,
+    tenant: String,
+    provider: Option<String>,
+}
+
+impl<C: SmsClient> RateLimitedClient<C> {
+    /// Send through `inner` on behalf of `tenant`.
+    pub fn new(inner: C, limiter: Arc<RateLimiter>, tenant: impl Into<String>) -> Self {
+        Self {
+            inner,
+            limiter,
+            tenant: tenant.into(),
+            provider: None,
+        }
+    }
+
+    /// Also throttle sends through the `provider` bucket.
+    pub fn with_provider(mut self, provider: impl Into<String>) -> Self {
+        self.provider = Some(provider.into());
+        self
+    }
 
-impl KeyGenerator for DefaultKeyGenerator {}
+    /// Borrow the wrapped client.
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+}
+
+impl<C: SmsClient> SmsClient for RateLimitedClient<C> {
+    async fn send(&self, req: SendRequest<'_>) -> Result<SendResponse, SmsError> {
+        let result = self.limiter.check_tenant_then(&self.tenant, || match &self.provider {
+            Some(provider) => self.limiter.check(&format!("{}:send/{}", provider, self.tenant)),
+            None => RateLimitResult::Allowed,
+        });
+        if let RateLimitResult::Limited { retry_after } = result {
+            debug!("Send for tenant {} rate limited", self.tenant);
+            return Err(SmsError::RateLimited {
+                retry_after: Some(retry_after),
+            });
+        }
+        self.inner.send(req).await
+    }
+
+    fn supports(&self, channel: Channel) -> bool {
+        self.inner.supports(channel)
+    }
+
+    fn max_text_len(&self) -> Option<usize> {
+        self.inner.max_text_len()
+    }
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use tokio::time::{sleep, Duration};
+    use crate::clock::ManualClock;
+    use crate::simulation::SimulatedClient;
 
     #[tokio::test]
     async fn test_rate_limiter_allows_requests_within_limit() {
@@ -234,6 +622,8 @@ mod tests {
             window_seconds: 1,
             enabled: true,
             per_provider: HashMap::new(),
+            per_tenant: HashMap::new(),
+            trusted_proxy_hops: 0,
         };
 
         let limiter = RateLimiter::new(config);
@@ -261,9 +651,12 @@ mod tests {
             window_seconds: 1,
             enabled: true,
             per_provider: HashMap::new(),
+            per_tenant: HashMap::new(),
+            trusted_proxy_hops: 0,
         };
 
-        let limiter = RateLimiter::new(config);
+        let clock = ManualClock::new();
+        let limiter = RateLimiter::new(config).with_clock(clock.clone());
 
         match limiter.check_rate_limit("test-key").await {
             RateLimitResult::Allowed => {}
@@ -275,7 +668,7 @@ mod tests {
             RateLimitResult::Allowed => panic!("Second request should be limited"),
         }
 
-        sleep(Duration::from_millis(1100)).await;
+        clock.advance(Duration::from_millis(1100));
 
         match limiter.check_rate_limit("test-key").await {
             RateLimitResult::Allowed => {}
@@ -283,6 +676,76 @@ mod tests {
         }
     }
 
+    #[test]
+    fn frequent_checks_keep_partial_refill_progress() {
+        let clock = ManualClock::new();
+        let limiter = RateLimiter::new(RateLimitConfig {
+            max_requests: 1,
+            window_seconds: 1,
+            ..Default::default()
+        })
+        .with_clock(clock.clone());
+        assert!(matches!(limiter.check("plivo:a"), RateLimitResult::Allowed));
+
+        // Neither a status read nor a rejected check may reset the clock
+        // on the 600ms already earned.
+        clock.advance(Duration::from_millis(600));
+        assert_eq!(limiter.status().exhausted_keys, vec!["plivo:a"]);
+        assert!(matches!(limiter.check("plivo:a"), RateLimitResult::Limited { .. }));
+        clock.advance(Duration::from_millis(600));
+        assert!(matches!(limiter.check("plivo:a"), RateLimitResult::Allowed));
+    }
+
+    #[test]
+    fn status_reports_exhausted_keys() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            max_requests: 2,
+            window_seconds: 60,
+            ..Default::default()
+        });
+        limiter.check("plivo:1.2.3.4");
+        limiter.check("plivo:5.6.7.8");
+        limiter.check("plivo:5.6.7.8");
+
+        let status = limiter.status();
+        assert!(status.enabled);
+        assert_eq!(status.tracked_keys, 2);
+        assert_eq!(status.exhausted_keys, vec!["plivo:5.6.7.8"]);
+    }
+
+    #[test]
+    fn status_reports_buckets_and_rejections() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            max_requests: 2,
+            window_seconds: 60,
+            ..Default::default()
+        });
+        limiter.check("twilio:a");
+        for _ in 0..4 {
+            limiter.check("plivo:b");
+        }
+
+        let status = limiter.status();
+        assert_eq!(status.rejections, 2);
+        assert_eq!(
+            status.buckets,
+            vec![
+                BucketStatus {
+                    key: "plivo:b".into(),
+                    remaining: 0,
+                    capacity: 2,
+                    rejections: 2,
+                },
+                BucketStatus {
+                    key: "twilio:a".into(),
+                    remaining: 1,
+                    capacity: 2,
+                    rejections: 0,
+                },
+            ]
+        );
+    }
+
     #[tokio::test]
     async fn test_disabled_rate_limiter() {
         let config = RateLimitConfig {
@@ -290,6 +753,8 @@ mod tests {
             window_seconds: 1,
             enabled: false,
             per_provider: HashMap::new(),
+            per_tenant: HashMap::new(),
+            trusted_proxy_hops: 0,
         };
 
         let limiter = RateLimiter::new(config);
@@ -320,6 +785,8 @@ mod tests {
             window_seconds: 60,
             enabled: true,
             per_provider,
+            per_tenant: HashMap::new(),
+            trusted_proxy_hops: 0,
         };
 
         let limiter = RateLimiter::new(config);
@@ -348,30 +815,214 @@ mod tests {
         }
     }
 
+    #[test]
+    fn webhook_rate_limiting_is_per_client_ip() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            max_requests: 1,
+            window_seconds: 60,
+            enabled: true,
+            per_provider: HashMap::new(),
+            per_tenant: HashMap::new(),
+            trusted_proxy_hops: 1,
+        });
+        let a = vec![("X-Forwarded-For".to_string(), "1.1.1.1".to_string())];
+        let b = vec![("X-Forwarded-For".to_string(), "2.2.2.2".to_string())];
+        // A client can't dodge the limit by prepending its own entries.
+        let spoofed = vec![("X-Forwarded-For".to_string(), "9.9.9.9, 1.1.1.1".to_string())];
+
+        assert!(limiter.check_webhook("plivo", &a).is_ok());
+        assert!(limiter.check_webhook("plivo", &a).is_err());
+        assert!(limiter.check_webhook("plivo", &spoofed).is_err());
+        assert!(limiter.check_webhook("plivo", &b).is_ok());
+    }
+
+    #[test]
+    fn forwarded_headers_ignored_without_trusted_proxies() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            max_requests: 1,
+            ..Default::default()
+        });
+        let a = vec![("X-Forwarded-For".to_string(), "1.1.1.1".to_string())];
+        let b = vec![("X-Forwarded-For".to_string(), "2.2.2.2".to_string())];
+
+        assert!(limiter.check_webhook("plivo", &a).is_ok());
+        assert!(limiter.check_webhook("plivo", &b).is_err());
+
+        let peer: IpAddr = "2.2.2.2".parse().unwrap();
+        assert!(limiter.check_client_webhook(None, "plivo", peer).is_ok());
+        assert!(limiter.check_client_webhook(None, "plivo", peer).is_err());
+    }
+
+    #[derive(Debug)]
+    struct ByUserAgent;
+
+    impl KeyGenerator for ByUserAgent {
+        fn extract_client_ip(&self, headers: &sms_core::Headers) -> Option<String> {
+            headers
+                .iter()
+                .find(|(name, _)| name == "user-agent")
+                .map(|(_, value)| value.clone())
+        }
+    }
+
+    #[test]
+    fn webhook_keys_use_configured_key_generator() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            max_requests: 1,
+            ..Default::default()
+        })
+        .with_key_generator(ByUserAgent);
+        let a = vec![("user-agent".to_string(), "a".to_string())];
+        let b = vec![("user-agent".to_string(), "b".to_string())];
+
+        assert!(limiter.check_webhook("plivo", &a).is_ok());
+        assert!(limiter.check_webhook("plivo", &b).is_ok());
+        assert!(limiter.check_webhook("plivo", &a).is_err());
+        assert_eq!(limiter.status().exhausted_keys, vec!["plivo:a", "plivo:b"]);
+    }
+
+    fn tenant_config(max_requests: u32, quota: Option<u64>) -> RateLimitConfig {
+        let mut per_tenant = HashMap::new();
+        per_tenant.insert(
+            "acme".to_string(),
+            TenantRateLimit {
+                max_requests,
+                window_seconds: 60,
+                quota,
+                quota_period_seconds: default_quota_period(),
+            },
+        );
+        RateLimitConfig {
+            per_tenant,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn tenant_cap_applies_only_to_configured_tenant() {
+        let limiter = RateLimiter::new(tenant_config(2, None));
+        assert!(matches!(limiter.check_tenant("acme"), RateLimitResult::Allowed));
+        assert!(matches!(limiter.check_tenant("acme"), RateLimitResult::Allowed));
+        assert!(matches!(
+            limiter.check_tenant("acme"),
+            RateLimitResult::Limited { .. }
+        ));
+        for _ in 0..10 {
+            assert!(matches!(limiter.check_tenant("globex"), RateLimitResult::Allowed));
+        }
+    }
+
+    #[test]
+    fn tenant_quota_is_enforced() {
+        let limiter = RateLimiter::new(tenant_config(100, Some(3)));
+        for _ in 0..3 {
+            assert!(matches!(limiter.check_tenant("acme"), RateLimitResult::Allowed));
+        }
+        assert_eq!(limiter.tenant_usage("acme"), 3);
+        match limiter.check_tenant("acme") {
+            RateLimitResult::Limited { retry_after } => {
+                assert!(retry_after > Duration::from_secs(60 * 60))
+            }
+            RateLimitResult::Allowed => panic!("quota should be exhausted"),
+        }
+        assert_eq!(limiter.tenant_usage("acme"), 3);
+    }
+
+    #[test]
+    fn tenant_quota_resets_after_period() {
+        let clock = ManualClock::new();
+        let limiter = RateLimiter::new(tenant_config(100, Some(1))).with_clock(clock.clone());
+        assert!(matches!(limiter.check_tenant("acme"), RateLimitResult::Allowed));
+        assert!(matches!(
+            limiter.check_tenant("acme"),
+            RateLimitResult::Limited { .. }
+        ));
+
+        clock.advance(Duration::from_secs(default_quota_period()));
+        assert!(matches!(limiter.check_tenant("acme"), RateLimitResult::Allowed));
+        assert_eq!(limiter.tenant_usage("acme"), 1);
+    }
+
+    #[test]
+    fn tenant_webhook_checks_tenant_cap() {
+        let limiter = RateLimiter::new(tenant_config(1, None));
+        assert!(limiter.check_tenant_webhook("acme", "plivo", &Vec::new()).is_ok());
+        assert!(limiter.check_tenant_webhook("acme", "twilio", &Vec::new()).is_err());
+    }
+
+    #[test]
+    fn quota_is_not_charged_when_ip_check_rejects() {
+        let mut config = tenant_config(100, Some(5));
+        config.max_requests = 1;
+        let limiter = RateLimiter::new(config);
+        let ip: IpAddr = "1.2.3.4".parse().unwrap();
+        assert!(limiter.check_client_webhook(Some("acme"), "plivo", ip).is_ok());
+        assert!(limiter.check_client_webhook(Some("acme"), "plivo", ip).is_err());
+        assert_eq!(limiter.tenant_usage("acme"), 1);
+    }
+
+    #[tokio::test]
+    async fn client_enforces_tenant_quota_on_send() {
+        let limiter = Arc::new(RateLimiter::new(tenant_config(100, Some(2))));
+        let client =
+            RateLimitedClient::new(SimulatedClient::new(), limiter.clone(), "acme");
+        let req = SendRequest {
+            to: "+15551234567",
+            from: "+15550000000",
+            text: "hi",
+            ..Default::default()
+        };
+        assert!(client.send(req.clone()).await.is_ok());
+        assert!(client.send(req.clone()).await.is_ok());
+        let err = client.send(req).await.unwrap_err();
+        assert!(matches!(err, SmsError::RateLimited { retry_after: Some(_) }));
+        assert_eq!(limiter.tenant_usage("acme"), 2);
+    }
+
     #[test]
     fn default_key_generator() {
-        let keygen = DefaultKeyGenerator;
+        let keygen = DefaultKeyGenerator::default();
         assert_eq!(keygen.generate_key("plivo", "+1234"), "plivo:+1234");
     }
 
     #[test]
     fn extract_client_ip_forwarded_for() {
-        let keygen = DefaultKeyGenerator;
-        let headers = vec![("X-Forwarded-For".to_string(), "1.2.3.4, 5.6.7.8".to_string())];
+        let headers = vec![(
+            "X-Forwarded-For".to_string(),
+            "6.6.6.6, 1.2.3.4, 5.6.7.8".to_string(),
+        )];
+        let keygen = DefaultKeyGenerator::new(1);
+        assert_eq!(keygen.extract_client_ip(&headers), Some("5.6.7.8".to_string()));
+        let keygen = DefaultKeyGenerator::new(2);
+        assert_eq!(keygen.extract_client_ip(&headers), Some("1.2.3.4".to_string()));
+        let keygen = DefaultKeyGenerator::new(4);
+        assert_eq!(keygen.extract_client_ip(&headers), None);
+    }
+
+    #[test]
+    fn extract_client_ip_joins_repeated_headers() {
+        let keygen = DefaultKeyGenerator::new(2);
+        let headers = vec![
+            ("X-Forwarded-For".to_string(), "1.2.3.4".to_string()),
+            ("x-forwarded-for".to_string(), "5.6.7.8".to_string()),
+        ];
         assert_eq!(keygen.extract_client_ip(&headers), Some("1.2.3.4".to_string()));
     }
 
     #[test]
     fn extract_client_ip_real_ip() {
-        let keygen = DefaultKeyGenerator;
+        let keygen = DefaultKeyGenerator::new(1);
         let headers = vec![("X-Real-IP".to_string(), "10.0.0.1".to_string())];
         assert_eq!(keygen.extract_client_ip(&headers), Some("10.0.0.1".to_string()));
     }
 
     #[test]
     fn extract_client_ip_none() {
-        let keygen = DefaultKeyGenerator;
+        let keygen = DefaultKeyGenerator::new(1);
         let headers = vec![("Content-Type".to_string(), "text/html".to_string())];
         assert_eq!(keygen.extract_client_ip(&headers), None);
+
+        let untrusted = vec![("X-Forwarded-For".to_string(), "1.2.3.4".to_string())];
+        assert_eq!(DefaultKeyGenerator::default().extract_client_ip(&untrusted), None);
     }
 }
@@ -0,0 +1,153 @@
+//! Async runtime shims.
+//!
+//! The rate limiter, outbox, pollers and other background components sleep,
+//! time out and spawn through this module instead of calling tokio directly.
+//! By default it uses tokio; with the `async-std` feature it uses async-std,
+//! so applications on that runtime (tide, for instance) don't need a second
+//! executor.
+//!
+//! The HTTP providers are built on `reqwest` and the AWS SDK, which need a
+//! tokio reactor.  On async-std, wrap them in [`CompatClient`] to run their
+//! I/O on a background tokio runtime:
+//!
+//! ```rust,ignore
+//! use smskit::runtime::CompatClient;
+//!
+//! let client = CompatClient::new(PlivoClient::from_env()?);
+//! async_std::task::block_on(client.send(req.as_ref()))?;
+//! ```
+
+use std::fmt;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+/// Returned by [`timeout`] when the deadline passes first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Elapsed;
+
+impl fmt::Display for Elapsed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("deadline elapsed")
+    }
+}
+
+impl std::error::Error for Elapsed {}
+
+/// Wait for `duration`.
+pub async fn sleep(duration: Duration) {
+    #[cfg(feature = "async-std")]
+    async_std::task::sleep(duration).await;
+    #[cfg(not(feature = "async-std"))]
+    tokio::time::sleep(duration).await;
+}
+
+/// Run `future`, giving up after `duration`.
+pub async fn timeout<F: Future>(duration: Duration, future: F) -> Result<F::Output, Elapsed> {
+    #[cfg(feature = "async-std")]
+    let result = async_std::future::timeout(duration, future).await;
+    #[cfg(not(feature = "async-std"))]
+    let result = tokio::time::timeout(duration, future).await;
+    result.map_err(|_| Elapsed)
+}
+
+/// Run `future` in the background, detached.
+pub fn spawn<F>(future: F)
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    #[cfg(feature = "async-std")]
+    async_std::task::spawn(future);
+    #[cfg(not(feature = "async-std"))]
+    tokio::spawn(future);
+}
+
+/// A periodic timer.
+///
+/// The first [`tick`](Self::tick) completes immediately.  A tick that is
+/// late pushes the schedule back rather than firing a burst to catch up.
+#[derive(Debug)]
+pub struct Interval {
+    period: Duration,
+    next: Instant,
+}
+
+impl Interval {
+    /// Tick every `period`.
+    pub fn new(period: Duration) -> Self {
+        Self {
+            period,
+            next: Instant::now(),
+        }
+    }
+
+    /// Wait for the next tick.
+    pub async fn tick(&mut self) {
+        let now = Instant::now();
+        if self.next > now {
+            sleep(self.next - now).await;
+        }
+        self.next = Instant::now().max(self.next) + self.period;
+    }
+}
+
+/// Runs a tokio-based [`SmsClient`](sms_core::SmsClient) on any executor by
+/// entering a background tokio runtime for each send.
+#[cfg(feature = "async-std")]
+#[derive(Debug, Clone)]
+pub struct CompatClient<C> {
+    inner: C,
+}
+
+#[cfg(feature = "async-std")]
+impl<C: sms_core::SmsClient> CompatClient<C> {
+    /// Wrap `inner`.
+    pub fn new(inner: C) -> Self {
+        Self { inner }
+    }
+
+    /// Borrow the wrapped client.
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+}
+
+#[cfg(feature = "async-std")]
+impl<C: sms_core::SmsClient> sms_core::SmsClient for CompatClient<C> {
+    async fn send(
+        &self,
+        req: sms_core::SendRequest<'_>,
+    ) -> Result<sms_core::SendResponse, sms_core::SmsError> {
+        async_compat::Compat::new(self.inner.send(req)).await
+    }
+
+    fn supports(&self, channel: sms_core::Channel) -> bool {
+        self.inner.supports(channel)
+    }
+
+    fn max_text_len(&self) -> Option<usize> {
+        self.inner.max_text_len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn timeout_reports_elapsed() {
+        let slow = timeout(Duration::from_millis(5), sleep(Duration::from_secs(5))).await;
+        assert_eq!(slow, Err(Elapsed));
+        let fast = timeout(Duration::from_secs(5), async { 7 }).await;
+        assert_eq!(fast, Ok(7));
+    }
+
+    #[tokio::test]
+    async fn interval_ticks_immediately_then_waits() {
+        let mut ticks = Interval::new(Duration::from_millis(20));
+        let started = Instant::now();
+        ticks.tick().await;
+        assert!(started.elapsed() < Duration::from_millis(20));
+        ticks.tick().await;
+        assert!(started.elapsed() >= Duration::from_millis(20));
+    }
+}
@@ -0,0 +1,426 @@
+//! Destination screening.
+//!
+//! [`DestinationRules`] is a blocklist and allowlist of destination numbers
+//! and prefixes — e.g. blocking premium-rate `+1900` numbers — and
+//! [`ScreenedClient`] enforces it in front of any [`SmsClient`].  Rules are
+//! read from a [`DestinationStore`] on every send, so operators can update
+//! them at runtime; [`InMemoryDestinationStore`] is the built-in store.
+//!
+//! [`CountryAllowlist`] additionally restricts sends to a set of destination
+//! countries — by default the sender's own — which stops SMS pumping (toll
+//! fraud) from draining an account through premium international routes.
+//!
+//! ```rust,ignore
+//! use smskit::screening::{DestinationRules, InMemoryDestinationStore, ScreenedClient};
+//!
+//! let store = InMemoryDestinationStore::new(config.destinations.clone());
+//! let client = ScreenedClient::new(plivo, Arc::new(store.clone()))
+//!     .with_countries(CountryAllowlist::default().allow("44"));
+//! // Later, from an admin task:
+//! store.block("+44909*");
+//! ```
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sms_core::{
+    Channel, SendRequest, SendResponse, SmsClient, SmsError, normalize_number, redact_number,
+};
+use std::collections::BTreeSet;
+use std::sync::{Arc, RwLock};
+use tracing::debug;
+
+/// Destination blocklist and allowlist.
+///
+/// Each entry is either an exact number (`"+14155551234"`) or a prefix ending
+/// in `*` (`"+1900*"`).  Both sides are compared after [`normalize_number`],
+/// so punctuation is ignored and a `00` international prefix reads as `+`.
+/// A destination is rejected if it matches any blocked entry, or if the
+/// allowlist is non-empty and it matches no allowed entry.  Prefixes can only
+/// be matched against international numbers, so while any entry is blocked,
+/// a destination that doesn't start with `+` is rejected as well.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DestinationRules {
+    /// Numbers and prefixes that may be sent to; empty allows everything.
+    #[serde(default)]
+    pub allow: Vec<String>,
+    /// Numbers and prefixes that must never be sent to.
+    #[serde(default)]
+    pub block: Vec<String>,
+}
+
+impl DestinationRules {
+    /// Rules that allow every destination.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only allow destinations matching `pattern` (and any other allowed
+    /// patterns).
+    pub fn allow(mut self, pattern: impl Into<String>) -> Self {
+        self.allow.push(pattern.into());
+        self
+    }
+
+    /// Block destinations matching `pattern`.
+    pub fn block(mut self, pattern: impl Into<String>) -> Self {
+        self.block.push(pattern.into());
+        self
+    }
+
+    /// Check `to` against the rules.
+    ///
+    /// Returns [`SmsError::Suppressed`] if it may not be sent to.
+    pub fn check(&self, to: &str) -> Result<(), SmsError> {
+        let to = normalize_number(to);
+        if !self.block.is_empty() && !to.starts_with('+') {
+            return Err(SmsError::Suppressed(format!(
+                "destination {} is not in international format and cannot be screened",
+                to
+            )));
+        }
+        if let Some(pattern) = self.block.iter().find(|p| pattern_matches(p, &to)) {
+            return Err(SmsError::Suppressed(format!(
+                "destination {} is blocked by {}",
+                to, pattern
+            )));
+        }
+        if !self.allow.is_empty() && !self.allow.iter().any(|p| pattern_matches(p, &to)) {
+            return Err(SmsError::Suppressed(format!(
+                "destination {} is not on the allowlist",
+                to
+            )));
+        }
+        Ok(())
+    }
+}
+
+fn pattern_matches(pattern: &str, to: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => to.starts_with(&normalize_number(prefix)),
+        None => normalize_number(pattern) == to,
+    }
+}
+
+/// Two-digit country calling codes; every other code starting with a digit
+/// other than 1 or 7 has three digits.
+const TWO_DIGIT_CODES: &[&str] = &[
+    "20", "27", "30", "31", "32", "33", "34", "36", "39", "40", "41", "43", "44", "45", "46", "47",
+    "48", "49", "51", "52", "53", "54", "55", "56", "57", "58", "60", "61", "62", "63", "64", "65",
+    "66", "81", "82", "84", "86", "90", "91", "92", "93", "94", "95", "98",
+];
+
+/// The country calling code of an E.164 number, e.g. `"44"` for
+/// `"+447700900000"`.
+///
+/// Returns `None` for anything that isn't `+` followed by digits, such as
+/// an alphanumeric sender ID.
+pub fn calling_code(number: &str) -> Option<&str> {
+    let digits = number.strip_prefix('+')?;
+    if digits.len() < 4 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let len = match digits.as_bytes()[0] {
+        b'1' | b'7' => 1,
+        _ if TWO_DIGIT_CODES.contains(&&digits[..2]) => 2,
+        _ => 3,
+    };
+    Some(&digits[..len])
+}
+
+/// Restricts sends to an allowlisted set of destination countries.
+///
+/// Countries are identified by calling code (`"1"`, `"44"`), so `"1"`
+/// covers the whole North American Numbering Plan — including Caribbean
+/// area codes often abused for toll fraud; block those with
+/// [`DestinationRules`] prefixes if needed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CountryAllowlist {
+    codes: BTreeSet<String>,
+    sender_country: bool,
+}
+
+impl Default for CountryAllowlist {
+    /// Allow only the sender's own country.
+    fn default() -> Self {
+        Self {
+            codes: BTreeSet::new(),
+            sender_country: true,
+        }
+    }
+}
+
+impl CountryAllowlist {
+    /// Allow only the given calling codes, regardless of the sender.
+    pub fn only<I, S>(codes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let list = Self {
+            codes: BTreeSet::new(),
+            sender_country: false,
+        };
+        codes.into_iter().fold(list, |list, code| list.allow(code))
+    }
+
+    /// Also allow the country with calling code `code` (a leading `+` is
+    /// ignored).
+    pub fn allow(mut self, code: impl AsRef<str>) -> Self {
+        let code = code.as_ref();
+        self.codes
+            .insert(code.strip_prefix('+').unwrap_or(code).to_string());
+        self
+    }
+
+    /// Check a request's destination against the allowlist.
+    ///
+    /// Returns [`SmsError::Suppressed`] for destinations outside it, and for
+    /// destinations that aren't E.164 numbers.
+    pub fn check(&self, req: &SendRequest<'_>) -> Result<(), SmsError> {
+        let to = normalize_number(req.to);
+        let Some(code) = calling_code(&to) else {
+            return Err(SmsError::Suppressed(format!(
+                "destination {} has no recognisable country code",
+                to
+            )));
+        };
+        let from = normalize_number(req.from);
+        let sender = calling_code(&from).filter(|_| self.sender_country);
+        if self.codes.contains(code) || sender == Some(code) {
+            return Ok(());
+        }
+        Err(SmsError::Suppressed(format!(
+            "destination country +{} is not on the allowlist",
+            code
+        )))
+    }
+}
+
+/// Source of the [`DestinationRules`] a [`ScreenedClient`] enforces.
+///
+/// Implement this to keep rules in a database or config service; the rules
+/// are fetched on every send.
+#[async_trait]
+pub trait DestinationStore: Send + Sync {
+    /// The current rules.
+    async fn rules(&self) -> Result<Arc<DestinationRules>, SmsError>;
+}
+
+/// A [`DestinationStore`] held in process memory.
+///
+/// Cloning yields another handle to the same rules, so one handle can be
+/// given to a [`ScreenedClient`] and another kept for runtime updates.
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryDestinationStore {
+    rules: Arc<RwLock<Arc<DestinationRules>>>,
+}
+
+impl InMemoryDestinationStore {
+    /// Create a store holding `rules`.
+    pub fn new(rules: DestinationRules) -> Self {
+        Self {
+            rules: Arc::new(RwLock::new(Arc::new(rules))),
+        }
+    }
+
+    /// Replace every rule.
+    pub fn replace(&self, rules: DestinationRules) {
+        *self.rules.write().expect("destination store poisoned") = Arc::new(rules);
+    }
+
+    /// Add `pattern` to the blocklist.
+    pub fn block(&self, pattern: impl Into<String>) {
+        self.update(|rules| rules.block.push(pattern.into()));
+    }
+
+    /// Remove `pattern` from the blocklist.  Returns `true` if it was there.
+    pub fn unblock(&self, pattern: &str) -> bool {
+        self.update(|rules| remove(&mut rules.block, pattern))
+    }
+
+    /// Add `pattern` to the allowlist.
+    pub fn allow(&self, pattern: impl Into<String>) {
+        self.update(|rules| rules.allow.push(pattern.into()));
+    }
+
+    /// Remove `pattern` from the allowlist.  Returns `true` if it was there.
+    pub fn disallow(&self, pattern: &str) -> bool {
+        self.update(|rules| remove(&mut rules.allow, pattern))
+    }
+
+    /// A snapshot of the current rules.
+    pub fn snapshot(&self) -> Arc<DestinationRules> {
+        self.rules
+            .read()
+            .expect("destination store poisoned")
+            .clone()
+    }
+
+    fn update<T>(&self, f: impl FnOnce(&mut DestinationRules) -> T) -> T {
+        let mut rules = self.rules.write().expect("destination store poisoned");
+        f(Arc::make_mut(&mut rules))
+    }
+}
+
+fn remove(patterns: &mut Vec<String>, pattern: &str) -> bool {
+    let before = patterns.len();
+    patterns.retain(|p| p != pattern);
+    patterns.len() != before
+}
+
+#[async_trait]
+impl DestinationStore for InMemoryDestinationStore {
+    async fn rules(&self) -> Result<Arc<DestinationRules>, SmsError> {
+        Ok(self.snapshot())
+    }
+}
+
+/// Wraps an [`SmsClient`] and refuses sends to screened-out destinations.
+pub struct ScreenedClient<C> {
+    inner: C,
+    store: Arc<dyn DestinationStore>,
+    countries: Option<CountryAllowlist>,
+}
+
+impl<C: SmsClient> ScreenedClient<C> {
+    /// Screen sends through `inner` against the rules in `store`.
+    pub fn new(inner: C, store: Arc<dyn DestinationStore>) -> Self {
+        Self {
+            inner,
+            store,
+            countries: None,
+        }
+    }
+
+    /// Also restrict sends to the countries in `countries`.
+    pub fn with_countries(mut self, countries: CountryAllowlist) -> Self {
+        self.countries = Some(countries);
+        self
+    }
+
+    /// Borrow the wrapped client.
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+}
+
+impl<C: SmsClient> SmsClient for ScreenedClient<C> {
+    async fn send(&self, req: SendRequest<'_>) -> Result<SendResponse, SmsError> {
+        let countries = match &self.countries {
+            Some(countries) => countries.check(&req),
+            None => Ok(()),
+        };
+        countries
+            .and(self.store.rules().await?.check(req.to))
+            .inspect_err(|_| debug!("Screening rejected message to {}", redact_number(req.to)))?;
+        self.inner.send(req).await
+    }
+
+    fn supports(&self, channel: Channel) -> bool {
+        self.inner.supports(channel)
+    }
+
+    fn max_text_len(&self) -> Option<usize> {
+        self.inner.max_text_len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::SimulatedClient;
+
+    fn req(to: &str) -> SendRequest<'_> {
+        SendRequest {
+            to,
+            from: "+15550000",
+            text: "hi",
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn block_wins_over_allow() {
+        let rules = DestinationRules::new()
+            .allow("+1*")
+            .block("+1900*")
+            .block("+1 (415) 555-0000");
+        assert!(rules.check("+14155551234").is_ok());
+        assert!(matches!(
+            rules.check("+19005551234"),
+            Err(SmsError::Suppressed(_))
+        ));
+        assert!(rules.check("+14155550000").is_err());
+        assert!(rules.check("+447700900000").is_err());
+    }
+
+    #[test]
+    fn block_rules_cannot_be_bypassed_by_number_format() {
+        let rules = DestinationRules::new().block("+1900*");
+        assert!(rules.check("0019005551234").is_err());
+        assert!(rules.check("19005551234").is_err());
+        assert!(rules.check("00 44 7700 900000").is_ok());
+        assert!(DestinationRules::new().check("19005551234").is_ok());
+    }
+
+    #[test]
+    fn empty_rules_allow_everything() {
+        assert!(DestinationRules::new().check("+447700900000").is_ok());
+    }
+
+    #[test]
+    fn calling_codes() {
+        assert_eq!(calling_code("+14155551234"), Some("1"));
+        assert_eq!(calling_code("+447700900000"), Some("44"));
+        assert_eq!(calling_code("+3538512345678"), Some("353"));
+        assert_eq!(calling_code("+79161234567"), Some("7"));
+        assert_eq!(calling_code("ACME"), None);
+        assert_eq!(calling_code("14155551234"), None);
+    }
+
+    #[test]
+    fn country_allowlist_defaults_to_sender_country() {
+        let countries = CountryAllowlist::default();
+        assert!(countries.check(&req("+1 415 555 1234")).is_ok());
+        assert!(matches!(
+            countries.check(&req("+3728123456")),
+            Err(SmsError::Suppressed(_))
+        ));
+
+        let countries = countries.allow("+372");
+        assert!(countries.check(&req("+3728123456")).is_ok());
+
+        let alphanumeric = SendRequest {
+            from: "ACME",
+            ..req("+14155551234")
+        };
+        assert!(CountryAllowlist::default().check(&alphanumeric).is_err());
+        assert!(CountryAllowlist::only(["1"]).check(&alphanumeric).is_ok());
+    }
+
+    #[tokio::test]
+    async fn client_enforces_countries_and_rules() {
+        let store = InMemoryDestinationStore::new(DestinationRules::new().block("+1900*"));
+        let client = ScreenedClient::new(SimulatedClient::new(), Arc::new(store))
+            .with_countries(CountryAllowlist::default());
+        assert!(client.send(req("+14155551234")).await.is_ok());
+        assert!(client.send(req("+19005551234")).await.is_err());
+        let err = client.send(req("+2348012345678")).await.unwrap_err();
+        assert!(err.to_string().contains("+234 is not on the allowlist"));
+    }
+
+    #[tokio::test]
+    async fn client_sees_runtime_updates() {
+        let store = InMemoryDestinationStore::default();
+        let client = ScreenedClient::new(SimulatedClient::new(), Arc::new(store.clone()));
+        assert!(client.send(req("+19005551234")).await.is_ok());
+
+        store.block("+1900*");
+        let err = client.send(req("+19005551234")).await.unwrap_err();
+        assert!(err.to_string().contains("blocked by +1900*"));
+
+        assert!(store.unblock("+1900*"));
+        assert!(!store.unblock("+1900*"));
+        assert!(client.send(req("+19005551234")).await.is_ok());
+    }
+}
@@ -0,0 +1,220 @@
+//! Sender number pools with sticky senders.
+//!
+//! [`SenderPool`] spreads outbound traffic across a pool of numbers while
+//! pinning each recipient to the number that first messaged them, so
+//! replies land in the same conversation and per-number carrier limits are
+//! shared out.  [`PooledClient`] fills in `from` from the pool for any
+//! request that leaves it empty.
+//!
+//! ```rust,ignore
+//! use smskit::sender_pool::{PooledClient, SenderPool};
+//!
+//! let pool = SenderPool::new(["+14155550001", "+14155550002", "+14155550003"]);
+//! let client = PooledClient::new(twilio, pool.clone());
+//! client.send(SendRequest { to: "+14155551234", from: "", text: "Hi", ..Default::default() }).await?;
+//! ```
+
+use sms_core::{Channel, SendRequest, SendResponse, SmsClient, SmsError};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Default)]
+struct PoolInner {
+    numbers: Vec<String>,
+    sticky: HashMap<String, String>,
+    next: usize,
+}
+
+/// A pool of sender numbers with per-recipient stickiness.
+///
+/// New recipients are assigned numbers round-robin.  Cloning yields another
+/// handle to the same pool and assignments.
+#[derive(Debug, Clone, Default)]
+pub struct SenderPool {
+    inner: Arc<Mutex<PoolInner>>,
+}
+
+impl SenderPool {
+    /// Create a pool of `numbers`.
+    pub fn new<I, S>(numbers: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let pool = Self::default();
+        for number in numbers {
+            pool.add(number);
+        }
+        pool
+    }
+
+    /// The sender for `to`: its pinned number, or the next one in rotation
+    /// (which then becomes pinned).  Returns `None` if the pool is empty.
+    pub fn sender_for(&self, to: &str) -> Option<String> {
+        let mut inner = self.lock();
+        if let Some(from) = inner.sticky.get(to) {
+            return Some(from.clone());
+        }
+        if inner.numbers.is_empty() {
+            return None;
+        }
+        let from = inner.numbers[inner.next % inner.numbers.len()].clone();
+        inner.next = inner.next.wrapping_add(1);
+        inner.sticky.insert(to.to_string(), from.clone());
+        Some(from)
+    }
+
+    /// Pin `to` to `from`, e.g. to restore assignments after a restart.
+    /// `from` does not have to be in the pool.
+    pub fn pin(&self, to: impl Into<String>, from: impl Into<String>) {
+        self.lock().sticky.insert(to.into(), from.into());
+    }
+
+    /// Forget the number pinned to `to`.
+    pub fn unpin(&self, to: &str) -> Option<String> {
+        self.lock().sticky.remove(to)
+    }
+
+    /// Add `number` to the rotation (duplicates are ignored).
+    pub fn add(&self, number: impl Into<String>) {
+        let number = number.into();
+        let mut inner = self.lock();
+        if !inner.numbers.contains(&number) {
+            inner.numbers.push(number);
+        }
+    }
+
+    /// Take `number` out of the rotation and unpin every recipient that was
+    /// pinned to it, so they are reassigned on their next message.  Returns
+    /// `true` if it was in the pool.
+    pub fn remove(&self, number: &str) -> bool {
+        let mut inner = self.lock();
+        let before = inner.numbers.len();
+        inner.numbers.retain(|n| n != number);
+        inner.sticky.retain(|_, from| from != number);
+        inner.numbers.len() != before
+    }
+
+    /// Numbers in the rotation.
+    pub fn numbers(&self) -> Vec<String> {
+        self.lock().numbers.clone()
+    }
+
+    /// Every recipient → sender assignment, for persistence.
+    pub fn assignments(&self) -> HashMap<String, String> {
+        self.lock().sticky.clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, PoolInner> {
+        self.inner.lock().expect("sender pool poisoned")
+    }
+}
+
+/// Wraps an [`SmsClient`] and picks `from` from a [`SenderPool`].
+///
+/// Requests that already set `from` are sent unchanged.
+pub struct PooledClient<C> {
+    inner: C,
+    pool: SenderPool,
+}
+
+impl<C: SmsClient> PooledClient<C> {
+    /// Send through `inner` with senders from `pool`.
+    pub fn new(inner: C, pool: SenderPool) -> Self {
+        Self { inner, pool }
+    }
+
+    /// The pool senders are drawn from.
+    pub fn pool(&self) -> &SenderPool {
+        &self.pool
+    }
+}
+
+impl<C: SmsClient> SmsClient for PooledClient<C> {
+    async fn send(&self, req: SendRequest<'_>) -> Result<SendResponse, SmsError> {
+        if !req.from.is_empty() {
+            return self.inner.send(req).await;
+        }
+        let from = self
+            .pool
+            .sender_for(req.to)
+            .ok_or_else(|| SmsError::Invalid("sender pool is empty".into()))?;
+        self.inner.send(SendRequest { from: &from, ..req }).await
+    }
+
+    fn supports(&self, channel: Channel) -> bool {
+        self.inner.supports(channel)
+    }
+
+    fn max_text_len(&self) -> Option<usize> {
+        self.inner.max_text_len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Echoes the sender back as the message ID.
+    struct EchoFrom;
+
+    impl SmsClient for EchoFrom {
+        async fn send(&self, req: SendRequest<'_>) -> Result<SendResponse, SmsError> {
+            Ok(SendResponse {
+                id: req.from.to_string(),
+                provider: "echo",
+                channel: Channel::Sms,
+                http_status: None,
+                provider_request_id: None,
+                thread_id: None,
+                raw: serde_json::json!({}),
+            })
+        }
+    }
+
+    fn req(to: &str) -> SendRequest<'_> {
+        SendRequest {
+            to,
+            text: "hi",
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn rotates_new_recipients_and_sticks() {
+        let pool = SenderPool::new(["A", "B"]);
+        assert_eq!(pool.sender_for("+1").as_deref(), Some("A"));
+        assert_eq!(pool.sender_for("+2").as_deref(), Some("B"));
+        assert_eq!(pool.sender_for("+3").as_deref(), Some("A"));
+        assert_eq!(pool.sender_for("+2").as_deref(), Some("B"));
+        assert_eq!(pool.assignments().len(), 3);
+    }
+
+    #[test]
+    fn removing_a_number_reassigns_its_recipients() {
+        let pool = SenderPool::new(["A", "B"]);
+        pool.sender_for("+1");
+        assert!(pool.remove("A"));
+        assert!(!pool.remove("A"));
+        assert_eq!(pool.sender_for("+1").as_deref(), Some("B"));
+        assert!(SenderPool::default().sender_for("+1").is_none());
+    }
+
+    #[tokio::test]
+    async fn client_fills_in_missing_sender() {
+        let client = PooledClient::new(EchoFrom, SenderPool::new(["A", "B"]));
+        assert_eq!(client.send(req("+1")).await.unwrap().id, "A");
+        assert_eq!(client.send(req("+1")).await.unwrap().id, "A");
+        let explicit = SendRequest {
+            from: "C",
+            ..req("+2")
+        };
+        assert_eq!(client.send(explicit).await.unwrap().id, "C");
+
+        let empty = PooledClient::new(EchoFrom, SenderPool::default());
+        assert!(matches!(
+            empty.send(req("+1")).await,
+            Err(SmsError::Invalid(_))
+        ));
+    }
+}
@@ -0,0 +1,343 @@
+//! Simulated SMS provider for load and failover testing.
+//!
+//! [`SimulatedClient`] implements [`SmsClient`] without touching any real
+//! provider.  Each send waits for a sampled latency, fails with a configurable
+//! probability, and can later emit a delivery report as an
+//! [`InboundMessage`], so capacity and failover behaviour can be exercised
+//! end-to-end without spending money on real SMS.
+//!
+//! ```rust,ignore
+//! use smskit::simulation::{LatencyDistribution, SimulatedClient};
+//!
+//! let (dlr_tx, mut dlr_rx) = tokio::sync::mpsc::unbounded_channel();
+//! let client = SimulatedClient::new()
+//!     .with_latency(LatencyDistribution::Uniform {
+//!         min: Duration::from_millis(50),
+//!         max: Duration::from_millis(400),
+//!     })
+//!     .with_error_rate(0.02)
+//!     .with_delivery_reports(LatencyDistribution::Fixed(Duration::from_secs(2)), dlr_tx);
+//! ```
+
+use crate::runtime::{sleep, spawn};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use sms_core::{
+    Channel, IdGenerator, InboundDirection, InboundMessage, SendRequest, SendResponse, SmsClient,
+    SmsError, UuidV4, redact_number,
+};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc::UnboundedSender;
+use tracing::debug;
+
+const PROVIDER: &str = "simulated";
+
+/// How long a simulated operation takes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LatencyDistribution {
+    /// Always the same duration.
+    Fixed(Duration),
+    /// Uniformly distributed between `min` and `max` (inclusive).
+    Uniform {
+        /// Shortest latency.
+        min: Duration,
+        /// Longest latency.
+        max: Duration,
+    },
+    /// Exponentially distributed with the given mean, capped at `max`.
+    /// Models the long tail real providers show under load.
+    Exponential {
+        /// Mean latency.
+        mean: Duration,
+        /// Upper bound on any single sample.
+        max: Duration,
+    },
+}
+
+impl Default for LatencyDistribution {
+    fn default() -> Self {
+        Self::Fixed(Duration::ZERO)
+    }
+}
+
+impl LatencyDistribution {
+    fn sample(&self, rng: &mut StdRng) -> Duration {
+        match *self {
+            Self::Fixed(d) => d,
+            Self::Uniform { min, max } if max > min => {
+                Duration::from_secs_f64(rng.gen_range(min.as_secs_f64()..=max.as_secs_f64()))
+            }
+            Self::Uniform { min, .. } => min,
+            Self::Exponential { mean, max } => {
+                let u: f64 = rng.gen_range(0.0..1.0);
+                let secs = -mean.as_secs_f64() * (1.0 - u).ln();
+                Duration::from_secs_f64(secs).min(max)
+            }
+        }
+    }
+}
+
+/// Counters describing what a [`SimulatedClient`] has done so far.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SimulationStats {
+    /// Sends that returned `Ok`.
+    pub sent: u64,
+    /// Sends that returned a simulated error.
+    pub failed: u64,
+    /// Delivery reports emitted.
+    pub delivery_reports: u64,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    sent: AtomicU64,
+    failed: AtomicU64,
+    delivery_reports: AtomicU64,
+}
+
+/// An [`SmsClient`] that simulates a provider with configurable latency,
+/// failure rate, and delayed delivery reports.
+#[derive(Clone)]
+pub struct SimulatedClient {
+    latency: LatencyDistribution,
+    error_rate: f64,
+    delivery_failure_rate: f64,
+    dlr_latency: LatencyDistribution,
+    dlr_sink: Option<UnboundedSender<InboundMessage>>,
+    ids: Arc<dyn IdGenerator>,
+    rng: Arc<Mutex<StdRng>>,
+    counters: Arc<Counters>,
+}
+
+impl Default for SimulatedClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SimulatedClient {
+    /// Create a client that succeeds instantly and emits no delivery reports.
+    pub fn new() -> Self {
+        Self {
+            latency: LatencyDistribution::default(),
+            error_rate: 0.0,
+            delivery_failure_rate: 0.0,
+            dlr_latency: LatencyDistribution::default(),
+            dlr_sink: None,
+            ids: Arc::new(UuidV4),
+            rng: Arc::new(Mutex::new(StdRng::from_entropy())),
+            counters: Arc::new(Counters::default()),
+        }
+    }
+
+    /// Seed the random number generator for reproducible runs.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = Arc::new(Mutex::new(StdRng::seed_from_u64(seed)));
+        self
+    }
+
+    /// Assign message IDs with `ids` instead of random UUIDs.
+    pub fn with_id_generator(mut self, ids: impl IdGenerator + 'static) -> Self {
+        self.ids = Arc::new(ids);
+        self
+    }
+
+    /// Set the latency distribution of each send call.
+    pub fn with_latency(mut self, latency: LatencyDistribution) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    /// Probability (`0.0..=1.0`) that a send returns an error.
+    pub fn with_error_rate(mut self, rate: f64) -> Self {
+        self.error_rate = rate.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Probability (`0.0..=1.0`) that an accepted message is later reported
+    /// as undelivered.
+    pub fn with_delivery_failure_rate(mut self, rate: f64) -> Self {
+        self.delivery_failure_rate = rate.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Emit a delivery report for every accepted message into `sink` after a
+    /// delay sampled from `latency`.
+    ///
+    /// Reports are sent from a spawned tokio task, so a runtime must be
+    /// running when [`send`](SmsClient::send) is called.
+    pub fn with_delivery_reports(
+        mut self,
+        latency: LatencyDistribution,
+        sink: UnboundedSender<InboundMessage>,
+    ) -> Self {
+        self.dlr_latency = latency;
+        self.dlr_sink = Some(sink);
+        self
+    }
+
+    /// Snapshot of the counters accumulated so far.
+    pub fn stats(&self) -> SimulationStats {
+        SimulationStats {
+            sent: self.counters.sent.load(Ordering::Relaxed),
+            failed: self.counters.failed.load(Ordering::Relaxed),
+            delivery_reports: self.counters.delivery_reports.load(Ordering::Relaxed),
+        }
+    }
+
+    fn roll(&self) -> (Duration, bool, Duration, bool) {
+        let mut rng = self.rng.lock().expect("simulation rng poisoned");
+        let latency = self.latency.sample(&mut rng);
+        let fail = rng.gen_bool(self.error_rate);
+        let dlr_delay = self.dlr_latency.sample(&mut rng);
+        let undelivered = rng.gen_bool(self.delivery_failure_rate);
+        (latency, fail, dlr_delay, undelivered)
+    }
+
+    fn emit_delivery_report(&self, id: String, to: String, delay: Duration, undelivered: bool) {
+        let Some(sink) = self.dlr_sink.clone() else {
+            return;
+        };
+        let counters = self.counters.clone();
+        spawn(async move {
+            sleep(delay).await;
+            let status = if undelivered { "FAILED" } else { "DELIVERED" };
+            let report = InboundMessage {
+                id: Some(id.clone()),
+                from: "SIMULATED".to_string(),
+                to,
+                text: format!("Delivery Status: {}", status),
+                timestamp: Some(time::OffsetDateTime::now_utc()),
+                provider: PROVIDER,
+                direction: InboundDirection::DeliveryReport,
+                channel: Channel::Sms,
+                provider_account: None,
+                thread_id: None,
+                flag: None,
+                raw: serde_json::json!({ "message_id": id, "status": status }),
+            };
+            if sink.send(report).is_ok() {
+                counters.delivery_reports.fetch_add(1, Ordering::Relaxed);
+            }
+        });
+    }
+}
+
+impl SmsClient for SimulatedClient {
+    async fn send(&self, req: SendRequest<'_>) -> Result<SendResponse, SmsError> {
+        let (latency, fail, dlr_delay, undelivered) = self.roll();
+        sleep(latency).await;
+
+        if fail {
+            self.counters.failed.fetch_add(1, Ordering::Relaxed);
+            debug!(
+                "Simulated send to {} failed after {:?}",
+                redact_number(req.to),
+                latency
+            );
+            return Err(SmsError::Provider("simulated provider failure".into()));
+        }
+
+        let id = self.ids.generate();
+        self.counters.sent.fetch_add(1, Ordering::Relaxed);
+        self.emit_delivery_report(id.clone(), req.to.to_string(), dlr_delay, undelivered);
+
+        Ok(SendResponse {
+            id: id.clone(),
+            provider: PROVIDER,
+            channel: req.channel,
+            http_status: None,
+            provider_request_id: None,
+            thread_id: None,
+            raw: serde_json::json!({
+                "message_id": id,
+                "latency_ms": latency.as_millis() as u64,
+            }),
+        })
+    }
+
+    /// The simulator accepts every channel.
+    fn supports(&self, _channel: Channel) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn req() -> SendRequest<'static> {
+        SendRequest {
+            to: "+14155551234",
+            from: "+10005551234",
+            text: "load test",
+            channel: Channel::Sms,
+            timeout: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn default_client_always_succeeds() {
+        let client = SimulatedClient::new();
+        for _ in 0..10 {
+            let resp = client.send(req()).await.unwrap();
+            assert_eq!(resp.provider, "simulated");
+        }
+        assert_eq!(client.stats().sent, 10);
+        assert_eq!(client.stats().failed, 0);
+    }
+
+    #[tokio::test]
+    async fn error_rate_one_always_fails() {
+        let client = SimulatedClient::new().with_error_rate(1.0);
+        assert!(client.send(req()).await.is_err());
+        assert_eq!(client.stats().failed, 1);
+    }
+
+    #[tokio::test]
+    async fn seeded_runs_are_reproducible() {
+        let outcomes = |client: SimulatedClient| async move {
+            let mut v = Vec::new();
+            for _ in 0..20 {
+                v.push(client.send(req()).await.is_ok());
+            }
+            v
+        };
+        let a = outcomes(SimulatedClient::new().with_seed(7).with_error_rate(0.5)).await;
+        let b = outcomes(SimulatedClient::new().with_seed(7).with_error_rate(0.5)).await;
+        assert_eq!(a, b);
+    }
+
+    #[tokio::test]
+    async fn emits_delivery_reports() {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let client = SimulatedClient::new()
+            .with_delivery_reports(LatencyDistribution::Fixed(Duration::from_millis(5)), tx);
+
+        let resp = client.send(req()).await.unwrap();
+        let report = rx.recv().await.unwrap();
+        assert_eq!(report.id, Some(resp.id));
+        assert_eq!(report.to, "+14155551234");
+        assert!(report.text.contains("DELIVERED"));
+    }
+
+    #[test]
+    fn latency_samples_stay_in_bounds() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let uniform = LatencyDistribution::Uniform {
+            min: Duration::from_millis(10),
+            max: Duration::from_millis(20),
+        };
+        let exp = LatencyDistribution::Exponential {
+            mean: Duration::from_millis(10),
+            max: Duration::from_millis(50),
+        };
+        for _ in 0..100 {
+            let d = uniform.sample(&mut rng);
+            assert!(d >= Duration::from_millis(10) && d <= Duration::from_millis(20));
+            assert!(exp.sample(&mut rng) <= Duration::from_millis(50));
+        }
+    }
+}
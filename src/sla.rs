@@ -0,0 +1,442 @@
+//! Delivery SLA tracking.
+//!
+//! [`SlaTracker`] measures delivery latency (send to delivery report) and
+//! failure rate per provider and destination country over a rolling window,
+//! and calls the registered breach callbacks when either crosses its
+//! [`SlaThresholds`] — so operators hear about carrier problems from smskit
+//! rather than from customers.  A callback fires once when a breach starts;
+//! it fires again only after the stats have recovered and breached anew.
+//!
+//! ```rust,ignore
+//! use smskit::sla::{SlaThresholds, SlaTracker};
+//!
+//! let sla = SlaTracker::new(Duration::from_secs(900), SlaThresholds::default())
+//!     .on_breach(|breach| alert(format!("{}", breach)));
+//!
+//! let result = client.send(req.clone()).await;
+//! sla.record_send("plivo", &req, &result);
+//! // From the delivery-report webhook:
+//! sla.record_delivery(&message_id, delivered);
+//! ```
+
+use crate::clock::{Clock, SystemClock};
+use crate::screening::calling_code;
+use serde::Serialize;
+use sms_core::{SendRequest, SendResponse, SmsError};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// Limits beyond which a provider/country pair is in breach.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SlaThresholds {
+    /// Highest acceptable 95th-percentile delivery latency.
+    pub max_p95_latency: Duration,
+    /// Highest acceptable fraction of failed messages, `0.0..=1.0`.
+    pub max_failure_rate: f64,
+    /// Outcomes needed in the window before thresholds are checked, so one
+    /// early failure doesn't read as a 100% failure rate.
+    pub min_samples: usize,
+}
+
+impl Default for SlaThresholds {
+    fn default() -> Self {
+        Self {
+            max_p95_latency: Duration::from_secs(60),
+            max_failure_rate: 0.05,
+            min_samples: 20,
+        }
+    }
+}
+
+/// A provider and destination country (calling code, or `"unknown"`).
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+pub struct SlaKey {
+    /// Provider name, e.g. `"plivo"`.
+    pub provider: String,
+    /// Destination calling code, e.g. `"44"`.
+    pub country: String,
+}
+
+impl SlaKey {
+    fn new(provider: &str, to: &str) -> Self {
+        Self {
+            provider: provider.to_string(),
+            country: calling_code(to).unwrap_or("unknown").to_string(),
+        }
+    }
+}
+
+impl fmt::Display for SlaKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (+{})", self.provider, self.country)
+    }
+}
+
+/// Delivery statistics for one [`SlaKey`] over the current window.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct SlaStats {
+    /// Messages delivered.
+    pub delivered: usize,
+    /// Messages that failed to send or were reported undelivered.
+    pub failed: usize,
+    /// 95th-percentile send-to-delivery latency, if anything was delivered.
+    pub p95_latency: Option<Duration>,
+}
+
+impl SlaStats {
+    /// Total outcomes in the window.
+    pub fn total(&self) -> usize {
+        self.delivered + self.failed
+    }
+
+    /// Fraction of outcomes that failed (`0.0` when empty).
+    pub fn failure_rate(&self) -> f64 {
+        if self.total() == 0 {
+            0.0
+        } else {
+            self.failed as f64 / self.total() as f64
+        }
+    }
+}
+
+/// Which threshold a [`SlaBreach`] crossed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BreachKind {
+    /// The p95 delivery latency exceeded `max_p95_latency`.
+    Latency {
+        /// Observed p95 latency.
+        p95: Duration,
+        /// Configured limit.
+        threshold: Duration,
+    },
+    /// The failure rate exceeded `max_failure_rate`.
+    FailureRate {
+        /// Observed failure rate.
+        rate: f64,
+        /// Configured limit.
+        threshold: f64,
+    },
+}
+
+/// A threshold breach reported to [`SlaTracker::on_breach`] callbacks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlaBreach {
+    /// The provider/country pair in breach.
+    pub key: SlaKey,
+    /// The threshold crossed.
+    pub kind: BreachKind,
+    /// Stats at the time of the breach.
+    pub stats: SlaStats,
+}
+
+impl fmt::Display for SlaBreach {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            BreachKind::Latency { p95, threshold } => write!(
+                f,
+                "{}: p95 delivery latency {:?} exceeds {:?}",
+                self.key, p95, threshold
+            ),
+            BreachKind::FailureRate { rate, threshold } => write!(
+                f,
+                "{}: failure rate {:.1}% exceeds {:.1}%",
+                self.key,
+                rate * 100.0,
+                threshold * 100.0
+            ),
+        }
+    }
+}
+
+/// Callback invoked when a provider/country pair starts breaching.
+pub type BreachCallback = dyn Fn(&SlaBreach) + Send + Sync;
+
+#[derive(Debug)]
+struct Outcome {
+    at: Instant,
+    latency: Option<Duration>,
+}
+
+#[derive(Debug, Default)]
+struct Series {
+    outcomes: VecDeque<Outcome>,
+    breached: bool,
+}
+
+#[derive(Debug, Default)]
+struct SlaInner {
+    pending: HashMap<String, (SlaKey, Instant)>,
+    series: BTreeMap<SlaKey, Series>,
+}
+
+/// Rolling-window delivery SLA tracker.
+///
+/// Cloning yields another handle to the same statistics, so the send path
+/// and the delivery-report webhook can each hold one.
+#[derive(Clone)]
+pub struct SlaTracker {
+    inner: Arc<Mutex<SlaInner>>,
+    window: Duration,
+    thresholds: SlaThresholds,
+    callbacks: Vec<Arc<BreachCallback>>,
+    clock: Arc<dyn Clock>,
+}
+
+impl SlaTracker {
+    /// Track outcomes over the last `window` against `thresholds`.
+    pub fn new(window: Duration, thresholds: SlaThresholds) -> Self {
+        Self {
+            inner: Arc::default(),
+            window,
+            thresholds,
+            callbacks: Vec::new(),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Call `callback` whenever a provider/country pair starts breaching.
+    pub fn on_breach(mut self, callback: impl Fn(&SlaBreach) + Send + Sync + 'static) -> Self {
+        self.callbacks.push(Arc::new(callback));
+        self
+    }
+
+    /// Read time from `clock` instead of the system clock.
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Record a send through `provider`.
+    ///
+    /// A successful send starts the latency timer for its message ID; a
+    /// failed send counts as a failure immediately.
+    pub fn record_send(
+        &self,
+        provider: &str,
+        req: &SendRequest<'_>,
+        result: &Result<SendResponse, SmsError>,
+    ) {
+        let key = SlaKey::new(provider, req.to);
+        let now = self.clock.now();
+        match result {
+            Ok(resp) => {
+                self.lock().pending.insert(resp.id.clone(), (key, now));
+            }
+            Err(_) => self.record(key, now, None),
+        }
+    }
+
+    /// Record the delivery report for a message previously passed to
+    /// [`record_send`](Self::record_send).
+    ///
+    /// Reports for unknown or already-reported IDs are ignored.
+    pub fn record_delivery(&self, message_id: &str, delivered: bool) {
+        let Some((key, sent_at)) = self.lock().pending.remove(message_id) else {
+            return;
+        };
+        let now = self.clock.now();
+        let latency = delivered.then(|| now.saturating_duration_since(sent_at));
+        self.record(key, now, latency);
+    }
+
+    /// Stats for `provider` sending to `country` over the current window.
+    pub fn stats(&self, provider: &str, country: &str) -> SlaStats {
+        let key = SlaKey {
+            provider: provider.to_string(),
+            country: country.to_string(),
+        };
+        let now = self.clock.now();
+        let mut inner = self.lock();
+        inner
+            .series
+            .get_mut(&key)
+            .map(|series| self.evaluate(series, now))
+            .unwrap_or_default()
+    }
+
+    /// Stats for every provider/country pair seen in the current window.
+    pub fn snapshot(&self) -> BTreeMap<SlaKey, SlaStats> {
+        let now = self.clock.now();
+        let mut inner = self.lock();
+        inner
+            .series
+            .iter_mut()
+            .map(|(key, series)| (key.clone(), self.evaluate(series, now)))
+            .filter(|(_, stats)| stats.total() > 0)
+            .collect()
+    }
+
+    fn record(&self, key: SlaKey, now: Instant, latency: Option<Duration>) {
+        let breach = {
+            let mut inner = self.lock();
+            let cutoff = now.checked_sub(self.window);
+            inner
+                .pending
+                .retain(|_, (_, sent_at)| cutoff.is_none_or(|c| *sent_at >= c));
+
+            let series = inner.series.entry(key.clone()).or_default();
+            series.outcomes.push_back(Outcome { at: now, latency });
+            let stats = self.evaluate(series, now);
+            let kind = self.breach_kind(&stats);
+            let starting = kind.is_some() && !series.breached;
+            series.breached = kind.is_some();
+            kind.filter(|_| starting)
+                .map(|kind| SlaBreach { key, kind, stats })
+        };
+
+        if let Some(breach) = breach {
+            warn!("SLA breach: {}", breach);
+            for callback in &self.callbacks {
+                callback(&breach);
+            }
+        }
+    }
+
+    /// Drop outcomes older than the window and summarize the rest.
+    fn evaluate(&self, series: &mut Series, now: Instant) -> SlaStats {
+        if let Some(cutoff) = now.checked_sub(self.window) {
+            while series.outcomes.front().is_some_and(|o| o.at < cutoff) {
+                series.outcomes.pop_front();
+            }
+        }
+
+        let mut latencies: Vec<_> = series.outcomes.iter().filter_map(|o| o.latency).collect();
+        latencies.sort_unstable();
+        let p95_latency =
+            (!latencies.is_empty()).then(|| latencies[(latencies.len() * 95).div_ceil(100) - 1]);
+        SlaStats {
+            delivered: latencies.len(),
+            failed: series.outcomes.len() - latencies.len(),
+            p95_latency,
+        }
+    }
+
+    fn breach_kind(&self, stats: &SlaStats) -> Option<BreachKind> {
+        let t = &self.thresholds;
+        if stats.total() < t.min_samples {
+            return None;
+        }
+        if stats.failure_rate() > t.max_failure_rate {
+            return Some(BreachKind::FailureRate {
+                rate: stats.failure_rate(),
+                threshold: t.max_failure_rate,
+            });
+        }
+        stats
+            .p95_latency
+            .filter(|p95| *p95 > t.max_p95_latency)
+            .map(|p95| BreachKind::Latency {
+                p95,
+                threshold: t.max_p95_latency,
+            })
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, SlaInner> {
+        self.inner.lock().expect("SLA tracker poisoned")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use sms_core::Channel;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn req(to: &str) -> SendRequest<'_> {
+        SendRequest {
+            to,
+            from: "+15550000",
+            text: "hi",
+            ..Default::default()
+        }
+    }
+
+    fn sent(id: &str) -> Result<SendResponse, SmsError> {
+        Ok(SendResponse {
+            id: id.into(),
+            provider: "plivo",
+            channel: Channel::Sms,
+            http_status: None,
+            provider_request_id: None,
+            thread_id: None,
+            raw: serde_json::json!({}),
+        })
+    }
+
+    fn thresholds() -> SlaThresholds {
+        SlaThresholds {
+            max_p95_latency: Duration::from_secs(10),
+            max_failure_rate: 0.25,
+            min_samples: 4,
+        }
+    }
+
+    #[test]
+    fn measures_latency_per_country() {
+        let clock = ManualClock::new();
+        let sla = SlaTracker::new(Duration::from_secs(60), thresholds()).with_clock(clock.clone());
+        sla.record_send("plivo", &req("+14155551234"), &sent("us"));
+        sla.record_send("plivo", &req("+447700900000"), &sent("uk"));
+        clock.advance(Duration::from_secs(3));
+        sla.record_delivery("us", true);
+        sla.record_delivery("uk", false);
+        sla.record_delivery("uk", true);
+
+        let us = sla.stats("plivo", "1");
+        assert_eq!(us.delivered, 1);
+        assert_eq!(us.p95_latency, Some(Duration::from_secs(3)));
+        let uk = sla.stats("plivo", "44");
+        assert_eq!((uk.delivered, uk.failed), (0, 1));
+        assert_eq!(sla.snapshot().len(), 2);
+    }
+
+    #[test]
+    fn fires_once_per_breach_and_forgets_old_outcomes() {
+        let clock = ManualClock::new();
+        let fired = Arc::new(AtomicUsize::new(0));
+        let counter = fired.clone();
+        let sla = SlaTracker::new(Duration::from_secs(60), thresholds())
+            .with_clock(clock.clone())
+            .on_breach(move |breach| {
+                assert!(matches!(breach.kind, BreachKind::FailureRate { .. }));
+                counter.fetch_add(1, Ordering::SeqCst);
+            });
+        let failure: Result<SendResponse, SmsError> = Err(SmsError::Provider("down".into()));
+
+        for _ in 0..3 {
+            sla.record_send("twilio", &req("+14155551234"), &failure);
+        }
+        assert_eq!(fired.load(Ordering::SeqCst), 0, "below min_samples");
+        sla.record_send("twilio", &req("+14155551234"), &failure);
+        sla.record_send("twilio", &req("+14155551234"), &failure);
+        assert_eq!(fired.load(Ordering::SeqCst), 1);
+
+        clock.advance(Duration::from_secs(61));
+        assert_eq!(sla.stats("twilio", "1").total(), 0);
+    }
+
+    #[test]
+    fn latency_breach() {
+        let clock = ManualClock::new();
+        let breaches = Arc::new(Mutex::new(Vec::new()));
+        let log = breaches.clone();
+        let sla = SlaTracker::new(Duration::from_secs(600), thresholds())
+            .with_clock(clock.clone())
+            .on_breach(move |breach| log.lock().unwrap().push(breach.to_string()));
+
+        for i in 0..4 {
+            sla.record_send("plivo", &req("+14155551234"), &sent(&i.to_string()));
+        }
+        clock.advance(Duration::from_secs(30));
+        for i in 0..4 {
+            sla.record_delivery(&i.to_string(), true);
+        }
+        let breaches = breaches.lock().unwrap();
+        assert_eq!(breaches.len(), 1);
+        assert!(breaches[0].starts_with("plivo (+1): p95 delivery latency"));
+    }
+}
@@ -0,0 +1,392 @@
+//! Message persistence.
+//!
+//! [`MessageStore`] is the extension point for keeping a record of sent and
+//! received messages; [`InMemoryMessageStore`] is the built-in implementation
+//! for tests and single-process deployments.  See [`crate::export`] for
+//! archiving a store's contents.
+//!
+//! An erasure request has to reach every place that keeps a number, not
+//! just this store: the opt-out list, the delivery correlator, the outbox
+//! and any sink file each have their own `forget_number`.
+//!
+//! ```rust,ignore
+//! use smskit::store::{DateRange, InMemoryMessageStore, MessageStore, StoredMessage};
+//!
+//! let store = InMemoryMessageStore::new();
+//! store.save(StoredMessage::outbound(&req, &response)).await?;
+//! store.save(StoredMessage::inbound(&message)).await?;
+//! let today = store.query(&DateRange::all().since(midnight)).await?;
+//! ```
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sms_core::{InboundMessage, SendRequest, SendResponse, SmsError, normalize_number};
+use std::sync::{Arc, Mutex};
+use time::OffsetDateTime;
+
+/// Whether a stored message was sent or received.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MessageDirection {
+    /// Received from a provider webhook.
+    Inbound,
+    /// Sent through an [`SmsClient`](sms_core::SmsClient).
+    Outbound,
+}
+
+impl MessageDirection {
+    /// Lowercase name, as used in serialized output.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MessageDirection::Inbound => "inbound",
+            MessageDirection::Outbound => "outbound",
+        }
+    }
+}
+
+/// A message as kept in a [`MessageStore`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredMessage {
+    /// Provider message ID, or a locally generated one.
+    pub id: String,
+    /// Sent or received.
+    pub direction: MessageDirection,
+    /// Sender phone number.
+    pub from: String,
+    /// Destination phone number / short code.
+    pub to: String,
+    /// The message body.
+    pub text: String,
+    /// Provider name, e.g. `"plivo"`.
+    pub provider: String,
+    /// When the message was sent or received.
+    #[serde(with = "time::serde::rfc3339")]
+    pub timestamp: OffsetDateTime,
+}
+
+impl StoredMessage {
+    /// Record of a successful send, timestamped now.
+    pub fn outbound(req: &SendRequest<'_>, resp: &SendResponse) -> Self {
+        Self {
+            id: resp.id.clone(),
+            direction: MessageDirection::Outbound,
+            from: req.from.to_string(),
+            to: req.to.to_string(),
+            text: req.text.to_string(),
+            provider: resp.provider.to_string(),
+            timestamp: OffsetDateTime::now_utc(),
+        }
+    }
+
+    /// Record of an inbound message.  Uses the provider's timestamp when
+    /// present, otherwise now.
+    pub fn inbound(msg: &InboundMessage) -> Self {
+        Self {
+            id: msg.id.clone().unwrap_or_else(sms_core::fallback_id),
+            direction: MessageDirection::Inbound,
+            from: msg.from.clone(),
+            to: msg.to.clone(),
+            text: msg.text.clone(),
+            provider: msg.provider.to_string(),
+            timestamp: msg.timestamp.unwrap_or_else(OffsetDateTime::now_utc),
+        }
+    }
+}
+
+/// A half-open time range `[since, until)`; either bound may be open.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DateRange {
+    /// Inclusive lower bound.
+    pub since: Option<OffsetDateTime>,
+    /// Exclusive upper bound.
+    pub until: Option<OffsetDateTime>,
+}
+
+impl DateRange {
+    /// A range matching every timestamp.
+    pub fn all() -> Self {
+        Self::default()
+    }
+
+    /// Only include timestamps at or after `since`.
+    pub fn since(mut self, since: OffsetDateTime) -> Self {
+        self.since = Some(since);
+        self
+    }
+
+    /// Only include timestamps before `until`.
+    pub fn until(mut self, until: OffsetDateTime) -> Self {
+        self.until = Some(until);
+        self
+    }
+
+    /// Returns `true` if `ts` falls within the range.
+    pub fn contains(&self, ts: OffsetDateTime) -> bool {
+        self.since.is_none_or(|s| ts >= s) && self.until.is_none_or(|u| ts < u)
+    }
+}
+
+/// How [`MessageStore::forget_number`] disposes of a subject's messages.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ForgetMode {
+    /// Remove the messages entirely.
+    #[default]
+    Delete,
+    /// Keep the messages (for volume statistics) but overwrite the subject's
+    /// number and the message text with [`REDACTED`].
+    Redact,
+}
+
+/// Placeholder written over redacted fields.
+pub const REDACTED: &str = "[redacted]";
+
+/// What a [`MessageStore::forget_number`] call purged.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ForgetReport {
+    /// Messages removed from the store.
+    pub messages_deleted: usize,
+    /// Messages whose personal data was overwritten.
+    pub messages_redacted: usize,
+}
+
+impl ForgetReport {
+    /// Total number of messages affected.
+    pub fn total(&self) -> usize {
+        self.messages_deleted + self.messages_redacted
+    }
+}
+
+/// Persistent record of sent and received messages.
+#[async_trait]
+pub trait MessageStore: Send + Sync {
+    /// Store a message.
+    async fn save(&self, message: StoredMessage) -> Result<(), SmsError>;
+
+    /// Messages whose timestamp falls within `range`, oldest first.
+    async fn query(&self, range: &DateRange) -> Result<Vec<StoredMessage>, SmsError>;
+
+    /// The `limit` most recent messages, newest first.
+    ///
+    /// The default queries every message; stores that can read just the
+    /// newest rows should override it.
+    async fn recent(&self, limit: usize) -> Result<Vec<StoredMessage>, SmsError> {
+        let mut messages = self.query(&DateRange::all()).await?;
+        let skip = messages.len().saturating_sub(limit);
+        messages.drain(..skip);
+        messages.reverse();
+        Ok(messages)
+    }
+
+    /// Delete or irreversibly redact every message sent to or from `phone`,
+    /// e.g. to honour a GDPR erasure request.  Numbers are compared after
+    /// [`normalize_number`], so formatting differences don't hide messages.
+    async fn forget_number(&self, phone: &str, mode: ForgetMode) -> Result<ForgetReport, SmsError>;
+}
+
+/// A [`MessageStore`] held in process memory.
+///
+/// Cloning yields another handle to the same messages.
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryMessageStore {
+    messages: Arc<Mutex<Vec<StoredMessage>>>,
+}
+
+impl InMemoryMessageStore {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of stored messages.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Returns `true` if nothing is stored.
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<StoredMessage>> {
+        self.messages.lock().expect("message store poisoned")
+    }
+}
+
+#[async_trait]
+impl MessageStore for InMemoryMessageStore {
+    async fn save(&self, message: StoredMessage) -> Result<(), SmsError> {
+        self.lock().push(message);
+        Ok(())
+    }
+
+    async fn query(&self, range: &DateRange) -> Result<Vec<StoredMessage>, SmsError> {
+        let mut found: Vec<_> = self
+            .lock()
+            .iter()
+            .filter(|m| range.contains(m.timestamp))
+            .cloned()
+            .collect();
+        found.sort_by_key(|m| m.timestamp);
+        Ok(found)
+    }
+
+    async fn recent(&self, limit: usize) -> Result<Vec<StoredMessage>, SmsError> {
+        let messages = self.lock();
+        let mut newest: Vec<&StoredMessage> = messages.iter().collect();
+        newest.sort_by_key(|m| std::cmp::Reverse(m.timestamp));
+        Ok(newest.into_iter().take(limit).cloned().collect())
+    }
+
+    async fn forget_number(&self, phone: &str, mode: ForgetMode) -> Result<ForgetReport, SmsError> {
+        let mut messages = self.lock();
+        let mut report = ForgetReport::default();
+        let phone = normalize_number(phone);
+        let is_subject = |number: &str| normalize_number(number) == phone;
+        let involves = |m: &StoredMessage| is_subject(&m.from) || is_subject(&m.to);
+
+        match mode {
+            ForgetMode::Delete => {
+                let before = messages.len();
+                messages.retain(|m| !involves(m));
+                report.messages_deleted = before - messages.len();
+            }
+            ForgetMode::Redact => {
+                for m in messages.iter_mut().filter(|m| involves(m)) {
+                    if is_subject(&m.from) {
+                        m.from = REDACTED.to_string();
+                    }
+                    if is_subject(&m.to) {
+                        m.to = REDACTED.to_string();
+                    }
+                    m.text = REDACTED.to_string();
+                    report.messages_redacted += 1;
+                }
+            }
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(id: &str, timestamp: OffsetDateTime) -> StoredMessage {
+        StoredMessage {
+            id: id.into(),
+            direction: MessageDirection::Outbound,
+            from: "+15550001".into(),
+            to: "+15550002".into(),
+            text: format!("message {}", id),
+            provider: "plivo".into(),
+            timestamp,
+        }
+    }
+
+    /// Midnight UTC on the given day of 2024.
+    fn day(ordinal: i64) -> OffsetDateTime {
+        OffsetDateTime::from_unix_timestamp(1_704_067_200 + (ordinal - 1) * 86_400).unwrap()
+    }
+
+    #[test]
+    fn date_range_is_half_open() {
+        let range = DateRange::all().since(day(1)).until(day(32));
+        assert!(range.contains(day(1)));
+        assert!(range.contains(day(32) - time::Duration::seconds(1)));
+        assert!(!range.contains(day(32)));
+        assert!(DateRange::all().contains(OffsetDateTime::UNIX_EPOCH));
+    }
+
+    #[tokio::test]
+    async fn query_filters_and_sorts() {
+        let store = InMemoryMessageStore::new();
+        store.save(message("b", day(2))).await.unwrap();
+        store.save(message("a", day(1))).await.unwrap();
+        store.save(message("c", day(61))).await.unwrap();
+
+        let jan = DateRange::all().until(day(32));
+        let ids: Vec<_> = store
+            .query(&jan)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|m| m.id)
+            .collect();
+        assert_eq!(ids, vec!["a", "b"]);
+        assert_eq!(store.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn recent_returns_newest_first() {
+        let store = InMemoryMessageStore::new();
+        store.save(message("b", day(2))).await.unwrap();
+        store.save(message("c", day(3))).await.unwrap();
+        store.save(message("a", day(1))).await.unwrap();
+
+        let ids: Vec<_> = store
+            .recent(2)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|m| m.id)
+            .collect();
+        assert_eq!(ids, vec!["c", "b"]);
+    }
+
+    #[tokio::test]
+    async fn forget_number_deletes_subject_messages() {
+        let store = InMemoryMessageStore::new();
+        store.save(message("a", day(1))).await.unwrap();
+        let mut other = message("b", day(2));
+        other.to = "+15559999".into();
+        store.save(other).await.unwrap();
+
+        let report = store
+            .forget_number("+1 555-0002", ForgetMode::Delete)
+            .await
+            .unwrap();
+        assert_eq!(report.messages_deleted, 1);
+        assert_eq!(store.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn forget_number_redacts_in_place() {
+        let store = InMemoryMessageStore::new();
+        store.save(message("a", day(1))).await.unwrap();
+
+        let report = store
+            .forget_number("+15550001", ForgetMode::Redact)
+            .await
+            .unwrap();
+        assert_eq!(report.messages_redacted, 1);
+
+        let kept = &store.query(&DateRange::all()).await.unwrap()[0];
+        assert_eq!(kept.from, REDACTED);
+        assert_eq!(kept.to, "+15550002");
+        assert_eq!(kept.text, REDACTED);
+    }
+
+    #[test]
+    fn inbound_falls_back_to_generated_id() {
+        use sms_core::{Channel, InboundDirection};
+
+        let msg = InboundMessage {
+            id: None,
+            from: "+1".into(),
+            to: "+2".into(),
+            text: "hi".into(),
+            timestamp: None,
+            provider: "twilio",
+            direction: InboundDirection::Inbound,
+            channel: Channel::Sms,
+            provider_account: None,
+            thread_id: None,
+            flag: None,
+            raw: serde_json::json!({}),
+        };
+        let stored = StoredMessage::inbound(&msg);
+        assert_eq!(stored.direction, MessageDirection::Inbound);
+        assert!(!stored.id.is_empty());
+        assert_eq!(stored.provider, "twilio");
+    }
+}
@@ -0,0 +1,309 @@
+//! Store-and-forward when every provider is down.
+//!
+//! [`StoreAndForward`] wraps a client, usually a
+//! [`FallbackClient`](sms_core::FallbackClient) chain.  When a send fails for
+//! a reason that waiting could fix ([`SmsError::is_transient`]), the message
+//! is parked in an [`Outbox`] in the
+//! [`Deferred`](crate::outbox::OutboxState::Deferred) state and the caller
+//! gets a response from provider `"outbox"` instead of an error.
+//! [`run`](StoreAndForward::run) periodically checks whether providers are
+//! healthy again and sends the parked messages, highest priority first.
+//! A message that keeps failing is dropped after
+//! [`with_max_attempts`](StoreAndForward::with_max_attempts) tries.
+//!
+//! ```rust,ignore
+//! use smskit::store_forward::StoreAndForward;
+//!
+//! let chain = FallbackClient::new(vec![Arc::new(primary), Arc::new(backup)]);
+//! let client = Arc::new(
+//!     StoreAndForward::new(Arc::new(chain), Outbox::new())
+//!         .with_check_interval(Duration::from_secs(30)),
+//! );
+//!
+//! let recovery = client.clone();
+//! tokio::spawn(async move { recovery.run().await });
+//!
+//! let resp = client.send(req.as_ref()).await?;
+//! if resp.provider == "outbox" {
+//!     // Accepted for later delivery; resp.id is the outbox queue ID.
+//! }
+//! ```
+
+use crate::outbox::{Outbox, Priority};
+use crate::runtime::Interval;
+use sms_core::{
+    BoxFuture, Channel, DynSmsClient, OwnedSendRequest, SendRequest, SendResponse, SmsClient,
+    SmsError,
+};
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// The provider name reported for messages accepted into the outbox.
+pub const DEFERRED_PROVIDER: &str = "outbox";
+
+type HealthCheck = Arc<dyn Fn() -> BoxFuture<'static, bool> + Send + Sync>;
+
+/// An [`SmsClient`] that defers messages to an [`Outbox`] instead of
+/// failing when its inner client is unavailable.
+pub struct StoreAndForward {
+    client: Arc<dyn DynSmsClient>,
+    outbox: Outbox,
+    priority: Priority,
+    check_interval: Duration,
+    max_attempts: u32,
+    health_check: Option<HealthCheck>,
+}
+
+impl StoreAndForward {
+    /// Wrap `client`, deferring into `outbox` at [`Priority::Normal`],
+    /// checking for recovery every 30 seconds and giving up on a message
+    /// after 20 failed retries.
+    pub fn new(client: Arc<dyn DynSmsClient>, outbox: Outbox) -> Self {
+        Self {
+            client,
+            outbox,
+            priority: Priority::Normal,
+            check_interval: Duration::from_secs(30),
+            max_attempts: 20,
+            health_check: None,
+        }
+    }
+
+    /// Defer messages with `priority`.
+    pub fn with_priority(mut self, priority: Priority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// How often [`run`](Self::run) checks for recovery.
+    pub fn with_check_interval(mut self, interval: Duration) -> Self {
+        self.check_interval = interval;
+        self
+    }
+
+    /// Drop a deferred message once it has failed `attempts` retries.
+    ///
+    /// Without a health check every retry counts, so an outage longer than
+    /// `attempts` check intervals drops the messages parked during it.
+    pub fn with_max_attempts(mut self, attempts: u32) -> Self {
+        self.max_attempts = attempts.max(1);
+        self
+    }
+
+    /// Only resume sending once `check` reports a provider healthy.
+    ///
+    /// Without a health check, recovery is detected by retrying the
+    /// highest-priority deferred message.
+    pub fn with_health_check<F, Fut>(mut self, check: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = bool> + Send + 'static,
+    {
+        self.health_check = Some(Arc::new(move || Box::pin(check())));
+        self
+    }
+
+    /// The outbox deferred messages are parked in.
+    pub fn outbox(&self) -> &Outbox {
+        &self.outbox
+    }
+
+    /// Retry every deferred message once.
+    ///
+    /// Returns how many were sent.  A message that fails transiently goes
+    /// back to the outbox and the rest are still tried, so one bad message
+    /// never blocks the queue; it is dropped with a warning once it reaches
+    /// the attempt limit.  Messages that fail permanently are dropped
+    /// straight away.
+    pub async fn recover(&self) -> usize {
+        if self.outbox.deferred_len().await == 0 {
+            return 0;
+        }
+        if let Some(check) = &self.health_check
+            && !check().await
+        {
+            debug!("Health check failed; keeping deferred messages");
+            return 0;
+        }
+
+        let mut sent = 0;
+        let mut retry = Vec::new();
+        while let Some(mut entry) = self.outbox.pop_deferred().await {
+            match self.client.send(entry.request.as_ref()).await {
+                Ok(resp) => {
+                    debug!("Sent deferred message {} as {}", entry.id, resp.id);
+                    sent += 1;
+                }
+                Err(e) if e.is_transient() => {
+                    entry.attempts += 1;
+                    if entry.attempts >= self.max_attempts {
+                        warn!(
+                            "Dropping deferred message {} after {} attempts: {}",
+                            entry.id, entry.attempts, e
+                        );
+                    } else {
+                        debug!("Deferred message {} failed again: {}", entry.id, e);
+                        retry.push(entry);
+                    }
+                }
+                Err(e) => warn!("Dropping deferred message {}: {}", entry.id, e),
+            }
+        }
+        // Put failures back only after the pass so each is tried once.
+        for entry in retry {
+            self.outbox.restore_deferred(entry).await;
+        }
+        if sent > 0 {
+            info!("Providers recovered; sent {} deferred messages", sent);
+        }
+        sent
+    }
+
+    /// Run forever, calling [`recover`](Self::recover) every check interval.
+    pub async fn run(&self) {
+        let mut ticks = Interval::new(self.check_interval);
+        loop {
+            ticks.tick().await;
+            self.recover().await;
+        }
+    }
+}
+
+impl SmsClient for StoreAndForward {
+    async fn send(&self, req: SendRequest<'_>) -> Result<SendResponse, SmsError> {
+        let error = match self.client.send(req.clone()).await {
+            Ok(resp) => return Ok(resp),
+            Err(e) if e.is_transient() => e,
+            Err(e) => return Err(e),
+        };
+
+        let id = self
+            .outbox
+            .defer(OwnedSendRequest::from(req.clone()), self.priority)
+            .await;
+        warn!("Send failed ({}); deferred as {}", error, id);
+        Ok(SendResponse {
+            id: id.clone(),
+            provider: DEFERRED_PROVIDER,
+            channel: req.channel,
+            http_status: None,
+            provider_request_id: None,
+            thread_id: Some(req.thread_id()),
+            raw: serde_json::json!({
+                "queue_id": id,
+                "state": "deferred",
+                "error": error.to_string(),
+            }),
+        })
+    }
+
+    fn supports(&self, channel: Channel) -> bool {
+        self.client.supports(channel)
+    }
+
+    fn max_text_len(&self) -> Option<usize> {
+        self.client.max_text_len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockClient;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    fn msg(text: &str) -> OwnedSendRequest {
+        OwnedSendRequest::new("+15551234567", "+10005551234", text)
+    }
+
+    #[tokio::test]
+    async fn defers_when_providers_are_down_and_resends_on_recovery() {
+        let mock = MockClient::new();
+        let client = StoreAndForward::new(Arc::new(mock.clone()), Outbox::new());
+
+        mock.fail_next(SmsError::Http("all 2 providers failed".into()));
+        let resp = client.send(msg("hello").as_ref()).await.unwrap();
+        assert_eq!(resp.provider, DEFERRED_PROVIDER);
+        assert_eq!(resp.raw["state"], "deferred");
+        assert_eq!(client.outbox().deferred_len().await, 1);
+
+        // Still down: the message stays parked.
+        mock.fail_next(SmsError::Timeout("slow".into()));
+        assert_eq!(client.recover().await, 0);
+        assert_eq!(client.outbox().deferred_len().await, 1);
+
+        assert_eq!(client.recover().await, 1);
+        assert_eq!(client.outbox().deferred_len().await, 0);
+        assert_eq!(mock.sent().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn permanent_errors_are_returned() {
+        let mock = MockClient::new();
+        let client = StoreAndForward::new(Arc::new(mock.clone()), Outbox::new());
+        mock.fail_next(SmsError::Invalid("bad number".into()));
+        let err = client.send(msg("hello").as_ref()).await.unwrap_err();
+        assert!(matches!(err, SmsError::Invalid(_)));
+        assert_eq!(client.outbox().deferred_len().await, 0);
+    }
+
+    #[tokio::test]
+    async fn failing_message_does_not_block_the_rest() {
+        let mock = MockClient::new();
+        let client = StoreAndForward::new(Arc::new(mock.clone()), Outbox::new())
+            .with_priority(Priority::Critical)
+            .with_max_attempts(2);
+
+        mock.fail_next(SmsError::Timeout("slow".into()));
+        client.send(msg("poison").as_ref()).await.unwrap();
+        let client = client.with_priority(Priority::Normal);
+        mock.fail_next(SmsError::Timeout("slow".into()));
+        client.send(msg("fine").as_ref()).await.unwrap();
+
+        // The poison message goes first, fails, and the next one still sends.
+        mock.fail_next(SmsError::Timeout("slow".into()));
+        assert_eq!(client.recover().await, 1);
+        assert_eq!(client.outbox().deferred_len().await, 1);
+
+        // Second failure reaches the limit and drops it.
+        mock.fail_next(SmsError::Timeout("slow".into()));
+        assert_eq!(client.recover().await, 0);
+        assert_eq!(client.outbox().deferred_len().await, 0);
+        let texts: Vec<_> = mock.sent().into_iter().map(|r| r.text).collect();
+        assert_eq!(texts, ["poison", "fine", "poison", "fine", "poison"]);
+    }
+
+    #[tokio::test]
+    async fn non_transient_provider_errors_are_returned() {
+        let mock = MockClient::new();
+        let client = StoreAndForward::new(Arc::new(mock.clone()), Outbox::new());
+        mock.fail_next(SmsError::Provider("insufficient balance".into()));
+        let err = client.send(msg("hello").as_ref()).await.unwrap_err();
+        assert!(matches!(err, SmsError::Provider(_)));
+        assert_eq!(client.outbox().deferred_len().await, 0);
+    }
+
+    #[tokio::test]
+    async fn waits_for_health_check() {
+        let healthy = Arc::new(AtomicBool::new(false));
+        let mock = MockClient::new();
+        let probe = healthy.clone();
+        let client = StoreAndForward::new(Arc::new(mock.clone()), Outbox::new()).with_health_check(
+            move || {
+                let healthy = probe.load(Ordering::SeqCst);
+                async move { healthy }
+            },
+        );
+
+        mock.fail_next(SmsError::Http("connection refused".into()));
+        client.send(msg("hello").as_ref()).await.unwrap();
+        assert_eq!(client.recover().await, 0);
+        assert_eq!(mock.sent().len(), 1);
+
+        healthy.store(true, Ordering::SeqCst);
+        assert_eq!(client.recover().await, 1);
+        assert_eq!(mock.sent()[1].text, "hello");
+    }
+}
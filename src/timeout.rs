@@ -0,0 +1,123 @@
+//! Deadlines for provider calls.
+//!
+//! [`TimeoutClient`] wraps any [`SmsClient`] and fails a send with
+//! [`SmsError::Timeout`] once its deadline passes, so one slow provider can't
+//! stall a request handler indefinitely.  A request's own
+//! [`timeout`](SendRequest::timeout) overrides the wrapper's default.
+//!
+//! ```rust,ignore
+//! use smskit::timeout::TimeoutClient;
+//!
+//! let client = TimeoutClient::new(plivo, Duration::from_secs(5));
+//! let req = OwnedSendRequest::new("+1555", "+1666", "Code: 1234").with_timeout(Duration::from_secs(2));
+//! client.send(req.as_ref()).await?;
+//! ```
+
+use sms_core::{Channel, SendRequest, SendResponse, SmsClient, SmsError, redact_number};
+use std::time::Duration;
+use tracing::warn;
+
+/// Wraps an [`SmsClient`] and enforces a deadline on every send.
+pub struct TimeoutClient<C> {
+    inner: C,
+    timeout: Duration,
+}
+
+impl<C: SmsClient> TimeoutClient<C> {
+    /// Fail sends through `inner` that take longer than `timeout`.
+    pub fn new(inner: C, timeout: Duration) -> Self {
+        Self { inner, timeout }
+    }
+
+    /// The default deadline applied when a request doesn't set its own.
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Borrow the wrapped client.
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+}
+
+impl<C: SmsClient> SmsClient for TimeoutClient<C> {
+    async fn send(&self, req: SendRequest<'_>) -> Result<SendResponse, SmsError> {
+        let deadline = req.timeout.unwrap_or(self.timeout);
+        let to = req.to;
+        match crate::runtime::timeout(deadline, self.inner.send(req)).await {
+            Ok(result) => result,
+            Err(_) => {
+                warn!(
+                    "Send to {} timed out after {:?}",
+                    redact_number(to),
+                    deadline
+                );
+                Err(SmsError::Timeout(format!(
+                    "no response within {}ms",
+                    deadline.as_millis()
+                )))
+            }
+        }
+    }
+
+    fn supports(&self, channel: Channel) -> bool {
+        self.inner.supports(channel)
+    }
+
+    fn max_text_len(&self) -> Option<usize> {
+        self.inner.max_text_len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sms_core::OwnedSendRequest;
+
+    struct SlowClient(Duration);
+
+    impl SmsClient for SlowClient {
+        async fn send(&self, _req: SendRequest<'_>) -> Result<SendResponse, SmsError> {
+            tokio::time::sleep(self.0).await;
+            Ok(SendResponse {
+                id: "slow".into(),
+                provider: "slow",
+                channel: Channel::Sms,
+                http_status: None,
+                provider_request_id: None,
+                thread_id: None,
+                raw: serde_json::json!({}),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn fast_sends_pass_through() {
+        let client =
+            TimeoutClient::new(SlowClient(Duration::from_millis(1)), Duration::from_secs(1));
+        let req = OwnedSendRequest::new("+1", "+2", "hi");
+        assert_eq!(client.send(req.as_ref()).await.unwrap().id, "slow");
+    }
+
+    #[tokio::test]
+    async fn slow_sends_time_out() {
+        let client = TimeoutClient::new(
+            SlowClient(Duration::from_secs(5)),
+            Duration::from_millis(10),
+        );
+        let req = OwnedSendRequest::new("+1", "+2", "hi");
+        let err = client.send(req.as_ref()).await.unwrap_err();
+        assert!(matches!(err, SmsError::Timeout(_)));
+    }
+
+    #[tokio::test]
+    async fn request_deadline_overrides_default() {
+        let client =
+            TimeoutClient::new(SlowClient(Duration::from_secs(5)), Duration::from_secs(60));
+        let req = OwnedSendRequest::new("+1", "+2", "hi").with_timeout(Duration::from_millis(10));
+        assert!(matches!(
+            client.send(req.as_ref()).await,
+            Err(SmsError::Timeout(_))
+        ));
+    }
+}
@@ -1,5 +1,5 @@
 use sms_core::*;
-use sms_web_generic::WebhookProcessor;
+use sms_web_generic::{Bytes, WebhookProcessor};
 
 #[tokio::test]
 async fn test_webhook_processor_unknown_provider() {
@@ -8,7 +8,8 @@ async fn test_webhook_processor_unknown_provider() {
     let processor = WebhookProcessor::new(registry);
 
     let headers: Headers = vec![];
-    let response = processor.process_webhook("unknown-provider", headers, b"test payload");
+    let response =
+        processor.process_webhook("unknown-provider", headers, Bytes::from_static(b"test payload"));
 
     // Should return 404 for unknown provider
     assert_eq!(response.status.as_u16(), 404);
@@ -22,7 +23,7 @@ async fn test_webhook_processor_empty_payload() {
     let processor = WebhookProcessor::new(registry);
 
     let headers: Headers = vec![("content-type".to_string(), "application/json".to_string())];
-    let response = processor.process_webhook("test-provider", headers, b"");
+    let response = processor.process_webhook("test-provider", headers, Bytes::from_static(b""));
 
     // Should return 404 for unknown provider
     assert_eq!(response.status.as_u16(), 404);
@@ -38,7 +39,7 @@ async fn test_webhook_processor_large_payload() {
     let large_payload = "x".repeat(10000);
     let headers: Headers = vec![("content-type".to_string(), "application/json".to_string())];
 
-    let response = processor.process_webhook("test-provider", headers, large_payload.as_bytes());
+    let response = processor.process_webhook("test-provider", headers, large_payload.into());
 
     // Should handle gracefully (return 404 since provider not registered)
     assert_eq!(response.status.as_u16(), 404);
@@ -58,7 +59,7 @@ async fn test_webhook_processor_concurrent_requests() {
         let payload = format!("test payload {}", i);
         let headers: Headers = vec![("content-type".to_string(), "application/json".to_string())];
 
-        async move { processor_clone.process_webhook("test-provider", headers, payload.as_bytes()) }
+        async move { processor_clone.process_webhook("test-provider", headers, payload.into()) }
     });
 
     let responses = future::join_all(futures).await;
@@ -83,7 +84,7 @@ async fn test_webhook_processor_with_headers() {
         ("x-custom-header".to_string(), "custom-value".to_string()),
     ];
 
-    let response = processor.process_webhook("test", headers_with_auth, b"{}");
+    let response = processor.process_webhook("test", headers_with_auth, Bytes::from_static(b"{}"));
     assert_eq!(response.status.as_u16(), 404);
 }
 
@@ -93,17 +94,17 @@ async fn test_webhook_processor_edge_cases() {
     let processor = WebhookProcessor::new(registry);
 
     // Empty provider name
-    let response1 = processor.process_webhook("", vec![], b"test");
+    let response1 = processor.process_webhook("", vec![], Bytes::from_static(b"test"));
     assert_eq!(response1.status.as_u16(), 404);
 
     // Very long provider name
     let long_provider = "a".repeat(1000);
-    let response2 = processor.process_webhook(&long_provider, vec![], b"test");
+    let response2 = processor.process_webhook(&long_provider, vec![], Bytes::from_static(b"test"));
     assert_eq!(response2.status.as_u16(), 404);
 
     // Null bytes in payload
     let null_payload = b"test\x00payload\x00with\x00nulls";
-    let response3 = processor.process_webhook("test", vec![], null_payload);
+    let response3 = processor.process_webhook("test", vec![], Bytes::from_static(null_payload));
     assert_eq!(response3.status.as_u16(), 404);
 
     // Unicode in headers
@@ -114,7 +115,7 @@ async fn test_webhook_processor_edge_cases() {
             "application/json; charset=utf-8".to_string(),
         ),
     ];
-    let response4 = processor.process_webhook("test", unicode_headers, "测试".as_bytes());
+    let response4 = processor.process_webhook("test", unicode_headers, Bytes::from("测试"));
     assert_eq!(response4.status.as_u16(), 404);
 }
 
@@ -143,7 +144,7 @@ async fn test_high_throughput_webhook_processing() {
 
         async move {
             let start = std::time::Instant::now();
-            let response = processor_clone.process_webhook(provider, headers, payload.as_bytes());
+            let response = processor_clone.process_webhook(provider, headers, payload.into());
             let duration = start.elapsed();
             (response, duration)
         }